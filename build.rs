@@ -1,19 +1,116 @@
+use std::env;
 use std::fs;
 use std::path::PathBuf;
+use std::process::Command;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
 fn main() {
     let git_hash = read_git_hash().unwrap_or_else(|| String::from("unknown"));
+    let git_dirty = read_git_dirty()
+        .map(|dirty| dirty.to_string())
+        .unwrap_or_else(|| String::from("unknown"));
+
+    let target = env::var("TARGET").unwrap_or_default();
+    let rustc = rustc_version().unwrap_or_default();
+    let features = enabled_features().join(",");
 
     println!("cargo:rustc-env=GIT_HASH={}", git_hash);
+    println!("cargo:rustc-env=GIT_DIRTY={}", git_dirty);
+    println!("cargo:rustc-env=BUILD_DATE={}", build_date());
+    println!("cargo:rustc-env=BUILD_TARGET={}", target);
+    println!("cargo:rustc-env=BUILD_RUSTC={}", rustc);
+    println!("cargo:rustc-env=BUILD_FEATURES={}", features);
 }
 
 fn read_git_hash() -> Option<String> {
+    if let Some(hash) = git(&["rev-parse", "HEAD"]) {
+        return Some(hash);
+    }
+
     let git_base_path = PathBuf::from(".git");
 
     let head_file_path = git_base_path.join("HEAD");
     let head_content = fs::read_to_string(head_file_path).ok()?;
 
-    let head_ref = head_content.strip_prefix("ref: ")?;
+    // A detached HEAD holds the hash itself
+    let head_ref = match head_content.strip_prefix("ref: ") {
+        Some(head_ref) => head_ref,
+        None => return Some(head_content.trim().to_string()),
+    };
+
     let head_ref_path = git_base_path.join(head_ref.trim());
-    fs::read_to_string(head_ref_path).ok()
+    fs::read_to_string(head_ref_path)
+        .ok()
+        .map(|hash| hash.trim().to_string())
+}
+
+// Whether the source had uncommitted changes, None when git isn't available
+fn read_git_dirty() -> Option<bool> {
+    git(&["status", "--porcelain", "--untracked-files=no"]).map(|status| !status.is_empty())
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8(output.stdout)
+        .ok()
+        .map(|stdout| stdout.trim().to_string())
+}
+
+fn rustc_version() -> Option<String> {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| String::from("rustc"));
+    let output = Command::new(rustc).arg("--version").output().ok()?;
+
+    String::from_utf8(output.stdout)
+        .ok()
+        .map(|stdout| stdout.trim().to_string())
+}
+
+// Cargo sets CARGO_FEATURE_<NAME> for every enabled feature
+fn enabled_features() -> Vec<String> {
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(name, _)| {
+            name.strip_prefix("CARGO_FEATURE_")
+                .map(|feature| feature.to_lowercase().replace('_', "-"))
+        })
+        .collect();
+
+    features.sort();
+    features
+}
+
+// UTC date of the build, SOURCE_DATE_EPOCH is used instead of the current time for
+// reproducible builds
+fn build_date() -> String {
+    let seconds = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default()
+        });
+
+    let (year, month, day) = civil_from_days((seconds / 86400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// Days since 1970-01-01 to a calendar date, see http://howardhinnant.github.io/date_algorithms.html
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
 }
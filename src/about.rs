@@ -1,8 +1,51 @@
-pub fn about() {
-    println!("gdrive is a command line application for interacting with Google Drive.");
+use crate::common::read_only;
+use crate::version;
+use crate::version::VersionInfo;
+use serde::Serialize;
+
+const DESCRIPTION: &str = "gdrive is a command line application for interacting with Google Drive.";
+const PROJECT_PAGE: &str = "https://github.com/glotlabs/gdrive";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct About {
+    description: &'static str,
+    project_page: &'static str,
+    read_only: bool,
+    version: VersionInfo,
+}
+
+pub fn about(json: bool) -> Result<(), version::Error> {
+    if json {
+        let about = About {
+            description: DESCRIPTION,
+            project_page: PROJECT_PAGE,
+            read_only: read_only::is_enabled(),
+            version: VersionInfo::current(),
+        };
+        let json = serde_json::to_string_pretty(&about).map_err(version::Error::SerializeJson)?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    println!("{}", DESCRIPTION);
     println!();
     println!(
-        "For the latest information check out the project page: https://github.com/glotlabs/gdrive"
+        "For the latest information check out the project page: {}",
+        PROJECT_PAGE
     );
     println!("You will also find link to the community chat and information on how to support the project.");
+    println!();
+    println!(
+        "Version {} (commit {})",
+        env!("CARGO_PKG_VERSION"),
+        VersionInfo::current().commit_description()
+    );
+
+    if read_only::is_enabled() {
+        println!();
+        println!("Read-only mode is enabled, commands that modify the drive will be refused.");
+    }
+
+    Ok(())
 }
@@ -1,35 +1,63 @@
 use crate::app_config;
+use crate::app_config::AccountScope;
+use crate::common::client_secret;
+use crate::common::hub_helper;
 use crate::hub;
+use crate::hub::HubConfig;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::io;
 use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub struct Config {
+    pub scope: AccountScope,
+    // client_secret.json of the OAuth app to use, instead of prompting for the client id and secret
+    pub client_secret: Option<PathBuf>,
+    pub request_timeout: Duration,
+}
+
+pub async fn add(config: Config) -> Result<(), Error> {
+    // Checked before anything is printed, a broken file shouldn't get as far as the browser
+    let file_secret = match &config.client_secret {
+        Some(path) => Some(client_secret::load(path).map_err(Error::ClientSecret)?),
+        None => None,
+    };
 
-pub async fn add() -> Result<(), Error> {
-    println!("To add an account you need a Google Client ID and Client Secret.");
-    println!("Instructions for how to create credentials can be found here: https://github.com/glotlabs/gdrive/blob/main/docs/create_google_api_credentials.md");
+    if file_secret.is_none() {
+        println!("To add an account you need a Google Client ID and Client Secret.");
+        println!("Instructions for how to create credentials can be found here: https://github.com/glotlabs/gdrive/blob/main/docs/create_google_api_credentials.md");
+    }
     println!("Note that if you are using gdrive on a remote server you should read this first: https://github.com/glotlabs/gdrive#using-gdrive-on-a-remote-server");
+    if config.scope == AccountScope::File {
+        println!("The account will use the drive.file scope, gdrive can only access files it creates.");
+    }
     println!();
 
-    let secret = secret_prompt().map_err(Error::Prompt)?;
+    let secret = match file_secret {
+        Some(secret) => {
+            println!("Using the OAuth client {}", secret.client_id);
+            secret
+        }
+        None => secret_prompt().map_err(Error::Prompt)?,
+    };
 
     let tmp_dir = tempfile::tempdir().map_err(Error::Tempdir)?;
     let tokens_path = tmp_dir.path().join("tokens.json");
 
-    let auth = hub::Auth::new(&secret, &tokens_path)
+    // Get access tokens
+    let hub_config = HubConfig {
+        account_scope: config.scope,
+        extra_scopes: Vec::new(),
+        request_timeout: config.request_timeout,
+    };
+    let auth = hub_helper::authorize(&secret, &tokens_path, &hub_config)
         .await
-        .map_err(Error::Auth)?;
+        .map_err(Error::Authorize)?;
 
-    // Get access tokens
-    auth.token(&[
-        "https://www.googleapis.com/auth/drive",
-        "https://www.googleapis.com/auth/drive.metadata.readonly",
-    ])
-    .await
-    .map_err(Error::AccessToken)?;
-
-    let hub = hub::Hub::new(auth).await;
+    let hub = hub::Hub::new(auth, &hub_config).await;
     let (_, about) = hub
         .about()
         .get()
@@ -44,7 +72,8 @@ pub async fn add() -> Result<(), Error> {
         .unwrap_or_else(|| String::from("unknown"));
 
     let app_cfg =
-        app_config::add_account(&email, &secret, &tokens_path).map_err(Error::AppConfig)?;
+        app_config::add_account(&email, &secret, &tokens_path, config.scope)
+            .map_err(Error::AppConfig)?;
 
     println!();
     println!(
@@ -62,11 +91,11 @@ pub async fn add() -> Result<(), Error> {
 
 #[derive(Debug)]
 pub enum Error {
+    ClientSecret(client_secret::Error),
     Prompt(io::Error),
     Tempdir(io::Error),
-    Auth(io::Error),
+    Authorize(hub_helper::Error),
     AppConfig(app_config::Error),
-    AccessToken(google_drive3::oauth2::Error),
     About(google_drive3::Error),
 }
 
@@ -75,11 +104,11 @@ impl error::Error for Error {}
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
+            Error::ClientSecret(e) => write!(f, "{}", e),
             Error::Prompt(e) => write!(f, "Failed to get input from user: {}", e),
             Error::Tempdir(e) => write!(f, "Failed to create temporary directory: {}", e),
-            Error::Auth(e) => write!(f, "Failed to authenticate: {}", e),
+            Error::Authorize(e) => write!(f, "Failed to authenticate: {}", e),
             Error::AppConfig(e) => write!(f, "{}", e),
-            Error::AccessToken(e) => write!(f, "Failed to get access token: {}", e),
             Error::About(e) => write!(f, "Failed to get user info: {}", e),
         }
     }
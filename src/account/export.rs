@@ -2,14 +2,25 @@ use crate::app_config;
 use crate::app_config::set_file_permissions;
 use crate::app_config::AppConfig;
 use crate::common::account_archive;
+use crate::common::account_json;
+use crate::common::account_json::AccountJson;
+use crate::common::archive_crypto;
 use std::error;
+use std::fmt;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::io::IsTerminal;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub account_name: String,
+    pub format: ExportFormat,
+    pub stdout: bool,
+    pub encrypt: bool,
 }
 
 pub fn export(config: Config) -> Result<(), Error> {
@@ -17,11 +28,46 @@ pub fn export(config: Config) -> Result<(), Error> {
     err_if_account_not_found(&accounts, &config.account_name)?;
 
     let app_cfg = AppConfig::init_account(&config.account_name).map_err(Error::AppConfig)?;
+
+    match config.format {
+        ExportFormat::Tar => export_archive(&config, &app_cfg),
+        ExportFormat::Json if config.encrypt => Err(Error::EncryptRequiresTar),
+        ExportFormat::Json => export_json(&config, &app_cfg),
+    }
+}
+
+fn export_archive(config: &Config, app_cfg: &AppConfig) -> Result<(), Error> {
+    err_if_stdout(config)?;
+
     let account_path = app_cfg.account_base_path();
 
-    let archive_name = format!("gdrive_export-{}.tar", normalize_name(&config.account_name));
+    let archive_name = if config.encrypt {
+        format!(
+            "gdrive_export-{}.tar.age",
+            normalize_name(&config.account_name)
+        )
+    } else {
+        format!("gdrive_export-{}.tar", normalize_name(&config.account_name))
+    };
     let archive_path = PathBuf::from(&archive_name);
-    account_archive::create(&account_path, &archive_path).map_err(Error::CreateArchive)?;
+
+    if config.encrypt {
+        err_if_exists(&archive_path)?;
+        let passphrase = archive_crypto::read_passphrase(true).map_err(Error::Encrypt)?;
+
+        // The plaintext archive only exists in a temporary directory
+        let tmp_dir = tempfile::tempdir().map_err(Error::Tempdir)?;
+        let tmp_archive_path = tmp_dir.path().join("account.tar");
+        account_archive::create(&account_path, &tmp_archive_path).map_err(Error::CreateArchive)?;
+
+        let content = fs::read(&tmp_archive_path)
+            .map_err(|err| Error::ReadFile(tmp_archive_path.clone(), err))?;
+        let encrypted = archive_crypto::encrypt(&content, &passphrase).map_err(Error::Encrypt)?;
+        fs::write(&archive_path, encrypted)
+            .map_err(|err| Error::WriteFile(archive_path.clone(), err))?;
+    } else {
+        account_archive::create(&account_path, &archive_path).map_err(Error::CreateArchive)?;
+    }
 
     if let Err(err) = set_file_permissions(&archive_path) {
         eprintln!("Warning: Failed to set permissions on archive: {}", err);
@@ -35,11 +81,77 @@ pub fn export(config: Config) -> Result<(), Error> {
     Ok(())
 }
 
+fn export_json(config: &Config, app_cfg: &AppConfig) -> Result<(), Error> {
+    let account_json = AccountJson::from_account(app_cfg).map_err(Error::AccountJson)?;
+    let content = account_json.to_json().map_err(Error::AccountJson)?;
+
+    if config.stdout {
+        if io::stdout().is_terminal() {
+            eprintln!("Warning: Printing account credentials to the terminal. Anyone who sees them will be able to access your Google Drive.");
+        }
+
+        println!("{}", content);
+        return Ok(());
+    }
+
+    let file_name = format!("gdrive_export-{}.json", normalize_name(&config.account_name));
+    let file_path = PathBuf::from(&file_name);
+    err_if_exists(&file_path)?;
+    fs::write(&file_path, content).map_err(|err| Error::WriteFile(file_path.clone(), err))?;
+
+    if let Err(err) = set_file_permissions(&file_path) {
+        eprintln!("Warning: Failed to set permissions on export: {}", err);
+    }
+
+    println!("Exported account '{}' to {}", config.account_name, file_name);
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    #[default]
+    Tar,
+    Json,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tar" => Ok(ExportFormat::Tar),
+            "json" => Ok(ExportFormat::Json),
+            _ => Err(format!(
+                "'{}' is not a valid export format, valid formats are: tar, json",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ExportFormat::Tar => write!(f, "tar"),
+            ExportFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
     AppConfig(app_config::Error),
     AccountNotFound(String),
     CreateArchive(account_archive::Error),
+    AccountJson(account_json::Error),
+    FileExists(PathBuf),
+    WriteFile(PathBuf, io::Error),
+    StdoutRequiresJson,
+    EncryptRequiresTar,
+    Encrypt(archive_crypto::Error),
+    Tempdir(io::Error),
+    ReadFile(PathBuf, io::Error),
 }
 
 impl error::Error for Error {}
@@ -50,6 +162,18 @@ impl Display for Error {
             Error::AppConfig(e) => write!(f, "{}", e),
             Error::AccountNotFound(name) => write!(f, "Account '{}' not found", name),
             Error::CreateArchive(e) => write!(f, "{}", e),
+            Error::AccountJson(e) => write!(f, "{}", e),
+            Error::FileExists(path) => write!(f, "'{}' already exists", path.display()),
+            Error::WriteFile(path, e) => {
+                write!(f, "Failed to write '{}': {}", path.display(), e)
+            }
+            Error::StdoutRequiresJson => write!(f, "--stdout can only be used with --format json"),
+            Error::EncryptRequiresTar => write!(f, "--encrypt can only be used with --format tar"),
+            Error::Encrypt(e) => write!(f, "{}", e),
+            Error::Tempdir(e) => write!(f, "Failed to create temporary directory: {}", e),
+            Error::ReadFile(path, e) => {
+                write!(f, "Failed to read '{}': {}", path.display(), e)
+            }
         }
     }
 }
@@ -62,6 +186,22 @@ fn err_if_account_not_found(accounts: &[String], account_name: &str) -> Result<(
     }
 }
 
+fn err_if_stdout(config: &Config) -> Result<(), Error> {
+    if config.stdout {
+        Err(Error::StdoutRequiresJson)
+    } else {
+        Ok(())
+    }
+}
+
+fn err_if_exists(path: &PathBuf) -> Result<(), Error> {
+    if path.exists() {
+        Err(Error::FileExists(path.clone()))
+    } else {
+        Ok(())
+    }
+}
+
 fn normalize_name(account_name: &str) -> String {
     account_name
         .chars()
@@ -1,25 +1,48 @@
 use crate::app_config;
 use crate::app_config::AppConfig;
 use crate::common::account_archive;
+use crate::common::account_json;
+use crate::common::account_json::AccountJson;
+use crate::common::archive_crypto;
+use std::env;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::io::Read;
 use std::path::PathBuf;
 
 #[derive(Debug, Clone)]
 pub struct Config {
-    pub archive_path: PathBuf,
+    pub source: ImportSource,
+}
+
+#[derive(Debug, Clone)]
+pub enum ImportSource {
+    Archive(PathBuf),
+    Env(String),
+    Stdin,
 }
 
 pub fn import(config: Config) -> Result<(), Error> {
-    let account_name =
-        account_archive::get_account_name(&config.archive_path).map_err(Error::ReadAccountName)?;
+    let account_name = match &config.source {
+        ImportSource::Archive(archive_path) => import_archive(archive_path)?,
 
-    let accounts = app_config::list_accounts().map_err(Error::AppConfig)?;
-    err_if_account_exists(&accounts, &account_name)?;
+        ImportSource::Env(var_name) => {
+            let content =
+                env::var(var_name).map_err(|_| Error::MissingEnvVar(var_name.clone()))?;
+            import_json(&content)?
+        }
 
-    let config_base_path = AppConfig::default_base_path().map_err(Error::AppConfig)?;
-    account_archive::unpack(&config.archive_path, &config_base_path).map_err(Error::Unpack)?;
+        ImportSource::Stdin => {
+            let mut content = String::new();
+            io::stdin()
+                .read_to_string(&mut content)
+                .map_err(Error::ReadStdin)?;
+            import_json(&content)?
+        }
+    };
 
     println!("Imported account '{}'", account_name);
 
@@ -32,12 +55,62 @@ pub fn import(config: Config) -> Result<(), Error> {
     Ok(())
 }
 
+fn import_archive(archive_path: &PathBuf) -> Result<String, Error> {
+    // Encrypted archives are decrypted into a temporary directory, which is removed when done
+    let tmp_dir = tempfile::tempdir().map_err(Error::Tempdir)?;
+    let archive_path = if archive_crypto::is_encrypted_file(archive_path).map_err(Error::Decrypt)? {
+        let tmp_archive_path = tmp_dir.path().join("account.tar");
+        decrypt_archive(archive_path, &tmp_archive_path)?;
+        tmp_archive_path
+    } else {
+        archive_path.clone()
+    };
+    let archive_path = &archive_path;
+
+    let account_name =
+        account_archive::get_account_name(archive_path).map_err(Error::ReadAccountName)?;
+
+    let accounts = app_config::list_accounts().map_err(Error::AppConfig)?;
+    err_if_account_exists(&accounts, &account_name)?;
+
+    let config_base_path = AppConfig::default_base_path().map_err(Error::AppConfig)?;
+    account_archive::unpack(archive_path, &config_base_path).map_err(Error::Unpack)?;
+
+    Ok(account_name)
+}
+
+fn decrypt_archive(archive_path: &PathBuf, dst_path: &PathBuf) -> Result<(), Error> {
+    let content =
+        fs::read(archive_path).map_err(|err| Error::ReadFile(archive_path.clone(), err))?;
+    let passphrase = archive_crypto::read_passphrase(false).map_err(Error::Decrypt)?;
+    let decrypted = archive_crypto::decrypt(&content, &passphrase).map_err(Error::Decrypt)?;
+    fs::write(dst_path, decrypted).map_err(|err| Error::WriteFile(dst_path.clone(), err))
+}
+
+fn import_json(content: &str) -> Result<String, Error> {
+    let account_json = AccountJson::from_json(content).map_err(Error::AccountJson)?;
+
+    let accounts = app_config::list_accounts().map_err(Error::AppConfig)?;
+    err_if_account_exists(&accounts, &account_json.account_name)?;
+
+    account_json.save().map_err(Error::AccountJson)?;
+
+    Ok(account_json.account_name)
+}
+
 #[derive(Debug)]
 pub enum Error {
     AppConfig(app_config::Error),
     AccountExists(String),
     ReadAccountName(account_archive::Error),
     Unpack(account_archive::Error),
+    AccountJson(account_json::Error),
+    MissingEnvVar(String),
+    ReadStdin(io::Error),
+    Decrypt(archive_crypto::Error),
+    Tempdir(io::Error),
+    ReadFile(PathBuf, io::Error),
+    WriteFile(PathBuf, io::Error),
 }
 
 impl error::Error for Error {}
@@ -49,6 +122,15 @@ impl Display for Error {
             Error::AccountExists(name) => write!(f, "Account '{}' already exists", name),
             Error::ReadAccountName(e) => write!(f, "{}", e),
             Error::Unpack(e) => write!(f, "{}", e),
+            Error::AccountJson(e) => write!(f, "{}", e),
+            Error::MissingEnvVar(name) => {
+                write!(f, "Environment variable '{}' is not set or not valid unicode", name)
+            }
+            Error::ReadStdin(e) => write!(f, "Failed to read from stdin: {}", e),
+            Error::Decrypt(e) => write!(f, "{}", e),
+            Error::Tempdir(e) => write!(f, "Failed to create temporary directory: {}", e),
+            Error::ReadFile(path, e) => write!(f, "Failed to read '{}': {}", path.display(), e),
+            Error::WriteFile(path, e) => write!(f, "Failed to write '{}': {}", path.display(), e),
         }
     }
 }
@@ -1,12 +1,24 @@
 use crate::app_config;
+use crate::app_config::AppConfig;
+use crate::common::table;
+use crate::common::table::Table;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::io;
 
-pub fn list() -> Result<(), Error> {
+pub struct Config {
+    pub details: bool,
+}
+
+pub fn list(config: Config) -> Result<(), Error> {
     let accounts = app_config::list_accounts().map_err(Error::AppConfig)?;
     err_if_no_accounts(&accounts)?;
 
+    if config.details {
+        return print_details(&accounts);
+    }
+
     for account in accounts {
         println!("{}", account);
     }
@@ -14,6 +26,25 @@ pub fn list() -> Result<(), Error> {
     Ok(())
 }
 
+fn print_details(accounts: &[String]) -> Result<(), Error> {
+    let mut values: Vec<[String; 2]> = vec![];
+
+    for account in accounts {
+        let app_cfg = AppConfig::load_account(account).map_err(Error::AppConfig)?;
+        let scope = app_cfg.load_scope().map_err(Error::AppConfig)?;
+        values.push([account.clone(), scope.to_string()]);
+    }
+
+    let table = Table {
+        header: ["Account", "Scope"],
+        values,
+    };
+
+    let _ = table::write(io::stdout(), table, &table::DisplayConfig::default());
+
+    Ok(())
+}
+
 #[derive(Debug)]
 pub enum Error {
     AppConfig(app_config::Error),
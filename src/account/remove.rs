@@ -1,29 +1,119 @@
 use crate::app_config;
 use crate::app_config::AppConfig;
+use crate::common::account_json;
+use crate::common::account_json::AccountJson;
+use crate::common::cancel;
+use crate::common::url_fetch;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::io;
+use std::io::IsTerminal;
+use std::io::Write;
+
+const REVOKE_URL: &str = "https://oauth2.googleapis.com/revoke";
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub account_name: String,
+    // Revoke the refresh token at Google before the local files are removed
+    pub revoke: bool,
+    // Remove without asking for confirmation
+    pub force: bool,
 }
 
-pub fn remove(config: Config) -> Result<(), Error> {
+pub async fn remove(config: Config) -> Result<(), Error> {
     let accounts = app_config::list_accounts().map_err(Error::AppConfig)?;
     err_if_account_not_found(&accounts, &config.account_name)?;
 
     let app_cfg = AppConfig::init_account(&config.account_name).map_err(Error::AppConfig)?;
+
+    if !config.force && !confirm_remove(&config.account_name)? {
+        println!("Account '{}' was not removed", config.account_name);
+        return Ok(());
+    }
+
+    if config.revoke {
+        revoke_access(&app_cfg).await;
+    }
+
     app_cfg.remove_account().map_err(Error::AppConfig)?;
     println!("Removed account '{}'", config.account_name);
 
     Ok(())
 }
 
+// A failed revocation is reported but doesn't keep the local files around
+async fn revoke_access(app_cfg: &AppConfig) {
+    let refresh_token = match stored_refresh_token(app_cfg) {
+        Ok(Some(token)) => token,
+        Ok(None) => {
+            println!("Not revoking access, the account has no refresh token (service accounts can't be revoked)");
+            return;
+        }
+        Err(err) => {
+            warn_revoke_failed(err);
+            return;
+        }
+    };
+
+    match url_fetch::post_form(REVOKE_URL, &revoke_request_body(&refresh_token)).await {
+        Ok(()) => println!("Revoked access at Google"),
+        Err(err) => warn_revoke_failed(err),
+    }
+}
+
+fn warn_revoke_failed(err: impl Display) {
+    eprintln!(
+        "Warning: Failed to revoke access at Google: {}. The access can be removed at https://myaccount.google.com/permissions",
+        err
+    );
+}
+
+// None when the stored tokens don't include a refresh token
+pub fn stored_refresh_token(app_cfg: &AppConfig) -> Result<Option<String>, account_json::Error> {
+    match AccountJson::from_account(app_cfg) {
+        Ok(account_json) => Ok(Some(account_json.refresh_token)),
+        Err(account_json::Error::MissingRefreshToken) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+// Form encoded body of the revocation request
+pub fn revoke_request_body(token: &str) -> String {
+    let encoded: String = token
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect();
+
+    format!("token={}", encoded)
+}
+
+fn confirm_remove(account_name: &str) -> Result<bool, Error> {
+    if !io::stdin().is_terminal() {
+        return Err(Error::NotInteractive);
+    }
+
+    let _prompt = cancel::Prompt::start();
+    print!("Remove account '{}'? [y/N] ", account_name);
+    let _ = io::stdout().flush();
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).map_err(Error::Prompt)?;
+    Ok(matches!(input.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
 #[derive(Debug)]
 pub enum Error {
     AppConfig(app_config::Error),
     AccountNotFound(String),
+    NotInteractive,
+    Prompt(io::Error),
 }
 
 impl error::Error for Error {}
@@ -33,6 +123,11 @@ impl Display for Error {
         match self {
             Error::AppConfig(e) => write!(f, "{}", e),
             Error::AccountNotFound(name) => write!(f, "Account '{}' not found", name),
+            Error::NotInteractive => write!(
+                f,
+                "Can't ask for confirmation without a terminal, use --force to remove the account"
+            ),
+            Error::Prompt(err) => write!(f, "Failed to read confirmation: {}", err),
         }
     }
 }
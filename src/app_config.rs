@@ -1,3 +1,5 @@
+use crate::common::mime_overrides::MimeOverrides;
+use crate::hub;
 use serde::Deserialize;
 use serde::Serialize;
 use std::error;
@@ -5,13 +7,18 @@ use std::fmt::Display;
 use std::fmt::Formatter;
 use std::fs;
 use std::io;
+use std::path::Path;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 const SYSTEM_CONFIG_DIR_NAME: &str = ".config";
 const BASE_PATH_DIR_NAME: &str = "gdrive3";
 const ACCOUNT_CONFIG_NAME: &str = "account.json";
 const SECRET_CONFIG_NAME: &str = "secret.json";
 const TOKENS_CONFIG_NAME: &str = "tokens.json";
+const SETTINGS_CONFIG_NAME: &str = "settings.json";
+const SCOPE_CONFIG_NAME: &str = "scope.json";
+const CONFIG_DIR_ENV: &str = "GDRIVE_CONFIG_DIR";
 
 #[derive(Debug, Clone)]
 pub struct AppConfig {
@@ -23,9 +30,11 @@ pub fn add_account(
     account_name: &str,
     secret: &Secret,
     tokens_path: &PathBuf,
+    scope: AccountScope,
 ) -> Result<AppConfig, Error> {
     let config = AppConfig::init_account(account_name)?;
     config.save_secret(secret)?;
+    config.save_scope(scope)?;
     fs::copy(tokens_path, config.tokens_path()).map_err(Error::CopyTokens)?;
     Ok(config)
 }
@@ -36,7 +45,13 @@ pub fn switch_account(config: &AppConfig) -> Result<(), Error> {
 
 pub fn list_accounts() -> Result<Vec<String>, Error> {
     let base_path = AppConfig::default_base_path()?;
-    fs::create_dir_all(&base_path).map_err(|err| Error::CreateBaseDir(base_path.clone(), err))?;
+    list_accounts_in(&base_path)
+}
+
+// Accounts in the config directory at base_path
+pub fn list_accounts_in(base_path: &Path) -> Result<Vec<String>, Error> {
+    fs::create_dir_all(base_path)
+        .map_err(|err| Error::CreateBaseDir(base_path.to_path_buf(), err))?;
     let entries = fs::read_dir(base_path).map_err(Error::ListFiles)?;
 
     let mut accounts: Vec<String> = entries
@@ -63,9 +78,17 @@ impl AppConfig {
 
     pub fn load_current_account() -> Result<AppConfig, Error> {
         let base_path = AppConfig::default_base_path()?;
-        let account_config = AppConfig::load_account_config()?;
+        AppConfig::load_current_account_in(&base_path)
+    }
+
+    // Current account of the config directory at base_path
+    pub fn load_current_account_in(base_path: &Path) -> Result<AppConfig, Error> {
+        let account_config = AppConfig::load_account_config_in(base_path)?;
         let account = Account::new(&account_config.current);
-        let config = AppConfig { base_path, account };
+        let config = AppConfig {
+            base_path: base_path.to_path_buf(),
+            account,
+        };
         Ok(config)
     }
 
@@ -78,9 +101,17 @@ impl AppConfig {
 
     pub fn init_account(account_name: &str) -> Result<AppConfig, Error> {
         let base_path = AppConfig::default_base_path()?;
+        AppConfig::init_account_in(&base_path, account_name)
+    }
+
+    // Creates the account in the config directory at base_path
+    pub fn init_account_in(base_path: &Path, account_name: &str) -> Result<AppConfig, Error> {
         let account = Account::new(account_name);
 
-        let config = AppConfig { base_path, account };
+        let config = AppConfig {
+            base_path: base_path.to_path_buf(),
+            account,
+        };
         config.create_account_dir()?;
 
         Ok(config)
@@ -118,8 +149,32 @@ impl AppConfig {
         serde_json::from_str(&content).map_err(Error::DeserializeSecret)
     }
 
+    pub fn save_scope(&self, scope: AccountScope) -> Result<(), Error> {
+        let content =
+            serde_json::to_string_pretty(&ScopeConfig { scope }).map_err(Error::SerializeScope)?;
+        fs::write(self.scope_path(), content).map_err(Error::WriteScope)?;
+        Ok(())
+    }
+
+    // Accounts added before the scope was saved use the full drive scope
+    pub fn load_scope(&self) -> Result<AccountScope, Error> {
+        let path = self.scope_path();
+        if !path.exists() {
+            return Ok(AccountScope::default());
+        }
+
+        let content = fs::read_to_string(path).map_err(Error::ReadScope)?;
+        let config: ScopeConfig =
+            serde_json::from_str(&content).map_err(Error::DeserializeScope)?;
+        Ok(config.scope)
+    }
+
     pub fn load_account_config() -> Result<AccountConfig, Error> {
         let base_path = AppConfig::default_base_path()?;
+        AppConfig::load_account_config_in(&base_path)
+    }
+
+    pub fn load_account_config_in(base_path: &Path) -> Result<AccountConfig, Error> {
         let account_config_path = base_path.join(ACCOUNT_CONFIG_NAME);
         account_config_path
             .exists()
@@ -129,6 +184,18 @@ impl AppConfig {
         serde_json::from_str(&content).map_err(Error::DeserializeAccountConfig)
     }
 
+    pub fn load_settings() -> Result<Settings, Error> {
+        let base_path = AppConfig::default_base_path()?;
+        let settings_path = base_path.join(SETTINGS_CONFIG_NAME);
+
+        if !settings_path.exists() {
+            return Ok(Settings::default());
+        }
+
+        let content = fs::read_to_string(settings_path).map_err(Error::ReadSettings)?;
+        serde_json::from_str(&content).map_err(Error::DeserializeSettings)
+    }
+
     pub fn save_account_config(&self) -> Result<(), Error> {
         let account_config = AccountConfig {
             current: self.account.name.clone(),
@@ -156,7 +223,15 @@ impl AppConfig {
         self.account_base_path().join(TOKENS_CONFIG_NAME)
     }
 
+    pub fn scope_path(&self) -> PathBuf {
+        self.account_base_path().join(SCOPE_CONFIG_NAME)
+    }
+
     pub fn default_base_path() -> Result<PathBuf, Error> {
+        if let Some(path) = std::env::var_os(CONFIG_DIR_ENV) {
+            return Ok(PathBuf::from(path));
+        }
+
         let home_path = home::home_dir().ok_or(Error::HomeDirNotFound)?;
         let base_path = home_path
             .join(SYSTEM_CONFIG_DIR_NAME)
@@ -176,6 +251,68 @@ pub struct AccountConfig {
     pub current: String,
 }
 
+// Global settings stored in settings.json in the config directory
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub read_only: bool,
+
+    // Extension to mime type, i.e. {"md": "text/markdown"}, used before guessing from the extension
+    #[serde(default)]
+    pub mime_types: MimeOverrides,
+}
+
+// Access the account was added with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum AccountScope {
+    // All files in the drive
+    #[default]
+    #[serde(rename = "drive")]
+    Full,
+
+    // Only files created or opened by gdrive
+    #[serde(rename = "drive.file")]
+    File,
+}
+
+impl AccountScope {
+    pub fn oauth_scopes(&self) -> &'static [&'static str] {
+        match self {
+            AccountScope::Full => hub::SCOPES,
+            AccountScope::File => &[hub::FILE_SCOPE],
+        }
+    }
+}
+
+impl FromStr for AccountScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "drive" => Ok(AccountScope::Full),
+            "drive.file" => Ok(AccountScope::File),
+            _ => Err(format!(
+                "'{}' is not a valid scope, valid scopes are: drive, drive.file",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for AccountScope {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            AccountScope::Full => write!(f, "drive"),
+            AccountScope::File => write!(f, "drive.file"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct ScopeConfig {
+    scope: AccountScope,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Account {
     pub name: String,
@@ -224,6 +361,12 @@ pub enum Error {
     RemoveAccountDir(io::Error),
     RemoveAccountConfig(io::Error),
     CreateBaseDir(PathBuf, io::Error),
+    ReadSettings(io::Error),
+    DeserializeSettings(serde_json::Error),
+    SerializeScope(serde_json::Error),
+    WriteScope(io::Error),
+    ReadScope(io::Error),
+    DeserializeScope(serde_json::Error),
 }
 
 impl error::Error for Error {}
@@ -322,6 +465,36 @@ impl Display for Error {
                     err
                 )
             }
+
+            Error::ReadSettings(err) => {
+                // fmt
+                write!(f, "Failed to read settings: {}", err)
+            }
+
+            Error::DeserializeSettings(err) => {
+                // fmt
+                write!(f, "Failed to deserialize settings: {}", err)
+            }
+
+            Error::SerializeScope(err) => {
+                // fmt
+                write!(f, "Failed to serialize account scope: {}", err)
+            }
+
+            Error::WriteScope(err) => {
+                // fmt
+                write!(f, "Failed to write account scope: {}", err)
+            }
+
+            Error::ReadScope(err) => {
+                // fmt
+                write!(f, "Failed to read account scope: {}", err)
+            }
+
+            Error::DeserializeScope(err) => {
+                // fmt
+                write!(f, "Failed to deserialize account scope: {}", err)
+            }
         }
     }
 }
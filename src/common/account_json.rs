@@ -0,0 +1,142 @@
+use crate::app_config;
+use crate::app_config::set_file_permissions;
+use crate::app_config::AppConfig;
+use crate::hub;
+use serde::Deserialize;
+use serde::Serialize;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Self-contained account credentials, suitable for storing in a CI secret
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountJson {
+    pub account_name: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub refresh_token: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+}
+
+impl AccountJson {
+    pub fn from_account(app_cfg: &AppConfig) -> Result<AccountJson, Error> {
+        let secret = app_cfg.load_secret().map_err(Error::AppConfig)?;
+        let content = fs::read_to_string(app_cfg.tokens_path()).map_err(Error::ReadTokens)?;
+        let tokens: Vec<StoredToken> = serde_json::from_str(&content).map_err(Error::ParseTokens)?;
+
+        let (scopes, refresh_token) = tokens
+            .into_iter()
+            .find_map(|t| t.token.refresh_token.map(|token| (t.scopes, token)))
+            .ok_or(Error::MissingRefreshToken)?;
+
+        Ok(AccountJson {
+            account_name: app_cfg.account.name.clone(),
+            client_id: secret.client_id,
+            client_secret: secret.client_secret,
+            refresh_token,
+            scopes,
+        })
+    }
+
+    pub fn from_json(s: &str) -> Result<AccountJson, Error> {
+        serde_json::from_str(s.trim()).map_err(Error::Deserialize)
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string(self).map_err(Error::Serialize)
+    }
+
+    // Creates the account directory with the secret and a tokens file holding only the refresh token.
+    // A new access token is fetched on first use.
+    pub fn save(&self) -> Result<AppConfig, Error> {
+        let base_path = AppConfig::default_base_path().map_err(Error::AppConfig)?;
+        self.save_in(&base_path)
+    }
+
+    // Same as save, in the config directory at base_path
+    pub fn save_in(&self, base_path: &Path) -> Result<AppConfig, Error> {
+        let app_cfg =
+            AppConfig::init_account_in(base_path, &self.account_name).map_err(Error::AppConfig)?;
+
+        let secret = app_config::Secret {
+            client_id: self.client_id.clone(),
+            client_secret: self.client_secret.clone(),
+        };
+        app_cfg.save_secret(&secret).map_err(Error::AppConfig)?;
+
+        let scopes = if self.scopes.is_empty() {
+            hub::SCOPES.iter().map(|s| s.to_string()).collect()
+        } else {
+            self.scopes.clone()
+        };
+
+        let tokens = vec![StoredToken {
+            scopes,
+            token: StoredTokenInfo {
+                access_token: None,
+                refresh_token: Some(self.refresh_token.clone()),
+                expires_at: None,
+                id_token: None,
+            },
+        }];
+
+        let content = serde_json::to_string(&tokens).map_err(Error::Serialize)?;
+        let tokens_path = app_cfg.tokens_path();
+        fs::write(&tokens_path, content).map_err(Error::WriteTokens)?;
+
+        if let Err(err) = set_file_permissions(&tokens_path) {
+            eprintln!(
+                "Warning: Failed to set file permissions on tokens file: {}",
+                err
+            );
+        }
+
+        Ok(app_cfg)
+    }
+}
+
+// Mirrors the token storage format used by the authenticator
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredToken {
+    scopes: Vec<String>,
+    token: StoredTokenInfo,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredTokenInfo {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    expires_at: Option<serde_json::Value>,
+    id_token: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    AppConfig(app_config::Error),
+    ReadTokens(io::Error),
+    WriteTokens(io::Error),
+    ParseTokens(serde_json::Error),
+    MissingRefreshToken,
+    Serialize(serde_json::Error),
+    Deserialize(serde_json::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::AppConfig(err) => write!(f, "{}", err),
+            Error::ReadTokens(err) => write!(f, "Failed to read tokens: {}", err),
+            Error::WriteTokens(err) => write!(f, "Failed to write tokens: {}", err),
+            Error::ParseTokens(err) => write!(f, "Failed to parse tokens: {}", err),
+            Error::MissingRefreshToken => write!(f, "Account does not have a refresh token"),
+            Error::Serialize(err) => write!(f, "Failed to serialize account: {}", err),
+            Error::Deserialize(err) => write!(f, "Invalid account json: {}", err),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use crate::app_config;
+use crate::app_config::AppConfig;
+use crate::common::cancel;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::io;
+use std::io::IsTerminal;
+use std::io::Write;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+static REMEMBER: AtomicBool = AtomicBool::new(false);
+
+// Makes the picked account the current account instead of using it for this run only
+pub fn enable_remember() {
+    REMEMBER.store(true, Ordering::SeqCst)
+}
+
+// Used when no account is current. A single account is used directly, with several
+// accounts the user is asked to pick one, or the accounts are listed in the error
+// when gdrive isn't running interactively.
+pub fn pick_account() -> Result<AppConfig, Error> {
+    let accounts = app_config::list_accounts().map_err(Error::AppConfig)?;
+
+    let account_name = match accounts.as_slice() {
+        [] => return Err(Error::NoAccounts),
+        [account_name] => account_name.clone(),
+        _ if is_interactive() => prompt_account(&accounts)?,
+        _ => return Err(Error::NotInteractive(accounts)),
+    };
+
+    let config = AppConfig::load_account(&account_name).map_err(Error::AppConfig)?;
+
+    if REMEMBER.load(Ordering::SeqCst) {
+        app_config::switch_account(&config).map_err(Error::AppConfig)?;
+        eprintln!("Switched to account '{}'", account_name);
+    } else {
+        eprintln!("Using account '{}' for this command", account_name);
+    }
+
+    Ok(config)
+}
+
+fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+fn prompt_account(accounts: &[String]) -> Result<String, Error> {
+    let _prompt = cancel::Prompt::start();
+    eprintln!("No account has been selected, pick one:");
+    for (i, account_name) in accounts.iter().enumerate() {
+        eprintln!("  {}) {}", i + 1, account_name);
+    }
+
+    loop {
+        eprint!("Account [1-{}]: ", accounts.len());
+        let _ = io::stderr().flush();
+
+        let mut input = String::new();
+        let read = io::stdin().read_line(&mut input).map_err(Error::Prompt)?;
+        if read == 0 {
+            return Err(Error::NotInteractive(accounts.to_vec()));
+        }
+
+        match parse_choice(&input, accounts) {
+            Some(account_name) => return Ok(account_name.clone()),
+            None => eprintln!("'{}' is not one of the listed accounts", input.trim()),
+        }
+    }
+}
+
+// Accepts the number shown in the list or the account name
+pub fn parse_choice<'a>(input: &str, accounts: &'a [String]) -> Option<&'a String> {
+    let input = input.trim();
+
+    match input.parse::<usize>() {
+        Ok(n) if n >= 1 => accounts.get(n - 1),
+        Ok(_) => None,
+        Err(_) => accounts.iter().find(|account_name| *account_name == input),
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    AppConfig(app_config::Error),
+    NoAccounts,
+    NotInteractive(Vec<String>),
+    Prompt(io::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::AppConfig(err) => write!(f, "{}", err),
+            Error::NoAccounts => {
+                write!(f, "No accounts found, use `gdrive account add` to add one")
+            }
+            Error::NotInteractive(accounts) => {
+                writeln!(f, "No account has been selected, available accounts:")?;
+                for account_name in accounts {
+                    writeln!(f, "  {}", account_name)?;
+                }
+                write!(
+                    f,
+                    "Use `gdrive account switch <ACCOUNT_NAME>` to select an account."
+                )
+            }
+            Error::Prompt(err) => write!(f, "Failed to read account choice: {}", err),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use age::secrecy::Secret;
+use std::env;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::io::IsTerminal;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+// Read instead of prompting, for scripts
+pub const PASSPHRASE_ENV: &str = "GDRIVE_EXPORT_PASSPHRASE";
+
+// Every age file starts with this line, which is how encrypted archives are recognized
+const AGE_HEADER: &[u8] = b"age-encryption.org/v1";
+
+pub fn is_encrypted(content: &[u8]) -> bool {
+    content.starts_with(AGE_HEADER)
+}
+
+pub fn is_encrypted_file(path: &Path) -> Result<bool, Error> {
+    let mut file = fs::File::open(path).map_err(|err| Error::Read(path.to_path_buf(), err))?;
+    let mut header = vec![0; AGE_HEADER.len()];
+    let read = file
+        .read(&mut header)
+        .map_err(|err| Error::Read(path.to_path_buf(), err))?;
+
+    Ok(is_encrypted(&header[..read]))
+}
+
+pub fn encrypt(content: &[u8], passphrase: &str) -> Result<Vec<u8>, Error> {
+    let encryptor = age::Encryptor::with_user_passphrase(Secret::new(passphrase.to_string()));
+
+    let mut encrypted = vec![];
+    let mut writer = encryptor
+        .wrap_output(&mut encrypted)
+        .map_err(|err| Error::Encrypt(err.to_string()))?;
+    writer
+        .write_all(content)
+        .map_err(|err| Error::Encrypt(err.to_string()))?;
+    writer
+        .finish()
+        .map_err(|err| Error::Encrypt(err.to_string()))?;
+
+    Ok(encrypted)
+}
+
+pub fn decrypt(content: &[u8], passphrase: &str) -> Result<Vec<u8>, Error> {
+    let decryptor =
+        match age::Decryptor::new(content).map_err(|err| Error::Decrypt(err.to_string()))? {
+            age::Decryptor::Passphrase(decryptor) => decryptor,
+            _ => return Err(Error::NotPassphraseEncrypted),
+        };
+
+    let mut reader = decryptor
+        .decrypt(&Secret::new(passphrase.to_string()), None)
+        .map_err(|err| Error::Decrypt(err.to_string()))?;
+
+    let mut decrypted = vec![];
+    reader
+        .read_to_end(&mut decrypted)
+        .map_err(|err| Error::Decrypt(err.to_string()))?;
+
+    Ok(decrypted)
+}
+
+// The passphrase comes from PASSPHRASE_ENV, or is asked for without echo. A new passphrase
+// is asked for twice.
+pub fn read_passphrase(confirm: bool) -> Result<String, Error> {
+    if let Some(passphrase) = env::var(PASSPHRASE_ENV).ok().filter(|p| !p.is_empty()) {
+        return Ok(passphrase);
+    }
+
+    if !io::stdin().is_terminal() {
+        return Err(Error::NotInteractive);
+    }
+
+    let passphrase = rpassword::prompt_password("Passphrase: ").map_err(Error::Prompt)?;
+    if passphrase.is_empty() {
+        return Err(Error::EmptyPassphrase);
+    }
+
+    if confirm {
+        let repeated = rpassword::prompt_password("Repeat passphrase: ").map_err(Error::Prompt)?;
+        if repeated != passphrase {
+            return Err(Error::PassphraseMismatch);
+        }
+    }
+
+    Ok(passphrase)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Read(PathBuf, io::Error),
+    Encrypt(String),
+    Decrypt(String),
+    NotPassphraseEncrypted,
+    NotInteractive,
+    Prompt(io::Error),
+    EmptyPassphrase,
+    PassphraseMismatch,
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Read(path, err) => write!(f, "Failed to read '{}': {}", path.display(), err),
+            Error::Encrypt(err) => write!(f, "Failed to encrypt archive: {}", err),
+            Error::Decrypt(err) => write!(
+                f,
+                "Failed to decrypt archive, check the passphrase: {}",
+                err
+            ),
+            Error::NotPassphraseEncrypted => {
+                write!(f, "Archive is encrypted for a key instead of a passphrase")
+            }
+            Error::NotInteractive => write!(
+                f,
+                "A passphrase is needed, set {} when not running in a terminal",
+                PASSPHRASE_ENV
+            ),
+            Error::Prompt(err) => write!(f, "Failed to read passphrase: {}", err),
+            Error::EmptyPassphrase => write!(f, "Passphrase can't be empty"),
+            Error::PassphraseMismatch => write!(f, "Passphrases don't match"),
+        }
+    }
+}
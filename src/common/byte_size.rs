@@ -0,0 +1,141 @@
+use human_bytes::human_bytes;
+use std::fmt;
+use std::fmt::Formatter;
+use std::str::FromStr;
+
+pub const KIB: u64 = 1 << 10;
+pub const MIB: u64 = 1 << 20;
+pub const GIB: u64 = 1 << 30;
+pub const TIB: u64 = 1 << 40;
+pub const PIB: u64 = 1 << 50;
+
+// Largest unit first, used when formatting
+const UNITS: [(u64, &str); 5] = [(PIB, "P"), (TIB, "T"), (GIB, "G"), (MIB, "M"), (KIB, "K")];
+
+// Digits after the decimal point beyond this can't change the result
+const MAX_FRACTION_DIGITS: usize = 18;
+
+// A size in bytes. Parsed from raw bytes ("1048576") or a number with a binary unit
+// ("512K", "1.5G", "8MiB"), and displayed in the largest unit that represents it exactly.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    pub fn in_bytes(&self) -> u64 {
+        self.0
+    }
+}
+
+impl FromStr for ByteSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s).map(ByteSize)
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let unit = UNITS
+            .iter()
+            .find(|(unit_bytes, _)| self.0 != 0 && self.0 % unit_bytes == 0);
+
+        match unit {
+            Some((unit_bytes, suffix)) => write!(f, "{}{}", self.0 / unit_bytes, suffix),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+pub fn parse(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let unit_start = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(unit_start);
+
+    let multiplier = unit_multiplier(unit.trim())
+        .ok_or_else(|| invalid_size(s, "unknown unit, valid units are K, M, G, T and P"))?;
+
+    let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+
+    if integer.is_empty() || (number.contains('.') && fraction.is_empty()) {
+        return Err(invalid_size(s, "expected a number"));
+    }
+
+    if fraction.contains('.') {
+        return Err(invalid_size(s, "expected a number"));
+    }
+
+    if multiplier == 1 && fraction.trim_end_matches('0') != "" {
+        return Err(invalid_size(s, "a size in bytes can't have a fraction"));
+    }
+
+    let too_large = || invalid_size(s, "size is too large");
+
+    let integer: u128 = integer.parse().map_err(|_| too_large())?;
+    let mut bytes = integer
+        .checked_mul(u128::from(multiplier))
+        .ok_or_else(too_large)?;
+
+    // The fraction is truncated to whole bytes
+    let fraction = &fraction[..fraction.len().min(MAX_FRACTION_DIGITS)];
+    if !fraction.is_empty() {
+        let numerator: u128 = fraction.parse().map_err(|_| too_large())?;
+        let denominator = 10u128.pow(fraction.len() as u32);
+        bytes += numerator * u128::from(multiplier) / denominator;
+    }
+
+    u64::try_from(bytes).map_err(|_| too_large())
+}
+
+fn unit_multiplier(unit: &str) -> Option<u64> {
+    match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => Some(1),
+        "k" | "kb" | "kib" => Some(KIB),
+        "m" | "mb" | "mib" => Some(MIB),
+        "g" | "gb" | "gib" => Some(GIB),
+        "t" | "tb" | "tib" => Some(TIB),
+        "p" | "pb" | "pib" => Some(PIB),
+        _ => None,
+    }
+}
+
+fn invalid_size(s: &str, reason: &str) -> String {
+    format!("'{}' is not a valid size: {}", s, reason)
+}
+
+// Human readable size for display, i.e. "1.5 GiB". Takes u128 so totals of many files don't need a cast.
+pub fn format_human(bytes: impl Into<u128>) -> String {
+    human_bytes(bytes.into() as f64)
+}
+
+// Units of human readable sizes, binary (KiB, powers of 1024) or decimal (kB, powers of 1000)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SizeUnits {
+    #[default]
+    Iec,
+    Si,
+}
+
+const SI_UNITS: [&str; 6] = ["B", "kB", "MB", "GB", "TB", "PB"];
+
+pub fn format_human_in(bytes: impl Into<u128>, units: SizeUnits) -> String {
+    match units {
+        SizeUnits::Iec => format_human(bytes),
+        SizeUnits::Si => format_si(bytes.into()),
+    }
+}
+
+// Rounded to one decimal like format_human, i.e. "1.5 GB"
+fn format_si(bytes: u128) -> String {
+    let mut value = bytes as f64;
+    let mut unit = 0;
+
+    while unit + 1 < SI_UNITS.len() && (value * 10.0).round() / 10.0 >= 1000.0 {
+        value /= 1000.0;
+        unit += 1;
+    }
+
+    format!("{} {}", (value * 10.0).round() / 10.0, SI_UNITS[unit])
+}
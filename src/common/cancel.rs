@@ -0,0 +1,152 @@
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+
+// Exit code used when gdrive is stopped with Ctrl-C, same as a shell reports for SIGINT
+pub const EXIT_CODE: i32 = 130;
+
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+static PROMPTING: AtomicBool = AtomicBool::new(false);
+static PARTIAL_FILES: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+static IN_PROGRESS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+// The first Ctrl-C lets recursive operations stop after the current file, the second
+// one removes partially written files and exits right away. Ctrl-C while waiting for
+// an answer to a prompt stops right away, as nothing is being transferred.
+pub fn listen() {
+    tokio::spawn(async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+
+        eprintln!();
+        if PROMPTING.load(Ordering::SeqCst) {
+            stop_now();
+        }
+
+        cancel();
+        match in_progress().as_slice() {
+            [path] => eprintln!(
+                "Stopping after '{}', press Ctrl-C again to stop now",
+                path.display()
+            ),
+            _ => eprintln!("Stopping after the current file, press Ctrl-C again to stop now"),
+        }
+
+        if tokio::signal::ctrl_c().await.is_ok() {
+            stop_now();
+        }
+    });
+}
+
+fn stop_now() {
+    remove_partial_files();
+
+    for path in in_progress() {
+        eprintln!(
+            "Interrupted '{}', it has to be transferred again",
+            path.display()
+        );
+    }
+
+    eprintln!("Cancelled");
+    process::exit(EXIT_CODE);
+}
+
+pub fn cancel() {
+    CANCELLED.store(true, Ordering::SeqCst)
+}
+
+pub fn is_cancelled() -> bool {
+    CANCELLED.load(Ordering::SeqCst)
+}
+
+pub fn remove_partial_files() {
+    let paths = PARTIAL_FILES.lock().map(|paths| paths.clone()).unwrap_or_default();
+
+    for path in paths {
+        if path.is_dir() {
+            let _ = fs::remove_dir_all(path);
+        } else {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+pub fn in_progress() -> Vec<PathBuf> {
+    IN_PROGRESS.lock().map(|paths| paths.clone()).unwrap_or_default()
+}
+
+// A local file or temporary directory that is being written. It's removed if gdrive is
+// stopped before the guard is dropped, which should happen once the file is complete.
+#[derive(Debug)]
+pub struct PartialFile {
+    path: PathBuf,
+}
+
+impl PartialFile {
+    pub fn new(path: &Path) -> PartialFile {
+        if let Ok(mut paths) = PARTIAL_FILES.lock() {
+            paths.push(path.to_path_buf());
+        }
+
+        PartialFile {
+            path: path.to_path_buf(),
+        }
+    }
+}
+
+impl Drop for PartialFile {
+    fn drop(&mut self) {
+        if let Ok(mut paths) = PARTIAL_FILES.lock() {
+            paths.retain(|path| path != &self.path);
+        }
+    }
+}
+
+// A file that is being uploaded or downloaded, printed when gdrive is stopped
+pub struct InProgress {
+    path: PathBuf,
+}
+
+impl InProgress {
+    pub fn new(path: &Path) -> InProgress {
+        if let Ok(mut paths) = IN_PROGRESS.lock() {
+            paths.push(path.to_path_buf());
+        }
+
+        InProgress {
+            path: path.to_path_buf(),
+        }
+    }
+}
+
+impl Drop for InProgress {
+    fn drop(&mut self) {
+        if let Ok(mut paths) = IN_PROGRESS.lock() {
+            if let Some(i) = paths.iter().position(|path| path == &self.path) {
+                paths.remove(i);
+            }
+        }
+    }
+}
+
+// Held while waiting for the user to answer a prompt
+pub struct Prompt;
+
+impl Prompt {
+    pub fn start() -> Prompt {
+        PROMPTING.store(true, Ordering::SeqCst);
+        Prompt
+    }
+}
+
+impl Drop for Prompt {
+    fn drop(&mut self) {
+        PROMPTING.store(false, Ordering::SeqCst)
+    }
+}
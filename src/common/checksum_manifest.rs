@@ -0,0 +1,45 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Lists downloaded files with their md5 checksum in the format read by `md5sum -c`.
+// Paths are relative to the download destination.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChecksumManifest {
+    lines: Vec<String>,
+}
+
+impl ChecksumManifest {
+    pub fn push_checksum(&mut self, path: &Path, md5: &str) {
+        let path = path.to_string_lossy();
+
+        // md5sum escapes file names containing a backslash or newline and marks the line with a leading backslash
+        if path.contains('\\') || path.contains('\n') {
+            let escaped = path.replace('\\', "\\\\").replace('\n', "\\n");
+            self.lines.push(format!("\\{}  {}", md5, escaped));
+        } else {
+            self.lines.push(format!("{}  {}", md5, path));
+        }
+    }
+
+    // Files without a checksum, i.e. google documents, are noted in a comment so they aren't silently missing
+    pub fn push_skipped(&mut self, path: &Path, reason: &str) {
+        let path = path.to_string_lossy().replace('\n', "\\n");
+        self.lines.push(format!("# {}: {}", path, reason));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn to_text(&self) -> String {
+        self.lines
+            .iter()
+            .map(|line| format!("{}\n", line))
+            .collect()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), io::Error> {
+        fs::write(path, self.to_text())
+    }
+}
@@ -0,0 +1,71 @@
+use crate::app_config;
+use serde::Deserialize;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+// client_secret.json as downloaded from the Google Cloud console. Desktop apps have the
+// credentials under "installed", web apps under "web".
+#[derive(Debug, Deserialize)]
+struct ClientSecretFile {
+    installed: Option<OAuthClient>,
+    web: Option<OAuthClient>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OAuthClient {
+    client_id: Option<String>,
+    client_secret: Option<String>,
+}
+
+pub fn load(path: &Path) -> Result<app_config::Secret, Error> {
+    let content = fs::read_to_string(path).map_err(|err| Error::Read(path.to_path_buf(), err))?;
+    parse(&content)
+}
+
+pub fn parse(content: &str) -> Result<app_config::Secret, Error> {
+    let file: ClientSecretFile = serde_json::from_str(content).map_err(Error::Deserialize)?;
+    let client = file.installed.or(file.web).ok_or(Error::MissingClient)?;
+
+    Ok(app_config::Secret {
+        client_id: required(client.client_id, "client_id")?,
+        client_secret: required(client.client_secret, "client_secret")?,
+    })
+}
+
+fn required(value: Option<String>, field: &'static str) -> Result<String, Error> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .ok_or(Error::MissingField(field))
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Read(PathBuf, io::Error),
+    Deserialize(serde_json::Error),
+    MissingClient,
+    MissingField(&'static str),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Read(path, err) => write!(f, "Failed to read '{}': {}", path.display(), err),
+            Error::Deserialize(err) => write!(f, "Invalid client secret json: {}", err),
+            Error::MissingClient => write!(
+                f,
+                "Invalid client secret json: expected an 'installed' or 'web' client, download the json of an OAuth client ID from the Google Cloud console"
+            ),
+            Error::MissingField(field) => {
+                write!(f, "Invalid client secret json: '{}' is missing", field)
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use crate::common::cancel;
+use std::fmt;
+use std::fmt::Formatter;
+use std::future::Future;
+use std::process;
+use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+// Exit code used when the deadline has passed, the same as timeout(1) uses
+pub const EXIT_CODE: i32 = 124;
+
+// Time recursive operations get to finish the current file once the deadline has passed
+pub const GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+// Largest unit first, used when formatting
+const UNITS: [(u64, char); 3] = [(3600, 'h'), (60, 'm'), (1, 's')];
+
+static EXPIRED: AtomicBool = AtomicBool::new(false);
+
+// Wall-clock limit for a whole command. Parsed from seconds ("90") or numbers with a unit
+// ("90s", "15m", "1h30m"), and displayed with every non-zero unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Deadline(Duration);
+
+impl Deadline {
+    pub fn duration(&self) -> Duration {
+        self.0
+    }
+}
+
+impl FromStr for Deadline {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let seconds = parse_seconds(s.trim())?;
+        if seconds == 0 {
+            return Err(format!("Invalid deadline '{}': must be longer than 0s", s));
+        }
+
+        Ok(Deadline(Duration::from_secs(seconds)))
+    }
+}
+
+impl fmt::Display for Deadline {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut seconds = self.0.as_secs();
+        if seconds == 0 {
+            return write!(f, "0s");
+        }
+
+        for (unit_seconds, suffix) in UNITS {
+            if seconds >= unit_seconds {
+                write!(f, "{}{}", seconds / unit_seconds, suffix)?;
+                seconds %= unit_seconds;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_seconds(s: &str) -> Result<u64, String> {
+    let invalid = |reason: &str| format!("Invalid deadline '{}': {}", s, reason);
+
+    if !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()) {
+        return s.parse().map_err(|_| invalid("too long"));
+    }
+
+    let mut seconds: u64 = 0;
+    let mut number = String::new();
+    let mut last_unit = None;
+
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let position = UNITS
+            .iter()
+            .position(|(_, suffix)| *suffix == c)
+            .ok_or_else(|| invalid("expected a number of seconds or units h, m and s"))?;
+
+        // Units have to be in order and can't repeat, i.e. 1h30m but not 30m1h
+        if number.is_empty() || matches!(last_unit, Some(last) if position <= last) {
+            return Err(invalid(
+                "expected a number before each unit, largest unit first",
+            ));
+        }
+
+        let (unit_seconds, _) = UNITS[position];
+        let value: u64 = number.parse().map_err(|_| invalid("too long"))?;
+        seconds = value
+            .checked_mul(unit_seconds)
+            .and_then(|value| seconds.checked_add(value))
+            .ok_or_else(|| invalid("too long"))?;
+
+        number.clear();
+        last_unit = Some(position);
+    }
+
+    if !number.is_empty() || last_unit.is_none() {
+        return Err(invalid("expected a unit after each number"));
+    }
+
+    Ok(seconds)
+}
+
+// Runs the command with a deadline. Once it passes, recursive operations stop after the
+// current file, like they do after Ctrl-C. If the command is still running after the grace
+// period, partial files are removed and gdrive exits.
+pub async fn run<F: Future<Output = ()>>(deadline: Deadline, command: F) {
+    tokio::spawn(async move {
+        tokio::time::sleep(deadline.duration()).await;
+        expire(deadline);
+    });
+
+    let limit = deadline.duration() + GRACE_PERIOD;
+    if tokio::time::timeout(limit, command).await.is_err() {
+        stop_now(deadline);
+    }
+}
+
+fn expire(deadline: Deadline) {
+    EXPIRED.store(true, Ordering::SeqCst);
+    cancel::cancel();
+    eprintln!(
+        "Deadline of {} reached, stopping after the current file",
+        deadline
+    );
+}
+
+fn stop_now(deadline: Deadline) {
+    cancel::remove_partial_files();
+
+    for path in cancel::in_progress() {
+        eprintln!(
+            "Interrupted '{}', it has to be transferred again",
+            path.display()
+        );
+    }
+
+    eprintln!(
+        "Stopped {} after the deadline of {}",
+        Deadline(GRACE_PERIOD),
+        deadline
+    );
+    process::exit(EXIT_CODE);
+}
+
+pub fn is_expired() -> bool {
+    EXPIRED.load(Ordering::SeqCst)
+}
@@ -1,9 +1,25 @@
+use crate::common::byte_size;
+use crate::common::byte_size::ByteSize;
+use crate::common::byte_size::MIB;
+use crate::common::drive_error;
+use google_drive3::chrono;
 use google_drive3::hyper;
 use google_drive3::hyper::http;
-use human_bytes::human_bytes;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
 use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
 use std::time::Duration;
+use std::time::Instant;
 
 #[derive(Debug, Clone)]
 pub struct UploadDelegateConfig {
@@ -11,6 +27,8 @@ pub struct UploadDelegateConfig {
     pub backoff_config: BackoffConfig,
     pub print_chunk_errors: bool,
     pub print_chunk_info: bool,
+    pub error_log: Option<PathBuf>,
+    pub upload_mode: UploadMode,
 }
 
 impl Default for UploadDelegateConfig {
@@ -20,6 +38,71 @@ impl Default for UploadDelegateConfig {
             backoff_config: BackoffConfig::default(),
             print_chunk_errors: false,
             print_chunk_info: false,
+            error_log: None,
+            upload_mode: UploadMode::default(),
+        }
+    }
+}
+
+impl UploadDelegateConfig {
+    // Settings of the upload flags upload and update share, with the default sleeps between retries
+    pub fn from_flags(
+        chunk_size: ChunkSize,
+        max_retry_duration: Duration,
+        print_chunk_errors: bool,
+        print_chunk_info: bool,
+        error_log: Option<PathBuf>,
+        upload_mode: UploadMode,
+    ) -> UploadDelegateConfig {
+        UploadDelegateConfig {
+            chunk_size,
+            backoff_config: BackoffConfig {
+                max_retry_duration,
+                ..BackoffConfig::default()
+            },
+            print_chunk_errors,
+            print_chunk_info,
+            error_log,
+            upload_mode,
+        }
+    }
+
+    // An empty file has no chunk to send, so it always goes in a single request. Otherwise files
+    // larger than a chunk are resumable, unless the mode forces one or the other.
+    pub fn is_resumable(&self, size: u64) -> bool {
+        let larger_than_chunk = size > self.chunk_size.in_bytes();
+        size > 0 && self.upload_mode.is_resumable(larger_than_chunk)
+    }
+}
+
+// Resumable uploads send the file in chunks and can pick up where they left off after a failed chunk,
+// at the cost of an extra request to start the session. Simple uploads send everything in one request
+// and have to start over if it fails.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum UploadMode {
+    #[default]
+    Auto,
+    Resumable,
+    Simple,
+}
+
+impl UploadMode {
+    pub fn from_flags(force_resumable: bool, force_simple: bool) -> UploadMode {
+        if force_resumable {
+            UploadMode::Resumable
+        } else if force_simple {
+            UploadMode::Simple
+        } else {
+            UploadMode::Auto
+        }
+    }
+
+    // Returns whether to use a resumable upload, auto_resumable is the size based decision of the caller
+    pub fn is_resumable(&self, auto_resumable: bool) -> bool {
+        match self {
+            UploadMode::Auto => auto_resumable,
+            UploadMode::Resumable => true,
+            UploadMode::Simple => false,
         }
     }
 }
@@ -57,7 +140,7 @@ impl UploadDelegate {
                 println!(
                     "Info: {} {} chunk ({}-{} of {})",
                     action,
-                    human_bytes(chunk_size as f64),
+                    byte_size::format_human(chunk_size),
                     range.first,
                     range.last,
                     chunk.total_length
@@ -65,6 +148,63 @@ impl UploadDelegate {
             }
         }
     }
+
+    // Only applies to --chunk-size auto. The client asks for the chunk size once per upload, so
+    // the new size is used from the next file on
+    fn tune_chunk_size(&self, record: fn(&ChunkTuner) -> Option<u64>, reason: &str) {
+        if let ChunkSize::Auto(tuner) = &self.config.chunk_size {
+            if let Some(chunk_size) = record(tuner) {
+                if self.config.print_chunk_info {
+                    println!(
+                        "Info: Chunk size set to {} after {}",
+                        ByteSize(chunk_size),
+                        reason
+                    );
+                }
+            }
+        }
+    }
+
+    fn log_chunk_error(&self, message: &str) {
+        if let Some(path) = &self.config.error_log {
+            let line = error_log_line(chrono::Utc::now(), self.previous_chunk.as_ref(), message);
+
+            if let Err(err) = append_error_log(path, &line) {
+                eprintln!(
+                    "Warning: Failed to write to error log '{}': {}",
+                    path.display(),
+                    err
+                );
+            }
+        }
+    }
+}
+
+// i.e. "2024-05-01T10:00:00+00:00\t0-262143 of 1048576\tmessage", the range is "-" until a chunk
+// has been sent
+pub fn error_log_line(
+    time: chrono::DateTime<chrono::Utc>,
+    chunk: Option<&google_drive3::client::ContentRange>,
+    message: &str,
+) -> String {
+    let range = chunk
+        .and_then(|chunk| Some((chunk.range.as_ref()?, chunk.total_length)))
+        .map(|(range, total)| format!("{}-{} of {}", range.first, range.last, total))
+        .unwrap_or_else(|| String::from("-"));
+
+    format!("{}\t{}\t{}", time.to_rfc3339(), range, message)
+}
+
+pub fn append_error_log(path: &Path, line: &str) -> Result<(), io::Error> {
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", line)
+}
+
+pub fn truncate_error_log(path: &Path) -> Result<(), io::Error> {
+    fs::File::create(path).map(|_| ())
 }
 
 impl google_drive3::client::Delegate for UploadDelegate {
@@ -73,6 +213,13 @@ impl google_drive3::client::Delegate for UploadDelegate {
     }
 
     fn cancel_chunk_upload(&mut self, chunk: &google_drive3::client::ContentRange) -> bool {
+        // Moving on to a new range means the previous chunk was accepted, so the retry budget
+        // starts over for the failures that come after it
+        if self.previous_chunk.is_some() && Some(chunk) != self.previous_chunk.as_ref() {
+            self.backoff.reset();
+            self.tune_chunk_size(ChunkTuner::record_success, "several successful chunks");
+        }
+
         self.print_chunk_info(chunk);
         self.previous_chunk = Some(chunk.clone());
 
@@ -88,41 +235,96 @@ impl google_drive3::client::Delegate for UploadDelegate {
     }
 
     fn http_error(&mut self, err: &hyper::Error) -> google_drive3::client::Retry {
+        let retry = self.backoff.retry(None);
+        self.tune_chunk_size(ChunkTuner::record_failure, "a failed chunk");
+
         if self.config.print_chunk_errors {
-            eprintln!("Warning: Failed attempt to upload chunk: {}", err);
+            eprintln!(
+                "Warning: Failed attempt to upload chunk: {}, {}",
+                err,
+                format_retry(&retry)
+            );
         }
-        self.backoff.retry()
+        self.log_chunk_error(&format!("Failed attempt to upload chunk: {}", err));
+        retry
     }
 
     fn http_failure(
         &mut self,
         res: &http::response::Response<hyper::body::Body>,
-        _err: Option<serde_json::Value>,
+        err: Option<serde_json::Value>,
     ) -> google_drive3::client::Retry {
         let status = res.status();
 
-        if should_retry(status) {
+        if should_retry(status, &err) {
+            let retry_after = parse_retry_after(res.headers(), chrono::Utc::now());
+            let retry = self.backoff.retry(retry_after);
+            self.tune_chunk_size(ChunkTuner::record_failure, "a failed chunk");
+
             if self.config.print_chunk_errors {
                 eprintln!(
-                    "Warning: Failed attempt to upload chunk. Status code: {}, body: {:?}",
+                    "Warning: Failed attempt to upload chunk. Status code: {}, body: {:?}, {}",
                     status,
-                    res.body()
+                    res.body(),
+                    format_retry(&retry)
                 );
             }
-            self.backoff.retry()
+            self.log_chunk_error(&format!(
+                "Failed attempt to upload chunk. Status code: {}",
+                status
+            ));
+            retry
         } else {
+            self.log_chunk_error(&format!("Aborting upload. Status code: {}", status));
             self.backoff.abort()
         }
     }
 }
 
-fn should_retry(status: http::StatusCode) -> bool {
-    status.is_server_error() || status == http::StatusCode::TOO_MANY_REQUESTS
+fn should_retry(status: http::StatusCode, err: &Option<serde_json::Value>) -> bool {
+    status.is_server_error()
+        || status == http::StatusCode::TOO_MANY_REQUESTS
+        || (status == http::StatusCode::FORBIDDEN && is_rate_limit_error(err))
+}
+
+// Drive reports rate limiting as 403 with a rate limit reason in the error body
+fn is_rate_limit_error(err: &Option<serde_json::Value>) -> bool {
+    err.as_ref()
+        .and_then(drive_error::quota_error_from_json)
+        .map_or(false, |quota_error| quota_error.is_rate_limit())
+}
+
+// Retry-After is either a number of seconds or a http date
+pub fn parse_retry_after(
+    headers: &http::HeaderMap,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Option<Duration> {
+    let value = headers.get(http::header::RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = date.with_timezone(&chrono::Utc) - now;
+    Some(delay.to_std().unwrap_or(Duration::ZERO))
+}
+
+fn format_retry(retry: &google_drive3::client::Retry) -> String {
+    match retry {
+        google_drive3::client::Retry::After(sleep) => {
+            format!("retrying in {:.1}s", sleep.as_secs_f64())
+        }
+        google_drive3::client::Retry::Abort => String::from("giving up"),
+    }
 }
 
+// How long failed requests are retried by default, --max-retry-duration overrides it
+pub const DEFAULT_MAX_RETRY_DURATION_SECS: u64 = 3600;
+
 #[derive(Debug, Clone)]
 pub struct BackoffConfig {
-    pub max_retries: u32,
+    pub max_retry_duration: Duration,
     pub min_sleep: Duration,
     pub max_sleep: Duration,
 }
@@ -130,34 +332,76 @@ pub struct BackoffConfig {
 impl Default for BackoffConfig {
     fn default() -> Self {
         BackoffConfig {
-            max_retries: 100,
+            max_retry_duration: Duration::from_secs(DEFAULT_MAX_RETRY_DURATION_SECS),
             min_sleep: Duration::from_secs(1),
             max_sleep: Duration::from_secs(60),
         }
     }
 }
 
+// Exponential backoff with full jitter, limited by the total time spent retrying
 pub struct Backoff {
+    config: BackoffConfig,
     attempts: u32,
-    backoff: exponential_backoff::Backoff,
+    started: Option<Instant>,
+    rng: StdRng,
 }
 
 impl Backoff {
     pub fn new(config: BackoffConfig) -> Backoff {
+        Backoff::with_rng(config, StdRng::from_entropy())
+    }
+
+    pub fn with_rng(config: BackoffConfig, rng: StdRng) -> Backoff {
         Backoff {
+            config,
             attempts: 0,
-            backoff: exponential_backoff::Backoff::new(
-                config.max_retries,
-                config.min_sleep,
-                config.max_sleep,
-            ),
+            started: None,
+            rng,
         }
     }
 
-    fn retry(&mut self) -> google_drive3::client::Retry {
+    // Returns the time to sleep before the next attempt, or None if the retry budget is spent.
+    // A server provided retry_after is used as is instead of the jittered sleep.
+    pub fn next_sleep(
+        &mut self,
+        elapsed: Duration,
+        retry_after: Option<Duration>,
+    ) -> Option<Duration> {
         self.attempts += 1;
-        self.backoff
-            .next(self.attempts)
+
+        let sleep = retry_after.unwrap_or_else(|| {
+            let cap = self.sleep_cap();
+            let millis = self.rng.gen_range(0, cap.as_millis() as u64 + 1);
+            Duration::from_millis(millis)
+        });
+
+        if elapsed + sleep > self.config.max_retry_duration {
+            None
+        } else {
+            Some(sleep)
+        }
+    }
+
+    // Forgets earlier failures, the next failure starts with the shortest sleep and a full budget
+    pub fn reset(&mut self) {
+        self.attempts = 0;
+        self.started = None;
+    }
+
+    fn sleep_cap(&self) -> Duration {
+        let exponent = self.attempts.saturating_sub(1).min(31);
+        self.config
+            .min_sleep
+            .checked_mul(1u32 << exponent)
+            .map(|sleep| sleep.min(self.config.max_sleep))
+            .unwrap_or(self.config.max_sleep)
+    }
+
+    fn retry(&mut self, retry_after: Option<Duration>) -> google_drive3::client::Retry {
+        let elapsed = self.started.get_or_insert_with(Instant::now).elapsed();
+
+        self.next_sleep(elapsed, retry_after)
             .map(google_drive3::client::Retry::After)
             .unwrap_or(google_drive3::client::Retry::Abort)
     }
@@ -167,61 +411,34 @@ impl Backoff {
     }
 }
 
-#[derive(Debug, Clone, Default)]
+const MAX_CHUNK_SIZE_MB: u64 = 8192;
+
+// Drive requires every chunk except the last to be a multiple of 256 KiB
+const CHUNK_ALIGNMENT: u64 = 256 * 1024;
+
+const AUTO_CHUNK_START: u64 = 8 * MIB;
+const AUTO_CHUNK_MAX: u64 = 256 * MIB;
+const AUTO_CHUNK_GROW_AFTER: u32 = 3;
+
+#[derive(Debug, Clone)]
 pub enum ChunkSize {
-    Approx1,
-    Approx2,
-    Approx4,
-    Approx8,
-    Approx16,
-    #[default]
-    Approx32,
-    Approx64,
-    Approx128,
-    Approx256,
-    Approx512,
-    Approx1024,
-    Approx2048,
-    Approx4096,
-    Approx8192,
+    Fixed(u64),
+    Auto(ChunkTuner),
+}
+
+impl Default for ChunkSize {
+    fn default() -> Self {
+        ChunkSize::Fixed(32 * MIB)
+    }
 }
 
 impl ChunkSize {
+    // The current chunk size, for auto this changes as chunks succeed or fail. Uploads read it
+    // once when they start.
     pub fn in_bytes(&self) -> u64 {
         match self {
-            ChunkSize::Approx1 => u64::pow(2, 20),
-            ChunkSize::Approx2 => u64::pow(2, 21),
-            ChunkSize::Approx4 => u64::pow(2, 22),
-            ChunkSize::Approx8 => u64::pow(2, 23),
-            ChunkSize::Approx16 => u64::pow(2, 24),
-            ChunkSize::Approx32 => u64::pow(2, 25),
-            ChunkSize::Approx64 => u64::pow(2, 26),
-            ChunkSize::Approx128 => u64::pow(2, 27),
-            ChunkSize::Approx256 => u64::pow(2, 28),
-            ChunkSize::Approx512 => u64::pow(2, 29),
-            ChunkSize::Approx1024 => u64::pow(2, 30),
-            ChunkSize::Approx2048 => u64::pow(2, 31),
-            ChunkSize::Approx4096 => u64::pow(2, 32),
-            ChunkSize::Approx8192 => u64::pow(2, 33),
-        }
-    }
-    
-    pub fn from_mb(mb: u64) -> Self {
-        match mb {
-            0..=1 => ChunkSize::Approx1,
-            2..=3 => ChunkSize::Approx2,
-            4..=7 => ChunkSize::Approx4,
-            8..=15 => ChunkSize::Approx8,
-            16..=31 => ChunkSize::Approx16,
-            32..=63 => ChunkSize::Approx32,
-            64..=127 => ChunkSize::Approx64,
-            128..=255 => ChunkSize::Approx128,
-            256..=511 => ChunkSize::Approx256,
-            512..=1023 => ChunkSize::Approx512,
-            1024..=2047 => ChunkSize::Approx1024,
-            2048..=4095 => ChunkSize::Approx2048,
-            4096..=8191 => ChunkSize::Approx4096,
-            _ => ChunkSize::Approx8192,
+            ChunkSize::Fixed(bytes) => *bytes,
+            ChunkSize::Auto(tuner) => tuner.chunk_size(),
         }
     }
 }
@@ -229,44 +446,108 @@ impl ChunkSize {
 impl FromStr for ChunkSize {
     type Err = String;
 
+    // A number without unit is in MB, which is what the option used to accept
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "1" => Ok(ChunkSize::Approx1),
-            "2" => Ok(ChunkSize::Approx2),
-            "4" => Ok(ChunkSize::Approx4),
-            "8" => Ok(ChunkSize::Approx8),
-            "16" => Ok(ChunkSize::Approx16),
-            "32" => Ok(ChunkSize::Approx32),
-            "64" => Ok(ChunkSize::Approx64),
-            "128" => Ok(ChunkSize::Approx128),
-            "256" => Ok(ChunkSize::Approx256),
-            "512" => Ok(ChunkSize::Approx512),
-            "1024" => Ok(ChunkSize::Approx1024),
-            "2048" => Ok(ChunkSize::Approx2048),
-            "4096" => Ok(ChunkSize::Approx4096),
-            "8192" => Ok(ChunkSize::Approx8192),
-            _ => Err("Not a valid chunk size, must be a power of 2 between 1 and 8192".to_string()),
+        if s == "auto" {
+            return Ok(ChunkSize::Auto(ChunkTuner::new()));
         }
+
+        let bytes = if !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()) {
+            let mb: u64 = s.parse().map_err(|_| invalid_chunk_size(s))?;
+            mb.checked_mul(MIB).ok_or_else(|| invalid_chunk_size(s))?
+        } else {
+            byte_size::parse(s)?
+        };
+
+        if bytes == 0 || bytes > MAX_CHUNK_SIZE_MB * MIB || bytes % CHUNK_ALIGNMENT != 0 {
+            return Err(invalid_chunk_size(s));
+        }
+
+        Ok(ChunkSize::Fixed(bytes))
     }
 }
 
 impl Display for ChunkSize {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ChunkSize::Approx1 => write!(f, "1"),
-            ChunkSize::Approx2 => write!(f, "2"),
-            ChunkSize::Approx4 => write!(f, "4"),
-            ChunkSize::Approx8 => write!(f, "8"),
-            ChunkSize::Approx16 => write!(f, "16"),
-            ChunkSize::Approx32 => write!(f, "32"),
-            ChunkSize::Approx64 => write!(f, "64"),
-            ChunkSize::Approx128 => write!(f, "128"),
-            ChunkSize::Approx256 => write!(f, "256"),
-            ChunkSize::Approx512 => write!(f, "512"),
-            ChunkSize::Approx1024 => write!(f, "1024"),
-            ChunkSize::Approx2048 => write!(f, "2048"),
-            ChunkSize::Approx4096 => write!(f, "4096"),
-            ChunkSize::Approx8192 => write!(f, "8192"),
+            ChunkSize::Fixed(bytes) => write!(f, "{}", ByteSize(*bytes)),
+            ChunkSize::Auto(_) => write!(f, "auto"),
+        }
+    }
+}
+
+// Adapts the chunk size to the connection: starts at 8M, doubles after a few chunks in a row
+// succeed and halves after a failed chunk. A file is uploaded with the size it starts with, so
+// the tuning only pays off across files. Clones share the state, so a directory upload keeps
+// tuning from one file to the next.
+#[derive(Debug, Clone)]
+pub struct ChunkTuner {
+    state: Arc<Mutex<TunerState>>,
+}
+
+#[derive(Debug)]
+struct TunerState {
+    chunk_size: u64,
+    successes: u32,
+}
+
+impl Default for ChunkTuner {
+    fn default() -> Self {
+        ChunkTuner::new()
+    }
+}
+
+impl ChunkTuner {
+    pub fn new() -> ChunkTuner {
+        ChunkTuner {
+            state: Arc::new(Mutex::new(TunerState {
+                chunk_size: AUTO_CHUNK_START,
+                successes: 0,
+            })),
+        }
+    }
+
+    pub fn chunk_size(&self) -> u64 {
+        self.lock().chunk_size
+    }
+
+    // Returns the new chunk size if it changed
+    pub fn record_success(&self) -> Option<u64> {
+        let mut state = self.lock();
+        state.successes += 1;
+
+        if state.successes < AUTO_CHUNK_GROW_AFTER || state.chunk_size >= AUTO_CHUNK_MAX {
+            return None;
+        }
+
+        state.successes = 0;
+        state.chunk_size = (state.chunk_size * 2).min(AUTO_CHUNK_MAX);
+        Some(state.chunk_size)
+    }
+
+    // Returns the new chunk size if it changed
+    pub fn record_failure(&self) -> Option<u64> {
+        let mut state = self.lock();
+        state.successes = 0;
+
+        if state.chunk_size <= CHUNK_ALIGNMENT {
+            return None;
         }
+
+        // Sizes stay powers of two between 256K and the max, so they're always a multiple of 256K
+        state.chunk_size = (state.chunk_size / 2).max(CHUNK_ALIGNMENT);
+        Some(state.chunk_size)
     }
+
+    fn lock(&self) -> MutexGuard<'_, TunerState> {
+        // The state is always valid, so a panic while holding the lock doesn't matter
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+fn invalid_chunk_size(s: &str) -> String {
+    format!(
+        "'{}' is not a valid chunk size, it must be a multiple of 256K up to {}M, a number without unit is in MB",
+        s, MAX_CHUNK_SIZE_MB
+    )
 }
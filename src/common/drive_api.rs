@@ -0,0 +1,54 @@
+use crate::common::delegate::UploadDelegateConfig;
+use crate::files;
+use crate::files::list::ListFilesConfig;
+use crate::hub::Hub;
+use google_drive3::api::File;
+use std::future::Future;
+
+// The drive operations shared code is written against, implemented by Hub and by the in-memory
+// FakeDrive of the test-support feature
+pub trait DriveApi {
+    // Every file matching the query, up to max_files
+    fn list_files(
+        &self,
+        config: &ListFilesConfig,
+    ) -> impl Future<Output = Result<Vec<File>, google_drive3::Error>>;
+
+    fn get_file(&self, file_id: &str) -> impl Future<Output = Result<File, google_drive3::Error>>;
+
+    fn create_folder(
+        &self,
+        name: &str,
+        parent_id: &str,
+    ) -> impl Future<Output = Result<File, google_drive3::Error>>;
+}
+
+impl DriveApi for Hub {
+    async fn list_files(
+        &self,
+        config: &ListFilesConfig,
+    ) -> Result<Vec<File>, google_drive3::Error> {
+        files::list::fetch_files(self, config).await
+    }
+
+    async fn get_file(&self, file_id: &str) -> Result<File, google_drive3::Error> {
+        files::info::get_file(self, file_id).await
+    }
+
+    async fn create_folder(
+        &self,
+        name: &str,
+        parent_id: &str,
+    ) -> Result<File, google_drive3::Error> {
+        let config = files::mkdir::Config {
+            id: None,
+            name: name.to_string(),
+            parents: Some(vec![parent_id.to_string()]),
+            print_only_id: false,
+            skip_parent_check: true,
+            create_parents: false,
+        };
+
+        files::mkdir::create_directory(self, &config, UploadDelegateConfig::default()).await
+    }
+}
@@ -0,0 +1,175 @@
+use crate::app_config::AccountScope;
+use crate::common::hub_helper;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuotaError {
+    StorageQuotaExceeded,
+    UserRateLimitExceeded,
+    RateLimitExceeded,
+}
+
+impl QuotaError {
+    pub fn from_reason(reason: &str) -> Option<QuotaError> {
+        match reason {
+            "storageQuotaExceeded" => Some(QuotaError::StorageQuotaExceeded),
+            "userRateLimitExceeded" => Some(QuotaError::UserRateLimitExceeded),
+            "rateLimitExceeded" => Some(QuotaError::RateLimitExceeded),
+            _ => None,
+        }
+    }
+
+    pub fn is_rate_limit(&self) -> bool {
+        matches!(
+            self,
+            QuotaError::UserRateLimitExceeded | QuotaError::RateLimitExceeded
+        )
+    }
+}
+
+impl error::Error for QuotaError {}
+
+impl Display for QuotaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuotaError::StorageQuotaExceeded => write!(
+                f,
+                "Storage quota exceeded, free up space on the drive or use a different account"
+            ),
+            QuotaError::UserRateLimitExceeded => write!(
+                f,
+                "User rate limit exceeded, wait a moment and try again with fewer parallel requests"
+            ),
+            QuotaError::RateLimitExceeded => {
+                write!(f, "Rate limit exceeded, wait a moment and try again")
+            }
+        }
+    }
+}
+
+// Reasons from a Drive error body: {"error": {"errors": [{"reason": "..."}]}}
+pub fn error_reasons(value: &serde_json::Value) -> Vec<String> {
+    value
+        .pointer("/error/errors")
+        .and_then(|errors| errors.as_array())
+        .map(|errors| {
+            errors
+                .iter()
+                .filter_map(|e| e.get("reason").and_then(|r| r.as_str()))
+                .map(|reason| reason.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Status code from a Drive error body: {"error": {"code": 404}}
+pub fn error_code(value: &serde_json::Value) -> Option<u64> {
+    value.pointer("/error/code").and_then(|code| code.as_u64())
+}
+
+// Drive refuses to export documents larger than 10 MB with files.export
+pub fn is_export_size_limit_exceeded(err: &google_drive3::Error) -> bool {
+    match err {
+        google_drive3::Error::BadRequest(value) => error_reasons(value)
+            .iter()
+            .any(|reason| reason == "exportSizeLimitExceeded"),
+        _ => false,
+    }
+}
+
+pub fn is_not_found(err: &google_drive3::Error) -> bool {
+    match err {
+        google_drive3::Error::BadRequest(value) => {
+            error_code(value) == Some(404)
+                || error_reasons(value).iter().any(|reason| reason == "notFound")
+        }
+        google_drive3::Error::Failure(response) => {
+            response.status() == google_drive3::hyper::StatusCode::NOT_FOUND
+        }
+        _ => false,
+    }
+}
+
+// Reason in the error details and message Drive uses when the access token lacks a scope
+const SCOPE_INSUFFICIENT_REASON: &str = "ACCESS_TOKEN_SCOPE_INSUFFICIENT";
+const SCOPE_INSUFFICIENT_MESSAGE: &str = "insufficient authentication scopes";
+
+// Drive answers 403 when the access token lacks the scope a request needs
+pub fn is_insufficient_scope_json(value: &serde_json::Value) -> bool {
+    if error_code(value) != Some(403) {
+        return false;
+    }
+
+    let has_reason = error_reasons(value)
+        .iter()
+        .any(|reason| reason == "insufficientPermissions" || reason == "insufficientScopes");
+
+    let has_detail = value
+        .pointer("/error/details")
+        .and_then(|details| details.as_array())
+        .map(|details| {
+            details.iter().any(|detail| {
+                detail.get("reason").and_then(|r| r.as_str()) == Some(SCOPE_INSUFFICIENT_REASON)
+            })
+        })
+        .unwrap_or(false);
+
+    has_reason || has_detail
+}
+
+pub fn is_insufficient_scope(err: &google_drive3::Error) -> bool {
+    match err {
+        google_drive3::Error::BadRequest(value) => is_insufficient_scope_json(value),
+        _ => false,
+    }
+}
+
+// Error messages include the Drive error body, which lets scope errors be recognized
+// whichever command and error type they came from
+pub fn is_insufficient_scope_message(message: &str) -> bool {
+    message.contains(SCOPE_INSUFFICIENT_REASON)
+        || message.to_lowercase().contains(SCOPE_INSUFFICIENT_MESSAGE)
+}
+
+// OAuth error code of the token endpoint when the refresh token was revoked or expired, i.e.
+// after a password change or when an admin removed access. It ends up in the message of
+// whichever error the token request failed with.
+const INVALID_GRANT: &str = "invalid_grant";
+
+pub fn is_invalid_grant_message(message: &str) -> bool {
+    message.contains(INVALID_GRANT)
+}
+
+// With the drive.file scope, files gdrive didn't create look missing or forbidden
+pub fn file_scope_hint(err: &google_drive3::Error, scope: AccountScope) -> Option<&'static str> {
+    if scope == AccountScope::File && (is_insufficient_scope(err) || is_not_found(err)) {
+        Some(FILE_SCOPE_HINT)
+    } else {
+        None
+    }
+}
+
+pub const FILE_SCOPE_HINT: &str = "The account uses the drive.file scope, which only gives gdrive access to files it created. Add the account again with `gdrive account add --scope drive` to access other files";
+
+// Error message for commands that need access to files gdrive didn't create
+pub fn describe(err: &google_drive3::Error) -> String {
+    match file_scope_hint(err, hub_helper::command_account_scope()) {
+        Some(hint) => hint.to_string(),
+        None => err.to_string(),
+    }
+}
+
+pub fn quota_error_from_json(value: &serde_json::Value) -> Option<QuotaError> {
+    error_reasons(value)
+        .iter()
+        .find_map(|reason| QuotaError::from_reason(reason))
+}
+
+pub fn quota_error(err: &google_drive3::Error) -> Option<QuotaError> {
+    match err {
+        google_drive3::Error::BadRequest(value) => quota_error_from_json(value),
+        _ => None,
+    }
+}
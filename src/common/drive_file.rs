@@ -1,6 +1,7 @@
 use mime::Mime;
 use std::fmt;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 pub const MIME_TYPE_DRIVE_FOLDER: &str = "application/vnd.google-apps.folder";
 pub const MIME_TYPE_DRIVE_DOCUMENT: &str = "application/vnd.google-apps.document";
@@ -96,6 +97,19 @@ impl DocType {
             })
     }
 
+    pub fn from_source_mime(mime: &Mime) -> Option<DocType> {
+        Self::IMPORT_EXTENSION_MAP
+            .iter()
+            .find_map(|(ext, doc_type)| {
+                let ext_mime = ext.get_export_mime()?;
+                if ext_mime.essence_str() == mime.essence_str() {
+                    Some(doc_type.clone())
+                } else {
+                    None
+                }
+            })
+    }
+
     pub fn from_mime_type(mime: &str) -> Option<DocType> {
         match mime {
             MIME_TYPE_DRIVE_DOCUMENT => Some(DocType::Document),
@@ -114,9 +128,9 @@ impl DocType {
 
     pub fn default_export_type(&self) -> FileExtension {
         match self {
-            DocType::Document => FileExtension::Pdf,
-            DocType::Spreadsheet => FileExtension::Csv,
-            DocType::Presentation => FileExtension::Pdf,
+            DocType::Document => FileExtension::Docx,
+            DocType::Spreadsheet => FileExtension::Xlsx,
+            DocType::Presentation => FileExtension::Pptx,
         }
     }
 
@@ -162,6 +176,22 @@ impl DocType {
     }
 }
 
+impl FromStr for DocType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "doc" | "document" => Ok(DocType::Document),
+            "sheet" | "spreadsheet" => Ok(DocType::Spreadsheet),
+            "slides" | "presentation" => Ok(DocType::Presentation),
+            _ => Err(format!(
+                "'{}' is not a valid document type, valid types are: doc, sheet, slides",
+                s
+            )),
+        }
+    }
+}
+
 impl fmt::Display for DocType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -224,9 +254,35 @@ impl fmt::Display for FileExtension {
 }
 
 impl FileExtension {
+    const ALL: &'static [FileExtension] = &[
+        FileExtension::Doc,
+        FileExtension::Docx,
+        FileExtension::Odt,
+        FileExtension::Jpg,
+        FileExtension::Jpeg,
+        FileExtension::Gif,
+        FileExtension::Png,
+        FileExtension::Rtf,
+        FileExtension::Pdf,
+        FileExtension::Html,
+        FileExtension::Xls,
+        FileExtension::Xlsx,
+        FileExtension::Csv,
+        FileExtension::Tsv,
+        FileExtension::Ods,
+        FileExtension::Ppt,
+        FileExtension::Pptx,
+        FileExtension::Odp,
+        FileExtension::Epub,
+        FileExtension::Txt,
+    ];
+
     pub fn from_path(path: &PathBuf) -> Option<FileExtension> {
         let extension = path.extension()?.to_str()?;
+        Self::from_extension(extension)
+    }
 
+    pub fn from_extension(extension: &str) -> Option<FileExtension> {
         match extension {
             EXTENSION_DOC => Some(FileExtension::Doc),
             EXTENSION_DOCX => Some(FileExtension::Docx),
@@ -252,6 +308,18 @@ impl FileExtension {
         }
     }
 
+    // Export mime types that are shared by several extensions map to the first one
+    pub fn from_export_mime(mime: &str) -> Option<FileExtension> {
+        Self::ALL.iter().find_map(|ext| {
+            let ext_mime = ext.get_export_mime()?;
+            if ext_mime.essence_str() == mime {
+                Some(ext.clone())
+            } else {
+                None
+            }
+        })
+    }
+
     pub fn get_export_mime(&self) -> Option<Mime> {
         match self {
             FileExtension::Doc => MIME_TYPE_DOC.parse().ok(),
@@ -289,3 +357,9 @@ pub fn is_binary(file: &google_drive3::api::File) -> bool {
 pub fn is_shortcut(file: &google_drive3::api::File) -> bool {
     file.mime_type == Some(String::from(MIME_TYPE_DRIVE_SHORTCUT))
 }
+
+pub fn shortcut_target_id(file: &google_drive3::api::File) -> Option<String> {
+    file.shortcut_details
+        .as_ref()
+        .and_then(|details| details.target_id.clone())
+}
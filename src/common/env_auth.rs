@@ -0,0 +1,107 @@
+use crate::app_config;
+use std::env;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::io;
+use std::io::BufRead;
+use std::sync::Mutex;
+
+pub const REFRESH_TOKEN_ENV: &str = "GDRIVE_REFRESH_TOKEN";
+pub const CLIENT_ID_ENV: &str = "GDRIVE_CLIENT_ID";
+pub const CLIENT_SECRET_ENV: &str = "GDRIVE_CLIENT_SECRET";
+
+// Refresh token read from stdin, takes precedence over GDRIVE_REFRESH_TOKEN
+static STDIN_REFRESH_TOKEN: Mutex<Option<String>> = Mutex::new(None);
+
+// Client credentials and refresh token of an account that only exists for this run.
+// Nothing of it is written to the config directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub client_id: String,
+    pub client_secret: String,
+    pub refresh_token: String,
+}
+
+impl Credentials {
+    pub fn secret(&self) -> app_config::Secret {
+        app_config::Secret {
+            client_id: self.client_id.clone(),
+            client_secret: self.client_secret.clone(),
+        }
+    }
+}
+
+pub fn read_refresh_token_from_stdin() -> Result<(), Error> {
+    let token = read_refresh_token(io::stdin().lock())?;
+    if let Ok(mut current) = STDIN_REFRESH_TOKEN.lock() {
+        *current = Some(token);
+    }
+    Ok(())
+}
+
+// The refresh token is the first line of the input
+pub fn read_refresh_token(mut reader: impl BufRead) -> Result<String, Error> {
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(Error::ReadStdin)?;
+
+    let token = line.trim();
+    if token.is_empty() {
+        return Err(Error::EmptyRefreshToken);
+    }
+
+    Ok(token.to_string())
+}
+
+// Credentials from stdin or the environment, None when no refresh token was given
+pub fn credentials() -> Result<Option<Credentials>, Error> {
+    let stdin_token = STDIN_REFRESH_TOKEN
+        .lock()
+        .map(|token| token.clone())
+        .unwrap_or_default();
+
+    credentials_from(stdin_token, |name| env::var(name).ok())
+}
+
+pub fn credentials_from(
+    stdin_token: Option<String>,
+    env_var: impl Fn(&str) -> Option<String>,
+) -> Result<Option<Credentials>, Error> {
+    let non_empty = |name: &str| env_var(name).filter(|value| !value.trim().is_empty());
+
+    let refresh_token = match stdin_token.or_else(|| non_empty(REFRESH_TOKEN_ENV)) {
+        Some(token) => token.trim().to_string(),
+        None => return Ok(None),
+    };
+
+    let client_id = non_empty(CLIENT_ID_ENV).ok_or(Error::MissingEnvVar(CLIENT_ID_ENV))?;
+    let client_secret =
+        non_empty(CLIENT_SECRET_ENV).ok_or(Error::MissingEnvVar(CLIENT_SECRET_ENV))?;
+
+    Ok(Some(Credentials {
+        client_id: client_id.trim().to_string(),
+        client_secret: client_secret.trim().to_string(),
+        refresh_token,
+    }))
+}
+
+#[derive(Debug)]
+pub enum Error {
+    ReadStdin(io::Error),
+    EmptyRefreshToken,
+    MissingEnvVar(&'static str),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::ReadStdin(err) => write!(f, "Failed to read refresh token from stdin: {}", err),
+            Error::EmptyRefreshToken => write!(f, "No refresh token was given on stdin"),
+            Error::MissingEnvVar(name) => {
+                write!(f, "{} must be set when a refresh token is given", name)
+            }
+        }
+    }
+}
@@ -0,0 +1,347 @@
+use crate::common::drive_api::DriveApi;
+use crate::common::drive_file;
+use crate::files::list::ListFilesConfig;
+use google_drive3::api::File;
+use std::sync::Mutex;
+
+pub const ROOT_ID: &str = "root";
+
+// An in-memory drive for tests, without any network access. Files are added to a tree under
+// ROOT_ID, listings understand the queries gdrive builds: terms joined with 'and', comparing
+// name and mimeType, checking trashed and 'id' in parents or 'email' in owners.
+#[derive(Debug, Default)]
+pub struct FakeDrive {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    files: Vec<FakeFile>,
+    next_id: u64,
+    requests: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct FakeFile {
+    file: File,
+    content: Vec<u8>,
+}
+
+impl FakeDrive {
+    pub fn new() -> FakeDrive {
+        FakeDrive::default()
+    }
+
+    pub fn add_folder(&self, parent_id: &str, name: &str) -> String {
+        self.insert(
+            File {
+                name: Some(name.to_string()),
+                mime_type: Some(drive_file::MIME_TYPE_DRIVE_FOLDER.to_string()),
+                parents: Some(vec![parent_id.to_string()]),
+                ..File::default()
+            },
+            None,
+        )
+    }
+
+    pub fn add_file(&self, parent_id: &str, name: &str, content: &[u8]) -> String {
+        self.insert(
+            File {
+                name: Some(name.to_string()),
+                mime_type: Some(String::from("application/octet-stream")),
+                parents: Some(vec![parent_id.to_string()]),
+                ..File::default()
+            },
+            Some(content.to_vec()),
+        )
+    }
+
+    // Adds the file as it is, an id is assigned when it doesn't have one
+    pub fn add(&self, file: File) -> String {
+        self.insert(file, None)
+    }
+
+    // Creates the folders of a '/' separated path below root and returns the id of the last one
+    pub fn add_path(&self, path: &str) -> String {
+        path.split('/').filter(|part| !part.is_empty()).fold(
+            ROOT_ID.to_string(),
+            |parent_id, name| match self.find_child(&parent_id, name) {
+                Some(id) => id,
+                None => self.add_folder(&parent_id, name),
+            },
+        )
+    }
+
+    pub fn trash(&self, file_id: &str) {
+        self.update(file_id, |file| file.file.trashed = Some(true));
+    }
+
+    pub fn file(&self, file_id: &str) -> Option<File> {
+        let state = self.state.lock().unwrap();
+        state.find(file_id).map(|file| file.file.clone())
+    }
+
+    pub fn content(&self, file_id: &str) -> Option<Vec<u8>> {
+        let state = self.state.lock().unwrap();
+        state.find(file_id).map(|file| file.content.clone())
+    }
+
+    // Files that aren't trashed directly in the folder
+    pub fn children(&self, folder_id: &str) -> Vec<File> {
+        let state = self.state.lock().unwrap();
+        state
+            .files
+            .iter()
+            .filter(|file| file.file.trashed != Some(true) && has_parent(&file.file, folder_id))
+            .map(|file| file.file.clone())
+            .collect()
+    }
+
+    // Every request made so far, like "list 'root' in parents and trashed = false"
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    // Files with content get its size and md5 checksum, like binary files on drive
+    fn insert(&self, mut file: File, content: Option<Vec<u8>>) -> String {
+        let mut state = self.state.lock().unwrap();
+
+        let id = match &file.id {
+            Some(id) => id.clone(),
+            None => {
+                state.next_id += 1;
+                format!("fake{}", state.next_id)
+            }
+        };
+
+        file.id = Some(id.clone());
+        if let Some(content) = &content {
+            file.size = Some(content.len() as i64);
+            file.md5_checksum = Some(format!("{:x}", md5::compute(content)));
+        }
+
+        state.files.push(FakeFile {
+            file,
+            content: content.unwrap_or_default(),
+        });
+        id
+    }
+
+    fn update(&self, file_id: &str, f: impl FnOnce(&mut FakeFile)) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(file) = state
+            .files
+            .iter_mut()
+            .find(|file| file.file.id.as_deref() == Some(file_id))
+        {
+            f(file);
+        }
+    }
+
+    fn find_child(&self, parent_id: &str, name: &str) -> Option<String> {
+        self.children(parent_id)
+            .into_iter()
+            .find(|file| file.name.as_deref() == Some(name))
+            .and_then(|file| file.id)
+    }
+
+    fn record(&self, request: String) {
+        self.state.lock().unwrap().requests.push(request);
+    }
+}
+
+impl State {
+    fn find(&self, file_id: &str) -> Option<&FakeFile> {
+        self.files
+            .iter()
+            .find(|file| file.file.id.as_deref() == Some(file_id))
+    }
+}
+
+impl DriveApi for FakeDrive {
+    async fn list_files(
+        &self,
+        config: &ListFilesConfig,
+    ) -> Result<Vec<File>, google_drive3::Error> {
+        let query = config.query.to_string();
+        self.record(format!("list {}", query));
+
+        let terms =
+            parse_query(&query).ok_or_else(|| bad_request(400, "invalid", "Invalid Value"))?;
+
+        let state = self.state.lock().unwrap();
+        let mut files: Vec<File> = state
+            .files
+            .iter()
+            .map(|file| &file.file)
+            .filter(|file| terms.iter().all(|term| term.matches(file)))
+            .cloned()
+            .collect();
+
+        // Folders first like the default sort order, otherwise in the order they were added
+        files.sort_by_key(|file| !drive_file::is_directory(file));
+        files.truncate(config.max_files);
+
+        Ok(files)
+    }
+
+    async fn get_file(&self, file_id: &str) -> Result<File, google_drive3::Error> {
+        self.record(format!("get {}", file_id));
+
+        if file_id == ROOT_ID {
+            return Ok(File {
+                id: Some(ROOT_ID.to_string()),
+                name: Some(String::from("My Drive")),
+                mime_type: Some(drive_file::MIME_TYPE_DRIVE_FOLDER.to_string()),
+                ..File::default()
+            });
+        }
+
+        self.file(file_id).ok_or_else(not_found)
+    }
+
+    async fn create_folder(
+        &self,
+        name: &str,
+        parent_id: &str,
+    ) -> Result<File, google_drive3::Error> {
+        self.record(format!("create folder {} in {}", name, parent_id));
+
+        let id = self.add_folder(parent_id, name);
+        self.file(&id).ok_or_else(not_found)
+    }
+}
+
+fn has_parent(file: &File, parent_id: &str) -> bool {
+    file.parents
+        .as_ref()
+        .map(|parents| parents.iter().any(|parent| parent == parent_id))
+        .unwrap_or(false)
+}
+
+#[derive(Debug, PartialEq)]
+enum Term {
+    InParents(String),
+    InOwners(String),
+    Trashed(bool),
+    Name(String),
+    MimeType { value: String, equal: bool },
+}
+
+impl Term {
+    fn matches(&self, file: &File) -> bool {
+        match self {
+            Term::InParents(id) => has_parent(file, id),
+            Term::InOwners(email) => file
+                .owners
+                .as_ref()
+                .map(|owners| {
+                    owners
+                        .iter()
+                        .any(|owner| owner.email_address.as_deref() == Some(email.as_str()))
+                })
+                .unwrap_or(false),
+            Term::Trashed(trashed) => file.trashed.unwrap_or(false) == *trashed,
+            Term::Name(name) => file.name.as_deref() == Some(name.as_str()),
+            Term::MimeType { value, equal } => {
+                (file.mime_type.as_deref() == Some(value.as_str())) == *equal
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+}
+
+// None for anything the fake doesn't understand, like 'or' and 'contains'
+fn parse_query(query: &str) -> Option<Vec<Term>> {
+    let tokens = tokenize(query)?;
+    let mut terms = Vec::new();
+
+    for term in tokens.split(|token| *token == Token::Word(String::from("and"))) {
+        let words: Vec<&str> = term
+            .iter()
+            .map(|token| match token {
+                Token::Word(word) | Token::Quoted(word) => word.as_str(),
+            })
+            .collect();
+
+        let term = match (term.first(), words.as_slice()) {
+            (_, []) => continue,
+            (Some(Token::Quoted(_)), [value, "in", "parents"]) => {
+                Term::InParents(value.to_string())
+            }
+            (Some(Token::Quoted(_)), [value, "in", "owners"]) => Term::InOwners(value.to_string()),
+            (_, ["trashed", "=", "true"]) => Term::Trashed(true),
+            (_, ["trashed", "=", "false"]) => Term::Trashed(false),
+            (_, ["name", "=", value]) => Term::Name(value.to_string()),
+            (_, ["mimeType", "=", value]) => Term::MimeType {
+                value: value.to_string(),
+                equal: true,
+            },
+            (_, ["mimeType", "!=", value]) => Term::MimeType {
+                value: value.to_string(),
+                equal: false,
+            },
+            _ => return None,
+        };
+
+        terms.push(term);
+    }
+
+    Some(terms)
+}
+
+// Splits on whitespace, keeping quoted values together. Parentheses are dropped, which is fine
+// as long as the query only uses 'and'
+fn tokenize(query: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next()? {
+                        '\\' => value.push(chars.next()?),
+                        '\'' => break,
+                        c => value.push(c),
+                    }
+                }
+                tokens.push(Token::Quoted(value));
+            }
+            c if c.is_whitespace() || c == '(' || c == ')' => {}
+            c => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' || c == '\'' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    Some(tokens)
+}
+
+fn not_found() -> google_drive3::Error {
+    bad_request(404, "notFound", "File not found")
+}
+
+fn bad_request(code: u16, reason: &str, message: &str) -> google_drive3::Error {
+    google_drive3::Error::BadRequest(serde_json::json!({
+        "error": {
+            "errors": [{ "reason": reason, "message": message }],
+            "code": code,
+            "message": message,
+        }
+    }))
+}
@@ -1,14 +1,25 @@
+use crate::common::mime_overrides;
+use google_drive3::chrono::DateTime;
+use google_drive3::chrono::Utc;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::fs;
+use std::io;
+use std::io::Seek;
 use std::path::PathBuf;
 
+// Bounds of the read buffer of uploads, BufReader defaults to 8 KiB
+const MIN_READ_BUFFER: u64 = 64 * 1024;
+const MAX_READ_BUFFER: u64 = 8 * 1024 * 1024;
+
 pub struct FileInfo {
     pub name: String,
     pub mime_type: mime::Mime,
     pub parents: Option<Vec<String>>,
     pub size: u64,
+    // Sent as modifiedTime, Drive uses the time of the upload when this isn't set
+    pub modified_time: Option<DateTime<Utc>>,
 }
 
 pub struct Config {
@@ -27,21 +38,48 @@ impl FileInfo {
 
         let file_size = file.metadata().map(|m| m.len()).unwrap_or(0);
 
-        let mime_type = config.mime_type.clone().unwrap_or_else(|| {
-            mime_guess::from_path(&config.file_path)
-                .first()
-                .unwrap_or(mime::APPLICATION_OCTET_STREAM)
-        });
+        let mime_type = config
+            .mime_type
+            .clone()
+            .or_else(|| mime_overrides::lookup(&config.file_path))
+            .unwrap_or_else(|| {
+                mime_guess::from_path(&config.file_path)
+                    .first()
+                    .unwrap_or(mime::APPLICATION_OCTET_STREAM)
+            });
 
         Ok(FileInfo {
             name: file_name,
             mime_type,
             parents: config.parents.clone(),
             size: file_size,
+            modified_time: None,
         })
     }
 }
 
+// A quarter of a chunk, so a chunk is filled with a few large reads
+pub fn read_buffer_size(chunk_size: u64) -> usize {
+    (chunk_size / 4).clamp(MIN_READ_BUFFER, MAX_READ_BUFFER) as usize
+}
+
+// Reads the upload from the handle FileInfo::from_file was given, from the start
+pub fn upload_reader(mut file: fs::File, chunk_size: u64) -> io::Result<io::BufReader<fs::File>> {
+    file.rewind()?;
+    Ok(io::BufReader::with_capacity(
+        read_buffer_size(chunk_size),
+        file,
+    ))
+}
+
+// The size of the open file when it no longer is the size the upload was started with
+pub fn changed_size(file: &fs::File, size: u64) -> Option<u64> {
+    file.metadata()
+        .ok()
+        .map(|metadata| metadata.len())
+        .filter(|current_size| *current_size != size)
+}
+
 #[derive(Debug)]
 pub enum Error {
     InvalidFilePath(PathBuf),
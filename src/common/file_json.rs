@@ -0,0 +1,45 @@
+use serde::Serialize;
+use std::path::Path;
+
+// Json output of a created or updated file, field names follow the Drive api
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FileJson {
+    pub id: String,
+    pub name: String,
+    pub mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub md5_checksum: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub web_view_link: Option<String>,
+    pub parents: Vec<String>,
+    // Local path the file was uploaded from, only set for recursive uploads
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+impl FileJson {
+    pub fn with_path(self, path: &Path) -> FileJson {
+        FileJson {
+            path: Some(path.to_string_lossy().to_string()),
+            ..self
+        }
+    }
+}
+
+impl From<&google_drive3::api::File> for FileJson {
+    fn from(file: &google_drive3::api::File) -> FileJson {
+        FileJson {
+            id: file.id.clone().unwrap_or_default(),
+            name: file.name.clone().unwrap_or_default(),
+            mime_type: file.mime_type.clone().unwrap_or_default(),
+            size: file.size,
+            md5_checksum: file.md5_checksum.clone(),
+            web_view_link: file.web_view_link.clone(),
+            parents: file.parents.clone().unwrap_or_default(),
+            path: None,
+        }
+    }
+}
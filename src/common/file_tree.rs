@@ -7,6 +7,7 @@ use std::fmt::Display;
 use std::fmt::Formatter;
 use std::fs;
 use std::io;
+use std::path::Path;
 use std::path::PathBuf;
 
 #[derive(Debug, Clone)]
@@ -15,12 +16,18 @@ pub struct FileTree {
 }
 
 impl FileTree {
-    pub async fn from_path<'a>(path: &PathBuf, ids: &mut IdGen<'a>) -> Result<FileTree, Error> {
+    // With skip_unreadable, entries below the root that vanish or can't be read are kept in
+    // unreadable instead of failing the whole tree
+    pub async fn from_path<'a>(
+        path: &PathBuf,
+        ids: &mut IdGen<'a>,
+        skip_unreadable: bool,
+    ) -> Result<FileTree, Error> {
         let canonical_path = path
             .canonicalize()
             .map_err(|err| Error::CanonicalizePath(path.clone(), err))?;
 
-        let root = Folder::from_path(&canonical_path, None, ids).await?;
+        let root = Folder::from_path(&canonical_path, None, ids, skip_unreadable).await?;
         Ok(FileTree { root })
     }
 
@@ -49,9 +56,11 @@ impl FileTree {
         let mut file_count = 0;
         let mut folder_count = 0;
         let mut total_file_size = 0;
+        let mut skipped_count = 0;
 
         for folder in self.folders() {
             folder_count += 1;
+            skipped_count += folder.skipped.len() as u128;
 
             for file in folder.files() {
                 file_count += 1;
@@ -63,8 +72,35 @@ impl FileTree {
             file_count,
             folder_count,
             total_file_size,
+            skipped_count,
         }
     }
+
+    // Special files found in the whole tree, these are not uploaded
+    pub fn skipped(&self) -> Vec<SpecialFile> {
+        let mut skipped: Vec<SpecialFile> = self
+            .folders()
+            .into_iter()
+            .flat_map(|folder| folder.skipped)
+            .collect();
+
+        skipped.sort_by(|a, b| a.path.cmp(&b.path));
+
+        skipped
+    }
+
+    // Entries that couldn't be read in the whole tree, only found with skip_unreadable
+    pub fn unreadable(&self) -> Vec<UnreadableEntry> {
+        let mut unreadable: Vec<UnreadableEntry> = self
+            .folders()
+            .into_iter()
+            .flat_map(|folder| folder.unreadable)
+            .collect();
+
+        unreadable.sort_by(|a, b| a.path.cmp(&b.path));
+
+        unreadable
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -72,6 +108,93 @@ pub struct TreeInfo {
     pub file_count: u128,
     pub folder_count: u128,
     pub total_file_size: u128,
+    pub skipped_count: u128,
+}
+
+// What a directory entry is, symlinks are followed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    Directory,
+    Regular,
+    Fifo,
+    Socket,
+    BlockDevice,
+    CharDevice,
+    Other,
+}
+
+impl FileKind {
+    pub fn from_path(path: &Path) -> Result<FileKind, Error> {
+        let file_type = fs::metadata(path)
+            .map_err(|err| Error::Metadata(path.to_path_buf(), err))?
+            .file_type();
+
+        Ok(FileKind::from_file_type(file_type))
+    }
+
+    #[cfg(unix)]
+    fn from_file_type(file_type: fs::FileType) -> FileKind {
+        use std::os::unix::fs::FileTypeExt;
+
+        if file_type.is_dir() {
+            FileKind::Directory
+        } else if file_type.is_file() {
+            FileKind::Regular
+        } else if file_type.is_fifo() {
+            FileKind::Fifo
+        } else if file_type.is_socket() {
+            FileKind::Socket
+        } else if file_type.is_block_device() {
+            FileKind::BlockDevice
+        } else if file_type.is_char_device() {
+            FileKind::CharDevice
+        } else {
+            FileKind::Other
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn from_file_type(file_type: fs::FileType) -> FileKind {
+        if file_type.is_dir() {
+            FileKind::Directory
+        } else if file_type.is_file() {
+            FileKind::Regular
+        } else {
+            FileKind::Other
+        }
+    }
+
+    // Only directories and regular files have content that can be uploaded
+    pub fn is_special(&self) -> bool {
+        !matches!(self, FileKind::Directory | FileKind::Regular)
+    }
+}
+
+impl Display for FileKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileKind::Directory => write!(f, "directory"),
+            FileKind::Regular => write!(f, "regular file"),
+            FileKind::Fifo => write!(f, "fifo"),
+            FileKind::Socket => write!(f, "socket"),
+            FileKind::BlockDevice => write!(f, "block device"),
+            FileKind::CharDevice => write!(f, "character device"),
+            FileKind::Other => write!(f, "unknown file type"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecialFile {
+    pub path: PathBuf,
+    pub kind: FileKind,
+}
+
+// A file or directory that vanished, a dangling symlink or a directory without read permission
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnreadableEntry {
+    pub path: PathBuf,
+    pub error: String,
 }
 
 #[derive(Debug, Clone)]
@@ -86,15 +209,24 @@ pub struct Folder {
     pub path: PathBuf,
     pub parent: Option<Box<Folder>>,
     pub children: Vec<Node>,
+    // Fifos, sockets and devices in this folder, they are skipped by the upload
+    pub skipped: Vec<SpecialFile>,
+    pub unreadable: Vec<UnreadableEntry>,
     pub drive_id: String,
 }
 
+enum Entry {
+    Node(Node),
+    Special(SpecialFile),
+}
+
 impl Folder {
     #[async_recursion]
     pub async fn from_path<'a>(
         path: &PathBuf,
         parent: Option<&'async_recursion Folder>,
         ids: &mut IdGen<'a>,
+        skip_unreadable: bool,
     ) -> Result<Folder, Error> {
         let name = path
             .file_name()
@@ -108,36 +240,63 @@ impl Folder {
             path: path.clone(),
             parent: parent.map(|folder| Box::new(folder.clone())),
             children: Vec::new(),
+            skipped: Vec::new(),
+            unreadable: Vec::new(),
             drive_id,
         };
 
         let entries = fs::read_dir(path).map_err(Error::ReadDir)?;
         let mut children = Vec::new();
+        let mut skipped = Vec::new();
+        let mut unreadable = Vec::new();
 
         for e in entries {
-            let entry = e.map_err(Error::ReadDirEntry)?;
-            let path = entry.path();
-
-            if path.is_dir() {
-                let folder = Folder::from_path(&path, Some(&folder), ids).await?;
-                let node = Node::FolderNode(folder);
-                children.push(node);
-            } else if path.is_file() {
-                let file = File::from_path(&path, &folder, ids).await?;
-                let node = Node::FileNode(file);
-                children.push(node);
-            } else if path.is_file() {
-                return Err(Error::IsSymlink(path.clone()));
-            } else {
-                return Err(Error::UnknownFileType(path.clone()));
+            match Folder::read_entry(e, &folder, ids, skip_unreadable).await {
+                Ok(Entry::Node(node)) => children.push(node),
+                Ok(Entry::Special(special_file)) => skipped.push(special_file),
+                Err((path, err)) if skip_unreadable && err.is_unreadable() => {
+                    unreadable.push(UnreadableEntry {
+                        path,
+                        error: err.to_string(),
+                    })
+                }
+                Err((_, err)) => return Err(err),
             }
         }
 
         folder.children = children;
+        folder.skipped = skipped;
+        folder.unreadable = unreadable;
 
         Ok(folder)
     }
 
+    // Errors come with the path of the entry, or of the folder when the entry itself can't be read
+    async fn read_entry<'a>(
+        entry: io::Result<fs::DirEntry>,
+        folder: &Folder,
+        ids: &mut IdGen<'a>,
+        skip_unreadable: bool,
+    ) -> Result<Entry, (PathBuf, Error)> {
+        let entry = entry.map_err(|err| (folder.path.clone(), Error::ReadDirEntry(err)))?;
+        let path = entry.path();
+        let kind = FileKind::from_path(&path).map_err(|err| (path.clone(), err))?;
+
+        match kind {
+            FileKind::Directory => Folder::from_path(&path, Some(folder), ids, skip_unreadable)
+                .await
+                .map(|folder| Entry::Node(Node::FolderNode(folder)))
+                .map_err(|err| (path, err)),
+
+            FileKind::Regular => File::from_path(&path, folder, ids)
+                .await
+                .map(|file| Entry::Node(Node::FileNode(file)))
+                .map_err(|err| (path, err)),
+
+            kind => Ok(Entry::Special(SpecialFile { path, kind })),
+        }
+    }
+
     pub fn files(&self) -> Vec<File> {
         let mut files = vec![];
 
@@ -210,8 +369,10 @@ impl File {
             .map(|s| s.to_string_lossy().to_string())
             .ok_or(Error::InvalidPath(path.clone()))?;
 
-        let os_file = fs::File::open(path).map_err(|err| Error::OpenFile(path.clone(), err))?;
-        let size = os_file.metadata().map(|m| m.len()).unwrap_or(0);
+        // The file is only opened when it's uploaded
+        let size = fs::metadata(path)
+            .map_err(|err| Error::Metadata(path.clone(), err))?
+            .len();
         let mime_type = mime_guess::from_path(path)
             .first()
             .unwrap_or(mime::APPLICATION_OCTET_STREAM);
@@ -241,6 +402,7 @@ impl File {
             size: self.size,
             mime_type: self.mime_type.clone(),
             parents,
+            modified_time: None,
         }
     }
 }
@@ -250,11 +412,19 @@ pub enum Error {
     CanonicalizePath(PathBuf, io::Error),
     ReadDir(io::Error),
     ReadDirEntry(io::Error),
-    OpenFile(PathBuf, io::Error),
     GetId(id_gen::Error),
     InvalidPath(PathBuf),
-    IsSymlink(PathBuf),
-    UnknownFileType(PathBuf),
+    Metadata(PathBuf, io::Error),
+}
+
+impl Error {
+    // Problems with the local files, as opposed to getting ids from drive
+    pub fn is_unreadable(&self) -> bool {
+        matches!(
+            self,
+            Error::ReadDir(_) | Error::ReadDirEntry(_) | Error::Metadata(_, _)
+        )
+    }
 }
 
 impl error::Error for Error {}
@@ -270,13 +440,11 @@ impl Display for Error {
             ),
             Error::ReadDir(e) => write!(f, "Error reading directory: '{}'", e),
             Error::ReadDirEntry(e) => write!(f, "Error reading directory entry: {}", e),
-            Error::OpenFile(path, e) => {
-                write!(f, "Failed to open file '{}': {}", path.display(), e)
-            }
             Error::GetId(e) => write!(f, "Error getting id: {}", e),
             Error::InvalidPath(path) => write!(f, "Invalid path: {}", path.display()),
-            Error::IsSymlink(path) => write!(f, "Path is symlink: {}", path.display()),
-            Error::UnknownFileType(path) => write!(f, "Unknown file type: {}", path.display()),
+            Error::Metadata(path, e) => {
+                write!(f, "Failed to read metadata of '{}': {}", path.display(), e)
+            }
         }
     }
 }
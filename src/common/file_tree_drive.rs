@@ -1,6 +1,8 @@
 use crate::common::drive_file;
+use crate::common::path_filter::PathFilter;
 use crate::files::list;
 use crate::files::list::ListQuery;
+use crate::files::list::Space;
 use crate::hub::Hub;
 use async_recursion::async_recursion;
 use std::error;
@@ -17,8 +19,19 @@ impl FileTreeDrive {
     pub async fn from_file(
         hub: &Hub,
         file: &google_drive3::api::File,
+        space: Space,
     ) -> Result<FileTreeDrive, Error> {
-        let root = Folder::from_file(hub, file, None).await?;
+        FileTreeDrive::from_file_filtered(hub, file, space, &PathFilter::default()).await
+    }
+
+    // Folders the filter doesn't enter are never listed
+    pub async fn from_file_filtered(
+        hub: &Hub,
+        file: &google_drive3::api::File,
+        space: Space,
+        filter: &PathFilter,
+    ) -> Result<FileTreeDrive, Error> {
+        let root = Folder::from_file(hub, file, None, space, filter).await?;
         Ok(FileTreeDrive { root })
     }
 
@@ -47,9 +60,13 @@ impl FileTreeDrive {
         let mut file_count = 0;
         let mut folder_count = 0;
         let mut total_file_size = 0;
+        let mut filtered_count = 0;
+        let mut depth_limited_count = 0;
 
         for folder in self.folders() {
             folder_count += 1;
+            filtered_count += folder.filtered_count as u128;
+            depth_limited_count += folder.depth_limited_count as u128;
 
             for file in folder.files() {
                 file_count += 1;
@@ -61,6 +78,8 @@ impl FileTreeDrive {
             file_count,
             folder_count,
             total_file_size,
+            filtered_count,
+            depth_limited_count,
         }
     }
 }
@@ -70,6 +89,10 @@ pub struct TreeInfo {
     pub file_count: u128,
     pub folder_count: u128,
     pub total_file_size: u128,
+    // Files and folders left out by the filter, a folder counts once whatever was in it
+    pub filtered_count: u128,
+    // Folders that weren't walked because of the depth limit, included in filtered_count
+    pub depth_limited_count: u128,
 }
 
 #[derive(Debug, Clone)]
@@ -84,6 +107,12 @@ pub struct Folder {
     pub parent: Option<Box<Folder>>,
     pub children: Vec<Node>,
     pub drive_id: String,
+    // Names of google documents in the folder, which have no binary content to download
+    pub skipped_documents: Vec<String>,
+    // Files and folders in the folder that were left out by the filter
+    pub filtered_count: usize,
+    // Folders in the folder that weren't walked because of the depth limit
+    pub depth_limited_count: usize,
 }
 
 impl Folder {
@@ -92,6 +121,8 @@ impl Folder {
         hub: &Hub,
         file: &google_drive3::api::File,
         parent: Option<&'async_recursion Folder>,
+        space: Space,
+        filter: &'async_recursion PathFilter,
     ) -> Result<Folder, Error> {
         err_if_not_directory(&file)?;
 
@@ -103,14 +134,21 @@ impl Folder {
             parent: parent.map(|folder| Box::new(folder.clone())),
             children: Vec::new(),
             drive_id: file_id.clone(),
+            skipped_documents: Vec::new(),
+            filtered_count: 0,
+            depth_limited_count: 0,
         };
 
+        // The filter sees paths relative to the folder the walk started from
+        let folder_path: PathBuf = folder.relative_path().components().skip(1).collect();
+
         let files = list::list_files(
             hub,
             &list::ListFilesConfig {
                 query: ListQuery::FilesInFolder { folder_id: file_id },
                 order_by: Default::default(),
                 max_files: usize::MAX,
+                space,
             },
         )
         .await
@@ -119,16 +157,41 @@ impl Folder {
         let mut children = Vec::new();
 
         for file in files {
+            let path = folder_path.join(file.name.clone().unwrap_or_default());
+
             if drive_file::is_directory(&file) {
-                let folder = Folder::from_file(hub, &file, Some(&folder)).await?;
-                let node = Node::FolderNode(folder);
+                if !filter.enters_folder(&path) {
+                    folder.filtered_count += 1;
+                    if filter.is_below_depth_limit(&path) {
+                        folder.depth_limited_count += 1;
+                    }
+                    continue;
+                }
+
+                let child = Folder::from_file(hub, &file, Some(&folder), space, filter).await?;
+
+                // A folder where the filter left nothing to download isn't created
+                if !filter.is_empty()
+                    && child.children.is_empty()
+                    && child.skipped_documents.is_empty()
+                {
+                    folder.filtered_count += 1;
+                    continue;
+                }
+
+                let node = Node::FolderNode(child);
                 children.push(node);
+            } else if !filter.includes_file(&path) {
+                folder.filtered_count += 1;
             } else if drive_file::is_binary(&file) {
                 let f = File::from_file(&file, &folder).await?;
                 let node = Node::FileNode(f);
                 children.push(node);
             } else {
                 // Skip documents
+                folder
+                    .skipped_documents
+                    .push(file.name.clone().unwrap_or_default());
             }
         }
 
@@ -207,7 +270,10 @@ impl File {
         parent: &Folder,
     ) -> Result<File, Error> {
         let name = file.name.clone().ok_or(Error::MissingFileName)?;
-        let size = file.size.ok_or(Error::MissingFileSize)? as u64;
+        let size = file
+            .size
+            .and_then(|size| u64::try_from(size).ok())
+            .ok_or(Error::MissingFileSize)?;
         let file_id = file.id.clone().ok_or(Error::MissingFileId)?;
         let md5 = file.md5_checksum.clone();
 
@@ -0,0 +1,113 @@
+use google_drive3::chrono::DateTime;
+use google_drive3::chrono::Utc;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+// Marks the start of a commit in the log output, file names can't contain it
+const COMMIT_MARKER: char = '\u{1}';
+
+// Time of the last commit that touched each file, by absolute path. Every repository is read
+// with a single git log the first time one of its files is looked up.
+#[derive(Debug, Default)]
+pub struct GitMtimes {
+    // Repository root of each directory, None outside a git working tree
+    roots: HashMap<PathBuf, Option<PathBuf>>,
+    repos: HashMap<PathBuf, HashMap<PathBuf, DateTime<Utc>>>,
+}
+
+impl GitMtimes {
+    // Commit time of the file, or its filesystem mtime when it isn't committed in a repository
+    pub fn modified_time(&mut self, path: &Path) -> Option<DateTime<Utc>> {
+        self.commit_time(path).or_else(|| filesystem_mtime(path))
+    }
+
+    pub fn commit_time(&mut self, path: &Path) -> Option<DateTime<Utc>> {
+        let dir = path.parent()?;
+
+        let root = match self.roots.get(dir) {
+            Some(root) => root.clone(),
+            None => {
+                let root = repo_root(dir);
+                self.roots.insert(dir.to_path_buf(), root.clone());
+                root
+            }
+        }?;
+
+        let times = self
+            .repos
+            .entry(root.clone())
+            .or_insert_with(|| commit_times(&root));
+
+        times.get(path).copied()
+    }
+}
+
+pub fn filesystem_mtime(path: &Path) -> Option<DateTime<Utc>> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    Some(DateTime::<Utc>::from(modified))
+}
+
+// Parses the output of git log --format=<COMMIT_MARKER>%cI --name-only. The log is newest first,
+// so the first time a path shows up is the last commit that touched it.
+pub fn parse_log(output: &str) -> HashMap<PathBuf, DateTime<Utc>> {
+    let mut times = HashMap::new();
+    let mut commit_time = None;
+
+    for line in output.lines() {
+        if let Some(time) = line.strip_prefix(COMMIT_MARKER) {
+            commit_time = DateTime::parse_from_rfc3339(time.trim())
+                .ok()
+                .map(|time| time.with_timezone(&Utc));
+        } else if let (Some(time), false) = (commit_time, line.is_empty()) {
+            times.entry(PathBuf::from(line)).or_insert(time);
+        }
+    }
+
+    times
+}
+
+// Canonical path of the working tree containing dir
+fn repo_root(dir: &Path) -> Option<PathBuf> {
+    let output = git(dir, &["rev-parse", "--show-toplevel"])?;
+    PathBuf::from(output.trim()).canonicalize().ok()
+}
+
+fn commit_times(root: &Path) -> HashMap<PathBuf, DateTime<Utc>> {
+    let format = format!("--format={}%cI", COMMIT_MARKER);
+    let args = [
+        "-c",
+        "core.quotePath=false",
+        "log",
+        &format,
+        "--name-only",
+        "--no-renames",
+    ];
+
+    git(root, &args)
+        .map(|output| {
+            parse_log(&output)
+                .into_iter()
+                .map(|(path, time)| (root.join(path), time))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Output of a successful git command run in dir, None if git is missing or the command failed
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .ok()?;
+
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        None
+    }
+}
@@ -1,29 +1,249 @@
 use crate::app_config;
+use crate::common::account_picker;
+use crate::common::cancel;
+use crate::common::drive_error;
+use crate::common::env_auth;
+use crate::app_config::AccountScope;
 use crate::app_config::AppConfig;
 use crate::hub::Auth;
 use crate::hub::Hub;
+use crate::hub::HubConfig;
 use std::error;
 use std::fmt;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::fs;
 use std::io;
+use std::io::IsTerminal;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
 
-pub async fn get_hub() -> Result<Hub, Error> {
-    let app_cfg = AppConfig::load_current_account().map_err(Error::AppConfig)?;
+// Exit code when the saved login can't be used anymore, EX_NOPERM from sysexits.h
+pub const AUTH_EXIT_CODE: i32 = 77;
+
+// Name of the account hubs are created for, None with a refresh token from the environment
+static ACCOUNT_NAME: Mutex<Option<String>> = Mutex::new(None);
+
+// Config of the hub of the command, only used for the hints in error messages
+static COMMAND_HUB_CONFIG: Mutex<Option<HubConfig>> = Mutex::new(None);
+
+// The hub of the command, config has the scopes and timeout from the command line, the account
+// scope comes from the saved account
+pub async fn get_hub(config: &HubConfig) -> Result<Hub, Error> {
+    if let Some(credentials) = env_auth::credentials().map_err(Error::EnvAuth)? {
+        let auth = Auth::from_refresh_token(&credentials.secret(), &credentials.refresh_token)
+            .await
+            .map_err(Error::Auth)?;
+        remember_hub_config(config);
+        return Ok(Hub::new(auth, config).await);
+    }
+
+    let app_cfg = load_account()?;
     let secret = app_cfg.load_secret().map_err(Error::AppConfig)?;
-    let auth = Auth::new(&secret, &app_cfg.tokens_path())
+    let config = account_hub_config(&app_cfg, config)?;
+    remember_hub_config(&config);
+
+    let mut auth = Auth::new(&secret, &app_cfg.tokens_path(), &config)
         .await
         .map_err(Error::Auth)?;
 
-    let hub = Hub::new(auth).await;
+    // The token is needed by the command anyway, getting it first turns a revoked login into
+    // a clear error instead of failing the first request
+    if let Err(err) = auth.token(&config.requested_scopes()).await {
+        if !drive_error::is_invalid_grant_message(&err.to_string()) {
+            return Err(Error::AccessToken(err));
+        }
+
+        let account_name = app_cfg.account.name.clone();
+        if !is_interactive() {
+            return Err(Error::InvalidCredentials(account_name));
+        }
+
+        eprintln!("{}", Error::InvalidCredentials(account_name.clone()));
+        if !confirm_reauthorize()? {
+            return Err(Error::InvalidCredentials(account_name));
+        }
+
+        // Keeps the account name and its client credentials, only the tokens are replaced
+        reauthorize_account(&app_cfg, &secret, &config).await?;
+        auth = Auth::new(&secret, &app_cfg.tokens_path(), &config)
+            .await
+            .map_err(Error::Auth)?;
+    }
+
+    let hub = Hub::new(auth, &config).await;
 
     Ok(hub)
 }
 
+// The config with the scope the account was added with
+fn account_hub_config(app_cfg: &AppConfig, config: &HubConfig) -> Result<HubConfig, Error> {
+    let account_scope = app_cfg.load_scope().map_err(Error::AppConfig)?;
+
+    Ok(HubConfig {
+        account_scope,
+        ..config.clone()
+    })
+}
+
+fn remember_hub_config(config: &HubConfig) {
+    if let Ok(mut current) = COMMAND_HUB_CONFIG.lock() {
+        *current = Some(config.clone());
+    }
+}
+
+fn command_hub_config() -> HubConfig {
+    COMMAND_HUB_CONFIG
+        .lock()
+        .ok()
+        .and_then(|config| config.clone())
+        .unwrap_or_default()
+}
+
+// Scope of the account the command uses, for hints in error messages
+pub fn command_account_scope() -> AccountScope {
+    command_hub_config().account_scope
+}
+
+fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+fn confirm_reauthorize() -> Result<bool, Error> {
+    let _prompt = cancel::Prompt::start();
+    eprint!("Sign in to the account again? [Y/n] ");
+    let _ = io::stderr().flush();
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).map_err(Error::Prompt)?;
+    Ok(parse_confirmation(&input))
+}
+
+// Anything but no confirms, pressing enter included
+pub fn parse_confirmation(input: &str) -> bool {
+    !matches!(input.trim().to_lowercase().as_str(), "n" | "no")
+}
+
+// Explains an invalid_grant error from a token request made while the command ran
+pub fn invalid_credentials_hint() -> String {
+    let account_name = ACCOUNT_NAME.lock().ok().and_then(|name| name.clone());
+    invalid_credentials_message(account_name.as_deref())
+}
+
+pub fn invalid_credentials_message(account_name: Option<&str>) -> String {
+    match account_name {
+        Some(name) => format!(
+            "{}. Run the command again with --reauth to sign in again",
+            Error::InvalidCredentials(name.to_string())
+        ),
+        None => format!(
+            "The refresh token from stdin or {} is no longer valid",
+            env_auth::REFRESH_TOKEN_ENV
+        ),
+    }
+}
+
+// Hub of another saved account, used next to the hub of the current account. It gets the scope
+// of its own account and the rest of config.
+pub async fn get_hub_for_account(account_name: &str, config: &HubConfig) -> Result<Hub, Error> {
+    let accounts = app_config::list_accounts().map_err(Error::AppConfig)?;
+    if !accounts.iter().any(|name| name == account_name) {
+        return Err(Error::AccountNotFound(account_name.to_string()));
+    }
+
+    let app_cfg = AppConfig::load_account(account_name).map_err(Error::AppConfig)?;
+    let secret = app_cfg.load_secret().map_err(Error::AppConfig)?;
+    let config = account_hub_config(&app_cfg, config)?;
+
+    let auth = Auth::new(&secret, &app_cfg.tokens_path(), &config)
+        .await
+        .map_err(Error::Auth)?;
+
+    Ok(Hub::new(auth, &config).await)
+}
+
+// Runs the consent flow for the requested scopes of config, unless tokens_path already has a
+// token for them
+pub async fn authorize(
+    secret: &app_config::Secret,
+    tokens_path: &PathBuf,
+    config: &HubConfig,
+) -> Result<Auth, Error> {
+    let auth = Auth::new(secret, tokens_path, config)
+        .await
+        .map_err(Error::Auth)?;
+    auth.token(&config.requested_scopes())
+        .await
+        .map_err(Error::AccessToken)?;
+    Ok(auth)
+}
+
+// Asks the user to approve the requested scopes again and replaces the saved tokens of
+// the current account. Used when the saved login is missing access a command needs.
+pub async fn reauthorize(config: &HubConfig) -> Result<(), Error> {
+    if env_auth::credentials().map_err(Error::EnvAuth)?.is_some() {
+        return Err(Error::ReauthWithRefreshToken);
+    }
+
+    let app_cfg = load_account()?;
+    let secret = app_cfg.load_secret().map_err(Error::AppConfig)?;
+    let config = account_hub_config(&app_cfg, config)?;
+    remember_hub_config(&config);
+
+    reauthorize_account(&app_cfg, &secret, &config).await
+}
+
+async fn reauthorize_account(
+    app_cfg: &AppConfig,
+    secret: &app_config::Secret,
+    config: &HubConfig,
+) -> Result<(), Error> {
+    // Authorize into an empty token file, a saved token would be reused as is
+    let tmp_dir = tempfile::tempdir().map_err(Error::Tempdir)?;
+    let tokens_path = tmp_dir.path().join("tokens.json");
+    authorize(secret, &tokens_path, config).await?;
+
+    fs::copy(&tokens_path, app_cfg.tokens_path()).map_err(Error::SaveTokens)?;
+    eprintln!("Updated the saved login of '{}'", app_cfg.account.name);
+
+    Ok(())
+}
+
+pub fn reauth_hint() -> String {
+    format!(
+        "The saved login doesn't include the access this command needs ({}). Run the command again with --reauth to approve it",
+        command_hub_config().requested_scopes().join(", ")
+    )
+}
+
+fn load_account() -> Result<AppConfig, Error> {
+    let app_cfg = if AppConfig::has_current_account() {
+        AppConfig::load_current_account().map_err(Error::AppConfig)?
+    } else {
+        account_picker::pick_account().map_err(Error::AccountPicker)?
+    };
+
+    if let Ok(mut account_name) = ACCOUNT_NAME.lock() {
+        *account_name = Some(app_cfg.account.name.clone());
+    }
+
+    Ok(app_cfg)
+}
+
 #[derive(Debug)]
 pub enum Error {
     AppConfig(app_config::Error),
+    AccountPicker(account_picker::Error),
     Auth(io::Error),
+    AccessToken(google_drive3::oauth2::Error),
+    Tempdir(io::Error),
+    SaveTokens(io::Error),
+    EnvAuth(env_auth::Error),
+    ReauthWithRefreshToken,
+    AccountNotFound(String),
+    InvalidCredentials(String),
+    Prompt(io::Error),
 }
 
 impl error::Error for Error {}
@@ -32,7 +252,24 @@ impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Error::AppConfig(err) => write!(f, "{}", err),
+            Error::AccountPicker(err) => write!(f, "{}", err),
             Error::Auth(err) => write!(f, "Auth error: {}", err),
+            Error::AccessToken(err) => write!(f, "Failed to get access token: {}", err),
+            Error::Tempdir(err) => write!(f, "Failed to create temporary directory: {}", err),
+            Error::SaveTokens(err) => write!(f, "Failed to save tokens: {}", err),
+            Error::EnvAuth(err) => write!(f, "{}", err),
+            Error::ReauthWithRefreshToken => write!(
+                f,
+                "--reauth can't be used with a refresh token from stdin or {}",
+                env_auth::REFRESH_TOKEN_ENV
+            ),
+            Error::AccountNotFound(name) => write!(f, "Account '{}' not found", name),
+            Error::InvalidCredentials(name) => write!(
+                f,
+                "Stored credentials for account '{}' are no longer valid",
+                name
+            ),
+            Error::Prompt(err) => write!(f, "Failed to read answer: {}", err),
         }
     }
 }
@@ -0,0 +1,106 @@
+use mime::Mime;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+static OVERRIDES: Mutex<Option<MimeOverrides>> = Mutex::new(None);
+
+// Extension to mime type table, consulted before the type is guessed from the extension
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "HashMap<String, String>", into = "HashMap<String, String>")]
+pub struct MimeOverrides(HashMap<String, Mime>);
+
+impl MimeOverrides {
+    pub fn insert(&mut self, extension: &str, mime_type: Mime) {
+        self.0.insert(normalize_extension(extension), mime_type);
+    }
+
+    pub fn extend(&mut self, other: MimeOverrides) {
+        self.0.extend(other.0)
+    }
+
+    pub fn get(&self, path: &Path) -> Option<Mime> {
+        let extension = path.extension()?.to_str()?;
+        self.0.get(&normalize_extension(extension)).cloned()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl TryFrom<HashMap<String, String>> for MimeOverrides {
+    type Error = String;
+
+    fn try_from(table: HashMap<String, String>) -> Result<Self, Self::Error> {
+        let mut overrides = MimeOverrides::default();
+
+        for (extension, mime_type) in table {
+            let mime_type = parse_mime(&extension, &mime_type)?;
+            overrides.insert(&extension, mime_type);
+        }
+
+        Ok(overrides)
+    }
+}
+
+impl From<MimeOverrides> for HashMap<String, String> {
+    fn from(overrides: MimeOverrides) -> Self {
+        overrides
+            .0
+            .into_iter()
+            .map(|(extension, mime_type)| (extension, mime_type.to_string()))
+            .collect()
+    }
+}
+
+// A single override from the command line, i.e. md=text/markdown
+impl FromStr for MimeOverrides {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (extension, mime_type) = s
+            .split_once('=')
+            .ok_or_else(|| format!("'{}' is not in the form EXTENSION=MIME", s))?;
+
+        if normalize_extension(extension).is_empty() {
+            return Err(format!("'{}' is missing the extension", s));
+        }
+
+        let mut overrides = MimeOverrides::default();
+        overrides.insert(extension, parse_mime(extension, mime_type)?);
+        Ok(overrides)
+    }
+}
+
+// Sets the table used by every upload, replacing any earlier one
+pub fn set(overrides: MimeOverrides) {
+    if let Ok(mut current) = OVERRIDES.lock() {
+        *current = Some(overrides);
+    }
+}
+
+pub fn lookup(path: &Path) -> Option<Mime> {
+    OVERRIDES
+        .lock()
+        .ok()?
+        .as_ref()
+        .and_then(|overrides| overrides.get(path))
+}
+
+fn parse_mime(extension: &str, mime_type: &str) -> Result<Mime, String> {
+    mime_type.trim().parse::<Mime>().map_err(|err| {
+        format!(
+            "Invalid mime type '{}' for extension '{}': {}",
+            mime_type, extension, err
+        )
+    })
+}
+
+// Extensions match case-insensitively and with or without the leading dot
+fn normalize_extension(extension: &str) -> String {
+    extension.trim().trim_start_matches('.').to_lowercase()
+}
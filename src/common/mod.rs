@@ -1,13 +1,39 @@
 pub mod account_archive;
+pub mod account_json;
+pub mod account_picker;
+pub mod archive_crypto;
+pub mod byte_size;
+pub mod cancel;
+pub mod checksum_manifest;
+pub mod client_secret;
+pub mod deadline;
 pub mod delegate;
+pub mod drive_api;
+pub mod drive_error;
 pub mod drive_file;
+pub mod env_auth;
 pub mod empty_file;
+#[cfg(feature = "test-support")]
+pub mod fake_drive;
 pub mod file_info;
+pub mod file_json;
 pub mod file_tree;
 pub mod file_tree_drive;
+pub mod git_mtime;
 pub mod hub_helper;
 pub mod id_gen;
 pub mod md5_writer;
+pub mod mime_overrides;
+pub mod output;
+pub mod parent_check;
+pub mod path_filter;
+pub mod pending_owner;
 pub mod permission;
+pub mod read_only;
+pub mod remote_walk;
 pub mod table;
+pub mod temp_copy;
+pub mod time_fmt;
+pub mod upload_manifest;
+pub mod url_fetch;
 pub mod file_helper;
@@ -0,0 +1,241 @@
+use std::env;
+use std::fmt;
+use std::fmt::Formatter;
+use std::io;
+use std::io::IsTerminal;
+use std::io::Write;
+use std::str::FromStr;
+use unicode_width::UnicodeWidthChar;
+use unicode_width::UnicodeWidthStr;
+
+const COLUMN_PADDING: usize = 3;
+
+// A column fitted to the terminal width is never truncated below this width
+const MIN_FIT_WIDTH: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputMode {
+    Plain,
+    Pretty,
+}
+
+impl OutputMode {
+    // Pretty output is used on a terminal unless colors are disabled, piped output stays plain
+    pub fn detect(no_color: bool) -> OutputMode {
+        if io::stdout().is_terminal() && color_enabled(no_color) {
+            OutputMode::Pretty
+        } else {
+            OutputMode::Plain
+        }
+    }
+}
+
+impl FromStr for OutputMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(OutputMode::Plain),
+            "pretty" => Ok(OutputMode::Pretty),
+            _ => Err(format!(
+                "'{}' is not a valid output mode, valid modes are: plain, pretty",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputMode {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            OutputMode::Plain => write!(f, "plain"),
+            OutputMode::Pretty => write!(f, "pretty"),
+        }
+    }
+}
+
+// See https://no-color.org
+pub fn color_enabled(no_color: bool) -> bool {
+    !no_color && env::var_os("NO_COLOR").map_or(true, |value| value.is_empty())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Normal,
+    Folder,
+    Document,
+    Shortcut,
+    Trashed,
+}
+
+impl Style {
+    fn ansi_code(&self) -> Option<&'static str> {
+        match self {
+            Style::Normal => None,
+            Style::Folder => Some("1;34"),
+            Style::Document => Some("32"),
+            Style::Shortcut => Some("2"),
+            Style::Trashed => Some("9"),
+        }
+    }
+
+    pub fn paint(&self, s: &str) -> String {
+        match self.ansi_code() {
+            Some(code) => format!("\x1b[{}m{}\x1b[0m", code, s),
+            None => s.to_string(),
+        }
+    }
+}
+
+pub struct PrettyTable<const COLUMNS: usize> {
+    pub header: [String; COLUMNS],
+    pub rows: Vec<([String; COLUMNS], Style)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PrettyConfig {
+    pub skip_header: bool,
+    pub color: bool,
+    // Indexes of the columns that are right aligned, i.e. sizes
+    pub right_aligned: Vec<usize>,
+    // Truncates the values of fit_column when the lines would be wider than max_width
+    pub max_width: Option<usize>,
+    pub fit_column: Option<usize>,
+}
+
+// Writes columns aligned by their display width, so wide characters don't break the alignment
+pub fn write_pretty<W: Write, const COLUMNS: usize>(
+    mut writer: W,
+    table: PrettyTable<COLUMNS>,
+    config: &PrettyConfig,
+) -> Result<(), io::Error> {
+    let mut rows = table.rows;
+    let mut widths = column_widths(&table.header, &rows, config);
+
+    if let (Some(max_width), Some(column)) = (config.max_width, config.fit_column) {
+        let line_width: usize = widths.iter().sum::<usize>() + COLUMN_PADDING * (COLUMNS - 1);
+
+        if line_width > max_width && column < COLUMNS {
+            let available = max_width
+                .saturating_sub(line_width - widths[column])
+                .max(MIN_FIT_WIDTH);
+
+            for (row, _) in rows.iter_mut() {
+                row[column] = truncate_middle(&row[column], available);
+            }
+
+            widths = column_widths(&table.header, &rows, config);
+        }
+    }
+
+    if !config.skip_header {
+        let line = pad_row(&table.header, &widths, &config.right_aligned);
+        if config.color {
+            writeln!(writer, "\x1b[1m{}\x1b[0m", line)?;
+        } else {
+            writeln!(writer, "{}", line)?;
+        }
+    }
+
+    for (row, style) in &rows {
+        let line = pad_row(row, &widths, &config.right_aligned);
+        if config.color {
+            writeln!(writer, "{}", style.paint(&line))?;
+        } else {
+            writeln!(writer, "{}", line)?;
+        }
+    }
+
+    writer.flush()
+}
+
+fn column_widths<const COLUMNS: usize>(
+    header: &[String; COLUMNS],
+    rows: &[([String; COLUMNS], Style)],
+    config: &PrettyConfig,
+) -> [usize; COLUMNS] {
+    let mut widths = [0; COLUMNS];
+
+    let rows = rows.iter().map(|(row, _)| row);
+    let header = Some(header).filter(|_| !config.skip_header);
+
+    for row in header.into_iter().chain(rows) {
+        for (width, value) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(display_width(value));
+        }
+    }
+
+    widths
+}
+
+fn pad_row<const COLUMNS: usize>(
+    row: &[String; COLUMNS],
+    widths: &[usize; COLUMNS],
+    right_aligned: &[usize],
+) -> String {
+    let mut line = String::new();
+
+    for (i, (value, width)) in row.iter().zip(widths.iter()).enumerate() {
+        let fill = " ".repeat(width - display_width(value));
+
+        if right_aligned.contains(&i) {
+            line.push_str(&fill);
+            line.push_str(value);
+        } else {
+            line.push_str(value);
+            line.push_str(&fill);
+        }
+
+        if i + 1 < COLUMNS {
+            line.push_str(&" ".repeat(COLUMN_PADDING));
+        }
+    }
+
+    line.trim_end().to_string()
+}
+
+// Width of the terminal stdout is written to, None when it's piped
+pub fn terminal_width() -> Option<usize> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+
+    terminal_size::terminal_size().map(|(terminal_size::Width(width), _)| width as usize)
+}
+
+pub fn display_width(s: &str) -> usize {
+    UnicodeWidthStr::width(s)
+}
+
+// Truncates string to the given display width, and inserts ellipsis into
+// the middle of the string to signify that the string has been truncated
+pub fn truncate_middle(s: &str, max_width: usize) -> String {
+    if display_width(s) <= max_width {
+        return s.to_string();
+    }
+
+    let tail_width = max_width / 2;
+    let head_width = max_width - tail_width - 1;
+
+    let head = take_width(s.chars(), head_width);
+    let mut tail: Vec<char> = take_width(s.chars().rev(), tail_width).chars().collect();
+    tail.reverse();
+
+    format!("{}…{}", head, tail.into_iter().collect::<String>())
+}
+
+fn take_width(chars: impl Iterator<Item = char>, max_width: usize) -> String {
+    let mut taken = String::new();
+    let mut width = 0;
+
+    for c in chars {
+        let char_width = c.width().unwrap_or(0);
+        if width + char_width > max_width {
+            break;
+        }
+        width += char_width;
+        taken.push(c);
+    }
+
+    taken
+}
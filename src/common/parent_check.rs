@@ -0,0 +1,125 @@
+use crate::common::drive_error;
+use crate::common::drive_file;
+use crate::files::path_utils;
+use crate::files::path_utils::PathResolutionError;
+use crate::hub::Hub;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+const PARENT_FIELDS: &str = "id,name,mimeType,driveId";
+
+// Gets a parent folder, failing if it doesn't exist, isn't accessible or isn't a folder
+pub async fn get_folder(hub: &Hub, parent_id: &str) -> Result<google_drive3::api::File, Error> {
+    let (_, file) = hub
+        .files()
+        .get(parent_id)
+        .param("fields", PARENT_FIELDS)
+        .add_scope(google_drive3::api::Scope::Full)
+        .supports_all_drives(true)
+        .doit()
+        .await
+        .map_err(|err| {
+            if drive_error::is_not_found(&err) {
+                Error::NotFound(parent_id.to_string())
+            } else {
+                Error::GetParent(parent_id.to_string(), err)
+            }
+        })?;
+
+    if !drive_file::is_directory(&file) {
+        return Err(Error::NotFolder(
+            parent_id.to_string(),
+            file.name.unwrap_or_default(),
+            file.mime_type.unwrap_or_default(),
+        ));
+    }
+
+    Ok(file)
+}
+
+// Checks every parent before anything is created. Returns the ids as reported by Drive,
+// which resolves aliases like 'root' to the actual folder id.
+pub async fn validate_parents(hub: &Hub, parent_ids: &[String]) -> Result<Vec<String>, Error> {
+    let mut ids = Vec::new();
+
+    for parent_id in parent_ids {
+        let folder = get_folder(hub, parent_id).await?;
+        ids.push(folder.id.unwrap_or_else(|| parent_id.clone()));
+    }
+
+    Ok(ids)
+}
+
+// Resolves parents given as ids or paths starting with '/'. Paths are looked up, or created
+// with create, and must be folders. Ids are checked to be folders with check_ids, otherwise
+// they're used as they are.
+pub async fn resolve_parents(
+    hub: &Hub,
+    parents: &[String],
+    create: bool,
+    check_ids: bool,
+) -> Result<Vec<String>, Error> {
+    let mut ids = Vec::new();
+
+    for parent in parents {
+        if path_utils::is_path(parent) {
+            let folder = path_utils::resolve_directory(hub, parent, create)
+                .await
+                .map_err(|err| Error::ResolvePath(parent.clone(), err))?;
+
+            if !drive_file::is_directory(&folder) {
+                return Err(Error::NotFolder(
+                    parent.clone(),
+                    folder.name.unwrap_or_default(),
+                    folder.mime_type.unwrap_or_default(),
+                ));
+            }
+
+            let id = folder.id.ok_or_else(|| {
+                Error::ResolvePath(parent.clone(), PathResolutionError::MissingId)
+            })?;
+            ids.push(id);
+        } else if check_ids {
+            let folder = get_folder(hub, parent).await?;
+            ids.push(folder.id.unwrap_or_else(|| parent.clone()));
+        } else {
+            ids.push(parent.clone());
+        }
+    }
+
+    Ok(ids)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    NotFound(String),
+    NotFolder(String, String, String),
+    GetParent(String, google_drive3::Error),
+    ResolvePath(String, PathResolutionError),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NotFound(id) => write!(
+                f,
+                "Parent folder '{}' does not exist or you don't have access to it",
+                id
+            ),
+            Error::NotFolder(id, name, mime_type) => write!(
+                f,
+                "Parent '{}' ({}) is not a folder, it has mime type {}",
+                name, id, mime_type
+            ),
+            Error::GetParent(id, err) => {
+                write!(f, "Failed to get parent folder '{}': {}", id, err)
+            }
+            Error::ResolvePath(path, err) => {
+                write!(f, "Failed to resolve parent '{}': {}", path, err)
+            }
+        }
+    }
+}
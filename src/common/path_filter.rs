@@ -0,0 +1,193 @@
+use crate::files::path_utils;
+use regex::Regex;
+use std::fmt;
+use std::fmt::Formatter;
+use std::path::Path;
+use std::str::FromStr;
+
+// Glob for --include and --exclude. A pattern without '/' is matched against the name at any
+// depth, like *.csv, anything else against the whole relative path, like reports/**/*.csv.
+// With match_path every pattern is matched against the whole relative path.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    glob: String,
+    regex: Regex,
+}
+
+impl Pattern {
+    fn is_name_pattern(&self, match_path: bool) -> bool {
+        !match_path && !self.glob.contains('/')
+    }
+
+    pub fn matches(&self, path: &str, match_path: bool) -> bool {
+        if self.is_name_pattern(match_path) {
+            let name = path.rsplit('/').next().unwrap_or_default();
+            self.regex.is_match(name)
+        } else {
+            self.regex.is_match(path)
+        }
+    }
+
+    // Whether the pattern could match something inside the folder
+    fn can_match_below(&self, folder: &str, match_path: bool) -> bool {
+        if self.is_name_pattern(match_path) {
+            return true;
+        }
+
+        let segments: Vec<&str> = self.glob.split('/').collect();
+        for (i, folder_segment) in folder.split('/').enumerate() {
+            match segments.get(i) {
+                Some(&"**") => return true,
+                // The last segment is matched by what's in the folder, not by the folder
+                Some(segment) if i + 1 < segments.len() => {
+                    if !segment_matches(segment, folder_segment) {
+                        return false;
+                    }
+                }
+                _ => return false,
+            }
+        }
+
+        true
+    }
+
+    // Whether everything in the folder is excluded by the pattern, i.e. node_modules or build/**
+    fn covers_folder(&self, folder: &str, match_path: bool) -> bool {
+        if self.matches(folder, match_path) {
+            return true;
+        }
+
+        match self.glob.strip_suffix("/**") {
+            Some(prefix) => {
+                matches!(prefix.parse::<Pattern>(), Ok(pattern) if pattern.matches(folder, match_path))
+            }
+            None => false,
+        }
+    }
+}
+
+fn segment_matches(glob: &str, segment: &str) -> bool {
+    Regex::new(&path_utils::wildcard_to_regex(glob))
+        .map(|regex| regex.is_match(segment))
+        .unwrap_or(false)
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let glob = s.trim_matches('/');
+        if glob.is_empty() {
+            return Err(String::from("Pattern can't be empty"));
+        }
+
+        let regex = Regex::new(&path_utils::wildcard_to_regex(glob))
+            .map_err(|err| format!("Invalid pattern '{}': {}", s, err))?;
+
+        Ok(Pattern {
+            glob: glob.to_string(),
+            regex,
+        })
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.glob)
+    }
+}
+
+// Decides which files of a recursive walk are kept, by their path relative to the folder the
+// walk starts from. Excludes win over includes, without includes every file is included.
+#[derive(Debug, Clone, Default)]
+pub struct PathFilter {
+    // Files directly in the starting folder are at depth 1
+    pub max_depth: Option<usize>,
+    pub include: Vec<Pattern>,
+    pub exclude: Vec<Pattern>,
+    // Match patterns without '/' against the relative path instead of the name
+    pub match_path: bool,
+}
+
+impl PathFilter {
+    pub fn is_empty(&self) -> bool {
+        self.max_depth.is_none() && self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn includes_file(&self, path: &Path) -> bool {
+        let path = slash_path(path);
+
+        if matches!(self.max_depth, Some(max_depth) if depth(&path) > max_depth) {
+            return false;
+        }
+
+        if self
+            .exclude
+            .iter()
+            .any(|pattern| pattern.matches(&path, self.match_path))
+        {
+            return false;
+        }
+
+        self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|pattern| pattern.matches(&path, self.match_path))
+    }
+
+    // Folders themselves are kept within the depth limit unless an exclude covers them
+    pub fn includes_folder(&self, path: &Path) -> bool {
+        let path = slash_path(path);
+
+        if matches!(self.max_depth, Some(max_depth) if depth(&path) > max_depth) {
+            return false;
+        }
+
+        !self
+            .exclude
+            .iter()
+            .any(|pattern| pattern.covers_folder(&path, self.match_path))
+    }
+
+    // The depth limit keeps the walk out of the folder, whatever the globs say
+    pub fn is_below_depth_limit(&self, path: &Path) -> bool {
+        let path = slash_path(path);
+        matches!(self.max_depth, Some(max_depth) if depth(&path) >= max_depth)
+    }
+
+    // Folders are only listed when something in them can still be included
+    pub fn enters_folder(&self, path: &Path) -> bool {
+        let path = slash_path(path);
+
+        if matches!(self.max_depth, Some(max_depth) if depth(&path) >= max_depth) {
+            return false;
+        }
+
+        if self
+            .exclude
+            .iter()
+            .any(|pattern| pattern.covers_folder(&path, self.match_path))
+        {
+            return false;
+        }
+
+        self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|pattern| pattern.can_match_below(&path, self.match_path))
+    }
+}
+
+// Patterns always use '/', whatever the local separator is
+fn slash_path(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn depth(path: &str) -> usize {
+    path.split('/').count()
+}
@@ -0,0 +1,150 @@
+use crate::common::drive_error;
+use crate::hub::Hub;
+use google_drive3::api::Permission;
+use google_drive3::hyper;
+use google_drive3::hyper::http;
+use serde::Deserialize;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+// The generated api types don't include pendingOwner, so these requests are made directly to
+// the api url of the hub
+
+const PERMISSION_FIELDS: &str =
+    "permissions(id,role,type,domain,emailAddress,allowFileDiscovery,expirationTime,pendingOwner)";
+
+#[derive(Debug, Deserialize)]
+struct PermissionList {
+    #[serde(default)]
+    permissions: Vec<PendingPermission>,
+}
+
+// A permission of the file and whether it has been offered ownership
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingPermission {
+    #[serde(flatten)]
+    pub permission: Permission,
+    #[serde(default)]
+    pub pending_owner: bool,
+}
+
+// Every permission of the file with pendingOwner, in a single request
+pub async fn list_permissions(hub: &Hub, file_id: &str) -> Result<Vec<PendingPermission>, Error> {
+    let url = format!(
+        "{}files/{}/permissions?fields={}&supportsAllDrives=true",
+        hub.api_url(),
+        file_id,
+        PERMISSION_FIELDS
+    );
+
+    let body = send(hub, http::Method::GET, &url, hyper::Body::empty()).await?;
+    let list: PermissionList = serde_json::from_slice(&body).map_err(Error::Deserialize)?;
+
+    Ok(list.permissions)
+}
+
+// Returns the ids of permissions that have been offered ownership of the file
+pub async fn pending_owner_ids(hub: &Hub, file_id: &str) -> Result<Vec<String>, Error> {
+    let permissions = list_permissions(hub, file_id).await?;
+
+    Ok(permissions
+        .into_iter()
+        .filter(|p| p.pending_owner)
+        .filter_map(|p| p.permission.id)
+        .collect())
+}
+
+pub async fn set_pending_owner(
+    hub: &Hub,
+    file_id: &str,
+    permission_id: &str,
+    pending_owner: bool,
+) -> Result<(), Error> {
+    let url = format!(
+        "{}files/{}/permissions/{}?supportsAllDrives=true",
+        hub.api_url(),
+        file_id,
+        permission_id
+    );
+
+    let body = serde_json::json!({ "pendingOwner": pending_owner }).to_string();
+    send(hub, http::Method::PATCH, &url, hyper::Body::from(body)).await?;
+
+    Ok(())
+}
+
+async fn send(
+    hub: &Hub,
+    method: http::Method,
+    url: &str,
+    body: hyper::Body,
+) -> Result<hyper::body::Bytes, Error> {
+    let scopes = [google_drive3::api::Scope::Full.as_ref()];
+    let token = hub
+        .auth
+        .get_token(&scopes)
+        .await
+        .map_err(|err| Error::Token(err.to_string()))?
+        .ok_or_else(|| Error::Token(String::from("no access token")))?;
+
+    let req = hyper::Request::builder()
+        .method(method)
+        .uri(url)
+        .header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .map_err(Error::BuildRequest)?;
+
+    let res = hub.client.request(req).await.map_err(Error::Request)?;
+    let status = res.status();
+    let bytes = hyper::body::to_bytes(res.into_body())
+        .await
+        .map_err(Error::Request)?;
+
+    if status.is_success() {
+        Ok(bytes)
+    } else {
+        let value = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+        Err(Error::Api(status, value))
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Token(String),
+    BuildRequest(http::Error),
+    Request(hyper::Error),
+    Api(http::StatusCode, serde_json::Value),
+    Deserialize(serde_json::Error),
+}
+
+impl Error {
+    pub fn reasons(&self) -> Vec<String> {
+        match self {
+            Error::Api(_, value) => drive_error::error_reasons(value),
+            _ => vec![],
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Token(err) => write!(f, "Failed to get access token: {}", err),
+            Error::BuildRequest(err) => write!(f, "Failed to build request: {}", err),
+            Error::Request(err) => write!(f, "Request failed: {}", err),
+            Error::Api(status, value) => {
+                let message = value
+                    .pointer("/error/message")
+                    .and_then(|m| m.as_str())
+                    .unwrap_or_default();
+                write!(f, "Server responded with {}: {}", status, message)
+            }
+            Error::Deserialize(err) => write!(f, "Failed to parse response: {}", err),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+pub fn enable() {
+    READ_ONLY.store(true, Ordering::SeqCst)
+}
+
+pub fn is_enabled() -> bool {
+    READ_ONLY.load(Ordering::SeqCst)
+}
+
+// Guard called by every operation that modifies the drive, before any api call is made
+pub fn err_if_enabled(operation: &str) -> Result<(), Error> {
+    if is_enabled() {
+        Err(Error::ReadOnly(operation.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    ReadOnly(String),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::ReadOnly(operation) => write!(
+                f,
+                "Refusing to {}, gdrive is running in read-only mode",
+                operation
+            ),
+        }
+    }
+}
@@ -0,0 +1,361 @@
+use crate::common::drive_api::DriveApi;
+use crate::common::drive_error;
+use crate::common::drive_file;
+use crate::common::path_filter::PathFilter;
+use crate::files;
+use crate::files::list::ListFilesConfig;
+use crate::files::list::ListQuery;
+use crate::files::list::ListSortOrder;
+use crate::files::list::Space;
+use crate::hub::Hub;
+use futures::stream;
+use futures::Stream;
+use google_drive3::api::File;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::future::Future;
+use std::path::Path;
+
+const PAGE_SIZE: i32 = 1000;
+
+// One page of the children of a folder
+#[derive(Debug, Clone, Default)]
+pub struct ListPage {
+    pub files: Vec<File>,
+    pub next_page_token: Option<String>,
+}
+
+// Where a walk gets its listings from, the drive api or a fake in tests
+pub trait FolderLister {
+    fn list_page(
+        &self,
+        folder_id: &str,
+        page_token: Option<&str>,
+    ) -> impl Future<Output = Result<ListPage, google_drive3::Error>>;
+
+    fn get_file(&self, file_id: &str) -> impl Future<Output = Result<File, google_drive3::Error>>;
+}
+
+// Lists folders with the drive api. Items of shared drives are included, drive_id limits the
+// listing to one shared drive.
+pub struct DriveLister<'a> {
+    pub hub: &'a Hub,
+    pub space: Space,
+    pub drive_id: Option<String>,
+}
+
+impl<'a> DriveLister<'a> {
+    pub fn new(hub: &'a Hub, space: Space) -> DriveLister<'a> {
+        DriveLister {
+            hub,
+            space,
+            drive_id: None,
+        }
+    }
+}
+
+impl FolderLister for DriveLister<'_> {
+    async fn list_page(
+        &self,
+        folder_id: &str,
+        page_token: Option<&str>,
+    ) -> Result<ListPage, google_drive3::Error> {
+        let mut req = self.hub.files().list();
+
+        if let Some(token) = page_token {
+            req = req.page_token(token);
+        }
+
+        if let Some(drive_id) = &self.drive_id {
+            req = req.corpora("drive").drive_id(drive_id);
+        }
+
+        if self.space != Space::Drive {
+            req = req.spaces(&self.space.to_string());
+        }
+
+        let (_, file_list) = req
+            .page_size(PAGE_SIZE)
+            .q(&format!("'{}' in parents and trashed = false", folder_id))
+            .order_by(&ListSortOrder::default().to_string())
+            .add_scope(google_drive3::api::Scope::Full)
+            .supports_all_drives(true)
+            .include_items_from_all_drives(true)
+            .param("fields", files::list::FILE_LIST_FIELDS)
+            .doit()
+            .await?;
+
+        Ok(ListPage {
+            files: file_list.files.unwrap_or_default(),
+            next_page_token: file_list.next_page_token,
+        })
+    }
+
+    async fn get_file(&self, file_id: &str) -> Result<File, google_drive3::Error> {
+        files::info::get_file(self.hub, file_id).await
+    }
+}
+
+// Lists each folder in one go through any DriveApi, for code that is generic over the api
+pub struct ApiLister<'a, A>(pub &'a A);
+
+impl<A: DriveApi> FolderLister for ApiLister<'_, A> {
+    async fn list_page(
+        &self,
+        folder_id: &str,
+        _page_token: Option<&str>,
+    ) -> Result<ListPage, google_drive3::Error> {
+        let config = ListFilesConfig {
+            query: ListQuery::FilesInFolder {
+                folder_id: folder_id.to_string(),
+            },
+            order_by: ListSortOrder::default(),
+            max_files: usize::MAX,
+            space: Space::default(),
+        };
+
+        Ok(ListPage {
+            files: self.0.list_files(&config).await?,
+            next_page_token: None,
+        })
+    }
+
+    async fn get_file(&self, file_id: &str) -> Result<File, google_drive3::Error> {
+        self.0.get_file(file_id).await
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ShortcutPolicy {
+    // Shortcuts are reported as they are
+    #[default]
+    Keep,
+    // Shortcuts are left out
+    Skip,
+    // The target is reported under the name of the shortcut, and walked when it's a folder
+    Follow,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WalkConfig {
+    // Depth limit and include/exclude globs, matched against the path relative to the start
+    pub filter: PathFilter,
+    pub shortcuts: ShortcutPolicy,
+}
+
+#[derive(Debug, Clone)]
+pub struct WalkEntry {
+    // Relative to the folder the walk starts from, separated by '/'
+    pub path: String,
+    // Files directly in the starting folder are at depth 1
+    pub depth: usize,
+    pub file: File,
+}
+
+impl WalkEntry {
+    pub fn is_folder(&self) -> bool {
+        drive_file::is_directory(&self.file)
+    }
+
+    // Path of the folder holding the entry, empty for the starting folder
+    pub fn parent_path(&self) -> &str {
+        self.path
+            .rsplit_once('/')
+            .map(|(parent, _)| parent)
+            .unwrap_or_default()
+    }
+}
+
+struct PendingFolder {
+    id: String,
+    path: String,
+    depth: usize,
+    page_token: Option<String>,
+}
+
+struct Walk<'a, L> {
+    lister: &'a L,
+    config: &'a WalkConfig,
+    // Folders breadth first, a folder with more pages is put back in front
+    pending: VecDeque<PendingFolder>,
+    entries: VecDeque<WalkEntry>,
+    // Guards against shortcuts that lead back into the walk
+    visited: HashSet<String>,
+}
+
+// Walks every folder below folder_id, breadth first, yielding the files and folders the filter
+// lets through. Folders are yielded before their contents. Pages are fetched as the stream is
+// consumed, so a consumer that stops early doesn't list the rest of the tree.
+pub fn walk<'a, L: FolderLister>(
+    lister: &'a L,
+    folder_id: &str,
+    config: &'a WalkConfig,
+) -> impl Stream<Item = Result<WalkEntry, Error>> + 'a {
+    let walk = Walk {
+        lister,
+        config,
+        pending: VecDeque::from([PendingFolder {
+            id: folder_id.to_string(),
+            path: String::new(),
+            depth: 0,
+            page_token: None,
+        }]),
+        entries: VecDeque::new(),
+        visited: HashSet::from([folder_id.to_string()]),
+    };
+
+    stream::try_unfold(walk, |mut walk| async move {
+        match walk.next_entry().await? {
+            Some(entry) => Ok(Some((entry, walk))),
+            None => Ok(None),
+        }
+    })
+}
+
+// Collects the whole walk
+pub async fn walk_all<L: FolderLister>(
+    lister: &L,
+    folder_id: &str,
+    config: &WalkConfig,
+) -> Result<Vec<WalkEntry>, Error> {
+    use futures::TryStreamExt;
+
+    walk(lister, folder_id, config).try_collect().await
+}
+
+impl<L: FolderLister> Walk<'_, L> {
+    async fn next_entry(&mut self) -> Result<Option<WalkEntry>, Error> {
+        loop {
+            if let Some(entry) = self.entries.pop_front() {
+                return Ok(Some(entry));
+            }
+
+            match self.pending.pop_front() {
+                Some(folder) => self.list_next_page(folder).await?,
+                None => return Ok(None),
+            }
+        }
+    }
+
+    async fn list_next_page(&mut self, mut folder: PendingFolder) -> Result<(), Error> {
+        let page = self
+            .lister
+            .list_page(&folder.id, folder.page_token.as_deref())
+            .await
+            .map_err(|err| Error::ListFolder(folder.path.clone(), err))?;
+
+        for file in page.files {
+            self.add_child(&folder, file).await?;
+        }
+
+        // The rest of the folder comes before the folders found in it
+        if page.next_page_token.is_some() {
+            folder.page_token = page.next_page_token;
+            self.pending.push_front(folder);
+        }
+
+        Ok(())
+    }
+
+    async fn add_child(&mut self, folder: &PendingFolder, file: File) -> Result<(), Error> {
+        let name = match &file.name {
+            Some(name) => name.clone(),
+            None => return Ok(()),
+        };
+
+        let path = if folder.path.is_empty() {
+            name
+        } else {
+            format!("{}/{}", folder.path, name)
+        };
+
+        let file = if drive_file::is_shortcut(&file) {
+            match self.config.shortcuts {
+                ShortcutPolicy::Keep => file,
+                ShortcutPolicy::Skip => return Ok(()),
+                ShortcutPolicy::Follow => self.shortcut_target(&path, &file).await?,
+            }
+        } else {
+            file
+        };
+
+        let filter = &self.config.filter;
+        let depth = folder.depth + 1;
+
+        if drive_file::is_directory(&file) {
+            if !filter.includes_folder(Path::new(&path)) {
+                return Ok(());
+            }
+
+            let id = file.id.clone().unwrap_or_default();
+            if filter.enters_folder(Path::new(&path)) && self.visited.insert(id.clone()) {
+                self.pending.push_back(PendingFolder {
+                    id,
+                    path: path.clone(),
+                    depth,
+                    page_token: None,
+                });
+            }
+        } else if !filter.includes_file(Path::new(&path)) {
+            return Ok(());
+        }
+
+        self.entries.push_back(WalkEntry { path, depth, file });
+
+        Ok(())
+    }
+
+    async fn shortcut_target(&self, path: &str, shortcut: &File) -> Result<File, Error> {
+        let target_id = drive_file::shortcut_target_id(shortcut)
+            .ok_or_else(|| Error::MissingShortcutTarget(path.to_string()))?;
+
+        let target = self
+            .lister
+            .get_file(&target_id)
+            .await
+            .map_err(|err| Error::GetShortcutTarget(path.to_string(), err))?;
+
+        // The path keeps the name of the shortcut
+        Ok(File {
+            name: shortcut.name.clone(),
+            ..target
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    ListFolder(String, google_drive3::Error),
+    MissingShortcutTarget(String),
+    GetShortcutTarget(String, google_drive3::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::ListFolder(path, err) if path.is_empty() => {
+                write!(f, "Failed to list files: {}", drive_error::describe(err))
+            }
+            Error::ListFolder(path, err) => write!(
+                f,
+                "Failed to list files in '{}': {}",
+                path,
+                drive_error::describe(err)
+            ),
+            Error::MissingShortcutTarget(path) => {
+                write!(f, "Shortcut '{}' doesn't have a target", path)
+            }
+            Error::GetShortcutTarget(path, err) => write!(
+                f,
+                "Failed to get the target of shortcut '{}': {}",
+                path,
+                drive_error::describe(err)
+            ),
+        }
+    }
+}
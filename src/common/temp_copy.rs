@@ -0,0 +1,61 @@
+use crate::common::cancel::PartialFile;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+// A copy of a local file under a different name, used to control the name of the uploaded file.
+// The copy lives in its own temporary directory which is removed when the guard is dropped.
+#[derive(Debug)]
+pub struct TempCopy {
+    path: PathBuf,
+    dir: Option<TempDir>,
+    _partial_dir: Option<PartialFile>,
+}
+
+impl TempCopy {
+    pub fn create(src: &Path, file_name: &str, keep: bool) -> Result<TempCopy, io::Error> {
+        let dir = tempfile::Builder::new().prefix("gdrive-").tempdir()?;
+        let path = dir.path().join(file_name);
+        fs::copy(src, &path)?;
+
+        let (dir, partial_dir) = if keep {
+            eprintln!("Keeping temporary file '{}'", path.display());
+            let _ = dir.keep();
+            (None, None)
+        } else {
+            // Also removed when gdrive is stopped with Ctrl-C
+            let partial_dir = PartialFile::new(dir.path());
+            (Some(dir), Some(partial_dir))
+        };
+
+        Ok(TempCopy {
+            path,
+            dir,
+            _partial_dir: partial_dir,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_kept(&self) -> bool {
+        self.dir.is_none()
+    }
+}
+
+impl Drop for TempCopy {
+    fn drop(&mut self) {
+        if let Some(dir) = self.dir.take() {
+            if let Err(err) = dir.close() {
+                eprintln!(
+                    "Warning: Failed to remove temporary file '{}': {}",
+                    self.path.display(),
+                    err
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use google_drive3::chrono;
+use google_drive3::chrono::DateTime;
+use google_drive3::chrono::SecondsFormat;
+use google_drive3::chrono::TimeZone;
+use google_drive3::chrono::Utc;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::str::FromStr;
+
+const LOCAL_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TimeStyle {
+    #[default]
+    Local,
+    Relative,
+    Iso,
+}
+
+impl FromStr for TimeStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "local" => Ok(TimeStyle::Local),
+            "relative" => Ok(TimeStyle::Relative),
+            "iso" => Ok(TimeStyle::Iso),
+            _ => Err(format!(
+                "'{}' is not a valid time style, valid styles are: local, relative, iso",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TimeStyle {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            TimeStyle::Local => write!(f, "local"),
+            TimeStyle::Relative => write!(f, "relative"),
+            TimeStyle::Iso => write!(f, "iso"),
+        }
+    }
+}
+
+pub fn format_time(time: DateTime<Utc>, style: &TimeStyle) -> String {
+    match style {
+        TimeStyle::Local => format_in_timezone(time, &chrono::Local),
+        TimeStyle::Relative => format_relative(time, Utc::now()),
+        TimeStyle::Iso => time.to_rfc3339_opts(SecondsFormat::Secs, true),
+    }
+}
+
+pub fn format_in_timezone<Tz>(time: DateTime<Utc>, timezone: &Tz) -> String
+where
+    Tz: TimeZone,
+    Tz::Offset: Display,
+{
+    time.with_timezone(timezone).format(LOCAL_FORMAT).to_string()
+}
+
+// The age is computed in utc, so a daylight saving change between time and now doesn't skew it
+pub fn format_relative(time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let seconds = now.signed_duration_since(time).num_seconds();
+
+    if seconds < 0 {
+        return String::from("in the future");
+    }
+
+    let (count, unit) = match seconds {
+        0 => return String::from("just now"),
+        1..=59 => (seconds, "second"),
+        60..=3599 => (seconds / 60, "minute"),
+        3600..=86399 => (seconds / 3600, "hour"),
+        86400..=2591999 => (seconds / 86400, "day"),
+        2592000..=31535999 => (seconds / 2592000, "month"),
+        _ => (seconds / 31536000, "year"),
+    };
+
+    if count == 1 {
+        format!("1 {} ago", unit)
+    } else {
+        format!("{} {}s ago", count, unit)
+    }
+}
@@ -0,0 +1,74 @@
+use serde::Deserialize;
+use serde::Serialize;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+// Files that failed during a recursive upload, can be fed back to retry only the failures
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadManifest {
+    pub failed_files: Vec<FailedFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FailedFile {
+    pub path: PathBuf,
+    pub parent_id: String,
+    pub error: String,
+}
+
+impl UploadManifest {
+    pub fn is_empty(&self) -> bool {
+        self.failed_files.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.failed_files.len()
+    }
+
+    pub fn push(&mut self, failed_file: FailedFile) {
+        self.failed_files.push(failed_file)
+    }
+
+    pub fn load(path: &Path) -> Result<UploadManifest, Error> {
+        let content =
+            fs::read_to_string(path).map_err(|err| Error::Read(path.to_path_buf(), err))?;
+        serde_json::from_str(&content).map_err(|err| Error::Deserialize(path.to_path_buf(), err))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let content = serde_json::to_string_pretty(self).map_err(Error::Serialize)?;
+        fs::write(path, content).map_err(|err| Error::Write(path.to_path_buf(), err))
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Read(PathBuf, io::Error),
+    Write(PathBuf, io::Error),
+    Serialize(serde_json::Error),
+    Deserialize(PathBuf, serde_json::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Read(path, err) => {
+                write!(f, "Failed to read manifest '{}': {}", path.display(), err)
+            }
+            Error::Write(path, err) => {
+                write!(f, "Failed to write manifest '{}': {}", path.display(), err)
+            }
+            Error::Serialize(err) => write!(f, "Failed to serialize manifest: {}", err),
+            Error::Deserialize(path, err) => {
+                write!(f, "Invalid manifest '{}': {}", path.display(), err)
+            }
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use crate::common::byte_size;
+use google_drive3::hyper;
+use google_drive3::hyper::body::HttpBody;
+use google_drive3::hyper::http;
+use google_drive3::hyper_rustls::HttpsConnectorBuilder;
+use mime::Mime;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::io;
+use std::io::Write;
+
+const MAX_REDIRECTS: usize = 5;
+const USER_AGENT: &str = concat!("gdrive/", env!("CARGO_PKG_VERSION"));
+
+pub struct FetchedContent {
+    pub size: u64,
+    pub mime_type: Option<Mime>,
+    pub file_name: Option<String>,
+}
+
+// Downloads url into writer, failing as soon as the body exceeds max_size bytes
+pub async fn fetch<W: Write>(
+    url: &str,
+    max_size: u64,
+    writer: &mut W,
+) -> Result<FetchedContent, Error> {
+    let connector = HttpsConnectorBuilder::new()
+        .with_native_roots()
+        .https_only()
+        .enable_http1()
+        .enable_http2()
+        .build();
+
+    let client = hyper::Client::builder().build::<_, hyper::Body>(connector);
+
+    let mut current_url = url.to_string();
+    let mut redirects = 0;
+
+    let res = loop {
+        let uri: hyper::Uri = current_url
+            .parse()
+            .map_err(|_| Error::InvalidUrl(current_url.clone()))?;
+
+        // Some hosts, like the GitHub api, reject requests without a user agent
+        let req = hyper::Request::get(uri)
+            .header(http::header::USER_AGENT, USER_AGENT)
+            .body(hyper::Body::empty())
+            .map_err(|_| Error::InvalidUrl(current_url.clone()))?;
+
+        let res = client.request(req).await.map_err(Error::Request)?;
+
+        if !res.status().is_redirection() {
+            break res;
+        }
+
+        redirects += 1;
+        if redirects > MAX_REDIRECTS {
+            return Err(Error::TooManyRedirects);
+        }
+
+        current_url = res
+            .headers()
+            .get(http::header::LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|location| resolve_location(&current_url, location))
+            .ok_or(Error::Status(res.status()))?;
+    };
+
+    if !res.status().is_success() {
+        return Err(Error::Status(res.status()));
+    }
+
+    let content_length = res
+        .headers()
+        .get(http::header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+
+    if content_length.map_or(false, |length| length > max_size) {
+        return Err(Error::TooLarge(max_size));
+    }
+
+    let mime_type = res
+        .headers()
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<Mime>().ok());
+
+    let mut body = res.into_body();
+    let mut size: u64 = 0;
+
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(Error::Request)?;
+        size += chunk.len() as u64;
+
+        if size > max_size {
+            return Err(Error::TooLarge(max_size));
+        }
+
+        writer.write_all(&chunk).map_err(Error::Write)?;
+    }
+
+    Ok(FetchedContent {
+        size,
+        mime_type,
+        file_name: file_name_from_url(&current_url),
+    })
+}
+
+// Posts a form encoded body to url, only the status of the response is checked
+pub async fn post_form(url: &str, body: &str) -> Result<(), Error> {
+    let connector = HttpsConnectorBuilder::new()
+        .with_native_roots()
+        .https_only()
+        .enable_http1()
+        .enable_http2()
+        .build();
+
+    let client = hyper::Client::builder().build::<_, hyper::Body>(connector);
+
+    let uri: hyper::Uri = url
+        .parse()
+        .map_err(|_| Error::InvalidUrl(url.to_string()))?;
+
+    let req = hyper::Request::post(uri)
+        .header(http::header::USER_AGENT, USER_AGENT)
+        .header(
+            http::header::CONTENT_TYPE,
+            "application/x-www-form-urlencoded",
+        )
+        .body(hyper::Body::from(body.to_string()))
+        .map_err(|_| Error::InvalidUrl(url.to_string()))?;
+
+    let res = client.request(req).await.map_err(Error::Request)?;
+
+    if !res.status().is_success() {
+        return Err(Error::Status(res.status()));
+    }
+
+    Ok(())
+}
+
+// A Location header can be relative to the url of the request, i.e. /files/report.csv
+pub fn resolve_location(url: &str, location: &str) -> Option<String> {
+    if location.contains("://") {
+        return Some(location.to_string());
+    }
+
+    let (scheme, rest) = url.split_once("://")?;
+
+    if let Some(network_path) = location.strip_prefix("//") {
+        return Some(format!("{}://{}", scheme, network_path));
+    }
+
+    let host = rest.split(['/', '?', '#']).next()?;
+
+    if location.starts_with('/') {
+        return Some(format!("{}://{}{}", scheme, host, location));
+    }
+
+    // Relative to the directory of the request path
+    let path = rest[host.len()..]
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default();
+    let directory = path
+        .rsplit_once('/')
+        .map(|(dir, _)| dir)
+        .unwrap_or_default();
+
+    Some(format!("{}://{}{}/{}", scheme, host, directory, location))
+}
+
+pub fn file_name_from_url(url: &str) -> Option<String> {
+    let without_query = url.split(|c| c == '?' || c == '#').next()?;
+    let (_, host_and_path) = without_query.split_once("://")?;
+    let (_, path) = host_and_path.split_once('/')?;
+    let name = path.rsplit('/').next()?;
+
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidUrl(String),
+    Request(hyper::Error),
+    Status(http::StatusCode),
+    TooManyRedirects,
+    TooLarge(u64),
+    Write(io::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidUrl(url) => write!(f, "Invalid url '{}'", url),
+            Error::Request(err) => write!(f, "Request failed: {}", err),
+            Error::Status(status) => write!(f, "Server responded with {}", status),
+            Error::TooManyRedirects => write!(f, "Too many redirects"),
+            Error::TooLarge(max_size) => {
+                write!(
+                    f,
+                    "Content is larger than the max size of {}",
+                    byte_size::format_human(*max_size)
+                )
+            }
+            Error::Write(err) => write!(f, "Failed to buffer content: {}", err),
+        }
+    }
+}
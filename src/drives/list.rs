@@ -1,44 +1,72 @@
 use crate::common::delegate::UploadDelegate;
 use crate::common::delegate::UploadDelegateConfig;
-use crate::common::hub_helper;
 use crate::common::table;
 use crate::common::table::Table;
+use crate::common::time_fmt;
+use crate::common::time_fmt::TimeStyle;
+use crate::files;
+use crate::files::info;
 use crate::hub::Hub;
+use google_drive3::api::Drive;
 use std::error;
 use std::fmt;
 use std::io;
+use std::str::FromStr;
+
+const MAX_PAGE_SIZE: i32 = 100;
 
 pub struct Config {
     pub skip_header: bool,
     pub field_separator: String,
+    pub name_contains: Option<String>,
+    pub sort: Option<DriveSort>,
+    pub json: bool,
 }
 
-pub async fn list(config: Config) -> Result<(), Error> {
-    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+pub async fn list(hub: &Hub, config: Config) -> Result<(), Error> {
     let delegate_config = UploadDelegateConfig::default();
 
-    let drives = list_drives(&hub, delegate_config)
+    let query = config.name_contains.as_deref().map(name_query);
+
+    let mut drives = list_drives(hub, delegate_config, query.as_deref())
         .await
         .map_err(Error::ListDrives)?;
 
-    print_drives_table(&config, drives);
+    if let Some(sort) = &config.sort {
+        sort_drives(&mut drives, sort);
+    }
+
+    if config.json {
+        let json = serde_json::to_string_pretty(&drives).map_err(Error::SerializeJson)?;
+        println!("{}", json);
+    } else {
+        print_drives_table(&config, drives);
+    }
 
     Ok(())
 }
 
-fn print_drives_table(config: &Config, drives: Vec<google_drive3::api::Drive>) {
-    let mut values: Vec<[String; 2]> = vec![];
+fn print_drives_table(config: &Config, drives: Vec<Drive>) {
+    let mut values: Vec<[String; 4]> = vec![];
 
     for drive in drives {
+        let can_add_children = drive
+            .capabilities
+            .and_then(|capabilities| capabilities.can_add_children);
+
         values.push([
-            // fmt
             drive.id.unwrap_or_default(),
             drive.name.unwrap_or_default(),
+            drive
+                .created_time
+                .map(|time| time_fmt::format_time(time, &TimeStyle::Local))
+                .unwrap_or_default(),
+            can_add_children.map(info::format_bool).unwrap_or_default(),
         ])
     }
 
     let table = Table {
-        header: ["Id", "Name"],
+        header: ["Id", "Name", "Created", "Can add children"],
         values,
     };
 
@@ -52,27 +80,99 @@ fn print_drives_table(config: &Config, drives: Vec<google_drive3::api::Drive>) {
     );
 }
 
+pub fn name_query(name_contains: &str) -> String {
+    format!(
+        "name contains '{}'",
+        files::list::escape_query_value(name_contains)
+    )
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DriveSort {
+    Name,
+    Created,
+}
+
+impl FromStr for DriveSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(DriveSort::Name),
+            "created" => Ok(DriveSort::Created),
+            _ => Err(format!(
+                "'{}' is not a valid sort order, valid orders are: name, created",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for DriveSort {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DriveSort::Name => write!(f, "name"),
+            DriveSort::Created => write!(f, "created"),
+        }
+    }
+}
+
+// Names are compared case insensitively, drives with the same name keep the order of the api
+pub fn sort_drives(drives: &mut [Drive], sort: &DriveSort) {
+    match sort {
+        DriveSort::Name => {
+            drives.sort_by_key(|drive| drive.name.clone().unwrap_or_default().to_lowercase())
+        }
+        DriveSort::Created => drives.sort_by_key(|drive| drive.created_time),
+    }
+}
+
 pub async fn list_drives(
     hub: &Hub,
     delegate_config: UploadDelegateConfig,
-) -> Result<Vec<google_drive3::api::Drive>, google_drive3::Error> {
+    query: Option<&str>,
+) -> Result<Vec<Drive>, google_drive3::Error> {
     let mut delegate = UploadDelegate::new(delegate_config);
+    let mut collected_drives: Vec<Drive> = vec![];
+    let mut next_page_token: Option<String> = None;
+
+    loop {
+        let mut req = hub.drives().list();
+
+        if let Some(token) = &next_page_token {
+            req = req.page_token(token);
+        }
+
+        if let Some(query) = query {
+            req = req.q(query);
+        }
 
-    let (_, drives_list) = hub
-        .drives()
-        .list()
-        .add_scope(google_drive3::api::Scope::Full)
-        .delegate(&mut delegate)
-        .doit()
-        .await?;
+        let (_, drive_list) = req
+            .page_size(MAX_PAGE_SIZE)
+            .param("fields", "drives,nextPageToken")
+            .add_scope(google_drive3::api::Scope::Full)
+            .delegate(&mut delegate)
+            .doit()
+            .await?;
+
+        if let Some(mut drives) = drive_list.drives {
+            collected_drives.append(&mut drives);
+        }
+
+        next_page_token = drive_list.next_page_token;
+
+        if next_page_token.is_none() {
+            break;
+        }
+    }
 
-    Ok(drives_list.drives.unwrap_or_default())
+    Ok(collected_drives)
 }
 
 #[derive(Debug)]
 pub enum Error {
-    Hub(hub_helper::Error),
     ListDrives(google_drive3::Error),
+    SerializeJson(serde_json::Error),
 }
 
 impl error::Error for Error {}
@@ -80,10 +180,10 @@ impl error::Error for Error {}
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Error::Hub(err) => write!(f, "{}", err),
             Error::ListDrives(err) => {
                 write!(f, "Failed to list drives: {}", err)
             }
+            Error::SerializeJson(err) => write!(f, "Failed to serialize json: {}", err),
         }
     }
 }
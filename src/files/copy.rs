@@ -1,7 +1,9 @@
 use crate::common::delegate::UploadDelegate;
 use crate::common::delegate::UploadDelegateConfig;
+use crate::common::drive_error;
 use crate::common::drive_file;
-use crate::common::hub_helper;
+use crate::common::parent_check;
+use crate::common::read_only;
 use crate::files;
 use crate::files::info::DisplayConfig;
 use crate::hub::Hub;
@@ -9,27 +11,43 @@ use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
 
+// The fields of the source that are used, including the metadata the copy gets
+const SOURCE_FIELDS: &str = "id,name,mimeType,parents,description,starred,properties,appProperties";
+
 #[derive(Clone, Debug)]
 pub struct Config {
     pub file_id: String,
     pub to_folder_id: String,
+    // Copy into the folder the file is already in, which creates a second file with the same name
+    pub allow_same_parent: bool,
+    // Give the copy the description, starred flag and properties of the source
+    pub copy_metadata: bool,
 }
 
-pub async fn copy(config: Config) -> Result<(), Error> {
-    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+pub async fn copy(hub: &Hub, config: Config) -> Result<(), Error> {
+    read_only::err_if_enabled("copy files").map_err(Error::ReadOnly)?;
+
     let delegate_config = UploadDelegateConfig::default();
 
-    let file = files::info::get_file(&hub, &config.file_id)
+    let file = files::info::get_file_with_fields(hub, &config.file_id, SOURCE_FIELDS)
         .await
         .map_err(Error::GetFile)?;
 
     err_if_directory(&file)?;
 
-    let to_parent = files::info::get_file(&hub, &config.to_folder_id)
+    let to_parent = parent_check::get_folder(hub, &config.to_folder_id)
         .await
-        .map_err(Error::GetDestinationFolder)?;
+        .map_err(Error::DestinationFolder)?;
 
-    err_if_not_directory(&to_parent)?;
+    if !config.allow_same_parent && is_in_folder(&file, &to_parent) {
+        return Err(Error::SameParent(file.name.unwrap_or_default()));
+    }
+
+    let metadata = if config.copy_metadata {
+        copy_metadata(&file)
+    } else {
+        google_drive3::api::File::default()
+    };
 
     println!(
         "Copying '{}' to '{}'",
@@ -40,11 +58,12 @@ pub async fn copy(config: Config) -> Result<(), Error> {
     let copy_config = CopyConfig {
         file_id: config.file_id,
         to_folder_id: config.to_folder_id,
+        metadata,
     };
 
-    let new_file = copy_file(&hub, delegate_config, &copy_config)
+    let new_file = copy_file(hub, delegate_config, &copy_config)
         .await
-        .map_err(Error::Copy)?;
+        .map_err(copy_error)?;
 
     let fields = files::info::prepare_fields(&new_file, &DisplayConfig::default());
     files::info::print_fields(&fields);
@@ -55,6 +74,8 @@ pub async fn copy(config: Config) -> Result<(), Error> {
 pub struct CopyConfig {
     pub file_id: String,
     pub to_folder_id: String,
+    // Fields the copy is created with besides its parent
+    pub metadata: google_drive3::api::File,
 }
 
 pub async fn copy_file(
@@ -66,7 +87,7 @@ pub async fn copy_file(
 
     let file = google_drive3::api::File {
         parents: Some(vec![config.to_folder_id.clone()]),
-        ..google_drive3::api::File::default()
+        ..config.metadata.clone()
     };
 
     let (_, file) = hub
@@ -83,12 +104,20 @@ pub async fn copy_file(
 
 #[derive(Debug)]
 pub enum Error {
-    Hub(hub_helper::Error),
+    ReadOnly(read_only::Error),
     GetFile(google_drive3::Error),
-    GetDestinationFolder(google_drive3::Error),
-    DestinationNotADirectory,
+    DestinationFolder(parent_check::Error),
     SourceIsADirectory,
+    SameParent(String),
     Copy(google_drive3::Error),
+    Quota(drive_error::QuotaError),
+}
+
+fn copy_error(err: google_drive3::Error) -> Error {
+    match drive_error::quota_error(&err) {
+        Some(quota_error) => Error::Quota(quota_error),
+        None => Error::Copy(err),
+    }
 }
 
 impl error::Error for Error {}
@@ -96,37 +125,51 @@ impl error::Error for Error {}
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         match self {
-            Error::Hub(err) => write!(f, "{}", err),
+            Error::ReadOnly(err) => write!(f, "{}", err),
             Error::GetFile(err) => {
                 write!(f, "Failed to get file: {}", err)
             }
-            Error::GetDestinationFolder(err) => {
-                write!(f, "Failed to get destination folder: {}", err)
-            }
-            Error::DestinationNotADirectory => {
-                write!(f, "Can only copy to a directory")
-            }
+            Error::DestinationFolder(err) => write!(f, "{}", err),
             Error::SourceIsADirectory => {
                 write!(f, "Copy directories is not supported")
             }
+            Error::SameParent(name) => write!(
+                f,
+                "'{}' is already in that folder, copying it there creates a duplicate. Use --allow-same-parent to copy anyway, or 'gdrive files update' to upload a new version of the file",
+                name
+            ),
             Error::Copy(err) => {
-                write!(f, "Failed to move file: {}", err)
+                write!(f, "Failed to copy file: {}", err)
+            }
+            Error::Quota(err) => {
+                write!(f, "Failed to copy file: {}", err)
             }
         }
     }
 }
 
-fn err_if_directory(file: &google_drive3::api::File) -> Result<(), Error> {
-    if drive_file::is_directory(file) {
-        Err(Error::SourceIsADirectory)
-    } else {
-        Ok(())
+// The metadata Drive doesn't carry over to a copy by itself
+pub fn copy_metadata(source: &google_drive3::api::File) -> google_drive3::api::File {
+    google_drive3::api::File {
+        description: source.description.clone(),
+        starred: source.starred,
+        properties: source.properties.clone(),
+        app_properties: source.app_properties.clone(),
+        ..google_drive3::api::File::default()
     }
 }
 
-fn err_if_not_directory(file: &google_drive3::api::File) -> Result<(), Error> {
-    if !drive_file::is_directory(file) {
-        Err(Error::DestinationNotADirectory)
+// The folder is one of the parents of the file
+pub fn is_in_folder(file: &google_drive3::api::File, folder: &google_drive3::api::File) -> bool {
+    match (&file.parents, &folder.id) {
+        (Some(parents), Some(folder_id)) => parents.contains(folder_id),
+        _ => false,
+    }
+}
+
+fn err_if_directory(file: &google_drive3::api::File) -> Result<(), Error> {
+    if drive_file::is_directory(file) {
+        Err(Error::SourceIsADirectory)
     } else {
         Ok(())
     }
@@ -0,0 +1,252 @@
+use crate::common::byte_size;
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::drive_file;
+use crate::common::path_filter::PathFilter;
+use crate::common::read_only;
+use crate::common::remote_walk;
+use crate::common::remote_walk::DriveLister;
+use crate::common::remote_walk::WalkConfig;
+use crate::files;
+use crate::files::duplicates;
+use crate::files::info;
+use crate::files::list::Space;
+use crate::files::path_utils;
+use crate::files::update::PatchFile;
+use crate::hub::Hub;
+use futures::TryStreamExt;
+use std::error;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::pin::pin;
+use std::str::FromStr;
+
+pub struct Config {
+    // Folder id or path starting with '/'
+    pub folder: String,
+    pub by_md5: bool,
+    pub keep: Keep,
+    // Without it the files that would be trashed are only printed
+    pub apply: bool,
+}
+
+// Which file of a group of duplicates is kept, by created time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Keep {
+    #[default]
+    Oldest,
+    Newest,
+}
+
+impl FromStr for Keep {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "oldest" => Ok(Keep::Oldest),
+            "newest" => Ok(Keep::Newest),
+            _ => Err(format!(
+                "'{}' is not a valid choice, valid choices are: oldest, newest",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for Keep {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Keep::Oldest => write!(f, "oldest"),
+            Keep::Newest => write!(f, "newest"),
+        }
+    }
+}
+
+// The file kept from a group of duplicates and the ones to trash
+#[derive(Debug, Clone)]
+pub struct DedupGroup {
+    pub key: String,
+    pub keep: google_drive3::api::File,
+    pub trash: Vec<google_drive3::api::File>,
+}
+
+pub async fn dedup(hub: &Hub, config: Config) -> Result<(), Error> {
+    if config.apply {
+        read_only::err_if_enabled("trash duplicate files").map_err(Error::ReadOnly)?;
+    }
+
+    let folder_id = path_utils::resolve_parent_ids(hub, &[config.folder.clone()], false)
+        .await
+        .map_err(Error::ResolvePath)?
+        .into_iter()
+        .next()
+        .unwrap_or_default();
+
+    // Only the files directly in the folder are compared
+    let lister = DriveLister::new(hub, Space::Drive);
+    let one_level = WalkConfig {
+        filter: PathFilter {
+            max_depth: Some(1),
+            ..PathFilter::default()
+        },
+        ..WalkConfig::default()
+    };
+
+    let files: Vec<google_drive3::api::File> =
+        remote_walk::walk_all(&lister, &folder_id, &one_level)
+            .await
+            .map_err(Error::Walk)?
+            .into_iter()
+            .map(|entry| entry.file)
+            .collect();
+    let groups = plan(&files, config.by_md5, config.keep);
+
+    if groups.is_empty() {
+        println!("No duplicates among {} files", files.len());
+        return Ok(());
+    }
+
+    let mut trashed_count = 0;
+    let mut skipped_count = 0;
+    let mut reclaimed_size: u128 = 0;
+
+    for group in &groups {
+        let label = if config.by_md5 {
+            format!("md5 {}", group.key)
+        } else {
+            format!("'{}'", group.key)
+        };
+        println!("{}: keeping {}", label, describe(&group.keep));
+
+        for file in &group.trash {
+            let file_id = file.id.clone().unwrap_or_default();
+
+            // Trashing a folder also trashes everything in it, which isn't necessarily a duplicate
+            let is_folder = drive_file::is_directory(file);
+            if is_folder && has_children(&lister, &file_id, &one_level).await? {
+                println!("  Skipping folder {}, it isn't empty", describe(file));
+                skipped_count += 1;
+                continue;
+            }
+
+            if config.apply {
+                trash_file(hub, &file_id).await.map_err(Error::Trash)?;
+                println!("  Trashed {}", describe(file));
+            } else {
+                println!("  Would trash {}", describe(file));
+            }
+
+            trashed_count += 1;
+            reclaimed_size += file.size.unwrap_or(0).max(0) as u128;
+        }
+    }
+
+    println!();
+    if config.apply {
+        println!(
+            "Trashed {} files, {} is reclaimed once the trash is emptied",
+            trashed_count,
+            byte_size::format_human(reclaimed_size)
+        );
+    } else {
+        println!(
+            "Would trash {} files and reclaim {}. Run again with --apply to trash them",
+            trashed_count,
+            byte_size::format_human(reclaimed_size)
+        );
+    }
+
+    if skipped_count > 0 {
+        println!("Skipped {} folders that aren't empty", skipped_count);
+    }
+
+    Ok(())
+}
+
+// Folders and other files are grouped separately, so a file is never trashed in favor of a
+// folder with the same name. Groups are sorted by key.
+pub fn plan(files: &[google_drive3::api::File], by_md5: bool, keep: Keep) -> Vec<DedupGroup> {
+    let (folders, others): (Vec<_>, Vec<_>) = files
+        .iter()
+        .cloned()
+        .partition(|file| drive_file::is_directory(file));
+
+    let mut groups: Vec<DedupGroup> = [folders, others]
+        .iter()
+        .flat_map(|files| duplicates::group_files(files, by_md5))
+        .map(|(key, mut files)| {
+            files.sort_by(|a, b| a.created_time.cmp(&b.created_time).then(a.id.cmp(&b.id)));
+            if keep == Keep::Newest {
+                files.reverse();
+            }
+
+            let kept = files.remove(0);
+            DedupGroup {
+                key,
+                keep: kept,
+                trash: files,
+            }
+        })
+        .collect();
+
+    groups.sort_by(|a, b| a.key.cmp(&b.key));
+    groups
+}
+
+fn describe(file: &google_drive3::api::File) -> String {
+    let mut details = vec![file.id.clone().unwrap_or_default()];
+
+    if let Some(size) = file.size {
+        details.push(info::format_bytes(size, &info::DisplayConfig::default()));
+    }
+
+    if let Some(time) = file.created_time {
+        details.push(format!("created {}", info::format_date_time(time)));
+    }
+
+    format!(
+        "'{}' ({})",
+        file.name.clone().unwrap_or_default(),
+        details.join(", ")
+    )
+}
+
+// Stops the walk at the first child instead of listing the whole folder
+async fn has_children(
+    lister: &DriveLister<'_>,
+    folder_id: &str,
+    config: &WalkConfig,
+) -> Result<bool, Error> {
+    let mut children = pin!(remote_walk::walk(lister, folder_id, config));
+    let first = children.try_next().await.map_err(Error::Walk)?;
+    Ok(first.is_some())
+}
+
+async fn trash_file(
+    hub: &Hub,
+    file_id: &str,
+) -> Result<google_drive3::api::File, google_drive3::Error> {
+    let patch_file = PatchFile::new(file_id.to_string()).with_trashed(true);
+    files::update::update_metadata(hub, UploadDelegateConfig::default(), patch_file).await
+}
+
+#[derive(Debug)]
+pub enum Error {
+    ReadOnly(read_only::Error),
+    ResolvePath(path_utils::PathResolutionError),
+    Walk(remote_walk::Error),
+    Trash(google_drive3::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::ReadOnly(err) => write!(f, "{}", err),
+            Error::ResolvePath(err) => write!(f, "Failed to resolve path: {}", err),
+            Error::Walk(err) => write!(f, "{}", err),
+            Error::Trash(err) => write!(f, "Failed to trash file: {}", err),
+        }
+    }
+}
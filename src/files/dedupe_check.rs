@@ -0,0 +1,191 @@
+use crate::common::drive_error;
+use crate::common::drive_file;
+use crate::common::md5_writer::Md5Writer;
+use crate::files;
+use crate::files::list::ListFilesConfig;
+use crate::files::list::ListQuery;
+use crate::files::list::ListSortOrder;
+use crate::files::list::Space;
+use crate::files::path_utils;
+use crate::hub::Hub;
+use google_drive3::api::File;
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+const CHECKSUM_LIST_FIELDS: &str =
+    "files(id,name,md5Checksum,size,mimeType,parents,createdTime),nextPageToken";
+
+// What to do when the drive already has a file with the content of the local file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DedupeAction {
+    #[default]
+    Skip,
+    // Create a shortcut to the existing file where the upload would have gone
+    Shortcut,
+    // Upload anyway, the existing file is only reported
+    Upload,
+}
+
+impl FromStr for DedupeAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(DedupeAction::Skip),
+            "shortcut" => Ok(DedupeAction::Shortcut),
+            "upload" => Ok(DedupeAction::Upload),
+            _ => Err(format!(
+                "'{}' is not a valid dedupe action, valid actions are: skip, shortcut, upload",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for DedupeAction {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            DedupeAction::Skip => write!(f, "skip"),
+            DedupeAction::Shortcut => write!(f, "shortcut"),
+            DedupeAction::Upload => write!(f, "upload"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Decision {
+    Upload,
+    Skip(File),
+    Shortcut(File),
+}
+
+// Files with the same md5 checksum and size as the local file
+pub fn same_content(files: Vec<File>, md5: &str, size: u64) -> Vec<File> {
+    files
+        .into_iter()
+        .filter(|file| !drive_file::is_directory(file))
+        .filter(|file| file.md5_checksum.as_deref() == Some(md5))
+        .filter(|file| file.size.and_then(|size| u64::try_from(size).ok()) == Some(size))
+        .collect()
+}
+
+// The oldest match is the one acted on, ties go to the lowest id so repeated runs agree
+pub fn pick_existing(matches: &[File]) -> Option<&File> {
+    matches.iter().min_by(|a, b| {
+        a.created_time
+            .cmp(&b.created_time)
+            .then_with(|| a.id.cmp(&b.id))
+    })
+}
+
+pub fn decide(matches: &[File], action: DedupeAction) -> Decision {
+    match (pick_existing(matches).cloned(), action) {
+        (None, _) | (Some(_), DedupeAction::Upload) => Decision::Upload,
+        (Some(file), DedupeAction::Skip) => Decision::Skip(file),
+        (Some(file), DedupeAction::Shortcut) => Decision::Shortcut(file),
+    }
+}
+
+pub fn local_md5(path: &Path) -> Result<String, io::Error> {
+    let mut file = fs::File::open(path)?;
+    let mut writer = Md5Writer::new(io::sink());
+    io::copy(&mut file, &mut writer)?;
+    Ok(writer.md5())
+}
+
+// The files owned by the user by md5 checksum. Drive can't search by checksum, so every file is
+// listed once per command and each local file is compared against the listing. Files shared with
+// the user are left out, a copy of someone else's file isn't a duplicate.
+#[derive(Debug, Default)]
+pub struct ChecksumIndex {
+    files: HashMap<String, Vec<File>>,
+}
+
+impl ChecksumIndex {
+    pub fn new(files: Vec<File>) -> ChecksumIndex {
+        let mut index = ChecksumIndex::default();
+        for file in files {
+            index.add(file);
+        }
+        index
+    }
+
+    pub async fn build(hub: &Hub) -> Result<ChecksumIndex, Error> {
+        let config = ListFilesConfig {
+            query: ListQuery::Custom(String::from("'me' in owners and trashed = false")),
+            order_by: ListSortOrder::default(),
+            max_files: usize::MAX,
+            space: Space::Drive,
+        };
+
+        let files = files::list::fetch_files_with_fields(hub, &config, CHECKSUM_LIST_FIELDS)
+            .await
+            .map_err(Error::ListFiles)?;
+
+        Ok(ChecksumIndex::new(files))
+    }
+
+    // Files uploaded by the command are added, so later files with the same content match them
+    pub fn add(&mut self, file: File) {
+        if let Some(md5) = file.md5_checksum.clone() {
+            self.files.entry(md5).or_default().push(file);
+        }
+    }
+
+    // Files with the same md5 checksum and size as the local file
+    pub fn find(&self, path: &Path, size: u64) -> Result<Vec<File>, Error> {
+        let md5 = local_md5(path).map_err(|err| Error::ReadFile(path.to_path_buf(), err))?;
+        let candidates = self.files.get(&md5).cloned().unwrap_or_default();
+        Ok(same_content(candidates, &md5, size))
+    }
+}
+
+// i.e. "/Projects/2024/report.pdf (1AbC)"
+pub async fn describe_existing(hub: &Hub, file: &File) -> Result<String, Error> {
+    let path = path_utils::file_path(hub, file)
+        .await
+        .map_err(Error::FilePath)?;
+
+    Ok(format!(
+        "{} ({})",
+        path,
+        file.id.as_deref().unwrap_or_default()
+    ))
+}
+
+#[derive(Debug)]
+pub enum Error {
+    ReadFile(PathBuf, io::Error),
+    ListFiles(google_drive3::Error),
+    FilePath(path_utils::PathResolutionError),
+    CreateShortcut(google_drive3::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::ReadFile(path, err) => {
+                write!(f, "Failed to read '{}': {}", path.display(), err)
+            }
+            Error::ListFiles(err) => write!(
+                f,
+                "Failed to list files to compare checksums: {}",
+                drive_error::describe(err)
+            ),
+            Error::FilePath(err) => {
+                write!(f, "Failed to get the path of the existing file: {}", err)
+            }
+            Error::CreateShortcut(err) => write!(f, "Failed to create shortcut: {}", err),
+        }
+    }
+}
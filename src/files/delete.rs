@@ -2,29 +2,43 @@ use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
 
+use crate::common::cancel;
+use crate::common::delegate::UploadDelegate;
+use crate::common::delegate::UploadDelegateConfig;
 use crate::common::drive_file;
-use crate::common::hub_helper;
+use crate::common::read_only;
+use crate::common::remote_walk;
+use crate::common::remote_walk::DriveLister;
+use crate::common::remote_walk::FolderLister;
+use crate::common::remote_walk::WalkConfig;
 use crate::files;
+use crate::files::list::Space;
+use crate::hub::Hub;
+use futures::future;
+use futures::stream;
+use futures::stream::StreamExt;
 
 pub struct Config {
     pub file_id: String,
     pub delete_directories: bool,
+    pub concurrency: usize,
+    pub space: Space,
 }
 
-pub async fn delete(config: Config) -> Result<(), Error> {
-    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+pub async fn delete(hub: &Hub, config: Config) -> Result<(), Error> {
+    read_only::err_if_enabled("delete files").map_err(Error::ReadOnly)?;
 
-    let file = files::info::get_file(&hub, &config.file_id)
+    let file = files::info::get_file(hub, &config.file_id)
         .await
         .map_err(Error::GetFile)?;
 
     err_if_directory(&file, &config)?;
 
-    hub.files()
-        .delete(&config.file_id)
-        .supports_all_drives(true)
-        .add_scope(google_drive3::api::Scope::Full)
-        .doit()
+    if drive_file::is_directory(&file) {
+        return delete_directory(hub, &file, &config).await;
+    }
+
+    delete_file(hub, &config.file_id)
         .await
         .map_err(Error::DeleteFile)?;
 
@@ -33,11 +47,125 @@ pub async fn delete(config: Config) -> Result<(), Error> {
     Ok(())
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub id: String,
+    pub path: String,
+    pub is_directory: bool,
+}
+
+// Deletes the tree one level at a time, starting with the deepest level, so children
+// are always gone before their parent is deleted
+async fn delete_directory(
+    hub: &Hub,
+    folder: &google_drive3::api::File,
+    config: &Config,
+) -> Result<(), Error> {
+    let lister = DriveLister::new(hub, config.space);
+    let levels = collect_levels(&lister, folder).await?;
+    let total: usize = levels.iter().map(Vec::len).sum();
+    let mut deleted = 0;
+
+    println!("Deleting {} files and directories", total);
+
+    for level in levels.into_iter().rev() {
+        // Deletions already started are finished after Ctrl-C, no new ones are started
+        let mut deletions = stream::iter(level)
+            .take_while(|_| future::ready(!cancel::is_cancelled()))
+            .map(|entry| async move {
+                let result = delete_file(hub, &entry.id).await;
+                (entry, result)
+            })
+            .buffer_unordered(config.concurrency.max(1));
+
+        let mut failed: Vec<(Entry, google_drive3::Error)> = vec![];
+
+        while let Some((entry, result)) = deletions.next().await {
+            match result {
+                Ok(()) => {
+                    deleted += 1;
+                    println!("Deleted '{}' ({}/{})", entry.path, deleted, total);
+                }
+
+                Err(err) => failed.push((entry, err)),
+            }
+        }
+
+        // The parents of failed entries are kept, deleting them would take the entries with them
+        if !failed.is_empty() {
+            for (entry, err) in &failed {
+                eprintln!("Failed to delete '{}': {}", entry.path, err);
+            }
+            return Err(Error::DeleteFiles(failed.len()));
+        }
+
+        if cancel::is_cancelled() {
+            eprintln!("Cancelled after deleting {} of {} files", deleted, total);
+            return Err(Error::Cancelled);
+        }
+    }
+
+    Ok(())
+}
+
+// Returns the folder and its descendants grouped by depth, the folder itself is the only entry of the first level
+pub async fn collect_levels<L: FolderLister>(
+    lister: &L,
+    folder: &google_drive3::api::File,
+) -> Result<Vec<Vec<Entry>>, Error> {
+    let folder_id = folder.id.clone().unwrap_or_default();
+    let folder_name = folder.name.clone().unwrap_or_default();
+
+    let root = Entry {
+        id: folder_id.clone(),
+        path: folder_name.clone(),
+        is_directory: true,
+    };
+
+    let mut levels = vec![vec![root]];
+
+    // The walk is breadth first, so the depth of the entries never goes down
+    let entries = remote_walk::walk_all(lister, &folder_id, &WalkConfig::default())
+        .await
+        .map_err(Error::Walk)?;
+
+    for entry in entries {
+        if levels.len() <= entry.depth {
+            levels.push(vec![]);
+        }
+
+        levels[entry.depth].push(Entry {
+            id: entry.file.id.clone().unwrap_or_default(),
+            path: format!("{}/{}", folder_name, entry.path),
+            is_directory: entry.is_folder(),
+        });
+    }
+
+    Ok(levels)
+}
+
+pub async fn delete_file(hub: &Hub, file_id: &str) -> Result<(), google_drive3::Error> {
+    let mut delegate = UploadDelegate::new(UploadDelegateConfig::default());
+
+    hub.files()
+        .delete(file_id)
+        .supports_all_drives(true)
+        .add_scope(google_drive3::api::Scope::Full)
+        .delegate(&mut delegate)
+        .doit()
+        .await?;
+
+    Ok(())
+}
+
 #[derive(Debug)]
 pub enum Error {
-    Hub(hub_helper::Error),
+    ReadOnly(read_only::Error),
     GetFile(google_drive3::Error),
     DeleteFile(google_drive3::Error),
+    Walk(remote_walk::Error),
+    DeleteFiles(usize),
+    Cancelled,
     IsDirectory(String),
 }
 
@@ -46,9 +174,12 @@ impl error::Error for Error {}
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::Hub(err) => write!(f, "{}", err),
+            Error::ReadOnly(err) => write!(f, "{}", err),
             Error::GetFile(err) => write!(f, "Failed getting file: {}", err),
             Error::DeleteFile(err) => write!(f, "Failed to delete file: {}", err),
+            Error::Walk(err) => write!(f, "{}", err),
+            Error::DeleteFiles(count) => write!(f, "Failed to delete {} files", count),
+            Error::Cancelled => write!(f, "Delete was cancelled"),
             Error::IsDirectory(name) => write!(
                 f,
                 "'{}' is a directory, use --recursive to delete directories",
@@ -1,15 +1,18 @@
+use crate::common::byte_size;
+use crate::common::cancel;
+use crate::common::checksum_manifest::ChecksumManifest;
+use crate::common::drive_error;
 use crate::common::drive_file;
 use crate::common::file_tree_drive;
 use crate::common::file_tree_drive::FileTreeDrive;
-use crate::common::hub_helper;
 use crate::common::md5_writer::Md5Writer;
+use crate::common::path_filter::PathFilter;
 use crate::files;
+use crate::files::list::Space;
 use crate::files::path_utils;
 use crate::hub::Hub;
-use async_recursion::async_recursion;
 use futures::stream::StreamExt;
 use google_drive3::hyper;
-use human_bytes::human_bytes;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
@@ -19,6 +22,7 @@ use std::io;
 use std::io::BufReader;
 use std::io::Read;
 use std::io::Write;
+use std::path::Path;
 use std::path::PathBuf;
 
 pub struct Config {
@@ -28,20 +32,33 @@ pub struct Config {
     pub follow_shortcuts: bool,
     pub download_directories: bool,
     pub destination: Destination,
+    pub checksum_manifest: Option<PathBuf>,
+    pub space: Space,
+    pub max_size: Option<u64>,
+    // Which files of a recursive download are downloaded
+    pub filter: PathFilter,
+    // Let the path name a trashed file
+    pub include_trashed: bool,
+    // Save a single file under this name instead of its name on drive
+    pub save_as: Option<String>,
 }
 
 impl Config {
     async fn resolve_file_id(&self, hub: &Hub) -> Result<String, Error> {
         if let Some(path) = &self.path {
-            let file = path_utils::resolve_path(hub, path)
+            path_utils::resolve_file_id_with_trashed(hub, path, self.include_trashed)
                 .await
-                .map_err(Error::ResolvePath)?;
-            Ok(file.id.unwrap_or_default())
+                .map_err(Error::ResolvePath)
         } else {
             Ok(self.file_id.clone())
         }
     }
     
+    // Name of the local file a single file is saved as
+    fn local_file_name(&self, file: &google_drive3::api::File) -> Option<String> {
+        self.save_as.clone().or_else(|| file.name.clone())
+    }
+
     fn canonical_destination_root(&self) -> Result<PathBuf, Error> {
         match &self.destination {
             Destination::CurrentDir => {
@@ -105,55 +122,84 @@ pub enum ExistingFileAction {
     Overwrite,
 }
 
-#[async_recursion]
-pub async fn download(config: Config) -> Result<(), Error> {
-    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+pub async fn download(hub: &Hub, config: Config) -> Result<(), Error> {
+    let mut manifest = ChecksumManifest::default();
+
+    let result = download_with_manifest(hub, &config, &mut manifest).await;
+
+    // Also written after a partial failure, so the files that did download can be verified
+    if let Some(manifest_path) = &config.checksum_manifest {
+        if result.is_ok() || !manifest.is_empty() {
+            manifest
+                .save(manifest_path)
+                .map_err(|err| Error::WriteManifest(manifest_path.clone(), err))?;
+            println!("Wrote checksum manifest to {}", manifest_path.display());
+        }
+    }
+
+    result
+}
+
+async fn download_with_manifest(
+    hub: &Hub,
+    config: &Config,
+    manifest: &mut ChecksumManifest,
+) -> Result<(), Error> {
+    if let Some(name) = &config.save_as {
+        err_if_invalid_save_as(name)?;
+    }
 
     // Check if this is a wildcard path download
     if let Some(path) = &config.path {
-        if path.contains('*') {
-            return download_wildcard(&hub, path, &config).await;
+        if path_utils::has_wildcard(path) {
+            if config.save_as.is_some() {
+                return Err(Error::SaveAsMultipleFiles);
+            }
+
+            return download_wildcard(hub, path, config, manifest).await;
         }
     }
 
     // Resolve file ID from path if provided
-    let file_id = config.resolve_file_id(&hub).await?;
+    let file_id = config.resolve_file_id(hub).await?;
 
     // Get file metadata
-    let file = files::info::get_file(&hub, &file_id)
+    let file = files::info::get_file(hub, &file_id)
         .await
         .map_err(Error::GetFile)?;
 
     // If file is a shortcut and follow_shortcuts is enabled, resolve the shortcut
     let file = if config.follow_shortcuts && drive_file::is_shortcut(&file) {
-        let target_id = file
-            .shortcut_details
-            .as_ref()
-            .and_then(|details| details.target_id.clone())
-            .ok_or(Error::ShortcutMissingTargetId)?;
-
-        files::info::get_file(&hub, &target_id)
+        files::info::get_shortcut_target(hub, &file)
             .await
             .map_err(Error::GetFile)?
+            .ok_or(Error::ShortcutMissingTargetId)?
     } else {
         file
     };
 
     // If file is a folder and download_directories is enabled, download the folder
     if drive_file::is_directory(&file) {
-        if config.download_directories {
-            return download_directory(&hub, &file, &config).await;
+        if config.save_as.is_some() {
+            return Err(Error::SaveAsDirectory(file.name.unwrap_or_default()));
+        } else if config.download_directories {
+            return download_directory(hub, &file, config, manifest).await;
         } else {
             return Err(Error::IsDirectory(file.name.unwrap_or_default()));
         }
     }
 
     // Download the file
-    download_regular(&hub, &file, &config).await
+    download_regular(hub, &file, config, manifest).await
 }
 
 /// Download files matching a wildcard pattern
-async fn download_wildcard(hub: &Hub, path: &str, config: &Config) -> Result<(), Error> {
+async fn download_wildcard(
+    hub: &Hub,
+    path: &str,
+    config: &Config,
+    manifest: &mut ChecksumManifest,
+) -> Result<(), Error> {
     // Resolve wildcard path to get matching files
     let files = path_utils::resolve_wildcard_path(hub, path)
         .await
@@ -165,6 +211,14 @@ async fn download_wildcard(hub: &Hub, path: &str, config: &Config) -> Result<(),
     let mut error_count = 0;
     
     for file in files {
+        if cancel::is_cancelled() {
+            eprintln!(
+                "Cancelled after downloading {} files successfully, {} errors",
+                success_count, error_count
+            );
+            return Err(Error::Cancelled);
+        }
+
         let file_name = file.name.clone().unwrap_or_default();
         
         // Create a new config for this specific file
@@ -175,6 +229,12 @@ async fn download_wildcard(hub: &Hub, path: &str, config: &Config) -> Result<(),
             follow_shortcuts: config.follow_shortcuts,
             download_directories: config.download_directories,
             destination: config.destination.clone(),
+            checksum_manifest: None,
+            space: config.space,
+            max_size: config.max_size,
+            filter: config.filter.clone(),
+            include_trashed: config.include_trashed,
+            save_as: None,
         };
         
         // If file is a folder and recursive flag is not set, skip it
@@ -185,9 +245,9 @@ async fn download_wildcard(hub: &Hub, path: &str, config: &Config) -> Result<(),
         
         // Download the file
         match if drive_file::is_directory(&file) {
-            download_directory(hub, &file, &file_config).await
+            download_directory(hub, &file, &file_config, manifest).await
         } else {
-            download_regular(hub, &file, &file_config).await
+            download_regular(hub, &file, &file_config, manifest).await
         } {
             Ok(_) => {
                 success_count += 1;
@@ -212,11 +272,19 @@ pub async fn download_regular(
     hub: &Hub,
     file: &google_drive3::api::File,
     config: &Config,
+    manifest: &mut ChecksumManifest,
 ) -> Result<(), Error> {
     // Use the file ID from the file object, not from the config
     // This ensures we use the resolved ID when path is provided
     let file_id = file.id.clone().unwrap_or_default();
-    let body = download_file(&hub, &file_id)
+    let file_size = file.size.unwrap_or(0).max(0) as u128;
+    err_if_too_large(
+        &file.name.clone().unwrap_or_default(),
+        file_size,
+        config.max_size,
+    )?;
+
+    let body = download_file(hub, &file_id)
         .await
         .map_err(Error::DownloadFile)?;
 
@@ -227,25 +295,42 @@ pub async fn download_regular(
         }
 
         _ => {
-            let file_name = file.name.clone().unwrap_or_else(|| "unknown_file".to_string());
+            let file_name = config
+                .local_file_name(file)
+                .unwrap_or_else(|| "unknown_file".to_string());
             let root_path = config.canonical_destination_root()?;
             let abs_file_path = root_path.join(&file_name);
 
             println!("Downloading {}", file_name);
             save_body_to_file(body, &abs_file_path, file.md5_checksum.clone()).await?;
             println!("Successfully downloaded {}", file_name);
+
+            add_to_manifest(manifest, Path::new(&file_name), &file.md5_checksum);
         }
     }
 
     Ok(())
 }
 
+// Checked before anything is downloaded, for a directory the size is the total of all its files
+pub fn err_if_too_large(name: &str, size: u128, max_size: Option<u64>) -> Result<(), Error> {
+    match max_size {
+        Some(max_size) if size > max_size.into() => Err(Error::TooLarge {
+            name: name.to_string(),
+            size,
+            max_size,
+        }),
+        _ => Ok(()),
+    }
+}
+
 pub async fn download_directory(
     hub: &Hub,
     file: &google_drive3::api::File,
     config: &Config,
+    manifest: &mut ChecksumManifest,
 ) -> Result<(), Error> {
-    let tree = FileTreeDrive::from_file(&hub, &file)
+    let tree = FileTreeDrive::from_file_filtered(hub, &file, config.space, &config.filter)
         .await
         .map_err(Error::CreateFileTree)?;
 
@@ -255,10 +340,17 @@ pub async fn download_directory(
         "Found {} files in {} directories with a total size of {}",
         tree_info.file_count,
         tree_info.folder_count,
-        human_bytes(tree_info.total_file_size as f64)
+        byte_size::format_human(tree_info.total_file_size)
     );
 
+    err_if_too_large(
+        &file.name.clone().unwrap_or_default(),
+        tree_info.total_file_size,
+        config.max_size,
+    )?;
+
     let root_path = config.canonical_destination_root()?;
+    let mut downloaded_count = 0;
 
     for folder in &tree.folders() {
         let folder_path = folder.relative_path();
@@ -268,33 +360,70 @@ pub async fn download_directory(
         fs::create_dir_all(&abs_folder_path)
             .map_err(|err| Error::CreateFile(err))?;
 
+        for name in &folder.skipped_documents {
+            manifest.push_skipped(
+                &folder_path.join(name),
+                "google document without checksum, not downloaded",
+            );
+        }
+
         for file in folder.files() {
+            if cancel::is_cancelled() {
+                eprintln!(
+                    "Cancelled after downloading {} of {} files",
+                    downloaded_count, tree_info.file_count
+                );
+                return Err(Error::Cancelled);
+            }
+
             let file_path = file.relative_path();
             let abs_file_path = root_path.join(&file_path);
 
-            if local_file_is_identical(&abs_file_path, &file) {
-                continue;
-            }
+            if !local_file_is_identical(&abs_file_path, &file) {
+                let body = download_file(hub, &file.drive_id)
+                    .await
+                    .map_err(Error::DownloadFile)?;
 
-            let body = download_file(&hub, &file.drive_id)
-                .await
-                .map_err(Error::DownloadFile)?;
+                println!("Downloading file '{}'", file_path.display());
+                save_body_to_file(body, &abs_file_path, file.md5.clone()).await?;
+            }
 
-            println!("Downloading file '{}'", file_path.display());
-            save_body_to_file(body, &abs_file_path, file.md5.clone()).await?;
+            add_to_manifest(manifest, &file_path, &file.md5);
+            downloaded_count += 1;
         }
     }
 
-    println!(
+    let summary = format!(
         "Downloaded {} files in {} directories with a total size of {}",
         tree_info.file_count,
         tree_info.folder_count,
-        human_bytes(tree_info.total_file_size as f64)
+        byte_size::format_human(tree_info.total_file_size)
     );
 
+    if config.filter.is_empty() {
+        println!("{}", summary);
+    } else if tree_info.depth_limited_count > 0 {
+        println!(
+            "{}, filtered out {} entries, {} directories were not walked because of the depth limit",
+            summary, tree_info.filtered_count, tree_info.depth_limited_count
+        );
+    } else {
+        println!(
+            "{}, filtered out {} entries",
+            summary, tree_info.filtered_count
+        );
+    }
+
     Ok(())
 }
 
+fn add_to_manifest(manifest: &mut ChecksumManifest, path: &Path, md5: &Option<String>) {
+    match md5 {
+        Some(md5) => manifest.push_checksum(path, md5),
+        None => manifest.push_skipped(path, "no checksum available"),
+    }
+}
+
 pub async fn download_file(hub: &Hub, file_id: &str) -> Result<hyper::Body, google_drive3::Error> {
     let (response, _) = hub
         .files()
@@ -310,7 +439,6 @@ pub async fn download_file(hub: &Hub, file_id: &str) -> Result<hyper::Body, goog
 
 #[derive(Debug)]
 pub enum Error {
-    Hub(hub_helper::Error),
     GetFile(google_drive3::Error),
     DownloadFile(google_drive3::Error),
     CreateFile(io::Error),
@@ -331,8 +459,18 @@ pub enum Error {
     ResolvePath(path_utils::PathResolutionError),
     AllDownloadsFailed,
     MissingFileName,
+    Cancelled,
     CreateDirectory(PathBuf, io::Error),
     CopyFile(io::Error),
+    WriteManifest(PathBuf, io::Error),
+    TooLarge {
+        name: String,
+        size: u128,
+        max_size: u64,
+    },
+    InvalidSaveAs(String),
+    SaveAsDirectory(String),
+    SaveAsMultipleFiles,
 }
 
 impl error::Error for Error {}
@@ -340,10 +478,14 @@ impl error::Error for Error {}
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::Hub(err) => write!(f, "{}", err),
-            Error::GetFile(err) => write!(f, "Failed getting file: {}", err),
-            Error::DownloadFile(err) => write!(f, "Failed to download file: {}", err),
+            Error::GetFile(err) => {
+                write!(f, "Failed getting file: {}", drive_error::describe(err))
+            }
+            Error::DownloadFile(err) => {
+                write!(f, "Failed to download file: {}", drive_error::describe(err))
+            }
             Error::MissingFileName => write!(f, "File does not have a name"),
+            Error::Cancelled => write!(f, "Download was cancelled"),
             Error::FileExists(path) => write!(
                 f,
                 "File '{}' already exists, use --overwrite to overwrite it",
@@ -406,6 +548,34 @@ impl Display for Error {
             Error::MissingFileName => write!(f, "Missing file name"),
             Error::CreateDirectory(path, err) => write!(f, "Failed to create directory '{}': {}", path.display(), err),
             Error::CopyFile(err) => write!(f, "Failed to copy file: {}", err),
+            Error::WriteManifest(path, err) => {
+                write!(f, "Failed to write manifest '{}': {}", path.display(), err)
+            }
+            Error::TooLarge {
+                name,
+                size,
+                max_size,
+            } => write!(
+                f,
+                "'{}' is {}, which exceeds the max size of {}. Nothing was downloaded",
+                name,
+                byte_size::format_human(*size),
+                byte_size::format_human(*max_size)
+            ),
+            Error::InvalidSaveAs(name) => write!(
+                f,
+                "'{}' can't be used with --as, give a file name without a directory",
+                name
+            ),
+            Error::SaveAsDirectory(name) => write!(
+                f,
+                "'{}' is a directory, --as can only be used when downloading a single file",
+                name
+            ),
+            Error::SaveAsMultipleFiles => write!(
+                f,
+                "--as can only be used when downloading a single file, not with a wildcard path"
+            ),
         }
     }
 }
@@ -418,21 +588,34 @@ pub async fn save_body_to_file(
     // Create temporary file
     let tmp_file_path = file_path.with_extension("incomplete");
     let file = File::create(&tmp_file_path).map_err(Error::CreateFile)?;
+    let _partial_file = cancel::PartialFile::new(&tmp_file_path);
+    let _in_progress = cancel::InProgress::new(file_path);
 
     // Wrap file in writer that calculates md5
     let mut writer = Md5Writer::new(file);
 
+    let result = write_body(&mut body, &mut writer)
+        .await
+        .and_then(|_| err_if_md5_mismatch(expected_md5, writer.md5()));
+
+    // Don't leave a partial file behind when the download failed
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_file_path);
+    }
+    result?;
+
+    // Rename temporary file to final file
+    fs::rename(&tmp_file_path, &file_path).map_err(Error::RenameFile)
+}
+
+async fn write_body<W: Write>(body: &mut hyper::Body, writer: &mut W) -> Result<(), Error> {
     // Read chunks from stream and write to file
     while let Some(chunk_result) = body.next().await {
         let chunk = chunk_result.map_err(Error::ReadChunk)?;
         writer.write_all(&chunk).map_err(Error::WriteChunk)?;
     }
 
-    // Check md5
-    err_if_md5_mismatch(expected_md5, writer.md5())?;
-
-    // Rename temporary file to final file
-    fs::rename(&tmp_file_path, &file_path).map_err(Error::RenameFile)
+    Ok(())
 }
 
 // TODO: move to common
@@ -448,8 +631,22 @@ pub async fn save_body_to_stdout(mut body: hyper::Body) -> Result<(), Error> {
     Ok(())
 }
 
+// The name is used in the destination directory, it can't point somewhere else
+fn err_if_invalid_save_as(name: &str) -> Result<(), Error> {
+    let is_plain_name = !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', std::path::MAIN_SEPARATOR]);
+
+    if is_plain_name {
+        Ok(())
+    } else {
+        Err(Error::InvalidSaveAs(name.to_string()))
+    }
+}
+
 fn err_if_file_exists(file: &google_drive3::api::File, config: &Config) -> Result<(), Error> {
-    let file_name = file.name.clone().ok_or(Error::MissingFileName)?;
+    let file_name = config.local_file_name(file).ok_or(Error::MissingFileName)?;
 
     let file_path = match &config.destination {
         Destination::CurrentDir => Some(PathBuf::from(".").join(file_name)),
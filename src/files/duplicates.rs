@@ -0,0 +1,170 @@
+use crate::common::drive_file;
+use crate::common::file_json::FileJson;
+use crate::files;
+use crate::files::info::DisplayConfig;
+use crate::files::list::ListFilesConfig;
+use crate::files::list::ListQuery;
+use crate::files::list::ListSortOrder;
+use crate::files::list::Space;
+use crate::hub::Hub;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+pub struct Config {
+    pub folder_id: String,
+    // Group by content instead of by name
+    pub by_md5: bool,
+    pub json: bool,
+    pub space: Space,
+}
+
+// Files of the folder sharing a name, or the same content with by_md5
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub md5_checksum: Option<String>,
+    pub files: Vec<FileJson>,
+}
+
+pub async fn find_duplicates(hub: &Hub, config: Config) -> Result<(), Error> {
+    let files = files::list::list_files(
+        hub,
+        &ListFilesConfig {
+            query: ListQuery::FilesInFolder {
+                folder_id: config.folder_id.clone(),
+            },
+            order_by: ListSortOrder::default(),
+            max_files: usize::MAX,
+            space: config.space,
+        },
+    )
+    .await
+    .map_err(Error::ListFiles)?;
+
+    let groups = duplicate_groups(&files, config.by_md5);
+
+    if config.json {
+        let json = serde_json::to_string_pretty(&groups).map_err(Error::SerializeJson)?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    if groups.is_empty() {
+        println!("No duplicates among {} files", files.len());
+        return Ok(());
+    }
+
+    for group in &groups {
+        print_group(group);
+    }
+
+    let duplicate_count: usize = groups.iter().map(|group| group.files.len()).sum();
+    println!(
+        "Found {} duplicate groups with {} files",
+        groups.len(),
+        duplicate_count
+    );
+
+    Ok(())
+}
+
+// Groups with more than one file, sorted by name or checksum. Files without a checksum,
+// like folders and google documents, are left out when grouping by md5.
+pub fn duplicate_groups(files: &[google_drive3::api::File], by_md5: bool) -> Vec<DuplicateGroup> {
+    group_files(files, by_md5)
+        .into_iter()
+        .map(|(key, files)| {
+            let (name, md5_checksum) = if by_md5 {
+                (None, Some(key))
+            } else {
+                (Some(key), None)
+            };
+
+            DuplicateGroup {
+                name,
+                md5_checksum,
+                files: files.iter().map(FileJson::from).collect(),
+            }
+        })
+        .collect()
+}
+
+// Files sharing a name or md5 checksum, keyed by it. Only keys with more than one file are kept.
+pub fn group_files(
+    files: &[google_drive3::api::File],
+    by_md5: bool,
+) -> Vec<(String, Vec<google_drive3::api::File>)> {
+    let mut groups: BTreeMap<String, Vec<google_drive3::api::File>> = BTreeMap::new();
+
+    for file in files {
+        let key = if by_md5 {
+            file.md5_checksum.clone()
+        } else {
+            file.name.clone()
+        };
+
+        if let Some(key) = key {
+            groups.entry(key).or_default().push(file.clone());
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, files)| files.len() > 1)
+        .collect()
+}
+
+fn print_group(group: &DuplicateGroup) {
+    match (&group.name, &group.md5_checksum) {
+        (Some(name), _) => println!("'{}' ({} files)", name, group.files.len()),
+        (_, Some(md5)) => println!("md5 {} ({} files)", md5, group.files.len()),
+        _ => {}
+    }
+
+    for file in &group.files {
+        let size = file
+            .size
+            .map(|bytes| files::info::format_bytes(bytes, &DisplayConfig::default()))
+            .unwrap_or_else(|| String::from("-"));
+
+        let description = if file.mime_type == drive_file::MIME_TYPE_DRIVE_FOLDER {
+            String::from("folder")
+        } else {
+            file.md5_checksum
+                .clone()
+                .unwrap_or_else(|| String::from("-"))
+        };
+
+        // The name differs within a group of identical content
+        if group.name.is_none() {
+            println!("  {}\t{}\t{}\t{}", file.id, file.name, size, description);
+        } else {
+            println!("  {}\t{}\t{}", file.id, size, description);
+        }
+    }
+
+    println!();
+}
+
+#[derive(Debug)]
+pub enum Error {
+    ListFiles(files::list::Error),
+    SerializeJson(serde_json::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::ListFiles(err) => write!(f, "Failed to list files: {}", err),
+            Error::SerializeJson(err) => write!(f, "Failed to serialize json: {}", err),
+        }
+    }
+}
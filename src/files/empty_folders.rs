@@ -0,0 +1,99 @@
+use crate::common::remote_walk;
+use crate::common::remote_walk::DriveLister;
+use crate::common::remote_walk::FolderLister;
+use crate::common::remote_walk::WalkConfig;
+use crate::common::table;
+use crate::common::table::Table;
+use crate::files::list::Space;
+use crate::hub::Hub;
+use std::collections::HashSet;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::io;
+
+pub struct Config {
+    pub folder_id: String,
+    pub skip_header: bool,
+    pub field_separator: String,
+    pub space: Space,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmptyFolder {
+    pub id: String,
+    // Relative to the folder the search starts from
+    pub path: String,
+}
+
+pub async fn list_empty_folders(hub: &Hub, config: Config) -> Result<(), Error> {
+    let lister = DriveLister::new(hub, config.space);
+    let folders = find_empty_folders(&lister, &config.folder_id).await?;
+
+    if folders.is_empty() {
+        eprintln!("No empty folders found");
+        return Ok(());
+    }
+
+    let values: Vec<[String; 2]> = folders
+        .into_iter()
+        .map(|folder| [folder.id, folder.path])
+        .collect();
+
+    let table = Table {
+        header: ["Id", "Path"],
+        values,
+    };
+
+    let _ = table::write(
+        io::stdout(),
+        table,
+        &table::DisplayConfig {
+            skip_header: config.skip_header,
+            separator: config.field_separator,
+        },
+    );
+
+    Ok(())
+}
+
+// Walks every folder below folder_id and returns the ones without any children, sorted by
+// path. A folder only holding empty folders isn't empty itself. The folder the search starts
+// from is never returned.
+pub async fn find_empty_folders<L: FolderLister>(
+    lister: &L,
+    folder_id: &str,
+) -> Result<Vec<EmptyFolder>, Error> {
+    let entries = remote_walk::walk_all(lister, folder_id, &WalkConfig::default())
+        .await
+        .map_err(Error::Walk)?;
+
+    let parents: HashSet<&str> = entries.iter().map(|entry| entry.parent_path()).collect();
+
+    let mut empty_folders: Vec<EmptyFolder> = entries
+        .iter()
+        .filter(|entry| entry.is_folder() && !parents.contains(entry.path.as_str()))
+        .map(|entry| EmptyFolder {
+            id: entry.file.id.clone().unwrap_or_default(),
+            path: entry.path.clone(),
+        })
+        .collect();
+
+    empty_folders.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(empty_folders)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Walk(remote_walk::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Walk(err) => write!(f, "{}", err),
+        }
+    }
+}
@@ -1,16 +1,25 @@
 use mime::Mime;
 
+use crate::common::drive_error;
 use crate::common::drive_file;
 use crate::common::drive_file::DocType;
 use crate::common::drive_file::FileExtension;
-use crate::common::hub_helper;
 use crate::files;
+use crate::files::path_utils;
 use crate::hub::Hub;
+use std::collections::HashMap;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::path::Path;
 use std::path::PathBuf;
 
+const EXPORT_FILE_FIELDS: &str = "id,name,mimeType,md5Checksum,exportLinks";
+
+const MAX_REDIRECTS: usize = 5;
+
 #[derive(Clone, Debug)]
 pub struct Config {
     pub file_id: String,
@@ -24,12 +33,12 @@ pub enum ExistingFileAction {
     Overwrite,
 }
 
-pub async fn export(config: Config) -> Result<(), Error> {
-    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
-
-    err_if_file_exists(&config)?;
+pub async fn export(hub: &Hub, config: Config) -> Result<(), Error> {
+    let file_id = path_utils::resolve_file_id(hub, &config.file_id)
+        .await
+        .map_err(Error::ResolvePath)?;
 
-    let file = files::info::get_file(&hub, &config.file_id)
+    let file = files::info::get_file_with_fields(hub, &file_id, EXPORT_FILE_FIELDS)
         .await
         .map_err(Error::GetFile)?;
 
@@ -37,35 +46,128 @@ pub async fn export(config: Config) -> Result<(), Error> {
     let doc_type = DocType::from_mime_type(&drive_mime)
         .ok_or(Error::UnsupportedDriveMime(drive_mime.clone()))?;
 
-    let extension = FileExtension::from_path(&config.file_path)
-        .ok_or(Error::UnsupportedExportExtension(doc_type.clone()))?;
+    let file_path = with_default_extension(&config.file_path, &doc_type);
+    if file_path != config.file_path {
+        println!(
+            "No file extension given, exporting as {}",
+            file_path.display()
+        );
+    }
+
+    err_if_file_exists(&file_path, &config)?;
 
-    err_if_unsupported(&doc_type, &extension)?;
+    let export_links = file.export_links.unwrap_or_default();
+    let mime_type = export_mime(&file_path, &doc_type, &export_links)?;
 
-    let mime_type = extension
-        .get_export_mime()
-        .ok_or(Error::GetFileExtensionMime(extension.clone()))?;
+    let body = match export_file(hub, &file_id, &mime_type).await {
+        Ok(body) => body,
 
-    let body = export_file(&hub, &config.file_id, &mime_type)
-        .await
-        .map_err(Error::ExportFile)?;
+        // The export links aren't limited to 10 MB like files.export
+        Err(err) if drive_error::is_export_size_limit_exceeded(&err) => {
+            let link = export_link(&export_links, &mime_type)
+                .ok_or_else(|| Error::ExportSizeLimitExceeded(mime_type.to_string()))?;
+
+            println!(
+                "The export is larger than 10 MB, downloading it from the export link instead"
+            );
+            download_export_link(hub, link)
+                .await
+                .map_err(Error::ExportLink)?
+        }
+
+        Err(err) => return Err(Error::ExportFile(err)),
+    };
+
+    create_parent_dirs(&file_path)?;
 
     println!(
         "Exporting {} '{}' to {}",
         doc_type,
         file.name.unwrap_or_default(),
-        config.file_path.display()
+        file_path.display()
     );
 
-    files::download::save_body_to_file(body, &config.file_path, file.md5_checksum)
+    files::download::save_body_to_file(body, &file_path, file.md5_checksum)
         .await
         .map_err(Error::SaveFile)?;
 
-    println!("Successfully exported {}", config.file_path.display());
+    println!("Successfully exported {}", file_path.display());
 
     Ok(())
 }
 
+// Missing directories of the local path are created, like download does
+pub fn create_parent_dirs(file_path: &Path) -> Result<(), Error> {
+    match file_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() && !parent.exists() => {
+            println!("Creating directory {}", parent.display());
+            fs::create_dir_all(parent)
+                .map_err(|err| Error::CreateParentDir(parent.to_path_buf(), err))
+        }
+        _ => Ok(()),
+    }
+}
+
+// Picks the format to export to from the file extension. The formats in the export links of the
+// file are the ones Drive can actually convert it to, without them the built-in list is used.
+pub fn export_mime(
+    file_path: &PathBuf,
+    doc_type: &DocType,
+    export_links: &HashMap<String, String>,
+) -> Result<Mime, Error> {
+    if export_links.is_empty() {
+        let extension = FileExtension::from_path(file_path)
+            .ok_or(Error::UnsupportedExportExtension(doc_type.clone()))?;
+
+        err_if_unsupported(doc_type, &extension)?;
+
+        return extension
+            .get_export_mime()
+            .ok_or(Error::GetFileExtensionMime(extension.clone()));
+    }
+
+    let extension = file_path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    // The mime type of a known extension comes first, other extensions are guessed
+    let known_mime =
+        FileExtension::from_extension(&extension).and_then(|ext| ext.get_export_mime());
+    let guessed_mimes = mime_guess::from_ext(&extension).iter();
+
+    known_mime
+        .into_iter()
+        .chain(guessed_mimes)
+        .find(|mime| export_links.contains_key(mime.essence_str()))
+        .ok_or_else(|| Error::UnsupportedExportFormat {
+            extension,
+            doc_type: doc_type.clone(),
+            supported: supported_formats(export_links),
+        })
+}
+
+// Extensions of the export formats, the mime type is used when there is no known extension
+pub fn supported_formats(export_links: &HashMap<String, String>) -> Vec<String> {
+    let mut formats: Vec<String> = export_links
+        .keys()
+        .map(|mime| {
+            FileExtension::from_export_mime(mime)
+                .map(|ext| ext.to_string())
+                .or_else(|| {
+                    mime_guess::get_mime_extensions_str(mime)
+                        .and_then(|extensions| extensions.first())
+                        .map(|ext| ext.to_string())
+                })
+                .unwrap_or_else(|| mime.clone())
+        })
+        .collect();
+
+    formats.sort();
+    formats.dedup();
+    formats
+}
+
 pub async fn export_file(
     hub: &Hub,
     file_id: &str,
@@ -81,16 +183,122 @@ pub async fn export_file(
     Ok(response.into_body())
 }
 
+// Export link of the file for the mime type, from the exportLinks of files.get
+pub fn export_link<'a>(
+    export_links: &'a HashMap<String, String>,
+    mime_type: &Mime,
+) -> Option<&'a str> {
+    export_links
+        .get(mime_type.essence_str())
+        .map(|link| link.as_str())
+}
+
+// Downloads an export link with the access token of the hub, following redirects
+pub async fn download_export_link(hub: &Hub, link: &str) -> Result<hyper::Body, LinkError> {
+    let scopes = [google_drive3::api::Scope::Full.as_ref()];
+    let token = hub
+        .auth
+        .get_token(&scopes)
+        .await
+        .map_err(|err| LinkError::Token(err.to_string()))?
+        .ok_or_else(|| LinkError::Token(String::from("no access token")))?;
+
+    let mut current_url = link.to_string();
+    let mut redirects = 0;
+
+    loop {
+        let uri: hyper::Uri = current_url
+            .parse()
+            .map_err(|_| LinkError::InvalidUrl(current_url.clone()))?;
+
+        let mut req = hyper::Request::builder().method(hyper::Method::GET);
+
+        // The token is only sent to Google, redirects to other hosts are followed without it
+        if is_google_host(&uri) {
+            req = req.header(hyper::header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+
+        let req = req
+            .uri(uri)
+            .body(hyper::Body::empty())
+            .map_err(LinkError::BuildRequest)?;
+
+        let res = hub.client.request(req).await.map_err(LinkError::Request)?;
+
+        if res.status().is_success() {
+            return Ok(res.into_body());
+        }
+
+        if !res.status().is_redirection() {
+            return Err(LinkError::Status(res.status()));
+        }
+
+        redirects += 1;
+        if redirects > MAX_REDIRECTS {
+            return Err(LinkError::TooManyRedirects);
+        }
+
+        current_url = res
+            .headers()
+            .get(hyper::header::LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .map(|location| location.to_string())
+            .ok_or(LinkError::Status(res.status()))?;
+    }
+}
+
+fn is_google_host(uri: &hyper::Uri) -> bool {
+    uri.host().map_or(false, |host| {
+        host == "google.com"
+            || host.ends_with(".google.com")
+            || host.ends_with(".googleapis.com")
+            || host.ends_with(".googleusercontent.com")
+    })
+}
+
+#[derive(Debug)]
+pub enum LinkError {
+    Token(String),
+    InvalidUrl(String),
+    BuildRequest(hyper::http::Error),
+    Request(hyper::Error),
+    Status(hyper::StatusCode),
+    TooManyRedirects,
+}
+
+impl error::Error for LinkError {}
+
+impl Display for LinkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkError::Token(err) => write!(f, "Failed to get access token: {}", err),
+            LinkError::InvalidUrl(url) => write!(f, "Invalid export link '{}'", url),
+            LinkError::BuildRequest(err) => write!(f, "Failed to build request: {}", err),
+            LinkError::Request(err) => write!(f, "Request failed: {}", err),
+            LinkError::Status(status) => write!(f, "Server responded with {}", status),
+            LinkError::TooManyRedirects => write!(f, "Too many redirects"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
-    Hub(hub_helper::Error),
     FileExists(PathBuf),
+    ResolvePath(path_utils::PathResolutionError),
+    CreateParentDir(PathBuf, io::Error),
     GetFile(google_drive3::Error),
     ExportFile(google_drive3::Error),
+    ExportSizeLimitExceeded(String),
+    ExportLink(LinkError),
     MissingDriveMime,
     UnsupportedDriveMime(String),
     GetFileExtensionMime(drive_file::FileExtension),
     UnsupportedExportExtension(DocType),
+    UnsupportedExportFormat {
+        extension: String,
+        doc_type: DocType,
+        supported: Vec<String>,
+    },
     SaveFile(files::download::Error),
 }
 
@@ -99,7 +307,6 @@ impl error::Error for Error {}
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::Hub(err) => write!(f, "{}", err),
             Error::FileExists(path) => {
                 write!(
                     f,
@@ -107,15 +314,38 @@ impl Display for Error {
                     path.display()
                 )
             }
+            Error::ResolvePath(err) => write!(f, "Failed to resolve path: {}", err),
+            Error::CreateParentDir(path, err) => {
+                write!(
+                    f,
+                    "Failed to create directory '{}': {}",
+                    path.display(),
+                    err
+                )
+            }
             Error::GetFile(err) => {
                 write!(f, "Failed to get file: {}", err)
             }
             Error::ExportFile(err) => {
                 write!(f, "Failed to export file: {}", err)
             }
+            Error::ExportSizeLimitExceeded(mime_type) => write!(
+                f,
+                "The export is larger than Drive's 10 MB export limit and the file has no export link for {}",
+                mime_type
+            ),
+            Error::ExportLink(err) => write!(
+                f,
+                "The export is larger than Drive's 10 MB export limit, downloading it from the export link failed: {}",
+                err
+            ),
             Error::MissingDriveMime => write!(f, "Drive file does not have a mime type"),
             Error::UnsupportedDriveMime(mime) => {
-                write!(f, "Mime type on drive file '{}' is not supported", mime)
+                write!(
+                    f,
+                    "Only google documents, spreadsheets and presentations can be exported, the drive file has mime type '{}'. Use `gdrive files download` for regular files",
+                    mime
+                )
             }
             Error::GetFileExtensionMime(doc_type) => write!(
                 f,
@@ -137,6 +367,17 @@ impl Display for Error {
                     supported_types
                 )
             }
+            Error::UnsupportedExportFormat {
+                extension,
+                doc_type,
+                supported,
+            } => write!(
+                f,
+                "This {} can't be exported to '{}', it can be exported to: {}",
+                doc_type,
+                extension,
+                supported.join(", ")
+            ),
             Error::SaveFile(err) => {
                 write!(f, "Failed to save file: {}", err)
             }
@@ -144,9 +385,18 @@ impl Display for Error {
     }
 }
 
-fn err_if_file_exists(config: &Config) -> Result<(), Error> {
-    if config.file_path.exists() && config.existing_file_action == ExistingFileAction::Abort {
-        Err(Error::FileExists(config.file_path.clone()))
+// Appends the default export extension for the document type if the path has no extension
+pub fn with_default_extension(path: &PathBuf, doc_type: &DocType) -> PathBuf {
+    if path.extension().is_some() {
+        path.clone()
+    } else {
+        path.with_extension(doc_type.default_export_type().to_string())
+    }
+}
+
+fn err_if_file_exists(file_path: &PathBuf, config: &Config) -> Result<(), Error> {
+    if file_path.exists() && config.existing_file_action == ExistingFileAction::Abort {
+        Err(Error::FileExists(file_path.clone()))
     } else {
         Ok(())
     }
@@ -1,75 +1,430 @@
+use crate::common::delegate::UploadDelegate;
 use crate::common::delegate::UploadDelegateConfig;
 use crate::common::drive_file;
 use crate::common::drive_file::DocType;
 use crate::common::file_info;
+use crate::common::file_json::FileJson;
 use crate::common::file_info::FileInfo;
-use crate::common::hub_helper;
+use crate::common::read_only;
+use crate::common::url_fetch;
 use crate::files;
 use crate::files::info::DisplayConfig;
+use crate::files::list::ListFilesConfig;
+use crate::files::list::ListQuery;
+use crate::hub::Hub;
+use mime::Mime;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::fs;
 use std::io;
+use std::io::Seek;
+use std::io::SeekFrom;
 use std::path::PathBuf;
+use std::str::FromStr;
+
+// Content larger than this is buffered in a temporary file instead of memory
+const MAX_IN_MEMORY_SIZE: usize = 16 * 1024 * 1024;
 
 #[derive(Clone, Debug)]
 pub struct Config {
-    pub file_path: PathBuf,
+    pub source: ImportSource,
     pub parents: Option<Vec<String>>,
     pub print_only_id: bool,
+    pub doc_type: Option<DocType>,
+    pub name: Option<String>,
+    pub source_mime: Option<Mime>,
+    pub max_size: u64,
+    pub json: bool,
+    pub existing: ExistingDoc,
+    // Create the missing directories of parents given as a path
+    pub create_parents: bool,
+}
+
+// What to do about a document imported earlier
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExistingDoc {
+    // Always create a new document
+    Ignore,
+    // Update the document with the same name and type in the parent folder, if there is one
+    Overwrite,
+    // Update the document with this id
+    Update(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportAction {
+    Created,
+    Updated,
+}
+
+impl Config {
+    // Progress messages are left out when only the id or json is printed
+    fn is_quiet(&self) -> bool {
+        self.print_only_id || self.json
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportSource {
+    Path(PathBuf),
+    Stdin,
+    Url(String),
+}
+
+impl FromStr for ImportSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "-" {
+            Ok(ImportSource::Stdin)
+        } else if s.starts_with("https://") {
+            Ok(ImportSource::Url(s.to_string()))
+        } else if s.starts_with("http://") {
+            Err(String::from("Only https urls are supported"))
+        } else {
+            Ok(ImportSource::Path(PathBuf::from(s)))
+        }
+    }
+}
+
+impl ImportSource {
+    // Parses the value of --url, which unlike the source argument is never a path
+    pub fn from_url(s: &str) -> Result<ImportSource, String> {
+        match ImportSource::from_str(s)? {
+            ImportSource::Url(url) => Ok(ImportSource::Url(url)),
+            _ => Err(format!("'{}' is not a https:// url", s)),
+        }
+    }
+}
+
+impl Display for ImportSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportSource::Path(path) => write!(f, "{}", path.display()),
+            ImportSource::Stdin => write!(f, "stdin"),
+            ImportSource::Url(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+pub async fn import(hub: &Hub, config: Config) -> Result<(), Error> {
+    read_only::err_if_enabled("import files").map_err(Error::ReadOnly)?;
+
+    let parents = match &config.parents {
+        Some(parents) => Some(resolve_parents(hub, &config, parents).await?),
+        None => None,
+    };
+    let config = Config { parents, ..config };
+
+    let (file, action) = match &config.source {
+        ImportSource::Path(path) => import_path(hub, &config, path).await?,
+        ImportSource::Stdin => import_stdin(hub, &config).await?,
+        ImportSource::Url(url) => import_url(hub, &config, url).await?,
+    };
+
+    if config.json {
+        let json = serde_json::to_string(&FileJson::from(&file)).map_err(Error::SerializeJson)?;
+        println!("{}", json);
+    } else if config.print_only_id {
+        print!("{}", file.id.unwrap_or_default())
+    } else {
+        match action {
+            ImportAction::Created => println!("File successfully imported"),
+            ImportAction::Updated => println!("Existing file successfully updated"),
+        }
+        let fields = files::info::prepare_fields(&file, &DisplayConfig::default());
+        files::info::print_fields(&fields);
+    }
+
+    Ok(())
 }
 
-pub async fn import(config: Config) -> Result<(), Error> {
-    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+async fn import_path(
+    hub: &Hub,
+    config: &Config,
+    path: &PathBuf,
+) -> Result<(google_drive3::api::File, ImportAction), Error> {
     let delegate_config = UploadDelegateConfig::default();
 
-    let doc_type =
-        drive_file::DocType::from_file_path(&config.file_path).ok_or(Error::UnsupportedFileType)?;
+    let doc_type = config
+        .doc_type
+        .clone()
+        .or_else(|| drive_file::DocType::from_file_path(path))
+        .ok_or(Error::UnsupportedFileType)?;
     let mime_type = doc_type.mime().ok_or(Error::GetMime(doc_type.clone()))?;
 
-    let file = fs::File::open(&config.file_path)
-        .map_err(|err| Error::OpenFile(config.file_path.clone(), err))?;
+    let file = fs::File::open(path).map_err(|err| Error::OpenFile(path.clone(), err))?;
 
-    let file_info = FileInfo::from_file(
+    let mut file_info = FileInfo::from_file(
         &file,
         &file_info::Config {
-            file_path: config.file_path.clone(),
+            file_path: path.clone(),
             mime_type: Some(mime_type),
             parents: config.parents.clone(),
         },
     )
     .map_err(Error::FileInfo)?;
 
+    if let Some(name) = &config.name {
+        file_info.name = name.clone();
+    }
+
     let reader = std::io::BufReader::new(file);
 
-    if !config.print_only_id {
-        println!("Importing {} as a {}", config.file_path.display(), doc_type);
+    let existing_id = find_existing(hub, config, &file_info.name, &mime_type).await?;
+
+    if !config.is_quiet() {
+        print_importing(&config.source, &doc_type, &existing_id);
     }
 
-    let file = files::upload::upload_file(&hub, reader, None, file_info, delegate_config)
+    match existing_id {
+        Some(file_id) => {
+            let file = files::update::update_file(hub, reader, &file_id, file_info, delegate_config)
+                .await
+                .map_err(Error::Convert)?;
+            Ok((file, ImportAction::Updated))
+        }
+
+        None => {
+            let file = files::upload::upload_file(hub, reader, None, file_info, delegate_config)
+                .await
+                .map_err(Error::Convert)?;
+            Ok((file, ImportAction::Created))
+        }
+    }
+}
+
+// Parents given as a path are resolved to their folder id, the resolved ids are reported
+async fn resolve_parents(
+    hub: &Hub,
+    config: &Config,
+    parents: &[String],
+) -> Result<Vec<String>, Error> {
+    let parent_ids = files::path_utils::resolve_parent_ids(hub, parents, config.create_parents)
         .await
-        .map_err(Error::UploadFile)?;
+        .map_err(Error::ResolveParent)?;
 
-    if config.print_only_id {
-        print!("{}", file.id.unwrap_or_default())
-    } else {
-        println!("File successfully imported");
-        let fields = files::info::prepare_fields(&file, &DisplayConfig::default());
-        files::info::print_fields(&fields);
+    if !config.is_quiet() {
+        for (parent, parent_id) in parents.iter().zip(&parent_ids) {
+            if files::path_utils::is_path(parent) {
+                println!("Resolved parent '{}' to {}", parent, parent_id);
+            }
+        }
     }
 
-    Ok(())
+    Ok(parent_ids)
+}
+
+// Returns the id of the document to update instead of creating a new one
+async fn find_existing(
+    hub: &Hub,
+    config: &Config,
+    name: &str,
+    mime_type: &Mime,
+) -> Result<Option<String>, Error> {
+    match &config.existing {
+        ExistingDoc::Ignore => Ok(None),
+        ExistingDoc::Update(file_id) => Ok(Some(file_id.clone())),
+        ExistingDoc::Overwrite => {
+            let parent_id = config
+                .parents
+                .as_ref()
+                .and_then(|parents| parents.first())
+                .map_or("root", |parent_id| parent_id.as_str());
+
+            let query = format!(
+                "'{}' in parents and name = '{}' and mimeType = '{}' and trashed = false",
+                parent_id,
+                files::list::escape_query_value(name),
+                mime_type
+            );
+
+            let list_config = ListFilesConfig {
+                query: ListQuery::Custom(query),
+                order_by: Default::default(),
+                max_files: 2,
+                space: Default::default(),
+            };
+
+            let existing = files::list::list_files(hub, &list_config)
+                .await
+                .map_err(Error::FindExisting)?;
+
+            match existing.as_slice() {
+                [] => Ok(None),
+                [file] => Ok(file.id.clone()),
+                _ => Err(Error::AmbiguousExisting(name.to_string())),
+            }
+        }
+    }
+}
+
+fn print_importing(source: &ImportSource, doc_type: &DocType, existing_id: &Option<String>) {
+    match existing_id {
+        Some(file_id) => println!("Importing {} as a {}, updating {}", source, doc_type, file_id),
+        None => println!("Importing {} as a {}", source, doc_type),
+    }
+}
+
+async fn import_stdin(
+    hub: &Hub,
+    config: &Config,
+) -> Result<(google_drive3::api::File, ImportAction), Error> {
+    let name = config.name.clone().ok_or(Error::MissingName)?;
+    let source_mime = config.source_mime.clone().ok_or(Error::MissingSourceMime)?;
+
+    let mut buffer = tempfile::spooled_tempfile(MAX_IN_MEMORY_SIZE);
+    let size = io::copy(&mut io::stdin().lock(), &mut buffer).map_err(Error::ReadStdin)?;
+    buffer.seek(SeekFrom::Start(0)).map_err(Error::ReadStdin)?;
+
+    import_content(hub, config, buffer, size, name, source_mime).await
+}
+
+async fn import_url(
+    hub: &Hub,
+    config: &Config,
+    url: &str,
+) -> Result<(google_drive3::api::File, ImportAction), Error> {
+    let mut buffer = tempfile::spooled_tempfile(MAX_IN_MEMORY_SIZE);
+
+    let fetched = url_fetch::fetch(url, config.max_size, &mut buffer)
+        .await
+        .map_err(|err| Error::Fetch(url.to_string(), err))?;
+    buffer
+        .seek(SeekFrom::Start(0))
+        .map_err(|err| Error::Fetch(url.to_string(), url_fetch::Error::Write(err)))?;
+
+    let name = config
+        .name
+        .clone()
+        .or(fetched.file_name)
+        .ok_or(Error::MissingName)?;
+
+    let source_mime = config
+        .source_mime
+        .clone()
+        .or(fetched.mime_type)
+        .or_else(|| mime_guess::from_path(&name).first())
+        .ok_or(Error::MissingSourceMime)?;
+
+    import_content(hub, config, buffer, fetched.size, name, source_mime).await
+}
+
+// Creates or updates a google document from content that doesn't exist as a local file
+async fn import_content<RS>(
+    hub: &Hub,
+    config: &Config,
+    content: RS,
+    size: u64,
+    name: String,
+    source_mime: Mime,
+) -> Result<(google_drive3::api::File, ImportAction), Error>
+where
+    RS: google_drive3::client::ReadSeek,
+{
+    let delegate_config = UploadDelegateConfig::default();
+
+    let doc_type = config
+        .doc_type
+        .clone()
+        .or_else(|| DocType::from_source_mime(&source_mime))
+        .ok_or(Error::UnsupportedFileType)?;
+    let mime_type = doc_type.mime().ok_or(Error::GetMime(doc_type.clone()))?;
+
+    let existing_id = find_existing(hub, config, &name, &mime_type).await?;
+
+    if !config.is_quiet() {
+        print_importing(&config.source, &doc_type, &existing_id);
+    }
+
+    // Updating a google document with content of another type converts it, like creating does
+    if let Some(file_id) = existing_id {
+        let file_info = FileInfo {
+            name,
+            mime_type: source_mime,
+            parents: None,
+            size,
+            modified_time: None,
+        };
+
+        let file = files::update::update_file(hub, content, &file_id, file_info, delegate_config)
+            .await
+            .map_err(Error::Convert)?;
+
+        return Ok((file, ImportAction::Updated));
+    }
+
+    let file_info = FileInfo {
+        name,
+        mime_type: source_mime,
+        parents: config.parents.clone(),
+        size,
+        modified_time: None,
+    };
+
+    let file = create_document(hub, content, file_info, &mime_type, delegate_config)
+        .await
+        .map_err(Error::Convert)?;
+
+    Ok((file, ImportAction::Created))
+}
+
+// Creates a google document of doc_mime from the content, which has the mime type of file_info.
+// Drive converts the content when the mime types differ.
+pub async fn create_document<RS>(
+    hub: &Hub,
+    content: RS,
+    file_info: FileInfo,
+    doc_mime: &Mime,
+    delegate_config: UploadDelegateConfig,
+) -> Result<google_drive3::api::File, google_drive3::Error>
+where
+    RS: google_drive3::client::ReadSeek,
+{
+    let dst_file = google_drive3::api::File {
+        name: Some(file_info.name),
+        mime_type: Some(doc_mime.to_string()),
+        parents: file_info.parents,
+        ..google_drive3::api::File::default()
+    };
+
+    let resumable = delegate_config.is_resumable(file_info.size);
+    let mut delegate = UploadDelegate::new(delegate_config);
+
+    let req = hub
+        .files()
+        .create(dst_file)
+        .param("fields", "id,name,size,createdTime,modifiedTime,md5Checksum,mimeType,parents,shared,description,webContentLink,webViewLink")
+        .add_scope(google_drive3::api::Scope::Full)
+        .delegate(&mut delegate)
+        .supports_all_drives(true);
+
+    let (_, file) = if resumable {
+        req.upload_resumable(content, file_info.mime_type).await?
+    } else {
+        req.upload(content, file_info.mime_type).await?
+    };
+
+    Ok(file)
 }
 
 #[derive(Debug)]
 pub enum Error {
-    Hub(hub_helper::Error),
+    ReadOnly(read_only::Error),
     OpenFile(PathBuf, io::Error),
     FileInfo(file_info::Error),
-    UploadFile(google_drive3::Error),
+    Convert(google_drive3::Error),
     UnsupportedFileType,
     GetMime(drive_file::DocType),
+    MissingName,
+    MissingSourceMime,
+    ReadStdin(io::Error),
+    Fetch(String, url_fetch::Error),
+    SerializeJson(serde_json::Error),
+    FindExisting(files::list::Error),
+    AmbiguousExisting(String),
+    ResolveParent(files::path_utils::PathResolutionError),
 }
 
 impl error::Error for Error {}
@@ -77,17 +432,17 @@ impl error::Error for Error {}
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::Hub(err) => write!(f, "{}", err),
+            Error::ReadOnly(err) => write!(f, "{}", err),
             Error::OpenFile(path, err) => {
                 write!(f, "Failed to open file '{}': {}", path.display(), err)
             }
             Error::FileInfo(err) => write!(f, "Failed to get file info: {}", err),
-            Error::UploadFile(err) => {
-                write!(f, "Failed to upload file: {}", err)
+            Error::Convert(err) => {
+                write!(f, "Drive failed to import the file: {}", err)
             }
             Error::UnsupportedFileType => write!(
                 f,
-                "Unsupported file type, supported file types: {}",
+                "Unsupported file type, supported file types: {}. Use --to to choose the document type",
                 DocType::supported_import_types().join(", ")
             ),
             Error::GetMime(doc_type) => write!(
@@ -95,6 +450,20 @@ impl Display for Error {
                 "Failed to get mime type from document type: {}",
                 doc_type
             ),
+            Error::MissingName => write!(f, "--name is required when importing from this source"),
+            Error::MissingSourceMime => {
+                write!(f, "--source-mime is required when importing from this source")
+            }
+            Error::ReadStdin(err) => write!(f, "Failed to read from stdin: {}", err),
+            Error::Fetch(url, err) => write!(f, "Failed to fetch '{}': {}", url, err),
+            Error::SerializeJson(err) => write!(f, "Failed to serialize json: {}", err),
+            Error::FindExisting(err) => write!(f, "Failed to look for an existing file: {}", err),
+            Error::ResolveParent(err) => write!(f, "Failed to resolve parent: {}", err),
+            Error::AmbiguousExisting(name) => write!(
+                f,
+                "Several documents named '{}' exist in the folder, use --update <FILE_ID> to pick one",
+                name
+            ),
         }
     }
 }
@@ -1,45 +1,116 @@
 use google_drive3::chrono;
 use google_drive3::chrono::DateTime;
-use human_bytes::human_bytes;
 
-use crate::common::hub_helper;
+use crate::common::byte_size;
+use crate::common::byte_size::SizeUnits;
+use crate::common::drive_api::DriveApi;
+use crate::common::drive_error;
+use crate::common::drive_file;
+use crate::common::table;
+use crate::common::table::Table;
+use crate::common::time_fmt;
+use crate::common::time_fmt::TimeStyle;
+use crate::files::path_utils;
 use crate::hub::Hub;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::io;
+
+const FILE_FIELDS: &str = "id,name,size,createdTime,modifiedTime,md5Checksum,mimeType,parents,shared,description,webContentLink,webViewLink,shortcutDetails(targetId,targetMimeType)";
+
+// Every field of the file, for --raw
+pub const ALL_FIELDS: &str = "*";
+
+// Permissions are only returned when the current account can read them
+const SHARING_FIELDS: &str = "capabilities,ownedByMe,owners(displayName,emailAddress),sharingUser(displayName,emailAddress),permissions(id,role,type,emailAddress)";
 
 pub struct Config {
     pub file_id: String,
     pub size_in_bytes: bool,
+    pub check_access: bool,
+    pub capabilities: bool,
+    pub json: bool,
+    // Fields to request and print unprocessed as json
+    pub raw_fields: Option<String>,
+    // Let a path name a trashed file
+    pub include_trashed: bool,
+    pub size_units: SizeUnits,
+    pub time_style: TimeStyle,
 }
 
-pub async fn info(config: Config) -> Result<(), Error> {
-    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+pub async fn info(hub: &Hub, config: Config) -> Result<(), Error> {
+    let file_id =
+        path_utils::resolve_file_id_with_trashed(hub, &config.file_id, config.include_trashed)
+            .await
+            .map_err(Error::ResolvePath)?;
 
-    let file = get_file(&hub, &config.file_id)
-        .await
-        .map_err(Error::GetFile)?;
+    if let Some(fields) = &config.raw_fields {
+        let file = get_file_with_fields(hub, &file_id, fields)
+            .await
+            .map_err(Error::GetFile)?;
+        let json = serde_json::to_string_pretty(&file).map_err(Error::SerializeJson)?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    let file = if config.capabilities {
+        let fields = format!("{},{}", FILE_FIELDS, SHARING_FIELDS);
+        get_file_with_fields(hub, &file_id, &fields).await
+    } else if config.check_access {
+        let fields = format!("{},capabilities", FILE_FIELDS);
+        get_file_with_fields(hub, &file_id, &fields).await
+    } else {
+        get_file(hub, &file_id).await
+    }
+    .map_err(Error::GetFile)?;
 
-    let fields = prepare_fields(
+    if config.json {
+        let json = serde_json::to_string_pretty(&file).map_err(Error::SerializeJson)?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    let mut fields = prepare_fields(
         &file,
         &DisplayConfig {
             size_in_bytes: config.size_in_bytes,
+            size_units: config.size_units,
+            time_style: config.time_style.clone(),
         },
     );
 
+    if config.capabilities {
+        fields.extend(sharing_fields(&file));
+    }
+
     print_fields(&fields);
 
+    if config.check_access {
+        println!();
+        let table = capability_table(&file);
+        let _ = table::write(io::stdout(), table, &table::DisplayConfig::default());
+    }
+
     Ok(())
 }
 
 pub async fn get_file(
     hub: &Hub,
     file_id: &str,
+) -> Result<google_drive3::api::File, google_drive3::Error> {
+    get_file_with_fields(hub, file_id, FILE_FIELDS).await
+}
+
+pub async fn get_file_with_fields(
+    hub: &Hub,
+    file_id: &str,
+    fields: &str,
 ) -> Result<google_drive3::api::File, google_drive3::Error> {
     let (_, file) = hub
         .files()
         .get(file_id)
-        .param("fields", "id,name,size,createdTime,modifiedTime,md5Checksum,mimeType,parents,shared,description,webContentLink,webViewLink,shortcutDetails(targetId,targetMimeType)")
+        .param("fields", fields)
         .supports_all_drives(true)
         .add_scope(google_drive3::api::Scope::Full)
         .doit()
@@ -48,6 +119,21 @@ pub async fn get_file(
     Ok(file)
 }
 
+// Target of a shortcut, None when the file isn't a shortcut or doesn't name its target
+pub async fn get_shortcut_target<A: DriveApi>(
+    api: &A,
+    file: &google_drive3::api::File,
+) -> Result<Option<google_drive3::api::File>, google_drive3::Error> {
+    if !drive_file::is_shortcut(file) {
+        return Ok(None);
+    }
+
+    match drive_file::shortcut_target_id(file) {
+        Some(target_id) => api.get_file(&target_id).await.map(Some),
+        None => Ok(None),
+    }
+}
+
 pub fn print_fields(fields: &Vec<Field>) {
     for field in fields {
         if let Some(value) = &field.value {
@@ -59,6 +145,8 @@ pub fn print_fields(fields: &Vec<Field>) {
 #[derive(Debug, Clone, Default)]
 pub struct DisplayConfig {
     pub size_in_bytes: bool,
+    pub size_units: SizeUnits,
+    pub time_style: TimeStyle,
 }
 
 pub struct Field {
@@ -86,11 +174,15 @@ pub fn prepare_fields(file: &google_drive3::api::File, config: &DisplayConfig) -
         },
         Field {
             name: String::from("Created"),
-            value: file.created_time.map(format_date_time),
+            value: file
+                .created_time
+                .map(|time| time_fmt::format_time(time, &config.time_style)),
         },
         Field {
             name: String::from("Modified"),
-            value: file.modified_time.map(format_date_time),
+            value: file
+                .modified_time
+                .map(|time| time_fmt::format_time(time, &config.time_style)),
         },
         Field {
             name: String::from("MD5"),
@@ -111,7 +203,110 @@ pub fn prepare_fields(file: &google_drive3::api::File, config: &DisplayConfig) -
     ]
 }
 
-// TODO: move to common
+// What the current account may do with the file, who owns it and who it is shared with
+pub fn sharing_fields(file: &google_drive3::api::File) -> Vec<Field> {
+    let mut fields = capability_fields(file);
+
+    fields.extend([
+        Field {
+            name: String::from("Owner"),
+            value: format_owner(file),
+        },
+        Field {
+            name: String::from("SharedBy"),
+            value: file.sharing_user.as_ref().and_then(format_user),
+        },
+        Field {
+            name: String::from("Permissions"),
+            value: file.permissions.as_ref().map(|permissions| {
+                permissions
+                    .iter()
+                    .map(format_permission)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }),
+        },
+    ]);
+
+    fields
+}
+
+// What the current account may do with the file, without a value when the api didn't say
+pub fn capability_fields(file: &google_drive3::api::File) -> Vec<Field> {
+    let capabilities = file.capabilities.clone().unwrap_or_default();
+
+    [
+        ("CanDownload", capabilities.can_download),
+        ("CanEdit", capabilities.can_edit),
+        ("CanComment", capabilities.can_comment),
+        ("CanCopy", capabilities.can_copy),
+        ("CanRename", capabilities.can_rename),
+        ("CanShare", capabilities.can_share),
+        ("CanTrash", capabilities.can_trash),
+        ("CanDelete", capabilities.can_delete),
+        (
+            "CanMoveItemWithinDrive",
+            capabilities.can_move_item_within_drive,
+        ),
+        ("CanAddChildren", capabilities.can_add_children),
+        ("CanListChildren", capabilities.can_list_children),
+        ("CanReadRevisions", capabilities.can_read_revisions),
+    ]
+    .into_iter()
+    .map(|(name, value)| Field {
+        name: name.to_string(),
+        value: value.map(format_bool),
+    })
+    .collect()
+}
+
+// The capability fields as a table for --check-access, listing unknown ones too
+pub fn capability_table(file: &google_drive3::api::File) -> Table<&'static str, String, 2> {
+    let values = capability_fields(file)
+        .into_iter()
+        .map(|field| {
+            [
+                field.name,
+                field.value.unwrap_or_else(|| String::from("Unknown")),
+            ]
+        })
+        .collect();
+
+    Table {
+        header: ["Capability", "Allowed"],
+        values,
+    }
+}
+
+fn format_owner(file: &google_drive3::api::File) -> Option<String> {
+    if file.owned_by_me == Some(true) {
+        return Some(String::from("me"));
+    }
+
+    file.owners
+        .as_ref()
+        .and_then(|owners| owners.iter().find_map(format_user))
+}
+
+fn format_user(user: &google_drive3::api::User) -> Option<String> {
+    user.email_address
+        .clone()
+        .or_else(|| user.display_name.clone())
+}
+
+// i.e. "user alice@example.com writer" or "anyone reader"
+fn format_permission(permission: &google_drive3::api::Permission) -> String {
+    [
+        permission.type_.clone(),
+        permission.email_address.clone(),
+        permission.role.clone(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
 pub fn format_bool(b: bool) -> String {
     if b {
         String::from("True")
@@ -128,19 +323,20 @@ pub fn format_bytes(bytes: i64, config: &DisplayConfig) -> String {
     if config.size_in_bytes {
         bytes.to_string()
     } else {
-        human_bytes(bytes as f64)
+        // Drive never reports a negative size
+        byte_size::format_human_in(u64::try_from(bytes).unwrap_or(0), config.size_units)
     }
 }
 
 pub fn format_date_time(utc_time: DateTime<chrono::Utc>) -> String {
-    let local_time: DateTime<chrono::Local> = DateTime::from(utc_time);
-    local_time.format("%Y-%m-%d %H:%M:%S").to_string()
+    time_fmt::format_time(utc_time, &TimeStyle::Local)
 }
 
 #[derive(Debug)]
 pub enum Error {
-    Hub(hub_helper::Error),
+    ResolvePath(path_utils::PathResolutionError),
     GetFile(google_drive3::Error),
+    SerializeJson(serde_json::Error),
 }
 
 impl error::Error for Error {}
@@ -148,8 +344,11 @@ impl error::Error for Error {}
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::Hub(err) => write!(f, "{}", err),
-            Error::GetFile(err) => write!(f, "Failed getting file: {}", err),
+            Error::ResolvePath(err) => write!(f, "Failed to resolve path: {}", err),
+            Error::GetFile(err) => {
+                write!(f, "Failed getting file: {}", drive_error::describe(err))
+            }
+            Error::SerializeJson(err) => write!(f, "Failed to serialize json: {}", err),
         }
     }
 }
@@ -1,20 +1,38 @@
+use crate::app_config::AccountScope;
+use crate::common::byte_size::SizeUnits;
+use crate::common::drive_api::DriveApi;
+use crate::common::drive_error;
 use crate::common::drive_file;
-use crate::common::hub_helper;
+use crate::common::output;
+use crate::common::output::OutputMode;
+use crate::common::output::PrettyTable;
+use crate::common::output::Style;
+use crate::common::permission;
 use crate::common::table;
 use crate::common::table::Table;
+use crate::common::time_fmt;
+use crate::common::time_fmt::TimeStyle;
 use crate::files;
 use crate::files::info::DisplayConfig;
+use crate::hub;
 use crate::hub::Hub;
+use google_drive3::api::File;
 use std::cmp::min;
+use std::cmp::Ordering;
 use std::error;
 use std::fmt;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::fs;
 use std::io;
+use std::path::Path;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 const MAX_PAGE_SIZE: usize = 1000;
 
+const PERMISSION_LIST_FIELDS: &str = "files(id,name,md5Checksum,mimeType,size,createdTime,modifiedTime,parents,trashed,shared,shortcutDetails(targetId),permissions(id,type,role,emailAddress,domain,expirationTime)),nextPageToken";
+
 pub struct Config {
     pub query: ListQuery,
     pub order_by: ListSortOrder,
@@ -22,68 +40,240 @@ pub struct Config {
     pub skip_header: bool,
     pub truncate_name: bool,
     pub field_separator: String,
+    pub time_style: TimeStyle,
+    pub output: OutputMode,
+    pub color: bool,
+    pub space: Space,
+    // Show the type and size of the targets of shortcuts, one request per shortcut
+    pub resolve_shortcuts: bool,
+    pub size_units: SizeUnits,
+    // Sorts the listed files after they are fetched, so only the first max_files are sorted
+    pub sort: Option<FileSort>,
+    pub reverse: bool,
+    // Fetch the permissions of each file and show who it's shared with
+    pub include_permissions: bool,
+    // Print the files as a json array with the fields drive returns
+    pub json: bool,
+    // Continue a listing where an earlier one stopped
+    pub page_token: Option<String>,
+    // Print the token of the next page to stderr
+    pub show_token: bool,
 }
 
-pub async fn list(config: Config) -> Result<(), Error> {
-    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
-    let files = list_files(
-        &hub,
+// Columns of the list output
+const NAME_COLUMN: usize = 1;
+const SIZE_COLUMN: usize = 3;
+
+pub async fn list(hub: &Hub, config: Config) -> Result<(), Error> {
+    if hub.account_scope() == AccountScope::File {
+        eprintln!("Note: the account uses the drive.file scope, only files created by gdrive are listed");
+    }
+
+    let fields = if config.include_permissions {
+        PERMISSION_LIST_FIELDS
+    } else {
+        FILE_LIST_FIELDS
+    };
+
+    let page = fetch_page(
+        hub,
         &ListFilesConfig {
             query: config.query.clone(),
             order_by: config.order_by.clone(),
             max_files: config.max_files,
+            space: config.space,
         },
+        fields,
+        config.page_token.clone(),
     )
-    .await?;
+    .await
+    .map_err(Error::ListFiles)?;
+
+    // Printed first so it isn't lost when stdout is cut short, i.e. by head
+    if config.show_token {
+        if let Some(token) = &page.next_page_token {
+            eprintln!("{}", token);
+        }
+    }
+
+    let mut files = page.files;
+
+    if let Some(sort) = &config.sort {
+        sort_files(&mut files, sort, config.reverse);
+    }
+
+    if config.json {
+        let json = serde_json::to_string_pretty(&files).map_err(Error::SerializeJson)?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    // Pretty output on a terminal fits the names to its width instead of a fixed width
+    let terminal_width = if config.output == OutputMode::Pretty && config.truncate_name {
+        output::terminal_width()
+    } else {
+        None
+    };
+    let truncate_name = config.truncate_name && terminal_width.is_none();
 
     let mut values: Vec<[String; 5]> = vec![];
+    let mut sharing: Vec<String> = vec![];
+    let mut styles: Vec<Style> = vec![];
+    let display_config = DisplayConfig {
+        size_units: config.size_units,
+        ..DisplayConfig::default()
+    };
 
     for file in files {
-        let file_type = simplified_file_type(&file);
-        let file_name = format_file_name(&config, &file);
+        let target = if config.resolve_shortcuts {
+            shortcut_target(hub, &file).await
+        } else {
+            None
+        };
+
+        let file_type = match &target {
+            Some(target) => format!("{} (shortcut)", simplified_file_type(target)),
+            None => simplified_file_type(&file),
+        };
+        let size = match &target {
+            Some(target) => target.size,
+            None => file.size,
+        };
+        let file_name = format_file_name(&file, truncate_name);
+        styles.push(file_style(&file));
+        sharing.push(sharing_summary(&file));
 
         values.push([
             file.id.unwrap_or_default(),
             file_name,
             file_type,
-            file.size
-                .map(|bytes| files::info::format_bytes(bytes, &DisplayConfig::default()))
+            size.map(|bytes| files::info::format_bytes(bytes, &display_config))
                 .unwrap_or_default(),
             file.created_time
-                .map(files::info::format_date_time)
+                .map(|time| time_fmt::format_time(time, &config.time_style))
                 .unwrap_or_default(),
         ])
     }
 
-    let table = Table {
-        header: ["Id", "Name", "Type", "Size", "Created"],
-        values,
-    };
+    let header = ["Id", "Name", "Type", "Size", "Created"];
+
+    if config.include_permissions {
+        let values = values
+            .into_iter()
+            .zip(sharing)
+            .map(|([id, name, type_, size, created], sharing)| {
+                [id, name, type_, size, created, sharing]
+            })
+            .collect();
+        let header = ["Id", "Name", "Type", "Size", "Created", "Sharing"];
+        print_files(&config, header, values, styles, terminal_width);
+    } else {
+        print_files(&config, header, values, styles, terminal_width);
+    }
+
+    Ok(())
+}
+
+fn print_files<const COLUMNS: usize>(
+    config: &Config,
+    header: [&str; COLUMNS],
+    values: Vec<[String; COLUMNS]>,
+    styles: Vec<Style>,
+    terminal_width: Option<usize>,
+) {
+    if config.output == OutputMode::Pretty {
+        let table = PrettyTable {
+            header: header.map(String::from),
+            rows: values.into_iter().zip(styles).collect(),
+        };
+
+        let _ = output::write_pretty(
+            io::stdout(),
+            table,
+            &output::PrettyConfig {
+                skip_header: config.skip_header,
+                color: config.color,
+                right_aligned: vec![SIZE_COLUMN],
+                max_width: terminal_width,
+                fit_column: Some(NAME_COLUMN),
+            },
+        );
+
+        return;
+    }
+
+    let table = Table { header, values };
 
     let _ = table::write(
         io::stdout(),
         table,
         &table::DisplayConfig {
             skip_header: config.skip_header,
-            separator: config.field_separator,
+            separator: config.field_separator.clone(),
         },
     );
-
-    Ok(())
 }
 
 pub struct ListFilesConfig {
     pub query: ListQuery,
     pub order_by: ListSortOrder,
     pub max_files: usize,
+    pub space: Space,
 }
 
 pub async fn list_files(
     hub: &Hub,
     config: &ListFilesConfig,
 ) -> Result<Vec<google_drive3::api::File>, Error> {
+    fetch_files(hub, config).await.map_err(Error::ListFiles)
+}
+
+// Every file directly in the folder in the drive space, following every page
+pub async fn list_folder<A: DriveApi>(api: &A, folder_id: &str) -> Result<Vec<File>, Error> {
+    let config = ListFilesConfig {
+        query: ListQuery::FilesInFolder {
+            folder_id: folder_id.to_string(),
+        },
+        order_by: ListSortOrder::default(),
+        max_files: usize::MAX,
+        space: Space::Drive,
+    };
+
+    api.list_files(&config).await.map_err(Error::ListFiles)
+}
+
+// Fetches pages until max_files files are listed or there are no more pages
+pub async fn fetch_files(
+    hub: &Hub,
+    config: &ListFilesConfig,
+) -> Result<Vec<google_drive3::api::File>, google_drive3::Error> {
+    fetch_files_with_fields(hub, config, FILE_LIST_FIELDS).await
+}
+
+pub async fn fetch_files_with_fields(
+    hub: &Hub,
+    config: &ListFilesConfig,
+    fields: &str,
+) -> Result<Vec<google_drive3::api::File>, google_drive3::Error> {
+    let page = fetch_page(hub, config, fields, None).await?;
+    Ok(page.files)
+}
+
+pub struct FilePage {
+    pub files: Vec<google_drive3::api::File>,
+    // Lists the files after the last one of files, None when there are no more files
+    pub next_page_token: Option<String>,
+}
+
+// Fetches pages from page_token on until max_files files are listed or there are no more pages
+pub async fn fetch_page(
+    hub: &Hub,
+    config: &ListFilesConfig,
+    fields: &str,
+    page_token: Option<String>,
+) -> Result<FilePage, google_drive3::Error> {
     let mut collected_files: Vec<google_drive3::api::File> = vec![];
-    let mut next_page_token: Option<String> = None;
+    let mut next_page_token = page_token;
 
     loop {
         let max_files = config.max_files - collected_files.len();
@@ -95,6 +285,14 @@ pub async fn list_files(
             req = req.page_token(&token);
         }
 
+        if let Some(drive_id) = config.query.drive_id() {
+            req = req.corpora("drive").drive_id(drive_id);
+        }
+
+        if config.space != Space::Drive {
+            req = req.spaces(&config.space.to_string());
+        }
+
         let (_, file_list) = req
             .page_size(page_size as i32)
             .q(&config.query.to_string())
@@ -102,13 +300,9 @@ pub async fn list_files(
             .add_scope(google_drive3::api::Scope::Full)
             .supports_all_drives(true)
             .include_items_from_all_drives(true)
-            .param(
-                "fields",
-                "files(id,name,md5Checksum,mimeType,size,createdTime,parents),nextPageToken",
-            )
+            .param("fields", fields)
             .doit()
-            .await
-            .map_err(Error::ListFiles)?;
+            .await?;
 
         if let Some(mut files) = file_list.files {
             collected_files.append(&mut files);
@@ -122,7 +316,10 @@ pub async fn list_files(
     }
 
     let max_files = min(config.max_files, collected_files.len());
-    Ok(collected_files[0..max_files].to_vec())
+    Ok(FilePage {
+        files: collected_files[0..max_files].to_vec(),
+        next_page_token,
+    })
 }
 
 #[derive(Debug, Clone, Default)]
@@ -132,11 +329,37 @@ pub enum ListQuery {
     FilesOnDrive {
         drive_id: String,
     },
+    DriveRoot {
+        drive_id: String,
+    },
     FilesInFolder {
         folder_id: String,
     },
     Custom(String),
     None,
+    OwnedBy {
+        query: Box<ListQuery>,
+        email: String,
+    },
+}
+
+impl ListQuery {
+    // Narrows the query to files owned by email
+    pub fn owned_by(self, email: &str) -> ListQuery {
+        ListQuery::OwnedBy {
+            query: Box::new(self),
+            email: email.to_string(),
+        }
+    }
+
+    // Shared drive the files are listed from, set with the corpora and driveId parameters
+    pub fn drive_id(&self) -> Option<&str> {
+        match self {
+            ListQuery::FilesOnDrive { drive_id } => Some(drive_id),
+            ListQuery::OwnedBy { query, .. } => query.drive_id(),
+            _ => None,
+        }
+    }
 }
 
 impl FromStr for ListQuery {
@@ -158,7 +381,12 @@ impl Display for ListQuery {
                 write!(f, "'root' in parents and trashed = false")
             }
 
-            ListQuery::FilesOnDrive { drive_id } => {
+            // Scoped to the drive by the corpora and driveId parameters
+            ListQuery::FilesOnDrive { .. } => {
+                write!(f, "trashed = false")
+            }
+
+            ListQuery::DriveRoot { drive_id } => {
                 write!(f, "'{}' in parents and trashed = false", drive_id)
             }
 
@@ -173,6 +401,162 @@ impl Display for ListQuery {
             ListQuery::None => {
                 write!(f, "")
             }
+
+            // The query is wrapped in parentheses, a custom query can contain 'or'
+            ListQuery::OwnedBy { query, email } => {
+                let owner = format!("'{}' in owners", escape_query_value(email));
+                match query.to_string().as_str() {
+                    "" => write!(f, "{}", owner),
+                    query => write!(f, "({}) and {}", query, owner),
+                }
+            }
+        }
+    }
+}
+
+// Examples for files query-help, the ones gdrive builds itself come from ListQuery
+pub fn query_examples() -> Vec<(&'static str, String)> {
+    let folder_id = String::from("<FOLDER_ID>");
+
+    vec![
+        ("Files in the root folder", ListQuery::RootNotTrashed.to_string()),
+        (
+            "Files in a folder (--parent)",
+            ListQuery::FilesInFolder {
+                folder_id: folder_id.clone(),
+            }
+            .to_string(),
+        ),
+        (
+            "Files owned by someone (--owner)",
+            ListQuery::FilesInFolder { folder_id }
+                .owned_by("alice@example.com")
+                .to_string(),
+        ),
+        ("Name contains a word", String::from("name contains 'report'")),
+        ("Exact name", String::from("name = 'budget.xlsx'")),
+        (
+            "Folders only",
+            format!("mimeType = '{}'", drive_file::MIME_TYPE_DRIVE_FOLDER),
+        ),
+        (
+            "Google documents only",
+            format!("mimeType = '{}'", drive_file::MIME_TYPE_DRIVE_DOCUMENT),
+        ),
+        (
+            "Modified after a date",
+            String::from("modifiedTime > '2024-01-01T00:00:00'"),
+        ),
+        ("Text in the content", String::from("fullText contains 'invoice'")),
+        ("Starred files", String::from("starred = true")),
+        ("Shared with you", String::from("sharedWithMe and trashed = false")),
+        ("In the trash", String::from("trashed = true")),
+        (
+            "Combined, with a quote escaped",
+            String::from("name contains 'Alice\\'s' and mimeType != 'application/pdf'"),
+        ),
+    ]
+}
+
+pub fn print_query_help() {
+    println!("Examples for files list --query, combine terms with 'and', 'or' and 'not'.");
+    println!("Values are single quoted, escape quotes and backslashes in them with a backslash.");
+    println!("See https://developers.google.com/drive/api/guides/search-files for all terms.");
+    println!();
+
+    for (description, query) in query_examples() {
+        println!("{}:", description);
+        println!("  {}", query);
+    }
+}
+
+// Query for files list --query-file
+pub fn read_query_file(path: &Path) -> Result<String, Error> {
+    let contents =
+        fs::read_to_string(path).map_err(|err| Error::ReadQueryFile(path.to_path_buf(), err))?;
+
+    let query = parse_query_file(&contents);
+    if query.is_empty() {
+        return Err(Error::EmptyQueryFile(path.to_path_buf()));
+    }
+
+    Ok(query)
+}
+
+// Lines starting with '#' are comments, the remaining lines are joined with spaces so a
+// query can be split over several lines
+pub fn parse_query_file(contents: &str) -> String {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Escapes a value for use inside single quotes in a query
+pub fn escape_query_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+// Files in appDataFolder are hidden application data, only visible to the app that
+// created them. Both appDataFolder and photos need a scope that isn't requested by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Space {
+    #[default]
+    Drive,
+    AppDataFolder,
+    Photos,
+}
+
+pub const FILE_LIST_FIELDS: &str = "files(id,name,md5Checksum,mimeType,size,createdTime,modifiedTime,parents,trashed,shortcutDetails(targetId)),nextPageToken";
+
+// Parent alias for the root of the appDataFolder space
+pub const APP_DATA_FOLDER: &str = "appDataFolder";
+
+impl Space {
+    pub fn required_scope(&self) -> Option<&'static str> {
+        match self {
+            Space::Drive => None,
+            Space::AppDataFolder => Some(hub::APP_DATA_SCOPE),
+            Space::Photos => Some(hub::PHOTOS_SCOPE),
+        }
+    }
+
+    // Query used when no parent or query is given, the equivalent of listing the root folder
+    pub fn default_query(&self) -> ListQuery {
+        match self {
+            Space::Drive => ListQuery::default(),
+            Space::AppDataFolder => ListQuery::FilesInFolder {
+                folder_id: APP_DATA_FOLDER.to_string(),
+            },
+            Space::Photos => ListQuery::Custom(String::from("trashed = false")),
+        }
+    }
+}
+
+impl FromStr for Space {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "drive" => Ok(Space::Drive),
+            "appDataFolder" => Ok(Space::AppDataFolder),
+            "photos" => Ok(Space::Photos),
+            _ => Err(format!(
+                "'{}' is not a valid space, valid spaces are: drive, appDataFolder, photos",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for Space {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Space::Drive => write!(f, "drive"),
+            Space::AppDataFolder => write!(f, "{}", APP_DATA_FOLDER),
+            Space::Photos => write!(f, "photos"),
         }
     }
 }
@@ -212,8 +596,10 @@ impl fmt::Display for ListSortOrder {
 
 #[derive(Debug)]
 pub enum Error {
-    Hub(hub_helper::Error),
     ListFiles(google_drive3::Error),
+    ReadQueryFile(PathBuf, io::Error),
+    EmptyQueryFile(PathBuf),
+    SerializeJson(serde_json::Error),
 }
 
 impl error::Error for Error {}
@@ -221,13 +607,129 @@ impl error::Error for Error {}
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::Hub(e) => write!(f, "{}", e),
-            Error::ListFiles(e) => write!(f, "Failed to list files: {}", e),
+            Error::ListFiles(e) => {
+                write!(f, "Failed to list files: {}", drive_error::describe(e))
+            }
+            Error::ReadQueryFile(path, e) => {
+                write!(f, "Failed to read query file '{}': {}", path.display(), e)
+            }
+            Error::EmptyQueryFile(path) => {
+                write!(f, "No query in '{}', only comments", path.display())
+            }
+            Error::SerializeJson(e) => write!(f, "Failed to serialize json: {}", e),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileSort {
+    Size,
+    Name,
+    Modified,
+}
+
+impl FromStr for FileSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "size" => Ok(FileSort::Size),
+            "name" => Ok(FileSort::Name),
+            "modified" => Ok(FileSort::Modified),
+            _ => Err(format!(
+                "'{}' is not a valid sort order, valid orders are: size, name, modified",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for FileSort {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            FileSort::Size => write!(f, "size"),
+            FileSort::Name => write!(f, "name"),
+            FileSort::Modified => write!(f, "modified"),
         }
     }
 }
 
-fn simplified_file_type(file: &google_drive3::api::File) -> String {
+// Smallest, first by name or least recently modified first, reverse turns that around. Files
+// that compare equal are ordered by id either way, so the output doesn't depend on the api order.
+pub fn sort_files(files: &mut [File], sort: &FileSort, reverse: bool) {
+    files.sort_by(|a, b| {
+        let ordering = compare_files(a, b, sort);
+        let ordering = if reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        ordering.then_with(|| a.id.cmp(&b.id))
+    });
+}
+
+// Files without a size, i.e. folders and google documents, come before any file with a size
+pub fn compare_files(a: &File, b: &File, sort: &FileSort) -> Ordering {
+    match sort {
+        FileSort::Size => a.size.cmp(&b.size),
+        FileSort::Name => compare_names(a, b),
+        FileSort::Modified => a.modified_time.cmp(&b.modified_time),
+    }
+}
+
+// Case insensitive, names that only differ in case are ordered by their bytes
+fn compare_names(a: &File, b: &File) -> Ordering {
+    let a = a.name.as_deref().unwrap_or_default();
+    let b = b.name.as_deref().unwrap_or_default();
+
+    a.to_lowercase()
+        .cmp(&b.to_lowercase())
+        .then_with(|| a.cmp(b))
+}
+
+// A shortcut whose target can't be read, i.e. because it was deleted, is listed as a shortcut
+async fn shortcut_target(
+    hub: &Hub,
+    file: &google_drive3::api::File,
+) -> Option<google_drive3::api::File> {
+    files::info::get_shortcut_target(hub, file)
+        .await
+        .ok()
+        .flatten()
+}
+
+// 'public' when anyone may access the file, 'shared(N)' for N permissions besides the owner and
+// 'private' for none. Drive doesn't return the permissions of files on shared drives, those only
+// show whether they are shared.
+pub fn sharing_summary(file: &google_drive3::api::File) -> String {
+    let permissions = match &file.permissions {
+        Some(permissions) => permissions,
+        None if file.shared == Some(true) => return String::from("shared"),
+        None => return String::new(),
+    };
+
+    let anyone = permission::Type::Anyone.to_string();
+    if permissions
+        .iter()
+        .any(|p| p.type_.as_ref() == Some(&anyone))
+    {
+        return String::from("public");
+    }
+
+    let owner = permission::Role::Owner.to_string();
+    let shared = permissions
+        .iter()
+        .filter(|p| p.role.as_ref() != Some(&owner))
+        .count();
+
+    if shared == 0 {
+        String::from("private")
+    } else {
+        format!("shared({})", shared)
+    }
+}
+
+pub fn simplified_file_type(file: &google_drive3::api::File) -> String {
     if drive_file::is_directory(file) {
         String::from("folder")
     } else if drive_file::is_binary(file) {
@@ -239,32 +741,29 @@ fn simplified_file_type(file: &google_drive3::api::File) -> String {
     }
 }
 
-fn format_file_name(config: &Config, file: &google_drive3::api::File) -> String {
+fn file_style(file: &google_drive3::api::File) -> Style {
+    if file.trashed == Some(true) {
+        Style::Trashed
+    } else if drive_file::is_directory(file) {
+        Style::Folder
+    } else if drive_file::is_shortcut(file) {
+        Style::Shortcut
+    } else if drive_file::is_binary(file) {
+        Style::Normal
+    } else {
+        Style::Document
+    }
+}
+
+fn format_file_name(file: &google_drive3::api::File, truncate_name: bool) -> String {
     let file_name = file.name.as_ref();
 
-    if config.truncate_name {
+    if truncate_name {
         file_name
-            .map(|s| truncate_middle(&s, 41))
+            .map(|s| output::truncate_middle(s, 41))
             .unwrap_or_default()
     } else {
         file_name.map(|s| s.to_string()).unwrap_or_default()
     }
 }
 
-// Truncates string to given max length, and inserts ellipsis into
-// the middle of the string to signify that the string has been truncated
-fn truncate_middle(s: &str, max_length: usize) -> String {
-    let chars: Vec<char> = s.chars().collect();
-
-    if chars.len() <= max_length {
-        return s.to_string();
-    }
-
-    let tail_count = max_length / 2;
-    let head_count = max_length - tail_count - 1;
-
-    let head: String = chars[0..head_count].iter().collect();
-    let tail: String = chars[chars.len() - tail_count..].iter().collect();
-
-    vec![head, tail].join("…")
-}
@@ -0,0 +1,117 @@
+use crate::common::table;
+use crate::common::table::Table;
+use crate::files;
+use crate::files::info::DisplayConfig;
+use crate::hub::Hub;
+use futures::stream;
+use futures::stream::StreamExt;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+pub struct Config {
+    pub file_ids: Vec<String>,
+    pub ids_file: Option<PathBuf>,
+    pub concurrency: usize,
+    pub skip_header: bool,
+    pub field_separator: String,
+}
+
+pub async fn meta(hub: &Hub, config: Config) -> Result<(), Error> {
+    let mut file_ids = config.file_ids.clone();
+
+    if let Some(path) = &config.ids_file {
+        let content =
+            fs::read_to_string(path).map_err(|err| Error::ReadIdsFile(path.clone(), err))?;
+        file_ids.extend(parse_ids(&content));
+    }
+
+    let results: Vec<(String, Result<google_drive3::api::File, google_drive3::Error>)> =
+        stream::iter(file_ids)
+            .map(|file_id| {
+                async move {
+                    let result = files::info::get_file(hub, &file_id).await;
+                    (file_id, result)
+                }
+            })
+            .buffered(config.concurrency.max(1))
+            .collect()
+            .await;
+
+    let mut values: Vec<[String; 5]> = vec![];
+    let mut failed_ids: Vec<(String, google_drive3::Error)> = vec![];
+
+    for (file_id, result) in results {
+        match result {
+            Ok(file) => values.push([
+                file.id.clone().unwrap_or(file_id),
+                file.name.clone().unwrap_or_default(),
+                files::list::simplified_file_type(&file),
+                file.size
+                    .map(|bytes| files::info::format_bytes(bytes, &DisplayConfig::default()))
+                    .unwrap_or_default(),
+                file.modified_time
+                    .map(files::info::format_date_time)
+                    .unwrap_or_default(),
+            ]),
+
+            Err(err) => failed_ids.push((file_id, err)),
+        }
+    }
+
+    let table = Table {
+        header: ["Id", "Name", "Type", "Size", "Modified"],
+        values,
+    };
+
+    let _ = table::write(
+        io::stdout(),
+        table,
+        &table::DisplayConfig {
+            skip_header: config.skip_header,
+            separator: config.field_separator,
+        },
+    );
+
+    for (file_id, err) in &failed_ids {
+        eprintln!("Failed to get '{}': {}", file_id, err);
+    }
+
+    if failed_ids.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::GetFiles(failed_ids.len()))
+    }
+}
+
+// One id per line, blank lines and lines starting with # are ignored
+pub fn parse_ids(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.to_string())
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum Error {
+    ReadIdsFile(PathBuf, io::Error),
+    GetFiles(usize),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::ReadIdsFile(path, err) => {
+                write!(f, "Failed to read ids file '{}': {}", path.display(), err)
+            }
+            Error::GetFiles(count) => write!(f, "Failed to get {} files", count),
+        }
+    }
+}
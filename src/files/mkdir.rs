@@ -2,8 +2,12 @@ use crate::common::delegate::UploadDelegate;
 use crate::common::delegate::UploadDelegateConfig;
 use crate::common::drive_file::MIME_TYPE_DRIVE_FOLDER;
 use crate::common::empty_file::EmptyFile;
-use crate::common::hub_helper;
+use crate::common::parent_check;
+use crate::common::read_only;
+use crate::files::path_utils;
+use crate::files::path_utils::ResolvedFolder;
 use crate::hub::Hub;
+use serde::Serialize;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
@@ -14,13 +18,32 @@ pub struct Config {
     pub name: String,
     pub parents: Option<Vec<String>>,
     pub print_only_id: bool,
+    pub skip_parent_check: bool,
+    // Create the missing folders of parents given as paths
+    pub create_parents: bool,
 }
 
-pub async fn mkdir(config: Config) -> Result<(), Error> {
-    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+pub async fn mkdir(hub: &Hub, config: Config) -> Result<(), Error> {
+    read_only::err_if_enabled("create directories").map_err(Error::ReadOnly)?;
+
     let delegate_config = UploadDelegateConfig::default();
 
-    let file = create_directory(&hub, &config, delegate_config)
+    let parents = match &config.parents {
+        Some(parents) => Some(
+            parent_check::resolve_parents(
+                hub,
+                parents,
+                config.create_parents,
+                !config.skip_parent_check,
+            )
+            .await
+            .map_err(Error::ParentCheck)?,
+        ),
+        parents => parents.clone(),
+    };
+    let config = Config { parents, ..config };
+
+    let file = create_directory(hub, &config, delegate_config)
         .await
         .map_err(Error::CreateDirectory)?;
 
@@ -37,6 +60,70 @@ pub async fn mkdir(config: Config) -> Result<(), Error> {
     Ok(())
 }
 
+#[derive(Clone, Debug)]
+pub struct PathConfig {
+    pub path: String,
+    pub print_only_id: bool,
+    pub json: bool,
+}
+
+// One entry of the json output of mkdir_path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FolderJson {
+    pub name: String,
+    pub id: String,
+    pub created: bool,
+}
+
+impl From<&ResolvedFolder> for FolderJson {
+    fn from(folder: &ResolvedFolder) -> FolderJson {
+        FolderJson {
+            name: folder.file.name.clone().unwrap_or_default(),
+            id: folder.file.id.clone().unwrap_or_default(),
+            created: folder.created,
+        }
+    }
+}
+
+// Creates every missing directory on the path, like mkdir -p
+pub async fn mkdir_path(hub: &Hub, config: PathConfig) -> Result<(), Error> {
+    read_only::err_if_enabled("create directories").map_err(Error::ReadOnly)?;
+
+    let chain = path_utils::resolve_or_create_chain(hub, &config.path)
+        .await
+        .map_err(Error::ResolvePath)?;
+
+    let leaf = chain
+        .last()
+        .ok_or(Error::ResolvePath(path_utils::PathResolutionError::InvalidPath))?;
+
+    if config.json {
+        let folders: Vec<FolderJson> = chain.iter().map(FolderJson::from).collect();
+        let json = serde_json::to_string(&folders).map_err(Error::SerializeJson)?;
+        println!("{}", json);
+    } else if config.print_only_id {
+        print!("{}", leaf.file.id.clone().unwrap_or_default())
+    } else {
+        for folder in chain.iter().filter(|folder| folder.created) {
+            println!(
+                "Created directory '{}' with id: {}",
+                folder.file.name.clone().unwrap_or_default(),
+                folder.file.id.clone().unwrap_or_default()
+            );
+        }
+
+        if !leaf.created {
+            println!(
+                "Directory '{}' already exists with id: {}",
+                config.path,
+                leaf.file.id.clone().unwrap_or_default()
+            );
+        }
+    }
+
+    Ok(())
+}
+
 pub async fn create_directory(
     hub: &Hub,
     config: &Config,
@@ -70,8 +157,11 @@ pub async fn create_directory(
 
 #[derive(Debug)]
 pub enum Error {
-    Hub(hub_helper::Error),
+    ReadOnly(read_only::Error),
+    ParentCheck(parent_check::Error),
     CreateDirectory(google_drive3::Error),
+    ResolvePath(path_utils::PathResolutionError),
+    SerializeJson(serde_json::Error),
 }
 
 impl error::Error for Error {}
@@ -79,10 +169,13 @@ impl error::Error for Error {}
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::Hub(err) => write!(f, "{}", err),
+            Error::ReadOnly(err) => write!(f, "{}", err),
+            Error::ParentCheck(err) => write!(f, "{}", err),
             Error::CreateDirectory(err) => {
                 write!(f, "Failed to create directory on drive: {}", err)
             }
+            Error::ResolvePath(err) => write!(f, "{}", err),
+            Error::SerializeJson(err) => write!(f, "Failed to serialize json: {}", err),
         }
     }
 }
@@ -1,16 +1,25 @@
 pub mod copy;
+pub mod dedup;
+pub mod dedupe_check;
 pub mod delete;
 pub mod download;
+pub mod duplicates;
+pub mod empty_folders;
 pub mod export;
 pub mod generate_ids;
 pub mod import;
 pub mod info;
 pub mod list;
+pub mod meta;
 pub mod mkdir;
 pub mod mv;
 pub mod path_utils;
 // pub mod permissions;
 pub mod rename;
+pub mod shortcut;
+pub mod stat;
+pub mod transfer;
 pub mod update;
 pub mod upload;
 pub mod upload_with_check;
+pub mod watch;
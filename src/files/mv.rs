@@ -1,9 +1,12 @@
 use crate::common::delegate::UploadDelegate;
 use crate::common::delegate::UploadDelegateConfig;
-use crate::common::drive_file;
-use crate::common::hub_helper;
+use crate::common::drive_error;
+use crate::common::parent_check;
+use crate::common::read_only;
 use crate::files;
+use crate::files::update::PatchFile;
 use crate::hub::Hub;
+use google_drive3::api::File;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
@@ -12,48 +15,117 @@ use std::fmt::Formatter;
 pub struct Config {
     pub file_id: String,
     pub to_folder_id: String,
+    pub verify: bool,
 }
 
-pub async fn mv(config: Config) -> Result<(), Error> {
-    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+pub async fn mv(hub: &Hub, config: Config) -> Result<(), Error> {
+    read_only::err_if_enabled("move files").map_err(Error::ReadOnly)?;
+
     let delegate_config = UploadDelegateConfig::default();
 
-    let old_file = files::info::get_file(&hub, &config.file_id)
+    let old_file = files::info::get_file(hub, &config.file_id)
         .await
         .map_err(Error::GetFile)?;
 
     let old_parent_id = get_old_parent_id(&old_file)?;
 
-    let old_parent = files::info::get_file(&hub, &old_parent_id)
+    let old_parent = files::info::get_file_with_fields(hub, &old_parent_id, "id,name,driveId")
         .await
         .map_err(|err| Error::GetOldParent(old_parent_id.clone(), err))?;
 
-    let new_parent = files::info::get_file(&hub, &config.to_folder_id)
+    let new_parent = parent_check::get_folder(hub, &config.to_folder_id)
         .await
-        .map_err(Error::GetNewParent)?;
+        .map_err(Error::NewParent)?;
 
-    err_if_not_directory(&new_parent)?;
+    // My Drive has no drive id
+    let cross_drive = old_parent.drive_id != new_parent.drive_id;
 
     println!(
         "Moving '{}' from '{}' to '{}'",
-        old_file.name.unwrap_or_default(),
+        old_file.name.clone().unwrap_or_default(),
         old_parent.name.unwrap_or_default(),
         new_parent.name.unwrap_or_default()
     );
 
     let change_parent_config = ChangeParentConfig {
-        file_id: config.file_id,
+        file_id: config.file_id.clone(),
         old_parent_id,
         new_parent_id: config.to_folder_id,
     };
 
-    change_parent(&hub, delegate_config, &change_parent_config)
+    let moved_file = change_parent(hub, delegate_config.clone(), &change_parent_config)
         .await
         .map_err(Error::Move)?;
 
+    if config.verify && cross_drive {
+        match verify_move(&old_file, &moved_file) {
+            Verification::InPlace => println!("Moved in place, nothing to verify"),
+
+            Verification::Verified => {
+                println!(
+                    "Verified the md5 checksum of the copy '{}', trashing the source",
+                    moved_file.id.unwrap_or_default()
+                );
+                trash_source(hub, delegate_config, &config.file_id).await?;
+            }
+
+            Verification::NoChecksum => return Err(Error::NoChecksum(config.file_id)),
+
+            Verification::Mismatch { source, copy } => {
+                return Err(Error::ChecksumMismatch {
+                    file_id: config.file_id,
+                    source,
+                    copy,
+                })
+            }
+        }
+    }
+
     Ok(())
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    // The file kept its id, Drive changed the parents without copying it
+    InPlace,
+    Verified,
+    // Folders and google documents have no md5 checksum to compare
+    NoChecksum,
+    Mismatch { source: String, copy: String },
+}
+
+// Compares the file returned by the move with the file before it, a different id means the
+// file was copied and the source still has to be removed
+pub fn verify_move(source: &File, moved: &File) -> Verification {
+    if moved.id.is_none() || moved.id == source.id {
+        return Verification::InPlace;
+    }
+
+    match (&source.md5_checksum, &moved.md5_checksum) {
+        (Some(source), Some(copy)) if source == copy => Verification::Verified,
+        (Some(source), Some(copy)) => Verification::Mismatch {
+            source: source.clone(),
+            copy: copy.clone(),
+        },
+        _ => Verification::NoChecksum,
+    }
+}
+
+async fn trash_source(
+    hub: &Hub,
+    delegate_config: UploadDelegateConfig,
+    file_id: &str,
+) -> Result<(), Error> {
+    let patch_file = PatchFile::new(file_id.to_string()).with_trashed(true);
+
+    match files::update::update_metadata(hub, delegate_config, patch_file).await {
+        Ok(_) => Ok(()),
+        // Drive already removed the source as part of the move
+        Err(err) if drive_error::is_not_found(&err) => Ok(()),
+        Err(err) => Err(Error::TrashSource(err)),
+    }
+}
+
 pub struct ChangeParentConfig {
     pub file_id: String,
     pub old_parent_id: String,
@@ -85,14 +157,20 @@ pub async fn change_parent(
 
 #[derive(Debug)]
 pub enum Error {
-    Hub(hub_helper::Error),
+    ReadOnly(read_only::Error),
     GetFile(google_drive3::Error),
     GetOldParent(String, google_drive3::Error),
-    GetNewParent(google_drive3::Error),
+    NewParent(parent_check::Error),
     NoParents,
     MultipleParents,
-    NotADirectory,
     Move(google_drive3::Error),
+    NoChecksum(String),
+    ChecksumMismatch {
+        file_id: String,
+        source: String,
+        copy: String,
+    },
+    TrashSource(google_drive3::Error),
 }
 
 impl error::Error for Error {}
@@ -100,13 +178,11 @@ impl error::Error for Error {}
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         match self {
-            Error::Hub(err) => write!(f, "{}", err),
+            Error::ReadOnly(err) => write!(f, "{}", err),
             Error::GetFile(err) => {
                 write!(f, "Failed to get file: {}", err)
             }
-            Error::GetNewParent(err) => {
-                write!(f, "Failed to get new parent: {}", err)
-            }
+            Error::NewParent(err) => write!(f, "{}", err),
             Error::GetOldParent(id, err) => {
                 write!(f, "Failed to get old parent '{}': {}", id, err)
             }
@@ -116,12 +192,26 @@ impl Display for Error {
             Error::MultipleParents => {
                 write!(f, "Can't move file with multiple parents")
             }
-            Error::NotADirectory => {
-                write!(f, "New parent is not a directory")
-            }
             Error::Move(err) => {
                 write!(f, "Failed to move file: {}", err)
             }
+            Error::NoChecksum(id) => write!(
+                f,
+                "Drive copied '{}' to the other drive but there is no md5 checksum to verify the copy, the source was kept",
+                id
+            ),
+            Error::ChecksumMismatch {
+                file_id,
+                source,
+                copy,
+            } => write!(
+                f,
+                "The copy of '{}' has md5 checksum {} instead of {}, the source was kept",
+                file_id, copy, source
+            ),
+            Error::TrashSource(err) => {
+                write!(f, "Verified the copy but failed to trash the source: {}", err)
+            }
         }
     }
 }
@@ -140,11 +230,3 @@ fn get_old_parent_id(file: &google_drive3::api::File) -> Result<String, Error> {
         },
     }
 }
-
-fn err_if_not_directory(file: &google_drive3::api::File) -> Result<(), Error> {
-    if !drive_file::is_directory(file) {
-        Err(Error::NotADirectory)
-    } else {
-        Ok(())
-    }
-}
@@ -1,64 +1,251 @@
-use crate::files;
+use crate::common::drive_api::DriveApi;
+use crate::common::drive_error;
+use crate::common::drive_file;
+use crate::common::path_filter::PathFilter;
+use crate::common::remote_walk;
+use crate::common::remote_walk::ApiLister;
+use crate::common::remote_walk::WalkConfig;
 use crate::files::list::{ListFilesConfig, ListQuery};
-use crate::hub::Hub;
 use google_drive3::api::File;
+use std::collections::HashSet;
 use std::fmt;
 use std::str::FromStr;
 use regex;
 
-pub async fn resolve_path(hub: &Hub, path: &str) -> Result<File, PathResolutionError> {
+// Files with the same name in a folder considered when trashed files are included
+const MAX_TRASHED_MATCHES: usize = 100;
+
+pub async fn resolve_path<A: DriveApi>(api: &A, path: &str) -> Result<File, PathResolutionError> {
+    resolve_path_with_trashed(api, path, false)
+        .await
+        .map(|resolved| resolved.file)
+}
+
+/// A file found by path, and whether a trashed file has the same path
+#[derive(Debug, Clone)]
+pub struct ResolvedPath {
+    pub file: File,
+    pub trashed_duplicate: bool,
+}
+
+/// Resolves a path, trashed files are only found when include_trashed is set. A file that isn't
+/// trashed wins over a trashed file with the same path
+pub async fn resolve_path_with_trashed<A: DriveApi>(
+    api: &A,
+    path: &str,
+    include_trashed: bool,
+) -> Result<ResolvedPath, PathResolutionError> {
     let parts: Vec<&str> = path.trim_matches('/').split('/').filter(|s| !s.is_empty()).collect();
     if parts.is_empty() {
         return Err(PathResolutionError::InvalidPath);
     }
 
+    // Root itself isn't fetched, every path has at least one part below it
     let mut current_id = "root".to_string();
-    let mut current_file = files::info::get_file(hub, &current_id)
-        .await
-        .map_err(|e| PathResolutionError::ApiError(e.to_string()))?;
+    let mut current = None;
+    let max_files = if include_trashed {
+        MAX_TRASHED_MATCHES
+    } else {
+        1
+    };
 
     for part in parts.iter() {
-        let query = format!(
-            "'{}' in parents and name = '{}' and trashed = false",
-            current_id, part
-        );
+        let query = path_part_query(&current_id, part, include_trashed);
         
         let config = ListFilesConfig {
             query: ListQuery::from_str(&query).map_err(|e| PathResolutionError::ApiError(e.to_string()))?,
             order_by: Default::default(),
-            max_files: 1,
+            max_files,
+            space: Default::default(),
         };
 
-        let files = files::list::list_files(hub, &config)
+        let files = api.list_files(&config)
             .await
-            .map_err(|e| PathResolutionError::ApiError(e.to_string()))?;
+            .map_err(api_error)?;
 
-        if let Some(file) = files.into_iter().next() {
-            current_id = file.id.clone().unwrap_or_default();
-            current_file = file;
+        if let Some(resolved) = pick_path_match(files) {
+            current_id = resolved.file.id.clone().unwrap_or_default();
+            current = Some(resolved);
         } else {
             return Err(PathResolutionError::NotFound(part.to_string()));
         }
     }
 
-    Ok(current_file)
+    current.ok_or(PathResolutionError::InvalidPath)
+}
+
+pub fn path_part_query(parent_id: &str, name: &str, include_trashed: bool) -> String {
+    if include_trashed {
+        format!("'{}' in parents and name = '{}'", parent_id, name)
+    } else {
+        format!(
+            "'{}' in parents and name = '{}' and trashed = false",
+            parent_id, name
+        )
+    }
+}
+
+/// Picks the file for a path among the files with the same name in a folder, preferring the
+/// first file that isn't trashed
+pub fn pick_path_match(files: Vec<File>) -> Option<ResolvedPath> {
+    let (live, trashed): (Vec<File>, Vec<File>) = files
+        .into_iter()
+        .partition(|file| file.trashed != Some(true));
+
+    match live.into_iter().next() {
+        Some(file) => Some(ResolvedPath {
+            file,
+            trashed_duplicate: !trashed.is_empty(),
+        }),
+        None => trashed.into_iter().next().map(|file| ResolvedPath {
+            file,
+            trashed_duplicate: false,
+        }),
+    }
 }
 
 /// Resolves a path, creating directories as needed
-pub async fn resolve_or_create_path(hub: &Hub, path: &str) -> Result<File, PathResolutionError> {
-    // If path is empty or just "/", return root folder
-    let parts: Vec<&str> = path.trim_matches('/').split('/').filter(|s| !s.is_empty()).collect();
-    if parts.is_empty() {
-        let root = files::info::get_file(hub, "root")
-            .await
-            .map_err(|e| PathResolutionError::ApiError(e.to_string()))?;
-        return Ok(root);
+pub async fn resolve_or_create_path<A: DriveApi>(api: &A, path: &str) -> Result<File, PathResolutionError> {
+    let chain = resolve_or_create_chain(api, path).await?;
+
+    match chain.into_iter().last() {
+        Some(folder) => Ok(folder.file),
+
+        // If path is empty or just "/", return root folder
+        None => api.get_file("root").await.map_err(api_error),
     }
-    
+}
+
+/// Resolves a destination directory. Missing directories are only created when create is set,
+/// otherwise a missing directory is an error
+pub async fn resolve_directory<A: DriveApi>(
+    api: &A,
+    path: &str,
+    create: bool,
+) -> Result<File, PathResolutionError> {
+    if create {
+        resolve_or_create_path(api, path).await
+    } else if path.trim_matches('/').is_empty() {
+        api.get_file("root").await.map_err(api_error)
+    } else {
+        resolve_path(api, path).await
+    }
+}
+
+/// Resolves a file id or a path starting with '/' to the file id. Ids are used as they are,
+/// without a request
+pub async fn resolve_file_id<A: DriveApi>(
+    api: &A,
+    file_id_or_path: &str,
+) -> Result<String, PathResolutionError> {
+    if is_path(file_id_or_path) {
+        let file = resolve_path(api, file_id_or_path).await?;
+        Ok(file.id.unwrap_or_default())
+    } else {
+        Ok(file_id_or_path.to_string())
+    }
+}
+
+/// Like resolve_file_id, but paths can also name trashed files when include_trashed is set.
+/// A trashed file with the same path as the resolved file is noted on stderr
+pub async fn resolve_file_id_with_trashed<A: DriveApi>(
+    api: &A,
+    file_id_or_path: &str,
+    include_trashed: bool,
+) -> Result<String, PathResolutionError> {
+    if !is_path(file_id_or_path) {
+        return Ok(file_id_or_path.to_string());
+    }
+
+    let resolved = resolve_path_with_trashed(api, file_id_or_path, include_trashed).await?;
+    if resolved.trashed_duplicate {
+        eprintln!(
+            "Note: a trashed file also has the path '{}', using the file that isn't trashed",
+            file_id_or_path
+        );
+    }
+
+    Ok(resolved.file.id.unwrap_or_default())
+}
+
+/// The path of a file from the root of its drive, i.e. "/Projects/report.pdf". Walks up the
+/// first parent of each folder, the root folder itself isn't part of the path
+pub async fn file_path<A: DriveApi>(api: &A, file: &File) -> Result<String, PathResolutionError> {
+    let mut names = vec![file.name.clone().unwrap_or_default()];
+    let mut parent_id = first_parent(file);
+    let mut visited = HashSet::new();
+
+    while let Some(id) = parent_id {
+        if !visited.insert(id.clone()) {
+            break;
+        }
+
+        let parent = api.get_file(&id).await.map_err(api_error)?;
+        parent_id = first_parent(&parent);
+
+        if parent_id.is_some() {
+            names.push(parent.name.unwrap_or_default());
+        }
+    }
+
+    names.reverse();
+    Ok(format!("/{}", names.join("/")))
+}
+
+fn first_parent(file: &File) -> Option<String> {
+    file.parents
+        .as_ref()
+        .and_then(|parents| parents.first())
+        .cloned()
+}
+
+/// A parent given with a leading slash is a path, anything else is a folder id
+pub fn is_path(parent: &str) -> bool {
+    parent.starts_with('/')
+}
+
+/// Resolves the paths among the parents to folder ids, ids are kept as they are.
+/// Missing directories are only created when create is set
+pub async fn resolve_parent_ids<A: DriveApi>(
+    api: &A,
+    parents: &[String],
+    create: bool,
+) -> Result<Vec<String>, PathResolutionError> {
+    let mut parent_ids = Vec::new();
+
+    for parent in parents {
+        if !is_path(parent) {
+            parent_ids.push(parent.clone());
+            continue;
+        }
+
+        let folder = resolve_directory(api, parent, create).await?;
+        if !drive_file::is_directory(&folder) {
+            return Err(PathResolutionError::NotADirectory(parent.clone()));
+        }
+
+        parent_ids.push(folder.id.ok_or(PathResolutionError::MissingId)?);
+    }
+
+    Ok(parent_ids)
+}
+
+/// A folder on a resolved path and whether it had to be created
+#[derive(Debug, Clone)]
+pub struct ResolvedFolder {
+    pub file: File,
+    pub created: bool,
+}
+
+/// Resolves a path, creating directories as needed, and returns every folder below root on the path
+pub async fn resolve_or_create_chain<A: DriveApi>(
+    api: &A,
+    path: &str,
+) -> Result<Vec<ResolvedFolder>, PathResolutionError> {
+    let parts: Vec<&str> = path.trim_matches('/').split('/').filter(|s| !s.is_empty()).collect();
+
+    let mut chain = Vec::new();
     let mut current_id = "root".to_string();
-    let mut current_file = files::info::get_file(hub, &current_id)
-        .await
-        .map_err(|e| PathResolutionError::ApiError(e.to_string()))?;
     
     for part in parts.iter() {
         // Try to find the folder
@@ -71,52 +258,50 @@ pub async fn resolve_or_create_path(hub: &Hub, path: &str) -> Result<File, PathR
             query: ListQuery::from_str(&query).map_err(|e| PathResolutionError::ApiError(e.to_string()))?,
             order_by: Default::default(),
             max_files: 1,
+            space: Default::default(),
         };
 
-        let files = files::list::list_files(hub, &config)
+        let files = api.list_files(&config)
             .await
-            .map_err(|e| PathResolutionError::ApiError(e.to_string()))?;
+            .map_err(api_error)?;
 
         if let Some(file) = files.into_iter().next() {
             current_id = file.id.clone().unwrap_or_default();
-            current_file = file;
+            chain.push(ResolvedFolder { file, created: false });
         } else {
-            // Folder not found, create it
-            let mkdir_config = files::mkdir::Config {
-                id: None,
-                name: part.to_string(),
-                parents: Some(vec![current_id]),
-                print_only_id: false,
-            };
-            
-            let new_folder = files::mkdir::create_directory(hub, &mkdir_config, Default::default())
+            // Folder not found, create it. The parent was either just resolved or created
+            let new_folder = api
+                .create_folder(part, &current_id)
                 .await
                 .map_err(|e| PathResolutionError::CreateDirectoryError(e.to_string()))?;
             
             current_id = new_folder.id.clone().ok_or(PathResolutionError::MissingId)?;
-            current_file = new_folder;
+            chain.push(ResolvedFolder { file: new_folder, created: true });
         }
     }
     
-    Ok(current_file)
+    Ok(chain)
 }
 
-/// Resolves a path that may contain wildcards and returns a list of matching files
-pub async fn resolve_wildcard_path(hub: &Hub, path: &str) -> Result<Vec<File>, PathResolutionError> {
-    // Split the path into directory parts and the wildcard part
+/// Resolves a path that may contain wildcards and returns a list of matching files. Folders
+/// before the first wildcard are resolved by name, the rest of the path is matched against
+/// everything below them, so `/projects/**/Makefile` finds a Makefile at any depth
+pub async fn resolve_wildcard_path<A: DriveApi>(api: &A, path: &str) -> Result<Vec<File>, PathResolutionError> {
     let parts: Vec<&str> = path.trim_matches('/').split('/').filter(|s| !s.is_empty()).collect();
     if parts.is_empty() {
         return Err(PathResolutionError::InvalidPath);
     }
     
-    // Check if the last part contains a wildcard
-    let (dir_parts, wildcard_part) = if parts.last().unwrap().contains('*') {
-        (&parts[0..parts.len()-1], parts.last().unwrap())
-    } else {
-        // If no wildcard, just resolve as a regular path
-        let file = resolve_path(hub, path).await?;
-        return Ok(vec![file]);
+    // Split the path into directory parts and the wildcard part
+    let (dir_parts, wildcard_parts) = match parts.iter().position(|part| has_wildcard(part)) {
+        Some(index) => parts.split_at(index),
+        None => {
+            // If no wildcard, just resolve as a regular path
+            let file = resolve_path(api, path).await?;
+            return Ok(vec![file]);
+        }
     };
+    let wildcard_part = wildcard_parts.join("/");
     
     // Navigate to the directory containing the wildcard
     let mut current_id = "root".to_string();
@@ -131,11 +316,12 @@ pub async fn resolve_wildcard_path(hub: &Hub, path: &str) -> Result<Vec<File>, P
             query: ListQuery::from_str(&query).map_err(|e| PathResolutionError::ApiError(e.to_string()))?,
             order_by: Default::default(),
             max_files: 1,
+            space: Default::default(),
         };
 
-        let files = files::list::list_files(hub, &config)
+        let files = api.list_files(&config)
             .await
-            .map_err(|e| PathResolutionError::ApiError(e.to_string()))?;
+            .map_err(api_error)?;
 
         if let Some(file) = files.into_iter().next() {
             current_id = file.id.clone().unwrap_or_default();
@@ -145,58 +331,175 @@ pub async fn resolve_wildcard_path(hub: &Hub, path: &str) -> Result<Vec<File>, P
     }
     
     // Convert wildcard to regex pattern
-    let wildcard_regex = wildcard_to_regex(wildcard_part);
+    let wildcard_regex = wildcard_to_regex(&wildcard_part);
     let regex = regex::Regex::new(&wildcard_regex)
         .map_err(|e| PathResolutionError::InvalidWildcard(e.to_string()))?;
     
-    // List all files in the directory and filter by the wildcard pattern
-    let query = format!("'{}' in parents and trashed = false", current_id);
-    let config = ListFilesConfig {
-        query: ListQuery::from_str(&query).map_err(|e| PathResolutionError::ApiError(e.to_string()))?,
-        order_by: Default::default(),
-        max_files: 1000, // Set a reasonable limit
+    // Without ** nothing deeper than the number of wildcard segments can match
+    let walk_config = WalkConfig {
+        filter: PathFilter {
+            max_depth: (!wildcard_part.contains("**")).then_some(wildcard_parts.len()),
+            ..Default::default()
+        },
+        ..Default::default()
     };
-    
-    let files = files::list::list_files(hub, &config)
+
+    // Walk the folders below the directory and filter by the wildcard pattern
+    let lister = ApiLister(api);
+    let matching_files: Vec<File> = remote_walk::walk_all(&lister, &current_id, &walk_config)
         .await
-        .map_err(|e| PathResolutionError::ApiError(e.to_string()))?;
-    
-    // Filter files by the wildcard pattern
-    let matching_files = files.into_iter()
-        .filter(|file| {
-            if let Some(name) = &file.name {
-                regex.is_match(name)
-            } else {
-                false
-            }
-        })
-        .collect::<Vec<_>>();
-    
+        .map_err(|e| PathResolutionError::ApiError(e.to_string()))?
+        .into_iter()
+        .filter(|entry| regex.is_match(&entry.path))
+        .map(|entry| entry.file)
+        .collect();
+
     if matching_files.is_empty() {
-        return Err(PathResolutionError::NoMatchesFound(wildcard_part.to_string()));
+        return Err(PathResolutionError::NoMatchesFound(wildcard_part));
     }
     
     Ok(matching_files)
 }
 
-/// Convert a wildcard pattern to a regex pattern
-fn wildcard_to_regex(pattern: &str) -> String {
-    let mut regex = String::from("^");
-    
-    for c in pattern.chars() {
-        match c {
-            '*' => regex.push_str(".*"),
-            '?' => regex.push('.'),
-            '.' | '+' | '(' | ')' | '[' | ']' | '{' | '}' | '\\' | '^' | '$' | '|' => {
-                regex.push('\\');
-                regex.push(c);
-            },
-            _ => regex.push(c),
+fn api_error(err: google_drive3::Error) -> PathResolutionError {
+    PathResolutionError::ApiError(drive_error::describe(&err))
+}
+
+/// Whether a path segment has to be matched as a wildcard instead of by name
+pub fn has_wildcard(s: &str) -> bool {
+    s.contains(['*', '?', '[', '{'])
+}
+
+/// Convert a wildcard pattern to a regex pattern. `*` and `?` stay within a path segment,
+/// `**` matches across segments and `**/` also matches no directory at all. `[abc]`, `[a-z]`
+/// and `[!abc]` match one character of a class, `{a,b}` matches either alternative and `\`
+/// escapes the next character. An unclosed `[` or `{` is matched literally.
+pub fn wildcard_to_regex(pattern: &str) -> String {
+    let mut translator = WildcardTranslator {
+        chars: pattern.chars().collect(),
+        pos: 0,
+    };
+
+    format!("^{}$", translator.sequence(false))
+}
+
+struct WildcardTranslator {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl WildcardTranslator {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    // Translates until the end of the pattern, or the end of the alternative inside braces
+    fn sequence(&mut self, in_braces: bool) -> String {
+        let mut regex = String::new();
+
+        while let Some(c) = self.peek() {
+            if in_braces && (c == ',' || c == '}') {
+                break;
+            }
+            self.pos += 1;
+
+            match c {
+                '*' if self.peek() == Some('*') => {
+                    self.pos += 1;
+                    if self.peek() == Some('/') {
+                        self.pos += 1;
+                        regex.push_str("(?:.*/)?");
+                    } else {
+                        regex.push_str(".*");
+                    }
+                }
+                '*' => regex.push_str("[^/]*"),
+                '?' => regex.push_str("[^/]"),
+                '[' => match self.class() {
+                    Some(class) => regex.push_str(&class),
+                    None => regex.push_str("\\["),
+                },
+                '{' => match self.alternatives() {
+                    Some(alternatives) => regex.push_str(&alternatives),
+                    None => regex.push_str("\\{"),
+                },
+                '\\' => match self.peek() {
+                    Some(escaped) => {
+                        self.pos += 1;
+                        regex.push_str(&regex::escape(&escaped.to_string()));
+                    }
+                    None => regex.push_str("\\\\"),
+                },
+                _ => regex.push_str(&regex::escape(&c.to_string())),
+            }
         }
+
+        regex
     }
-    
-    regex.push('$');
-    regex
+
+    // Called after '['. Returns None and consumes nothing if the class isn't closed
+    fn class(&mut self) -> Option<String> {
+        let mut i = self.pos;
+        let mut class = String::from("[");
+
+        if matches!(self.chars.get(i), Some('!') | Some('^')) {
+            // A negated class still doesn't cross a path segment
+            class.push_str("^/");
+            i += 1;
+        }
+
+        // A ']' right after the opening bracket is part of the class
+        let first = i;
+        loop {
+            let c = *self.chars.get(i)?;
+            match c {
+                ']' if i > first => break,
+                '\\' => {
+                    i += 1;
+                    push_class_char(&mut class, *self.chars.get(i)?);
+                }
+                '-' => class.push('-'),
+                _ => push_class_char(&mut class, c),
+            }
+            i += 1;
+        }
+
+        class.push(']');
+        self.pos = i + 1;
+        Some(class)
+    }
+
+    // Called after '{'. Returns None and consumes nothing if the braces aren't closed
+    fn alternatives(&mut self) -> Option<String> {
+        let start = self.pos;
+        let mut alternatives = Vec::new();
+
+        loop {
+            alternatives.push(self.sequence(true));
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => {
+                    self.pos = start;
+                    return None;
+                }
+            }
+        }
+
+        Some(format!("(?:{})", alternatives.join("|")))
+    }
+}
+
+// Only punctuation is escaped inside a class, an escaped letter like \d or \w would turn into
+// a class of its own
+fn push_class_char(class: &mut String, c: char) {
+    if c.is_ascii_punctuation() {
+        class.push('\\');
+    }
+    class.push(c);
 }
 
 #[derive(Debug)]
@@ -208,6 +511,7 @@ pub enum PathResolutionError {
     NoMatchesFound(String),
     CreateDirectoryError(String),
     MissingId,
+    NotADirectory(String),
 }
 
 impl std::error::Error for PathResolutionError {}
@@ -222,6 +526,7 @@ impl fmt::Display for PathResolutionError {
             Self::NoMatchesFound(pattern) => write!(f, "No files matching pattern: {}", pattern),
             Self::CreateDirectoryError(e) => write!(f, "Failed to create directory: {}", e),
             Self::MissingId => write!(f, "Created directory is missing ID"),
+            Self::NotADirectory(path) => write!(f, "'{}' exists but is not a directory", path),
         }
     }
 }
@@ -1,7 +1,10 @@
 use crate::common::delegate::UploadDelegateConfig;
-use crate::common::hub_helper;
+use crate::common::read_only;
 use crate::files;
+use crate::files::list::ListFilesConfig;
+use crate::files::list::ListQuery;
 use crate::files::update::PatchFile;
+use crate::hub::Hub;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
@@ -10,16 +13,23 @@ use std::fmt::Formatter;
 pub struct Config {
     pub file_id: String,
     pub name: String,
+    // Rename even if a file with the new name already exists in the same folder
+    pub force: bool,
 }
 
-pub async fn rename(config: Config) -> Result<(), Error> {
-    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+pub async fn rename(hub: &Hub, config: Config) -> Result<(), Error> {
+    read_only::err_if_enabled("rename files").map_err(Error::ReadOnly)?;
+
     let delegate_config = UploadDelegateConfig::default();
 
-    let old_file = files::info::get_file(&hub, &config.file_id)
+    let old_file = files::info::get_file(hub, &config.file_id)
         .await
         .map_err(Error::GetFile)?;
 
+    if !config.force {
+        err_if_name_taken(hub, &old_file, &config.name).await?;
+    }
+
     println!(
         "Renaming {} to {}",
         old_file.name.unwrap_or_default(),
@@ -28,17 +38,67 @@ pub async fn rename(config: Config) -> Result<(), Error> {
 
     let patch_file = PatchFile::new(config.file_id).with_name(&config.name);
 
-    files::update::update_metadata(&hub, delegate_config, patch_file)
+    files::update::update_metadata(hub, delegate_config, patch_file)
         .await
         .map_err(Error::Rename)?;
 
     Ok(())
 }
 
+// Drive allows several files with the same name in a folder, which is rarely intended
+async fn err_if_name_taken(
+    hub: &Hub,
+    file: &google_drive3::api::File,
+    name: &str,
+) -> Result<(), Error> {
+    let file_id = file.id.clone().unwrap_or_default();
+
+    for parent_id in file.parents.clone().unwrap_or_default() {
+        let query = format!(
+            "'{}' in parents and name = '{}' and trashed = false",
+            parent_id,
+            files::list::escape_query_value(name)
+        );
+
+        let list_config = ListFilesConfig {
+            query: ListQuery::Custom(query),
+            order_by: Default::default(),
+            max_files: 2,
+            space: Default::default(),
+        };
+
+        let siblings = files::list::list_files(hub, &list_config)
+            .await
+            .map_err(Error::ListSiblings)?;
+
+        if let Some(sibling) = find_name_conflict(&file_id, name, &siblings) {
+            return Err(Error::NameTaken {
+                name: name.to_string(),
+                existing_id: sibling.id.clone().unwrap_or_default(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+// A sibling other than the renamed file itself that already has the name
+pub fn find_name_conflict<'a>(
+    file_id: &str,
+    name: &str,
+    siblings: &'a [google_drive3::api::File],
+) -> Option<&'a google_drive3::api::File> {
+    siblings.iter().find(|sibling| {
+        sibling.id.as_deref() != Some(file_id) && sibling.name.as_deref() == Some(name)
+    })
+}
+
 #[derive(Debug)]
 pub enum Error {
-    Hub(hub_helper::Error),
+    ReadOnly(read_only::Error),
     GetFile(google_drive3::Error),
+    ListSiblings(files::list::Error),
+    NameTaken { name: String, existing_id: String },
     Rename(google_drive3::Error),
 }
 
@@ -47,10 +107,18 @@ impl error::Error for Error {}
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::Hub(err) => write!(f, "{}", err),
+            Error::ReadOnly(err) => write!(f, "{}", err),
             Error::GetFile(err) => {
                 write!(f, "Failed to get file: {}", err)
             }
+            Error::ListSiblings(err) => {
+                write!(f, "Failed to check the folder for files with the same name: {}", err)
+            }
+            Error::NameTaken { name, existing_id } => write!(
+                f,
+                "A file named '{}' already exists in the same folder ({}), use --force to rename anyway",
+                name, existing_id
+            ),
             Error::Rename(err) => {
                 write!(f, "Failed to rename file: {}", err)
             }
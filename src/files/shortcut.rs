@@ -0,0 +1,111 @@
+use crate::common::delegate::UploadDelegate;
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::drive_file::MIME_TYPE_DRIVE_SHORTCUT;
+use crate::common::empty_file::EmptyFile;
+use crate::common::read_only;
+use crate::files::path_utils;
+use crate::hub::Hub;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    // File id or path of the file the shortcut points to
+    pub target: String,
+    pub name: String,
+    // Folder ids or paths, the shortcut is created in the root folder without them
+    pub parents: Option<Vec<String>>,
+    pub print_only_id: bool,
+}
+
+pub async fn shortcut(hub: &Hub, config: Config) -> Result<(), Error> {
+    read_only::err_if_enabled("create shortcuts").map_err(Error::ReadOnly)?;
+
+    let target_id = path_utils::resolve_file_id(hub, &config.target)
+        .await
+        .map_err(Error::ResolvePath)?;
+
+    let parents = match &config.parents {
+        Some(parents) => Some(
+            path_utils::resolve_parent_ids(hub, parents, false)
+                .await
+                .map_err(Error::ResolvePath)?,
+        ),
+        None => None,
+    };
+
+    let file = create_shortcut(hub, &target_id, &config.name, parents)
+        .await
+        .map_err(Error::CreateShortcut)?;
+
+    if config.print_only_id {
+        print!("{}", file.id.unwrap_or_default())
+    } else {
+        println!(
+            "Created shortcut '{}' to {} with id: {}",
+            config.name,
+            target_id,
+            file.id.unwrap_or_default()
+        );
+    }
+
+    Ok(())
+}
+
+pub async fn create_shortcut(
+    hub: &Hub,
+    target_id: &str,
+    name: &str,
+    parents: Option<Vec<String>>,
+) -> Result<google_drive3::api::File, google_drive3::Error> {
+    let dst_file = google_drive3::api::File {
+        name: Some(name.to_string()),
+        parents,
+        mime_type: Some(MIME_TYPE_DRIVE_SHORTCUT.to_string()),
+        shortcut_details: Some(google_drive3::api::FileShortcutDetails {
+            target_id: Some(target_id.to_string()),
+            ..google_drive3::api::FileShortcutDetails::default()
+        }),
+        ..google_drive3::api::File::default()
+    };
+
+    let mut delegate = UploadDelegate::new(UploadDelegateConfig::default());
+
+    let req = hub
+        .files()
+        .create(dst_file)
+        .param(
+            "fields",
+            "id,name,mimeType,parents,shortcutDetails(targetId,targetMimeType)",
+        )
+        .add_scope(google_drive3::api::Scope::Full)
+        .delegate(&mut delegate)
+        .supports_all_drives(true);
+
+    let empty_file = EmptyFile();
+    let mime_type: mime::Mime = MIME_TYPE_DRIVE_SHORTCUT.parse().unwrap();
+
+    let (_, file) = req.upload(empty_file, mime_type).await?;
+
+    Ok(file)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    ReadOnly(read_only::Error),
+    ResolvePath(path_utils::PathResolutionError),
+    CreateShortcut(google_drive3::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::ReadOnly(err) => write!(f, "{}", err),
+            Error::ResolvePath(err) => write!(f, "Failed to resolve path: {}", err),
+            Error::CreateShortcut(err) => write!(f, "Failed to create shortcut: {}", err),
+        }
+    }
+}
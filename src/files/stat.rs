@@ -0,0 +1,184 @@
+use crate::common::time_fmt;
+use crate::common::time_fmt::TimeStyle;
+use crate::files::info;
+use crate::files::path_utils;
+use crate::hub::Hub;
+use std::error;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::str::FromStr;
+
+pub struct Config {
+    pub file_id: String,
+    pub format: StatFormat,
+    pub field_separator: String,
+}
+
+pub async fn stat(hub: &Hub, config: Config) -> Result<(), Error> {
+    let file_id = path_utils::resolve_file_id(hub, &config.file_id)
+        .await
+        .map_err(Error::ResolvePath)?;
+
+    let file = info::get_file_with_fields(hub, &file_id, &config.format.api_fields())
+        .await
+        .map_err(Error::GetFile)?;
+
+    println!("{}", config.format.format_line(&file, &config.field_separator));
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatField {
+    Id,
+    Name,
+    Size,
+    MimeType,
+    ModifiedTime,
+    CreatedTime,
+    Md5Checksum,
+    Parents,
+}
+
+const ALL_FIELDS: [StatField; 8] = [
+    StatField::Id,
+    StatField::Name,
+    StatField::Size,
+    StatField::MimeType,
+    StatField::ModifiedTime,
+    StatField::CreatedTime,
+    StatField::Md5Checksum,
+    StatField::Parents,
+];
+
+impl StatField {
+    // Field name in the Drive api, also used in --format
+    pub fn api_name(&self) -> &'static str {
+        match self {
+            StatField::Id => "id",
+            StatField::Name => "name",
+            StatField::Size => "size",
+            StatField::MimeType => "mimeType",
+            StatField::ModifiedTime => "modifiedTime",
+            StatField::CreatedTime => "createdTime",
+            StatField::Md5Checksum => "md5Checksum",
+            StatField::Parents => "parents",
+        }
+    }
+
+    // Sizes are in bytes and times in RFC 3339, missing values are empty
+    pub fn value(&self, file: &google_drive3::api::File) -> String {
+        match self {
+            StatField::Id => file.id.clone().unwrap_or_default(),
+            StatField::Name => file.name.clone().unwrap_or_default(),
+            StatField::Size => file.size.map(|size| size.to_string()).unwrap_or_default(),
+            StatField::MimeType => file.mime_type.clone().unwrap_or_default(),
+            StatField::ModifiedTime => file
+                .modified_time
+                .map(|time| time_fmt::format_time(time, &TimeStyle::Iso))
+                .unwrap_or_default(),
+            StatField::CreatedTime => file
+                .created_time
+                .map(|time| time_fmt::format_time(time, &TimeStyle::Iso))
+                .unwrap_or_default(),
+            StatField::Md5Checksum => file.md5_checksum.clone().unwrap_or_default(),
+            StatField::Parents => file.parents.clone().unwrap_or_default().join(","),
+        }
+    }
+}
+
+impl FromStr for StatField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ALL_FIELDS
+            .iter()
+            .find(|field| field.api_name() == s)
+            .copied()
+            .ok_or_else(|| {
+                let valid: Vec<&str> = ALL_FIELDS.iter().map(StatField::api_name).collect();
+                format!(
+                    "'{}' is not a valid field, valid fields are: {}",
+                    s,
+                    valid.join(", ")
+                )
+            })
+    }
+}
+
+impl Display for StatField {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.api_name())
+    }
+}
+
+// Comma separated list of the fields to print, in order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatFormat(pub Vec<StatField>);
+
+impl Default for StatFormat {
+    fn default() -> Self {
+        StatFormat(vec![
+            StatField::Id,
+            StatField::Name,
+            StatField::Size,
+            StatField::MimeType,
+            StatField::ModifiedTime,
+        ])
+    }
+}
+
+impl StatFormat {
+    pub fn api_fields(&self) -> String {
+        let names: Vec<&str> = self.0.iter().map(StatField::api_name).collect();
+        names.join(",")
+    }
+
+    pub fn format_line(&self, file: &google_drive3::api::File, separator: &str) -> String {
+        let values: Vec<String> = self.0.iter().map(|field| field.value(file)).collect();
+        values.join(separator)
+    }
+}
+
+impl FromStr for StatFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = s
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(StatField::from_str)
+            .collect::<Result<Vec<StatField>, String>>()?;
+
+        if fields.is_empty() {
+            return Err(String::from("format needs at least one field"));
+        }
+
+        Ok(StatFormat(fields))
+    }
+}
+
+impl Display for StatFormat {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.api_fields())
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    GetFile(google_drive3::Error),
+    ResolvePath(path_utils::PathResolutionError),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::GetFile(err) => write!(f, "Failed getting file: {}", err),
+            Error::ResolvePath(err) => write!(f, "{}", err),
+        }
+    }
+}
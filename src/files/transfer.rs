@@ -0,0 +1,464 @@
+use crate::common::byte_size;
+use crate::common::cancel;
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::drive_file;
+use crate::common::drive_file::DocType;
+use crate::common::file_info::FileInfo;
+use crate::common::read_only;
+use crate::common::remote_walk;
+use crate::common::remote_walk::DriveLister;
+use crate::common::remote_walk::WalkConfig;
+use crate::files;
+use crate::files::download;
+use crate::files::export;
+use crate::files::import;
+use crate::files::list;
+use crate::files::list::Space;
+use crate::files::mkdir;
+use crate::files::path_utils;
+use crate::files::upload;
+use crate::hub::Hub;
+use futures::TryStreamExt;
+use mime::Mime;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::pin::pin;
+
+pub struct Config {
+    // File or folder id, or path starting with '/', in the source account
+    pub source: String,
+    // Folder path in the destination account, missing folders are created
+    pub destination: String,
+    // Don't transfer files that already exist in the destination, to resume a failed transfer
+    pub skip_existing: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    pub transferred: usize,
+    pub transferred_size: u64,
+    pub skipped: usize,
+    pub unsupported: usize,
+}
+
+impl Report {
+    pub fn summary(&self) -> String {
+        format!(
+            "Transferred {} files ({}), skipped {} existing and {} unsupported files",
+            self.transferred,
+            byte_size::format_human(self.transferred_size as u128),
+            self.skipped,
+            self.unsupported
+        )
+    }
+}
+
+// Copies a file or folder from the account of src_hub to the account of dst_hub. Every file
+// passes through a temporary file, google documents are exported to office formats and
+// converted back on upload.
+pub async fn transfer(src_hub: &Hub, dst_hub: &Hub, config: Config) -> Result<(), Error> {
+    read_only::err_if_enabled("transfer files").map_err(Error::ReadOnly)?;
+
+    let source_id = path_utils::resolve_file_id(src_hub, &config.source)
+        .await
+        .map_err(Error::ResolveSource)?;
+    let source = files::info::get_file(src_hub, &source_id)
+        .await
+        .map_err(Error::GetFile)?;
+
+    let destination = path_utils::resolve_directory(dst_hub, &config.destination, true)
+        .await
+        .map_err(Error::ResolveDestination)?;
+    if !drive_file::is_directory(&destination) {
+        return Err(Error::DestinationNotFolder(config.destination.clone()));
+    }
+
+    let tmp_dir = tempfile::tempdir().map_err(Error::Tempdir)?;
+    let mut report = Report::default();
+
+    let destination_id = destination.id.unwrap_or_default();
+    let result = transfer_tree(
+        src_hub,
+        dst_hub,
+        &config,
+        source,
+        destination_id,
+        tmp_dir.path(),
+        &mut report,
+    )
+    .await;
+
+    if result.is_err() && report.transferred > 0 {
+        eprintln!("{}", report.summary());
+        eprintln!("Run the command again with --skip-existing to continue where it stopped");
+    }
+    result?;
+
+    println!("{}", report.summary());
+    Ok(())
+}
+
+// The source is walked breadth first, so every folder is created in the destination before
+// the files in it are transferred
+async fn transfer_tree(
+    src_hub: &Hub,
+    dst_hub: &Hub,
+    config: &Config,
+    source: google_drive3::api::File,
+    destination_id: String,
+    tmp_dir: &Path,
+    report: &mut Report,
+) -> Result<(), Error> {
+    let mut destination = Destination {
+        hub: dst_hub,
+        skip_existing: config.skip_existing,
+        listings: HashMap::new(),
+    };
+
+    let source_id = source.id.clone().unwrap_or_default();
+    let root_path = PathBuf::from(source.name.clone().unwrap_or_default());
+
+    if !drive_file::is_directory(&source) {
+        let target = Target {
+            parent_id: &destination_id,
+            path: &root_path,
+            tmp_dir,
+        };
+        return transfer_entry(src_hub, &mut destination, &source, target, report).await;
+    }
+
+    let root_id = destination
+        .folder(&source, &destination_id, &root_path)
+        .await?;
+
+    // Relative source path of each folder to the id of its copy, folders come before their
+    // contents in the walk
+    let mut folder_ids = HashMap::from([(String::new(), root_id)]);
+
+    let lister = DriveLister::new(src_hub, Space::Drive);
+    let walk_config = WalkConfig::default();
+    let mut entries = pin!(remote_walk::walk(&lister, &source_id, &walk_config));
+
+    while let Some(entry) = entries.try_next().await.map_err(Error::Walk)? {
+        // The summary of what was transferred is printed by transfer
+        if cancel::is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+
+        let parent_id = folder_ids
+            .get(entry.parent_path())
+            .cloned()
+            .unwrap_or_default();
+        let path = root_path.join(&entry.path);
+
+        if entry.is_folder() {
+            let folder_id = destination.folder(&entry.file, &parent_id, &path).await?;
+            folder_ids.insert(entry.path.clone(), folder_id);
+        } else {
+            let target = Target {
+                parent_id: &parent_id,
+                path: &path,
+                tmp_dir,
+            };
+            transfer_entry(src_hub, &mut destination, &entry.file, target, report).await?;
+        }
+    }
+
+    Ok(())
+}
+
+// Where a file of the source is copied to
+struct Target<'a> {
+    parent_id: &'a str,
+    path: &'a Path,
+    tmp_dir: &'a Path,
+}
+
+async fn transfer_entry(
+    src_hub: &Hub,
+    destination: &mut Destination<'_>,
+    file: &google_drive3::api::File,
+    target: Target<'_>,
+    report: &mut Report,
+) -> Result<(), Error> {
+    let path = target.path;
+
+    if destination.find(file, target.parent_id).await?.is_some() {
+        println!("Skipping '{}', it already exists", path.display());
+        report.skipped += 1;
+        return Ok(());
+    }
+
+    let transferred = transfer_file(
+        src_hub,
+        destination.hub,
+        file,
+        target.parent_id,
+        path,
+        target.tmp_dir,
+    )
+    .await?;
+
+    match transferred {
+        Some(size) => {
+            println!(
+                "Transferred '{}' ({})",
+                path.display(),
+                byte_size::format_human(size as u128)
+            );
+            report.transferred += 1;
+            report.transferred_size += size;
+        }
+        None => {
+            println!(
+                "Skipping '{}', files of type {} can't be transferred",
+                path.display(),
+                file.mime_type.clone().unwrap_or_default()
+            );
+            report.unsupported += 1;
+        }
+    }
+
+    Ok(())
+}
+
+// The destination account, with the folders it already has listed once when existing files
+// are skipped
+struct Destination<'a> {
+    hub: &'a Hub,
+    skip_existing: bool,
+    listings: HashMap<String, Vec<google_drive3::api::File>>,
+}
+
+impl Destination<'_> {
+    // Id of the copy of the folder, which is created unless it exists and is reused
+    async fn folder(
+        &mut self,
+        folder: &google_drive3::api::File,
+        parent_id: &str,
+        path: &Path,
+    ) -> Result<String, Error> {
+        let folder_id = match self.find(folder, parent_id).await? {
+            Some(existing) => existing.id.unwrap_or_default(),
+            None => {
+                let name = folder.name.clone().unwrap_or_default();
+                create_folder(self.hub, &name, parent_id)
+                    .await
+                    .map_err(|err| Error::CreateFolder(path.to_path_buf(), err))?
+            }
+        };
+
+        println!("Transferring folder '{}'", path.display());
+        Ok(folder_id)
+    }
+
+    // A file or folder with the same name and kind in the destination folder, only looked for
+    // with skip_existing
+    async fn find(
+        &mut self,
+        file: &google_drive3::api::File,
+        parent_id: &str,
+    ) -> Result<Option<google_drive3::api::File>, Error> {
+        if !self.skip_existing {
+            return Ok(None);
+        }
+
+        let existing = match self.listings.entry(parent_id.to_string()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let files = list::list_folder(self.hub, parent_id)
+                    .await
+                    .map_err(Error::ListFiles)?;
+                entry.insert(files)
+            }
+        };
+
+        let is_directory = drive_file::is_directory(file);
+        let found = existing.iter().find(|existing| {
+            existing.name == file.name && drive_file::is_directory(existing) == is_directory
+        });
+
+        Ok(found.cloned())
+    }
+}
+
+// Returns the size of the transferred content, or None when the file has no content that can
+// be copied, like shortcuts and forms
+async fn transfer_file(
+    src_hub: &Hub,
+    dst_hub: &Hub,
+    file: &google_drive3::api::File,
+    parent_id: &str,
+    path: &Path,
+    tmp_dir: &Path,
+) -> Result<Option<u64>, Error> {
+    let file_id = file.id.clone().unwrap_or_default();
+    let tmp_path = tmp_dir.join(&file_id);
+    let doc_type = file.mime_type.as_deref().and_then(DocType::from_mime_type);
+
+    let (body, mime_type, doc_mime, md5) = if drive_file::is_binary(file) {
+        let body = download::download_file(src_hub, &file_id)
+            .await
+            .map_err(|err| Error::Download(path.to_path_buf(), err))?;
+        let mime_type = file
+            .mime_type
+            .as_deref()
+            .and_then(|mime| mime.parse::<Mime>().ok())
+            .unwrap_or(mime::APPLICATION_OCTET_STREAM);
+
+        (body, mime_type, None, file.md5_checksum.clone())
+    } else if let Some(doc_type) = doc_type {
+        let (export_mime, doc_mime) = match (
+            doc_type.default_export_type().get_export_mime(),
+            doc_type.mime(),
+        ) {
+            (Some(export_mime), Some(doc_mime)) => (export_mime, doc_mime),
+            _ => return Ok(None),
+        };
+        let body = export::export_file(src_hub, &file_id, &export_mime)
+            .await
+            .map_err(|err| Error::Export(path.to_path_buf(), err))?;
+
+        (body, export_mime, Some(doc_mime), None)
+    } else {
+        return Ok(None);
+    };
+
+    download::save_body_to_file(body, &tmp_path, md5)
+        .await
+        .map_err(|err| Error::SaveFile(path.to_path_buf(), err))?;
+
+    let result = upload_content(dst_hub, file, parent_id, &tmp_path, mime_type, doc_mime).await;
+    let _ = fs::remove_file(&tmp_path);
+
+    result
+        .map(Some)
+        .map_err(|err| Error::Upload(path.to_path_buf(), err))
+}
+
+async fn upload_content(
+    dst_hub: &Hub,
+    file: &google_drive3::api::File,
+    parent_id: &str,
+    tmp_path: &Path,
+    mime_type: Mime,
+    doc_mime: Option<Mime>,
+) -> Result<u64, UploadError> {
+    let content = fs::File::open(tmp_path).map_err(UploadError::OpenFile)?;
+    let size = content.metadata().map_err(UploadError::OpenFile)?.len();
+
+    let file_info = FileInfo {
+        name: file.name.clone().unwrap_or_default(),
+        mime_type,
+        parents: Some(vec![parent_id.to_string()]),
+        size,
+        modified_time: None,
+    };
+    let delegate_config = UploadDelegateConfig::default();
+
+    match doc_mime {
+        Some(doc_mime) => {
+            import::create_document(dst_hub, content, file_info, &doc_mime, delegate_config).await
+        }
+        None => upload::upload_file(dst_hub, content, None, file_info, delegate_config).await,
+    }
+    .map_err(UploadError::Drive)?;
+
+    Ok(size)
+}
+
+async fn create_folder(
+    hub: &Hub,
+    name: &str,
+    parent_id: &str,
+) -> Result<String, google_drive3::Error> {
+    let config = mkdir::Config {
+        id: None,
+        name: name.to_string(),
+        parents: Some(vec![parent_id.to_string()]),
+        print_only_id: false,
+        // The parent is the destination or a folder created by the transfer
+        skip_parent_check: true,
+        create_parents: false,
+    };
+
+    let folder = mkdir::create_directory(hub, &config, UploadDelegateConfig::default()).await?;
+    Ok(folder.id.unwrap_or_default())
+}
+
+#[derive(Debug)]
+pub enum UploadError {
+    OpenFile(io::Error),
+    Drive(google_drive3::Error),
+}
+
+impl Display for UploadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UploadError::OpenFile(err) => write!(f, "Failed to open temporary file: {}", err),
+            UploadError::Drive(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    ReadOnly(read_only::Error),
+    ResolveSource(path_utils::PathResolutionError),
+    GetFile(google_drive3::Error),
+    ResolveDestination(path_utils::PathResolutionError),
+    DestinationNotFolder(String),
+    Tempdir(io::Error),
+    ListFiles(files::list::Error),
+    Walk(remote_walk::Error),
+    CreateFolder(PathBuf, google_drive3::Error),
+    Download(PathBuf, google_drive3::Error),
+    Export(PathBuf, google_drive3::Error),
+    SaveFile(PathBuf, download::Error),
+    Upload(PathBuf, UploadError),
+    Cancelled,
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::ReadOnly(err) => write!(f, "{}", err),
+            Error::ResolveSource(err) => write!(f, "Failed to resolve source: {}", err),
+            Error::GetFile(err) => write!(f, "Failed to get source file: {}", err),
+            Error::ResolveDestination(err) => {
+                write!(f, "Failed to resolve destination: {}", err)
+            }
+            Error::DestinationNotFolder(path) => {
+                write!(f, "Destination '{}' is not a folder", path)
+            }
+            Error::Tempdir(err) => write!(f, "Failed to create temporary directory: {}", err),
+            Error::ListFiles(err) => write!(f, "Failed to list files: {}", err),
+            Error::Walk(err) => write!(f, "{}", err),
+            Error::CreateFolder(path, err) => {
+                write!(f, "Failed to create folder '{}': {}", path.display(), err)
+            }
+            Error::Download(path, err) => {
+                write!(f, "Failed to download '{}': {}", path.display(), err)
+            }
+            Error::Export(path, err) => {
+                write!(f, "Failed to export '{}': {}", path.display(), err)
+            }
+            Error::SaveFile(path, err) => {
+                write!(f, "Failed to save '{}': {}", path.display(), err)
+            }
+            Error::Upload(path, err) => {
+                write!(f, "Failed to upload '{}': {}", path.display(), err)
+            }
+            Error::Cancelled => write!(f, "Transfer was cancelled"),
+        }
+    }
+}
@@ -1,14 +1,19 @@
-use crate::common::delegate::BackoffConfig;
+use crate::common::delegate;
 use crate::common::delegate::ChunkSize;
 use crate::common::delegate::UploadDelegate;
 use crate::common::delegate::UploadDelegateConfig;
+use crate::common::delegate::UploadMode;
+use crate::common::drive_error;
+use crate::common::drive_file;
 use crate::common::file_info;
+use crate::common::file_json::FileJson;
 use crate::common::file_info::FileInfo;
 use crate::common::file_helper;
-use crate::common::hub_helper;
+use crate::common::read_only;
 use crate::files;
 use crate::files::info;
 use crate::files::info::DisplayConfig;
+use crate::files::path_utils;
 use crate::hub::Hub;
 use mime::Mime;
 use std::error;
@@ -19,37 +24,63 @@ use std::path::PathBuf;
 use std::time::Duration;
 
 pub struct Config {
+    // File id, or a path starting with '/'
     pub file_id: String,
     pub file_path: Option<PathBuf>,
     pub mime_type: Option<Mime>,
     pub chunk_size: ChunkSize,
     pub print_chunk_errors: bool,
     pub print_chunk_info: bool,
+    pub error_log: Option<PathBuf>,
+    pub truncate_error_log: bool,
+    pub max_retry_duration: Duration,
+    pub upload_mode: UploadMode,
+    pub json: bool,
+    pub print_only_id: bool,
+    // Renames the file, otherwise it keeps its remote name
+    pub name: Option<String>,
 }
 
-pub async fn update(config: Config) -> Result<(), Error> {
-    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+impl Config {
+    // Progress messages are left out when only the id or json is printed
+    fn is_quiet(&self) -> bool {
+        self.print_only_id || self.json
+    }
+}
 
-    let delegate_config = UploadDelegateConfig {
-        chunk_size: config.chunk_size,
-        backoff_config: BackoffConfig {
-            max_retries: 20,
-            min_sleep: Duration::from_secs(1),
-            max_sleep: Duration::from_secs(60),
-        },
-        print_chunk_errors: config.print_chunk_errors,
-        print_chunk_info: config.print_chunk_info,
-    };
+pub async fn update(hub: &Hub, config: Config) -> Result<(), Error> {
+    read_only::err_if_enabled("update files").map_err(Error::ReadOnly)?;
+
+    let delegate_config = UploadDelegateConfig::from_flags(
+        config.chunk_size.clone(),
+        config.max_retry_duration,
+        config.print_chunk_errors,
+        config.print_chunk_info,
+        config.error_log.clone(),
+        config.upload_mode.clone(),
+    );
+
+    if let Some(path) = config.error_log.as_ref().filter(|_| config.truncate_error_log) {
+        delegate::truncate_error_log(path).map_err(|err| Error::ErrorLog(path.clone(), err))?;
+    }
 
     let (file, file_path) = file_helper::open_file(&config.file_path)
         .map_err(|err| Error::OpenFile(
             config.file_path.unwrap_or_else(|| PathBuf::from("<stdin>")), err))?;
 
-    let drive_file = info::get_file(&hub, &config.file_id)
+    let file_id = path_utils::resolve_file_id(hub, &config.file_id)
+        .await
+        .map_err(Error::ResolvePath)?;
+
+    let drive_file = info::get_file(hub, &file_id)
         .await
         .map_err(Error::GetFile)?;
 
-    let file_info = FileInfo::from_file(
+    if drive_file::is_directory(&drive_file) {
+        return Err(Error::IsDirectory(config.file_id.clone()));
+    }
+
+    let mut file_info = FileInfo::from_file(
         &file,
         &file_info::Config {
             file_path: file_path.clone(),
@@ -59,22 +90,35 @@ pub async fn update(config: Config) -> Result<(), Error> {
     )
     .map_err(Error::FileInfo)?;
 
+    if let Some(name) = config.name.clone().or(drive_file.name) {
+        file_info.name = name;
+    }
+
     let reader = std::io::BufReader::new(file);
 
-    println!(
-        "Updating {} with {}",
-        config.file_id,
-        file_path.display()
-    );
+    if !config.is_quiet() {
+        println!(
+            "Updating {} with {}",
+            config.file_id,
+            file_path.display()
+        );
+    }
 
-    let file = update_file(&hub, reader, &config.file_id, file_info, delegate_config)
+    let file = update_file(hub, reader, &file_id, file_info, delegate_config)
         .await
-        .map_err(Error::Update)?;
+        .map_err(update_error)?;
 
-    println!("File successfully updated");
+    if config.json {
+        let json = serde_json::to_string(&FileJson::from(&file)).map_err(Error::SerializeJson)?;
+        println!("{}", json);
+    } else if config.print_only_id {
+        print!("{}", file.id.as_deref().unwrap_or_default())
+    } else {
+        println!("File successfully updated");
 
-    let fields = files::info::prepare_fields(&file, &DisplayConfig::default());
-    files::info::print_fields(&fields);
+        let fields = files::info::prepare_fields(&file, &DisplayConfig::default());
+        files::info::print_fields(&fields);
+    }
 
     Ok(())
 }
@@ -91,9 +135,11 @@ where
 {
     let dst_file = google_drive3::api::File {
         name: Some(file_info.name),
+        modified_time: file_info.modified_time,
         ..google_drive3::api::File::default()
     };
 
+    let resumable = delegate_config.is_resumable(file_info.size);
     let mut delegate = UploadDelegate::new(delegate_config);
 
     let req = hub
@@ -104,7 +150,7 @@ where
         .delegate(&mut delegate)
         .supports_all_drives(true);
 
-    let (_, file) = if file_info.size > 0 {
+    let (_, file) = if resumable {
         req.upload_resumable(src_file, file_info.mime_type).await?
     } else {
         req.upload(src_file, file_info.mime_type).await?
@@ -134,11 +180,23 @@ pub async fn update_metadata(
 
 #[derive(Debug)]
 pub enum Error {
-    Hub(hub_helper::Error),
+    ReadOnly(read_only::Error),
     FileInfo(file_info::Error),
     OpenFile(PathBuf, io::Error),
     GetFile(google_drive3::Error),
+    ResolvePath(path_utils::PathResolutionError),
+    IsDirectory(String),
     Update(google_drive3::Error),
+    Quota(drive_error::QuotaError),
+    ErrorLog(PathBuf, io::Error),
+    SerializeJson(serde_json::Error),
+}
+
+fn update_error(err: google_drive3::Error) -> Error {
+    match drive_error::quota_error(&err) {
+        Some(quota_error) => Error::Quota(quota_error),
+        None => Error::Update(err),
+    }
 }
 
 impl error::Error for Error {}
@@ -146,13 +204,24 @@ impl error::Error for Error {}
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::Hub(err) => write!(f, "{}", err),
+            Error::ReadOnly(err) => write!(f, "{}", err),
             Error::FileInfo(err) => write!(f, "{}", err),
             Error::OpenFile(path, err) => {
                 write!(f, "Failed to open file '{}': {}", path.display(), err)
             }
             Error::GetFile(err) => write!(f, "Failed to get file: {}", err),
+            Error::ResolvePath(err) => write!(f, "Failed to resolve path: {}", err),
+            Error::IsDirectory(file) => write!(
+                f,
+                "'{}' is a directory, only the content of files can be updated",
+                file
+            ),
             Error::Update(err) => write!(f, "Failed to update file: {}", err),
+            Error::Quota(err) => write!(f, "Failed to update file: {}", err),
+            Error::ErrorLog(path, err) => {
+                write!(f, "Failed to truncate error log '{}': {}", path.display(), err)
+            }
+            Error::SerializeJson(err) => write!(f, "Failed to serialize json: {}", err),
         }
     }
 }
@@ -181,6 +250,16 @@ impl PatchFile {
         }
     }
 
+    pub fn with_trashed(&self, trashed: bool) -> Self {
+        Self {
+            file: google_drive3::api::File {
+                trashed: Some(trashed),
+                ..self.file.clone()
+            },
+            ..self.clone()
+        }
+    }
+
     pub fn id(&self) -> String {
         self.id.clone()
     }
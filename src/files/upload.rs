@@ -1,19 +1,38 @@
-use crate::common::delegate::BackoffConfig;
+use crate::common::byte_size;
+use crate::common::cancel;
+use crate::common::delegate;
 use crate::common::delegate::ChunkSize;
 use crate::common::delegate::UploadDelegate;
 use crate::common::delegate::UploadDelegateConfig;
+use crate::common::delegate::UploadMode;
+use crate::common::drive_error;
+use crate::common::drive_file;
 use crate::common::file_info;
+use crate::common::file_json::FileJson;
 use crate::common::file_info::FileInfo;
 use crate::common::file_tree;
 use crate::common::file_tree::FileTree;
-use crate::common::hub_helper;
+use crate::common::file_tree::SpecialFile;
+use crate::common::git_mtime::GitMtimes;
+use crate::common::read_only;
 use crate::common::id_gen::IdGen;
+use crate::common::parent_check;
+use crate::common::upload_manifest;
+use crate::common::upload_manifest::FailedFile;
+use crate::common::upload_manifest::UploadManifest;
 use crate::files;
+use crate::files::dedupe_check;
+use crate::files::dedupe_check::ChecksumIndex;
+use crate::files::dedupe_check::Decision;
+use crate::files::dedupe_check::DedupeAction;
 use crate::files::info::DisplayConfig;
 use crate::files::path_utils;
 use crate::hub::Hub;
-use human_bytes::human_bytes;
+use google_drive3::chrono::DateTime;
+use google_drive3::chrono::Utc;
 use mime::Mime;
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
@@ -23,6 +42,11 @@ use std::path::Path;
 use std::path::PathBuf;
 use std::time::Duration;
 
+const UPLOADED_FILE_FIELDS: &str = "id,name,size,createdTime,modifiedTime,md5Checksum,mimeType,parents,shared,description,webContentLink,webViewLink";
+
+// Error recorded for the files that were skipped after Ctrl-C
+const CANCELLED: &str = "not uploaded, cancelled";
+
 pub struct Config {
     pub file_path: PathBuf,
     pub mime_type: Option<Mime>,
@@ -32,39 +56,299 @@ pub struct Config {
     pub print_chunk_info: bool,
     pub upload_directories: bool,
     pub print_only_id: bool,
+    pub error_log: Option<PathBuf>,
+    pub truncate_error_log: bool,
+    pub max_retry_duration: Duration,
+    pub upload_mode: UploadMode,
+    pub continue_on_error: bool,
+    pub error_manifest: Option<PathBuf>,
+    pub retry_manifest: Option<PathBuf>,
+    pub skip_parent_check: bool,
+    // Create the missing folders of parents given as paths
+    pub create_parents: bool,
+    pub json: bool,
+    // Upload the children of the directory into the parent instead of creating a folder for it
+    pub contents_only: bool,
+    // Name of the folder created for a directory, instead of the directory's own name
+    pub root_name: Option<String>,
+    // Fail instead of skipping fifos, sockets and devices in a recursive upload
+    pub strict: bool,
+    // Update files with the same name in the destination instead of creating duplicates,
+    // directories with the same name are merged
+    pub overwrite: bool,
+    // Set the modified time of files in a git working tree to their last commit
+    pub mtime_from_git: bool,
+    // Skip files whose remote counterpart was modified at or after the local file, the others
+    // are updated like with overwrite
+    pub if_newer: bool,
+    // Look for a file with the same content anywhere in the drive before uploading a single file
+    pub dedupe: Option<DedupeAction>,
 }
 
 impl Config {
+    // Files with the same name in the destination are updated instead of duplicated
+    fn updates_existing(&self) -> bool {
+        self.overwrite || self.if_newer
+    }
+
+    // Progress messages are left out when only the id or json is printed
+    fn is_quiet(&self) -> bool {
+        self.print_only_id || self.json
+    }
+
+    // Chunk size, chunk output, error log and retry settings for every file of the upload
+    pub fn delegate_config(&self) -> UploadDelegateConfig {
+        UploadDelegateConfig::from_flags(
+            self.chunk_size.clone(),
+            self.max_retry_duration,
+            self.print_chunk_errors,
+            self.print_chunk_info,
+            self.error_log.clone(),
+            self.upload_mode.clone(),
+        )
+    }
+
+    // A trailing slash on the local directory uploads its contents, like rsync
+    pub fn with_contents_only(mut self, contents_only: bool) -> Self {
+        self.contents_only = contents_only || has_trailing_separator(&self.file_path);
+        self
+    }
+
+    // Makes remote_path the first parent, keeping any parents that were already given
     pub fn with_remote_path(mut self, remote_path: Option<String>) -> Self {
         if let Some(remote_path) = remote_path {
-            self.parents = Some(vec![remote_path]);
+            let mut parents = vec![remote_path];
+            parents.extend(self.parents.take().unwrap_or_default());
+            self.parents = Some(parents);
         }
         self
     }
 }
 
-pub async fn upload(config: Config) -> Result<(), Error> {
-    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+// Name of the folder created for the uploaded directory. Paths like . or ../dir are
+// canonicalized first, so the folder gets the real name of the directory.
+pub fn root_folder_name(path: &Path, name: Option<&str>) -> Result<String, Error> {
+    let canonical_path = path
+        .canonicalize()
+        .map_err(|err| Error::CanonicalizePath(path.to_path_buf(), err))?;
 
-    let delegate_config = UploadDelegateConfig {
-        chunk_size: config.chunk_size.clone(),
-        backoff_config: BackoffConfig {
-            max_retries: 100000,
-            min_sleep: Duration::from_secs(1),
-            max_sleep: Duration::from_secs(60),
-        },
-        print_chunk_errors: config.print_chunk_errors,
-        print_chunk_info: config.print_chunk_info,
-    };
+    let dir_name = canonical_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or(Error::FilesystemRoot)?;
+
+    Ok(name.map(String::from).unwrap_or(dir_name))
+}
+
+pub fn has_trailing_separator(path: &Path) -> bool {
+    path.to_string_lossy()
+        .chars()
+        .last()
+        .map(std::path::is_separator)
+        .unwrap_or(false)
+}
+
+pub async fn upload(hub: &Hub, config: Config) -> Result<(), Error> {
+    read_only::err_if_enabled("upload files").map_err(Error::ReadOnly)?;
+
+    let delegate_config = config.delegate_config();
 
     err_if_directory(&config.file_path, &config)?;
 
-    if config.file_path.is_dir() {
-        upload_directory(&hub, &config, delegate_config).await?;
+    if let Some(path) = config.error_log.as_ref().filter(|_| config.truncate_error_log) {
+        delegate::truncate_error_log(path).map_err(|err| Error::ErrorLog(path.clone(), err))?;
+    }
+
+    let parents = match &config.parents {
+        Some(parents) if config.retry_manifest.is_none() => Some(
+            parent_check::resolve_parents(
+                hub,
+                parents,
+                config.create_parents,
+                !config.skip_parent_check,
+            )
+            .await
+            .map_err(Error::ParentCheck)?,
+        ),
+        parents => parents.clone(),
+    };
+    let config = Config { parents, ..config };
+
+    if let Some(manifest_path) = &config.retry_manifest {
+        let report = upload_from_manifest(hub, &config, manifest_path, delegate_config).await?;
+        finish_directory_upload(&config, report)
+    } else if config.file_path.is_dir() {
+        let report = upload_directory(hub, &config, delegate_config).await?;
+        finish_directory_upload(&config, report)
     } else {
-        upload_regular(&hub, &config, delegate_config).await?;
+        upload_regular(hub, &config, delegate_config).await
+    }
+}
+
+// What a recursive upload created, by local path relative to the uploaded directory, so callers
+// can map paths to Drive ids without listing the folders again. Files that failed with
+// --continue-on-error are in failed, everything else in the report did complete.
+#[derive(Debug, Default)]
+pub struct UploadReport {
+    pub folders: Vec<(PathBuf, String)>,
+    pub files: Vec<(PathBuf, google_drive3::api::File)>,
+    pub failed: UploadManifest,
+    // Files skipped by if_newer, the remote file was at least as new
+    pub up_to_date: usize,
+    // Files skipped by dedupe, the drive already had their content. Shortcuts created instead
+    // are in files.
+    pub duplicates: usize,
+    // Name given with --name to the folder of the uploaded directory
+    pub root_name: Option<String>,
+    // Fifos, sockets and devices that were left out
+    pub skipped: Vec<SpecialFile>,
+}
+
+impl UploadReport {
+    pub fn total_file_size(&self) -> u64 {
+        self.files
+            .iter()
+            .filter_map(|(_, file)| file.size)
+            .filter_map(|size| u64::try_from(size).ok())
+            .sum()
+    }
+
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "Uploaded {} files in {} directories with a total size of {}",
+            self.files.len(),
+            self.folders.len(),
+            byte_size::format_human(self.total_file_size())
+        );
+
+        if self.up_to_date > 0 {
+            summary.push_str(&format!(", skipped {} up to date files", self.up_to_date));
+        }
+
+        if self.duplicates > 0 {
+            summary.push_str(&format!(
+                ", skipped {} files already in the drive",
+                self.duplicates
+            ));
+        }
+
+        if !self.skipped.is_empty() {
+            summary.push_str(&format!(", skipped {} special files", self.skipped.len()));
+        }
+
+        summary
     }
 
+    // Folders without a parent in the report were created in root_parents
+    pub fn to_json(&self, root_parents: &[String]) -> Vec<FileJson> {
+        let folder_ids: HashMap<&Path, &String> = self
+            .folders
+            .iter()
+            .map(|(path, id)| (path.as_path(), id))
+            .collect();
+
+        let folders = self.folders.iter().map(|(path, id)| {
+            let parents = path
+                .parent()
+                .and_then(|parent| folder_ids.get(parent))
+                .map(|parent_id| vec![parent_id.to_string()])
+                .unwrap_or_else(|| root_parents.to_vec());
+
+            let local_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            // Only the folder of the uploaded directory has a single component
+            let name = match &self.root_name {
+                Some(root_name) if path.components().count() == 1 => root_name.clone(),
+                _ => local_name,
+            };
+
+            FileJson {
+                id: id.clone(),
+                name,
+                mime_type: drive_file::MIME_TYPE_DRIVE_FOLDER.to_string(),
+                size: None,
+                md5_checksum: None,
+                web_view_link: None,
+                parents,
+                path: Some(path.to_string_lossy().to_string()),
+            }
+        });
+
+        let files = self
+            .files
+            .iter()
+            .map(|(path, file)| FileJson::from(file).with_path(path));
+
+        folders.chain(files).collect()
+    }
+}
+
+// Prints the summary or json output of a recursive upload, which includes the files that did
+// upload even when some failed, then reports the failures
+pub fn finish_directory_upload(config: &Config, report: UploadReport) -> Result<(), Error> {
+    print_report(config, &report)?;
+    err_if_failed_files(config, &report.failed)
+}
+
+// Without --continue-on-error the first failure stops the upload, what was uploaded up to then is
+// still printed so the ids aren't lost. Files that failed before the stop are written to the
+// failure manifest, so they can still be retried.
+fn stop_upload(config: &Config, report: &UploadReport, err: Error) -> Error {
+    if let Err(print_err) = print_report(config, report) {
+        eprintln!("Failed to print the upload report: {}", print_err);
+    }
+
+    let manifest_path = config
+        .error_manifest
+        .as_ref()
+        .filter(|_| !report.failed.is_empty());
+
+    if let Some(path) = manifest_path {
+        match report.failed.save(path) {
+            Ok(()) => eprintln!(
+                "Wrote failure manifest to '{}', use --retry-manifest to retry the failed files",
+                path.display()
+            ),
+            Err(save_err) => eprintln!("Failed to write the failure manifest: {}", save_err),
+        }
+    }
+
+    err
+}
+
+// With --continue-on-error the file is recorded for the failure manifest and the upload goes on
+fn fail_file(
+    config: &Config,
+    report: &mut UploadReport,
+    path: &Path,
+    failed_file: FailedFile,
+    err: Error,
+) -> Result<(), Error> {
+    if !config.continue_on_error {
+        return Err(stop_upload(config, report, err));
+    }
+
+    eprintln!("Failed to upload '{}': {}", path.display(), err);
+    report.failed.push(failed_file);
+    Ok(())
+}
+
+fn print_report(config: &Config, report: &UploadReport) -> Result<(), Error> {
+    if config.json {
+        print_json(&report.to_json(config.parents.as_deref().unwrap_or_default()))?;
+    } else if !config.print_only_id {
+        println!("{}", report.summary());
+    }
+
+    Ok(())
+}
+
+fn print_json<T: serde::Serialize>(value: &T) -> Result<(), Error> {
+    let json = serde_json::to_string(value).map_err(Error::SerializeJson)?;
+    println!("{}", json);
     Ok(())
 }
 
@@ -86,63 +370,179 @@ async fn upload_regular(
     )
     .map_err(Error::FileInfo)?;
 
-    let reader = std::io::BufReader::new(file);
+    if let Some(action) = config.dedupe {
+        if !check_same_content(hub, config, &file_info, action).await? {
+            return Ok(());
+        }
+    }
+
+    // A duplicate of the handle to check the size after the upload, the file isn't opened again
+    let handle = file
+        .try_clone()
+        .map_err(|err| Error::OpenFile(config.file_path.clone(), err))?;
+    let size = file_info.size;
+    let reader = file_info::upload_reader(file, delegate_config.chunk_size.in_bytes())
+        .map_err(|err| Error::OpenFile(config.file_path.clone(), err))?;
+
+    let mut existing = ExistingFiles::default();
+    let existing_file = match config.parents.as_ref().and_then(|parents| parents.first()) {
+        Some(parent_id) if config.updates_existing() => {
+            existing
+                .find_file(hub, parent_id, &file_info.name, false)
+                .await?
+        }
+        _ => None,
+    };
 
-    if !config.print_only_id {
-        println!("Uploading {}", config.file_path.display());
+    if let Some(remote_file) = &existing_file {
+        if config.if_newer && is_up_to_date(remote_file, local_mtime(&config.file_path)) {
+            return print_up_to_date(config, remote_file);
+        }
     }
 
-    let file = upload_file(hub, reader, None, file_info, delegate_config)
-        .await
-        .map_err(Error::Upload)?;
+    let existing_id = existing_file.and_then(|file| file.id);
 
-    if config.print_only_id {
-        print!("{}", file.id.unwrap_or_default())
+    if !config.is_quiet() {
+        match &existing_id {
+            Some(file_id) => println!("Updating {} ({})", config.file_path.display(), file_id),
+            None => println!("Uploading {}", config.file_path.display()),
+        }
+    }
+
+    let _in_progress = cancel::InProgress::new(&config.file_path);
+
+    let is_update = existing_id.is_some();
+    let file = match existing_id {
+        Some(file_id) => {
+            files::update::update_file(hub, reader, &file_id, file_info, delegate_config).await
+        }
+        None => upload_file(hub, reader, None, file_info, delegate_config).await,
+    }
+    .map_err(Error::from)?;
+
+    warn_if_size_changed(&config.file_path, &handle, size);
+
+    if config.json {
+        print_json(&FileJson::from(&file))?;
+    } else if config.print_only_id {
+        print!("{}", file.id.as_deref().unwrap_or_default())
     } else {
-        println!("File successfully uploaded");
+        if is_update {
+            println!("File successfully updated");
+        } else {
+            println!("File successfully uploaded");
+        }
         let fields = files::info::prepare_fields(&file, &DisplayConfig::default());
         files::info::print_fields(&fields);
     }
 
-    Ok(())
+    // An updated file stays in the parents it already had
+    if is_update {
+        Ok(())
+    } else {
+        err_if_missing_parents(config.parents.as_deref().unwrap_or_default(), &file)
+    }
 }
 
 pub async fn upload_directory(
     hub: &Hub,
     config: &Config,
     delegate_config: UploadDelegateConfig,
-) -> Result<(), Error> {
-    let mut ids = IdGen::new(hub, &delegate_config);
+) -> Result<UploadReport, Error> {
+    read_only::err_if_enabled("upload files").map_err(Error::ReadOnly)?;
+
     let path = &config.file_path;
-    let tree = FileTree::from_path(path, &mut ids)
+    let root_name = root_folder_name(path, config.root_name.as_deref())?;
+
+    let mut ids = IdGen::new(hub, &delegate_config);
+    let tree = FileTree::from_path(path, &mut ids, config.continue_on_error)
         .await
         .map_err(Error::CreateFileTree)?;
 
     let tree_info = tree.info();
+    let skipped = tree.skipped();
 
-    if !config.print_only_id {
+    if !config.is_quiet() {
         println!(
             "Found {} files in {} directories with a total size of {}",
             tree_info.file_count,
             tree_info.folder_count,
-            human_bytes(tree_info.total_file_size as f64)
+            byte_size::format_human(tree_info.total_file_size)
+        );
+    }
+
+    // Fifos, sockets and devices have no content to upload, reading them could block forever
+    for special_file in &skipped {
+        eprintln!(
+            "Skipping '{}': {}",
+            special_file.path.display(),
+            special_file.kind
         );
     }
 
-    let mut folder_ids: std::collections::HashMap<PathBuf, String> = std::collections::HashMap::new();
+    if config.strict && !skipped.is_empty() {
+        return Err(Error::SpecialFiles(skipped.len()));
+    }
+
+    // Only found with --continue-on-error, they are reported as failed once their folder exists
+    for entry in tree.unreadable() {
+        eprintln!("Failed to read '{}': {}", entry.path.display(), entry.error);
+    }
+
+    // The drive is listed once for the whole directory, not once per file
+    let mut checksums = match config.dedupe {
+        Some(_) => {
+            if !config.is_quiet() {
+                println!("Listing the files in the drive to look for files with the same content");
+            }
+            let index = ChecksumIndex::build(hub)
+                .await
+                .map_err(Error::DedupeCheck)?;
+            Some(index)
+        }
+        None => None,
+    };
+
+    let mut folder_ids: HashMap<PathBuf, String> = HashMap::new();
+    // Folders that couldn't be created with --continue-on-error, what's in them is left out
+    let mut failed_folders: HashSet<PathBuf> = HashSet::new();
+    let mut existing = ExistingFiles::default();
+    let mut git_mtimes = GitMtimes::default();
+    let mut report = UploadReport {
+        root_name: config.root_name.clone(),
+        skipped,
+        ..UploadReport::default()
+    };
 
     for folder in tree.folders() {
         let folder_path = folder.relative_path();
+
+        // The contents go straight into the first parent, so the root maps onto it
+        if folder.parent.is_none() && config.contents_only {
+            let parent_id = config
+                .parents
+                .as_ref()
+                .and_then(|parents| parents.first())
+                .ok_or_else(|| {
+                    Error::Other(format!(
+                        "No parent specified for the contents of {}",
+                        folder_path.display()
+                    ))
+                })?;
+            folder_ids.insert(folder_path, parent_id.clone());
+            continue;
+        }
+
         let folder_name = folder_path
             .file_name()
             .and_then(|s| s.to_str())
             .unwrap_or("")
             .to_string();
 
-        let parent_id = if folder.parent.is_none() {
+        let parent_ids = if folder.parent.is_none() {
             // This is the root folder, use the config's parents if available
             match &config.parents {
-                Some(parents) if !parents.is_empty() => &parents[0],
+                Some(parents) if !parents.is_empty() => parents.clone(),
                 _ => {
                     return Err(Error::Other(format!(
                         "No parent specified for root directory {}",
@@ -153,8 +553,13 @@ pub async fn upload_directory(
         } else {
             // This is a subfolder, get its parent from the folder_ids map
             let parent = folder.parent.as_ref().unwrap();
+            if failed_folders.contains(&parent.relative_path()) {
+                failed_folders.insert(folder_path);
+                continue;
+            }
+
             match folder_ids.get(&parent.relative_path()) {
-                Some(id) => id,
+                Some(id) => vec![id.clone()],
                 None => {
                     return Err(Error::Other(format!(
                         "Failed to find parent for {}",
@@ -164,37 +569,95 @@ pub async fn upload_directory(
             }
         };
 
+        let folder_name = if folder.parent.is_none() {
+            root_name.clone()
+        } else {
+            folder_name
+        };
+
+        // A directory that already exists in the destination is merged into
+        if config.updates_existing() {
+            let found = existing
+                .find(hub, &parent_ids[0], &folder_name, true)
+                .await
+                .map_err(|err| stop_upload(config, &report, err))?;
+
+            if let Some(id) = found {
+                folder_ids.insert(folder_path.clone(), id.clone());
+                report.folders.push((folder_path, id));
+                continue;
+            }
+        }
+
         // For directories, we don't need to read the file content
         // Just create the folder metadata
         let folder_info = FileInfo {
-            name: folder_path.file_name()
-                .and_then(|s| s.to_str())
-                .unwrap_or("")
-                .to_string(),
+            name: folder_name,
             // Use the correct MIME type for Google Drive folders
             mime_type: "application/vnd.google-apps.folder".parse().unwrap(),
-            parents: Some(vec![parent_id.to_string()]),
+            parents: Some(parent_ids.clone()),
             size: 0,
+            modified_time: None,
         };
         
         // Create an empty reader for the directory
         let reader = std::io::empty();
 
-        let file = upload_file(hub, reader, None, folder_info, delegate_config.clone())
+        let created = upload_file(hub, reader, None, folder_info, delegate_config.clone())
             .await
-            .map_err(Error::Upload)?;
+            .map_err(Error::from);
+
+        let file = match created {
+            Ok(file) => file,
+            Err(err) => {
+                let failed_file = FailedFile {
+                    path: folder.path.clone(),
+                    parent_id: parent_ids[0].clone(),
+                    error: err.to_string(),
+                };
+                fail_file(config, &mut report, &folder_path, failed_file, err)?;
+                failed_folders.insert(folder_path);
+                continue;
+            }
+        };
+
+        if folder.parent.is_none() {
+            err_if_missing_parents(&parent_ids, &file)?;
+        }
 
         if let Some(id) = &file.id {
-            folder_ids.insert(folder_path, id.clone());
+            existing.add_new_folder(id);
+            folder_ids.insert(folder_path.clone(), id.clone());
+            report.folders.push((folder_path, id.clone()));
         } else {
             return Err(Error::DriveFolderMissingId);
         }
     }
 
+    for folder in tree.folders() {
+        let parent_id = match folder_ids.get(&folder.relative_path()) {
+            Some(parent_id) => parent_id,
+            None => continue,
+        };
+
+        for entry in &folder.unreadable {
+            report.failed.push(FailedFile {
+                path: entry.path.clone(),
+                parent_id: parent_id.clone(),
+                error: entry.error.clone(),
+            });
+        }
+    }
+
     // The first loop already created all directories, now upload files
     for file in tree.root.files() {
         let file_path = file.relative_path();
         let parent_path = file_path.parent().unwrap_or_else(|| Path::new(""));
+
+        // Reported once with the folder that couldn't be created
+        if failed_folders.contains(parent_path) {
+            continue;
+        }
         
         let parent_id = folder_ids.get(parent_path).or_else(|| {
             if parent_path == Path::new("") {
@@ -214,52 +677,521 @@ pub async fn upload_directory(
             }
         };
 
-        if !config.print_only_id {
-            println!(
-                "Uploading file '{}' to parent id: {}",
-                file_path.display(),
-                parent_id
-            );
+        // The remaining files are reported as failed, so they can be retried with --retry-manifest
+        if cancel::is_cancelled() {
+            report.failed.push(FailedFile {
+                path: file.path.clone(),
+                parent_id: parent_id.to_string(),
+                error: String::from(CANCELLED),
+            });
+            continue;
+        }
+
+        if let (Some(action), Some(index)) = (config.dedupe, &checksums) {
+            let local = LocalFile {
+                path: &file.path,
+                name: &file.name,
+                size: file.size,
+            };
+            let parents = Some(vec![parent_id.to_string()]);
+
+            match dedupe_file(hub, index, local, parents, action).await {
+                Ok(None) => {}
+                Ok(Some(Deduplicated::Skipped(_))) => {
+                    if !config.is_quiet() {
+                        println!(
+                            "Skipping '{}', using the existing file",
+                            file_path.display()
+                        );
+                    }
+                    report.duplicates += 1;
+                    continue;
+                }
+                Ok(Some(Deduplicated::Shortcut(shortcut))) => {
+                    if !config.is_quiet() {
+                        println!(
+                            "Created a shortcut for '{}' to the existing file",
+                            file_path.display()
+                        );
+                    }
+                    report.files.push((file_path, shortcut));
+                    continue;
+                }
+                Err(err) => {
+                    let failed_file = FailedFile {
+                        path: file.path.clone(),
+                        parent_id: parent_id.to_string(),
+                        error: err.to_string(),
+                    };
+                    fail_file(config, &mut report, &file_path, failed_file, err)?;
+                    continue;
+                }
+            }
+        }
+
+        let existing_file = if config.updates_existing() {
+            existing
+                .find_file(hub, parent_id, &file.name, false)
+                .await
+                .map_err(|err| stop_upload(config, &report, err))?
+        } else {
+            None
+        };
+
+        // Falls back to the filesystem mtime outside a git working tree
+        let modified_time = if config.mtime_from_git {
+            git_mtimes.modified_time(&file.path)
+        } else {
+            None
+        };
+
+        if let Some(remote_file) = &existing_file {
+            let local_time = modified_time.or_else(|| local_mtime(&file.path));
+            if config.if_newer && is_up_to_date(remote_file, local_time) {
+                if !config.is_quiet() {
+                    println!("Skipping '{}', it is up to date", file_path.display());
+                }
+                report.up_to_date += 1;
+                continue;
+            }
+        }
+
+        let existing_id = existing_file.and_then(|file| file.id);
+
+        if !config.is_quiet() {
+            print_uploading(&file_path, parent_id, &existing_id);
         }
 
-        let file_handle = fs::File::open(&file_path)
-            .map_err(|err| Error::OpenFile(file_path.clone(), err))?;
-            
-        let file_info = FileInfo::from_file(
-            &file_handle,
+        let uploaded = upload_tree_file(
+            hub,
+            config,
+            &file.path,
+            parent_id,
+            existing_id,
+            modified_time,
+            delegate_config.clone(),
+        )
+        .await;
+
+        match uploaded {
+            Ok(uploaded) => {
+                if let Some(index) = &mut checksums {
+                    index.add(uploaded.clone());
+                }
+                report.files.push((file_path, uploaded))
+            }
+
+            Err(err) => {
+                let failed_file = FailedFile {
+                    path: file.path.clone(),
+                    parent_id: parent_id.to_string(),
+                    error: err.to_string(),
+                };
+                fail_file(config, &mut report, &file_path, failed_file, err)?;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+// Re-attempts the files listed in a manifest written by a previous upload with --continue-on-error
+async fn upload_from_manifest(
+    hub: &Hub,
+    config: &Config,
+    manifest_path: &Path,
+    delegate_config: UploadDelegateConfig,
+) -> Result<UploadReport, Error> {
+    let previous = UploadManifest::load(manifest_path).map_err(Error::Manifest)?;
+    let mut report = UploadReport::default();
+    let mut existing = ExistingFiles::default();
+
+    for failed_file in previous.failed_files {
+        if cancel::is_cancelled() {
+            report.failed.push(FailedFile {
+                error: String::from(CANCELLED),
+                ..failed_file
+            });
+            continue;
+        }
+
+        // A manifest retries single files, a folder that couldn't be read or created needs a
+        // recursive upload
+        if failed_file.path.is_dir() {
+            let err = Error::Other(format!(
+                "'{}' is a directory, upload it again with --recursive",
+                failed_file.path.display()
+            ));
+            let path = failed_file.path.clone();
+            let failed_file = FailedFile {
+                error: err.to_string(),
+                ..failed_file
+            };
+            fail_file(config, &mut report, &path, failed_file, err)?;
+            continue;
+        }
+
+        let name = failed_file
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let existing_file = if config.updates_existing() {
+            existing
+                .find_file(hub, &failed_file.parent_id, &name, false)
+                .await
+                .map_err(|err| stop_upload(config, &report, err))?
+        } else {
+            None
+        };
+
+        if let Some(remote_file) = &existing_file {
+            if config.if_newer && is_up_to_date(remote_file, local_mtime(&failed_file.path)) {
+                if !config.is_quiet() {
+                    println!(
+                        "Skipping '{}', it is up to date",
+                        failed_file.path.display()
+                    );
+                }
+                report.up_to_date += 1;
+                continue;
+            }
+        }
+
+        let existing_id = existing_file.and_then(|file| file.id);
+
+        if !config.is_quiet() {
+            print_uploading(&failed_file.path, &failed_file.parent_id, &existing_id);
+        }
+
+        let uploaded = upload_tree_file(
+            hub,
+            config,
+            &failed_file.path,
+            &failed_file.parent_id,
+            existing_id,
+            None,
+            delegate_config.clone(),
+        )
+        .await;
+
+        match uploaded {
+            Ok(file) => report.files.push((failed_file.path.clone(), file)),
+
+            Err(err) => {
+                let path = failed_file.path.clone();
+                let failed_file = FailedFile {
+                    error: err.to_string(),
+                    ..failed_file
+                };
+                fail_file(config, &mut report, &path, failed_file, err)?;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+fn print_uploading(file_path: &Path, parent_id: &str, existing_id: &Option<String>) {
+    match existing_id {
+        Some(file_id) => println!(
+            "Updating file '{}' ({}) in parent id: {}",
+            file_path.display(),
+            file_id,
+            parent_id
+        ),
+        None => println!(
+            "Uploading file '{}' to parent id: {}",
+            file_path.display(),
+            parent_id
+        ),
+    }
+}
+
+// Files that are already in a folder on Drive, each folder is only listed once
+#[derive(Default)]
+struct ExistingFiles {
+    listings: HashMap<String, Vec<google_drive3::api::File>>,
+}
+
+impl ExistingFiles {
+    // Folders created by the upload are known to be empty, they are never listed
+    fn add_new_folder(&mut self, folder_id: &str) {
+        self.listings.insert(folder_id.to_string(), Vec::new());
+    }
+
+    // Id of the file or folder with the name in the parent
+    async fn find(
+        &mut self,
+        hub: &Hub,
+        parent_id: &str,
+        name: &str,
+        is_dir: bool,
+    ) -> Result<Option<String>, Error> {
+        let file = self.find_file(hub, parent_id, name, is_dir).await?;
+        Ok(file.and_then(|file| file.id))
+    }
+
+    async fn find_file(
+        &mut self,
+        hub: &Hub,
+        parent_id: &str,
+        name: &str,
+        is_dir: bool,
+    ) -> Result<Option<google_drive3::api::File>, Error> {
+        if !self.listings.contains_key(parent_id) {
+            let files = files::list::list_folder(hub, parent_id)
+                .await
+                .map_err(Error::ListExisting)?;
+            self.listings.insert(parent_id.to_string(), files);
+        }
+
+        let files = self.listings.get(parent_id).map(Vec::as_slice).unwrap_or_default();
+        Ok(find_existing(files, name, is_dir).cloned())
+    }
+}
+
+// The remote file a local file or directory with this name replaces. Drive allows several files
+// with the same name, the first one in the listing is used.
+pub fn find_existing<'a>(
+    remote_files: &'a [google_drive3::api::File],
+    name: &str,
+    is_dir: bool,
+) -> Option<&'a google_drive3::api::File> {
+    remote_files.iter().find(|file| {
+        file.name.as_deref() == Some(name) && drive_file::is_directory(file) == is_dir
+    })
+}
+
+// Whether the remote file was modified at or after the local one. Unknown times are never up to
+// date, so the file is uploaded.
+pub fn is_up_to_date(
+    remote_file: &google_drive3::api::File,
+    local_mtime: Option<DateTime<Utc>>,
+) -> bool {
+    matches!(
+        (remote_file.modified_time, local_mtime),
+        (Some(remote_mtime), Some(local_mtime)) if remote_mtime >= local_mtime
+    )
+}
+
+fn local_mtime(path: &Path) -> Option<DateTime<Utc>> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .map(DateTime::<Utc>::from)
+}
+
+// Looks for a file with the content of the local file anywhere in the drive and returns whether
+// the upload should go ahead. The file that was skipped, or the shortcut created instead, is
+// printed like an uploaded file.
+async fn check_same_content(
+    hub: &Hub,
+    config: &Config,
+    file_info: &FileInfo,
+    action: DedupeAction,
+) -> Result<bool, Error> {
+    if !config.is_quiet() {
+        println!(
+            "Looking for files with the same content as {}",
+            config.file_path.display()
+        );
+    }
+
+    let index = ChecksumIndex::build(hub)
+        .await
+        .map_err(Error::DedupeCheck)?;
+
+    let local = LocalFile {
+        path: &config.file_path,
+        name: &file_info.name,
+        size: file_info.size,
+    };
+
+    match dedupe_file(hub, &index, local, config.parents.clone(), action).await? {
+        None => Ok(true),
+        Some(Deduplicated::Skipped(file)) => {
+            print_existing(config, &file, "Skipped upload, using the existing file")?;
+            Ok(false)
+        }
+        Some(Deduplicated::Shortcut(shortcut)) => {
+            print_existing(config, &shortcut, "Created a shortcut to the existing file")?;
+            Ok(false)
+        }
+    }
+}
+
+// A local file compared against the checksum index
+struct LocalFile<'a> {
+    path: &'a Path,
+    name: &'a str,
+    size: u64,
+}
+
+enum Deduplicated {
+    // The existing file that is used instead
+    Skipped(google_drive3::api::File),
+    // The shortcut created in parents
+    Shortcut(google_drive3::api::File),
+}
+
+// Skips the local file or creates a shortcut instead when the drive already has its content, None
+// when the file is to be uploaded. The existing file is reported on stderr.
+async fn dedupe_file(
+    hub: &Hub,
+    index: &ChecksumIndex,
+    local: LocalFile<'_>,
+    parents: Option<Vec<String>>,
+    action: DedupeAction,
+) -> Result<Option<Deduplicated>, Error> {
+    let matches = index
+        .find(local.path, local.size)
+        .map_err(Error::DedupeCheck)?;
+
+    let existing = match dedupe_check::pick_existing(&matches) {
+        Some(existing) => existing,
+        None => return Ok(None),
+    };
+
+    let description = dedupe_check::describe_existing(hub, existing)
+        .await
+        .map_err(Error::DedupeCheck)?;
+    eprintln!("{} already exists as {}", local.path.display(), description);
+
+    match dedupe_check::decide(&matches, action) {
+        Decision::Upload => Ok(None),
+        Decision::Skip(file) => Ok(Some(Deduplicated::Skipped(file))),
+        Decision::Shortcut(file) => {
+            let shortcut = files::shortcut::create_shortcut(
+                hub,
+                file.id.as_deref().unwrap_or_default(),
+                local.name,
+                parents,
+            )
+            .await
+            .map_err(|err| Error::DedupeCheck(dedupe_check::Error::CreateShortcut(err)))?;
+
+            Ok(Some(Deduplicated::Shortcut(shortcut)))
+        }
+    }
+}
+
+fn print_existing(
+    config: &Config,
+    file: &google_drive3::api::File,
+    message: &str,
+) -> Result<(), Error> {
+    if config.json {
+        print_json(&FileJson::from(file))
+    } else if config.print_only_id {
+        print!("{}", file.id.as_deref().unwrap_or_default());
+        Ok(())
+    } else {
+        println!("{}", message);
+        let fields = files::info::prepare_fields(file, &DisplayConfig::default());
+        files::info::print_fields(&fields);
+        Ok(())
+    }
+}
+
+fn print_up_to_date(config: &Config, remote_file: &google_drive3::api::File) -> Result<(), Error> {
+    if config.json {
+        print_json(&FileJson::from(remote_file))
+    } else if config.print_only_id {
+        print!("{}", remote_file.id.as_deref().unwrap_or_default());
+        Ok(())
+    } else {
+        println!(
+            "Skipping {}, the remote file ({}) is up to date",
+            config.file_path.display(),
+            remote_file.id.as_deref().unwrap_or_default()
+        );
+        Ok(())
+    }
+}
+
+async fn upload_tree_file(
+    hub: &Hub,
+    config: &Config,
+    file_path: &PathBuf,
+    parent_id: &str,
+    existing_id: Option<String>,
+    modified_time: Option<DateTime<Utc>>,
+    delegate_config: UploadDelegateConfig,
+) -> Result<google_drive3::api::File, Error> {
+    let file = fs::File::open(file_path).map_err(|err| Error::OpenFile(file_path.clone(), err))?;
+
+    let file_info = FileInfo {
+        modified_time,
+        ..FileInfo::from_file(
+            &file,
             &file_info::Config {
                 file_path: file_path.clone(),
                 mime_type: config.mime_type.clone(),
                 parents: Some(vec![parent_id.to_string()]),
             },
         )
-        .map_err(Error::FileInfo)?;
-
-        // Reopen the file for reading
-        let file = fs::File::open(&file_path)
-            .map_err(|err| Error::OpenFile(file_path.clone(), err))?;
+        .map_err(Error::FileInfo)?
+    };
 
-        let reader = std::io::BufReader::new(file);
+    let handle = file
+        .try_clone()
+        .map_err(|err| Error::OpenFile(file_path.clone(), err))?;
+    let size = file_info.size;
+    let reader = file_info::upload_reader(file, delegate_config.chunk_size.in_bytes())
+        .map_err(|err| Error::OpenFile(file_path.clone(), err))?;
+    let _in_progress = cancel::InProgress::new(file_path);
 
-        let _file = upload_file(hub, reader, None, file_info, delegate_config.clone())
-            .await
-            .map_err(Error::Upload)?;
+    let uploaded = match existing_id {
+        Some(file_id) => {
+            files::update::update_file(hub, reader, &file_id, file_info, delegate_config).await
+        }
+        None => upload_file(hub, reader, None, file_info, delegate_config).await,
     }
+    .map_err(Error::from)?;
 
-    if !config.print_only_id {
-        println!(
-            "Uploaded {} files in {} directories with a total size of {}",
-            tree_info.file_count,
-            tree_info.folder_count,
-            human_bytes(tree_info.total_file_size as f64)
+    warn_if_size_changed(file_path, &handle, size);
+
+    Ok(uploaded)
+}
+
+// The upload declares the size the file had when it was opened, a file written to meanwhile is
+// uploaded truncated or with mixed content
+fn warn_if_size_changed(path: &Path, file: &fs::File, size: u64) {
+    if let Some(current_size) = file_info::changed_size(file, size) {
+        eprintln!(
+            "Warning: '{}' changed size during the upload, from {} to {} bytes. The uploaded file may be incomplete, upload it again",
+            path.display(),
+            size,
+            current_size
         );
     }
+}
+
+fn err_if_failed_files(config: &Config, manifest: &UploadManifest) -> Result<(), Error> {
+    if manifest.is_empty() {
+        return Ok(());
+    }
 
-    // This section was removed as it contained references to non-existent variables
+    eprintln!("Failed to upload {} files:", manifest.len());
+    for failed_file in &manifest.failed_files {
+        eprintln!("  {}: {}", failed_file.path.display(), failed_file.error);
+    }
 
-    Ok(())
+    if let Some(path) = &config.error_manifest {
+        manifest.save(path).map_err(Error::Manifest)?;
+        eprintln!(
+            "Wrote failure manifest to '{}', use --retry-manifest to retry the failed files",
+            path.display()
+        );
+    }
+
+    Err(Error::FailedFiles(manifest.len()))
 }
 
+// Drive only accepts a single parent when a file is created, any additional parents
+// are added with follow-up updates once the content has been uploaded
 pub async fn upload_file<RS>(
     hub: &Hub,
     src_file: RS,
@@ -270,53 +1202,133 @@ pub async fn upload_file<RS>(
 where
     RS: google_drive3::client::ReadSeek,
 {
+    let (first_parent, extra_parents) = split_parents(file_info.parents);
+
     let dst_file = google_drive3::api::File {
         id: file_id,
         name: Some(file_info.name),
         mime_type: Some(file_info.mime_type.to_string()),
-        parents: file_info.parents,
+        parents: first_parent.map(|parent_id| vec![parent_id]),
+        modified_time: file_info.modified_time,
         ..google_drive3::api::File::default()
     };
 
-    let chunk_size_bytes = delegate_config.chunk_size.in_bytes();
+    // Empty files always use a simple upload, a resumable session has no chunk to send
+    let resumable = delegate_config.is_resumable(file_info.size);
     let mut delegate = UploadDelegate::new(delegate_config);
 
     let req = hub
         .files()
         .create(dst_file)
-        .param("fields", "id,name,size,createdTime,modifiedTime,md5Checksum,mimeType,parents,shared,description,webContentLink,webViewLink")
+        .param("fields", UPLOADED_FILE_FIELDS)
         .add_scope(google_drive3::api::Scope::Full)
         .delegate(&mut delegate)
         .supports_all_drives(true);
 
-    let (_, file) = if file_info.size > chunk_size_bytes {
+    let (_, file) = if resumable {
         req.upload_resumable(src_file, file_info.mime_type).await?
     } else {
         req.upload(src_file, file_info.mime_type).await?
     };
 
-    Ok(file)
+    if extra_parents.is_empty() {
+        Ok(file)
+    } else {
+        Ok(add_parents(hub, file, &extra_parents).await)
+    }
+}
+
+pub fn split_parents(parents: Option<Vec<String>>) -> (Option<String>, Vec<String>) {
+    let mut parents = parents.unwrap_or_default().into_iter();
+    (parents.next(), parents.collect())
+}
+
+// Adds the file to each parent and reports the result. A failed parent doesn't stop the others,
+// the returned file lists the parents the file actually ended up in.
+async fn add_parents(
+    hub: &Hub,
+    mut file: google_drive3::api::File,
+    parent_ids: &[String],
+) -> google_drive3::api::File {
+    let file_id = file.id.clone().unwrap_or_default();
+
+    for parent_id in parent_ids {
+        let result = hub
+            .files()
+            .update(google_drive3::api::File::default(), &file_id)
+            .add_parents(parent_id)
+            .param("fields", UPLOADED_FILE_FIELDS)
+            .add_scope(google_drive3::api::Scope::Full)
+            .supports_all_drives(true)
+            .doit_without_upload()
+            .await;
+
+        match result {
+            Ok((_, updated)) => {
+                eprintln!("Added to parent {}", parent_id);
+                file = updated;
+            }
+            Err(err) => eprintln!("Failed to add to parent {}: {}", parent_id, err),
+        }
+    }
+
+    file
+}
+
+pub fn missing_parents(parent_ids: &[String], file: &google_drive3::api::File) -> Vec<String> {
+    let file_parents = file.parents.clone().unwrap_or_default();
+
+    parent_ids
+        .iter()
+        .filter(|parent_id| !file_parents.contains(parent_id))
+        .cloned()
+        .collect()
+}
+
+fn err_if_missing_parents(parent_ids: &[String], file: &google_drive3::api::File) -> Result<(), Error> {
+    let missing = missing_parents(parent_ids, file);
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::AddParents(missing))
+    }
 }
 
 #[derive(Debug)]
 pub enum Error {
-    Hub(hub_helper::Error),
+    ReadOnly(read_only::Error),
     FileHelper(String),
     ResolvePath(path_utils::PathResolutionError),
     FileInfo(file_info::Error),
     OpenFile(PathBuf, io::Error),
     Upload(google_drive3::Error),
+    Quota(drive_error::QuotaError),
     IsDirectory(PathBuf),
     DriveFolderMissingId,
     CreateFileTree(file_tree::Error),
     Mkdir(google_drive3::Error),
+    ErrorLog(PathBuf, io::Error),
+    Manifest(upload_manifest::Error),
+    FailedFiles(usize),
+    ParentCheck(parent_check::Error),
+    AddParents(Vec<String>),
+    SerializeJson(serde_json::Error),
+    CanonicalizePath(PathBuf, io::Error),
+    FilesystemRoot,
+    SpecialFiles(usize),
+    ListExisting(files::list::Error),
+    DedupeCheck(dedupe_check::Error),
     Other(String),
 }
 
 // Implement From for google_drive3::Error to allow using ? operator
 impl From<google_drive3::Error> for Error {
     fn from(err: google_drive3::Error) -> Self {
-        Error::Upload(err)
+        match drive_error::quota_error(&err) {
+            Some(quota_error) => Error::Quota(quota_error),
+            None => Error::Upload(err),
+        }
     }
 }
 
@@ -325,7 +1337,7 @@ impl error::Error for Error {}
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::Hub(err) => write!(f, "{}", err),
+            Error::ReadOnly(err) => write!(f, "{}", err),
             Error::FileHelper(err) => write!(f, "{}", err),
             Error::ResolvePath(err) => write!(f, "{}", err),
             Error::FileInfo(err) => write!(f, "{}", err),
@@ -333,14 +1345,43 @@ impl Display for Error {
                 write!(f, "Failed to open file '{}': {}", path.display(), err)
             }
             Error::Upload(err) => write!(f, "Failed to upload file: {}", err),
+            Error::Quota(err) => write!(f, "Failed to upload file: {}", err),
             Error::IsDirectory(path) => write!(
                 f,
                 "'{}' is a directory. Use --recursive to upload directories.",
                 path.display()
             ),
             Error::DriveFolderMissingId => write!(f, "Folder created on drive does not have an id"),
+            Error::CanonicalizePath(path, err) => {
+                write!(f, "Failed to resolve '{}': {}", path.display(), err)
+            }
+            Error::FilesystemRoot => write!(
+                f,
+                "Can't upload the filesystem root, upload a directory below it instead"
+            ),
             Error::CreateFileTree(err) => write!(f, "Failed to create file tree: {}", err),
+            Error::SpecialFiles(count) => write!(
+                f,
+                "Found {} special files that can't be uploaded, remove --strict to skip them",
+                count
+            ),
             Error::Mkdir(err) => write!(f, "Failed to create directory: {}", err),
+            Error::ErrorLog(path, err) => {
+                write!(f, "Failed to truncate error log '{}': {}", path.display(), err)
+            }
+            Error::Manifest(err) => write!(f, "{}", err),
+            Error::FailedFiles(count) => write!(f, "{} files failed to upload", count),
+            Error::ParentCheck(err) => write!(f, "{}", err),
+            Error::AddParents(ids) => write!(
+                f,
+                "File was uploaded but could not be added to parents: {}",
+                ids.join(", ")
+            ),
+            Error::SerializeJson(err) => write!(f, "Failed to serialize json: {}", err),
+            Error::ListExisting(err) => {
+                write!(f, "Failed to list the existing files in the destination: {}", err)
+            }
+            Error::DedupeCheck(err) => write!(f, "{}", err),
             Error::Other(err) => write!(f, "{}", err),
         }
     }
@@ -349,16 +1390,28 @@ impl Display for Error {
 impl Error {
     fn description(&self) -> &str {
         match self {
-            Error::Hub(_) => "Failed to get hub",
+            Error::ReadOnly(_) => "Read-only mode",
             Error::FileHelper(_) => "File helper error",
             Error::ResolvePath(_) => "Failed to resolve path",
             Error::FileInfo(_) => "Failed to get file info",
             Error::Mkdir(_) => "Failed to create directory",
             Error::OpenFile(_, _) => "Failed to open file",
             Error::Upload(_) => "Failed to upload file",
+            Error::Quota(_) => "Quota exceeded",
             Error::IsDirectory(_) => "Is a directory",
             Error::DriveFolderMissingId => "Drive folder missing id",
+            Error::CanonicalizePath(_, _) => "Failed to resolve path",
+            Error::FilesystemRoot => "Filesystem root",
             Error::CreateFileTree(_) => "Failed to create file tree",
+            Error::ErrorLog(_, _) => "Failed to truncate error log",
+            Error::Manifest(_) => "Failed to read or write manifest",
+            Error::FailedFiles(_) => "Some files failed to upload",
+            Error::SpecialFiles(_) => "Found special files",
+            Error::ListExisting(_) => "Failed to list existing files",
+            Error::DedupeCheck(_) => "Failed to look for files with the same content",
+            Error::ParentCheck(_) => "Invalid parent folder",
+            Error::AddParents(_) => "Failed to add parents",
+            Error::SerializeJson(_) => "Failed to serialize json",
             Error::Other(_) => "Other error",
         }
     }
@@ -1,83 +1,143 @@
-use crate::files::upload::{Config, Error, upload, upload_directory};
+use crate::common::byte_size;
+use crate::common::cancel;
+use crate::common::drive_api::DriveApi;
+use crate::common::drive_file;
+use crate::common::read_only;
+use crate::files::upload::{Config, Error, finish_directory_upload, upload, upload_directory};
+use crate::files::list;
 use crate::files::list::{ListFilesConfig, ListQuery};
+use crate::files::upload;
 use crate::hub::Hub;
 use std::io::{self, Write};
+use std::path::Path;
+use std::path::PathBuf;
 use std::str::FromStr;
 
+// A file or directory that will be uploaded, by its name in the destination
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalEntry {
+    pub name: String,
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+// A local entry with the same name as an existing remote file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collision {
+    // Path relative to the destination
+    pub path: PathBuf,
+    pub is_dir: bool,
+    // Size of the existing remote file, 0 for folders and google documents
+    pub size: u64,
+    // Set when both sides are directories, so the check can descend into them
+    pub remote_folder_id: Option<String>,
+    pub local_path: PathBuf,
+}
+
+// Local entries that already exist in the remote listing of the same folder
+pub fn find_collisions(
+    prefix: &Path,
+    local_entries: &[LocalEntry],
+    remote_files: &[google_drive3::api::File],
+) -> Vec<Collision> {
+    let mut collisions: Vec<Collision> = local_entries
+        .iter()
+        .filter_map(|entry| {
+            let remote_file = remote_files
+                .iter()
+                .find(|remote_file| remote_file.name.as_deref() == Some(entry.name.as_str()))?;
+
+            let remote_is_dir = drive_file::is_directory(remote_file);
+            let size = remote_file
+                .size
+                .and_then(|size| u64::try_from(size).ok())
+                .unwrap_or(0);
+
+            Some(Collision {
+                path: prefix.join(&entry.name),
+                is_dir: entry.is_dir,
+                size: if entry.is_dir { 0 } else { size },
+                remote_folder_id: remote_file.id.clone().filter(|_| entry.is_dir && remote_is_dir),
+                local_path: entry.path.clone(),
+            })
+        })
+        .collect();
+
+    collisions.sort_by(|a, b| a.path.cmp(&b.path));
+    collisions
+}
+
+// What the overwrite prompt states about the collisions
+pub fn collision_summary(collisions: &[Collision]) -> String {
+    let files: Vec<&Collision> = collisions.iter().filter(|c| !c.is_dir).collect();
+    let directory_count = collisions.len() - files.len();
+    let total_size: u64 = files.iter().map(|c| c.size).sum();
+
+    format!(
+        "{} files ({}) and {} directories will be overwritten:",
+        files.len(),
+        byte_size::format_human(total_size),
+        directory_count
+    )
+}
+
 /// Upload with overwrite check
 /// 
 /// This function checks if files with the same name already exist in the destination
 /// and prompts the user for confirmation before overwriting.
-pub async fn upload_with_overwrite_check(hub: &Hub, config: Config) -> Result<(), Error> {
+/// With deep_check, directories that exist on both sides are compared level by level.
+pub async fn upload_with_overwrite_check(
+    hub: &Hub,
+    config: Config,
+    deep_check: bool,
+) -> Result<(), Error> {
+    read_only::err_if_enabled("upload files").map_err(Error::ReadOnly)?;
+
     if config.file_path.is_dir() && config.upload_directories {
         // For recursive directory uploads, check the top-level files/directories
-        println!("Checking for existing files in destination...");
+        notice(&config, "Checking for existing files in destination...");
         
         // Get the parent ID
         let parent_id = match &config.parents {
-            Some(parents) if !parents.is_empty() => &parents[0],
-            _ => "root",
-        };
-        
-        // List files in the destination folder
-        let query = format!("'{}' in parents and trashed = false", parent_id);
-        let list_config = ListFilesConfig {
-            query: ListQuery::from_str(&query).map_err(|e| Error::Other(e.to_string()))?,
-            order_by: Default::default(),
-            max_files: 100, // Limit to 100 files for performance
+            Some(parents) if !parents.is_empty() => parents[0].clone(),
+            _ => String::from("root"),
         };
-        
-        let remote_files = crate::files::list::list_files(hub, &list_config)
-            .await
-            .map_err(|e| Error::Other(e.to_string()))?;
-        
-        // Get local top-level files/directories
-        let local_entries = std::fs::read_dir(&config.file_path)
-            .map_err(|e| Error::Other(format!("Failed to read directory: {}", e)))?;
-        
-        let mut potential_overwrites = Vec::new();
-        
-        // Check for potential overwrites
-        for entry in local_entries {
-            if let Ok(entry) = entry {
-                let name = entry.file_name().to_string_lossy().to_string();
-                
-                // Check if this name exists in remote files
-                for remote_file in &remote_files {
-                    if let Some(remote_name) = &remote_file.name {
-                        if remote_name == &name {
-                            let file_type = if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
-                                "directory"
-                            } else {
-                                "file"
-                            };
-                            
-                            potential_overwrites.push(format!("{} ({})", name, file_type));
-                            break;
-                        }
-                    }
-                }
-            }
-        }
-        
-        if !potential_overwrites.is_empty() {
-            println!("The following files/directories will be overwritten:");
-            for item in &potential_overwrites {
-                println!("  - {}", item);
+
+        // The entries that end up directly in the destination folder
+        let local_entries = top_level_entries(&config)?;
+        let collisions =
+            check_destination(hub, &parent_id, local_entries, deep_check).await?;
+
+        if !collisions.is_empty() {
+            notice(&config, &collision_summary(&collisions));
+            for collision in &collisions {
+                let file_type = if collision.is_dir { "directory" } else { "file" };
+                notice(
+                    &config,
+                    &format!("  - {} ({})", collision.path.display(), file_type),
+                );
             }
+        } else if deep_check {
+            notice(&config, "No existing files will be overwritten.");
         } else {
-            println!("No existing files will be overwritten at the top level.");
-            println!("Note: Subdirectories may still contain files that will be overwritten.");
+            notice(&config, "No existing files will be overwritten at the top level.");
+            notice(&config, "Note: Subdirectories may still contain files that will be overwritten, use --deep-check to check them.");
         }
         
-        println!("Do you want to continue? [y/N]");
+        notice(&config, "Do you want to continue? [y/N]");
         
         if !confirm_overwrite() {
-            println!("Upload cancelled.");
+            notice(&config, "Upload cancelled.");
             return Ok(());
         }
-        
-        upload_directory(hub, &config, Default::default()).await
+
+        // The existing files are updated and the existing directories merged into
+        let config = Config {
+            overwrite: true,
+            ..config
+        };
+        let report = upload_directory(hub, &config, config.delegate_config()).await?;
+        finish_directory_upload(&config, report)
     } else if config.file_path.is_dir() {
         // Non-recursive directory upload - error out
         Err(Error::IsDirectory(config.file_path.clone()))
@@ -92,39 +152,130 @@ pub async fn upload_with_overwrite_check(hub: &Hub, config: Config) -> Result<()
             _ => "root",
         };
         
-        let query = format!(
-            "'{}' in parents and name = '{}' and trashed = false",
-            parent_id, file_name
-        );
-        
-        let list_config = ListFilesConfig {
-            query: ListQuery::from_str(&query).map_err(|e| Error::Other(e.to_string()))?,
-            order_by: Default::default(),
-            max_files: 1,
-        };
-        
-        let files = crate::files::list::list_files(hub, &list_config)
-            .await
-            .map_err(|e| Error::Other(e.to_string()))?;
-        
-        if !files.is_empty() {
-            println!("File '{}' already exists in the destination.", file_name);
-            println!("Do you want to overwrite it? [y/N]");
+        let existing = find_existing_file(hub, parent_id, file_name).await?;
+
+        if existing.is_some() {
+            notice(&config, &format!("File '{}' already exists in the destination.", file_name));
+            notice(&config, "Do you want to overwrite it? [y/N]");
             
             if !confirm_overwrite() {
-                println!("Upload cancelled.");
+                notice(&config, "Upload cancelled.");
                 return Ok(());
             }
         }
-        
-        upload(config).await
+
+        let config = Config {
+            overwrite: existing.is_some(),
+            ..config
+        };
+        upload(hub, config).await
+    }
+}
+
+// A file that isn't trashed with the name in the folder
+pub async fn find_existing_file<A: DriveApi>(
+    api: &A,
+    parent_id: &str,
+    file_name: &str,
+) -> Result<Option<google_drive3::api::File>, Error> {
+    let query = format!(
+        "'{}' in parents and name = '{}' and trashed = false",
+        parent_id, file_name
+    );
+
+    let list_config = ListFilesConfig {
+        query: ListQuery::from_str(&query).map_err(|e| Error::Other(e.to_string()))?,
+        order_by: Default::default(),
+        max_files: 1,
+        space: Default::default(),
+    };
+
+    let files = api
+        .list_files(&list_config)
+        .await
+        .map_err(|e| Error::Other(list::Error::ListFiles(e).to_string()))?;
+
+    Ok(files.into_iter().next())
+}
+
+// Compares the local entries with the complete listing of the destination. With deep_check
+// every directory that exists on both sides is compared as well, one listing per directory.
+pub async fn check_destination<A: DriveApi>(
+    api: &A,
+    parent_id: &str,
+    local_entries: Vec<LocalEntry>,
+    deep_check: bool,
+) -> Result<Vec<Collision>, Error> {
+    let mut collisions = Vec::new();
+    let mut pending = vec![(parent_id.to_string(), PathBuf::new(), local_entries)];
+
+    while let Some((folder_id, prefix, local_entries)) = pending.pop() {
+        let remote_files = list::list_folder(api, &folder_id)
+            .await
+            .map_err(|e| Error::Other(e.to_string()))?;
+        let found = find_collisions(&prefix, &local_entries, &remote_files);
+
+        if deep_check {
+            for collision in &found {
+                if let Some(remote_folder_id) = &collision.remote_folder_id {
+                    let children = local_entries_in(&collision.local_path)?;
+                    pending.push((remote_folder_id.clone(), collision.path.clone(), children));
+                }
+            }
+        }
+
+        collisions.extend(found);
+    }
+
+    collisions.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(collisions)
+}
+
+// With --contents the children of the directory are uploaded into the destination,
+// otherwise only the directory itself is
+fn top_level_entries(config: &Config) -> Result<Vec<LocalEntry>, Error> {
+    if config.contents_only {
+        local_entries_in(&config.file_path)
+    } else {
+        let name = upload::root_folder_name(&config.file_path, config.root_name.as_deref())?;
+
+        Ok(vec![LocalEntry {
+            name,
+            path: config.file_path.clone(),
+            is_dir: true,
+        }])
+    }
+}
+
+fn local_entries_in(dir: &Path) -> Result<Vec<LocalEntry>, Error> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| Error::Other(format!("Failed to read directory: {}", e)))?;
+
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| LocalEntry {
+            name: entry.file_name().to_string_lossy().to_string(),
+            is_dir: entry.path().is_dir(),
+            path: entry.path(),
+        })
+        .collect())
+}
+
+// Messages and prompts go to stderr with --json, so stdout only has the json output
+fn notice(config: &Config, message: &str) {
+    if config.json {
+        eprintln!("{}", message)
+    } else {
+        println!("{}", message)
     }
 }
 
 /// Helper function to get user confirmation
 fn confirm_overwrite() -> bool {
+    let _prompt = cancel::Prompt::start();
     let mut input = String::new();
     io::stdout().flush().unwrap();
+    io::stderr().flush().unwrap();
     io::stdin().read_line(&mut input).unwrap();
     
     let input = input.trim().to_lowercase();
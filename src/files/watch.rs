@@ -0,0 +1,507 @@
+use crate::common::byte_size;
+use crate::common::cancel;
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::drive_file;
+use crate::common::file_info;
+use crate::common::file_info::FileInfo;
+use crate::common::read_only;
+use crate::common::time_fmt;
+use crate::files;
+use crate::files::path_utils;
+use crate::files::path_utils::PathResolutionError;
+use crate::files::update::PatchFile;
+use crate::files::upload;
+use crate::hub::Hub;
+use google_drive3::chrono;
+use notify::event::ModifyKind;
+use notify::EventKind;
+use notify::RecursiveMode;
+use notify::Watcher;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_secs(2);
+
+// How often pending changes are checked for being due
+const TICK: Duration = Duration::from_millis(250);
+
+pub struct Config {
+    pub local_dir: PathBuf,
+    // Folder path, missing folders are created
+    pub remote_path: String,
+    // Time without events for a path before it's pushed
+    pub debounce: Duration,
+    // Trash the remote counterpart of files and folders deleted locally
+    pub propagate_deletes: bool,
+}
+
+// What a change to a path, relative to the watched directory, results in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    CreateFolder(PathBuf),
+    Upload(PathBuf),
+    Trash(PathBuf),
+}
+
+impl Action {
+    pub fn path(&self) -> &Path {
+        match self {
+            Action::CreateFolder(path) | Action::Upload(path) | Action::Trash(path) => path,
+        }
+    }
+}
+
+// Changed paths waiting until they had no events for the debounce time, so a file that is
+// being written is only pushed once
+#[derive(Debug, Default)]
+pub struct Pending {
+    changes: HashMap<PathBuf, Instant>,
+}
+
+impl Pending {
+    pub fn record(&mut self, path: PathBuf, now: Instant) {
+        self.changes.insert(path, now);
+    }
+
+    // Removes and returns the paths without events for the debounce time, sorted so folders
+    // come before what's in them
+    pub fn take_due(&mut self, now: Instant, debounce: Duration) -> Vec<PathBuf> {
+        let mut due: Vec<PathBuf> = self
+            .changes
+            .iter()
+            .filter(|(_, last_event)| now.saturating_duration_since(**last_event) >= debounce)
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        for path in &due {
+            self.changes.remove(path);
+        }
+
+        due.sort();
+        due
+    }
+
+    pub fn take_all(&mut self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self.changes.drain().map(|(path, _)| path).collect();
+        paths.sort();
+        paths
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+// Actions for the changed paths, based on what is on disk now. A folder that exists is
+// created with everything in it and a deleted one is trashed as a whole, so paths inside a
+// folder that is also in the batch are left out. Deletes are ignored without propagate_deletes.
+pub fn plan(local_dir: &Path, paths: &[PathBuf], propagate_deletes: bool) -> Vec<Action> {
+    let mut sorted = paths.to_vec();
+    sorted.sort();
+    sorted.dedup();
+
+    let mut planned: Vec<&PathBuf> = Vec::new();
+    let mut actions = Vec::new();
+
+    for path in &sorted {
+        if path.as_os_str().is_empty() || planned.iter().any(|parent| path.starts_with(parent)) {
+            continue;
+        }
+
+        let full_path = local_dir.join(path);
+        if full_path.is_dir() {
+            actions.push(Action::CreateFolder(path.clone()));
+            plan_folder(local_dir, path, &mut actions);
+        } else if full_path.is_file() {
+            actions.push(Action::Upload(path.clone()));
+        } else if !full_path.exists() && propagate_deletes {
+            actions.push(Action::Trash(path.clone()));
+        } else {
+            continue;
+        }
+
+        planned.push(path);
+    }
+
+    actions
+}
+
+fn plan_folder(local_dir: &Path, folder: &Path, actions: &mut Vec<Action>) {
+    let mut entries: Vec<PathBuf> = fs::read_dir(local_dir.join(folder))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| folder.join(entry.file_name()))
+                .collect()
+        })
+        .unwrap_or_default();
+    entries.sort();
+
+    for path in entries {
+        let full_path = local_dir.join(&path);
+        if full_path.is_dir() {
+            actions.push(Action::CreateFolder(path.clone()));
+            plan_folder(local_dir, &path, actions);
+        } else if full_path.is_file() {
+            actions.push(Action::Upload(path));
+        }
+    }
+}
+
+// Pushes changes in local_dir until Ctrl-C or the deadline, then pushes the pending changes
+// right away. Without a hub the actions are only logged, for a dry run.
+pub async fn watch(hub: Option<&Hub>, config: Config) -> Result<(), Error> {
+    if hub.is_some() {
+        read_only::err_if_enabled("upload files").map_err(Error::ReadOnly)?;
+    }
+
+    let local_dir = config
+        .local_dir
+        .canonicalize()
+        .map_err(|err| Error::LocalDir(config.local_dir.clone(), err))?;
+    if !local_dir.is_dir() {
+        return Err(Error::NotADirectory(config.local_dir.clone()));
+    }
+
+    let mut remote = match hub {
+        Some(hub) => Some(Remote::new(hub, &config.remote_path).await?),
+        None => None,
+    };
+
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        let _ = sender.send(result);
+    })
+    .map_err(Error::Watch)?;
+    watcher
+        .watch(&local_dir, RecursiveMode::Recursive)
+        .map_err(Error::Watch)?;
+
+    log(format!(
+        "Watching '{}' and pushing changes to '{}', press Ctrl-C to stop",
+        config.local_dir.display(),
+        config.remote_path
+    ));
+
+    let mut pending = Pending::default();
+    let mut ticker = tokio::time::interval(TICK);
+
+    loop {
+        tokio::select! {
+            Some(result) = receiver.recv() => match result {
+                Ok(event) => record_event(&mut pending, &local_dir, event),
+                Err(err) => log(format!("Failed to watch for changes: {}", err)),
+            },
+            _ = ticker.tick() => {
+                if cancel::is_cancelled() {
+                    break;
+                }
+
+                let due = pending.take_due(Instant::now(), config.debounce);
+                push(remote.as_mut(), &local_dir, &due, config.propagate_deletes).await;
+            }
+        }
+    }
+
+    if !pending.is_empty() {
+        log(format!("Pushing {} pending changes", pending.len()));
+        let remaining = pending.take_all();
+        push(
+            remote.as_mut(),
+            &local_dir,
+            &remaining,
+            config.propagate_deletes,
+        )
+        .await;
+    }
+
+    log("Stopped watching");
+    Ok(())
+}
+
+fn record_event(pending: &mut Pending, local_dir: &Path, event: notify::Event) {
+    // Reading a file or changing its permissions doesn't change what's uploaded
+    if matches!(
+        event.kind,
+        EventKind::Access(_) | EventKind::Modify(ModifyKind::Metadata(_))
+    ) {
+        return;
+    }
+
+    let now = Instant::now();
+    for path in event.paths {
+        if let Ok(relative) = path.strip_prefix(local_dir) {
+            if !relative.as_os_str().is_empty() {
+                pending.record(relative.to_path_buf(), now);
+            }
+        }
+    }
+}
+
+// Failures are logged and the path is pushed again on its next change, the requests
+// themselves are already retried with backoff
+async fn push(
+    mut remote: Option<&mut Remote<'_>>,
+    local_dir: &Path,
+    paths: &[PathBuf],
+    propagate_deletes: bool,
+) {
+    for action in plan(local_dir, paths, propagate_deletes) {
+        let path = action.path().display().to_string();
+
+        match remote.as_mut() {
+            Some(remote) => match remote.apply(local_dir, &action).await {
+                Ok(message) => log(message),
+                Err(err) => log(format!("Failed to push '{}': {}", path, err)),
+            },
+            None => log(match action {
+                Action::CreateFolder(_) => format!("Would create folder '{}'", path),
+                Action::Upload(_) => format!("Would upload '{}'", path),
+                Action::Trash(_) => format!("Would trash '{}'", path),
+            }),
+        }
+    }
+}
+
+fn log(message: impl Display) {
+    let now = time_fmt::format_in_timezone(chrono::Utc::now(), &chrono::Local);
+    println!("[{}] {}", now, message);
+}
+
+// The remote folder being pushed to, with the ids of the folders below it that were resolved
+// and the contents of the folders files were pushed to
+struct Remote<'a> {
+    hub: &'a Hub,
+    path: String,
+    folders: HashMap<PathBuf, String>,
+    // By folder id, listed once and then kept up to date with what is pushed
+    listings: HashMap<String, Vec<google_drive3::api::File>>,
+}
+
+impl<'a> Remote<'a> {
+    async fn new(hub: &'a Hub, path: &str) -> Result<Remote<'a>, Error> {
+        let folder = path_utils::resolve_directory(hub, path, true)
+            .await
+            .map_err(Error::ResolveRemote)?;
+        if !drive_file::is_directory(&folder) {
+            return Err(Error::RemoteNotFolder(path.to_string()));
+        }
+
+        let mut folders = HashMap::new();
+        folders.insert(PathBuf::new(), folder.id.unwrap_or_default());
+
+        Ok(Remote {
+            hub,
+            path: path.to_string(),
+            folders,
+            listings: HashMap::new(),
+        })
+    }
+
+    async fn apply(&mut self, local_dir: &Path, action: &Action) -> Result<String, Error> {
+        match action {
+            Action::CreateFolder(path) => {
+                self.folder_id(path, true).await?;
+                Ok(format!("Created folder '{}'", path.display()))
+            }
+            Action::Upload(path) => self.upload(local_dir, path).await,
+            Action::Trash(path) => self.trash(path).await,
+        }
+    }
+
+    async fn upload(&mut self, local_dir: &Path, path: &Path) -> Result<String, Error> {
+        let parent_id = self.folder_id(parent_of(path), true).await?;
+        let name = file_name(path);
+        let existing = self.listing(&parent_id).await?;
+        let existing_id =
+            upload::find_existing(existing, &name, false).and_then(|file| file.id.clone());
+
+        let file_path = local_dir.join(path);
+        let file = fs::File::open(&file_path).map_err(Error::OpenFile)?;
+        let file_info = FileInfo::from_file(
+            &file,
+            &file_info::Config {
+                file_path,
+                mime_type: None,
+                parents: Some(vec![parent_id.clone()]),
+            },
+        )
+        .map_err(Error::FileInfo)?;
+        let size = file_info.size;
+
+        let delegate_config = UploadDelegateConfig::default();
+        let reader = file_info::upload_reader(file, delegate_config.chunk_size.in_bytes())
+            .map_err(Error::OpenFile)?;
+        let verb = match existing_id {
+            Some(file_id) => {
+                files::update::update_file(self.hub, reader, &file_id, file_info, delegate_config)
+                    .await
+                    .map_err(Error::Upload)?;
+                "Updated"
+            }
+            None => {
+                let mut file =
+                    upload::upload_file(self.hub, reader, None, file_info, delegate_config)
+                        .await
+                        .map_err(Error::Upload)?;
+                file.name.get_or_insert(name);
+                self.listing(&parent_id).await?.push(file);
+                "Uploaded"
+            }
+        };
+
+        Ok(format!(
+            "{} '{}' ({})",
+            verb,
+            path.display(),
+            byte_size::format_human(size as u128)
+        ))
+    }
+
+    async fn trash(&mut self, path: &Path) -> Result<String, Error> {
+        let parent_id = match self.folder_id(parent_of(path), false).await {
+            Ok(id) => id,
+            Err(Error::ResolveRemote(PathResolutionError::NotFound(_))) => {
+                return Ok(format!("Nothing to trash for '{}'", path.display()))
+            }
+            Err(err) => return Err(err),
+        };
+
+        let name = file_name(path);
+        let existing = self.listing(&parent_id).await?;
+        let file_id = match existing
+            .iter()
+            .find(|file| file.name.as_deref() == Some(name.as_str()))
+        {
+            Some(file) => file.id.clone().unwrap_or_default(),
+            None => return Ok(format!("Nothing to trash for '{}'", path.display())),
+        };
+
+        let patch_file = PatchFile::new(file_id.clone()).with_trashed(true);
+        files::update::update_metadata(self.hub, UploadDelegateConfig::default(), patch_file)
+            .await
+            .map_err(Error::Trash)?;
+
+        if let Some(listing) = self.listings.get_mut(&parent_id) {
+            listing.retain(|file| file.id.as_deref() != Some(file_id.as_str()));
+        }
+
+        // The folders below a trashed folder are gone along with their listings
+        let listings = &mut self.listings;
+        self.folders.retain(|folder, id| {
+            let trashed = folder.starts_with(path);
+            if trashed {
+                listings.remove(id);
+            }
+            !trashed
+        });
+        Ok(format!("Trashed '{}'", path.display()))
+    }
+
+    // Missing folders are only created when create is set
+    async fn folder_id(&mut self, path: &Path, create: bool) -> Result<String, Error> {
+        if let Some(id) = self.folders.get(path) {
+            return Ok(id.clone());
+        }
+
+        let remote_path = remote_path(&self.path, path);
+        let folder = path_utils::resolve_directory(self.hub, &remote_path, create)
+            .await
+            .map_err(Error::ResolveRemote)?;
+        if !drive_file::is_directory(&folder) {
+            return Err(Error::RemoteNotFolder(remote_path));
+        }
+
+        // The folder may have just been created, so the listing of its parent is out of date
+        if let Some(parent_id) = self.folders.get(parent_of(path)) {
+            self.listings.remove(parent_id);
+        }
+
+        let id = folder.id.unwrap_or_default();
+        self.folders.insert(path.to_path_buf(), id.clone());
+        Ok(id)
+    }
+
+    async fn listing(
+        &mut self,
+        folder_id: &str,
+    ) -> Result<&mut Vec<google_drive3::api::File>, Error> {
+        match self.listings.entry(folder_id.to_string()) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                let files = files::list::list_folder(self.hub, folder_id)
+                    .await
+                    .map_err(Error::ListFiles)?;
+                Ok(entry.insert(files))
+            }
+        }
+    }
+}
+
+fn parent_of(path: &Path) -> &Path {
+    path.parent().unwrap_or_else(|| Path::new(""))
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// Drive paths always use '/', whatever the local separator is
+fn remote_path(root: &str, path: &Path) -> String {
+    let components: Vec<String> = path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect();
+
+    format!("{}/{}", root.trim_end_matches('/'), components.join("/"))
+}
+
+#[derive(Debug)]
+pub enum Error {
+    ReadOnly(read_only::Error),
+    LocalDir(PathBuf, io::Error),
+    NotADirectory(PathBuf),
+    ResolveRemote(PathResolutionError),
+    RemoteNotFolder(String),
+    Watch(notify::Error),
+    ListFiles(files::list::Error),
+    OpenFile(io::Error),
+    FileInfo(file_info::Error),
+    Upload(google_drive3::Error),
+    Trash(google_drive3::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::ReadOnly(err) => write!(f, "{}", err),
+            Error::LocalDir(path, err) => write!(f, "Failed to open '{}': {}", path.display(), err),
+            Error::NotADirectory(path) => write!(f, "'{}' is not a directory", path.display()),
+            Error::ResolveRemote(err) => write!(f, "Failed to resolve remote path: {}", err),
+            Error::RemoteNotFolder(path) => write!(f, "'{}' is not a folder", path),
+            Error::Watch(err) => write!(f, "Failed to watch for changes: {}", err),
+            Error::ListFiles(err) => write!(f, "Failed to list files: {}", err),
+            Error::OpenFile(err) => write!(f, "Failed to open file: {}", err),
+            Error::FileInfo(err) => write!(f, "{}", err),
+            Error::Upload(err) => write!(f, "Failed to upload file: {}", err),
+            Error::Trash(err) => write!(f, "Failed to trash file: {}", err),
+        }
+    }
+}
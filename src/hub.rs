@@ -1,4 +1,6 @@
 use crate::app_config;
+use crate::app_config::AccountScope;
+use google_drive3::client::GetToken;
 use google_drive3::hyper;
 use google_drive3::hyper::client::HttpConnector;
 use google_drive3::hyper_rustls::HttpsConnector;
@@ -7,42 +9,157 @@ use google_drive3::oauth2;
 use google_drive3::oauth2::authenticator::Authenticator;
 use google_drive3::oauth2::authenticator_delegate::InstalledFlowDelegate;
 use google_drive3::DriveHub;
+use hyper_timeout::TimeoutConnector;
+use std::error;
 use std::future::Future;
 use std::io;
 use std::ops::Deref;
 use std::path::PathBuf;
 use std::pin::Pin;
+use std::time::Duration;
 
+pub const SCOPES: &[&str] = &[
+    "https://www.googleapis.com/auth/drive",
+    "https://www.googleapis.com/auth/drive.metadata.readonly",
+];
+
+// Not part of SCOPES, only requested by commands that use the appDataFolder or photos space
+pub const APP_DATA_SCOPE: &str = "https://www.googleapis.com/auth/drive.appdata";
+pub const PHOTOS_SCOPE: &str = "https://www.googleapis.com/auth/drive.photos.readonly";
+
+// Replaces SCOPES for accounts added with --scope drive.file
+pub const FILE_SCOPE: &str = "https://www.googleapis.com/auth/drive.file";
+
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+// How a hub talks to drive, each hub gets its own so hubs of different accounts can live side
+// by side
+#[derive(Debug, Clone)]
 pub struct HubConfig {
-    pub secret: oauth2::ApplicationSecret,
-    pub tokens_path: PathBuf,
+    pub account_scope: AccountScope,
+    // Requested with every api call on top of the account scopes. When the saved token doesn't
+    // include them, the user is asked to approve access again.
+    pub extra_scopes: Vec<&'static str>,
+    // A request fails when its connection hasn't sent or received anything for this long
+    pub request_timeout: Duration,
+}
+
+impl Default for HubConfig {
+    fn default() -> Self {
+        HubConfig {
+            account_scope: AccountScope::Full,
+            extra_scopes: Vec::new(),
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+        }
+    }
+}
+
+impl HubConfig {
+    // Scopes of the account followed by the extra scopes it doesn't already include
+    pub fn requested_scopes(&self) -> Vec<&'static str> {
+        let mut scopes = self.account_scope.oauth_scopes().to_vec();
+        for &scope in &self.extra_scopes {
+            if !scopes.contains(&scope) {
+                scopes.push(scope);
+            }
+        }
+        scopes
+    }
 }
 
-pub struct Hub(DriveHub<HttpsConnector<HttpConnector>>);
+type Connector = TimeoutConnector<HttpsConnector<HttpConnector>>;
+
+// Same as the default base url of the generated api
+const DEFAULT_API_URL: &str = "https://www.googleapis.com/drive/v3/";
+
+pub struct Hub {
+    hub: DriveHub<Connector>,
+    account_scope: AccountScope,
+    // Base of the drive api urls, for the requests the generated api can't make
+    api_url: String,
+}
 
 impl Deref for Hub {
-    type Target = DriveHub<HttpsConnector<HttpConnector>>;
+    type Target = DriveHub<Connector>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.hub
     }
 }
 
 impl Hub {
-    pub async fn new(auth: Auth) -> Hub {
-        let connector = HttpsConnectorBuilder::new()
-            .with_native_roots()
-            .https_or_http()
-            .enable_http1()
-            .enable_http2()
-            .build();
+    pub async fn new(auth: Auth, config: &HubConfig) -> Hub {
+        let scopes = match config.account_scope {
+            AccountScope::Full => None,
+            scope => Some(scope.oauth_scopes()),
+        };
+
+        let client = http_client(config.request_timeout);
+        let hub = if scopes.is_none() && config.extra_scopes.is_empty() {
+            google_drive3::DriveHub::new(client, auth.0)
+        } else {
+            let auth = ScopedAuth {
+                auth: auth.0,
+                scopes,
+                extra_scopes: config.extra_scopes.clone(),
+            };
+            google_drive3::DriveHub::new(client, auth)
+        };
+
+        Hub {
+            hub,
+            account_scope: config.account_scope,
+            api_url: String::from(DEFAULT_API_URL),
+        }
+    }
+
+    // Hub sending requests to root_url with a fixed access token, i.e. a local mock server
+    pub fn with_root_url(root_url: &str, access_token: &str) -> Hub {
+        Hub::with_root_url_and_config(root_url, access_token, &HubConfig::default())
+    }
+
+    pub fn with_root_url_and_config(root_url: &str, access_token: &str, config: &HubConfig) -> Hub {
+        let root_url = root_url.trim_end_matches('/');
+        let client = http_client(config.request_timeout);
+        let mut hub = google_drive3::DriveHub::new(client, access_token.to_string());
+        let api_url = format!("{}/drive/v3/", root_url);
+        hub.root_url(format!("{}/", root_url));
+        hub.base_url(api_url.clone());
+
+        Hub {
+            hub,
+            account_scope: config.account_scope,
+            api_url,
+        }
+    }
 
-        let http_client = hyper::Client::builder().build(connector);
+    pub fn account_scope(&self) -> AccountScope {
+        self.account_scope
+    }
 
-        Hub(google_drive3::DriveHub::new(http_client, auth.0))
+    // i.e. "https://www.googleapis.com/drive/v3/", always ends with a slash
+    pub fn api_url(&self) -> &str {
+        &self.api_url
     }
 }
 
+fn http_client(timeout: Duration) -> hyper::Client<Connector> {
+    let https_connector = HttpsConnectorBuilder::new()
+        .with_native_roots()
+        .https_or_http()
+        .enable_http1()
+        .enable_http2()
+        .build();
+
+    let mut connector = TimeoutConnector::new(https_connector);
+    connector.set_connect_timeout(Some(CONNECT_TIMEOUT));
+    connector.set_read_timeout(Some(timeout));
+    connector.set_write_timeout(Some(timeout));
+
+    hyper::Client::builder().build(connector)
+}
+
 pub struct Auth(pub Authenticator<HttpsConnector<HttpConnector>>);
 
 impl Deref for Auth {
@@ -54,12 +171,17 @@ impl Deref for Auth {
 }
 
 impl Auth {
+    // The hub config tells the user which access is asked for when a new login is needed
     pub async fn new(
         config: &app_config::Secret,
         tokens_path: &PathBuf,
+        hub_config: &HubConfig,
     ) -> Result<Auth, io::Error> {
         let secret = oauth2_secret(config);
-        let delegate = Box::new(AuthDelegate);
+        let delegate = Box::new(AuthDelegate {
+            account_scope: hub_config.account_scope,
+            extra_scopes: hub_config.extra_scopes.clone(),
+        });
 
         let auth = oauth2::InstalledFlowAuthenticator::builder(
             secret,
@@ -72,6 +194,63 @@ impl Auth {
 
         Ok(Auth(auth))
     }
+
+    // Refreshes access tokens with the given refresh token, tokens are only kept in memory
+    pub async fn from_refresh_token(
+        config: &app_config::Secret,
+        refresh_token: &str,
+    ) -> Result<Auth, io::Error> {
+        let secret = oauth2::authorized_user::AuthorizedUserSecret {
+            client_id: config.client_id.clone(),
+            client_secret: config.client_secret.clone(),
+            refresh_token: refresh_token.to_string(),
+            key_type: String::from("authorized_user"),
+        };
+
+        let auth = oauth2::AuthorizedUserAuthenticator::builder(secret)
+            .build()
+            .await?;
+
+        Ok(Auth(auth))
+    }
+}
+
+// Adds the extra scopes to the scopes of every token request. With a narrow account
+// scope, the scopes picked by the api method are replaced by the account scopes.
+#[derive(Clone)]
+struct ScopedAuth {
+    auth: Authenticator<HttpsConnector<HttpConnector>>,
+    scopes: Option<&'static [&'static str]>,
+    extra_scopes: Vec<&'static str>,
+}
+
+impl GetToken for ScopedAuth {
+    fn get_token<'a>(
+        &'a self,
+        scopes: &'a [&str],
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Option<String>, Box<dyn error::Error + Send + Sync>>>
+                + Send
+                + 'a,
+        >,
+    > {
+        Box::pin(async move {
+            let base_scopes: &[&str] = match self.scopes {
+                Some(account_scopes) => account_scopes,
+                None => scopes,
+            };
+
+            let mut all_scopes: Vec<&str> = base_scopes.to_vec();
+            for &scope in &self.extra_scopes {
+                if !all_scopes.contains(&scope) {
+                    all_scopes.push(scope);
+                }
+            }
+
+            self.auth.get_token(&all_scopes).await
+        })
+    }
 }
 
 fn oauth2_secret(config: &app_config::Secret) -> oauth2::ApplicationSecret {
@@ -90,7 +269,10 @@ fn oauth2_secret(config: &app_config::Secret) -> oauth2::ApplicationSecret {
     }
 }
 
-struct AuthDelegate;
+struct AuthDelegate {
+    account_scope: AccountScope,
+    extra_scopes: Vec<&'static str>,
+}
 
 impl InstalledFlowDelegate for AuthDelegate {
     fn present_user_url<'a>(
@@ -98,14 +280,34 @@ impl InstalledFlowDelegate for AuthDelegate {
         url: &'a str,
         _need_code: bool,
     ) -> Pin<Box<dyn Future<Output = Result<String, String>> + Send + 'a>> {
-        Box::pin(present_user_url(url))
+        Box::pin(present_user_url(
+            url,
+            self.account_scope,
+            &self.extra_scopes,
+        ))
     }
 }
 
-async fn present_user_url(url: &str) -> Result<String, String> {
+async fn present_user_url(
+    url: &str,
+    account_scope: AccountScope,
+    extra_scopes: &[&str],
+) -> Result<String, String> {
     println!();
     println!();
     println!("Gdrive requires permissions to manage your files on Google Drive.");
+
+    if account_scope == AccountScope::File {
+        println!("Only files created by gdrive will be accessible (drive.file scope).");
+    }
+
+    if !extra_scopes.is_empty() {
+        println!(
+            "This command also needs access that the saved login doesn't include: {}",
+            extra_scopes.join(", ")
+        );
+    }
+
     println!("Open the url in your browser and follow the instructions:");
     println!("{}", url);
     Ok(String::new())
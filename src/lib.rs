@@ -6,4 +6,6 @@ pub mod drives;
 pub mod files;
 pub mod hub;
 pub mod permissions;
+#[cfg(feature = "self-update")]
+pub mod self_update;
 pub mod version;
@@ -6,14 +6,41 @@ pub mod drives;
 pub mod files;
 pub mod hub;
 pub mod permissions;
+#[cfg(feature = "self-update")]
+pub mod self_update;
 pub mod version;
 
 use clap::{Parser, Subcommand};
+use app_config::AccountScope;
+use common::account_picker;
+use common::byte_size::ByteSize;
+use common::byte_size::SizeUnits;
+use common::byte_size::MIB;
+use common::cancel;
+use common::deadline;
+use common::deadline::Deadline;
 use common::delegate::ChunkSize;
+use common::delegate::DEFAULT_MAX_RETRY_DURATION_SECS;
+use common::delegate::UploadMode;
+use common::env_auth;
+use common::mime_overrides;
+use common::mime_overrides::MimeOverrides;
+use common::output::OutputMode;
+use common::path_filter::PathFilter;
+use common::path_filter::Pattern;
 use common::permission;
+use common::read_only;
+use common::temp_copy::TempCopy;
+use common::time_fmt::TimeStyle;
+use crate::common::drive_error;
 use crate::common::drive_file;
+use crate::files::dedupe_check::DedupeAction;
 use crate::files::list::ListSortOrder;
+use crate::files::list::Space;
+use crate::files::stat::StatFormat;
 use crate::common::hub_helper;
+use crate::hub::Hub;
+use crate::hub::HubConfig;
 use crate::files::list::ListQuery;
 use crate::files::info::info;
 use crate::files::list::list;
@@ -30,20 +57,55 @@ use crate::files::import::import;
 use crate::files::export::export;
 use mime::Mime;
 use std::error::Error;
+use std::io::IsTerminal;
 use std::path::PathBuf;
-use std::str::FromStr;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None, disable_version_flag = true)]
 struct Cli {
     #[command(subcommand)]
     command: Command,
+
+    /// Refuse to run commands that modify the drive. Can also be enabled by setting "read_only": true in settings.json in the config directory
+    #[arg(long, global = true)]
+    read_only: bool,
+
+    /// When no account is selected and one is picked at the prompt, make it the current account
+    #[arg(long, global = true)]
+    remember: bool,
+
+    /// Fail a request when its connection has been idle for this many seconds (at least 1)
+    #[arg(
+        long,
+        global = true,
+        value_name = "SECONDS",
+        default_value_t = hub::DEFAULT_REQUEST_TIMEOUT.as_secs(),
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    request_timeout: u64,
+
+    /// Stop the command once it has run this long (e.g. 90s, 15m, 1h30m). Recursive operations stop after the current file and report what was done, the exit code is 124
+    #[arg(long, global = true, value_name = "DURATION")]
+    deadline: Option<Deadline>,
+
+    /// Approve access for the current account again before running the command. Use it when the saved login is missing access the command needs
+    #[arg(long, global = true)]
+    reauth: bool,
+
+    /// Read a refresh token from the first line of stdin and use it instead of a saved account. GDRIVE_CLIENT_ID and GDRIVE_CLIENT_SECRET must be set. GDRIVE_REFRESH_TOKEN can be used instead of stdin
+    #[arg(long, global = true, conflicts_with = "reauth")]
+    refresh_token_stdin: bool,
 }
 
 #[derive(Subcommand)]
 enum Command {
     /// Print information about gdrive
-    About,
+    About {
+        /// Print the information and build metadata as json
+        #[arg(long)]
+        json: bool,
+    },
 
     /// Commands for managing accounts
     Account {
@@ -75,7 +137,7 @@ enum Command {
         /// Local file or directory path
         file_path: PathBuf,
 
-        /// Remote destination path (e.g., "/path/to/destination")
+        /// Remote destination path (e.g., "/path/to/destination"). Missing directories are created unless --strict-path is given
         remote_path: String,
 
         /// MIME type (default: auto-detect)
@@ -86,22 +148,100 @@ enum Command {
         #[arg(short, long)]
         recursive: bool,
 
+        /// Upload the contents of the directory into the remote directory instead of creating a directory for it. Same as a trailing slash on the local path (dir/)
+        #[arg(long, requires = "recursive")]
+        contents: bool,
+
         /// Overwrite existing files without prompting
         #[arg(long, short = 'y')]
         overwrite: bool,
+
+        /// Also check the subdirectories that already exist in the destination before prompting, one listing per directory
+        #[arg(long, requires = "recursive", conflicts_with = "overwrite")]
+        deep_check: bool,
+
+        /// Don't delete the temporary copy made when the remote path renames the file (for debugging)
+        #[arg(long)]
+        keep_temp: bool,
+
+        /// Fail if a directory in the remote path doesn't exist instead of creating it. Recommended for scripts and CI, where a typo would otherwise create junk folders
+        #[arg(long, visible_alias = "no-create")]
+        strict_path: bool,
+
+        /// Print the uploaded file as json (id, name, size, md5Checksum, webViewLink and parents), or an array of every created file and folder for a recursive push. Nothing else is printed to stdout
+        #[arg(long)]
+        json: bool,
+
+        /// Before uploading each file, look for a file you own with the same content (md5 checksum and size) anywhere in the drive and print its path and id. Lists every file in the drive once, also for a recursive upload
+        #[arg(long)]
+        dedupe_check: bool,
+
+        /// What --dedupe-check does with a file that already exists: skip the upload, create a shortcut to the existing file, or upload anyway
+        #[arg(long, value_name = "ACTION", requires = "dedupe_check", default_value_t = DedupeAction::default())]
+        dedupe_action: DedupeAction,
+    },
+
+    /// Watch a local directory and push created and changed files until Ctrl-C
+    Watch {
+        /// Local directory to watch
+        local_dir: PathBuf,
+
+        /// Remote folder path (e.g., "/backup/photos"). Missing folders are created
+        remote_path: String,
+
+        /// Trash the remote file or folder when it's deleted locally
+        #[arg(long)]
+        propagate_deletes: bool,
+
+        /// Only log what would be pushed, without connecting to Google Drive
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Seconds without changes to a file before it's pushed
+        #[arg(long, value_name = "SECONDS", default_value_t = files::watch::DEFAULT_DEBOUNCE.as_secs())]
+        debounce: u64,
+    },
+
+    /// Update gdrive to the latest release
+    #[cfg(feature = "self-update")]
+    #[command(name = "update")]
+    SelfUpdate {
+        /// Only report whether a newer release exists. Exits with 1 when there is one and 0 when gdrive is up to date
+        #[arg(long)]
+        check: bool,
+
+        /// Update without asking for confirmation
+        #[arg(long, short = 'y', conflicts_with = "check")]
+        yes: bool,
     },
 
     /// Print version information
-    Version,
+    Version {
+        /// Print the version, commit, build date, rustc version, target and enabled features as json
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Subcommand)]
 enum AccountCommand {
     /// Add an account
-    Add,
+    Add {
+        /// Access to request: drive for all files, or drive.file for only the files gdrive creates. Listing and downloading other files doesn't work with drive.file
+        #[arg(long, default_value_t = AccountScope::Full)]
+        scope: AccountScope,
+
+        /// Use the OAuth client in this client_secret.json, as downloaded from the Google Cloud console, instead of prompting for the client id and secret. Refreshing the account keeps using it
+        #[arg(long, value_name = "PATH")]
+        client_secret: Option<PathBuf>,
+    },
 
     /// List all accounts
-    List,
+    List {
+        /// Also print the scope of each account
+        #[arg(long)]
+        details: bool,
+    },
 
     /// Print current account
     Current,
@@ -116,18 +256,47 @@ enum AccountCommand {
     Remove {
         /// Account name
         account_name: String,
+
+        /// Revoke the account's access at Google before removing the local credentials
+        #[arg(long)]
+        revoke: bool,
+
+        /// Remove without asking for confirmation
+        #[arg(long)]
+        force: bool,
     },
 
     /// Export account, this will create a zip file of the account which can be imported
     Export {
         /// Account name
         account_name: String,
+
+        /// Export format. The json format contains the client credentials and refresh token in a single document, suitable for CI secrets
+        #[arg(long, value_name = "tar|json", default_value_t = account::export::ExportFormat::default())]
+        format: account::export::ExportFormat,
+
+        /// Print the exported json to stdout instead of writing a file. This prints your credentials!
+        #[arg(long)]
+        stdout: bool,
+
+        /// Encrypt the tar archive with a passphrase, which is asked for or read from GDRIVE_EXPORT_PASSPHRASE. Import detects encrypted archives and asks for the passphrase
+        #[arg(long, conflicts_with = "stdout")]
+        encrypt: bool,
     },
 
     /// Import account that was created with the export command
     Import {
         /// Path to archive
-        file_path: PathBuf,
+        #[arg(required_unless_present_any = ["from_env", "from_stdin"])]
+        file_path: Option<PathBuf>,
+
+        /// Import account json from an environment variable
+        #[arg(long, value_name = "ENV_VAR", num_args = 0..=1, default_missing_value = "GDRIVE_AUTH_JSON", conflicts_with_all = ["file_path", "from_stdin"])]
+        from_env: Option<String>,
+
+        /// Import account json from stdin
+        #[arg(long, conflicts_with = "file_path")]
+        from_stdin: bool,
     },
 }
 
@@ -142,6 +311,18 @@ enum DriveCommand {
         /// Field separator
         #[arg(long, default_value_t = String::from("\t"))]
         field_separator: String,
+
+        /// Only list drives with a name containing this text
+        #[arg(long, value_name = "TEXT")]
+        name_contains: Option<String>,
+
+        /// Sort the drives: name (case insensitive) or created (oldest first)
+        #[arg(long, value_name = "ORDER")]
+        sort: Option<drives::list::DriveSort>,
+
+        /// Print the drives as a json array with every field Drive returns
+        #[arg(long)]
+        json: bool,
     },
 }
 
@@ -151,30 +332,136 @@ enum FileCommand {
     Info {
         /// File id or path
         file_id: String,
+
+        /// Also print what the current account is allowed to do with the file (download, edit, share, delete, ...)
+        #[arg(long)]
+        check_access: bool,
+
+        /// Also print what you can do with the file (i.e. edit, share or delete it), its owner, who shared it and its permissions (when you can read them)
+        #[arg(long)]
+        capabilities: bool,
+
+        /// Print everything fetched about the file as json, i.e. with --capabilities also the capabilities and permissions
+        #[arg(long, conflicts_with = "check_access")]
+        json: bool,
+
+        /// Print the file as json exactly as returned by the api, with every field unless --fields is given. Useful when reporting bugs
+        #[arg(long, conflicts_with_all = ["check_access", "capabilities", "json"])]
+        raw: bool,
+
+        /// Fields to request with --raw in the api's syntax, i.e. id,name,permissions(role). Defaults to * (every field)
+        #[arg(long, value_name = "FIELDS", requires = "raw")]
+        fields: Option<String>,
+
+        /// Let the path name a trashed file. A file that isn't trashed still wins when both have the path
+        #[arg(long)]
+        include_trashed: bool,
+
+        /// Show sizes in decimal units (kB, MB, GB: powers of 1000)
+        #[arg(long, conflicts_with = "iec")]
+        si: bool,
+
+        /// Show sizes in binary units (KiB, MiB, GiB: powers of 1024), the default
+        #[arg(long)]
+        iec: bool,
+
+        /// How to print times: local (local timezone), relative (e.g. "3 hours ago") or iso (RFC 3339 in UTC)
+        #[arg(long, default_value_t = TimeStyle::default())]
+        time_style: TimeStyle,
+    },
+
+    /// Print file info on a single line, for scripts
+    Stat {
+        /// File id or path
+        file_id: String,
+
+        /// Comma separated fields to print, in order. Valid fields: id, name, size, mimeType, modifiedTime, createdTime, md5Checksum, parents
+        #[arg(long, default_value_t = StatFormat::default())]
+        format: StatFormat,
+
+        /// Field separator
+        #[arg(long, default_value_t = String::from("\t"))]
+        field_separator: String,
+    },
+
+    /// Print info for many files in a table
+    Meta {
+        /// File ids
+        #[arg(required_unless_present = "ids_file")]
+        file_ids: Vec<String>,
+
+        /// Read file ids from a file, one id per line
+        #[arg(long, value_name = "PATH")]
+        ids_file: Option<PathBuf>,
+
+        /// Max number of files to fetch at the same time
+        #[arg(long, default_value_t = 8)]
+        concurrency: usize,
+
+        /// Don't print header
+        #[arg(long)]
+        skip_header: bool,
+
+        /// Field separator
+        #[arg(long, default_value_t = String::from("\t"))]
+        field_separator: String,
     },
 
     /// List files
+    #[command(group(clap::ArgGroup::new("json_source").args(["find_duplicates", "include_permissions"])))]
     List {
         /// Query string for filtering files
         #[arg(short, long)]
         query: Option<String>,
 
+        /// Read the query from a file. Lines starting with '#' are comments, the other lines are joined
+        #[arg(long, value_name = "PATH", conflicts_with = "query")]
+        query_file: Option<PathBuf>,
+
         /// Max files to list
         #[arg(long, default_value_t = 30)]
         max: usize,
 
-        /// Order by. See https://developers.google.com/drive/api/v3/reference/files/list
-        #[arg(long, default_value_t = ListSortOrder::default())]
-        order_by: ListSortOrder,
+        /// Order by, defaults to 'folder,modifiedTime desc,name'. See https://developers.google.com/drive/api/v3/reference/files/list
+        #[arg(long)]
+        order_by: Option<ListSortOrder>,
+
+        /// Sort the listed files by size, name or modified. Sorting happens after listing, so it only sorts the files --max lets through, and it replaces the order of --order-by
+        #[arg(long, value_name = "ORDER")]
+        sort: Option<files::list::FileSort>,
+
+        /// Reverse the order of --sort, i.e. largest first
+        #[arg(long, requires = "sort")]
+        reverse: bool,
+
+        /// List the N most recently modified files. Short for --order-by 'modifiedTime desc' --max N
+        #[arg(long, value_name = "N", conflicts_with_all = ["order_by", "max", "oldest"])]
+        newest: Option<usize>,
 
-        /// List files in a specific folder
-        #[arg(long, value_name = "DIRECTORY_ID")]
+        /// List the N least recently modified files. Short for --order-by modifiedTime --max N
+        #[arg(long, value_name = "N", conflicts_with_all = ["order_by", "max"])]
+        oldest: Option<usize>,
+
+        /// List files in a specific folder, by id or by path (e.g., "/Projects/2024")
+        #[arg(long, value_name = "DIRECTORY_ID_OR_PATH")]
         parent: Option<String>,
 
         /// List files on a shared drive
         #[arg(long, value_name = "DRIVE_ID")]
         drive: Option<String>,
 
+        /// Only list the top-level items of the shared drive given by --drive
+        #[arg(long, requires = "drive")]
+        top_level: bool,
+
+        /// Only list files owned by this email address. Combined with --parent, --drive or --query
+        #[arg(long, value_name = "EMAIL")]
+        owner: Option<String>,
+
+        /// Space to list: drive, appDataFolder (hidden application data) or photos. appDataFolder and photos need an extra scope, the first use asks to approve access again
+        #[arg(long, default_value_t = Space::default())]
+        space: Space,
+
         /// Don't print header
         #[arg(long)]
         skip_header: bool,
@@ -183,9 +470,65 @@ enum FileCommand {
         #[arg(long)]
         full_name: bool,
 
+        /// Show sizes in decimal units (kB, MB, GB: powers of 1000)
+        #[arg(long, conflicts_with = "iec")]
+        si: bool,
+
+        /// Show sizes in binary units (KiB, MiB, GiB: powers of 1024), the default
+        #[arg(long)]
+        iec: bool,
+
+        /// Output format: plain (tab separated) or pretty (aligned and colored). Defaults to pretty on a terminal and plain when piped
+        #[arg(long)]
+        output: Option<OutputMode>,
+
+        /// Print aligned columns with right aligned sizes, also when piped. Names are fitted to the terminal width unless --full-name is given. Same as --output pretty
+        #[arg(long, conflicts_with = "output")]
+        table: bool,
+
+        /// Don't color the output. Colors are also disabled when the NO_COLOR environment variable is set
+        #[arg(long)]
+        no_color: bool,
+
+        /// How to print times: local (local timezone), relative (e.g. "3 hours ago") or iso (RFC 3339 in UTC)
+        #[arg(long, default_value_t = TimeStyle::default())]
+        time_style: TimeStyle,
+
         /// Field separator
         #[arg(long, default_value_t = String::from("\t"))]
         field_separator: String,
+
+        /// Instead of listing, report names shared by more than one file in the folder given by --parent, with their ids, sizes and md5 checksums
+        #[arg(long, requires = "parent", conflicts_with_all = ["query", "query_file"])]
+        find_duplicates: bool,
+
+        /// With --find-duplicates, group files by md5 checksum to find identical content under different names
+        #[arg(long, requires = "find_duplicates")]
+        by_md5: bool,
+
+        /// Print the duplicates found by --find-duplicates, or the files with their permissions given by --include-permissions, as json
+        #[arg(long, requires = "json_source")]
+        json: bool,
+
+        /// Add a Sharing column: public, shared(N) for N permissions besides the owner, or private. Fetches the permissions of every file, which makes the listing larger. With --json each file has its full permissions array
+        #[arg(long, conflicts_with_all = ["find_duplicates", "empty_folders"])]
+        include_permissions: bool,
+
+        /// Instead of listing, walk every folder below --parent and print the path and id of the folders without any children
+        #[arg(long, requires = "parent", conflicts_with_all = ["query", "query_file", "find_duplicates"])]
+        empty_folders: bool,
+
+        /// Show the type and size of the target of each shortcut, marked as a shortcut. Makes one extra request per shortcut
+        #[arg(long, visible_alias = "dereference-shortcuts", conflicts_with_all = ["find_duplicates", "empty_folders"])]
+        resolve_shortcuts: bool,
+
+        /// Continue listing after the files of an earlier listing, with the token printed by --show-token. The query, --order-by and --space must be the same as in the earlier listing
+        #[arg(long, value_name = "TOKEN", conflicts_with_all = ["find_duplicates", "empty_folders"])]
+        page_token: Option<String>,
+
+        /// Print the token of the next page to stderr when there are more files than --max, to continue with --page-token
+        #[arg(long, conflicts_with_all = ["find_duplicates", "empty_folders"])]
+        show_token: bool,
     },
 
     /// Download file
@@ -216,27 +559,100 @@ enum FileCommand {
         /// Output to stdout
         #[arg(long)]
         stdout: bool,
+
+        /// Write an md5 checksum manifest of the downloaded files, verify with `md5sum -c` from the destination
+        #[arg(long, value_name = "PATH", conflicts_with = "stdout")]
+        manifest: Option<PathBuf>,
+
+        /// Space the file is in: drive, appDataFolder or photos. appDataFolder and photos need an extra scope, the first use asks to approve access again
+        #[arg(long, default_value_t = Space::default())]
+        space: Space,
+
+        /// Abort without downloading anything when the file, or the total of a recursive download, is larger than this, i.e. 2G or a number of bytes
+        #[arg(long, value_name = "SIZE")]
+        max_size: Option<ByteSize>,
+
+        /// Only download files this many levels deep in a recursive download, 1 for the files directly in the directory
+        #[arg(long, value_name = "N", requires = "recursive")]
+        max_depth: Option<usize>,
+
+        /// Only walk N directories down in a recursive download, 0 for the files directly in the directory. Same as --max-depth N+1
+        #[arg(long, value_name = "N", requires = "recursive", conflicts_with = "max_depth")]
+        depth: Option<usize>,
+
+        /// Only download files matching the glob in a recursive download, i.e. *.csv or reports/**/*.csv. A glob without '/' matches the name at any depth. Can be repeated
+        #[arg(long, value_name = "GLOB", requires = "recursive")]
+        include: Vec<Pattern>,
+
+        /// Don't download files and directories matching the glob in a recursive download, wins over --include. Can be repeated
+        #[arg(long, value_name = "GLOB", requires = "recursive")]
+        exclude: Vec<Pattern>,
+
+        /// Match --include and --exclude globs against the path relative to the downloaded directory, also without '/'
+        #[arg(long, requires = "recursive")]
+        match_path: bool,
+
+        /// Let the path name a trashed file. A file that isn't trashed still wins when both have the path
+        #[arg(long)]
+        include_trashed: bool,
+
+        /// Save the file under this name in the output directory instead of its name on drive. Only for a single file
+        #[arg(long = "as", value_name = "NAME", conflicts_with_all = ["recursive", "stdout"])]
+        save_as: Option<String>,
     },
 
     /// Upload file or directory
     Upload {
         /// Local file or directory path
-        file_path: PathBuf,
+        #[arg(required_unless_present = "retry_manifest")]
+        file_path: Option<PathBuf>,
 
-        /// Remote destination path (e.g., "/path/to/destination")
+        /// Remote destination path (e.g., "/path/to/destination"). Missing directories are created unless --strict-path is given
         remote_path: Option<String>,
 
+        /// Upload into this directory, by id or by path (e.g., "/Projects/2024"). Can be repeated to add the file to several directories. Use appDataFolder to upload hidden application data, which needs an extra scope that the first use asks to approve
+        #[arg(long, value_name = "DIRECTORY_ID_OR_PATH")]
+        parent: Option<Vec<String>>,
+
+        /// Don't check that the parent directories given by id exist before uploading. Saves a request per parent
+        #[arg(long)]
+        skip_parent_check: bool,
+
+        /// Create the missing directories of parents given as paths
+        #[arg(long, requires = "parent")]
+        create_parents: bool,
+
         /// MIME type (default: auto-detect)
         #[arg(short, long)]
         mime: Option<String>,
 
+        /// Use this MIME type for files with the extension, i.e. md=text/markdown. Can be repeated and adds to the mime_types table in settings.json. --mime still wins
+        #[arg(long, value_name = "EXTENSION=MIME")]
+        mime_from_extension: Vec<MimeOverrides>,
+
         /// Upload directories recursively
         #[arg(long)]
         recursive: bool,
 
-        /// Chunk size in bytes
-        #[arg(long)]
-        chunk_size: Option<u64>,
+        /// Upload the contents of the directory into the destination instead of creating a directory for it. Same as a trailing slash on the local path (dir/)
+        #[arg(long, requires = "recursive")]
+        contents: bool,
+
+        /// Name of the remote directory created for a recursive upload [default: name of the local directory]
+        #[arg(long, requires = "recursive", conflicts_with = "contents")]
+        name: Option<String>,
+
+        /// Fail a recursive upload that finds fifos, sockets or device files instead of skipping them
+        #[arg(long, requires = "recursive")]
+        strict: bool,
+
+        /// Set the modified time of files in a git working tree to the time of the last commit that touched them, other files get their filesystem mtime. Needs git
+        #[arg(long, requires = "recursive")]
+        mtime_from_git: bool,
+
+        /// Chunk size for resumable uploads, i.e. 8M or 512K (a multiple of 256K). A number without unit is in MB, auto tunes the size across files as chunks succeed or fail [default: 32M]
+        #[arg(long, value_name = "SIZE")]
+        chunk_size: Option<ChunkSize>,
 
         /// Print chunk errors
         #[arg(long)]
@@ -249,11 +665,67 @@ enum FileCommand {
         /// Print only the file ID
         #[arg(long)]
         print_only_id: bool,
+
+        /// Append chunk errors (timestamp, chunk range and message) to this file
+        #[arg(long, value_name = "PATH")]
+        error_log: Option<PathBuf>,
+
+        /// Truncate the error log before uploading instead of appending to it
+        #[arg(long, requires = "error_log")]
+        truncate_error_log: bool,
+
+        /// Give up retrying failed chunks after this many seconds
+        #[arg(long, value_name = "SECONDS", default_value_t = DEFAULT_MAX_RETRY_DURATION_SECS)]
+        max_retry_duration: u64,
+
+        /// Always use a resumable upload, which is sent in chunks and can recover from a failed chunk. Useful on flaky connections, costs an extra request per file
+        #[arg(long, conflicts_with = "force_simple")]
+        force_resumable: bool,
+
+        /// Always upload the file in a single request. Saves a request for tiny files, but a failure means starting over
+        #[arg(long)]
+        force_simple: bool,
+
+        /// Keep uploading the remaining files when a file in a directory fails to upload or can't be read, or a folder can't be created. The failures are listed at the end
+        #[arg(long)]
+        continue_on_error: bool,
+
+        /// Write the files that failed to upload to this json file
+        #[arg(long, value_name = "PATH")]
+        error_manifest: Option<PathBuf>,
+
+        /// Only upload the files listed in a manifest written by --error-manifest
+        #[arg(long, value_name = "PATH", conflicts_with = "remote_path")]
+        retry_manifest: Option<PathBuf>,
+
+        /// Don't delete the temporary copy made when the remote path renames the file (for debugging)
+        #[arg(long)]
+        keep_temp: bool,
+
+        /// Fail if a directory in the remote path doesn't exist instead of creating it. Recommended for scripts and CI, where a typo would otherwise create junk folders
+        #[arg(long, visible_alias = "no-create", requires = "remote_path")]
+        strict_path: bool,
+
+        /// Print the uploaded file as json (id, name, size, md5Checksum, webViewLink and parents), or an array of every created file and folder for a recursive upload. Nothing else is printed to stdout
+        #[arg(long, conflicts_with = "print_only_id")]
+        json: bool,
+
+        /// Skip files that have a file with the same name in the destination modified at or after the local file. Older remote files are updated, keeping their id
+        #[arg(long)]
+        if_newer: bool,
+
+        /// Before uploading each file, look for a file you own with the same content (md5 checksum and size) anywhere in the drive and print its path and id. Lists every file in the drive once, also for a recursive upload
+        #[arg(long)]
+        dedupe_check: bool,
+
+        /// What --dedupe-check does with a file that already exists: skip the upload, create a shortcut to the existing file, or upload anyway
+        #[arg(long, value_name = "ACTION", requires = "dedupe_check", default_value_t = DedupeAction::default())]
+        dedupe_action: DedupeAction,
     },
 
     /// Update file. This will create a new version of the file. The older versions will typically be kept for 30 days.
     Update {
-        /// File id of the file you want ot update
+        /// File id or path of the file you want to update (e.g., "/path/to/file" or "file_id")
         file_id: String,
 
         /// Path of file to upload
@@ -263,8 +735,8 @@ enum FileCommand {
         #[arg(long, value_name = "MIME_TYPE")]
         mime: Option<Mime>,
 
-        /// Set chunk size in MB, must be a power of two.
-        #[arg(long, value_name = "1|2|4|8|16|32|64|128|256|512|1024|4096|8192", default_value_t = ChunkSize::default())]
+        /// Chunk size for resumable uploads, i.e. 8M or 512K (a multiple of 256K). A number without unit is in MB, auto tunes the size across files as chunks succeed or fail
+        #[arg(long, value_name = "SIZE", default_value_t = ChunkSize::default())]
         chunk_size: ChunkSize,
 
         /// Print errors occuring during chunk upload
@@ -274,6 +746,38 @@ enum FileCommand {
         /// Print details about each chunk
         #[arg(long, value_name = "", default_value_t = false)]
         print_chunk_info: bool,
+
+        /// Append chunk errors (timestamp, chunk range and message) to this file
+        #[arg(long, value_name = "PATH")]
+        error_log: Option<PathBuf>,
+
+        /// Truncate the error log before uploading instead of appending to it
+        #[arg(long, requires = "error_log")]
+        truncate_error_log: bool,
+
+        /// Give up retrying failed chunks after this many seconds
+        #[arg(long, value_name = "SECONDS", default_value_t = DEFAULT_MAX_RETRY_DURATION_SECS)]
+        max_retry_duration: u64,
+
+        /// Always use a resumable upload, which is sent in chunks and can recover from a failed chunk. Useful on flaky connections, costs an extra request per file
+        #[arg(long, conflicts_with = "force_simple")]
+        force_resumable: bool,
+
+        /// Always upload the file in a single request. Saves a request for tiny files, but a failure means starting over
+        #[arg(long)]
+        force_simple: bool,
+
+        /// Print the updated file as json (id, name, size, md5Checksum, webViewLink and parents). Nothing else is printed to stdout
+        #[arg(long)]
+        json: bool,
+
+        /// Print only the file ID
+        #[arg(long, conflicts_with = "json")]
+        print_only_id: bool,
+
+        /// Rename the file to this name [default: keep the remote name]
+        #[arg(long)]
+        name: Option<String>,
     },
 
     /// Delete file
@@ -284,6 +788,35 @@ enum FileCommand {
         /// Delete directory and all it's content
         #[arg(long)]
         recursive: bool,
+
+        /// Max number of files to delete at the same time when deleting a directory
+        #[arg(long, default_value_t = 8)]
+        concurrency: usize,
+
+        /// Space the file is in: drive or appDataFolder. appDataFolder needs an extra scope, the first use asks to approve access again
+        #[arg(long, default_value_t = Space::default())]
+        space: Space,
+    },
+
+    /// Print examples of the query syntax used by files list --query
+    QueryHelp,
+
+    /// Trash all but one of the files sharing a name in a folder. Only prints what would be trashed unless --apply is given
+    Dedup {
+        /// Folder id or path (e.g., "/Projects/2024")
+        folder: String,
+
+        /// Group files by md5 checksum instead of by name, to trash identical content under any name
+        #[arg(long)]
+        by_md5: bool,
+
+        /// Which file of each group to keep, by created time: oldest or newest
+        #[arg(long, default_value_t = files::dedup::Keep::default())]
+        keep: files::dedup::Keep,
+
+        /// Trash the duplicates instead of only printing them
+        #[arg(long)]
+        apply: bool,
     },
 
     /// Create directory
@@ -291,13 +824,46 @@ enum FileCommand {
         /// Name
         name: String,
 
-        /// Create in an existing directory
-        #[arg(long, value_name = "DIRECTORY_ID")]
+        /// Create in an existing directory, by id or by path (e.g., "/Projects/2024")
+        #[arg(long, value_name = "DIRECTORY_ID_OR_PATH")]
         parent: Option<Vec<String>>,
 
         /// Print only id of folder
         #[arg(long, default_value_t = false)]
         print_only_id: bool,
+
+        /// Don't check that the parent directories given by id exist before creating the directory. Saves a request per parent
+        #[arg(long)]
+        skip_parent_check: bool,
+
+        /// Create the missing directories of parents given as paths
+        #[arg(long, requires = "parent")]
+        create_parents: bool,
+
+        /// Treat name as a path (e.g. "/a/b/c") and create every missing directory on it
+        #[arg(long, conflicts_with_all = ["parent", "skip_parent_check"])]
+        parents_path: bool,
+
+        /// Print every directory on the path as a json array of name, id and whether it was created
+        #[arg(long, requires = "parents_path")]
+        json: bool,
+    },
+
+    /// Create a shortcut to a file or directory, to have it in several folders without copying it
+    Shortcut {
+        /// Id or path of the file the shortcut points to
+        target_id: String,
+
+        /// Name of the shortcut
+        name: String,
+
+        /// Folder to create the shortcut in, by id or by path (e.g., "/Projects/2024"). Can be specified multiple times
+        #[arg(long, value_name = "DIRECTORY_ID|PATH")]
+        parent: Option<Vec<String>>,
+
+        /// Print only the id of the shortcut
+        #[arg(long)]
+        print_only_id: bool,
     },
 
     /// Rename file/directory
@@ -307,6 +873,10 @@ enum FileCommand {
 
         /// New name
         name: String,
+
+        /// Rename even if a file with the new name already exists in the same folder
+        #[arg(long)]
+        force: bool,
     },
 
     /// Move file/directory
@@ -316,6 +886,10 @@ enum FileCommand {
 
         /// Id of folder to move to
         folder_id: String,
+
+        /// When Drive copies the file to another shared drive instead of moving it, compare the md5 checksum of the copy with the source before trashing the source
+        #[arg(long, alias = "after-copy-verify")]
+        verify: bool,
     },
 
     /// Copy file
@@ -325,29 +899,92 @@ enum FileCommand {
 
         /// Id of folder to copy to
         folder_id: String,
+
+        /// Copy even if the folder already holds the file, which creates a duplicate
+        #[arg(long)]
+        allow_same_parent: bool,
+
+        /// Don't give the copy the description, starred flag and properties of the file
+        #[arg(long)]
+        no_metadata: bool,
+    },
+
+    /// Copy a file or directory to another account. Google documents are exported to office formats and converted back
+    Transfer {
+        /// Id of the file or directory, or a path with a leading slash
+        source: String,
+
+        /// Name of the account to copy to, as shown by 'account list'
+        #[arg(long, value_name = "ACCOUNT_NAME")]
+        to_account: String,
+
+        /// Directory path in the other account (e.g., "/Backup"). Missing directories are created
+        #[arg(long, value_name = "PATH")]
+        dest: String,
+
+        /// Skip files that already exist in the destination, i.e. to continue a transfer that failed
+        #[arg(long)]
+        skip_existing: bool,
     },
 
     /// Import file as a google document/spreadsheet/presentation.
     /// Example of file types that can be imported: doc, docx, odt, pdf, html, xls, xlsx, csv, ods, ppt, pptx, odp
     Import {
-        /// Path to file
-        file_path: PathBuf,
+        /// Path to file, '-' to read from stdin or a https:// url to download
+        #[arg(required_unless_present = "url")]
+        source: Option<files::import::ImportSource>,
+
+        /// Download the content from this https:// url. The type is taken from the Content-Type of the response unless --source-mime is given
+        #[arg(long, value_name = "URL", value_parser = files::import::ImportSource::from_url, conflicts_with = "source")]
+        url: Option<files::import::ImportSource>,
 
-        /// Upload to an existing directory
-        #[arg(long, value_name = "DIRECTORY_ID")]
+        /// Import into this directory, given as an id or a path with a leading slash (/Imports/2024). Missing directories on a path are created unless --strict-path is given
+        #[arg(long, value_name = "DIRECTORY_ID|PATH")]
         parent: Option<Vec<String>>,
 
+        /// Fail if a directory in a --parent path doesn't exist instead of creating it
+        #[arg(long, visible_alias = "no-create", requires = "parent")]
+        strict_path: bool,
+
         /// Print only id of file
         #[arg(long, default_value_t = false)]
         print_only_id: bool,
+
+        /// Document type to import as [default: based on the file type]
+        #[arg(long, value_name = "doc|sheet|slides")]
+        to: Option<drive_file::DocType>,
+
+        /// Name of the imported document. Required when importing from stdin
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Mime type of the source content, i.e. text/csv. Required when importing from stdin
+        #[arg(long, value_name = "MIME_TYPE")]
+        source_mime: Option<Mime>,
+
+        /// Max size of content downloaded from a url, i.e. 500M or a number of bytes
+        #[arg(long, value_name = "SIZE", default_value_t = ByteSize(100 * MIB))]
+        max_size: ByteSize,
+
+        /// Print the imported file as json (id, name, size, md5Checksum, webViewLink and parents). Nothing else is printed to stdout
+        #[arg(long, conflicts_with = "print_only_id")]
+        json: bool,
+
+        /// Update the document with the same name and type in the parent folder instead of creating a new one, keeping its id and sharing
+        #[arg(long)]
+        overwrite: bool,
+
+        /// Update the document with this id instead of creating a new one
+        #[arg(long, value_name = "FILE_ID", conflicts_with = "overwrite")]
+        update: Option<String>,
     },
 
     /// Export google document to file
     Export {
-        /// File id
+        /// File id or path (e.g., "/path/to/document" or "file_id")
         file_id: String,
 
-        /// File path to export to. The file extension will determine the export format
+        /// File path to export to. The file extension will determine the export format. If the path has no extension the default format for the document type is used (docx, xlsx or pptx). Missing directories are created
         file_path: PathBuf,
 
         /// Overwrite existing files
@@ -398,18 +1035,77 @@ enum PermissionCommand {
         field_separator: String,
     },
 
+    /// Accept an ownership transfer offered to the current account
+    AcceptOwnership {
+        /// File id
+        file_id: String,
+    },
+
+    /// Cancel a pending ownership transfer of a file owned by the current account
+    CancelTransfer {
+        /// File id
+        file_id: String,
+    },
+
+    /// Copy permissions (except owner) from one file to another
+    Copy {
+        /// File id to copy permissions from
+        source_file_id: String,
+
+        /// File id to copy permissions to
+        dest_file_id: String,
+
+        /// Show which permissions would be applied without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
     /// Revoke permissions for a file. If no other options are specified, the 'anyone' permission will be revoked
     Revoke {
-        /// File id
+        /// File id or path (e.g., "/path/to/file" or "file_id")
         file_id: String,
 
         /// Revoke all permissions (except owner)
-        #[arg(long)]
+        #[arg(long, conflicts_with_all = ["id", "email", "domain"])]
         all: bool,
 
         /// Revoke specific permission
-        #[arg(long, value_name = "PERMISSION_ID")]
+        #[arg(long, value_name = "PERMISSION_ID", conflicts_with_all = ["email", "domain"])]
         id: Option<String>,
+
+        /// Revoke the permission of the user or group with this email address
+        #[arg(long, conflicts_with = "domain")]
+        email: Option<String>,
+
+        /// Revoke the permission of this domain
+        #[arg(long)]
+        domain: Option<String>,
+
+        /// Show which permissions would be revoked without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Report who has access to a folder and the files in it, as csv or json
+    Audit {
+        /// Folder id or path (e.g., "/Finance" or "folder_id")
+        folder_id: String,
+
+        /// Audit everything below the folder, not only the files directly in it
+        #[arg(long)]
+        recursive: bool,
+
+        /// Report format: csv or json
+        #[arg(long, default_value_t = permissions::audit::AuditFormat::default())]
+        format: permissions::audit::AuditFormat,
+
+        /// Only report access by users, groups and domains outside this domain, and anyone with the link
+        #[arg(long, value_name = "DOMAIN")]
+        only_external: Option<String>,
+
+        /// Max number of files to fetch permissions for at the same time
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
     },
 }
 
@@ -417,23 +1113,88 @@ enum PermissionCommand {
 async fn main() {
     let cli = Cli::parse();
 
-    match cli.command {
-        Command::About => {
+    let settings = load_settings();
+
+    if cli.read_only || settings.read_only {
+        read_only::enable();
+    }
+
+    let mut mime_types = settings.mime_types;
+    if let Command::Files {
+        command: FileCommand::Upload {
+            mime_from_extension,
+            ..
+        },
+    } = &cli.command
+    {
+        for overrides in mime_from_extension {
+            mime_types.extend(overrides.clone());
+        }
+    }
+    mime_overrides::set(mime_types);
+
+    if cli.remember {
+        account_picker::enable_remember();
+    }
+
+    if cli.refresh_token_stdin {
+        env_auth::read_refresh_token_from_stdin().unwrap_or_else(handle_error);
+    }
+
+    // The account scope is filled in from the account the hub is created for
+    let hub_config = HubConfig {
+        extra_scopes: required_scopes(&cli.command),
+        request_timeout: Duration::from_secs(cli.request_timeout),
+        ..HubConfig::default()
+    };
+
+    if is_cancellable(&cli.command) {
+        cancel::listen();
+    }
+
+    if let Some(operation) = mutating_operation(&cli.command) {
+        read_only::err_if_enabled(operation).unwrap_or_else(handle_error);
+    }
+
+    if cli.reauth {
+        hub_helper::reauthorize(&hub_config)
+            .await
+            .unwrap_or_else(handle_error);
+    }
+
+    match cli.deadline {
+        Some(deadline) => deadline::run(deadline, run_command(cli.command, hub_config)).await,
+        None => run_command(cli.command, hub_config).await,
+    }
+}
+
+async fn run_command(command: Command, hub_config: HubConfig) {
+    match command {
+        Command::About { json } => {
             // fmt
-            about::about()
+            about::about(json).unwrap_or_else(handle_error)
         }
 
         Command::Account { command } => {
             // fmt
             match command {
-                AccountCommand::Add => {
+                AccountCommand::Add {
+                    scope,
+                    client_secret,
+                } => {
                     // fmt
-                    account::add().await.unwrap_or_else(handle_error)
+                    account::add(account::add::Config {
+                        scope,
+                        client_secret,
+                        request_timeout: hub_config.request_timeout,
+                    })
+                    .await
+                    .unwrap_or_else(handle_error)
                 }
 
-                AccountCommand::List => {
+                AccountCommand::List { details } => {
                     // fmt
-                    account::list().unwrap_or_else(handle_error)
+                    account::list(account::list::Config { details }).unwrap_or_else(handle_error)
                 }
 
                 AccountCommand::Current => {
@@ -447,91 +1208,294 @@ async fn main() {
                         .unwrap_or_else(handle_error)
                 }
 
-                AccountCommand::Remove { account_name } => {
-                    // fmt
-                    account::remove(account::remove::Config { account_name })
-                        .unwrap_or_else(handle_error)
-                }
-
-                AccountCommand::Export { account_name } => {
+                AccountCommand::Remove {
+                    account_name,
+                    revoke,
+                    force,
+                } => {
                     // fmt
-                    account::export(account::export::Config { account_name })
-                        .unwrap_or_else(handle_error)
+                    account::remove(account::remove::Config {
+                        account_name,
+                        revoke,
+                        force,
+                    })
+                    .await
+                    .unwrap_or_else(handle_error)
                 }
 
-                AccountCommand::Import { file_path } => {
+                AccountCommand::Export {
+                    account_name,
+                    format,
+                    stdout,
+                    encrypt,
+                } => {
                     // fmt
-                    account::import(account::import::Config {
-                        archive_path: file_path,
+                    account::export(account::export::Config {
+                        account_name,
+                        format,
+                        stdout,
+                        encrypt,
                     })
                     .unwrap_or_else(handle_error)
                 }
+
+                AccountCommand::Import {
+                    file_path,
+                    from_env,
+                    from_stdin,
+                } => {
+                    let source = if let Some(var_name) = from_env {
+                        account::import::ImportSource::Env(var_name)
+                    } else if from_stdin {
+                        account::import::ImportSource::Stdin
+                    } else {
+                        account::import::ImportSource::Archive(file_path.unwrap_or_default())
+                    };
+
+                    account::import(account::import::Config { source })
+                        .unwrap_or_else(handle_error)
+                }
             }
         }
 
         Command::Drives { command } => {
-            // fmt
+            let hub = get_hub(&hub_config).await;
+
             match command {
                 DriveCommand::List {
                     skip_header,
                     field_separator,
-                } => drives::list(drives::list::Config {
+                    name_contains,
+                    sort,
+                    json,
+                } => drives::list(&hub, drives::list::Config {
                     skip_header,
                     field_separator,
+                    name_contains,
+                    sort,
+                    json,
                 })
                 .await
                 .unwrap_or_else(handle_error),
             }
         }
 
+        Command::Files {
+            command: FileCommand::QueryHelp,
+        } => {
+            // fmt
+            files::list::print_query_help()
+        }
+
         Command::Files { command } => {
+            let hub = get_hub(&hub_config).await;
+
             match command {
-                FileCommand::Info { file_id } => {
+                FileCommand::Info {
+                    file_id,
+                    check_access,
+                    capabilities,
+                    json,
+                    raw,
+                    fields,
+                    include_trashed,
+                    si,
+                    iec: _,
+                    time_style,
+                } => {
                     // fmt
-                    info(files::info::Config { 
+                    info(&hub, files::info::Config { 
                         file_id,
                         size_in_bytes: false, // Display size in human-readable format
+                        check_access,
+                        capabilities,
+                        json,
+                        raw_fields: raw.then(|| {
+                            fields.unwrap_or_else(|| files::info::ALL_FIELDS.to_string())
+                        }),
+                        include_trashed,
+                        size_units: size_units(si),
+                        time_style,
                     })
                         .await
                         .unwrap_or_else(handle_error)
                 }
 
+                FileCommand::Stat {
+                    file_id,
+                    format,
+                    field_separator,
+                } => {
+                    // fmt
+                    files::stat::stat(&hub, files::stat::Config {
+                        file_id,
+                        format,
+                        field_separator,
+                    })
+                    .await
+                    .unwrap_or_else(handle_error)
+                }
+
+                FileCommand::Meta {
+                    file_ids,
+                    ids_file,
+                    concurrency,
+                    skip_header,
+                    field_separator,
+                } => {
+                    // fmt
+                    files::meta::meta(&hub, files::meta::Config {
+                        file_ids,
+                        ids_file,
+                        concurrency,
+                        skip_header,
+                        field_separator,
+                    })
+                    .await
+                    .unwrap_or_else(handle_error)
+                }
+
                 FileCommand::List {
                     query,
+                    query_file,
                     max,
                     order_by,
+                    sort,
+                    reverse,
+                    newest,
+                    oldest,
                     parent,
                     drive,
+                    top_level,
+                    owner,
+                    space,
                     skip_header,
                     full_name,
+                    si,
+                    iec: _,
                     field_separator,
+                    time_style,
+                    output,
+                    table,
+                    no_color,
+                    find_duplicates,
+                    by_md5,
+                    json,
+                    empty_folders,
+                    resolve_shortcuts,
+                    include_permissions,
+                    page_token,
+                    show_token,
                 } => {
+                    let output = if table {
+                        OutputMode::Pretty
+                    } else {
+                        output.unwrap_or_else(|| OutputMode::detect(no_color))
+                    };
+                    let color =
+                        std::io::stdout().is_terminal() && common::output::color_enabled(no_color);
+
+                    if order_by.is_some() && sort.is_some() {
+                        eprintln!("Warning: --sort replaces the order of --order-by, which only decides which files are listed");
+                    }
+
+                    let (order_by, max) = match (newest, oldest) {
+                        (Some(n), _) => (ListSortOrder::Custom(String::from("modifiedTime desc")), n),
+                        (_, Some(n)) => (ListSortOrder::Custom(String::from("modifiedTime")), n),
+                        _ => (order_by.unwrap_or_default(), max),
+                    };
+
+                    // A leading slash is a path, which is resolved to the folder id first
+                    let parent = match parent {
+                        Some(parent) => {
+                            common::parent_check::resolve_parents(&hub, &[parent], false, true)
+                                .await
+                                .unwrap_or_else(|e| {
+                                    eprintln!("Error: {}", e);
+                                    std::process::exit(1);
+                                })
+                                .into_iter()
+                                .next()
+                        }
+                        None => None,
+                    };
+
+                    let duplicates_config = files::duplicates::Config {
+                        folder_id: parent.clone().unwrap_or_default(),
+                        by_md5,
+                        json,
+                        space,
+                    };
+
+                    let empty_folders_config = files::empty_folders::Config {
+                        folder_id: parent.clone().unwrap_or_default(),
+                        skip_header,
+                        field_separator: field_separator.clone(),
+                        space,
+                    };
+
                     let parent_query =
                         parent.map(|folder_id| ListQuery::FilesInFolder { folder_id });
 
-                    let drive_query = drive.map(|drive_id| ListQuery::FilesOnDrive { drive_id });
+                    let drive_query = drive.map(|drive_id| {
+                        if top_level {
+                            ListQuery::DriveRoot { drive_id }
+                        } else {
+                            ListQuery::FilesOnDrive { drive_id }
+                        }
+                    });
+
+                    let query = match query_file {
+                        Some(path) => {
+                            Some(files::list::read_query_file(&path).unwrap_or_else(|e| {
+                                eprintln!("Error: {}", e);
+                                std::process::exit(1);
+                            }))
+                        }
+                        None => query,
+                    };
 
-                    let q = parent_query.or(drive_query).unwrap_or(ListQuery::default());
+                    // A custom query replaces the one built from --parent, --drive and the space
+                    let query = match query {
+                        Some(query) => ListQuery::Custom(query),
+                        None => parent_query
+                            .or(drive_query)
+                            .unwrap_or_else(|| space.default_query()),
+                    };
 
-                    if let Some(query) = query {
-                        list(files::list::Config {
-                            query: ListQuery::Custom(query),
-                            order_by,
-                            max_files: max,
-                            skip_header,
-                            truncate_name: !full_name,
-                            field_separator,
-                        })
-                        .await
-                        .unwrap_or_else(handle_error)
+                    let query = match &owner {
+                        Some(email) => query.owned_by(email),
+                        None => query,
+                    };
+
+                    if find_duplicates {
+                        files::duplicates::find_duplicates(&hub, duplicates_config)
+                            .await
+                            .unwrap_or_else(handle_error)
+                    } else if empty_folders {
+                        files::empty_folders::list_empty_folders(&hub, empty_folders_config)
+                            .await
+                            .unwrap_or_else(handle_error)
                     } else {
-                        list(files::list::Config {
-                            query: q,
+                        list(&hub, files::list::Config {
+                            query,
                             order_by,
                             max_files: max,
                             skip_header,
                             truncate_name: !full_name,
                             field_separator,
+                            time_style,
+                            output,
+                            color,
+                            space,
+                            resolve_shortcuts,
+                            size_units: size_units(si),
+                            sort,
+                            reverse,
+                            include_permissions,
+                            json,
+                            page_token,
+                            show_token,
                         })
                         .await
                         .unwrap_or_else(handle_error)
@@ -546,6 +1510,16 @@ async fn main() {
                     recursive,
                     destination,
                     stdout,
+                    manifest,
+                    space,
+                    max_size,
+                    max_depth,
+                    depth,
+                    include,
+                    exclude,
+                    match_path,
+                    include_trashed,
+                    save_as,
                 } => {
                     // For debugging
                     println!("Downloading file: {}", file_id);
@@ -622,13 +1596,24 @@ async fn main() {
                         }
                     }
 
-                    download(files::download::Config {
+                    download(&hub, files::download::Config {
                         file_id,
                         path,
                         existing_file_action,
                         follow_shortcuts,
                         download_directories: recursive,
                         destination: dst,
+                        checksum_manifest: manifest,
+                        space,
+                        max_size: max_size.map(|size| size.in_bytes()),
+                        filter: PathFilter {
+                            max_depth: max_depth.or(depth.map(|depth| depth + 1)),
+                            include,
+                            exclude,
+                            match_path,
+                        },
+                        include_trashed,
+                        save_as,
                     })
                     .await
                     .unwrap_or_else(handle_error)
@@ -637,36 +1622,74 @@ async fn main() {
                 FileCommand::Upload {
                     file_path,
                     remote_path,
+                    parent,
+                    skip_parent_check,
+                    create_parents,
                     mime,
+                    mime_from_extension: _,
                     recursive,
+                    contents,
+                    name,
+                    strict,
+                    mtime_from_git,
                     chunk_size,
                     print_chunk_errors,
                     print_chunk_info,
                     print_only_id,
+                    error_log,
+                    truncate_error_log,
+                    max_retry_duration,
+                    force_resumable,
+                    force_simple,
+                    continue_on_error,
+                    error_manifest,
+                    retry_manifest,
+                    keep_temp,
+                    strict_path,
+                    json,
+                    if_newer,
+                    dedupe_check,
+                    dedupe_action,
                 } => {
                     // Convert MIME string to Mime type if provided
                     let mime_type = mime.and_then(|m| m.parse::<Mime>().ok());
                     
                     // Create config with common parameters
                     let config = files::upload::Config {
-                        file_path,
+                        file_path: file_path.unwrap_or_default(),
                         mime_type,
-                        parents: None, // Will be set by with_remote_path
-                        chunk_size: chunk_size.map(ChunkSize::from_mb).unwrap_or_default(),
+                        parents: parent, // The remote path is prepended by with_remote_path
+                        chunk_size: chunk_size.unwrap_or_default(),
                         print_chunk_errors,
                         print_chunk_info,
                         upload_directories: recursive,
                         print_only_id,
-                    };
+                        error_log,
+                        truncate_error_log,
+                        max_retry_duration: Duration::from_secs(max_retry_duration),
+                        upload_mode: UploadMode::from_flags(force_resumable, force_simple),
+                        continue_on_error,
+                        error_manifest,
+                        retry_manifest,
+                        skip_parent_check,
+                        create_parents,
+                        json,
+                        contents_only: false,
+                        root_name: name,
+                        strict,
+                        overwrite: false,
+                        mtime_from_git,
+                        if_newer,
+                        dedupe: dedupe_check.then_some(dedupe_action),
+                    }
+                    .with_contents_only(contents);
                     
+                    // Removed once the upload has finished, unless --keep-temp is given
+                    let mut temp_copy: Option<TempCopy> = None;
+
                     // If remote_path is provided, resolve it to a folder ID
                     // If the path doesn't exist, create the necessary directories
                     let config = if let Some(remote_path) = remote_path {
-                        let hub = hub_helper::get_hub().await.unwrap_or_else(|e| {
-                            eprintln!("Error getting hub: {}", e);
-                            std::process::exit(1);
-                        });
-                        
                         // Check if the remote path has a file extension (likely a filename)
                         let remote_path_clone = remote_path.clone();
                         let (dir_path, filename) = if remote_path_clone.contains('.') && !remote_path_clone.ends_with('/') {
@@ -683,7 +1706,7 @@ async fn main() {
                             (remote_path_clone, None)
                         };
                         
-                        match files::path_utils::resolve_or_create_path(&hub, &dir_path).await {
+                        match files::path_utils::resolve_directory(&hub, &dir_path, !strict_path).await {
                             Ok(file) => {
                                 if !drive_file::is_directory(&file) {
                                     eprintln!("Error: '{}' exists but is not a directory", remote_path);
@@ -692,22 +1715,19 @@ async fn main() {
                                 
                                 // If a filename was specified in the remote path, use it
                                 if let Some(new_filename) = filename {
-                                    println!("Uploading to: {} as {}", file.name.unwrap_or_default(), new_filename);
+                                    if !config.json {
+                                        println!("Uploading to: {} as {}", file.name.unwrap_or_default(), new_filename);
+                                    }
                                     
                                     // We'll use the new filename specified in the remote path
                                     
-                                    // Create a temporary file with the new name
-                                    let temp_dir = std::env::temp_dir();
-                                    let temp_file_path = temp_dir.join(&new_filename);
-                                    
-                                    // Copy the file to the temporary location with the new name
-                                    match std::fs::copy(&config.file_path, &temp_file_path) {
-                                        Ok(_) => {
-                                            files::upload::Config {
-                                                file_path: temp_file_path,
-                                                parents: Some(vec![file.id.unwrap_or_default()]),
-                                                ..config
-                                            }
+                                    // Copy the file to a temporary location with the new name
+                                    match TempCopy::create(&config.file_path, &new_filename, keep_temp) {
+                                        Ok(copy) => {
+                                            let file_path = copy.path().to_path_buf();
+                                            temp_copy = Some(copy);
+                                            files::upload::Config { file_path, ..config }
+                                                .with_remote_path(file.id)
                                         },
                                         Err(e) => {
                                             eprintln!("Error creating temporary file: {}", e);
@@ -715,11 +1735,10 @@ async fn main() {
                                         }
                                     }
                                 } else {
-                                    println!("Uploading to: {}", file.name.unwrap_or_default());
-                                    files::upload::Config {
-                                        parents: Some(vec![file.id.unwrap_or_default()]),
-                                        ..config
+                                    if !config.json {
+                                        println!("Uploading to: {}", file.name.unwrap_or_default());
                                     }
+                                    config.with_remote_path(file.id)
                                 }
                             },
                             Err(e) => {
@@ -731,9 +1750,9 @@ async fn main() {
                         config
                     };
 
-                    upload(config)
-                        .await
-                        .unwrap_or_else(handle_error)
+                    let result = upload(&hub, config).await;
+                    drop(temp_copy);
+                    result.unwrap_or_else(handle_error)
                 }
 
                 FileCommand::Update {
@@ -743,25 +1762,67 @@ async fn main() {
                     chunk_size,
                     print_chunk_errors,
                     print_chunk_info,
+                    error_log,
+                    truncate_error_log,
+                    max_retry_duration,
+                    force_resumable,
+                    force_simple,
+                    json,
+                    print_only_id,
+                    name,
                 } => {
                     // fmt
-                    update(files::update::Config {
+                    update(&hub, files::update::Config {
                         file_id,
                         file_path,
                         mime_type: mime,
                         chunk_size,
                         print_chunk_errors,
                         print_chunk_info,
+                        error_log,
+                        truncate_error_log,
+                        max_retry_duration: Duration::from_secs(max_retry_duration),
+                        upload_mode: UploadMode::from_flags(force_resumable, force_simple),
+                        json,
+                        print_only_id,
+                        name,
                     })
                     .await
                     .unwrap_or_else(handle_error)
                 }
 
-                FileCommand::Delete { file_id, recursive } => {
+                FileCommand::Delete {
+                    file_id,
+                    recursive,
+                    concurrency,
+                    space,
+                } => {
                     // fmt
-                    delete(files::delete::Config {
+                    delete(&hub, files::delete::Config {
                         file_id,
                         delete_directories: recursive,
+                        concurrency,
+                        space,
+                    })
+                    .await
+                    .unwrap_or_else(handle_error)
+                }
+
+                // Handled above, it doesn't need an account
+                FileCommand::QueryHelp => {}
+
+                FileCommand::Dedup {
+                    folder,
+                    by_md5,
+                    keep,
+                    apply,
+                } => {
+                    // fmt
+                    files::dedup::dedup(&hub, files::dedup::Config {
+                        folder,
+                        by_md5,
+                        keep,
+                        apply,
                     })
                     .await
                     .unwrap_or_else(handle_error)
@@ -771,10 +1832,42 @@ async fn main() {
                     name,
                     parent,
                     print_only_id,
+                    skip_parent_check,
+                    create_parents,
+                    parents_path,
+                    json,
+                } => {
+                    if parents_path {
+                        files::mkdir::mkdir_path(&hub, files::mkdir::PathConfig {
+                            path: name,
+                            print_only_id,
+                            json,
+                        })
+                        .await
+                        .unwrap_or_else(handle_error)
+                    } else {
+                        mkdir(&hub, files::mkdir::Config {
+                            id: None,
+                            name,
+                            parents: parent,
+                            print_only_id,
+                            skip_parent_check,
+                            create_parents,
+                        })
+                        .await
+                        .unwrap_or_else(handle_error)
+                    }
+                }
+
+                FileCommand::Shortcut {
+                    target_id,
+                    name,
+                    parent,
+                    print_only_id,
                 } => {
                     // fmt
-                    mkdir(files::mkdir::Config {
-                        id: None,
+                    files::shortcut::shortcut(&hub, files::shortcut::Config {
+                        target: target_id,
                         name,
                         parents: parent,
                         print_only_id,
@@ -783,43 +1876,108 @@ async fn main() {
                     .unwrap_or_else(handle_error)
                 }
 
-                FileCommand::Rename { file_id, name } => {
+                FileCommand::Rename {
+                    file_id,
+                    name,
+                    force,
+                } => {
                     // fmt
-                    rename(files::rename::Config { file_id, name })
+                    rename(&hub, files::rename::Config {
+                        file_id,
+                        name,
+                        force,
+                    })
                         .await
                         .unwrap_or_else(handle_error)
                 }
 
-                FileCommand::Move { file_id, folder_id } => {
+                FileCommand::Move {
+                    file_id,
+                    folder_id,
+                    verify,
+                } => {
                     // fmt
-                    mv(files::mv::Config {
+                    mv(&hub, files::mv::Config {
                         file_id,
                         to_folder_id: folder_id,
+                        verify,
                     })
                     .await
                     .unwrap_or_else(handle_error)
                 }
 
-                FileCommand::Copy { file_id, folder_id } => {
+                FileCommand::Copy {
+                    file_id,
+                    folder_id,
+                    allow_same_parent,
+                    no_metadata,
+                } => {
                     // fmt
-                    copy(files::copy::Config {
+                    copy(&hub, files::copy::Config {
                         file_id,
                         to_folder_id: folder_id,
+                        allow_same_parent,
+                        copy_metadata: !no_metadata,
                     })
                     .await
                     .unwrap_or_else(handle_error)
                 }
 
+                FileCommand::Transfer {
+                    source,
+                    to_account,
+                    dest,
+                    skip_existing,
+                } => {
+                    let dst_hub = hub_helper::get_hub_for_account(&to_account, &hub_config)
+                        .await
+                        .unwrap_or_else(|err| {
+                            eprintln!("Error: {}", err);
+                            std::process::exit(1);
+                        });
+
+                    let config = files::transfer::Config {
+                        source,
+                        destination: dest,
+                        skip_existing,
+                    };
+
+                    files::transfer::transfer(&hub, &dst_hub, config)
+                        .await
+                        .unwrap_or_else(handle_error)
+                }
+
                 FileCommand::Import {
-                    file_path,
+                    source,
+                    url,
                     parent,
+                    strict_path,
                     print_only_id,
+                    to,
+                    name,
+                    source_mime,
+                    max_size,
+                    json,
+                    overwrite,
+                    update,
                 } => {
-                    // fmt
-                    import(files::import::Config {
-                        file_path,
+                    let existing = match update {
+                        Some(file_id) => files::import::ExistingDoc::Update(file_id),
+                        None if overwrite => files::import::ExistingDoc::Overwrite,
+                        None => files::import::ExistingDoc::Ignore,
+                    };
+
+                    import(&hub, files::import::Config {
+                        source: url.or(source).expect("clap requires a source or --url"),
                         parents: parent,
                         print_only_id,
+                        doc_type: to,
+                        name,
+                        source_mime,
+                        max_size: max_size.in_bytes(),
+                        json,
+                        existing,
+                        create_parents: !strict_path,
                     })
                     .await
                     .unwrap_or_else(handle_error)
@@ -836,7 +1994,7 @@ async fn main() {
                         files::export::ExistingFileAction::Abort
                     };
 
-                    export(files::export::Config {
+                    export(&hub, files::export::Config {
                         file_id,
                         file_path,
                         existing_file_action,
@@ -848,6 +2006,8 @@ async fn main() {
         }
 
         Command::Permissions { command } => {
+            let hub = get_hub(&hub_config).await;
+
             match command {
                 PermissionCommand::Share {
                     file_id,
@@ -858,13 +2018,45 @@ async fn main() {
                     domain,
                 } => {
                     // fmt
-                    permissions::share(permissions::share::Config {
+                    permissions::share(&hub, permissions::share::Config {
                         file_id,
                         role,
                         type_,
                         discoverable,
                         email,
                         domain,
+                        expiration_time: None,
+                    })
+                    .await
+                    .unwrap_or_else(handle_error)
+                }
+
+                PermissionCommand::AcceptOwnership { file_id } => {
+                    // fmt
+                    let config = permissions::accept_ownership::Config { file_id };
+                    permissions::accept_ownership(&hub, config)
+                        .await
+                        .unwrap_or_else(handle_error)
+                }
+
+                PermissionCommand::CancelTransfer { file_id } => {
+                    // fmt
+                    let config = permissions::cancel_transfer::Config { file_id };
+                    permissions::cancel_transfer(&hub, config)
+                        .await
+                        .unwrap_or_else(handle_error)
+                }
+
+                PermissionCommand::Copy {
+                    source_file_id,
+                    dest_file_id,
+                    dry_run,
+                } => {
+                    // fmt
+                    permissions::copy(&hub, permissions::copy::Config {
+                        source_file_id,
+                        dest_file_id,
+                        dry_run,
                     })
                     .await
                     .unwrap_or_else(handle_error)
@@ -876,7 +2068,7 @@ async fn main() {
                     field_separator,
                 } => {
                     // fmt
-                    permissions::list(permissions::list::Config {
+                    permissions::list(&hub, permissions::list::Config {
                         file_id,
                         skip_header,
                         field_separator,
@@ -885,18 +2077,64 @@ async fn main() {
                     .unwrap_or_else(handle_error)
                 }
 
-                PermissionCommand::Revoke { file_id, all, id } => {
+                PermissionCommand::Revoke {
+                    file_id,
+                    all,
+                    id,
+                    email,
+                    domain,
+                    dry_run,
+                } => {
                     let action = if all {
                         permissions::revoke::RevokeAction::AllExceptOwner
                     } else if id.is_some() {
                         permissions::revoke::RevokeAction::Id(id.unwrap_or_default())
+                    } else if let Some(email) = email {
+                        permissions::revoke::RevokeAction::Email(email)
+                    } else if let Some(domain) = domain {
+                        permissions::revoke::RevokeAction::Domain(domain)
                     } else {
                         permissions::revoke::RevokeAction::Anyone
                     };
 
-                    permissions::revoke(permissions::revoke::Config { file_id, action })
-                        .await
-                        .unwrap_or_else(handle_error)
+                    permissions::revoke(
+                        &hub,
+                        permissions::revoke::Config {
+                            file_id,
+                            action,
+                            dry_run,
+                        },
+                    )
+                    .await
+                    .unwrap_or_else(|err| {
+                        if err.is_no_match() {
+                            eprintln!("Error: {}", err);
+                            std::process::exit(permissions::revoke::NO_MATCH_EXIT_CODE);
+                        }
+                        handle_error(err)
+                    })
+                }
+
+                PermissionCommand::Audit {
+                    folder_id,
+                    recursive,
+                    format,
+                    only_external,
+                    concurrency,
+                } => {
+                    // fmt
+                    permissions::audit(
+                        &hub,
+                        permissions::audit::Config {
+                            folder_id,
+                            recursive,
+                            format,
+                            only_external,
+                            concurrency,
+                        },
+                    )
+                    .await
+                    .unwrap_or_else(handle_error)
                 }
             }
         }
@@ -906,18 +2144,22 @@ async fn main() {
             remote_path,
             mime,
             recursive,
+            contents,
             overwrite,
+            deep_check,
+            keep_temp,
+            strict_path,
+            json,
+            dedupe_check,
+            dedupe_action,
         } => {
-            // Get hub for path resolution
-            let hub = hub_helper::get_hub().await.unwrap_or_else(|e| {
-                eprintln!("Error getting hub: {}", e);
-                std::process::exit(1);
-            });
+            let hub = get_hub(&hub_config).await;
 
             let rp = std::path::PathBuf::from(&remote_path);
 
-            // Determine if a filename was specified (remote_path does not end with '/')
-            let (dir_path, desired_name): (String, Option<String>) = if remote_path.ends_with('/') {
+            // Determine if a filename was specified (remote_path does not end with '/').
+            // A directory always goes into the remote path, it can't be renamed.
+            let (dir_path, desired_name): (String, Option<String>) = if remote_path.ends_with('/') || file_path.is_dir() {
                 (remote_path.clone(), None)
             } else if let Some(parent) = rp.parent() {
                 let parent_str = if parent.as_os_str().is_empty() { "/".to_string() } else { parent.to_string_lossy().to_string() };
@@ -928,10 +2170,12 @@ async fn main() {
                 ("/".to_string(), Some(remote_path.clone()))
             };
 
-            println!("Resolving remote directory: {} (name: {:?})", dir_path, desired_name);
+            if !json {
+                println!("Resolving remote directory: {} (name: {:?})", dir_path, desired_name);
+            }
 
-            // Resolve or create the destination directory
-            let remote_dir = files::path_utils::resolve_or_create_path(&hub, &dir_path)
+            // Resolve the destination directory, creating it unless --strict-path is given
+            let remote_dir = files::path_utils::resolve_directory(&hub, &dir_path, !strict_path)
                 .await
                 .unwrap_or_else(|e| {
                     eprintln!("Error resolving remote path: {}", e);
@@ -950,20 +2194,25 @@ async fn main() {
 
             // If a destination filename is provided and we're uploading a single file,
             // copy to a temp file with the desired name to control the uploaded name.
-            let adjusted_file_path = if desired_name.is_some() && !file_path.is_dir() {
+            // The copy is removed once the upload has finished, unless --keep-temp is given.
+            let temp_copy = if desired_name.is_some() && !file_path.is_dir() {
                 let name = desired_name.as_ref().unwrap();
-                let temp_file_path = std::env::temp_dir().join(name);
-                match std::fs::copy(&file_path, &temp_file_path) {
-                    Ok(_) => temp_file_path,
+                match TempCopy::create(&file_path, name, keep_temp) {
+                    Ok(copy) => Some(copy),
                     Err(e) => {
-                        eprintln!("Error creating temporary file '{}': {}", temp_file_path.display(), e);
+                        eprintln!("Error creating temporary copy of '{}': {}", file_path.display(), e);
                         std::process::exit(1);
                     }
                 }
             } else {
-                file_path.clone()
+                None
             };
 
+            let adjusted_file_path = temp_copy
+                .as_ref()
+                .map(|copy| copy.path().to_path_buf())
+                .unwrap_or_else(|| file_path.clone());
+
             let config = files::upload::Config {
                 file_path: adjusted_file_path,
                 mime_type: mime.and_then(|m| m.parse::<Mime>().ok()),
@@ -973,89 +2222,247 @@ async fn main() {
                 print_chunk_info: false,
                 upload_directories: recursive,
                 print_only_id: false,
+                error_log: None,
+                truncate_error_log: false,
+                max_retry_duration: Duration::from_secs(DEFAULT_MAX_RETRY_DURATION_SECS),
+                upload_mode: UploadMode::default(),
+                continue_on_error: false,
+                error_manifest: None,
+                retry_manifest: None,
+                // The remote directory was resolved and checked above
+                skip_parent_check: true,
+                create_parents: false,
+                json,
+                contents_only: false,
+                root_name: None,
+                strict: false,
+                // Set for --overwrite or once the overwrite prompt is confirmed
+                overwrite: false,
+                mtime_from_git: false,
+                if_newer: false,
+                dedupe: dedupe_check.then_some(dedupe_action),
+            }
+            .with_contents_only(contents);
+
+            if !json {
+                println!(
+                    "Upload config: file_path={}, parent_id={}, recursive={}",
+                    config.file_path.display(),
+                    folder_id,
+                    recursive
+                );
+            }
+
+            let result: Result<(), Box<dyn Error>> = async {
+                if !overwrite {
+                    upload_with_overwrite_check(&hub, config, deep_check).await?;
+                } else {
+                    // Files with the same name are updated, keeping their id, and directories merged
+                    upload(&hub, files::upload::Config { overwrite: true, ..config }).await?;
+                }
+
+                Ok(())
+            }
+            .await;
+
+            drop(temp_copy);
+            if let Err(err) = result {
+                handle_error(&*err)
+            }
+        }
+
+        Command::Watch {
+            local_dir,
+            remote_path,
+            propagate_deletes,
+            dry_run,
+            debounce,
+        } => {
+            // A dry run only logs, it doesn't need an account
+            let hub = if dry_run {
+                None
+            } else {
+                Some(get_hub(&hub_config).await)
             };
 
-            println!(
-                "Upload config: file_path={}, parent_id={}, recursive={}",
-                config.file_path.display(),
-                folder_id,
-                recursive
-            );
+            let config = files::watch::Config {
+                local_dir,
+                remote_path,
+                debounce: Duration::from_secs(debounce),
+                propagate_deletes,
+            };
 
-            if !overwrite {
-                upload_with_overwrite_check(&hub, config)
+            files::watch::watch(hub.as_ref(), config)
+                .await
+                .unwrap_or_else(handle_error)
+        }
+
+        #[cfg(feature = "self-update")]
+        Command::SelfUpdate { check, yes } => {
+            if check {
+                match self_update::check().await {
+                    Ok(true) => std::process::exit(self_update::UPDATE_AVAILABLE_EXIT_CODE),
+                    Ok(false) => {}
+                    Err(err) => handle_error(err),
+                }
+            } else {
+                self_update::update(self_update::Config { yes })
                     .await
                     .unwrap_or_else(handle_error)
-            } else {
-                // Check if file exists and update it, otherwise upload new
-                if !config.file_path.is_dir() {
-                    // For single files, check if exists and update
-                    let file_name = config.file_path.file_name()
-                        .and_then(|s| s.to_str())
-                        .unwrap_or("");
-                    
-                    let query = format!(
-                        "'{}' in parents and name = '{}' and trashed = false",
-                        folder_id, file_name
-                    );
-                    
-                    let list_config = files::list::ListFilesConfig {
-                        query: files::list::ListQuery::from_str(&query).unwrap_or_default(),
-                        order_by: Default::default(),
-                        max_files: 1,
-                    };
-                    
-                    match files::list::list_files(&hub, &list_config).await {
-                        Ok(existing_files) => {
-                            if !existing_files.is_empty() {
-                                // File exists, update it
-                                let existing_file_id = existing_files[0].id.as_ref().unwrap();
-                                println!("Updating existing file: {}", file_name);
-                                
-                                let update_config = files::update::Config {
-                                    file_id: existing_file_id.clone(),
-                                    file_path: Some(config.file_path.clone()),
-                                    mime_type: config.mime_type.clone(),
-                                    chunk_size: config.chunk_size.clone(),
-                                    print_chunk_errors: config.print_chunk_errors,
-                                    print_chunk_info: config.print_chunk_info,
-                                };
-                                
-                                files::update::update(update_config)
-                                    .await
-                                    .unwrap_or_else(handle_error);
-                            } else {
-                                // File doesn't exist, upload new
-                                upload(config)
-                                    .await
-                                    .unwrap_or_else(handle_error);
-                            }
-                        }
-                        Err(_) => {
-                            // Error checking for existing file, just upload
-                            upload(config)
-                                .await
-                                .unwrap_or_else(handle_error);
-                        }
-                    }
-                } else {
-                    // For directories, use regular upload (recursive directory handling)
-                    upload(config)
-                        .await
-                        .unwrap_or_else(handle_error);
-                }
             }
         }
 
-        Command::Version => {
+        Command::Version { json } => {
             // fmt
-            version::version()
+            version::version(json).unwrap_or_else(handle_error)
+        }
+    }
+}
+
+fn load_settings() -> app_config::Settings {
+    app_config::AppConfig::load_settings().unwrap_or_else(|err| {
+        eprintln!("Warning: {}", err);
+        app_config::Settings::default()
+    })
+}
+
+// Scopes needed on top of the default scopes, for files outside the drive space
+fn required_scopes(command: &Command) -> Vec<&'static str> {
+    let space = match command {
+        Command::Files { command } => match command {
+            FileCommand::List { space, .. }
+            | FileCommand::Download { space, .. }
+            | FileCommand::Delete { space, .. } => *space,
+
+            FileCommand::Upload {
+                parent: Some(parents),
+                ..
+            } if parents.iter().any(|parent| parent == files::list::APP_DATA_FOLDER) => {
+                Space::AppDataFolder
+            }
+
+            _ => Space::Drive,
+        },
+        _ => Space::Drive,
+    };
+
+    space.required_scope().into_iter().collect()
+}
+
+// Commands that stop after the current file on Ctrl-C instead of being killed
+fn is_cancellable(command: &Command) -> bool {
+    match command {
+        Command::Files { command } => matches!(
+            command,
+            FileCommand::Download { .. }
+                | FileCommand::Upload { .. }
+                | FileCommand::Delete { .. }
+                | FileCommand::Transfer { .. }
+        ),
+        Command::Push { .. } | Command::Watch { .. } => true,
+        _ => false,
+    }
+}
+
+// Returns a description of the operation if the command modifies the drive
+fn mutating_operation(command: &Command) -> Option<&'static str> {
+    match command {
+        Command::About { .. }
+        | Command::Account { .. }
+        | Command::Drives { .. }
+        | Command::Version { .. } => None,
+
+        // Replaces the local executable, nothing on the drive
+        #[cfg(feature = "self-update")]
+        Command::SelfUpdate { .. } => None,
+
+        Command::Files { command } => match command {
+            FileCommand::Info { .. }
+            | FileCommand::QueryHelp
+            | FileCommand::Stat { .. }
+            | FileCommand::Meta { .. }
+            | FileCommand::List { .. }
+            | FileCommand::Download { .. }
+            | FileCommand::Export { .. } => None,
+            FileCommand::Upload { .. } => Some("upload files"),
+            FileCommand::Update { .. } => Some("update files"),
+            FileCommand::Delete { .. } => Some("delete files"),
+            FileCommand::Dedup { apply: false, .. } => None,
+            FileCommand::Dedup { .. } => Some("trash duplicate files"),
+            FileCommand::Mkdir { .. } => Some("create directories"),
+            FileCommand::Shortcut { .. } => Some("create shortcuts"),
+            FileCommand::Rename { .. } => Some("rename files"),
+            FileCommand::Move { .. } => Some("move files"),
+            FileCommand::Copy { .. } => Some("copy files"),
+            FileCommand::Transfer { .. } => Some("transfer files"),
+            FileCommand::Import { .. } => Some("import files"),
+        },
+
+        Command::Permissions { command } => match command {
+            PermissionCommand::List { .. } | PermissionCommand::Audit { .. } => None,
+            PermissionCommand::Share { .. } => Some("share files"),
+            PermissionCommand::AcceptOwnership { .. } => Some("accept ownership"),
+            PermissionCommand::CancelTransfer { .. } => Some("cancel ownership transfers"),
+            PermissionCommand::Copy { dry_run: true, .. } => None,
+            PermissionCommand::Copy { .. } => Some("copy permissions"),
+            PermissionCommand::Revoke { dry_run: true, .. } => None,
+            PermissionCommand::Revoke { .. } => Some("revoke permissions"),
+        },
+
+        Command::Push { .. } => Some("upload files"),
+        Command::Watch { dry_run: true, .. } => None,
+        Command::Watch { .. } => Some("upload files"),
+    }
+}
+
+// Built once per command and passed to every operation the command runs
+async fn get_hub(config: &HubConfig) -> Hub {
+    match hub_helper::get_hub(config).await {
+        Ok(hub) => hub,
+        Err(hub_helper::Error::InvalidCredentials(account_name)) => {
+            eprintln!(
+                "Error: {}",
+                hub_helper::invalid_credentials_message(Some(&account_name))
+            );
+            std::process::exit(hub_helper::AUTH_EXIT_CODE);
+        }
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            std::process::exit(1);
         }
     }
 }
 
+// --iec is the default, it only exists to be explicit
+fn size_units(si: bool) -> SizeUnits {
+    if si {
+        SizeUnits::Si
+    } else {
+        SizeUnits::Iec
+    }
+}
+
 fn handle_error(err: impl Error) {
-    eprintln!("Error: {}", err);
+    let message = err.to_string();
+    eprintln!("Error: {}", message);
+
+    if drive_error::is_insufficient_scope_message(&message) {
+        eprintln!("{}", hub_helper::reauth_hint());
+    }
+
+    if drive_error::is_invalid_grant_message(&message) {
+        eprintln!("{}", hub_helper::invalid_credentials_hint());
+        std::process::exit(hub_helper::AUTH_EXIT_CODE);
+    }
+
+    if deadline::is_expired() {
+        std::process::exit(deadline::EXIT_CODE);
+    }
+
+    if cancel::is_cancelled() {
+        std::process::exit(cancel::EXIT_CODE);
+    }
+
     std::process::exit(1);
 }
 
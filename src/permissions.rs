@@ -1,7 +1,15 @@
+pub mod accept_ownership;
+pub mod audit;
+pub mod cancel_transfer;
+pub mod copy;
 pub mod list;
 pub mod revoke;
 pub mod share;
 
+pub use accept_ownership::accept_ownership;
+pub use audit::audit;
+pub use cancel_transfer::cancel_transfer;
+pub use copy::copy;
 pub use list::list;
 pub use revoke::revoke;
 pub use share::share;
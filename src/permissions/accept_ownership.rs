@@ -0,0 +1,160 @@
+use crate::common::delegate::UploadDelegate;
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::pending_owner;
+use crate::common::read_only;
+use crate::files;
+use crate::hub::Hub;
+use crate::permissions;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub file_id: String,
+}
+
+pub async fn accept_ownership(hub: &Hub, config: Config) -> Result<(), Error> {
+    read_only::err_if_enabled("accept ownership").map_err(Error::ReadOnly)?;
+
+    let delegate_config = UploadDelegateConfig::default();
+
+    let file = files::info::get_file(hub, &config.file_id)
+        .await
+        .map_err(Error::GetFile)?;
+    let file_name = file.name.unwrap_or_default();
+
+    let permission_id = current_user_permission_id(hub).await?;
+
+    let permissions =
+        permissions::list::list_permissions(hub, delegate_config.clone(), &config.file_id)
+            .await
+            .map_err(Error::ListPermissions)?;
+
+    let own_permission = permissions
+        .iter()
+        .find(|p| p.id.as_deref() == Some(permission_id.as_str()))
+        .ok_or_else(|| Error::NotPendingOwner(file_name.clone()))?;
+
+    err_if_owner(own_permission, &file_name)?;
+
+    let pending_owner_ids = pending_owner::pending_owner_ids(hub, &config.file_id)
+        .await
+        .map_err(Error::PendingOwner)?;
+
+    if !pending_owner_ids.contains(&permission_id) {
+        return Err(Error::NotPendingOwner(file_name));
+    }
+
+    println!("Accepting ownership of '{}'", file_name);
+
+    update_role_to_owner(hub, delegate_config, &config.file_id, &permission_id)
+        .await
+        .map_err(Error::UpdatePermission)?;
+
+    println!("You are now the owner of '{}'", file_name);
+
+    Ok(())
+}
+
+pub async fn current_user_permission_id(hub: &Hub) -> Result<String, Error> {
+    let (_, about) = hub
+        .about()
+        .get()
+        .param("fields", "user(permissionId)")
+        .add_scope(google_drive3::api::Scope::Full)
+        .doit()
+        .await
+        .map_err(Error::GetUser)?;
+
+    about
+        .user
+        .and_then(|user| user.permission_id)
+        .ok_or(Error::MissingPermissionId)
+}
+
+pub async fn update_role_to_owner(
+    hub: &Hub,
+    delegate_config: UploadDelegateConfig,
+    file_id: &str,
+    permission_id: &str,
+) -> Result<google_drive3::api::Permission, google_drive3::Error> {
+    let mut delegate = UploadDelegate::new(delegate_config);
+
+    let permission = google_drive3::api::Permission {
+        role: Some(String::from("owner")),
+        ..google_drive3::api::Permission::default()
+    };
+
+    let (_, permission) = hub
+        .permissions()
+        .update(permission, file_id, permission_id)
+        .param("fields", "id,role,type,emailAddress")
+        .transfer_ownership(true)
+        .add_scope(google_drive3::api::Scope::Full)
+        .delegate(&mut delegate)
+        .supports_all_drives(true)
+        .doit()
+        .await?;
+
+    Ok(permission)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    ReadOnly(read_only::Error),
+    GetFile(google_drive3::Error),
+    GetUser(google_drive3::Error),
+    MissingPermissionId,
+    ListPermissions(google_drive3::Error),
+    PendingOwner(pending_owner::Error),
+    NotPendingOwner(String),
+    TransferCompleted(String),
+    UpdatePermission(google_drive3::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::ReadOnly(err) => write!(f, "{}", err),
+            Error::GetFile(err) => {
+                write!(f, "Failed to get file: {}", err)
+            }
+            Error::GetUser(err) => {
+                write!(f, "Failed to get current user: {}", err)
+            }
+            Error::MissingPermissionId => {
+                write!(f, "Drive did not return a permission id for the current user")
+            }
+            Error::ListPermissions(err) => {
+                write!(f, "Failed to list permissions: {}", err)
+            }
+            Error::PendingOwner(err) => {
+                write!(f, "Failed to get pending owners: {}", err)
+            }
+            Error::NotPendingOwner(name) => {
+                write!(
+                    f,
+                    "The current account has not been offered ownership of '{}'. Ask the owner to share it with --role owner",
+                    name
+                )
+            }
+            Error::TransferCompleted(name) => {
+                write!(f, "The current account already owns '{}'", name)
+            }
+            Error::UpdatePermission(err) => {
+                write!(f, "Failed to accept ownership: {}", err)
+            }
+        }
+    }
+}
+
+fn err_if_owner(permission: &google_drive3::api::Permission, file_name: &str) -> Result<(), Error> {
+    if permission.role.as_deref() == Some("owner") {
+        Err(Error::TransferCompleted(file_name.to_string()))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,392 @@
+use crate::common::delegate::UploadDelegate;
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::drive_error;
+use crate::common::drive_file;
+use crate::common::path_filter::PathFilter;
+use crate::common::permission;
+use crate::common::remote_walk;
+use crate::common::remote_walk::DriveLister;
+use crate::common::remote_walk::WalkConfig;
+use crate::common::remote_walk::WalkEntry;
+use crate::files;
+use crate::files::list::Space;
+use crate::files::path_utils;
+use crate::hub::Hub;
+use futures::stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use google_drive3::api::File;
+use google_drive3::api::Permission;
+use serde::Serialize;
+use std::error;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::io;
+use std::io::Write;
+use std::str::FromStr;
+
+const PERMISSION_FIELDS: &str = "permissions(id,role,type,domain,emailAddress,allowFileDiscovery,expirationTime,permissionDetails(inherited)),nextPageToken";
+
+const CSV_HEADER: [&str; 8] = [
+    "path",
+    "file_id",
+    "principal",
+    "role",
+    "type",
+    "discoverable",
+    "expiration",
+    "inherited",
+];
+
+pub struct Config {
+    // Folder id or a path starting with '/'
+    pub folder_id: String,
+    // Audit everything below the folder instead of only the files directly in it
+    pub recursive: bool,
+    pub format: AuditFormat,
+    // Only report access by principals outside this domain
+    pub only_external: Option<String>,
+    pub concurrency: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AuditFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+impl FromStr for AuditFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(AuditFormat::Csv),
+            "json" => Ok(AuditFormat::Json),
+            _ => Err(format!(
+                "'{}' is not a valid report format, valid formats are: csv, json",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for AuditFormat {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            AuditFormat::Csv => write!(f, "csv"),
+            AuditFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+// One permission of one file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditRow {
+    pub path: String,
+    pub file_id: String,
+    // Email address of a user or group, the domain, or anyone
+    pub principal: String,
+    pub role: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub discoverable: Option<bool>,
+    // RFC 3339
+    pub expiration: Option<String>,
+    // Only known for items on shared drives
+    pub inherited: Option<bool>,
+}
+
+impl AuditRow {
+    pub fn csv_values(&self) -> [String; 8] {
+        [
+            self.path.clone(),
+            self.file_id.clone(),
+            self.principal.clone(),
+            self.role.clone(),
+            self.type_.clone(),
+            format_optional_bool(self.discoverable),
+            self.expiration.clone().unwrap_or_default(),
+            format_optional_bool(self.inherited),
+        ]
+    }
+}
+
+// Rows are written as each file is audited, so large trees aren't kept in memory
+pub async fn audit(hub: &Hub, config: Config) -> Result<(), Error> {
+    let folder_id = path_utils::resolve_file_id(hub, &config.folder_id)
+        .await
+        .map_err(Error::ResolvePath)?;
+
+    let folder = files::info::get_file(hub, &folder_id)
+        .await
+        .map_err(Error::GetFile)?;
+
+    let folder_name = folder.name.clone().unwrap_or_default();
+    let is_directory = drive_file::is_directory(&folder);
+
+    let lister = DriveLister::new(hub, Space::Drive);
+    let walk_config = WalkConfig {
+        filter: PathFilter {
+            max_depth: if config.recursive { None } else { Some(1) },
+            ..PathFilter::default()
+        },
+        ..WalkConfig::default()
+    };
+
+    let root = WalkEntry {
+        path: String::new(),
+        depth: 0,
+        file: folder,
+    };
+
+    let children = if is_directory {
+        remote_walk::walk(&lister, &folder_id, &walk_config).left_stream()
+    } else {
+        stream::empty().right_stream()
+    };
+
+    let mut audited = stream::iter([Ok(root)])
+        .chain(children)
+        .map_err(Error::Walk)
+        .map_ok(|entry| async move {
+            let result =
+                list_all_permissions(hub, entry.file.id.as_deref().unwrap_or_default()).await;
+            Ok::<_, Error>((entry, result))
+        })
+        .try_buffered(config.concurrency.max(1));
+
+    let mut report = Report::start(io::stdout(), config.format).map_err(Error::WriteReport)?;
+    let mut failed = 0;
+
+    while let Some((entry, result)) = audited.try_next().await? {
+        let path = report_path(&folder_name, &entry.path);
+
+        let permissions = match result {
+            Ok(permissions) => permissions,
+            Err(err) => {
+                eprintln!(
+                    "Failed to list permissions of '{}': {}",
+                    path,
+                    drive_error::describe(&err)
+                );
+                failed += 1;
+                continue;
+            }
+        };
+
+        for row in audit_rows(&path, &entry.file, permissions) {
+            if is_reported(&row, config.only_external.as_deref()) {
+                report.write_row(&row).map_err(Error::WriteReport)?;
+            }
+        }
+    }
+
+    report.finish().map_err(Error::WriteReport)?;
+
+    if failed > 0 {
+        return Err(Error::Incomplete(failed));
+    }
+
+    Ok(())
+}
+
+// Permissions of a file with the details needed to tell inherited access from direct access.
+// Rate limited requests are retried by the delegate.
+pub async fn list_all_permissions(
+    hub: &Hub,
+    file_id: &str,
+) -> Result<Vec<Permission>, google_drive3::Error> {
+    let mut permissions: Vec<Permission> = vec![];
+    let mut next_page_token: Option<String> = None;
+
+    loop {
+        let mut delegate = UploadDelegate::new(UploadDelegateConfig::default());
+        let mut req = hub.permissions().list(file_id);
+
+        if let Some(token) = &next_page_token {
+            req = req.page_token(token);
+        }
+
+        let (_, permission_list) = req
+            .param("fields", PERMISSION_FIELDS)
+            .add_scope(google_drive3::api::Scope::Full)
+            .delegate(&mut delegate)
+            .supports_all_drives(true)
+            .doit()
+            .await?;
+
+        permissions.extend(permission_list.permissions.unwrap_or_default());
+        next_page_token = permission_list.next_page_token;
+
+        if next_page_token.is_none() {
+            return Ok(permissions);
+        }
+    }
+}
+
+// The audited folder is reported under its name, the files below it as name/relative/path
+pub fn report_path(folder_name: &str, relative_path: &str) -> String {
+    if relative_path.is_empty() {
+        folder_name.to_string()
+    } else {
+        format!("{}/{}", folder_name, relative_path)
+    }
+}
+
+pub fn audit_rows(path: &str, file: &File, permissions: Vec<Permission>) -> Vec<AuditRow> {
+    permissions
+        .into_iter()
+        .map(|permission| AuditRow {
+            path: path.to_string(),
+            file_id: file.id.clone().unwrap_or_default(),
+            principal: principal(&permission),
+            role: permission.role.clone().unwrap_or_default(),
+            type_: permission.type_.clone().unwrap_or_default(),
+            discoverable: permission.allow_file_discovery,
+            expiration: permission.expiration_time.map(|time| time.to_rfc3339()),
+            inherited: is_inherited(&permission),
+        })
+        .collect()
+}
+
+fn principal(permission: &Permission) -> String {
+    let anyone = permission::Type::Anyone.to_string();
+    if permission.type_.as_ref() == Some(&anyone) {
+        return anyone;
+    }
+
+    permission
+        .email_address
+        .clone()
+        .or_else(|| permission.domain.clone())
+        .unwrap_or_default()
+}
+
+// Drive only has permission details for items on shared drives
+fn is_inherited(permission: &Permission) -> Option<bool> {
+    let details = permission.permission_details.as_ref()?;
+    let inherited: Vec<bool> = details.iter().filter_map(|d| d.inherited).collect();
+
+    if inherited.is_empty() {
+        None
+    } else {
+        Some(inherited.iter().all(|inherited| *inherited))
+    }
+}
+
+// Without a domain every row is reported. Anyone with the link is always external.
+pub fn is_reported(row: &AuditRow, only_external: Option<&str>) -> bool {
+    let domain = match only_external {
+        Some(domain) => domain,
+        None => return true,
+    };
+
+    let principal_domain = match row.principal.rsplit_once('@') {
+        Some((_, email_domain)) => email_domain,
+        None if row.type_ == permission::Type::Domain.to_string() => &row.principal,
+        None => return true,
+    };
+
+    !principal_domain.eq_ignore_ascii_case(domain)
+}
+
+fn format_optional_bool(value: Option<bool>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+// Quotes fields with separators, quotes or line breaks, doubling the quotes
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn csv_line(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|value| csv_field(value))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+// Writes a csv file or a json array one row at a time
+pub struct Report<W: Write> {
+    writer: W,
+    format: AuditFormat,
+    rows: usize,
+}
+
+impl<W: Write> Report<W> {
+    pub fn start(mut writer: W, format: AuditFormat) -> Result<Report<W>, io::Error> {
+        match format {
+            AuditFormat::Csv => {
+                let header = CSV_HEADER.map(String::from);
+                writeln!(writer, "{}", csv_line(&header))?
+            }
+            AuditFormat::Json => write!(writer, "[")?,
+        }
+
+        Ok(Report {
+            writer,
+            format,
+            rows: 0,
+        })
+    }
+
+    pub fn write_row(&mut self, row: &AuditRow) -> Result<(), io::Error> {
+        match self.format {
+            AuditFormat::Csv => writeln!(self.writer, "{}", csv_line(&row.csv_values()))?,
+            AuditFormat::Json => {
+                let separator = if self.rows == 0 { "\n  " } else { ",\n  " };
+                let json = serde_json::to_string(row)?;
+                write!(self.writer, "{}{}", separator, json)?;
+            }
+        }
+
+        self.rows += 1;
+        self.writer.flush()
+    }
+
+    pub fn finish(mut self) -> Result<W, io::Error> {
+        if self.format == AuditFormat::Json {
+            let end = if self.rows == 0 { "]" } else { "\n]" };
+            writeln!(self.writer, "{}", end)?;
+        }
+
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    ResolvePath(path_utils::PathResolutionError),
+    GetFile(google_drive3::Error),
+    Walk(remote_walk::Error),
+    WriteReport(io::Error),
+    Incomplete(usize),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Error::ResolvePath(err) => write!(f, "{}", err),
+            Error::GetFile(err) => {
+                write!(f, "Failed to get file: {}", drive_error::describe(err))
+            }
+            Error::Walk(err) => write!(f, "{}", err),
+            Error::WriteReport(err) => write!(f, "Failed to write report: {}", err),
+            Error::Incomplete(count) => write!(
+                f,
+                "The report is incomplete, the permissions of {} files couldn't be listed",
+                count
+            ),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::common::pending_owner;
+use crate::common::read_only;
+use crate::files;
+use crate::hub::Hub;
+use google_drive3::hyper::http;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub file_id: String,
+}
+
+pub async fn cancel_transfer(hub: &Hub, config: Config) -> Result<(), Error> {
+    read_only::err_if_enabled("cancel ownership transfers").map_err(Error::ReadOnly)?;
+
+    let file = files::info::get_file(hub, &config.file_id)
+        .await
+        .map_err(Error::GetFile)?;
+    let file_name = file.name.unwrap_or_default();
+
+    let pending_owner_ids = pending_owner::pending_owner_ids(hub, &config.file_id)
+        .await
+        .map_err(Error::PendingOwner)?;
+
+    if pending_owner_ids.is_empty() {
+        return Err(Error::NoPendingTransfer(file_name));
+    }
+
+    for permission_id in pending_owner_ids {
+        println!(
+            "Cancelling ownership transfer of '{}' to permission id: {}",
+            file_name, permission_id
+        );
+
+        pending_owner::set_pending_owner(hub, &config.file_id, &permission_id, false)
+            .await
+            .map_err(|err| cancel_error(err, &file_name))?;
+    }
+
+    Ok(())
+}
+
+fn cancel_error(err: pending_owner::Error, file_name: &str) -> Error {
+    match &err {
+        pending_owner::Error::Api(status, _) if *status == http::StatusCode::FORBIDDEN => {
+            Error::NotOwner(file_name.to_string())
+        }
+        pending_owner::Error::Api(status, _) if *status == http::StatusCode::NOT_FOUND => {
+            Error::NoPendingTransfer(file_name.to_string())
+        }
+        _ => Error::CancelTransfer(err),
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    ReadOnly(read_only::Error),
+    GetFile(google_drive3::Error),
+    PendingOwner(pending_owner::Error),
+    NoPendingTransfer(String),
+    NotOwner(String),
+    CancelTransfer(pending_owner::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::ReadOnly(err) => write!(f, "{}", err),
+            Error::GetFile(err) => {
+                write!(f, "Failed to get file: {}", err)
+            }
+            Error::PendingOwner(err) => {
+                write!(f, "Failed to get pending owners: {}", err)
+            }
+            Error::NoPendingTransfer(name) => {
+                write!(
+                    f,
+                    "'{}' has no pending ownership transfer, it may already have been accepted",
+                    name
+                )
+            }
+            Error::NotOwner(name) => {
+                write!(
+                    f,
+                    "Only the current owner of '{}' can cancel an ownership transfer",
+                    name
+                )
+            }
+            Error::CancelTransfer(err) => {
+                write!(f, "Failed to cancel ownership transfer: {}", err)
+            }
+        }
+    }
+}
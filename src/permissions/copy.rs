@@ -0,0 +1,183 @@
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::permission;
+use crate::common::read_only;
+use crate::common::table;
+use crate::common::table::Table;
+use crate::files;
+use crate::permissions;
+use crate::permissions::share;
+use crate::hub::Hub;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::io;
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub source_file_id: String,
+    pub dest_file_id: String,
+    pub dry_run: bool,
+}
+
+pub async fn copy(hub: &Hub, config: Config) -> Result<(), Error> {
+    if !config.dry_run {
+        read_only::err_if_enabled("copy permissions").map_err(Error::ReadOnly)?;
+    }
+
+    let delegate_config = UploadDelegateConfig::default();
+
+    let source_file = files::info::get_file(hub, &config.source_file_id)
+        .await
+        .map_err(Error::GetFile)?;
+
+    let dest_file = files::info::get_file(hub, &config.dest_file_id)
+        .await
+        .map_err(Error::GetFile)?;
+
+    let source_permissions = permissions::list::list_permissions(
+        hub,
+        delegate_config.clone(),
+        &config.source_file_id,
+    )
+    .await
+    .map_err(Error::ListPermissions)?;
+
+    let dest_permissions =
+        permissions::list::list_permissions(hub, delegate_config.clone(), &config.dest_file_id)
+            .await
+            .map_err(Error::ListPermissions)?;
+
+    println!(
+        "Copying permissions from '{}' to '{}'",
+        source_file.name.unwrap_or_default(),
+        dest_file.name.unwrap_or_default()
+    );
+
+    let mut values: Vec<[String; 4]> = vec![];
+    let mut failed_count = 0;
+
+    for permission in source_permissions {
+        if permission.role.as_deref() == Some("owner") {
+            continue;
+        }
+
+        let result = if dest_permissions.iter().any(|p| is_equivalent(p, &permission)) {
+            String::from("Skipped, already exists")
+        } else {
+            match to_share_config(&config.dest_file_id, &permission) {
+                Err(err) => {
+                    failed_count += 1;
+                    format!("Failed: {}", err)
+                }
+
+                Ok(_) if config.dry_run => String::from("Would apply"),
+
+                Ok(share_config) => {
+                    let created =
+                        share::create_permission(hub, delegate_config.clone(), &share_config)
+                            .await;
+
+                    match created {
+                        Ok(_) => String::from("Applied"),
+                        Err(err) => {
+                            failed_count += 1;
+                            format!("Failed: {}", err)
+                        }
+                    }
+                }
+            }
+        };
+
+        values.push([
+            permission.type_.clone().unwrap_or_default(),
+            permission.role.clone().unwrap_or_default(),
+            grantee(&permission),
+            result,
+        ]);
+    }
+
+    let table = Table {
+        header: ["Type", "Role", "Grantee", "Result"],
+        values,
+    };
+
+    let _ = table::write(io::stdout(), table, &table::DisplayConfig::default());
+
+    if failed_count > 0 {
+        Err(Error::FailedPermissions(failed_count))
+    } else {
+        Ok(())
+    }
+}
+
+fn to_share_config(
+    file_id: &str,
+    permission: &google_drive3::api::Permission,
+) -> Result<share::Config, String> {
+    let role = permission
+        .role
+        .clone()
+        .unwrap_or_default()
+        .parse::<permission::Role>()?;
+
+    let type_ = permission
+        .type_
+        .clone()
+        .unwrap_or_default()
+        .parse::<permission::Type>()?;
+
+    Ok(share::Config {
+        file_id: file_id.to_string(),
+        role,
+        type_,
+        discoverable: permission.allow_file_discovery.unwrap_or_default(),
+        email: permission.email_address.clone(),
+        domain: permission.domain.clone(),
+        expiration_time: permission.expiration_time,
+    })
+}
+
+fn is_equivalent(a: &google_drive3::api::Permission, b: &google_drive3::api::Permission) -> bool {
+    let same_email = match (&a.email_address, &b.email_address) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        (None, None) => true,
+        _ => false,
+    };
+
+    a.type_ == b.type_ && a.role == b.role && same_email && a.domain == b.domain
+}
+
+fn grantee(permission: &google_drive3::api::Permission) -> String {
+    permission
+        .email_address
+        .clone()
+        .or_else(|| permission.domain.clone())
+        .unwrap_or_default()
+}
+
+#[derive(Debug)]
+pub enum Error {
+    ReadOnly(read_only::Error),
+    GetFile(google_drive3::Error),
+    ListPermissions(google_drive3::Error),
+    FailedPermissions(usize),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::ReadOnly(err) => write!(f, "{}", err),
+            Error::GetFile(err) => {
+                write!(f, "Failed to get file: {}", err)
+            }
+            Error::ListPermissions(err) => {
+                write!(f, "Failed to list permissions: {}", err)
+            }
+            Error::FailedPermissions(count) => {
+                write!(f, "Failed to copy {} permissions", count)
+            }
+        }
+    }
+}
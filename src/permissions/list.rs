@@ -1,6 +1,7 @@
 use crate::common::delegate::UploadDelegate;
 use crate::common::delegate::UploadDelegateConfig;
-use crate::common::hub_helper;
+use crate::common::pending_owner;
+use crate::common::pending_owner::PendingPermission;
 use crate::common::table;
 use crate::common::table::Table;
 use crate::files;
@@ -17,15 +18,13 @@ pub struct Config {
     pub field_separator: String,
 }
 
-pub async fn list(config: Config) -> Result<(), Error> {
-    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
-    let delegate_config = UploadDelegateConfig::default();
-
-    files::info::get_file(&hub, &config.file_id)
+pub async fn list(hub: &Hub, config: Config) -> Result<(), Error> {
+    files::info::get_file(hub, &config.file_id)
         .await
         .map_err(Error::GetFile)?;
 
-    let permissions = list_permissions(&hub, delegate_config, &config.file_id)
+    // Lists the permissions with pendingOwner, which the generated api leaves out
+    let permissions = pending_owner::list_permissions(hub, &config.file_id)
         .await
         .map_err(Error::ListPermissions)?;
 
@@ -34,10 +33,14 @@ pub async fn list(config: Config) -> Result<(), Error> {
     Ok(())
 }
 
-fn print_permissions_table(config: &Config, permissions: Vec<google_drive3::api::Permission>) {
-    let mut values: Vec<[String; 6]> = vec![];
+fn print_permissions_table(config: &Config, permissions: Vec<PendingPermission>) {
+    let mut values: Vec<[String; 7]> = vec![];
 
-    for permission in permissions {
+    for PendingPermission {
+        permission,
+        pending_owner,
+    } in permissions
+    {
         values.push([
             permission.id.unwrap_or_default(),
             permission.type_.unwrap_or_default(),
@@ -45,11 +48,20 @@ fn print_permissions_table(config: &Config, permissions: Vec<google_drive3::api:
             permission.email_address.unwrap_or_default(),
             permission.domain.unwrap_or_default(),
             files::info::format_bool(permission.allow_file_discovery.unwrap_or_default()),
+            files::info::format_bool(pending_owner),
         ])
     }
 
     let table = Table {
-        header: ["Id", "Type", "Role", "Email", "Domain", "Discoverable"],
+        header: [
+            "Id",
+            "Type",
+            "Role",
+            "Email",
+            "Domain",
+            "Discoverable",
+            "Pending owner",
+        ],
         values,
     };
 
@@ -75,7 +87,7 @@ pub async fn list_permissions(
         .list(file_id)
         .param(
             "fields",
-            "permissions(id,role,type,domain,emailAddress,allowFileDiscovery)",
+            "permissions(id,role,type,domain,emailAddress,allowFileDiscovery,expirationTime)",
         )
         .add_scope(google_drive3::api::Scope::Full)
         .delegate(&mut delegate)
@@ -88,9 +100,8 @@ pub async fn list_permissions(
 
 #[derive(Debug)]
 pub enum Error {
-    Hub(hub_helper::Error),
     GetFile(google_drive3::Error),
-    ListPermissions(google_drive3::Error),
+    ListPermissions(pending_owner::Error),
 }
 
 impl error::Error for Error {}
@@ -98,7 +109,6 @@ impl error::Error for Error {}
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         match self {
-            Error::Hub(err) => write!(f, "{}", err),
             Error::GetFile(err) => {
                 write!(f, "Failed to get file: {}", err)
             }
@@ -1,47 +1,73 @@
 use crate::common::delegate::UploadDelegate;
 use crate::common::delegate::UploadDelegateConfig;
-use crate::common::hub_helper;
 use crate::common::permission;
+use crate::common::read_only;
 use crate::files;
+use crate::files::path_utils;
 use crate::hub::Hub;
 use crate::permissions;
+use google_drive3::api::Permission;
+use google_drive3::chrono::DateTime;
+use google_drive3::chrono::Utc;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
 
+// Exit code used when --email or --domain doesn't match any permission of the file
+pub const NO_MATCH_EXIT_CODE: i32 = 3;
+
 #[derive(Clone, Debug)]
 pub struct Config {
+    // File id or a path starting with '/'
     pub file_id: String,
     pub action: RevokeAction,
+    pub dry_run: bool,
 }
 
-pub async fn revoke(config: Config) -> Result<(), Error> {
-    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+pub async fn revoke(hub: &Hub, config: Config) -> Result<(), Error> {
+    if !config.dry_run {
+        read_only::err_if_enabled("revoke permissions").map_err(Error::ReadOnly)?;
+    }
+
     let delegate_config = UploadDelegateConfig::default();
 
-    let file = files::info::get_file(&hub, &config.file_id)
+    let file_id = path_utils::resolve_file_id(hub, &config.file_id)
+        .await
+        .map_err(Error::ResolvePath)?;
+
+    let file = files::info::get_file(hub, &file_id)
         .await
         .map_err(Error::GetFile)?;
 
-    let permissions =
-        permissions::list::list_permissions(&hub, delegate_config.clone(), &config.file_id)
-            .await
-            .map_err(Error::ListPermissions)?;
+    let permissions = permissions::list::list_permissions(hub, delegate_config.clone(), &file_id)
+        .await
+        .map_err(Error::ListPermissions)?;
 
     let delete_list = config.action.get_matching_permissions(permissions)?;
 
+    let verb = if config.dry_run {
+        "Would revoke"
+    } else {
+        "Revoking"
+    };
+
     for permission in delete_list {
-        if let Err(_) = print_revoke_details(&file, &permission) {
+        if print_revoke_details(verb, &file, &permission).is_err() {
             println!(
-                "Revoking permission with id: '{}'",
+                "{} permission with id: '{}'",
+                verb,
                 permission.id.clone().unwrap_or_default()
             );
         }
 
+        if config.dry_run {
+            continue;
+        }
+
         delete_permission(
-            &hub,
+            hub,
             delegate_config.clone(),
-            &config.file_id,
+            &file_id,
             &permission.id.clone().unwrap_or_default(),
         )
         .await
@@ -76,21 +102,34 @@ pub async fn delete_permission(
 
 #[derive(Debug)]
 pub enum Error {
-    Hub(hub_helper::Error),
+    ReadOnly(read_only::Error),
     GetFile(google_drive3::Error),
     ListPermissions(google_drive3::Error),
     DeletePermission(google_drive3::api::Permission, google_drive3::Error),
     PermissionNotFound(String),
+    NoPermissionForEmail(String),
+    NoPermissionForDomain(String),
+    ResolvePath(path_utils::PathResolutionError),
     UnknownPermissionType(String),
     UnknownPermissionRole(String),
 }
 
 impl error::Error for Error {}
 
+impl Error {
+    // The file has no permission for the email address or domain that was asked for
+    pub fn is_no_match(&self) -> bool {
+        matches!(
+            self,
+            Error::NoPermissionForEmail(_) | Error::NoPermissionForDomain(_)
+        )
+    }
+}
+
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         match self {
-            Error::Hub(err) => write!(f, "{}", err),
+            Error::ReadOnly(err) => write!(f, "{}", err),
             Error::GetFile(err) => {
                 write!(f, "Failed to get file: {}", err)
             }
@@ -108,6 +147,13 @@ impl Display for Error {
             Error::PermissionNotFound(id) => {
                 write!(f, "Permission '{}' not found", id)
             }
+            Error::NoPermissionForEmail(email) => {
+                write!(f, "The file isn't shared with '{}'", email)
+            }
+            Error::NoPermissionForDomain(domain) => {
+                write!(f, "The file isn't shared with the domain '{}'", domain)
+            }
+            Error::ResolvePath(err) => write!(f, "{}", err),
             Error::UnknownPermissionType(type_) => {
                 write!(f, "Unknown permission type: '{}'", type_)
             }
@@ -122,10 +168,14 @@ pub enum RevokeAction {
     Anyone,
     AllExceptOwner,
     Id(String),
+    // The permission of the user or group with the email address
+    Email(String),
+    // The permission of the domain
+    Domain(String),
 }
 
 impl RevokeAction {
-    fn get_matching_permissions(
+    pub fn get_matching_permissions(
         &self,
         permissions: Vec<google_drive3::api::Permission>,
     ) -> Result<Vec<google_drive3::api::Permission>, Error> {
@@ -152,6 +202,16 @@ impl RevokeAction {
                     .map(|p| vec![p])
                     .ok_or_else(|| Error::PermissionNotFound(id.to_string()))
             }
+
+            RevokeAction::Email(email) => {
+                let matching = permissions_for_email(permissions, email, Utc::now());
+                err_if_empty(matching, || Error::NoPermissionForEmail(email.clone()))
+            }
+
+            RevokeAction::Domain(domain) => {
+                let matching = permissions_for_domain(permissions, domain, Utc::now());
+                err_if_empty(matching, || Error::NoPermissionForDomain(domain.clone()))
+            }
         }
     }
 
@@ -188,7 +248,69 @@ impl RevokeAction {
     }
 }
 
+// Permissions of the user or group with the email address that haven't expired by now. The
+// owner's permission can't be revoked and is left out, like with AllExceptOwner.
+pub fn permissions_for_email(
+    permissions: Vec<Permission>,
+    email: &str,
+    now: DateTime<Utc>,
+) -> Vec<Permission> {
+    let owner_role = permission::Role::Owner.to_string();
+
+    permissions
+        .into_iter()
+        .filter(|p| !is_expired(p, now))
+        .filter(|p| p.role.as_deref() != Some(owner_role.as_str()))
+        .filter(|p| matches_ignoring_case(&p.email_address, email))
+        .collect()
+}
+
+// Permissions of type domain for the domain that haven't expired by now. Users and groups of
+// the domain are left alone.
+pub fn permissions_for_domain(
+    permissions: Vec<Permission>,
+    domain: &str,
+    now: DateTime<Utc>,
+) -> Vec<Permission> {
+    let domain_type = permission::Type::Domain.to_string();
+
+    permissions
+        .into_iter()
+        .filter(|p| !is_expired(p, now))
+        .filter(|p| p.type_.as_ref() == Some(&domain_type))
+        .filter(|p| matches_ignoring_case(&p.domain, domain))
+        .collect()
+}
+
+// Drive removes expired permissions on its own, they may still be listed for a while
+fn is_expired(permission: &Permission, now: DateTime<Utc>) -> bool {
+    permission
+        .expiration_time
+        .map(|expiration_time| expiration_time <= now)
+        .unwrap_or(false)
+}
+
+// Email addresses and domains aren't case sensitive
+fn matches_ignoring_case(value: &Option<String>, expected: &str) -> bool {
+    value
+        .as_ref()
+        .map(|value| value.eq_ignore_ascii_case(expected))
+        .unwrap_or(false)
+}
+
+fn err_if_empty(
+    permissions: Vec<google_drive3::api::Permission>,
+    err: impl FnOnce() -> Error,
+) -> Result<Vec<google_drive3::api::Permission>, Error> {
+    if permissions.is_empty() {
+        Err(err())
+    } else {
+        Ok(permissions)
+    }
+}
+
 fn print_revoke_details(
+    verb: &str,
     file: &google_drive3::api::File,
     permission: &google_drive3::api::Permission,
 ) -> Result<(), Error> {
@@ -208,7 +330,8 @@ fn print_revoke_details(
 
     if type_.requires_domain() {
         println!(
-            "Revoking '{}' permission to {} '{}' for '{}'",
+            "{} '{}' permission to {} '{}' for '{}'",
+            verb,
             role,
             type_,
             permission.domain.clone().unwrap_or_default(),
@@ -216,7 +339,8 @@ fn print_revoke_details(
         );
     } else if type_.requires_email() {
         println!(
-            "Revoking '{}' permission to '{}' with email '{}' for '{}'",
+            "{} '{}' permission to '{}' with email '{}' for '{}'",
+            verb,
             role,
             type_,
             permission.email_address.clone().unwrap_or_default(),
@@ -224,7 +348,8 @@ fn print_revoke_details(
         );
     } else {
         println!(
-            "Revoking '{}' permission to '{}' for '{}'",
+            "{} '{}' permission to '{}' for '{}'",
+            verb,
             role,
             type_,
             file.name.clone().unwrap_or_default()
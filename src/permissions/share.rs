@@ -1,9 +1,11 @@
 use crate::common::delegate::UploadDelegate;
 use crate::common::delegate::UploadDelegateConfig;
-use crate::common::hub_helper;
 use crate::common::permission;
+use crate::common::read_only;
 use crate::files;
 use crate::hub::Hub;
+use google_drive3::chrono;
+use google_drive3::chrono::DateTime;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
@@ -16,6 +18,7 @@ pub struct Config {
     pub discoverable: bool,
     pub email: Option<String>,
     pub domain: Option<String>,
+    pub expiration_time: Option<DateTime<chrono::Utc>>,
 }
 
 impl Config {
@@ -32,20 +35,20 @@ impl Config {
     }
 }
 
-pub async fn share(config: Config) -> Result<(), Error> {
+pub async fn share(hub: &Hub, config: Config) -> Result<(), Error> {
+    read_only::err_if_enabled("share files").map_err(Error::ReadOnly)?;
     err_if_missing_email(&config)?;
     err_if_missing_domain(&config)?;
 
-    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
     let delegate_config = UploadDelegateConfig::default();
 
-    let file = files::info::get_file(&hub, &config.file_id)
+    let file = files::info::get_file(hub, &config.file_id)
         .await
         .map_err(Error::GetFile)?;
 
     print_grant_details(&file, &config);
 
-    create_permission(&hub, delegate_config, &config)
+    create_permission(hub, delegate_config, &config)
         .await
         .map_err(Error::CreatePermission)?;
 
@@ -65,6 +68,7 @@ pub async fn create_permission(
         allow_file_discovery: config.allow_file_discovery(),
         email_address: config.email.clone(),
         domain: config.domain.clone(),
+        expiration_time: config.expiration_time,
         ..google_drive3::api::Permission::default()
     };
 
@@ -73,7 +77,7 @@ pub async fn create_permission(
         .create(new_permission, &config.file_id)
         .param(
             "fields",
-            "id,role,type,domain,emailAddress,allowFileDiscovery",
+            "id,role,type,domain,emailAddress,allowFileDiscovery,expirationTime",
         )
         .transfer_ownership(config.requires_ownership_transfer())
         .add_scope(google_drive3::api::Scope::Full)
@@ -87,7 +91,7 @@ pub async fn create_permission(
 
 #[derive(Debug)]
 pub enum Error {
-    Hub(hub_helper::Error),
+    ReadOnly(read_only::Error),
     GetFile(google_drive3::Error),
     CreatePermission(google_drive3::Error),
     MissingEmail(permission::Type),
@@ -99,7 +103,7 @@ impl error::Error for Error {}
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         match self {
-            Error::Hub(err) => write!(f, "{}", err),
+            Error::ReadOnly(err) => write!(f, "{}", err),
             Error::GetFile(err) => {
                 write!(f, "Failed to get file: {}", err)
             }
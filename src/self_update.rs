@@ -0,0 +1,393 @@
+use crate::common::byte_size::MIB;
+use crate::common::cancel;
+use crate::common::url_fetch;
+use serde::Deserialize;
+use sha2::Digest;
+use sha2::Sha256;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::io::Cursor;
+use std::io::IsTerminal;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+const MAX_RELEASE_SIZE: u64 = MIB;
+const MAX_ASSET_SIZE: u64 = 100 * MIB;
+
+// Exit code of update --check when a newer release exists
+pub const UPDATE_AVAILABLE_EXIT_CODE: i32 = 1;
+
+pub struct Config {
+    pub yes: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    pub html_url: Option<String>,
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+impl Release {
+    fn find_asset(&self, name: &str) -> Option<&Asset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+}
+
+// Prints whether a newer release exists and returns true if it does
+pub async fn check() -> Result<bool, Error> {
+    let release = latest_release().await?;
+    let current = env!("CARGO_PKG_VERSION");
+
+    if !is_newer(&release.tag_name, current)? {
+        println!("gdrive {} is the latest version", current);
+        return Ok(false);
+    }
+
+    println!(
+        "gdrive {} is available, the current version is {}",
+        version_of_tag(&release.tag_name),
+        current
+    );
+
+    if let Some(url) = &release.html_url {
+        println!("Release notes: {}", url);
+    }
+
+    Ok(true)
+}
+
+pub async fn update(config: Config) -> Result<(), Error> {
+    let release = latest_release().await?;
+    let current = env!("CARGO_PKG_VERSION");
+
+    if !is_newer(&release.tag_name, current)? {
+        println!("gdrive {} is the latest version", current);
+        return Ok(());
+    }
+
+    let target = env!("BUILD_TARGET");
+    let asset_name = asset_name(target).ok_or(Error::UnsupportedTarget(target.to_string()))?;
+    let asset = release
+        .find_asset(asset_name)
+        .ok_or(Error::MissingAsset(asset_name.to_string()))?;
+
+    let checksum_name = checksum_asset_name(asset_name);
+    let checksum_asset = release
+        .find_asset(&checksum_name)
+        .ok_or(Error::MissingChecksum(asset_name.to_string()))?;
+
+    let new_version = version_of_tag(&release.tag_name);
+    if !config.yes && !confirm_update(current, new_version)? {
+        println!("Update cancelled");
+        return Ok(());
+    }
+
+    let checksums = fetch(&checksum_asset.browser_download_url, MAX_RELEASE_SIZE).await?;
+    let checksums = String::from_utf8_lossy(&checksums);
+    let expected = checksum_for(&checksums, asset_name)
+        .ok_or(Error::MissingChecksum(asset_name.to_string()))?;
+
+    println!("Downloading {}", asset_name);
+    let archive = fetch(&asset.browser_download_url, MAX_ASSET_SIZE).await?;
+
+    let actual = sha256_hex(&archive);
+    if !actual.eq_ignore_ascii_case(&expected) {
+        return Err(Error::ChecksumMismatch {
+            asset_name: asset_name.to_string(),
+            expected,
+            actual,
+        });
+    }
+
+    let binary = extract_binary(asset_name, &archive)?;
+    let exe_path = std::env::current_exe().map_err(Error::CurrentExe)?;
+    replace_executable(&exe_path, &binary)?;
+
+    println!("Updated gdrive from {} to {}", current, new_version);
+
+    Ok(())
+}
+
+// Releases of the repository gdrive was built from, a fork only updates to its own builds
+pub fn latest_release_url(repository: &str) -> String {
+    let repository = repository.trim_end_matches('/').trim_end_matches(".git");
+    let repository = repository
+        .strip_prefix("https://github.com/")
+        .unwrap_or(repository);
+
+    format!(
+        "https://api.github.com/repos/{}/releases/latest",
+        repository
+    )
+}
+
+async fn latest_release() -> Result<Release, Error> {
+    let url = latest_release_url(env!("CARGO_PKG_REPOSITORY"));
+    let body = fetch(&url, MAX_RELEASE_SIZE).await?;
+    serde_json::from_slice(&body).map_err(Error::ParseRelease)
+}
+
+async fn fetch(url: &str, max_size: u64) -> Result<Vec<u8>, Error> {
+    let mut body: Vec<u8> = Vec::new();
+    url_fetch::fetch(url, max_size, &mut body)
+        .await
+        .map_err(|err| Error::Fetch(url.to_string(), err))?;
+    Ok(body)
+}
+
+fn confirm_update(current: &str, new_version: &str) -> Result<bool, Error> {
+    if !io::stdin().is_terminal() {
+        return Err(Error::NotInteractive);
+    }
+
+    let _prompt = cancel::Prompt::start();
+    print!("Update gdrive from {} to {}? [y/N] ", current, new_version);
+    let _ = io::stdout().flush();
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).map_err(Error::Prompt)?;
+    Ok(matches!(input.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+// Release tags are like v3.9.1 or 3.9.1
+pub fn version_of_tag(tag: &str) -> &str {
+    tag.strip_prefix('v').unwrap_or(tag)
+}
+
+// Numeric components of a version, pre-release and build suffixes are ignored
+pub fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let version = version_of_tag(version.trim());
+    let core = version.split(['-', '+']).next()?;
+
+    core.split('.')
+        .map(|part| part.parse::<u64>().ok())
+        .collect()
+}
+
+pub fn is_newer(tag: &str, current: &str) -> Result<bool, Error> {
+    let latest = parse_version(tag).ok_or(Error::InvalidVersion(tag.to_string()))?;
+    let current = parse_version(current).ok_or(Error::InvalidVersion(current.to_string()))?;
+
+    // Missing components count as 0, so 3.10 and 3.10.0 are the same version
+    let len = latest.len().max(current.len());
+    let component = |parts: &[u64], i: usize| parts.get(i).copied().unwrap_or(0);
+
+    for i in 0..len {
+        let (latest, current) = (component(&latest, i), component(&current, i));
+        if latest != current {
+            return Ok(latest > current);
+        }
+    }
+
+    Ok(false)
+}
+
+// Release asset built for the target triple, matching the names used by the release workflows
+pub fn asset_name(target: &str) -> Option<&'static str> {
+    match target {
+        "x86_64-unknown-linux-musl" | "x86_64-unknown-linux-gnu" => Some("gdrive_linux-x64.tar.gz"),
+        "x86_64-apple-darwin" => Some("gdrive_macos-x64.tar.gz"),
+        "x86_64-pc-windows-msvc" | "x86_64-pc-windows-gnu" => Some("gdrive_windows-x64.zip"),
+        _ => None,
+    }
+}
+
+pub fn checksum_asset_name(asset_name: &str) -> String {
+    format!("{}.sha256", asset_name)
+}
+
+// Finds the checksum of the asset in the output of sha256sum, a file with only the checksum
+// is accepted too
+pub fn checksum_for(checksums: &str, asset_name: &str) -> Option<String> {
+    let lines: Vec<&str> = checksums
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    for line in &lines {
+        let mut parts = line.split_whitespace();
+        let checksum = parts.next()?;
+
+        match parts.next() {
+            // sha256sum marks files read in binary mode with *
+            Some(name) if name.trim_start_matches('*') == asset_name => {
+                return Some(checksum.to_lowercase())
+            }
+            None if lines.len() == 1 => return Some(checksum.to_lowercase()),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn binary_name() -> &'static str {
+    if cfg!(windows) {
+        "gdrive.exe"
+    } else {
+        "gdrive"
+    }
+}
+
+pub fn extract_binary(asset_name: &str, archive: &[u8]) -> Result<Vec<u8>, Error> {
+    let binary_name = binary_name();
+
+    if asset_name.ends_with(".zip") {
+        let mut zip = zip::ZipArchive::new(Cursor::new(archive)).map_err(Error::ExtractZip)?;
+        let mut file = zip.by_name(binary_name).map_err(Error::ExtractZip)?;
+        let mut binary = Vec::new();
+        file.read_to_end(&mut binary).map_err(Error::Extract)?;
+        return Ok(binary);
+    }
+
+    let decoder = flate2::read::GzDecoder::new(archive);
+    let mut tar = tar::Archive::new(decoder);
+
+    for entry in tar.entries().map_err(Error::Extract)? {
+        let mut entry = entry.map_err(Error::Extract)?;
+        let path = entry.path().map_err(Error::Extract)?;
+
+        if path.file_name().map_or(false, |name| name == binary_name) {
+            let mut binary = Vec::new();
+            entry.read_to_end(&mut binary).map_err(Error::Extract)?;
+            return Ok(binary);
+        }
+    }
+
+    Err(Error::BinaryNotInArchive(asset_name.to_string()))
+}
+
+// The new binary is written next to the executable and renamed over it, so a failed update
+// leaves the old executable in place
+fn replace_executable(exe_path: &Path, binary: &[u8]) -> Result<(), Error> {
+    let dir = exe_path
+        .parent()
+        .ok_or(Error::CurrentExe(io::Error::from(io::ErrorKind::NotFound)))?;
+
+    let mut tmp_file = tempfile::NamedTempFile::new_in(dir)
+        .map_err(|err| Error::Replace(exe_path.to_path_buf(), err))?;
+    tmp_file
+        .write_all(binary)
+        .map_err(|err| Error::Replace(exe_path.to_path_buf(), err))?;
+
+    let permissions = fs::metadata(exe_path)
+        .map_err(|err| Error::Replace(exe_path.to_path_buf(), err))?
+        .permissions();
+    fs::set_permissions(tmp_file.path(), permissions)
+        .map_err(|err| Error::Replace(exe_path.to_path_buf(), err))?;
+
+    swap_executable(exe_path, tmp_file)
+}
+
+#[cfg(not(windows))]
+fn swap_executable(exe_path: &Path, tmp_file: tempfile::NamedTempFile) -> Result<(), Error> {
+    tmp_file
+        .persist(exe_path)
+        .map_err(|err| Error::Replace(exe_path.to_path_buf(), err.error))?;
+    Ok(())
+}
+
+// A running executable can't be replaced on Windows, but it can be renamed. The old executable
+// is left as gdrive.exe.old and removed by the next update.
+#[cfg(windows)]
+fn swap_executable(exe_path: &Path, tmp_file: tempfile::NamedTempFile) -> Result<(), Error> {
+    let old_path = exe_path.with_extension("exe.old");
+    let _ = fs::remove_file(&old_path);
+
+    fs::rename(exe_path, &old_path).map_err(|err| Error::Replace(exe_path.to_path_buf(), err))?;
+
+    if let Err(err) = tmp_file.persist(exe_path) {
+        let _ = fs::rename(&old_path, exe_path);
+        return Err(Error::Replace(exe_path.to_path_buf(), err.error));
+    }
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Fetch(String, url_fetch::Error),
+    ParseRelease(serde_json::Error),
+    InvalidVersion(String),
+    UnsupportedTarget(String),
+    MissingAsset(String),
+    MissingChecksum(String),
+    ChecksumMismatch {
+        asset_name: String,
+        expected: String,
+        actual: String,
+    },
+    NotInteractive,
+    Prompt(io::Error),
+    Extract(io::Error),
+    ExtractZip(zip::result::ZipError),
+    BinaryNotInArchive(String),
+    CurrentExe(io::Error),
+    Replace(PathBuf, io::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Fetch(url, err) => write!(f, "Failed to fetch {}: {}", url, err),
+            Error::ParseRelease(err) => write!(f, "Failed to parse the release: {}", err),
+            Error::InvalidVersion(version) => write!(f, "Invalid version '{}'", version),
+            Error::UnsupportedTarget(target) => write!(
+                f,
+                "No release is built for {}, update gdrive the way it was installed",
+                target
+            ),
+            Error::MissingAsset(name) => write!(f, "The latest release has no {}", name),
+            Error::MissingChecksum(name) => write!(
+                f,
+                "The latest release has no checksum for {}, refusing to update",
+                name
+            ),
+            Error::ChecksumMismatch {
+                asset_name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Checksum of {} is {}, expected {}. The executable was not replaced",
+                asset_name, actual, expected
+            ),
+            Error::NotInteractive => {
+                write!(f, "Can't ask for confirmation, use --yes to update anyway")
+            }
+            Error::Prompt(err) => write!(f, "Failed to read the answer: {}", err),
+            Error::Extract(err) => write!(f, "Failed to extract the release: {}", err),
+            Error::ExtractZip(err) => write!(f, "Failed to extract the release: {}", err),
+            Error::BinaryNotInArchive(name) => write!(f, "{} doesn't contain gdrive", name),
+            Error::CurrentExe(err) => {
+                write!(f, "Failed to find the current executable: {}", err)
+            }
+            Error::Replace(path, err) => {
+                write!(f, "Failed to replace {}: {}", path.display(), err)
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod tests {
-        use crate::{Cli, Command, FileCommand};
+        use crate::{mutating_operation, Cli, Command, FileCommand};
     use clap::Parser;
 
     #[test]
@@ -26,6 +26,7 @@ mod tests {
                     print_chunk_errors: _,
                     print_chunk_info: _,
                     print_only_id: _,
+                    ..
                 } => {
                     assert!(!recursive, "default should be non-recursive");
                 }
@@ -59,6 +60,7 @@ mod tests {
                     print_chunk_errors: _,
                     print_chunk_info: _,
                     print_only_id: _,
+                    ..
                 } => {
                     assert!(recursive, "-r should enable recursive upload");
                 }
@@ -85,7 +87,12 @@ mod tests {
                 remote_path: _,
                 mime: _,
                 recursive,
+                contents: _,
                 overwrite: _,
+                deep_check: _,
+                keep_temp: _,
+                strict_path: _,
+                json: _,
             } => {
                 assert!(!recursive, "push should be non-recursive by default");
             }
@@ -111,11 +118,1628 @@ mod tests {
                 remote_path: _,
                 mime: _,
                 recursive,
+                contents: _,
                 overwrite: _,
+                deep_check: _,
+                keep_temp: _,
+                strict_path: _,
+                json: _,
             } => {
                 assert!(recursive, "push -r should enable recursive upload");
             }
             _ => panic!("unexpected command"),
         }
     }
+
+    fn parse(args: &[&str]) -> Cli {
+        let mut argv = vec!["gdrive"];
+        argv.extend_from_slice(args);
+        Cli::try_parse_from(argv).expect("parse failed")
+    }
+
+    #[test]
+    fn read_only_flag_is_global() {
+        assert!(parse(&["--read-only", "about"]).read_only);
+        assert!(parse(&["files", "list", "--read-only"]).read_only);
+        assert!(!parse(&["files", "list"]).read_only);
+    }
+
+    #[test]
+    fn read_only_blocks_mutating_commands() {
+        let blocked: &[&[&str]] = &[
+            &["files", "upload", "a.txt"],
+            &["files", "update", "id", "a.txt"],
+            &["files", "delete", "id"],
+            &["files", "mkdir", "dir"],
+            &["files", "rename", "id", "name"],
+            &["files", "move", "id", "parent"],
+            &["files", "copy", "id", "parent"],
+            &["files", "import", "a.csv"],
+            &["permissions", "share", "id"],
+            &["permissions", "copy", "src", "dst"],
+            &["permissions", "accept-ownership", "id"],
+            &["permissions", "cancel-transfer", "id"],
+            &["permissions", "revoke", "id"],
+            &["push", "a.txt", "/backup"],
+            &["files", "dedup", "/Photos", "--apply"],
+            &["files", "shortcut", "id", "name"],
+            &["watch", "photos", "/backup"],
+        ];
+
+        for args in blocked {
+            let cli = parse(args);
+            assert!(
+                mutating_operation(&cli.command).is_some(),
+                "{:?} should be blocked in read-only mode",
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn read_only_allows_read_commands() {
+        let allowed: &[&[&str]] = &[
+            &["about"],
+            &["version"],
+            &["account", "list"],
+            &["drives", "list"],
+            &["files", "info", "id"],
+            &["files", "meta", "id1", "id2"],
+            &["files", "list"],
+            &["files", "download", "id"],
+            &["files", "export", "id", "a.docx"],
+            &["permissions", "list", "id"],
+            &["permissions", "copy", "src", "dst", "--dry-run"],
+            &["files", "dedup", "/Photos"],
+            &["watch", "photos", "/backup", "--dry-run"],
+        ];
+
+        for args in allowed {
+            let cli = parse(args);
+            assert!(
+                mutating_operation(&cli.command).is_none(),
+                "{:?} should be allowed in read-only mode",
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn list_top_level_requires_drive() {
+        assert!(Cli::try_parse_from(["gdrive", "files", "list", "--top-level"]).is_err());
+
+        let cli = parse(&["files", "list", "--drive", "0ABC", "--top-level"]);
+        match cli.command {
+            Command::Files {
+                command: FileCommand::List {
+                    drive, top_level, ..
+                },
+            } => {
+                assert_eq!(drive.as_deref(), Some("0ABC"));
+                assert!(top_level);
+            }
+            _ => panic!("unexpected command"),
+        }
+    }
+
+    #[test]
+    fn upload_retry_manifest_without_file_path() {
+        let cli = parse(&["files", "upload", "--retry-manifest", "failures.json"]);
+        match cli.command {
+            Command::Files {
+                command:
+                    FileCommand::Upload {
+                        file_path,
+                        retry_manifest,
+                        ..
+                    },
+            } => {
+                assert!(file_path.is_none());
+                assert_eq!(retry_manifest, Some("failures.json".into()));
+            }
+            _ => panic!("unexpected command"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "upload"]).is_err());
+    }
+
+    #[test]
+    fn upload_force_resumable_conflicts_with_force_simple() {
+        assert!(Cli::try_parse_from([
+            "gdrive",
+            "files",
+            "upload",
+            "--force-resumable",
+            "--force-simple",
+            "a.txt",
+        ])
+        .is_err());
+
+        let cli = parse(&["files", "update", "--force-simple", "id", "a.txt"]);
+        match cli.command {
+            Command::Files {
+                command:
+                    FileCommand::Update {
+                        force_resumable,
+                        force_simple,
+                        ..
+                    },
+            } => {
+                assert!(!force_resumable);
+                assert!(force_simple);
+            }
+            _ => panic!("unexpected command"),
+        }
+    }
+
+    #[test]
+    fn import_source_from_stdin_and_url() {
+        use crate::files::import::ImportSource;
+
+        let cli = parse(&[
+            "files",
+            "import",
+            "-",
+            "--to",
+            "sheet",
+            "--name",
+            "Weekly report",
+            "--source-mime",
+            "text/csv",
+        ]);
+        match cli.command {
+            Command::Files {
+                command: FileCommand::Import { source, name, .. },
+            } => {
+                assert_eq!(source, Some(ImportSource::Stdin));
+                assert_eq!(name.as_deref(), Some("Weekly report"));
+            }
+            _ => panic!("unexpected command"),
+        }
+
+        let cli = parse(&["files", "import", "https://example.com/report.csv"]);
+        match cli.command {
+            Command::Files {
+                command: FileCommand::Import { source, .. },
+            } => {
+                assert_eq!(
+                    source,
+                    Some(ImportSource::Url(String::from("https://example.com/report.csv")))
+                );
+            }
+            _ => panic!("unexpected command"),
+        }
+
+        assert!(
+            Cli::try_parse_from(["gdrive", "files", "import", "http://example.com/a.csv"]).is_err()
+        );
+    }
+
+    #[test]
+    fn keep_temp_flag() {
+        match parse(&["push", "report.txt", "/backups/report-2024.txt", "--keep-temp"]).command {
+            Command::Push { keep_temp, .. } => assert!(keep_temp),
+            _ => panic!("unexpected command"),
+        }
+
+        match parse(&["files", "upload", "report.txt", "/backups/report-2024.txt"]).command {
+            Command::Files {
+                command: FileCommand::Upload { keep_temp, .. },
+            } => assert!(!keep_temp),
+            _ => panic!("unexpected command"),
+        }
+    }
+
+    #[test]
+    fn upload_parent_is_repeatable() {
+        let cli = parse(&[
+            "files", "upload", "report.txt", "--parent", "folderA", "--parent", "folderB",
+        ]);
+        match cli.command {
+            Command::Files {
+                command: FileCommand::Upload { parent, .. },
+            } => assert_eq!(
+                parent,
+                Some(vec![String::from("folderA"), String::from("folderB")])
+            ),
+            _ => panic!("unexpected command"),
+        }
+    }
+
+    #[test]
+    fn list_newest_and_oldest() {
+        match parse(&["files", "list", "--newest", "10"]).command {
+            Command::Files {
+                command: FileCommand::List { newest, oldest, .. },
+            } => {
+                assert_eq!(newest, Some(10));
+                assert_eq!(oldest, None);
+            }
+            _ => panic!("unexpected command"),
+        }
+
+        for args in [
+            ["files", "list", "--newest", "10", "--order-by", "name"],
+            ["files", "list", "--oldest", "10", "--max", "5"],
+            ["files", "list", "--newest", "10", "--oldest", "5"],
+        ] {
+            let mut argv = vec!["gdrive"];
+            argv.extend_from_slice(&args);
+            assert!(Cli::try_parse_from(argv).is_err(), "{:?} should fail", args);
+        }
+    }
+
+    #[test]
+    fn mkdir_parents_path_json() {
+        match parse(&["files", "mkdir", "/a/b/c", "--parents-path", "--json"]).command {
+            Command::Files {
+                command:
+                    FileCommand::Mkdir {
+                        name,
+                        parents_path,
+                        json,
+                        ..
+                    },
+            } => {
+                assert_eq!(name, "/a/b/c");
+                assert!(parents_path);
+                assert!(json);
+            }
+            _ => panic!("unexpected command"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "mkdir", "a", "--json"]).is_err());
+        assert!(Cli::try_parse_from([
+            "gdrive", "files", "mkdir", "/a/b", "--parents-path", "--parent", "folderA"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn list_time_style() {
+        use crate::common::time_fmt::TimeStyle;
+
+        match parse(&["files", "list"]).command {
+            Command::Files {
+                command: FileCommand::List { time_style, .. },
+            } => assert_eq!(time_style, TimeStyle::Local),
+            _ => panic!("unexpected command"),
+        }
+
+        match parse(&["files", "list", "--time-style", "relative"]).command {
+            Command::Files {
+                command: FileCommand::List { time_style, .. },
+            } => assert_eq!(time_style, TimeStyle::Relative),
+            _ => panic!("unexpected command"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "list", "--time-style", "utc"]).is_err());
+    }
+
+    #[test]
+    fn strict_path_flag() {
+        match parse(&["push", "report.txt", "/backups/2024", "--strict-path"]).command {
+            Command::Push { strict_path, .. } => assert!(strict_path),
+            _ => panic!("unexpected command"),
+        }
+
+        match parse(&["files", "upload", "report.txt", "/backups/2024", "--no-create"]).command {
+            Command::Files {
+                command: FileCommand::Upload { strict_path, .. },
+            } => assert!(strict_path),
+            _ => panic!("unexpected command"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "upload", "report.txt", "--strict-path"]).is_err());
+    }
+
+    #[test]
+    fn list_output_mode() {
+        use crate::common::output::OutputMode;
+
+        match parse(&["files", "list", "--output", "pretty", "--no-color"]).command {
+            Command::Files {
+                command:
+                    FileCommand::List {
+                        output, no_color, ..
+                    },
+            } => {
+                assert_eq!(output, Some(OutputMode::Pretty));
+                assert!(no_color);
+            }
+            _ => panic!("unexpected command"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "list", "--output", "fancy"]).is_err());
+    }
+
+    #[test]
+    fn download_manifest() {
+        match parse(&["files", "download", "abc123", "--recursive", "--manifest", "sums.md5"]).command {
+            Command::Files {
+                command: FileCommand::Download { manifest, .. },
+            } => assert_eq!(manifest, Some(std::path::PathBuf::from("sums.md5"))),
+            _ => panic!("unexpected command"),
+        }
+
+        assert!(Cli::try_parse_from([
+            "gdrive", "files", "download", "abc123", "--stdout", "--manifest", "sums.md5"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn chunk_size_syntax() {
+        match parse(&["files", "upload", "report.txt", "--chunk-size", "512K"]).command {
+            Command::Files {
+                command: FileCommand::Upload { chunk_size, .. },
+            } => assert_eq!(chunk_size.map(|size| size.in_bytes()), Some(512 * 1024)),
+            _ => panic!("unexpected command"),
+        }
+
+        match parse(&["files", "update", "abc123", "report.txt", "--chunk-size", "16"]).command {
+            Command::Files {
+                command: FileCommand::Update { chunk_size, .. },
+            } => assert_eq!(chunk_size.in_bytes(), 16 * 1024 * 1024),
+            _ => panic!("unexpected command"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "upload", "report.txt", "--chunk-size", "100K"]).is_err());
+    }
+
+    #[test]
+    fn remember_is_global() {
+        assert!(parse(&["files", "list", "--remember"]).remember);
+        assert!(!parse(&["files", "list"]).remember);
+    }
+
+    #[test]
+    fn update_by_path() {
+        match parse(&["files", "update", "/Docs/report.pdf", "./report.pdf"]).command {
+            Command::Files {
+                command: FileCommand::Update { file_id, file_path, .. },
+            } => {
+                assert_eq!(file_id, "/Docs/report.pdf");
+                assert_eq!(file_path, Some(std::path::PathBuf::from("./report.pdf")));
+            }
+            _ => panic!("unexpected command"),
+        }
+    }
+
+    #[test]
+    fn json_output_flags() {
+        match parse(&["files", "upload", "report.txt", "--json"]).command {
+            Command::Files {
+                command: FileCommand::Upload { json, .. },
+            } => assert!(json),
+            _ => panic!("unexpected command"),
+        }
+
+        match parse(&["push", "report.txt", "/backups/", "--json"]).command {
+            Command::Push { json, .. } => assert!(json),
+            _ => panic!("unexpected command"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "upload", "report.txt", "--json", "--print-only-id"]).is_err());
+        assert!(Cli::try_parse_from(["gdrive", "files", "update", "abc123", "report.txt", "--json"]).is_ok());
+        assert!(Cli::try_parse_from(["gdrive", "files", "import", "report.csv", "--json", "--print-only-id"]).is_err());
+    }
+
+    #[test]
+    fn import_existing_document_flags() {
+        match parse(&["files", "import", "report.csv", "--overwrite"]).command {
+            Command::Files {
+                command: FileCommand::Import { overwrite, update, .. },
+            } => {
+                assert!(overwrite);
+                assert_eq!(update, None);
+            }
+            _ => panic!("unexpected command"),
+        }
+
+        match parse(&["files", "import", "report.csv", "--update", "1AbC"]).command {
+            Command::Files {
+                command: FileCommand::Import { update, .. },
+            } => assert_eq!(update, Some(String::from("1AbC"))),
+            _ => panic!("unexpected command"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "import", "report.csv", "--overwrite", "--update", "1AbC"]).is_err());
+    }
+
+    #[test]
+    fn import_url_flag() {
+        use crate::files::import::ImportSource;
+
+        match parse(&["files", "import", "--url", "https://example.com/report.csv"]).command {
+            Command::Files {
+                command: FileCommand::Import { source, url, .. },
+            } => {
+                assert_eq!(source, None);
+                assert_eq!(
+                    url,
+                    Some(ImportSource::Url(String::from("https://example.com/report.csv")))
+                );
+            }
+            _ => panic!("unexpected command"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "import", "--url", "report.csv"]).is_err());
+        assert!(Cli::try_parse_from(["gdrive", "files", "import", "--url", "http://example.com/a.csv"]).is_err());
+        assert!(Cli::try_parse_from(["gdrive", "files", "import", "a.csv", "--url", "https://example.com/a.csv"]).is_err());
+        assert!(Cli::try_parse_from(["gdrive", "files", "import"]).is_err());
+    }
+
+    #[test]
+    fn delete_concurrency() {
+        match parse(&["files", "delete", "abc123", "--recursive"]).command {
+            Command::Files {
+                command: FileCommand::Delete { concurrency, .. },
+            } => assert_eq!(concurrency, 8),
+            _ => panic!("unexpected command"),
+        }
+
+        match parse(&["files", "delete", "abc123", "--recursive", "--concurrency", "2"]).command {
+            Command::Files {
+                command: FileCommand::Delete { concurrency, .. },
+            } => assert_eq!(concurrency, 2),
+            _ => panic!("unexpected command"),
+        }
+    }
+
+    #[test]
+    fn request_timeout_flag() {
+        assert_eq!(parse(&["files", "list"]).request_timeout, 300);
+        assert_eq!(
+            parse(&["files", "download", "abc123", "--request-timeout", "20"]).request_timeout,
+            20
+        );
+        assert!(
+            Cli::try_parse_from(["gdrive", "files", "list", "--request-timeout", "0"]).is_err()
+        );
+    }
+
+    #[test]
+    fn stat_format() {
+        match parse(&["files", "stat", "/docs/report.txt", "--format", "name,size"]).command {
+            Command::Files {
+                command:
+                    FileCommand::Stat {
+                        file_id,
+                        format,
+                        field_separator,
+                    },
+            } => {
+                assert_eq!(file_id, "/docs/report.txt");
+                assert_eq!(format.api_fields(), "name,size");
+                assert_eq!(field_separator, "\t");
+            }
+            _ => panic!("unexpected command"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "stat", "id", "--format", "owner"]).is_err());
+    }
+
+    #[test]
+    fn space_requires_extra_scope() {
+        use crate::hub::APP_DATA_SCOPE;
+        use crate::hub::PHOTOS_SCOPE;
+        use crate::required_scopes;
+
+        assert!(required_scopes(&parse(&["files", "list"]).command).is_empty());
+        assert_eq!(
+            required_scopes(&parse(&["files", "list", "--space", "appDataFolder"]).command),
+            vec![APP_DATA_SCOPE]
+        );
+        assert_eq!(
+            required_scopes(&parse(&["files", "list", "--space", "photos"]).command),
+            vec![PHOTOS_SCOPE]
+        );
+        assert_eq!(
+            required_scopes(&parse(&["files", "delete", "id", "--space", "appDataFolder"]).command),
+            vec![APP_DATA_SCOPE]
+        );
+        assert_eq!(
+            required_scopes(&parse(&["files", "upload", "a.txt", "--parent", "appDataFolder"]).command),
+            vec![APP_DATA_SCOPE]
+        );
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "list", "--space", "appdata"]).is_err());
+    }
+
+    #[test]
+    fn mime_from_extension() {
+        match parse(&[
+            "files",
+            "upload",
+            "notes.md",
+            "--mime-from-extension",
+            "md=text/markdown",
+            "--mime-from-extension",
+            ".LOG=text/plain",
+        ])
+        .command
+        {
+            Command::Files {
+                command: FileCommand::Upload { mime_from_extension, .. },
+            } => {
+                assert_eq!(mime_from_extension.len(), 2);
+                assert_eq!(
+                    mime_from_extension[0].get(std::path::Path::new("notes.md")),
+                    Some("text/markdown".parse().unwrap())
+                );
+                assert_eq!(
+                    mime_from_extension[1].get(std::path::Path::new("server.log")),
+                    Some(mime::TEXT_PLAIN)
+                );
+            }
+            _ => panic!("expected files upload"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "upload", "a.md", "--mime-from-extension", "md"]).is_err());
+        assert!(Cli::try_parse_from(["gdrive", "files", "upload", "a.md", "--mime-from-extension", "md=markdown"]).is_err());
+    }
+
+    #[test]
+    fn account_scope() {
+        use crate::app_config::AccountScope;
+        use crate::AccountCommand;
+
+        match parse(&["account", "add"]).command {
+            Command::Account {
+                command: AccountCommand::Add { scope, .. },
+            } => assert_eq!(scope, AccountScope::Full),
+            _ => panic!("expected account add"),
+        }
+
+        match parse(&["account", "add", "--scope", "drive.file"]).command {
+            Command::Account {
+                command: AccountCommand::Add { scope, .. },
+            } => assert_eq!(scope, AccountScope::File),
+            _ => panic!("expected account add"),
+        }
+
+        match parse(&["account", "list", "--details"]).command {
+            Command::Account {
+                command: AccountCommand::List { details },
+            } => assert!(details),
+            _ => panic!("expected account list"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "account", "add", "--scope", "drive.readonly"]).is_err());
+    }
+
+    #[test]
+    fn reauth_flag() {
+        assert!(!parse(&["files", "list"]).reauth);
+        assert!(parse(&["files", "list", "--reauth"]).reauth);
+        assert!(parse(&["--reauth", "files", "list", "--space", "appDataFolder"]).reauth);
+    }
+
+    #[test]
+    fn upload_contents_flag() {
+        match parse(&["push", "-r", "--contents", "photos", "/backup"]).command {
+            Command::Push { contents, .. } => assert!(contents),
+            _ => panic!("expected push"),
+        }
+
+        match parse(&["files", "upload", "--recursive", "--contents", "photos", "/backup"]).command {
+            Command::Files {
+                command: FileCommand::Upload { contents, .. },
+            } => assert!(contents),
+            _ => panic!("expected files upload"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "push", "--contents", "photos", "/backup"]).is_err());
+    }
+
+    #[test]
+    fn list_parent_accepts_path() {
+        match parse(&["files", "list", "--parent", "/Projects/2024"]).command {
+            Command::Files {
+                command: FileCommand::List { parent, .. },
+            } => assert_eq!(parent.as_deref(), Some("/Projects/2024")),
+            _ => panic!("expected files list"),
+        }
+    }
+
+    #[test]
+    fn upload_root_name() {
+        match parse(&["files", "upload", "--recursive", "--name", "Backup", "."]).command {
+            Command::Files {
+                command: FileCommand::Upload { name, .. },
+            } => assert_eq!(name.as_deref(), Some("Backup")),
+            _ => panic!("expected files upload"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "upload", "--name", "Backup", "."]).is_err());
+        assert!(Cli::try_parse_from([
+            "gdrive", "files", "upload", "--recursive", "--contents", "--name", "Backup", "."
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn list_table_flag() {
+        match parse(&["files", "list", "--table", "--skip-header"]).command {
+            Command::Files {
+                command: FileCommand::List {
+                    table, skip_header, ..
+                },
+            } => {
+                assert!(table);
+                assert!(skip_header);
+            }
+            _ => panic!("unexpected command"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "list", "--table", "--output", "plain"]).is_err());
+    }
+
+    #[test]
+    fn upload_strict_flag() {
+        match parse(&["files", "upload", "--recursive", "--strict", "."]).command {
+            Command::Files {
+                command: FileCommand::Upload { strict, .. },
+            } => assert!(strict),
+            _ => panic!("expected files upload"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "upload", "--strict", "."]).is_err());
+    }
+
+    #[test]
+    fn push_deep_check_flag() {
+        match parse(&["push", "-r", "--deep-check", "photos", "/backup"]).command {
+            Command::Push { deep_check, .. } => assert!(deep_check),
+            _ => panic!("expected push"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "push", "--deep-check", "photos", "/backup"]).is_err());
+        assert!(Cli::try_parse_from([
+            "gdrive", "push", "-r", "--deep-check", "--overwrite", "photos", "/backup"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn import_parent_accepts_paths() {
+        match parse(&["files", "import", "data.csv", "--parent", "/Imports/2024", "--parent", "1AbC", "--strict-path"]).command {
+            Command::Files {
+                command: FileCommand::Import { parent, strict_path, .. },
+            } => {
+                assert_eq!(parent, Some(vec![String::from("/Imports/2024"), String::from("1AbC")]));
+                assert!(strict_path);
+            }
+            _ => panic!("expected files import"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "import", "data.csv", "--strict-path"]).is_err());
+    }
+
+    #[test]
+    fn rename_force_flag() {
+        match parse(&["files", "rename", "1AbC", "report.txt", "--force"]).command {
+            Command::Files {
+                command: FileCommand::Rename { name, force, .. },
+            } => {
+                assert_eq!(name, "report.txt");
+                assert!(force);
+            }
+            _ => panic!("expected files rename"),
+        }
+    }
+
+    #[test]
+    fn update_by_path_with_rename() {
+        match parse(&["files", "update", "/reports/q1.csv", "q1.csv", "--name", "Q1.csv", "--print-only-id"]).command {
+            Command::Files {
+                command: FileCommand::Update {
+                    file_id,
+                    name,
+                    print_only_id,
+                    ..
+                },
+            } => {
+                assert_eq!(file_id, "/reports/q1.csv");
+                assert_eq!(name.as_deref(), Some("Q1.csv"));
+                assert!(print_only_id);
+            }
+            _ => panic!("expected files update"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "update", "1AbC", "q1.csv", "--json", "--print-only-id"]).is_err());
+    }
+
+    #[test]
+    fn upload_mtime_from_git_flag() {
+        match parse(&["files", "upload", "--recursive", "--mtime-from-git", "repo"]).command {
+            Command::Files {
+                command: FileCommand::Upload { mtime_from_git, .. },
+            } => assert!(mtime_from_git),
+            _ => panic!("expected files upload"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "upload", "--mtime-from-git", "repo"]).is_err());
+    }
+
+    #[test]
+    fn refresh_token_stdin_flag() {
+        assert!(!parse(&["files", "list"]).refresh_token_stdin);
+        assert!(parse(&["files", "list", "--refresh-token-stdin"]).refresh_token_stdin);
+        let args = [
+            "gdrive",
+            "--refresh-token-stdin",
+            "--reauth",
+            "files",
+            "list",
+        ];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn download_max_size() {
+        match parse(&["files", "download", "abc123", "--max-size", "2G"]).command {
+            Command::Files {
+                command: FileCommand::Download { max_size, .. },
+            } => assert_eq!(max_size.map(|size| size.in_bytes()), Some(2 * 1024 * 1024 * 1024)),
+            _ => panic!("unexpected command"),
+        }
+
+        match parse(&["files", "download", "abc123"]).command {
+            Command::Files {
+                command: FileCommand::Download { max_size, .. },
+            } => assert!(max_size.is_none()),
+            _ => panic!("unexpected command"),
+        }
+    }
+
+    #[test]
+    fn list_find_duplicates() {
+        match parse(&["files", "list", "--parent", "abc", "--find-duplicates", "--by-md5"]).command {
+            Command::Files {
+                command:
+                    FileCommand::List {
+                        find_duplicates,
+                        by_md5,
+                        json,
+                        ..
+                    },
+            } => assert!(find_duplicates && by_md5 && !json),
+            _ => panic!("unexpected command"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "list", "--find-duplicates"]).is_err());
+        assert!(Cli::try_parse_from(["gdrive", "files", "list", "--parent", "abc", "--json"]).is_err());
+    }
+
+    #[test]
+    fn info_capabilities_and_json() {
+        match parse(&["files", "info", "abc", "--capabilities", "--json"]).command {
+            Command::Files {
+                command:
+                    FileCommand::Info {
+                        capabilities, json, ..
+                    },
+            } => assert!(capabilities && json),
+            _ => panic!("unexpected command"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "info", "abc", "--check-access", "--json"]).is_err());
+    }
+
+    #[test]
+    fn list_owner_and_query_help() {
+        match parse(&["files", "list", "--parent", "abc", "--owner", "alice@example.com"]).command {
+            Command::Files {
+                command: FileCommand::List { owner, parent, .. },
+            } => {
+                assert_eq!(owner.as_deref(), Some("alice@example.com"));
+                assert_eq!(parent.as_deref(), Some("abc"));
+            }
+            _ => panic!("unexpected command"),
+        }
+
+        let cli = parse(&["files", "query-help"]);
+        assert!(matches!(
+            cli.command,
+            Command::Files {
+                command: FileCommand::QueryHelp
+            }
+        ));
+        assert!(mutating_operation(&cli.command).is_none());
+    }
+
+    #[test]
+    fn upload_parent_path_with_create_parents() {
+        let cli = parse(&[
+            "files",
+            "upload",
+            "report.txt",
+            "--parent",
+            "/Projects/2024",
+            "--parent",
+            "folderB",
+            "--create-parents",
+        ]);
+        match cli.command {
+            Command::Files {
+                command:
+                    FileCommand::Upload {
+                        parent,
+                        create_parents,
+                        ..
+                    },
+            } => {
+                let expected = vec![String::from("/Projects/2024"), String::from("folderB")];
+                assert_eq!(parent, Some(expected));
+                assert!(create_parents);
+            }
+            _ => panic!("unexpected command"),
+        }
+
+        let argv = [
+            "gdrive",
+            "files",
+            "upload",
+            "report.txt",
+            "--create-parents",
+        ];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn mkdir_parent_path_with_create_parents() {
+        let cli = parse(&[
+            "files",
+            "mkdir",
+            "2024",
+            "--parent",
+            "/Projects",
+            "--create-parents",
+        ]);
+        match cli.command {
+            Command::Files {
+                command:
+                    FileCommand::Mkdir {
+                        parent,
+                        create_parents,
+                        ..
+                    },
+            } => {
+                assert_eq!(parent, Some(vec![String::from("/Projects")]));
+                assert!(create_parents);
+            }
+            _ => panic!("unexpected command"),
+        }
+
+        assert!(
+            Cli::try_parse_from(["gdrive", "files", "mkdir", "2024", "--create-parents"]).is_err()
+        );
+    }
+
+    #[test]
+    fn list_query_file() {
+        match parse(&["files", "list", "--query-file", "queries/reports.txt"]).command {
+            Command::Files {
+                command: FileCommand::List { query_file, .. },
+            } => assert_eq!(
+                query_file,
+                Some(std::path::PathBuf::from("queries/reports.txt"))
+            ),
+            _ => panic!("expected files list"),
+        }
+
+        let argv = [
+            "gdrive",
+            "files",
+            "list",
+            "--query",
+            "starred = true",
+            "--query-file",
+            "q",
+        ];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn files_transfer() {
+        let cli = parse(&[
+            "files",
+            "transfer",
+            "/Projects",
+            "--to-account",
+            "work@example.com",
+            "--dest",
+            "/Backup",
+            "--skip-existing",
+        ]);
+        assert_eq!(mutating_operation(&cli.command), Some("transfer files"));
+
+        match cli.command {
+            Command::Files {
+                command:
+                    FileCommand::Transfer {
+                        source,
+                        to_account,
+                        dest,
+                        skip_existing,
+                    },
+            } => {
+                assert_eq!(source, "/Projects");
+                assert_eq!(to_account, "work@example.com");
+                assert_eq!(dest, "/Backup");
+                assert!(skip_existing);
+            }
+            _ => panic!("expected files transfer"),
+        }
+
+        let argv = ["gdrive", "files", "transfer", "id1", "--dest", "/Backup"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn deadline_flag() {
+        assert_eq!(parse(&["files", "list"]).deadline, None);
+
+        let cli = parse(&["push", "photos", "/backup", "--deadline", "1h30m"]);
+        assert_eq!(
+            cli.deadline.map(|deadline| deadline.duration()),
+            Some(std::time::Duration::from_secs(5400))
+        );
+
+        let argv = ["gdrive", "files", "list", "--deadline", "30m1h"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn watch_command() {
+        match parse(&["watch", "photos", "/backup/photos"]).command {
+            Command::Watch {
+                local_dir,
+                remote_path,
+                propagate_deletes,
+                dry_run,
+                debounce,
+            } => {
+                assert_eq!(local_dir, std::path::PathBuf::from("photos"));
+                assert_eq!(remote_path, "/backup/photos");
+                assert!(!propagate_deletes);
+                assert!(!dry_run);
+                assert_eq!(debounce, 2);
+            }
+            _ => panic!("expected watch"),
+        }
+
+        let cli = parse(&[
+            "watch",
+            "photos",
+            "/backup",
+            "--propagate-deletes",
+            "--dry-run",
+            "--debounce",
+            "5",
+        ]);
+        match cli.command {
+            Command::Watch {
+                propagate_deletes,
+                dry_run,
+                debounce,
+                ..
+            } => {
+                assert!(propagate_deletes);
+                assert!(dry_run);
+                assert_eq!(debounce, 5);
+            }
+            _ => panic!("expected watch"),
+        }
+    }
+
+    #[test]
+    fn upload_if_newer_flag() {
+        match parse(&["files", "upload", "--recursive", "photos", "--if-newer"]).command {
+            Command::Files {
+                command: FileCommand::Upload { if_newer, .. },
+            } => assert!(if_newer),
+            _ => panic!("expected files upload"),
+        }
+
+        match parse(&["files", "upload", "a.txt"]).command {
+            Command::Files {
+                command: FileCommand::Upload { if_newer, .. },
+            } => assert!(!if_newer),
+            _ => panic!("expected files upload"),
+        }
+    }
+
+    #[test]
+    fn download_filter_flags() {
+        let cli = parse(&[
+            "files",
+            "download",
+            "id1",
+            "--recursive",
+            "--max-depth",
+            "2",
+            "--include",
+            "*.csv",
+            "--include",
+            "reports/**",
+            "--exclude",
+            "tmp",
+        ]);
+        match cli.command {
+            Command::Files {
+                command:
+                    FileCommand::Download {
+                        max_depth,
+                        include,
+                        exclude,
+                        ..
+                    },
+            } => {
+                assert_eq!(max_depth, Some(2));
+                let include: Vec<String> = include.iter().map(|p| p.to_string()).collect();
+                assert_eq!(include, ["*.csv", "reports/**"]);
+                assert_eq!(exclude.len(), 1);
+            }
+            _ => panic!("expected files download"),
+        }
+
+        let argv = ["gdrive", "files", "download", "id1", "--include", "*.csv"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn account_add_client_secret() {
+        use crate::AccountCommand;
+
+        let cli = parse(&["account", "add", "--client-secret", "client_secret.json"]);
+        match cli.command {
+            Command::Account {
+                command: AccountCommand::Add { client_secret, .. },
+            } => assert_eq!(
+                client_secret,
+                Some(std::path::PathBuf::from("client_secret.json"))
+            ),
+            _ => panic!("expected account add"),
+        }
+
+        match parse(&["account", "add"]).command {
+            Command::Account {
+                command: AccountCommand::Add { client_secret, .. },
+            } => assert!(client_secret.is_none()),
+            _ => panic!("expected account add"),
+        }
+    }
+
+    #[test]
+    fn move_verify_flag() {
+        for flag in ["--verify", "--after-copy-verify"] {
+            match parse(&["files", "move", "id1", "folder1", flag]).command {
+                Command::Files {
+                    command: FileCommand::Move { verify, .. },
+                } => assert!(verify),
+                _ => panic!("expected files move"),
+            }
+        }
+
+        match parse(&["files", "move", "id1", "folder1"]).command {
+            Command::Files {
+                command: FileCommand::Move { verify, .. },
+            } => assert!(!verify),
+            _ => panic!("expected files move"),
+        }
+    }
+
+    #[test]
+    fn drives_list_flags() {
+        use crate::drives::list::DriveSort;
+        use crate::DriveCommand;
+
+        let cli = parse(&[
+            "drives",
+            "list",
+            "--name-contains",
+            "team",
+            "--sort",
+            "created",
+            "--json",
+            "--skip-header",
+        ]);
+        match cli.command {
+            Command::Drives {
+                command:
+                    DriveCommand::List {
+                        name_contains,
+                        sort,
+                        json,
+                        skip_header,
+                        ..
+                    },
+            } => {
+                assert_eq!(name_contains, Some(String::from("team")));
+                assert_eq!(sort, Some(DriveSort::Created));
+                assert!(json);
+                assert!(skip_header);
+            }
+            _ => panic!("expected drives list"),
+        }
+
+        let argv = ["gdrive", "drives", "list", "--sort", "size"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn list_empty_folders() {
+        match parse(&["files", "list", "--parent", "/Archive", "--empty-folders"]).command {
+            Command::Files {
+                command:
+                    FileCommand::List {
+                        empty_folders,
+                        parent,
+                        ..
+                    },
+            } => {
+                assert!(empty_folders);
+                assert_eq!(parent.as_deref(), Some("/Archive"));
+            }
+            _ => panic!("unexpected command"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "list", "--empty-folders"]).is_err());
+        assert!(Cli::try_parse_from([
+            "gdrive",
+            "files",
+            "list",
+            "--parent",
+            "abc",
+            "--empty-folders",
+            "--find-duplicates",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn account_export_encrypt() {
+        use crate::AccountCommand;
+
+        match parse(&["account", "export", "personal", "--encrypt"]).command {
+            Command::Account {
+                command: AccountCommand::Export { encrypt, .. },
+            } => assert!(encrypt),
+            _ => panic!("expected account export"),
+        }
+
+        let argv = [
+            "gdrive",
+            "account",
+            "export",
+            "personal",
+            "--encrypt",
+            "--stdout",
+        ];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn version_and_about_json() {
+        assert!(matches!(
+            parse(&["version", "--json"]).command,
+            Command::Version { json: true }
+        ));
+        assert!(matches!(
+            parse(&["version"]).command,
+            Command::Version { json: false }
+        ));
+        assert!(matches!(
+            parse(&["about", "--json"]).command,
+            Command::About { json: true }
+        ));
+    }
+
+    #[test]
+    fn download_match_path() {
+        let cli = parse(&[
+            "files",
+            "download",
+            "id1",
+            "--recursive",
+            "--include",
+            "*.jpg",
+            "--match-path",
+        ]);
+        match cli.command {
+            Command::Files {
+                command: FileCommand::Download { match_path, .. },
+            } => assert!(match_path),
+            _ => panic!("expected files download"),
+        }
+
+        let argv = ["gdrive", "files", "download", "id1", "--match-path"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[cfg(feature = "self-update")]
+    #[test]
+    fn self_update_flags() {
+        let cli = parse(&["update", "--check"]);
+        match cli.command {
+            Command::SelfUpdate { check, yes } => {
+                assert!(check);
+                assert!(!yes);
+            }
+            _ => panic!("expected update"),
+        }
+
+        assert!(matches!(
+            parse(&["update", "-y"]).command,
+            Command::SelfUpdate { yes: true, .. }
+        ));
+
+        let argv = ["gdrive", "update", "--check", "--yes"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn info_raw_fields() {
+        match parse(&["files", "info", "abc", "--raw", "--fields", "id,permissions(role)"]).command {
+            Command::Files {
+                command: FileCommand::Info { raw, fields, .. },
+            } => {
+                assert!(raw);
+                assert_eq!(fields.as_deref(), Some("id,permissions(role)"));
+            }
+            _ => panic!("unexpected command"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "info", "abc", "--fields", "id"]).is_err());
+        assert!(Cli::try_parse_from(["gdrive", "files", "info", "abc", "--raw", "--json"]).is_err());
+    }
+
+    #[test]
+    fn include_trashed_for_info_and_download() {
+        match parse(&["files", "info", "/old/report.pdf", "--include-trashed"]).command {
+            Command::Files {
+                command: FileCommand::Info { include_trashed, .. },
+            } => assert!(include_trashed),
+            _ => panic!("unexpected command"),
+        }
+
+        match parse(&["files", "download", "/old/report.pdf", "--include-trashed"]).command {
+            Command::Files {
+                command: FileCommand::Download { include_trashed, .. },
+            } => assert!(include_trashed),
+            _ => panic!("unexpected command"),
+        }
+    }
+
+    #[test]
+    fn list_resolve_shortcuts() {
+        for flag in ["--resolve-shortcuts", "--dereference-shortcuts"] {
+            match parse(&["files", "list", flag]).command {
+                Command::Files {
+                    command: FileCommand::List { resolve_shortcuts, .. },
+                } => assert!(resolve_shortcuts),
+                _ => panic!("unexpected command"),
+            }
+        }
+
+        let argv = ["gdrive", "files", "list", "--parent", "abc", "--find-duplicates", "--resolve-shortcuts"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn si_and_iec_size_units() {
+        match parse(&["files", "list", "--si"]).command {
+            Command::Files {
+                command: FileCommand::List { si, .. },
+            } => assert!(si),
+            _ => panic!("unexpected command"),
+        }
+
+        match parse(&["files", "info", "abc", "--iec"]).command {
+            Command::Files {
+                command: FileCommand::Info { si, iec, .. },
+            } => assert!(!si && iec),
+            _ => panic!("unexpected command"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "info", "abc", "--si", "--iec"]).is_err());
+    }
+
+    #[test]
+    fn download_as() {
+        let cli = parse(&["files", "download", "abc", "--as", "renamed.pdf"]);
+        match cli.command {
+            Command::Files {
+                command: FileCommand::Download { save_as, .. },
+            } => assert_eq!(save_as, Some(String::from("renamed.pdf"))),
+            _ => panic!("expected files download"),
+        }
+
+        let argv = ["gdrive", "files", "download", "abc", "--as", "x", "--recursive"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn revoke_by_email_or_domain() {
+        let cli = parse(&["permissions", "revoke", "/a.txt", "--email", "bob@example.com"]);
+        match cli.command {
+            Command::Permissions {
+                command: crate::PermissionCommand::Revoke { file_id, email, .. },
+            } => {
+                assert_eq!(file_id, "/a.txt");
+                assert_eq!(email, Some(String::from("bob@example.com")));
+            }
+            _ => panic!("expected permissions revoke"),
+        }
+
+        let argv = ["gdrive", "permissions", "revoke", "id", "--email", "a@b.c", "--all"];
+        assert!(Cli::try_parse_from(argv).is_err());
+
+        let argv = ["gdrive", "permissions", "revoke", "id", "--email", "a@b.c", "--domain", "b.c"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn revoke_dry_run_is_not_mutating() {
+        let argv = ["gdrive", "permissions", "revoke", "/a.txt", "--domain", "example.com", "--dry-run"];
+        let cli = Cli::try_parse_from(argv).unwrap();
+        assert_eq!(mutating_operation(&cli.command), None);
+
+        let argv = ["gdrive", "permissions", "revoke", "/a.txt", "--domain", "example.com"];
+        let cli = Cli::try_parse_from(argv).unwrap();
+        assert_eq!(mutating_operation(&cli.command), Some("revoke permissions"));
+    }
+
+    #[test]
+    fn copy_allow_same_parent() {
+        match parse(&["files", "copy", "f1", "d1", "--allow-same-parent"]).command {
+            Command::Files {
+                command: FileCommand::Copy {
+                    allow_same_parent, ..
+                },
+            } => assert!(allow_same_parent),
+            _ => panic!("expected files copy"),
+        }
+
+        match parse(&["files", "copy", "f1", "d1", "--no-metadata"]).command {
+            Command::Files {
+                command: FileCommand::Copy {
+                    allow_same_parent,
+                    no_metadata,
+                    ..
+                },
+            } => assert!(no_metadata && !allow_same_parent),
+            _ => panic!("expected files copy"),
+        }
+    }
+
+    #[test]
+    fn list_sort_and_reverse() {
+        match parse(&["files", "list", "--sort", "size", "--reverse"]).command {
+            Command::Files {
+                command: FileCommand::List {
+                    sort,
+                    reverse,
+                    order_by,
+                    ..
+                },
+            } => {
+                assert_eq!(sort, Some(crate::files::list::FileSort::Size));
+                assert!(reverse);
+                assert!(order_by.is_none());
+            }
+            _ => panic!("expected files list"),
+        }
+
+        let argv = ["gdrive", "files", "list", "--reverse"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn list_include_permissions() {
+        match parse(&["files", "list", "--include-permissions", "--json"]).command {
+            Command::Files {
+                command: FileCommand::List {
+                    include_permissions,
+                    json,
+                    ..
+                },
+            } => assert!(include_permissions && json),
+            _ => panic!("expected files list"),
+        }
+
+        let argv = ["gdrive", "files", "list", "--parent", "abc", "--find-duplicates", "--include-permissions"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn invalid_chunk_sizes_are_rejected_by_upload_and_update() {
+        for size in ["0", "100K", "8193", "abc", "-8"] {
+            let upload = ["gdrive", "files", "upload", "report.txt", "--chunk-size", size];
+            let update = ["gdrive", "files", "update", "abc123", "report.txt", "--chunk-size", size];
+
+            for argv in [&upload[..], &update[..]] {
+                let err = Cli::try_parse_from(argv).err().expect(size);
+                assert!(err.to_string().contains("not a valid chunk size"), "{}", err);
+            }
+        }
+    }
+
+    #[test]
+    fn dedupe_check_and_action() {
+        match parse(&["files", "upload", "a.txt", "--dedupe-check", "--dedupe-action", "shortcut"]).command {
+            Command::Files {
+                command: FileCommand::Upload {
+                    dedupe_check,
+                    dedupe_action,
+                    ..
+                },
+            } => {
+                assert!(dedupe_check);
+                assert_eq!(dedupe_action, crate::DedupeAction::Shortcut);
+            }
+            _ => panic!("expected files upload"),
+        }
+
+        match parse(&["push", "a.txt", "/backups/", "--dedupe-check"]).command {
+            Command::Push {
+                dedupe_check,
+                dedupe_action,
+                ..
+            } => assert!(dedupe_check && dedupe_action == crate::DedupeAction::Skip),
+            _ => panic!("expected push"),
+        }
+
+        assert!(Cli::try_parse_from(["gdrive", "files", "upload", "a.txt", "--dedupe-action", "skip"]).is_err());
+        assert!(Cli::try_parse_from(["gdrive", "files", "upload", "dir", "--recursive", "--dedupe-check"]).is_ok());
+    }
+
+    #[test]
+    fn download_depth() {
+        let cli = parse(&["files", "download", "id1", "--recursive", "--depth", "0"]);
+        match cli.command {
+            Command::Files {
+                command: FileCommand::Download { depth, .. },
+            } => assert_eq!(depth, Some(0)),
+            _ => panic!("expected files download"),
+        }
+
+        let argv = ["gdrive", "files", "download", "id1", "--depth", "1"];
+        assert!(Cli::try_parse_from(argv).is_err());
+
+        let argv = [
+            "gdrive",
+            "files",
+            "download",
+            "id1",
+            "--recursive",
+            "--depth",
+            "1",
+            "--max-depth",
+            "2",
+        ];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn account_remove_revoke_and_force() {
+        use crate::AccountCommand;
+
+        match parse(&["account", "remove", "me@example.com"]).command {
+            Command::Account {
+                command: AccountCommand::Remove { revoke, force, .. },
+            } => {
+                assert!(!revoke);
+                assert!(!force);
+            }
+            _ => panic!("expected account remove"),
+        }
+
+        match parse(&["account", "remove", "me@example.com", "--revoke", "--force"]).command {
+            Command::Account {
+                command:
+                    AccountCommand::Remove {
+                        account_name,
+                        revoke,
+                        force,
+                    },
+            } => {
+                assert_eq!(account_name, "me@example.com");
+                assert!(revoke);
+                assert!(force);
+            }
+            _ => panic!("expected account remove"),
+        }
+    }
+
+    #[test]
+    fn list_page_token() {
+        match parse(&["files", "list", "--page-token", "ABC123", "--show-token"]).command {
+            Command::Files {
+                command:
+                    FileCommand::List {
+                        page_token,
+                        show_token,
+                        ..
+                    },
+            } => {
+                assert_eq!(page_token.as_deref(), Some("ABC123"));
+                assert!(show_token);
+            }
+            _ => panic!("expected files list"),
+        }
+
+        let argv = [
+            "gdrive",
+            "files",
+            "list",
+            "--parent",
+            "1AbC",
+            "--empty-folders",
+            "--page-token",
+            "ABC123",
+        ];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn permissions_audit() {
+        use crate::permissions::audit::AuditFormat;
+        use crate::PermissionCommand;
+
+        match parse(&["permissions", "audit", "/Finance"]).command {
+            Command::Permissions {
+                command:
+                    PermissionCommand::Audit {
+                        folder_id,
+                        recursive,
+                        format,
+                        only_external,
+                        ..
+                    },
+            } => {
+                assert_eq!(folder_id, "/Finance");
+                assert!(!recursive);
+                assert_eq!(format, AuditFormat::Csv);
+                assert_eq!(only_external, None);
+            }
+            _ => panic!("expected permissions audit"),
+        }
+
+        let argv = [
+            "permissions",
+            "audit",
+            "/Finance",
+            "--recursive",
+            "--format",
+            "json",
+            "--only-external",
+            "example.com",
+        ];
+        match parse(&argv).command {
+            Command::Permissions {
+                command:
+                    PermissionCommand::Audit {
+                        recursive,
+                        format,
+                        only_external,
+                        ..
+                    },
+            } => {
+                assert!(recursive);
+                assert_eq!(format, AuditFormat::Json);
+                assert_eq!(only_external.as_deref(), Some("example.com"));
+            }
+            _ => panic!("expected permissions audit"),
+        }
+
+        assert!(mutating_operation(&parse(&["permissions", "audit", "1AbC"]).command).is_none());
+        assert!(
+            Cli::try_parse_from(["gdrive", "permissions", "audit", "1AbC", "--format", "xml"])
+                .is_err()
+        );
+    }
 }
@@ -1,9 +1,101 @@
+use serde::Serialize;
 use std::env::consts;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
 
-pub fn version() {
-    println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
-    println!("Commit: {}", env!("GIT_HASH"));
+// Build metadata captured by build.rs
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionInfo {
+    pub name: String,
+    pub version: String,
+    pub commit: String,
+    // None when git wasn't available to the build
+    pub dirty: Option<bool>,
+    pub build_date: String,
+    pub rustc: String,
+    pub target: String,
+    pub features: Vec<String>,
+    pub os: String,
+    pub arch: String,
+}
+
+impl VersionInfo {
+    pub fn current() -> VersionInfo {
+        VersionInfo {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            commit: env!("GIT_HASH").to_string(),
+            dirty: parse_dirty(env!("GIT_DIRTY")),
+            build_date: env!("BUILD_DATE").to_string(),
+            rustc: env!("BUILD_RUSTC").to_string(),
+            target: env!("BUILD_TARGET").to_string(),
+            features: parse_features(env!("BUILD_FEATURES")),
+            os: consts::OS.to_string(),
+            arch: consts::ARCH.to_string(),
+        }
+    }
+
+    pub fn commit_description(&self) -> String {
+        match self.dirty {
+            Some(true) => format!("{} (dirty)", self.commit),
+            _ => self.commit.clone(),
+        }
+    }
+}
+
+pub fn parse_dirty(value: &str) -> Option<bool> {
+    match value {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+pub fn parse_features(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .filter(|feature| !feature.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+pub fn version(json: bool) -> Result<(), Error> {
+    let info = VersionInfo::current();
+
+    if json {
+        let json = serde_json::to_string_pretty(&info).map_err(Error::SerializeJson)?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    println!("{} {}", info.name, info.version);
+    println!("Commit: {}", info.commit_description());
+    println!("Built: {}", info.build_date);
     println!("Rust: {}", rustc_version_runtime::version());
-    println!("Arch: {}", consts::ARCH);
-    println!("OS: {}", consts::OS);
+    println!("Target: {}", info.target);
+    println!("Arch: {}", info.arch);
+    println!("OS: {}", info.os);
+
+    if !info.features.is_empty() {
+        println!("Features: {}", info.features.join(", "));
+    }
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum Error {
+    SerializeJson(serde_json::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::SerializeJson(err) => write!(f, "Failed to serialize json: {}", err),
+        }
+    }
 }
@@ -0,0 +1,48 @@
+use gdrive::app_config;
+use gdrive::app_config::AppConfig;
+use gdrive::common::account_json::AccountJson;
+use gdrive::hub;
+
+#[test]
+fn account_json_round_trip_through_config_dir() {
+    let config_dir = tempfile::tempdir().unwrap();
+
+    let account_json = AccountJson {
+        account_name: String::from("ci@example.com"),
+        client_id: String::from("client-id"),
+        client_secret: String::from("client-secret"),
+        refresh_token: String::from("1//refresh-token"),
+        scopes: vec![],
+    };
+
+    let json = account_json.to_json().unwrap();
+    let imported = AccountJson::from_json(&json).unwrap();
+    let app_cfg = imported.save_in(config_dir.path()).unwrap();
+    app_config::switch_account(&app_cfg).unwrap();
+
+    // Resolve the account the same way hub_helper::get_hub does
+    let current = AppConfig::load_current_account_in(config_dir.path()).unwrap();
+    assert_eq!(current.account.name, "ci@example.com");
+    assert!(current.tokens_path().starts_with(config_dir.path()));
+    assert_eq!(
+        app_config::list_accounts_in(config_dir.path()).unwrap(),
+        vec!["ci@example.com"]
+    );
+
+    let secret = current.load_secret().unwrap();
+    assert_eq!(secret.client_id, "client-id");
+    assert_eq!(secret.client_secret, "client-secret");
+
+    let exported = AccountJson::from_account(&current).unwrap();
+    assert_eq!(exported.refresh_token, "1//refresh-token");
+    assert_eq!(exported.scopes, hub::SCOPES.to_vec());
+}
+
+#[test]
+fn account_json_requires_refresh_token() {
+    let result = AccountJson::from_json(
+        r#"{"account_name": "ci@example.com", "client_id": "id", "client_secret": "secret"}"#,
+    );
+
+    assert!(result.is_err());
+}
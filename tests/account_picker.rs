@@ -0,0 +1,41 @@
+use gdrive::common::account_picker;
+
+fn accounts() -> Vec<String> {
+    vec![
+        String::from("personal@example.com"),
+        String::from("work@example.com"),
+    ]
+}
+
+#[test]
+fn parse_choice_by_number() {
+    let accounts = accounts();
+
+    assert_eq!(
+        account_picker::parse_choice("2\n", &accounts),
+        Some(&String::from("work@example.com"))
+    );
+    assert_eq!(account_picker::parse_choice("0", &accounts), None);
+    assert_eq!(account_picker::parse_choice("3", &accounts), None);
+}
+
+#[test]
+fn parse_choice_by_name() {
+    let accounts = accounts();
+
+    assert_eq!(
+        account_picker::parse_choice(" personal@example.com ", &accounts),
+        Some(&String::from("personal@example.com"))
+    );
+    assert_eq!(account_picker::parse_choice("other@example.com", &accounts), None);
+    assert_eq!(account_picker::parse_choice("", &accounts), None);
+}
+
+#[test]
+fn not_interactive_error_lists_accounts() {
+    let message = account_picker::Error::NotInteractive(accounts()).to_string();
+
+    assert!(message.contains("  personal@example.com\n"));
+    assert!(message.contains("  work@example.com\n"));
+    assert!(message.contains("gdrive account switch"));
+}
@@ -0,0 +1,35 @@
+use gdrive::account::remove;
+use gdrive::common::account_json::AccountJson;
+use std::fs;
+
+#[test]
+fn revoke_request_body_encodes_the_token() {
+    assert_eq!(
+        remove::revoke_request_body("1//0gAb-c_d.e~f+g="),
+        "token=1%2F%2F0gAb-c_d.e~f%2Bg%3D"
+    );
+}
+
+#[test]
+fn accounts_without_a_refresh_token_have_nothing_to_revoke() {
+    let config_dir = tempfile::tempdir().unwrap();
+
+    let account_json = AccountJson {
+        account_name: String::from("ci@example.com"),
+        client_id: String::from("client-id"),
+        client_secret: String::from("client-secret"),
+        refresh_token: String::from("1//refresh-token"),
+        scopes: vec![],
+    };
+    let app_cfg = account_json.save_in(config_dir.path()).unwrap();
+
+    assert_eq!(
+        remove::stored_refresh_token(&app_cfg).unwrap().as_deref(),
+        Some("1//refresh-token")
+    );
+
+    let tokens = r#"[{"scopes": [], "token": {"access_token": "ya29.token", "refresh_token": null, "expires_at": null, "id_token": null}}]"#;
+    fs::write(app_cfg.tokens_path(), tokens).unwrap();
+
+    assert_eq!(remove::stored_refresh_token(&app_cfg).unwrap(), None);
+}
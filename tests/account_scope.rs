@@ -0,0 +1,58 @@
+use gdrive::app_config::AccountScope;
+use gdrive::hub;
+use gdrive::hub::Hub;
+use gdrive::hub::HubConfig;
+
+#[test]
+fn parses_scope_names() {
+    assert_eq!("drive".parse::<AccountScope>(), Ok(AccountScope::Full));
+    assert_eq!("drive.file".parse::<AccountScope>(), Ok(AccountScope::File));
+    assert!("drive.readonly".parse::<AccountScope>().is_err());
+    assert_eq!(AccountScope::File.to_string(), "drive.file");
+}
+
+#[test]
+fn saved_scope_uses_scope_names() {
+    let json = serde_json::to_string(&AccountScope::File).unwrap();
+    assert_eq!(json, r#""drive.file""#);
+    assert_eq!(
+        serde_json::from_str::<AccountScope>(r#""drive""#).unwrap(),
+        AccountScope::Full
+    );
+}
+
+#[test]
+fn narrow_scope_only_requests_drive_file() {
+    assert_eq!(AccountScope::File.oauth_scopes(), &[hub::FILE_SCOPE]);
+    assert_eq!(AccountScope::Full.oauth_scopes(), hub::SCOPES);
+    assert_eq!(AccountScope::default(), AccountScope::Full);
+}
+
+#[test]
+fn extra_scopes_are_requested_after_the_account_scopes() {
+    let config = HubConfig {
+        account_scope: AccountScope::File,
+        extra_scopes: vec![hub::APP_DATA_SCOPE, hub::FILE_SCOPE],
+        ..HubConfig::default()
+    };
+
+    assert_eq!(
+        config.requested_scopes(),
+        vec![hub::FILE_SCOPE, hub::APP_DATA_SCOPE]
+    );
+    assert_eq!(HubConfig::default().requested_scopes(), hub::SCOPES);
+}
+
+#[test]
+fn hubs_keep_the_scope_of_their_account() {
+    let file_config = HubConfig {
+        account_scope: AccountScope::File,
+        ..HubConfig::default()
+    };
+
+    let file_hub = Hub::with_root_url_and_config("http://127.0.0.1:1", "token", &file_config);
+    let full_hub = Hub::with_root_url("http://127.0.0.1:1", "token");
+
+    assert_eq!(file_hub.account_scope(), AccountScope::File);
+    assert_eq!(full_hub.account_scope(), AccountScope::Full);
+}
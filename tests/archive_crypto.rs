@@ -0,0 +1,38 @@
+use gdrive::common::archive_crypto;
+
+const CONTENT: &[u8] = b"account archive";
+
+#[test]
+fn encrypted_content_is_recognized() {
+    let encrypted = archive_crypto::encrypt(CONTENT, "secret").unwrap();
+
+    assert!(archive_crypto::is_encrypted(&encrypted));
+    assert!(!archive_crypto::is_encrypted(CONTENT));
+    assert!(!archive_crypto::is_encrypted(b""));
+}
+
+#[test]
+fn decrypting_with_the_passphrase_restores_the_content() {
+    let encrypted = archive_crypto::encrypt(CONTENT, "secret").unwrap();
+    assert_ne!(encrypted, CONTENT);
+
+    let decrypted = archive_crypto::decrypt(&encrypted, "secret").unwrap();
+    assert_eq!(decrypted, CONTENT);
+
+    assert!(archive_crypto::decrypt(&encrypted, "wrong").is_err());
+}
+
+#[test]
+fn encrypted_files_are_recognized_by_their_header() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let encrypted_path = dir.path().join("account.tar.age");
+    let encrypted = archive_crypto::encrypt(CONTENT, "secret").unwrap();
+    std::fs::write(&encrypted_path, encrypted).unwrap();
+    assert!(archive_crypto::is_encrypted_file(&encrypted_path).unwrap());
+
+    // Shorter than the header
+    let plain_path = dir.path().join("account.tar");
+    std::fs::write(&plain_path, b"tar").unwrap();
+    assert!(!archive_crypto::is_encrypted_file(&plain_path).unwrap());
+}
@@ -0,0 +1,123 @@
+use gdrive::common::delegate::parse_retry_after;
+use gdrive::common::delegate::Backoff;
+use gdrive::common::delegate::BackoffConfig;
+use gdrive::common::delegate::ChunkSize;
+use gdrive::common::delegate::UploadDelegateConfig;
+use gdrive::common::delegate::UploadMode;
+use google_drive3::chrono;
+use google_drive3::hyper::http;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::time::Duration;
+
+fn config() -> BackoffConfig {
+    BackoffConfig {
+        max_retry_duration: Duration::from_secs(600),
+        min_sleep: Duration::from_secs(1),
+        max_sleep: Duration::from_secs(60),
+    }
+}
+
+fn seeded_backoff(seed: u64) -> Backoff {
+    Backoff::with_rng(config(), StdRng::seed_from_u64(seed))
+}
+
+#[test]
+fn backoff_is_deterministic_for_a_seed() {
+    let mut a = seeded_backoff(42);
+    let mut b = seeded_backoff(42);
+
+    for _ in 0..10 {
+        assert_eq!(
+            a.next_sleep(Duration::ZERO, None),
+            b.next_sleep(Duration::ZERO, None)
+        );
+    }
+}
+
+#[test]
+fn backoff_sleep_stays_within_exponential_cap() {
+    let mut backoff = seeded_backoff(7);
+
+    for attempt in 0..20u32 {
+        let cap = Duration::from_secs(2u64.pow(attempt.min(10))).min(Duration::from_secs(60));
+        let sleep = backoff.next_sleep(Duration::ZERO, None).unwrap();
+        assert!(sleep <= cap, "attempt {}: {:?} > {:?}", attempt, sleep, cap);
+    }
+}
+
+#[test]
+fn reset_starts_over_with_the_shortest_sleep() {
+    let mut backoff = seeded_backoff(5);
+
+    for _ in 0..10 {
+        backoff.next_sleep(Duration::ZERO, None);
+    }
+    backoff.reset();
+
+    let sleep = backoff.next_sleep(Duration::ZERO, None).unwrap();
+    assert!(sleep <= Duration::from_secs(1), "{:?}", sleep);
+}
+
+#[test]
+fn backoff_uses_retry_after() {
+    let mut backoff = seeded_backoff(1);
+    let sleep = backoff.next_sleep(Duration::ZERO, Some(Duration::from_secs(120)));
+    assert_eq!(sleep, Some(Duration::from_secs(120)));
+}
+
+#[test]
+fn backoff_gives_up_when_retry_budget_is_spent() {
+    let mut backoff = seeded_backoff(3);
+
+    assert_eq!(backoff.next_sleep(Duration::from_secs(601), None), None);
+    assert_eq!(
+        backoff.next_sleep(Duration::from_secs(590), Some(Duration::from_secs(30))),
+        None
+    );
+}
+
+#[test]
+fn retry_after_seconds_and_date() {
+    let now = chrono::DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z")
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+
+    let mut headers = http::HeaderMap::new();
+    assert_eq!(parse_retry_after(&headers, now), None);
+
+    headers.insert(http::header::RETRY_AFTER, "30".parse().unwrap());
+    assert_eq!(
+        parse_retry_after(&headers, now),
+        Some(Duration::from_secs(30))
+    );
+
+    headers.insert(
+        http::header::RETRY_AFTER,
+        "Wed, 21 Oct 2015 07:30:00 GMT".parse().unwrap(),
+    );
+    assert_eq!(
+        parse_retry_after(&headers, now),
+        Some(Duration::from_secs(120))
+    );
+}
+
+#[test]
+fn flags_only_change_the_max_retry_duration() {
+    let config = UploadDelegateConfig::from_flags(
+        ChunkSize::default(),
+        Duration::from_secs(600),
+        false,
+        false,
+        None,
+        UploadMode::Auto,
+    );
+
+    let default = BackoffConfig::default();
+    assert_eq!(
+        config.backoff_config.max_retry_duration,
+        Duration::from_secs(600)
+    );
+    assert_eq!(config.backoff_config.min_sleep, default.min_sleep);
+    assert_eq!(config.backoff_config.max_sleep, default.max_sleep);
+}
@@ -0,0 +1,139 @@
+use gdrive::common::byte_size;
+use gdrive::common::byte_size::ByteSize;
+use gdrive::common::byte_size::SizeUnits;
+use gdrive::common::delegate::ChunkSize;
+use gdrive::common::delegate::ChunkTuner;
+use rand::Rng;
+
+#[test]
+fn parse_raw_bytes_and_units() {
+    assert_eq!(byte_size::parse("1048576"), Ok(1048576));
+    assert_eq!(byte_size::parse("512K"), Ok(512 * 1024));
+    assert_eq!(byte_size::parse("8MiB"), Ok(8 * 1024 * 1024));
+    assert_eq!(byte_size::parse("1.5G"), Ok(1536 * 1024 * 1024));
+    assert_eq!(byte_size::parse("2gb"), Ok(2 * 1024 * 1024 * 1024));
+    assert_eq!(byte_size::parse("100B"), Ok(100));
+}
+
+#[test]
+fn parse_sizes_over_4_gib() {
+    assert_eq!(byte_size::parse("5G"), Ok(5 * (1 << 30)));
+    assert_eq!(byte_size::parse("4.5T"), Ok(9 * (1 << 39)));
+    assert_eq!(byte_size::parse("18446744073709551615"), Ok(u64::MAX));
+}
+
+#[test]
+fn parse_rejects_invalid_sizes() {
+    assert!(byte_size::parse("").is_err());
+    assert!(byte_size::parse("M").is_err());
+    assert!(byte_size::parse("1.5").is_err());
+    assert!(byte_size::parse("1.").is_err());
+    assert!(byte_size::parse("1.2.3K").is_err());
+    assert!(byte_size::parse("10X").is_err());
+    assert!(byte_size::parse("-5M").is_err());
+    assert!(byte_size::parse("18446744073709551616").is_err());
+    assert!(byte_size::parse("16384P").is_err());
+}
+
+#[test]
+fn display_uses_largest_exact_unit() {
+    assert_eq!(ByteSize(0).to_string(), "0");
+    assert_eq!(ByteSize(1000).to_string(), "1000");
+    assert_eq!(ByteSize(512 * 1024).to_string(), "512K");
+    assert_eq!(ByteSize(1536 * 1024 * 1024).to_string(), "1536M");
+    assert_eq!(ByteSize(5 * (1 << 30)).to_string(), "5G");
+}
+
+#[test]
+fn display_round_trips_through_parse() {
+    let mut rng = rand::thread_rng();
+
+    for _ in 0..10_000 {
+        // Shifting covers small sizes and sizes that are exact multiples of a unit
+        let bytes = rng.gen::<u64>() >> rng.gen_range(0, 64) << rng.gen_range(0, 51);
+        let size = ByteSize(bytes);
+
+        assert_eq!(size.to_string().parse::<ByteSize>(), Ok(size), "{}", bytes);
+    }
+}
+
+#[test]
+fn chunk_size_accepts_mb_and_units() {
+    let mb = 1024 * 1024;
+
+    assert_eq!(ChunkSize::default().in_bytes(), 32 * mb);
+    assert_eq!("8".parse::<ChunkSize>().unwrap().in_bytes(), 8 * mb);
+    assert_eq!("10".parse::<ChunkSize>().unwrap().in_bytes(), 10 * mb);
+    assert_eq!("512K".parse::<ChunkSize>().unwrap().in_bytes(), 512 * 1024);
+    assert_eq!("8192M".parse::<ChunkSize>().unwrap().in_bytes(), 8192 * mb);
+    assert_eq!("1.5M".parse::<ChunkSize>().unwrap().to_string(), "1536K");
+
+    assert!("0".parse::<ChunkSize>().is_err());
+    assert!("100K".parse::<ChunkSize>().is_err());
+    assert!("8193".parse::<ChunkSize>().is_err());
+    assert!("16G".parse::<ChunkSize>().is_err());
+}
+
+#[test]
+fn chunk_size_auto_tunes() {
+    let chunk_size = "auto".parse::<ChunkSize>().unwrap();
+    assert_eq!(chunk_size.to_string(), "auto");
+    assert_eq!(chunk_size.in_bytes(), 8 * 1024 * 1024);
+
+    let tuner = match &chunk_size {
+        ChunkSize::Auto(tuner) => tuner.clone(),
+        ChunkSize::Fixed(_) => panic!("expected auto chunk size"),
+    };
+
+    assert_eq!(tuner.record_success(), None);
+    assert_eq!(tuner.record_success(), None);
+    assert_eq!(tuner.record_success(), Some(16 * 1024 * 1024));
+
+    // Clones share the state
+    assert_eq!(chunk_size.in_bytes(), 16 * 1024 * 1024);
+
+    // A failure halves the size and resets the run of successes
+    assert_eq!(tuner.record_failure(), Some(8 * 1024 * 1024));
+    assert_eq!(tuner.record_success(), None);
+    assert_eq!(tuner.record_success(), None);
+    assert_eq!(tuner.record_success(), Some(16 * 1024 * 1024));
+}
+
+#[test]
+fn chunk_tuner_stays_within_limits() {
+    let tuner = ChunkTuner::new();
+
+    for _ in 0..100 {
+        tuner.record_success();
+    }
+    assert_eq!(tuner.chunk_size(), 256 * 1024 * 1024);
+    assert_eq!(tuner.record_success(), None);
+
+    for _ in 0..100 {
+        tuner.record_failure();
+    }
+    assert_eq!(tuner.chunk_size(), 256 * 1024);
+    assert_eq!(tuner.record_failure(), None);
+}
+
+#[test]
+fn human_sizes_in_decimal_units() {
+    let si = |bytes: u64| byte_size::format_human_in(bytes, SizeUnits::Si);
+
+    assert_eq!(si(0), "0 B");
+    assert_eq!(si(999), "999 B");
+    assert_eq!(si(1500), "1.5 kB");
+    assert_eq!(si(1_000_000), "1 MB");
+    // Rounds up into the next unit instead of showing 1000 kB
+    assert_eq!(si(999_950), "1 MB");
+    assert_eq!(si(2_500_000_000), "2.5 GB");
+}
+
+#[test]
+fn binary_units_are_the_default() {
+    assert_eq!(SizeUnits::default(), SizeUnits::Iec);
+    assert_eq!(
+        byte_size::format_human_in(1536u64, SizeUnits::Iec),
+        byte_size::format_human(1536u64)
+    );
+}
@@ -0,0 +1,56 @@
+use gdrive::common::cancel;
+use gdrive::common::cancel::InProgress;
+use gdrive::common::cancel::PartialFile;
+use gdrive::common::temp_copy::TempCopy;
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn partial_files_are_removed_until_complete() {
+    let dir = tempfile::tempdir().unwrap();
+    let incomplete = dir.path().join("report.incomplete");
+    let complete = dir.path().join("notes.incomplete");
+    fs::write(&incomplete, "partial").unwrap();
+    fs::write(&complete, "done").unwrap();
+
+    let _incomplete_guard = PartialFile::new(&incomplete);
+    drop(PartialFile::new(&complete));
+
+    cancel::remove_partial_files();
+
+    assert!(!incomplete.exists());
+    assert!(complete.exists());
+}
+
+#[test]
+fn cancel_is_remembered() {
+    assert!(!cancel::is_cancelled());
+    cancel::cancel();
+    assert!(cancel::is_cancelled());
+}
+
+#[test]
+fn in_progress_files_are_tracked_until_done() {
+    let path = Path::new("videos/holiday.mp4");
+
+    let guard = InProgress::new(path);
+    assert!(cancel::in_progress().contains(&path.to_path_buf()));
+
+    drop(guard);
+    assert!(!cancel::in_progress().contains(&path.to_path_buf()));
+}
+
+#[test]
+fn temp_copies_are_removed_when_cancelled() {
+    let dir = tempfile::tempdir().unwrap();
+    let src = dir.path().join("report.txt");
+    fs::write(&src, "report").unwrap();
+
+    let copy = TempCopy::create(&src, "renamed.txt", false).unwrap();
+    assert!(copy.path().exists());
+
+    cancel::remove_partial_files();
+
+    assert!(!copy.path().exists());
+    assert!(src.exists());
+}
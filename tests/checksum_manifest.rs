@@ -0,0 +1,54 @@
+use gdrive::common::checksum_manifest::ChecksumManifest;
+use std::path::Path;
+
+#[test]
+fn checksum_lines_use_md5sum_format() {
+    let mut manifest = ChecksumManifest::default();
+    manifest.push_checksum(Path::new("photos/cat.jpg"), "d41d8cd98f00b204e9800998ecf8427e");
+    manifest.push_checksum(Path::new("notes.txt"), "0cc175b9c0f1b6a831c399e269772661");
+
+    assert_eq!(
+        manifest.to_text(),
+        "d41d8cd98f00b204e9800998ecf8427e  photos/cat.jpg\n0cc175b9c0f1b6a831c399e269772661  notes.txt\n"
+    );
+}
+
+#[test]
+fn checksum_line_escapes_special_characters() {
+    let mut manifest = ChecksumManifest::default();
+    manifest.push_checksum(Path::new("a\\b\nc.txt"), "0cc175b9c0f1b6a831c399e269772661");
+
+    assert_eq!(
+        manifest.to_text(),
+        "\\0cc175b9c0f1b6a831c399e269772661  a\\\\b\\nc.txt\n"
+    );
+}
+
+#[test]
+fn skipped_files_are_comments() {
+    let mut manifest = ChecksumManifest::default();
+    assert!(manifest.is_empty());
+
+    manifest.push_skipped(Path::new("docs/Budget"), "google document without checksum, not downloaded");
+
+    assert!(!manifest.is_empty());
+    assert_eq!(
+        manifest.to_text(),
+        "# docs/Budget: google document without checksum, not downloaded\n"
+    );
+}
+
+#[test]
+fn save_writes_manifest() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("sums.md5");
+
+    let mut manifest = ChecksumManifest::default();
+    manifest.push_checksum(Path::new("notes.txt"), "0cc175b9c0f1b6a831c399e269772661");
+    manifest.save(&path).unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "0cc175b9c0f1b6a831c399e269772661  notes.txt\n"
+    );
+}
@@ -0,0 +1,57 @@
+use gdrive::common::client_secret;
+
+#[test]
+fn desktop_app_credentials_are_read() {
+    let json = r#"{
+        "installed": {
+            "client_id": "123-abc.apps.googleusercontent.com",
+            "project_id": "gdrive-backup",
+            "auth_uri": "https://accounts.google.com/o/oauth2/auth",
+            "token_uri": "https://oauth2.googleapis.com/token",
+            "client_secret": "GOCSPX-secret",
+            "redirect_uris": ["http://localhost"]
+        }
+    }"#;
+
+    let secret = client_secret::parse(json).unwrap();
+    assert_eq!(secret.client_id, "123-abc.apps.googleusercontent.com");
+    assert_eq!(secret.client_secret, "GOCSPX-secret");
+}
+
+#[test]
+fn web_app_credentials_are_read() {
+    let json =
+        r#"{"web": {"client_id": "456-def.apps.googleusercontent.com", "client_secret": "s"}}"#;
+
+    let secret = client_secret::parse(json).unwrap();
+    assert_eq!(secret.client_id, "456-def.apps.googleusercontent.com");
+}
+
+#[test]
+fn invalid_shapes_are_rejected() {
+    assert!(matches!(
+        client_secret::parse(r#"{"client_id": "123", "client_secret": "s"}"#),
+        Err(client_secret::Error::MissingClient)
+    ));
+    assert!(matches!(
+        client_secret::parse(r#"{"installed": {"client_id": "123"}}"#),
+        Err(client_secret::Error::MissingField("client_secret"))
+    ));
+    assert!(matches!(
+        client_secret::parse(r#"{"installed": {"client_id": " ", "client_secret": "s"}}"#),
+        Err(client_secret::Error::MissingField("client_id"))
+    ));
+    assert!(matches!(
+        client_secret::parse("not json"),
+        Err(client_secret::Error::Deserialize(_))
+    ));
+}
+
+#[test]
+fn missing_file_names_the_path() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("client_secret.json");
+
+    let err = client_secret::load(&path).unwrap_err();
+    assert!(err.to_string().contains("client_secret.json"));
+}
@@ -0,0 +1,98 @@
+// Shared by the integration tests, each test crate only uses part of it
+#![allow(dead_code)]
+
+use std::sync::Arc;
+use std::sync::Mutex;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::net::TcpListener;
+
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    // Path and query string
+    pub target: String,
+    pub body: String,
+}
+
+impl Request {
+    pub fn path(&self) -> &str {
+        self.target.split('?').next().unwrap_or_default()
+    }
+}
+
+// Every request the server answered, in order
+pub type Requests = Arc<Mutex<Vec<Request>>>;
+
+// Minimal http server answering one request per connection with the status and json body
+// returned by respond. Returns the url to point the hubs at.
+pub async fn mock_drive<F>(respond: F) -> (String, Requests)
+where
+    F: Fn(&Request) -> (u16, String) + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let root_url = format!("http://{}", listener.local_addr().unwrap());
+    let requests: Requests = Arc::new(Mutex::new(Vec::new()));
+    let recorded = requests.clone();
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut reader = BufReader::new(stream);
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).await.unwrap();
+
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).await.unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).await.unwrap();
+
+            let mut parts = request_line.split_whitespace();
+            let request = Request {
+                method: parts.next().unwrap_or_default().to_string(),
+                target: parts.next().unwrap_or_default().to_string(),
+                body: String::from_utf8_lossy(&body).to_string(),
+            };
+
+            let (status, response_body) = respond(&request);
+            recorded.lock().unwrap().push(request);
+
+            let response = format!(
+                "HTTP/1.1 {} Mock\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                status,
+                response_body.len(),
+                response_body
+            );
+
+            let mut stream = reader.into_inner();
+            stream.write_all(response.as_bytes()).await.unwrap();
+            stream.shutdown().await.unwrap();
+        }
+    });
+
+    (root_url, requests)
+}
+
+pub fn not_found() -> (u16, String) {
+    (
+        404,
+        String::from(
+            r#"{"error": {"errors": [{"reason": "notFound", "message": "File not found"}], "code": 404, "message": "File not found"}}"#,
+        ),
+    )
+}
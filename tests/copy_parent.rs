@@ -0,0 +1,28 @@
+use gdrive::files::copy::is_in_folder;
+use google_drive3::api::File;
+
+fn file(parents: &[&str]) -> File {
+    File {
+        id: Some(String::from("f1")),
+        parents: Some(parents.iter().map(|parent| parent.to_string()).collect()),
+        ..Default::default()
+    }
+}
+
+fn folder(id: &str) -> File {
+    File {
+        id: Some(id.to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn a_parent_of_the_file_is_the_same_folder() {
+    assert!(is_in_folder(&file(&["a", "b"]), &folder("b")));
+}
+
+#[test]
+fn other_folders_are_not() {
+    assert!(!is_in_folder(&file(&["a"]), &folder("b")));
+    assert!(!is_in_folder(&File::default(), &folder("a")));
+}
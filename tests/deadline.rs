@@ -0,0 +1,61 @@
+use gdrive::common::cancel;
+use gdrive::common::deadline;
+use gdrive::common::deadline::Deadline;
+use std::time::Duration;
+
+fn seconds(s: &str) -> Result<u64, String> {
+    s.parse::<Deadline>()
+        .map(|deadline| deadline.duration().as_secs())
+}
+
+#[test]
+fn parse_seconds_and_units() {
+    assert_eq!(seconds("90"), Ok(90));
+    assert_eq!(seconds("90s"), Ok(90));
+    assert_eq!(seconds("15m"), Ok(900));
+    assert_eq!(seconds("2h"), Ok(7200));
+    assert_eq!(seconds("1h30m"), Ok(5400));
+    assert_eq!(seconds("1h0m5s"), Ok(3605));
+}
+
+#[test]
+fn parse_rejects_invalid_deadlines() {
+    assert!(seconds("").is_err());
+    assert!(seconds("0").is_err());
+    assert!(seconds("0s").is_err());
+    assert!(seconds("m").is_err());
+    assert!(seconds("10x").is_err());
+    assert!(seconds("1h30").is_err());
+    assert!(seconds("30m1h").is_err());
+    assert!(seconds("1m1m").is_err());
+    assert!(seconds("-5m").is_err());
+    assert!(seconds("99999999999999999999").is_err());
+}
+
+#[test]
+fn display_uses_every_non_zero_unit() {
+    let display = |s: &str| s.parse::<Deadline>().unwrap().to_string();
+
+    assert_eq!(display("90"), "1m30s");
+    assert_eq!(display("15m"), "15m");
+    assert_eq!(display("5400"), "1h30m");
+    assert_eq!(display("3605"), "1h5s");
+}
+
+#[tokio::test]
+async fn passed_deadline_stops_the_command_after_the_current_item() {
+    let deadline: Deadline = "1s".parse().unwrap();
+    let mut items = 0;
+
+    // Stands in for a recursive operation checking for cancellation between items
+    let command = async {
+        while !cancel::is_cancelled() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            items += 1;
+        }
+    };
+    deadline::run(deadline, command).await;
+
+    assert!(deadline::is_expired());
+    assert!(items > 0);
+}
@@ -0,0 +1,84 @@
+use gdrive::files::dedup;
+use gdrive::files::dedup::Keep;
+use google_drive3::api::File;
+use google_drive3::chrono::TimeZone;
+use google_drive3::chrono::Utc;
+use std::str::FromStr;
+
+const FOLDER: &str = "application/vnd.google-apps.folder";
+
+fn file(id: &str, name: &str, mime: &str, created_day: u32) -> File {
+    File {
+        id: Some(id.to_string()),
+        name: Some(name.to_string()),
+        mime_type: Some(mime.to_string()),
+        md5_checksum: (mime != FOLDER).then(|| format!("md5-{}", name)),
+        size: (mime != FOLDER).then_some(10),
+        created_time: Some(Utc.with_ymd_and_hms(2024, 1, created_day, 0, 0, 0).unwrap()),
+        ..Default::default()
+    }
+}
+
+fn ids(files: &[File]) -> Vec<&str> {
+    files.iter().filter_map(|file| file.id.as_deref()).collect()
+}
+
+fn files() -> Vec<File> {
+    vec![
+        file("b", "report.txt", "text/plain", 2),
+        file("a", "report.txt", "text/plain", 1),
+        file("c", "report.txt", "text/plain", 3),
+        file("f1", "photos", FOLDER, 5),
+        file("f2", "photos", FOLDER, 4),
+        file("p", "photos", "image/jpeg", 1),
+        file("u", "unique.txt", "text/plain", 1),
+    ]
+}
+
+#[test]
+fn oldest_file_of_each_group_is_kept() {
+    let groups = dedup::plan(&files(), false, Keep::Oldest);
+
+    assert_eq!(groups.len(), 2);
+
+    // The photos file isn't grouped with the photos folders
+    assert_eq!(groups[0].key, "photos");
+    assert_eq!(groups[0].keep.id.as_deref(), Some("f2"));
+    assert_eq!(ids(&groups[0].trash), vec!["f1"]);
+
+    assert_eq!(groups[1].key, "report.txt");
+    assert_eq!(groups[1].keep.id.as_deref(), Some("a"));
+    assert_eq!(ids(&groups[1].trash), vec!["b", "c"]);
+}
+
+#[test]
+fn newest_file_can_be_kept() {
+    let groups = dedup::plan(&files(), false, Keep::Newest);
+
+    assert_eq!(groups[1].keep.id.as_deref(), Some("c"));
+    assert_eq!(ids(&groups[1].trash), vec!["b", "a"]);
+}
+
+#[test]
+fn identical_content_is_grouped_by_md5() {
+    let mut files = files();
+    files.push(File {
+        name: Some(String::from("copy of report.txt")),
+        id: Some(String::from("d")),
+        ..file("d", "report.txt", "text/plain", 9)
+    });
+
+    let groups = dedup::plan(&files, true, Keep::Oldest);
+
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].key, "md5-report.txt");
+    assert_eq!(ids(&groups[0].trash), vec!["b", "c", "d"]);
+}
+
+#[test]
+fn keep_syntax() {
+    assert_eq!(Keep::from_str("oldest").unwrap(), Keep::Oldest);
+    assert_eq!(Keep::from_str("newest").unwrap(), Keep::Newest);
+    assert!(Keep::from_str("largest").is_err());
+    assert_eq!(Keep::default().to_string(), "oldest");
+}
@@ -0,0 +1,187 @@
+mod common;
+
+use assert_fs::prelude::*;
+use gdrive::files::dedupe_check;
+use gdrive::files::dedupe_check::ChecksumIndex;
+use gdrive::files::dedupe_check::Decision;
+use gdrive::files::dedupe_check::DedupeAction;
+use gdrive::hub::Hub;
+use google_drive3::api::File;
+use google_drive3::chrono::TimeZone;
+use google_drive3::chrono::Utc;
+
+const MD5: &str = "5d41402abc4b2a76b9719d911017c592";
+
+fn file(id: &str, md5: &str, size: i64, created_day: u32) -> File {
+    File {
+        id: Some(id.to_string()),
+        name: Some(format!("{}.txt", id)),
+        mime_type: Some(String::from("text/plain")),
+        md5_checksum: Some(md5.to_string()),
+        size: Some(size),
+        created_time: Some(Utc.with_ymd_and_hms(2024, 1, created_day, 0, 0, 0).unwrap()),
+        ..Default::default()
+    }
+}
+
+fn decided_id(decision: &Decision) -> Option<&str> {
+    match decision {
+        Decision::Upload => None,
+        Decision::Skip(file) | Decision::Shortcut(file) => file.id.as_deref(),
+    }
+}
+
+#[test]
+fn without_matches_the_file_is_uploaded() {
+    for action in [
+        DedupeAction::Skip,
+        DedupeAction::Shortcut,
+        DedupeAction::Upload,
+    ] {
+        assert!(matches!(
+            dedupe_check::decide(&[], action),
+            Decision::Upload
+        ));
+    }
+}
+
+#[test]
+fn the_oldest_match_is_skipped_to_or_linked() {
+    let matches = vec![
+        file("b", MD5, 5, 3),
+        file("a", MD5, 5, 1),
+        file("c", MD5, 5, 2),
+    ];
+
+    let skip = dedupe_check::decide(&matches, DedupeAction::Skip);
+    assert!(matches!(skip, Decision::Skip(_)));
+    assert_eq!(decided_id(&skip), Some("a"));
+
+    let shortcut = dedupe_check::decide(&matches, DedupeAction::Shortcut);
+    assert!(matches!(shortcut, Decision::Shortcut(_)));
+    assert_eq!(decided_id(&shortcut), Some("a"));
+
+    assert!(matches!(
+        dedupe_check::decide(&matches, DedupeAction::Upload),
+        Decision::Upload
+    ));
+}
+
+#[test]
+fn matches_created_at_the_same_time_are_ordered_by_id() {
+    let matches = vec![file("z", MD5, 5, 1), file("m", MD5, 5, 1)];
+
+    let decision = dedupe_check::decide(&matches, DedupeAction::Skip);
+
+    assert_eq!(decided_id(&decision), Some("m"));
+}
+
+#[test]
+fn only_files_with_the_same_checksum_and_size_match() {
+    let folder = File {
+        mime_type: Some(String::from("application/vnd.google-apps.folder")),
+        ..file("folder", MD5, 5, 1)
+    };
+    let document = File {
+        md5_checksum: None,
+        size: None,
+        ..file("doc", MD5, 5, 1)
+    };
+    let files = vec![
+        file("same", MD5, 5, 1),
+        file("other-content", "0cc175b9c0f1b6a831c399e269772661", 5, 1),
+        file("other-size", MD5, 6, 1),
+        folder,
+        document,
+    ];
+
+    let matches = dedupe_check::same_content(files, MD5, 5);
+
+    let ids: Vec<_> = matches
+        .iter()
+        .filter_map(|file| file.id.as_deref())
+        .collect();
+    assert_eq!(ids, ["same"]);
+}
+
+#[test]
+fn local_md5_matches_drive_checksums() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let local = dir.child("hello.txt");
+    local.write_str("hello").unwrap();
+
+    assert_eq!(dedupe_check::local_md5(local.path()).unwrap(), MD5);
+}
+
+#[test]
+fn index_finds_files_with_the_content_of_a_local_file() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let local = dir.child("hello.txt");
+    local.write_str("hello").unwrap();
+
+    let index = ChecksumIndex::new(vec![
+        file("same", MD5, 5, 1),
+        file("other-size", MD5, 6, 1),
+        file("other-content", "0cc175b9c0f1b6a831c399e269772661", 5, 1),
+    ]);
+
+    let matches = index.find(local.path(), 5).unwrap();
+    let ids: Vec<_> = matches
+        .iter()
+        .filter_map(|file| file.id.as_deref())
+        .collect();
+    assert_eq!(ids, ["same"]);
+}
+
+#[test]
+fn files_added_to_the_index_match_later_files() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let local = dir.child("copy.txt");
+    local.write_str("hello").unwrap();
+
+    let mut index = ChecksumIndex::default();
+    assert!(index.find(local.path(), 5).unwrap().is_empty());
+
+    index.add(file("uploaded", MD5, 5, 1));
+    let matches = index.find(local.path(), 5).unwrap();
+    assert_eq!(
+        decided_id(&dedupe_check::decide(&matches, DedupeAction::Skip)),
+        Some("uploaded")
+    );
+}
+
+#[tokio::test]
+async fn index_only_lists_files_owned_by_the_user() {
+    let (root_url, requests) = common::mock_drive(|_| {
+        (
+            200,
+            format!(
+                r#"{{"files": [{{"id": "mine", "name": "a.txt", "mimeType": "text/plain", "size": "5", "md5Checksum": "{}"}}]}}"#,
+                MD5
+            ),
+        )
+    })
+    .await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    ChecksumIndex::build(&hub).await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert!(
+        requests[0].target.contains("%27me%27+in+owners"),
+        "{}",
+        requests[0].target
+    );
+}
+
+#[test]
+fn dedupe_action_from_str() {
+    assert_eq!("skip".parse::<DedupeAction>(), Ok(DedupeAction::Skip));
+    assert_eq!(
+        "shortcut".parse::<DedupeAction>(),
+        Ok(DedupeAction::Shortcut)
+    );
+    assert_eq!("upload".parse::<DedupeAction>(), Ok(DedupeAction::Upload));
+    assert!("link".parse::<DedupeAction>().is_err());
+}
@@ -0,0 +1,51 @@
+use gdrive::common::fake_drive::FakeDrive;
+use gdrive::common::remote_walk::ApiLister;
+use gdrive::files::delete;
+
+fn paths(level: &[delete::Entry]) -> Vec<&str> {
+    let mut paths: Vec<&str> = level.iter().map(|entry| entry.path.as_str()).collect();
+    paths.sort();
+    paths
+}
+
+#[tokio::test]
+async fn folders_are_grouped_by_depth() {
+    let drive = FakeDrive::new();
+    let photos = drive.add_path("photos");
+    let y2024 = drive.add_path("photos/2024");
+    drive.add_path("photos/2024/summer");
+    drive.add_file(&photos, "cover.jpg", b"cover");
+    drive.add_file(&y2024, "beach.jpg", b"beach");
+
+    let folder = drive.file(&photos).unwrap();
+    let levels = delete::collect_levels(&ApiLister(&drive), &folder)
+        .await
+        .unwrap();
+
+    assert_eq!(levels.len(), 3);
+    assert_eq!(paths(&levels[0]), ["photos"]);
+    assert_eq!(paths(&levels[1]), ["photos/2024", "photos/cover.jpg"]);
+    assert_eq!(
+        paths(&levels[2]),
+        ["photos/2024/beach.jpg", "photos/2024/summer"]
+    );
+    assert!(levels[1]
+        .iter()
+        .any(|entry| entry.id == y2024 && entry.is_directory));
+}
+
+#[tokio::test]
+async fn trashed_files_are_left_out() {
+    let drive = FakeDrive::new();
+    let photos = drive.add_path("photos");
+    let old = drive.add_file(&photos, "old.jpg", b"old");
+    drive.add_file(&photos, "new.jpg", b"new");
+    drive.trash(&old);
+
+    let folder = drive.file(&photos).unwrap();
+    let levels = delete::collect_levels(&ApiLister(&drive), &folder)
+        .await
+        .unwrap();
+
+    assert_eq!(paths(&levels[1]), ["photos/new.jpg"]);
+}
@@ -0,0 +1,191 @@
+use gdrive::app_config::AccountScope;
+use gdrive::common::drive_error;
+use gdrive::common::drive_error::QuotaError;
+
+fn drive_error_body(reason: &str) -> serde_json::Value {
+    serde_json::from_str(&format!(
+        r#"{{
+            "error": {{
+                "errors": [
+                    {{
+                        "domain": "usageLimits",
+                        "reason": "{}",
+                        "message": "Quota exceeded"
+                    }}
+                ],
+                "code": 403,
+                "message": "Quota exceeded"
+            }}
+        }}"#,
+        reason
+    ))
+    .unwrap()
+}
+
+#[test]
+fn storage_quota_exceeded() {
+    let body = drive_error_body("storageQuotaExceeded");
+    assert_eq!(
+        drive_error::quota_error_from_json(&body),
+        Some(QuotaError::StorageQuotaExceeded)
+    );
+
+    let err = google_drive3::Error::BadRequest(body);
+    assert_eq!(
+        drive_error::quota_error(&err),
+        Some(QuotaError::StorageQuotaExceeded)
+    );
+}
+
+#[test]
+fn rate_limits_exceeded() {
+    let user_limit = drive_error::quota_error_from_json(&drive_error_body("userRateLimitExceeded"));
+    assert_eq!(user_limit, Some(QuotaError::UserRateLimitExceeded));
+    assert!(user_limit.unwrap().is_rate_limit());
+
+    let limit = drive_error::quota_error_from_json(&drive_error_body("rateLimitExceeded"));
+    assert_eq!(limit, Some(QuotaError::RateLimitExceeded));
+}
+
+#[test]
+fn other_errors_are_not_quota_errors() {
+    assert_eq!(
+        drive_error::quota_error_from_json(&drive_error_body("notFound")),
+        None
+    );
+    assert_eq!(
+        drive_error::quota_error_from_json(&serde_json::json!({"error": "invalid_grant"})),
+        None
+    );
+    assert_eq!(
+        drive_error::quota_error(&google_drive3::Error::Cancelled),
+        None
+    );
+}
+
+#[test]
+fn insufficient_scope() {
+    let err = google_drive3::Error::BadRequest(drive_error_body("insufficientPermissions"));
+    assert!(drive_error::is_insufficient_scope(&err));
+
+    let err = google_drive3::Error::BadRequest(drive_error_body("storageQuotaExceeded"));
+    assert!(!drive_error::is_insufficient_scope(&err));
+}
+
+#[test]
+fn file_scope_hint_only_for_narrow_accounts() {
+    let forbidden = google_drive3::Error::BadRequest(drive_error_body("insufficientPermissions"));
+    let not_found = google_drive3::Error::BadRequest(drive_error_body("notFound"));
+    let quota = google_drive3::Error::BadRequest(drive_error_body("storageQuotaExceeded"));
+
+    assert_eq!(
+        drive_error::file_scope_hint(&forbidden, AccountScope::File),
+        Some(drive_error::FILE_SCOPE_HINT)
+    );
+    assert_eq!(
+        drive_error::file_scope_hint(&not_found, AccountScope::File),
+        Some(drive_error::FILE_SCOPE_HINT)
+    );
+    assert_eq!(drive_error::file_scope_hint(&quota, AccountScope::File), None);
+    assert_eq!(drive_error::file_scope_hint(&forbidden, AccountScope::Full), None);
+}
+
+// Body Drive sends when the token was granted without a scope the request needs
+fn scope_error_body() -> serde_json::Value {
+    serde_json::json!({
+        "error": {
+            "code": 403,
+            "message": "Request had insufficient authentication scopes.",
+            "errors": [{"message": "Insufficient Permission", "domain": "global", "reason": "insufficientPermissions"}],
+            "status": "PERMISSION_DENIED",
+            "details": [{
+                "@type": "type.googleapis.com/google.rpc.ErrorInfo",
+                "reason": "ACCESS_TOKEN_SCOPE_INSUFFICIENT",
+                "domain": "googleapis.com"
+            }]
+        }
+    })
+}
+
+#[test]
+fn scope_errors_are_classified() {
+    assert!(drive_error::is_insufficient_scope_json(&scope_error_body()));
+
+    let details_only = serde_json::json!({
+        "error": {
+            "code": 403,
+            "details": [{"reason": "ACCESS_TOKEN_SCOPE_INSUFFICIENT"}]
+        }
+    });
+    assert!(drive_error::is_insufficient_scope_json(&details_only));
+
+    let file_permission = serde_json::json!({
+        "error": {
+            "code": 403,
+            "errors": [{"reason": "insufficientFilePermissions"}]
+        }
+    });
+    assert!(!drive_error::is_insufficient_scope_json(&file_permission));
+
+    let not_found = serde_json::json!({
+        "error": {"code": 404, "errors": [{"reason": "notFound"}]}
+    });
+    assert!(!drive_error::is_insufficient_scope_json(&not_found));
+}
+
+#[test]
+fn scope_errors_are_recognized_in_messages() {
+    let err = google_drive3::Error::BadRequest(scope_error_body());
+    let message = format!("Failed to create file: {}", err);
+    assert!(drive_error::is_insufficient_scope_message(&message));
+
+    let err = google_drive3::Error::BadRequest(drive_error_body("storageQuotaExceeded"));
+    assert!(!drive_error::is_insufficient_scope_message(&err.to_string()));
+}
+
+#[test]
+fn export_size_limit_exceeded() {
+    let err = google_drive3::Error::BadRequest(drive_error_body("exportSizeLimitExceeded"));
+    assert!(drive_error::is_export_size_limit_exceeded(&err));
+
+    let err = google_drive3::Error::BadRequest(drive_error_body("storageQuotaExceeded"));
+    assert!(!drive_error::is_export_size_limit_exceeded(&err));
+}
+
+// Messages of failed token requests with a revoked or expired refresh token
+const INVALID_GRANT_MESSAGES: [&str; 3] = [
+    "Failed to get access token: invalid_grant: Token has been expired or revoked.",
+    "Failed to list files: Token retrieval failed with error: invalid_grant: Bad Request",
+    r#"Failed to get file: Server error: {"error": "invalid_grant", "error_description": "Token has been expired or revoked."}"#,
+];
+
+#[test]
+fn invalid_grant_is_recognized_in_messages() {
+    for message in INVALID_GRANT_MESSAGES {
+        assert!(
+            drive_error::is_invalid_grant_message(message),
+            "{}",
+            message
+        );
+    }
+}
+
+#[test]
+fn other_auth_errors_are_not_invalid_grant() {
+    let messages = [
+        "Failed to get access token: invalid_client: The OAuth client was not found.",
+        "Failed to get access token: access_denied",
+        "Failed to get file: Request had insufficient authentication scopes.",
+    ];
+
+    for message in messages {
+        assert!(
+            !drive_error::is_invalid_grant_message(message),
+            "{}",
+            message
+        );
+    }
+
+    let err = google_drive3::Error::BadRequest(scope_error_body());
+    assert!(!drive_error::is_invalid_grant_message(&err.to_string()));
+}
@@ -0,0 +1,59 @@
+use gdrive::drives::list::name_query;
+use gdrive::drives::list::sort_drives;
+use gdrive::drives::list::DriveSort;
+use google_drive3::api::Drive;
+use google_drive3::chrono::TimeZone;
+use google_drive3::chrono::Utc;
+
+fn drive(name: &str, created_year: i32) -> Drive {
+    Drive {
+        id: Some(format!("id-{}", name)),
+        name: Some(name.to_string()),
+        created_time: Some(Utc.with_ymd_and_hms(created_year, 1, 1, 0, 0, 0).unwrap()),
+        ..Default::default()
+    }
+}
+
+fn names(drives: &[Drive]) -> Vec<String> {
+    drives
+        .iter()
+        .map(|drive| drive.name.clone().unwrap_or_default())
+        .collect()
+}
+
+#[test]
+fn name_query_escapes_quotes() {
+    assert_eq!(name_query("team"), "name contains 'team'");
+    assert_eq!(name_query("bob's"), "name contains 'bob\\'s'");
+}
+
+#[test]
+fn sort_by_name_ignores_case() {
+    let mut drives = vec![
+        drive("beta", 2020),
+        drive("Alpha", 2022),
+        drive("gamma", 2021),
+    ];
+    sort_drives(&mut drives, &DriveSort::Name);
+
+    assert_eq!(names(&drives), ["Alpha", "beta", "gamma"]);
+}
+
+#[test]
+fn sort_by_created_puts_the_oldest_first() {
+    let mut drives = vec![
+        drive("beta", 2020),
+        drive("Alpha", 2022),
+        drive("gamma", 2021),
+    ];
+    sort_drives(&mut drives, &DriveSort::Created);
+
+    assert_eq!(names(&drives), ["beta", "gamma", "Alpha"]);
+}
+
+#[test]
+fn drive_sort_from_str() {
+    assert_eq!("name".parse::<DriveSort>(), Ok(DriveSort::Name));
+    assert_eq!("created".parse::<DriveSort>(), Ok(DriveSort::Created));
+    assert!("size".parse::<DriveSort>().is_err());
+}
@@ -0,0 +1,65 @@
+use gdrive::files::duplicates;
+
+fn file(id: &str, name: &str, md5: Option<&str>) -> google_drive3::api::File {
+    google_drive3::api::File {
+        id: Some(id.to_string()),
+        name: Some(name.to_string()),
+        mime_type: Some(String::from("text/plain")),
+        size: Some(3),
+        md5_checksum: md5.map(String::from),
+        ..Default::default()
+    }
+}
+
+fn ids(group: &duplicates::DuplicateGroup) -> Vec<&str> {
+    group.files.iter().map(|file| file.id.as_str()).collect()
+}
+
+#[test]
+fn files_sharing_a_name_are_grouped() {
+    let files = vec![
+        file("1", "report.txt", Some("aaa")),
+        file("2", "notes.txt", Some("aaa")),
+        file("3", "report.txt", Some("bbb")),
+        file("4", "photos", None),
+        file("5", "photos", None),
+    ];
+
+    let groups = duplicates::duplicate_groups(&files, false);
+
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0].name.as_deref(), Some("photos"));
+    assert_eq!(ids(&groups[0]), vec!["4", "5"]);
+    assert_eq!(groups[1].name.as_deref(), Some("report.txt"));
+    assert_eq!(ids(&groups[1]), vec!["1", "3"]);
+}
+
+#[test]
+fn files_with_the_same_content_are_grouped_by_md5() {
+    let files = vec![
+        file("1", "report.txt", Some("aaa")),
+        file("2", "notes.txt", Some("aaa")),
+        file("3", "report.txt", Some("bbb")),
+        file("4", "photos", None),
+        file("5", "photos", None),
+    ];
+
+    let groups = duplicates::duplicate_groups(&files, true);
+
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].md5_checksum.as_deref(), Some("aaa"));
+    assert_eq!(groups[0].name, None);
+    assert_eq!(ids(&groups[0]), vec!["1", "2"]);
+}
+
+#[test]
+fn duplicate_groups_as_json() {
+    let files = vec![file("1", "a.txt", Some("aaa")), file("2", "a.txt", None)];
+    let groups = duplicates::duplicate_groups(&files, false);
+
+    let json = serde_json::to_value(&groups).unwrap();
+    assert_eq!(json[0]["name"], "a.txt");
+    assert!(json[0].get("md5Checksum").is_none());
+    assert_eq!(json[0]["files"][0]["md5Checksum"], "aaa");
+    assert_eq!(json[0]["files"][1]["id"], "2");
+}
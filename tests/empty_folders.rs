@@ -0,0 +1,54 @@
+use gdrive::common::fake_drive::FakeDrive;
+use gdrive::common::remote_walk::ApiLister;
+use gdrive::files::empty_folders;
+use gdrive::files::empty_folders::EmptyFolder;
+
+// archive holds 2019 (empty), 2020 with a file and old with an empty folder tmp in it
+fn drive() -> (FakeDrive, String) {
+    let drive = FakeDrive::new();
+    let archive = drive.add_path("archive");
+    let y2020 = drive.add_path("archive/2020");
+    drive.add_path("archive/2019");
+    drive.add_path("archive/old/tmp");
+    drive.add_file(&archive, "readme.txt", b"readme");
+    drive.add_file(&y2020, "report.pdf", b"report");
+    (drive, archive)
+}
+
+#[tokio::test]
+async fn only_folders_without_children_are_found() {
+    let (drive, archive) = drive();
+    // Looks up the existing folders
+    let y2019 = drive.add_path("archive/2019");
+    let tmp = drive.add_path("archive/old/tmp");
+
+    let folders = empty_folders::find_empty_folders(&ApiLister(&drive), &archive)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        folders,
+        [
+            EmptyFolder {
+                id: y2019,
+                path: String::from("2019"),
+            },
+            EmptyFolder {
+                id: tmp,
+                path: String::from("old/tmp"),
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn the_starting_folder_is_never_reported() {
+    let (drive, _) = drive();
+    let y2019 = drive.add_path("archive/2019");
+
+    let folders = empty_folders::find_empty_folders(&ApiLister(&drive), &y2019)
+        .await
+        .unwrap();
+
+    assert!(folders.is_empty());
+}
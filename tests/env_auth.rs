@@ -0,0 +1,73 @@
+use gdrive::common::env_auth;
+use gdrive::common::env_auth::Credentials;
+use std::collections::HashMap;
+
+fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let vars: HashMap<String, String> = vars
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    move |name| vars.get(name).cloned()
+}
+
+#[test]
+fn no_refresh_token_uses_saved_accounts() {
+    let credentials = env_auth::credentials_from(None, env(&[("GDRIVE_CLIENT_ID", "id")]));
+    assert_eq!(credentials.unwrap(), None);
+}
+
+#[test]
+fn refresh_token_from_env() {
+    let credentials = env_auth::credentials_from(
+        None,
+        env(&[
+            ("GDRIVE_REFRESH_TOKEN", "token\n"),
+            ("GDRIVE_CLIENT_ID", "id"),
+            ("GDRIVE_CLIENT_SECRET", "secret"),
+        ]),
+    );
+
+    assert_eq!(
+        credentials.unwrap(),
+        Some(Credentials {
+            client_id: String::from("id"),
+            client_secret: String::from("secret"),
+            refresh_token: String::from("token"),
+        })
+    );
+}
+
+#[test]
+fn stdin_token_takes_precedence() {
+    let credentials = env_auth::credentials_from(
+        Some(String::from("from-stdin")),
+        env(&[
+            ("GDRIVE_REFRESH_TOKEN", "from-env"),
+            ("GDRIVE_CLIENT_ID", "id"),
+            ("GDRIVE_CLIENT_SECRET", "secret"),
+        ]),
+    );
+
+    assert_eq!(credentials.unwrap().unwrap().refresh_token, "from-stdin");
+}
+
+#[test]
+fn client_credentials_are_required() {
+    let err = env_auth::credentials_from(
+        None,
+        env(&[
+            ("GDRIVE_REFRESH_TOKEN", "token"),
+            ("GDRIVE_CLIENT_ID", "id"),
+        ]),
+    )
+    .unwrap_err();
+
+    assert!(err.to_string().contains("GDRIVE_CLIENT_SECRET"));
+}
+
+#[test]
+fn refresh_token_is_the_first_line() {
+    let token = env_auth::read_refresh_token("  token \nrest\n".as_bytes()).unwrap();
+    assert_eq!(token, "token");
+    assert!(env_auth::read_refresh_token("\n".as_bytes()).is_err());
+}
@@ -0,0 +1,60 @@
+use gdrive::common::delegate;
+use google_drive3::chrono::TimeZone;
+use google_drive3::chrono::Utc;
+use google_drive3::client::Chunk;
+use google_drive3::client::ContentRange;
+use std::fs;
+
+#[test]
+fn line_has_time_range_and_message() {
+    let time = Utc.with_ymd_and_hms(2024, 5, 1, 10, 0, 0).unwrap();
+    let chunk = ContentRange {
+        range: Some(Chunk {
+            first: 0,
+            last: 262143,
+        }),
+        total_length: 1048576,
+    };
+
+    let line = delegate::error_log_line(time, Some(&chunk), "Aborting upload. Status code: 400");
+
+    assert_eq!(
+        line,
+        "2024-05-01T10:00:00+00:00\t0-262143 of 1048576\tAborting upload. Status code: 400"
+    );
+}
+
+#[test]
+fn line_without_a_chunk_has_no_range() {
+    let time = Utc.with_ymd_and_hms(2024, 5, 1, 10, 0, 0).unwrap();
+
+    let line = delegate::error_log_line(time, None, "Failed");
+
+    assert_eq!(line, "2024-05-01T10:00:00+00:00\t-\tFailed");
+}
+
+#[test]
+fn lines_are_appended_until_truncated() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("errors.log");
+
+    delegate::append_error_log(&path, "first").unwrap();
+    delegate::append_error_log(&path, "second").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "first\nsecond\n");
+
+    delegate::truncate_error_log(&path).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "");
+
+    delegate::append_error_log(&path, "third").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "third\n");
+}
+
+#[test]
+fn truncate_creates_a_missing_log() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("errors.log");
+
+    delegate::truncate_error_log(&path).unwrap();
+
+    assert!(path.exists());
+}
@@ -0,0 +1,142 @@
+use gdrive::common::drive_file::DocType;
+use gdrive::files::export;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+// Export links of a Google Doc as returned by files.get
+fn doc_export_links() -> HashMap<String, String> {
+    [
+        "application/rtf",
+        "application/vnd.oasis.opendocument.text",
+        "text/html",
+        "application/pdf",
+        "text/markdown",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "application/zip",
+        "text/plain",
+        "application/epub+zip",
+    ]
+    .iter()
+    .map(|mime| {
+        let link = format!(
+            "https://docs.google.com/feeds/download/documents/export/Export?id=doc1&mime={}",
+            mime
+        );
+        (mime.to_string(), link)
+    })
+    .collect()
+}
+
+fn export_mime(path: &str, links: &HashMap<String, String>) -> Result<String, export::Error> {
+    export::export_mime(&PathBuf::from(path), &DocType::Document, links)
+        .map(|mime| mime.essence_str().to_string())
+}
+
+#[test]
+fn extension_maps_to_an_export_link() {
+    let links = doc_export_links();
+
+    assert_eq!(export_mime("report.pdf", &links).unwrap(), "application/pdf");
+    assert_eq!(
+        export_mime("report.DOCX", &links).unwrap(),
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+    );
+    assert_eq!(export_mime("report.txt", &links).unwrap(), "text/plain");
+}
+
+#[test]
+fn extensions_without_a_built_in_format_are_guessed() {
+    assert_eq!(
+        export_mime("report.md", &doc_export_links()).unwrap(),
+        "text/markdown"
+    );
+}
+
+#[test]
+fn unsupported_format_lists_the_export_links() {
+    let err = export_mime("report.xlsx", &doc_export_links()).unwrap_err();
+
+    match &err {
+        export::Error::UnsupportedExportFormat {
+            extension,
+            supported,
+            ..
+        } => {
+            assert_eq!(extension, "xlsx");
+            assert!(supported.contains(&String::from("pdf")));
+            assert!(supported.contains(&String::from("docx")));
+            assert!(supported.contains(&String::from("epub")));
+            assert!(!supported.contains(&String::from("xlsx")));
+        }
+        _ => panic!("unexpected error: {}", err),
+    }
+
+    assert!(err.to_string().starts_with("This document can't be exported to 'xlsx'"));
+}
+
+#[test]
+fn built_in_formats_are_used_without_export_links() {
+    let links = HashMap::new();
+
+    assert_eq!(
+        export_mime("report.odt", &links).unwrap(),
+        "application/vnd.oasis.opendocument.text"
+    );
+    assert!(matches!(
+        export_mime("report.xlsx", &links),
+        Err(export::Error::UnsupportedExportExtension(_))
+    ));
+}
+
+#[test]
+fn missing_directories_of_the_export_path_are_created() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let file_path = tmp_dir.path().join("reports").join("2024").join("summary.pdf");
+
+    export::create_parent_dirs(&file_path).unwrap();
+    assert!(tmp_dir.path().join("reports").join("2024").is_dir());
+    assert!(!file_path.exists());
+
+    // Existing directories and bare file names are left as they are
+    export::create_parent_dirs(&file_path).unwrap();
+    export::create_parent_dirs(&PathBuf::from("summary.pdf")).unwrap();
+}
+
+#[test]
+fn export_path_below_a_file_fails() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let file = tmp_dir.path().join("reports");
+    std::fs::write(&file, "").unwrap();
+
+    let err = export::create_parent_dirs(&file.join("summary.pdf")).unwrap_err();
+    assert!(err.to_string().contains("Failed to create directory"));
+}
+
+#[test]
+fn export_link_for_the_requested_format() {
+    let links = doc_export_links();
+    let pdf: mime::Mime = "application/pdf".parse().unwrap();
+    let csv: mime::Mime = "text/csv".parse().unwrap();
+
+    assert_eq!(
+        export::export_link(&links, &pdf),
+        Some("https://docs.google.com/feeds/download/documents/export/Export?id=doc1&mime=application/pdf")
+    );
+    assert_eq!(export::export_link(&links, &csv), None);
+    assert_eq!(export::export_link(&HashMap::new(), &pdf), None);
+}
+
+#[test]
+fn default_extension_is_only_added_without_an_extension() {
+    let with_extension =
+        export::with_default_extension(&PathBuf::from("report.pdf"), &DocType::Document);
+    assert_eq!(with_extension, PathBuf::from("report.pdf"));
+
+    let without_extension =
+        export::with_default_extension(&PathBuf::from("budget"), &DocType::Spreadsheet);
+    assert_eq!(without_extension, PathBuf::from("budget.xlsx"));
+
+    // A leading dot doesn't start an extension
+    let dotfile = export::with_default_extension(&PathBuf::from(".slides"), &DocType::Presentation);
+    assert_eq!(dotfile, PathBuf::from(".slides.pptx"));
+}
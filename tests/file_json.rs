@@ -0,0 +1,53 @@
+use gdrive::common::file_json::FileJson;
+use std::path::Path;
+
+fn uploaded_file() -> google_drive3::api::File {
+    google_drive3::api::File {
+        id: Some(String::from("1AbC")),
+        name: Some(String::from("report.pdf")),
+        mime_type: Some(String::from("application/pdf")),
+        size: Some(5_368_709_120),
+        md5_checksum: Some(String::from("0cc175b9c0f1b6a831c399e269772661")),
+        web_view_link: Some(String::from(
+            "https://drive.google.com/file/d/1AbC/view?usp=drivesdk",
+        )),
+        parents: Some(vec![String::from("0AFolder")]),
+        ..google_drive3::api::File::default()
+    }
+}
+
+#[test]
+fn file_json() {
+    let json = serde_json::to_string(&FileJson::from(&uploaded_file())).unwrap();
+
+    assert_eq!(
+        json,
+        r#"{"id":"1AbC","name":"report.pdf","mimeType":"application/pdf","size":5368709120,"md5Checksum":"0cc175b9c0f1b6a831c399e269772661","webViewLink":"https://drive.google.com/file/d/1AbC/view?usp=drivesdk","parents":["0AFolder"]}"#
+    );
+}
+
+#[test]
+fn recursive_upload_json() {
+    let folder = google_drive3::api::File {
+        id: Some(String::from("1Dir")),
+        name: Some(String::from("docs")),
+        mime_type: Some(String::from("application/vnd.google-apps.folder")),
+        parents: Some(vec![String::from("0AFolder")]),
+        ..google_drive3::api::File::default()
+    };
+
+    let file = google_drive3::api::File {
+        parents: Some(vec![String::from("1Dir")]),
+        ..uploaded_file()
+    };
+
+    let uploaded = vec![
+        FileJson::from(&folder).with_path(Path::new("docs")),
+        FileJson::from(&file).with_path(Path::new("docs/report.pdf")),
+    ];
+
+    assert_eq!(
+        serde_json::to_string(&uploaded).unwrap(),
+        r#"[{"id":"1Dir","name":"docs","mimeType":"application/vnd.google-apps.folder","parents":["0AFolder"],"path":"docs"},{"id":"1AbC","name":"report.pdf","mimeType":"application/pdf","size":5368709120,"md5Checksum":"0cc175b9c0f1b6a831c399e269772661","webViewLink":"https://drive.google.com/file/d/1AbC/view?usp=drivesdk","parents":["1Dir"],"path":"docs/report.pdf"}]"#
+    );
+}
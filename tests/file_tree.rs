@@ -0,0 +1,115 @@
+mod common;
+
+use gdrive::common::delegate::UploadDelegateConfig;
+use gdrive::common::file_tree::FileKind;
+use gdrive::common::file_tree::FileTree;
+use gdrive::common::id_gen::IdGen;
+use gdrive::hub::Hub;
+use std::fs;
+
+#[test]
+fn directories_and_regular_files_are_not_special() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("a.txt");
+    let empty = dir.path().join("empty.txt");
+    fs::write(&file, "a").unwrap();
+    fs::write(&empty, "").unwrap();
+
+    assert_eq!(FileKind::from_path(dir.path()).unwrap(), FileKind::Directory);
+    assert_eq!(FileKind::from_path(&file).unwrap(), FileKind::Regular);
+    assert_eq!(FileKind::from_path(&empty).unwrap(), FileKind::Regular);
+    assert!(!FileKind::Directory.is_special());
+    assert!(!FileKind::Regular.is_special());
+}
+
+#[test]
+fn missing_path_is_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    assert!(FileKind::from_path(&dir.path().join("missing")).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks_are_followed() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("a.txt");
+    let link = dir.path().join("link.txt");
+    fs::write(&file, "a").unwrap();
+    std::os::unix::fs::symlink(&file, &link).unwrap();
+
+    assert_eq!(FileKind::from_path(&link).unwrap(), FileKind::Regular);
+}
+
+#[cfg(unix)]
+#[test]
+fn sockets_are_special() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("socket");
+    let _listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+
+    let kind = FileKind::from_path(&path).unwrap();
+    assert_eq!(kind, FileKind::Socket);
+    assert!(kind.is_special());
+    assert_eq!(kind.to_string(), "socket");
+}
+
+#[cfg(unix)]
+#[test]
+fn fifos_are_special() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("fifo");
+    let status = std::process::Command::new("mkfifo")
+        .arg(&path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let kind = FileKind::from_path(&path).unwrap();
+    assert_eq!(kind, FileKind::Fifo);
+    assert!(kind.is_special());
+}
+
+#[cfg(unix)]
+#[test]
+fn devices_are_special() {
+    let kind = FileKind::from_path(std::path::Path::new("/dev/null")).unwrap();
+    assert_eq!(kind, FileKind::CharDevice);
+    assert!(kind.is_special());
+}
+
+// Answers generateIds, the only request a tree build makes
+async fn id_hub() -> Hub {
+    let (root_url, _) = common::mock_drive(|_| {
+        let ids: Vec<String> = (0..1000).map(|i| format!("\"id{}\"", i)).collect();
+        (200, format!(r#"{{"ids": [{}]}}"#, ids.join(", ")))
+    })
+    .await;
+
+    Hub::with_root_url(&root_url, "test-token")
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn unreadable_entries_are_kept_with_skip_unreadable() {
+    let dir = tempfile::tempdir().unwrap();
+    // The tree has canonical paths
+    let root = dir.path().canonicalize().unwrap().join("photos");
+    fs::create_dir_all(root.join("2024")).unwrap();
+    fs::write(root.join("2024/cat.jpg"), "cat").unwrap();
+    let dangling = root.join("2024/missing.jpg");
+    std::os::unix::fs::symlink(root.join("gone.jpg"), &dangling).unwrap();
+
+    let hub = id_hub().await;
+    let delegate_config = UploadDelegateConfig::default();
+
+    let mut ids = IdGen::new(&hub, &delegate_config);
+    assert!(FileTree::from_path(&root, &mut ids, false).await.is_err());
+
+    let mut ids = IdGen::new(&hub, &delegate_config);
+    let tree = FileTree::from_path(&root, &mut ids, true).await.unwrap();
+
+    let unreadable = tree.unreadable();
+    assert_eq!(unreadable.len(), 1);
+    assert_eq!(unreadable[0].path, dangling);
+    assert_eq!(tree.info().file_count, 1);
+}
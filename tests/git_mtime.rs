@@ -0,0 +1,77 @@
+use gdrive::common::git_mtime;
+use gdrive::common::git_mtime::GitMtimes;
+use google_drive3::chrono::DateTime;
+use google_drive3::chrono::Utc;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn utc(time: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc)
+}
+
+#[test]
+fn last_commit_wins() {
+    let log = "\u{1}2024-03-02T10:00:00+01:00\n\nsrc/main.rs\n\n\u{1}2023-01-01T00:00:00Z\n\nsrc/main.rs\nREADME.md\n";
+
+    let times = git_mtime::parse_log(log);
+
+    assert_eq!(times.len(), 2);
+    assert_eq!(times[&PathBuf::from("src/main.rs")], utc("2024-03-02T09:00:00Z"));
+    assert_eq!(times[&PathBuf::from("README.md")], utc("2023-01-01T00:00:00Z"));
+}
+
+fn git(dir: &Path, args: &[&str], date: &str) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .env("GIT_AUTHOR_DATE", date)
+        .env("GIT_COMMITTER_DATE", date)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn files_get_the_time_of_their_last_commit() {
+    if Command::new("git").arg("--version").output().is_err() {
+        return;
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path().canonicalize().unwrap().join("repo");
+    fs::create_dir_all(repo.join("src")).unwrap();
+
+    git(&repo, &["init", "-q"], "2024-01-01T00:00:00Z");
+    fs::write(repo.join("README.md"), "readme").unwrap();
+    fs::write(repo.join("src/main.rs"), "fn main() {}").unwrap();
+    git(&repo, &["add", "."], "2024-01-01T00:00:00Z");
+    git(&repo, &["commit", "-q", "-m", "first"], "2024-01-01T00:00:00Z");
+
+    fs::write(repo.join("src/main.rs"), "fn main() { }").unwrap();
+    git(&repo, &["commit", "-q", "-am", "second"], "2024-02-01T12:00:00Z");
+
+    fs::write(repo.join("untracked.txt"), "new").unwrap();
+
+    let mut mtimes = GitMtimes::default();
+
+    assert_eq!(
+        mtimes.commit_time(&repo.join("README.md")),
+        Some(utc("2024-01-01T00:00:00Z"))
+    );
+    assert_eq!(
+        mtimes.commit_time(&repo.join("src/main.rs")),
+        Some(utc("2024-02-01T12:00:00Z"))
+    );
+
+    // Untracked files and files outside a repository keep their filesystem mtime
+    let untracked = repo.join("untracked.txt");
+    assert_eq!(mtimes.commit_time(&untracked), None);
+    assert_eq!(
+        mtimes.modified_time(&untracked),
+        git_mtime::filesystem_mtime(&untracked)
+    );
+}
@@ -0,0 +1,52 @@
+use gdrive::common::drive_file::DocType;
+use gdrive::common::url_fetch::file_name_from_url;
+use gdrive::common::url_fetch::resolve_location;
+
+#[test]
+fn file_name_is_taken_from_url_path() {
+    assert_eq!(
+        file_name_from_url("https://example.com/exports/report.csv?token=abc"),
+        Some(String::from("report.csv"))
+    );
+    assert_eq!(file_name_from_url("https://example.com/"), None);
+    assert_eq!(file_name_from_url("https://example.com"), None);
+}
+
+#[test]
+fn relative_redirects_are_resolved_against_the_request_url() {
+    let url = "https://example.com/exports/report.csv?token=abc";
+
+    assert_eq!(
+        resolve_location(url, "https://cdn.example.org/report.csv").as_deref(),
+        Some("https://cdn.example.org/report.csv")
+    );
+    assert_eq!(
+        resolve_location(url, "//cdn.example.org/report.csv").as_deref(),
+        Some("https://cdn.example.org/report.csv")
+    );
+    assert_eq!(
+        resolve_location(url, "/files/report.csv").as_deref(),
+        Some("https://example.com/files/report.csv")
+    );
+    assert_eq!(
+        resolve_location(url, "v2/report.csv").as_deref(),
+        Some("https://example.com/exports/v2/report.csv")
+    );
+    assert_eq!(
+        resolve_location("https://example.com", "report.csv").as_deref(),
+        Some("https://example.com/report.csv")
+    );
+}
+
+#[test]
+fn doc_type_from_source_mime() {
+    let csv: mime::Mime = "text/csv; charset=utf-8".parse().unwrap();
+    assert_eq!(DocType::from_source_mime(&csv), Some(DocType::Spreadsheet));
+
+    let docx: mime::Mime = "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+        .parse()
+        .unwrap();
+    assert_eq!(DocType::from_source_mime(&docx), Some(DocType::Document));
+
+    assert_eq!(DocType::from_source_mime(&mime::APPLICATION_OCTET_STREAM), None);
+}
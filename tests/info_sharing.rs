@@ -0,0 +1,129 @@
+use gdrive::files::info;
+use google_drive3::api::File;
+use google_drive3::api::FileCapabilities;
+use google_drive3::api::Permission;
+use google_drive3::api::User;
+
+fn value<'a>(fields: &'a [info::Field], name: &str) -> Option<&'a str> {
+    fields
+        .iter()
+        .find(|field| field.name == name)
+        .and_then(|field| field.value.as_deref())
+}
+
+fn user(email: &str) -> User {
+    User {
+        email_address: Some(email.to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn capabilities_owner_and_permissions() {
+    let file = File {
+        capabilities: Some(FileCapabilities {
+            can_edit: Some(true),
+            can_share: Some(false),
+            can_delete: Some(false),
+            ..Default::default()
+        }),
+        owned_by_me: Some(false),
+        owners: Some(vec![user("owner@example.com")]),
+        sharing_user: Some(user("owner@example.com")),
+        permissions: Some(vec![
+            Permission {
+                type_: Some(String::from("user")),
+                email_address: Some(String::from("me@example.com")),
+                role: Some(String::from("writer")),
+                ..Default::default()
+            },
+            Permission {
+                type_: Some(String::from("anyone")),
+                role: Some(String::from("reader")),
+                ..Default::default()
+            },
+        ]),
+        ..Default::default()
+    };
+
+    let fields = info::sharing_fields(&file);
+
+    assert_eq!(value(&fields, "CanEdit"), Some("True"));
+    assert_eq!(value(&fields, "CanShare"), Some("False"));
+    assert_eq!(value(&fields, "CanDelete"), Some("False"));
+    assert_eq!(value(&fields, "Owner"), Some("owner@example.com"));
+    assert_eq!(value(&fields, "SharedBy"), Some("owner@example.com"));
+    assert_eq!(
+        value(&fields, "Permissions"),
+        Some("user me@example.com writer, anyone reader")
+    );
+}
+
+#[test]
+fn own_files_without_readable_permissions() {
+    let file = File {
+        owned_by_me: Some(true),
+        owners: Some(vec![user("me@example.com")]),
+        ..Default::default()
+    };
+
+    let fields = info::sharing_fields(&file);
+
+    assert_eq!(value(&fields, "Owner"), Some("me"));
+    assert_eq!(value(&fields, "Permissions"), None);
+    assert_eq!(value(&fields, "CanEdit"), None);
+}
+
+#[test]
+fn capability_fields_list_every_capability() {
+    let file = File {
+        capabilities: Some(FileCapabilities {
+            can_download: Some(true),
+            can_trash: Some(false),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let fields = info::capability_fields(&file);
+    let names: Vec<&str> = fields.iter().map(|field| field.name.as_str()).collect();
+
+    assert_eq!(
+        names,
+        [
+            "CanDownload",
+            "CanEdit",
+            "CanComment",
+            "CanCopy",
+            "CanRename",
+            "CanShare",
+            "CanTrash",
+            "CanDelete",
+            "CanMoveItemWithinDrive",
+            "CanAddChildren",
+            "CanListChildren",
+            "CanReadRevisions",
+        ]
+    );
+    assert_eq!(value(&fields, "CanDownload"), Some("True"));
+    assert_eq!(value(&fields, "CanTrash"), Some("False"));
+    assert_eq!(value(&fields, "CanEdit"), None);
+}
+
+#[test]
+fn capability_table_shows_missing_capabilities_as_unknown() {
+    let file = File {
+        capabilities: Some(FileCapabilities {
+            can_edit: Some(false),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let table = info::capability_table(&file);
+
+    assert_eq!(table.header, ["Capability", "Allowed"]);
+    assert_eq!(table.values.len(), 12);
+    assert_eq!(table.values[0], ["CanDownload", "Unknown"]);
+    assert_eq!(table.values[1], ["CanEdit", "False"]);
+}
@@ -0,0 +1,31 @@
+use gdrive::common::hub_helper;
+use gdrive::common::hub_helper::Error;
+
+#[test]
+fn message_names_the_account() {
+    assert_eq!(
+        Error::InvalidCredentials(String::from("work")).to_string(),
+        "Stored credentials for account 'work' are no longer valid"
+    );
+    assert_eq!(
+        hub_helper::invalid_credentials_message(Some("work")),
+        "Stored credentials for account 'work' are no longer valid. Run the command again with --reauth to sign in again"
+    );
+}
+
+#[test]
+fn message_without_account_points_at_the_refresh_token() {
+    assert_eq!(
+        hub_helper::invalid_credentials_message(None),
+        "The refresh token from stdin or GDRIVE_REFRESH_TOKEN is no longer valid"
+    );
+}
+
+#[test]
+fn confirmation_defaults_to_yes() {
+    assert!(hub_helper::parse_confirmation("\n"));
+    assert!(hub_helper::parse_confirmation("y\n"));
+    assert!(hub_helper::parse_confirmation("Yes"));
+    assert!(!hub_helper::parse_confirmation("n\n"));
+    assert!(!hub_helper::parse_confirmation(" NO "));
+}
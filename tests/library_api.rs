@@ -0,0 +1,521 @@
+use gdrive::drives;
+use gdrive::files;
+use gdrive::hub::Hub;
+
+mod common;
+
+use common::Requests;
+
+// Stands in for the Drive api
+fn drive_api(method: &str, target: &str) -> (u16, String) {
+    let path = target.split('?').next().unwrap_or_default();
+
+    match (method, path) {
+        ("GET", "/drive/v3/files/report1") => (
+            200,
+            String::from(r#"{"id": "report1", "name": "report.txt", "mimeType": "text/plain"}"#),
+        ),
+        ("DELETE", "/drive/v3/files/report1") => (204, String::new()),
+        ("GET", "/drive/v3/files/a1") if target.contains("alt=media") => {
+            (200, String::from("hello"))
+        }
+        ("GET", "/drive/v3/files/a1") => (
+            200,
+            String::from(
+                r#"{"id": "a1", "name": "a.txt", "mimeType": "text/plain", "size": "5", "parents": ["dir1"]}"#,
+            ),
+        ),
+        ("GET", "/drive/v3/files/c1") => (
+            200,
+            String::from(
+                r#"{"id": "c1", "name": "c.txt", "mimeType": "text/plain", "parents": ["dir1"], "description": "Q3 numbers", "starred": true, "properties": {"team": "finance"}, "appProperties": {"origin": "import"}}"#,
+            ),
+        ),
+        ("POST", "/drive/v3/files/c1/copy") => (
+            200,
+            String::from(
+                r#"{"id": "c2", "name": "c.txt", "mimeType": "text/plain", "parents": ["dir1"]}"#,
+            ),
+        ),
+        ("GET", "/drive/v3/files/dir1") => (
+            200,
+            String::from(
+                r#"{"id": "dir1", "name": "dir", "mimeType": "application/vnd.google-apps.folder"}"#,
+            ),
+        ),
+        ("GET", "/drive/v3/files") if target.contains("%27dir1%27") => (
+            200,
+            String::from(
+                r#"{"files": [{"id": "sub1", "name": "sub", "mimeType": "application/vnd.google-apps.folder"}, {"id": "a1", "name": "a.txt", "mimeType": "text/plain", "size": "5"}]}"#,
+            ),
+        ),
+        ("GET", "/drive/v3/files")
+            if target.contains("%27pages1%27") && target.contains("pageToken=page2") =>
+        {
+            (
+                200,
+                String::from(
+                    r#"{"files": [{"id": "p2", "name": "two.txt", "mimeType": "text/plain"}]}"#,
+                ),
+            )
+        }
+        ("GET", "/drive/v3/files") if target.contains("%27pages1%27") => (
+            200,
+            String::from(
+                r#"{"files": [{"id": "p1", "name": "one.txt", "mimeType": "text/plain"}], "nextPageToken": "page2"}"#,
+            ),
+        ),
+        ("GET", "/drive/v3/files") if target.contains("%27sub1%27") => (
+            200,
+            String::from(
+                r#"{"files": [{"id": "b1", "name": "b.txt", "mimeType": "text/plain", "size": "7"}]}"#,
+            ),
+        ),
+        ("GET", "/drive/v3/files") => (200, String::from(r#"{"files": []}"#)),
+        ("DELETE", _) => (204, String::new()),
+        ("GET", "/drive/v3/drives") => (
+            200,
+            String::from(r#"{"drives": [{"id": "0ADrive", "name": "Team"}]}"#),
+        ),
+        _ => common::not_found(),
+    }
+}
+
+async fn mock_drive() -> (String, Requests) {
+    common::mock_drive(|request| drive_api(&request.method, &request.target)).await
+}
+
+fn methods(requests: &Requests) -> Vec<String> {
+    requests
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|request| format!("{} {}", request.method, request.path()))
+        .collect()
+}
+
+#[tokio::test]
+async fn operations_use_the_given_hub() {
+    let (root_url, requests) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    files::info::info(
+        &hub,
+        files::info::Config {
+            file_id: String::from("report1"),
+            size_in_bytes: true,
+            check_access: false,
+            capabilities: false,
+            json: false,
+            raw_fields: None,
+            include_trashed: false,
+            size_units: Default::default(),
+            time_style: Default::default(),
+        },
+    )
+    .await
+    .unwrap();
+
+    files::delete::delete(
+        &hub,
+        files::delete::Config {
+            file_id: String::from("report1"),
+            delete_directories: false,
+            concurrency: 1,
+            space: Default::default(),
+        },
+    )
+    .await
+    .unwrap();
+
+    drives::list(
+        &hub,
+        drives::list::Config {
+            skip_header: true,
+            field_separator: String::from("\t"),
+            name_contains: None,
+            sort: None,
+            json: false,
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        methods(&requests),
+        vec![
+            "GET /drive/v3/files/report1",
+            "GET /drive/v3/files/report1",
+            "DELETE /drive/v3/files/report1",
+            "GET /drive/v3/drives",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn raw_info_requests_every_field() {
+    let (root_url, requests) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    files::info::info(
+        &hub,
+        files::info::Config {
+            file_id: String::from("report1"),
+            size_in_bytes: false,
+            check_access: false,
+            capabilities: false,
+            json: false,
+            raw_fields: Some(String::from(files::info::ALL_FIELDS)),
+            include_trashed: false,
+            size_units: Default::default(),
+            time_style: Default::default(),
+        },
+    )
+    .await
+    .unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert!(
+        requests[0].target.contains("fields=*"),
+        "{}",
+        requests[0].target
+    );
+}
+
+#[tokio::test]
+async fn operation_errors_come_from_the_api() {
+    let (root_url, _) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    let result = files::delete::delete(
+        &hub,
+        files::delete::Config {
+            file_id: String::from("missing"),
+            delete_directories: false,
+            concurrency: 1,
+            space: Default::default(),
+        },
+    )
+    .await;
+
+    assert!(matches!(result, Err(files::delete::Error::GetFile(_))));
+}
+
+#[tokio::test]
+async fn recursive_delete_removes_children_before_parents() {
+    let (root_url, requests) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    files::delete::delete(
+        &hub,
+        files::delete::Config {
+            file_id: String::from("dir1"),
+            delete_directories: true,
+            concurrency: 4,
+            space: Default::default(),
+        },
+    )
+    .await
+    .unwrap();
+
+    let deleted: Vec<String> = methods(&requests)
+        .into_iter()
+        .filter_map(|request| request.strip_prefix("DELETE /drive/v3/files/").map(String::from))
+        .collect();
+    let position = |id: &str| deleted.iter().position(|d| d == id).unwrap();
+
+    assert_eq!(deleted.len(), 4);
+    assert!(position("b1") < position("sub1"));
+    assert!(position("sub1") < position("dir1"));
+    assert!(position("a1") < position("dir1"));
+}
+
+#[tokio::test]
+async fn directories_need_recursive() {
+    let (root_url, requests) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    let result = files::delete::delete(
+        &hub,
+        files::delete::Config {
+            file_id: String::from("dir1"),
+            delete_directories: false,
+            concurrency: 4,
+            space: Default::default(),
+        },
+    )
+    .await;
+
+    assert!(matches!(result, Err(files::delete::Error::IsDirectory(_))));
+    assert_eq!(methods(&requests), vec!["GET /drive/v3/files/dir1"]);
+}
+
+#[tokio::test]
+async fn ids_are_used_without_path_resolution() {
+    let (root_url, requests) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    files::stat::stat(
+        &hub,
+        files::stat::Config {
+            file_id: String::from("report1"),
+            format: Default::default(),
+            field_separator: String::from("\t"),
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(methods(&requests), vec!["GET /drive/v3/files/report1"]);
+}
+
+#[tokio::test]
+async fn rename_refuses_a_name_taken_in_the_folder() {
+    let (root_url, requests) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    let result = files::rename::rename(
+        &hub,
+        files::rename::Config {
+            file_id: String::from("a1"),
+            name: String::from("sub"),
+            force: false,
+        },
+    )
+    .await;
+
+    assert!(matches!(
+        result,
+        Err(files::rename::Error::NameTaken { existing_id, .. }) if existing_id == "sub1"
+    ));
+    assert_eq!(
+        methods(&requests),
+        vec!["GET /drive/v3/files/a1", "GET /drive/v3/files"]
+    );
+}
+
+#[test]
+fn renaming_to_the_current_name_is_no_conflict() {
+    let siblings = vec![google_drive3::api::File {
+        id: Some(String::from("a1")),
+        name: Some(String::from("a.txt")),
+        ..google_drive3::api::File::default()
+    }];
+
+    assert!(files::rename::find_name_conflict("a1", "a.txt", &siblings).is_none());
+    assert!(files::rename::find_name_conflict("b1", "a.txt", &siblings).is_some());
+}
+
+fn download_config(file_id: &str, recursive: bool, max_size: u64) -> files::download::Config {
+    files::download::Config {
+        file_id: file_id.to_string(),
+        path: None,
+        existing_file_action: files::download::ExistingFileAction::Abort,
+        follow_shortcuts: false,
+        download_directories: recursive,
+        destination: files::download::Destination::CurrentDir,
+        checksum_manifest: None,
+        space: files::list::Space::Drive,
+        max_size: Some(max_size),
+        filter: Default::default(),
+        include_trashed: false,
+        save_as: None,
+    }
+}
+
+#[tokio::test]
+async fn download_larger_than_max_size_is_refused() {
+    let (root_url, requests) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    let result = files::download::download(&hub, download_config("a1", false, 4)).await;
+
+    assert!(matches!(
+        result,
+        Err(files::download::Error::TooLarge {
+            size: 5,
+            max_size: 4,
+            ..
+        })
+    ));
+    assert_eq!(methods(&requests), vec!["GET /drive/v3/files/a1"]);
+}
+
+#[tokio::test]
+async fn download_saves_the_file_under_the_given_name() {
+    let (root_url, _) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+    let dir = tempfile::tempdir().unwrap();
+
+    let config = files::download::Config {
+        destination: files::download::Destination::Path(dir.path().to_path_buf()),
+        save_as: Some(String::from("renamed.txt")),
+        ..download_config("a1", false, 10)
+    };
+    files::download::download(&hub, config).await.unwrap();
+
+    let saved = dir.path().join("renamed.txt");
+    assert_eq!(std::fs::read_to_string(saved).unwrap(), "hello");
+    assert!(!dir.path().join("a.txt").exists());
+}
+
+#[tokio::test]
+async fn download_as_rejects_directories_and_paths() {
+    let (root_url, _) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    let config = files::download::Config {
+        save_as: Some(String::from("copy")),
+        ..download_config("dir1", true, 10)
+    };
+    assert!(matches!(
+        files::download::download(&hub, config).await,
+        Err(files::download::Error::SaveAsDirectory(name)) if name == "dir"
+    ));
+
+    let config = files::download::Config {
+        save_as: Some(String::from("../a.txt")),
+        ..download_config("a1", false, 10)
+    };
+    assert!(matches!(
+        files::download::download(&hub, config).await,
+        Err(files::download::Error::InvalidSaveAs(_))
+    ));
+}
+
+#[tokio::test]
+async fn recursive_download_checks_the_total_size() {
+    let (root_url, requests) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    let result = files::download::download(&hub, download_config("dir1", true, 10)).await;
+
+    assert!(matches!(
+        result,
+        Err(files::download::Error::TooLarge {
+            size: 12,
+            max_size: 10,
+            ..
+        })
+    ));
+    assert!(!requests
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|request| request.target.contains("alt=media")));
+}
+
+#[tokio::test]
+async fn copy_carries_over_the_metadata_of_the_source() {
+    let (root_url, requests) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    files::copy::copy(
+        &hub,
+        files::copy::Config {
+            file_id: String::from("c1"),
+            to_folder_id: String::from("dir1"),
+            allow_same_parent: true,
+            copy_metadata: true,
+        },
+    )
+    .await
+    .unwrap();
+
+    let requests = requests.lock().unwrap();
+    let request = requests
+        .iter()
+        .find(|request| request.method == "POST")
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+
+    assert_eq!(body["parents"], serde_json::json!(["dir1"]));
+    assert_eq!(body["description"], "Q3 numbers");
+    assert_eq!(body["starred"], true);
+    assert_eq!(body["properties"]["team"], "finance");
+    assert_eq!(body["appProperties"]["origin"], "import");
+}
+
+#[tokio::test]
+async fn copy_without_metadata_only_sets_the_parent() {
+    let (root_url, requests) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    files::copy::copy(
+        &hub,
+        files::copy::Config {
+            file_id: String::from("c1"),
+            to_folder_id: String::from("dir1"),
+            allow_same_parent: true,
+            copy_metadata: false,
+        },
+    )
+    .await
+    .unwrap();
+
+    let requests = requests.lock().unwrap();
+    let request = requests
+        .iter()
+        .find(|request| request.method == "POST")
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+
+    assert_eq!(body, serde_json::json!({"parents": ["dir1"]}));
+}
+
+fn pages_config(max_files: usize) -> files::list::ListFilesConfig {
+    files::list::ListFilesConfig {
+        query: files::list::ListQuery::FilesInFolder {
+            folder_id: String::from("pages1"),
+        },
+        order_by: files::list::ListSortOrder::default(),
+        max_files,
+        space: files::list::Space::Drive,
+    }
+}
+
+#[tokio::test]
+async fn listing_continues_from_the_page_token() {
+    let (root_url, requests) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    let first = files::list::fetch_page(&hub, &pages_config(1), "files(id),nextPageToken", None)
+        .await
+        .unwrap();
+    assert_eq!(first.files[0].id.as_deref(), Some("p1"));
+    assert_eq!(first.next_page_token.as_deref(), Some("page2"));
+
+    let second = files::list::fetch_page(
+        &hub,
+        &pages_config(1),
+        "files(id),nextPageToken",
+        first.next_page_token,
+    )
+    .await
+    .unwrap();
+    assert_eq!(second.files[0].id.as_deref(), Some("p2"));
+    assert_eq!(second.next_page_token, None);
+
+    let targets: Vec<String> = requests
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|request| request.target.clone())
+        .collect();
+    assert!(!targets[0].contains("pageToken"));
+    assert!(targets[1].contains("pageToken=page2"));
+}
+
+#[tokio::test]
+async fn fetching_without_a_limit_follows_every_page() {
+    let (root_url, _) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    let files = files::list::fetch_files(&hub, &pages_config(10))
+        .await
+        .unwrap();
+
+    let ids: Vec<String> = files.into_iter().filter_map(|file| file.id).collect();
+    assert_eq!(ids, vec!["p1", "p2"]);
+}
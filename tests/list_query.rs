@@ -0,0 +1,92 @@
+use gdrive::files::list;
+use gdrive::files::list::ListQuery;
+
+#[test]
+fn owner_is_combined_with_the_folder() {
+    let query = ListQuery::FilesInFolder {
+        folder_id: String::from("dir1"),
+    }
+    .owned_by("alice@example.com");
+
+    assert_eq!(
+        query.to_string(),
+        "('dir1' in parents and trashed = false) and 'alice@example.com' in owners"
+    );
+}
+
+#[test]
+fn owner_is_combined_with_a_custom_query() {
+    let query =
+        ListQuery::Custom(String::from("name = 'a' or name = 'b'")).owned_by("bob@example.com");
+
+    assert_eq!(
+        query.to_string(),
+        "(name = 'a' or name = 'b') and 'bob@example.com' in owners"
+    );
+    assert_eq!(
+        ListQuery::None.owned_by("bob@example.com").to_string(),
+        "'bob@example.com' in owners"
+    );
+}
+
+#[test]
+fn owner_email_is_escaped() {
+    let query = ListQuery::RootNotTrashed.owned_by("o'brien@example.com");
+
+    assert_eq!(
+        query.to_string(),
+        "('root' in parents and trashed = false) and 'o\\'brien@example.com' in owners"
+    );
+}
+
+#[test]
+fn owner_keeps_the_shared_drive() {
+    let query = ListQuery::FilesOnDrive {
+        drive_id: String::from("0ADrive"),
+    }
+    .owned_by("alice@example.com");
+
+    assert_eq!(query.drive_id(), Some("0ADrive"));
+    assert_eq!(ListQuery::RootNotTrashed.drive_id(), None);
+}
+
+#[test]
+fn query_examples_use_the_built_queries() {
+    let examples = list::query_examples();
+
+    assert!(examples
+        .iter()
+        .any(|(_, query)| query == "'root' in parents and trashed = false"));
+    assert!(examples
+        .iter()
+        .any(|(_, query)| query.ends_with("and 'alice@example.com' in owners")));
+}
+
+#[test]
+fn query_file_comments_are_stripped_and_lines_joined() {
+    let contents = "# Reports that aren't pdfs\nname contains 'report'\n\n  # the type\n  and mimeType != 'application/pdf'\n";
+
+    assert_eq!(
+        list::parse_query_file(contents),
+        "name contains 'report' and mimeType != 'application/pdf'"
+    );
+}
+
+#[test]
+fn query_file_without_a_query_is_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("query.txt");
+    std::fs::write(&path, "# only a comment\n\n").unwrap();
+    assert!(matches!(
+        list::read_query_file(&path),
+        Err(list::Error::EmptyQueryFile(_))
+    ));
+
+    std::fs::write(&path, "starred = true\n").unwrap();
+    assert_eq!(list::read_query_file(&path).unwrap(), "starred = true");
+
+    assert!(matches!(
+        list::read_query_file(&dir.path().join("missing.txt")),
+        Err(list::Error::ReadQueryFile(_, _))
+    ));
+}
@@ -0,0 +1,59 @@
+use gdrive::files::list::sharing_summary;
+use google_drive3::api::File;
+use google_drive3::api::Permission;
+
+fn permission(type_: &str, role: &str) -> Permission {
+    Permission {
+        type_: Some(type_.to_string()),
+        role: Some(role.to_string()),
+        ..Default::default()
+    }
+}
+
+fn file(permissions: Vec<Permission>) -> File {
+    File {
+        permissions: Some(permissions),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn anyone_makes_a_file_public() {
+    let file = file(vec![
+        permission("user", "owner"),
+        permission("user", "writer"),
+        permission("anyone", "reader"),
+    ]);
+
+    assert_eq!(sharing_summary(&file), "public");
+}
+
+#[test]
+fn permissions_besides_the_owner_are_counted() {
+    let file = file(vec![
+        permission("user", "owner"),
+        permission("group", "reader"),
+        permission("domain", "commenter"),
+    ]);
+
+    assert_eq!(sharing_summary(&file), "shared(2)");
+}
+
+#[test]
+fn only_the_owner_is_private() {
+    assert_eq!(
+        sharing_summary(&file(vec![permission("user", "owner")])),
+        "private"
+    );
+}
+
+#[test]
+fn files_without_permissions_fall_back_to_the_shared_flag() {
+    let shared = File {
+        shared: Some(true),
+        ..Default::default()
+    };
+
+    assert_eq!(sharing_summary(&shared), "shared");
+    assert_eq!(sharing_summary(&File::default()), "");
+}
@@ -0,0 +1,92 @@
+use gdrive::files::list::sort_files;
+use gdrive::files::list::FileSort;
+use google_drive3::api::File;
+use google_drive3::chrono::TimeZone;
+use google_drive3::chrono::Utc;
+
+fn file(id: &str, name: &str, size: Option<i64>, modified_day: u32) -> File {
+    File {
+        id: Some(id.to_string()),
+        name: Some(name.to_string()),
+        size,
+        modified_time: Some(
+            Utc.with_ymd_and_hms(2024, 1, modified_day, 0, 0, 0)
+                .unwrap(),
+        ),
+        ..Default::default()
+    }
+}
+
+fn ids(files: &[File]) -> Vec<String> {
+    files
+        .iter()
+        .map(|file| file.id.clone().unwrap_or_default())
+        .collect()
+}
+
+#[test]
+fn sort_by_size_puts_files_without_a_size_first() {
+    let mut files = vec![
+        file("a", "big.iso", Some(300), 1),
+        file("b", "Folder", None, 2),
+        file("c", "notes.txt", Some(10), 3),
+    ];
+
+    sort_files(&mut files, &FileSort::Size, false);
+    assert_eq!(ids(&files), ["b", "c", "a"]);
+
+    sort_files(&mut files, &FileSort::Size, true);
+    assert_eq!(ids(&files), ["a", "c", "b"]);
+}
+
+#[test]
+fn sort_by_name_ignores_case() {
+    let mut files = vec![
+        file("a", "beta", None, 1),
+        file("b", "Alpha", None, 1),
+        file("c", "gamma", None, 1),
+    ];
+
+    sort_files(&mut files, &FileSort::Name, false);
+
+    assert_eq!(ids(&files), ["b", "a", "c"]);
+}
+
+#[test]
+fn sort_by_modified_puts_the_oldest_first() {
+    let mut files = vec![
+        file("a", "a", None, 3),
+        file("b", "b", None, 1),
+        file("c", "c", None, 2),
+    ];
+
+    sort_files(&mut files, &FileSort::Modified, false);
+
+    assert_eq!(ids(&files), ["b", "c", "a"]);
+}
+
+#[test]
+fn ties_are_ordered_by_id_in_both_directions() {
+    let mut files = vec![
+        file("c", "same.txt", Some(5), 1),
+        file("a", "same.txt", Some(5), 1),
+        file("d", "other.txt", Some(9), 1),
+        file("b", "same.txt", Some(5), 1),
+    ];
+
+    sort_files(&mut files, &FileSort::Size, false);
+    assert_eq!(ids(&files), ["a", "b", "c", "d"]);
+
+    sort_files(&mut files, &FileSort::Size, true);
+    assert_eq!(ids(&files), ["d", "a", "b", "c"]);
+
+    sort_files(&mut files, &FileSort::Name, true);
+    assert_eq!(ids(&files), ["a", "b", "c", "d"]);
+}
+
+#[test]
+fn file_sort_from_str() {
+    assert_eq!("size".parse::<FileSort>(), Ok(FileSort::Size));
+    assert_eq!("modified".parse::<FileSort>(), Ok(FileSort::Modified));
+    assert!("path".parse::<FileSort>().is_err());
+}
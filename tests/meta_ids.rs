@@ -0,0 +1,7 @@
+use gdrive::files::meta::parse_ids;
+
+#[test]
+fn parse_ids_skips_blank_lines_and_comments() {
+    let content = "# exported ids\n1AbC\n\n  2DeF  \n#3GhI\n";
+    assert_eq!(parse_ids(content), vec!["1AbC", "2DeF"]);
+}
@@ -0,0 +1,85 @@
+use gdrive::app_config::Settings;
+use gdrive::common::file_info;
+use gdrive::common::file_info::FileInfo;
+use gdrive::common::mime_overrides;
+use gdrive::common::mime_overrides::MimeOverrides;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+#[test]
+fn settings_table_matches_extensions_case_insensitively() {
+    let settings: Settings =
+        serde_json::from_str(r#"{"mime_types": {"md": "text/markdown", ".TF": "text/x-terraform"}}"#)
+            .unwrap();
+
+    let overrides = settings.mime_types;
+    assert_eq!(
+        overrides.get(Path::new("README.MD")),
+        Some("text/markdown".parse().unwrap())
+    );
+    assert_eq!(
+        overrides.get(Path::new("main.tf")),
+        Some("text/x-terraform".parse().unwrap())
+    );
+    assert_eq!(overrides.get(Path::new("photo.jpg")), None);
+    assert_eq!(overrides.get(Path::new("Makefile")), None);
+}
+
+#[test]
+fn settings_without_table_have_no_overrides() {
+    let settings: Settings = serde_json::from_str(r#"{"read_only": true}"#).unwrap();
+    assert!(settings.mime_types.is_empty());
+}
+
+#[test]
+fn invalid_mime_type_fails_loading_settings() {
+    let result = serde_json::from_str::<Settings>(r#"{"mime_types": {"md": "markdown"}}"#);
+    let err = result.unwrap_err().to_string();
+    assert!(err.contains("Invalid mime type 'markdown' for extension 'md'"), "{}", err);
+}
+
+#[test]
+fn command_line_overrides_win_over_settings() {
+    let mut overrides: MimeOverrides =
+        serde_json::from_str(r#"{"md": "text/markdown", "log": "text/plain"}"#).unwrap();
+    overrides.extend("md=text/x-markdown".parse().unwrap());
+
+    assert_eq!(
+        overrides.get(Path::new("notes.md")),
+        Some("text/x-markdown".parse().unwrap())
+    );
+    assert_eq!(overrides.get(Path::new("app.log")), Some(mime::TEXT_PLAIN));
+}
+
+#[test]
+fn file_info_uses_overrides_before_guessing() {
+    let dir = std::env::temp_dir().join(format!("gdrive-mime-overrides-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let md_path = dir.join("notes.md");
+    let txt_path = dir.join("notes.txt");
+    fs::write(&md_path, "# notes").unwrap();
+    fs::write(&txt_path, "notes").unwrap();
+
+    mime_overrides::set("md=text/x-custom".parse().unwrap());
+
+    let info = |path: &PathBuf, mime_type: Option<mime::Mime>| {
+        let file = fs::File::open(path).unwrap();
+        FileInfo::from_file(
+            &file,
+            &file_info::Config {
+                file_path: path.clone(),
+                mime_type,
+                parents: None,
+            },
+        )
+        .unwrap()
+        .mime_type
+    };
+
+    assert_eq!(info(&md_path, None), "text/x-custom".parse::<mime::Mime>().unwrap());
+    assert_eq!(info(&md_path, Some(mime::TEXT_HTML)), mime::TEXT_HTML);
+    assert_eq!(info(&txt_path, None), mime::TEXT_PLAIN);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,29 @@
+use gdrive::files::mkdir::FolderJson;
+use gdrive::files::path_utils::ResolvedFolder;
+
+fn folder(name: &str, id: &str, created: bool) -> ResolvedFolder {
+    ResolvedFolder {
+        file: google_drive3::api::File {
+            name: Some(name.to_string()),
+            id: Some(id.to_string()),
+            ..google_drive3::api::File::default()
+        },
+        created,
+    }
+}
+
+#[test]
+fn folder_chain_json() {
+    let chain = vec![
+        folder("a", "1AaA", false),
+        folder("b", "1BbB", true),
+        folder("c", "1CcC", true),
+    ];
+
+    let folders: Vec<FolderJson> = chain.iter().map(FolderJson::from).collect();
+
+    assert_eq!(
+        serde_json::to_string(&folders).unwrap(),
+        r#"[{"name":"a","id":"1AaA","created":false},{"name":"b","id":"1BbB","created":true},{"name":"c","id":"1CcC","created":true}]"#
+    );
+}
@@ -0,0 +1,49 @@
+use gdrive::files::mv::verify_move;
+use gdrive::files::mv::Verification;
+use google_drive3::api::File;
+
+fn file(id: &str, md5: Option<&str>) -> File {
+    File {
+        id: Some(id.to_string()),
+        md5_checksum: md5.map(|md5| md5.to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn same_id_is_moved_in_place() {
+    let source = file("id1", Some("abc"));
+    let moved = file("id1", Some("def"));
+
+    assert_eq!(verify_move(&source, &moved), Verification::InPlace);
+}
+
+#[test]
+fn copy_with_the_same_checksum_is_verified() {
+    let source = file("id1", Some("abc"));
+    let moved = file("id2", Some("abc"));
+
+    assert_eq!(verify_move(&source, &moved), Verification::Verified);
+}
+
+#[test]
+fn copy_with_another_checksum_is_a_mismatch() {
+    let source = file("id1", Some("abc"));
+    let moved = file("id2", Some("def"));
+
+    assert_eq!(
+        verify_move(&source, &moved),
+        Verification::Mismatch {
+            source: String::from("abc"),
+            copy: String::from("def"),
+        }
+    );
+}
+
+#[test]
+fn copy_without_checksum_cant_be_verified() {
+    let source = file("id1", None);
+    let moved = file("id2", None);
+
+    assert_eq!(verify_move(&source, &moved), Verification::NoChecksum);
+}
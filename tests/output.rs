@@ -0,0 +1,158 @@
+use gdrive::common::output;
+use gdrive::common::output::OutputMode;
+use gdrive::common::output::PrettyConfig;
+use gdrive::common::output::PrettyTable;
+use gdrive::common::output::Style;
+
+fn row(values: [&str; 3], style: Style) -> ([String; 3], Style) {
+    (values.map(String::from), style)
+}
+
+fn render(table: PrettyTable<3>, config: &PrettyConfig) -> String {
+    let mut buffer = Vec::new();
+    output::write_pretty(&mut buffer, table, config).unwrap();
+    String::from_utf8(buffer).unwrap()
+}
+
+#[test]
+fn parse_output_mode() {
+    assert_eq!("plain".parse::<OutputMode>(), Ok(OutputMode::Plain));
+    assert_eq!("pretty".parse::<OutputMode>(), Ok(OutputMode::Pretty));
+    assert!("fancy".parse::<OutputMode>().is_err());
+}
+
+#[test]
+fn no_color_flag_disables_color() {
+    assert!(!output::color_enabled(true));
+}
+
+#[test]
+fn display_width_counts_wide_characters() {
+    assert_eq!(output::display_width("report.txt"), 10);
+    assert_eq!(output::display_width("報告書"), 6);
+}
+
+#[test]
+fn truncate_middle_uses_display_width() {
+    assert_eq!(output::truncate_middle("short.txt", 41), "short.txt");
+    assert_eq!(output::truncate_middle("abcdefghij", 7), "abc…hij");
+
+    let truncated = output::truncate_middle("四半期報告書の最終版ドラフト", 11);
+    assert_eq!(truncated, "四半…フト");
+    assert!(output::display_width(&truncated) <= 11);
+}
+
+#[test]
+fn pretty_table_aligns_wide_characters() {
+    let table = PrettyTable {
+        header: ["Id", "Name", "Type"].map(String::from),
+        rows: vec![
+            row(["1a", "報告書", "regular"], Style::Normal),
+            row(["2b", "notes", "document"], Style::Document),
+        ],
+    };
+
+    let rendered = render(table, &PrettyConfig::default());
+
+    assert_eq!(
+        rendered,
+        "Id   Name     Type\n1a   報告書   regular\n2b   notes    document\n"
+    );
+}
+
+#[test]
+fn pretty_table_colors_rows() {
+    let table = PrettyTable {
+        header: ["Id", "Name", "Type"].map(String::from),
+        rows: vec![
+            row(["1a", "Photos", "folder"], Style::Folder),
+            row(["2b", "old.txt", "regular"], Style::Trashed),
+            row(["3c", "plain.txt", "regular"], Style::Normal),
+        ],
+    };
+
+    let rendered = render(
+        table,
+        &PrettyConfig {
+            skip_header: true,
+            color: true,
+            ..Default::default()
+        },
+    );
+    let lines: Vec<&str> = rendered.lines().collect();
+
+    assert_eq!(lines[0], "\x1b[1;34m1a   Photos      folder\x1b[0m");
+    assert_eq!(lines[1], "\x1b[9m2b   old.txt     regular\x1b[0m");
+    assert_eq!(lines[2], "3c   plain.txt   regular");
+}
+
+#[test]
+fn pretty_table_right_aligns_columns() {
+    let table = PrettyTable {
+        header: ["Name", "Size", "Type"].map(String::from),
+        rows: vec![
+            row(["a.txt", "5 B", "regular"], Style::Normal),
+            row(["video.mp4", "1.2 GB", "regular"], Style::Normal),
+        ],
+    };
+
+    let rendered = render(
+        table,
+        &PrettyConfig {
+            right_aligned: vec![1],
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(
+        rendered,
+        "Name          Size   Type\na.txt          5 B   regular\nvideo.mp4   1.2 GB   regular\n"
+    );
+}
+
+#[test]
+fn pretty_table_fits_column_to_max_width() {
+    let long_name = "quarterly-report-final-version-with-comments.txt";
+    let table = PrettyTable {
+        header: ["Id", "Name", "Size"].map(String::from),
+        rows: vec![
+            row(["1a", long_name, "5 B"], Style::Normal),
+            row(["2b", "short.txt", "10 B"], Style::Normal),
+        ],
+    };
+
+    let rendered = render(
+        table,
+        &PrettyConfig {
+            skip_header: true,
+            max_width: Some(40),
+            fit_column: Some(1),
+            ..Default::default()
+        },
+    );
+
+    let lines: Vec<&str> = rendered.lines().collect();
+    assert!(lines.iter().all(|line| output::display_width(line) <= 40), "{:?}", lines);
+    assert!(lines[0].contains('…'));
+    assert!(lines[1].contains("short.txt"));
+}
+
+#[test]
+fn pretty_table_keeps_narrow_tables() {
+    let table = PrettyTable {
+        header: ["Id", "Name", "Size"].map(String::from),
+        rows: vec![row(["1a", "short.txt", "5 B"], Style::Normal)],
+    };
+
+    let rendered = render(
+        table,
+        &PrettyConfig {
+            skip_header: true,
+            max_width: Some(80),
+            fit_column: Some(1),
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(rendered, "1a   short.txt   5 B\n");
+}
@@ -0,0 +1,181 @@
+use gdrive::common::byte_size;
+use gdrive::common::drive_file;
+use gdrive::common::fake_drive::FakeDrive;
+use gdrive::common::fake_drive::ROOT_ID;
+use gdrive::files::upload_with_check;
+use gdrive::files::upload_with_check::collision_summary;
+use gdrive::files::upload_with_check::find_collisions;
+use gdrive::files::upload_with_check::LocalEntry;
+use std::path::Path;
+use std::path::PathBuf;
+
+fn remote_file(id: &str, name: &str, size: i64) -> google_drive3::api::File {
+    google_drive3::api::File {
+        id: Some(id.to_string()),
+        name: Some(name.to_string()),
+        mime_type: Some(String::from("text/plain")),
+        size: Some(size),
+        ..google_drive3::api::File::default()
+    }
+}
+
+fn remote_folder(id: &str, name: &str) -> google_drive3::api::File {
+    google_drive3::api::File {
+        id: Some(id.to_string()),
+        name: Some(name.to_string()),
+        mime_type: Some(drive_file::MIME_TYPE_DRIVE_FOLDER.to_string()),
+        ..google_drive3::api::File::default()
+    }
+}
+
+fn local(name: &str, is_dir: bool) -> LocalEntry {
+    LocalEntry {
+        name: name.to_string(),
+        path: PathBuf::from("/home/user/photos").join(name),
+        is_dir,
+    }
+}
+
+// A destination with more files than fit on a single page of the old listing
+fn large_listing() -> Vec<google_drive3::api::File> {
+    let mut files: Vec<google_drive3::api::File> = (0..500)
+        .map(|i| remote_file(&format!("id{}", i), &format!("file{}.txt", i), 10))
+        .collect();
+    files.push(remote_file("idLast", "last.txt", 2048));
+    files.push(remote_folder("idAlbums", "albums"));
+    files
+}
+
+#[test]
+fn collisions_anywhere_in_the_listing_are_found() {
+    let local_entries = vec![
+        local("file3.txt", false),
+        local("last.txt", false),
+        local("new.txt", false),
+        local("albums", true),
+    ];
+
+    let collisions = find_collisions(Path::new(""), &local_entries, &large_listing());
+    let paths: Vec<&Path> = collisions.iter().map(|c| c.path.as_path()).collect();
+
+    assert_eq!(
+        paths,
+        vec![Path::new("albums"), Path::new("file3.txt"), Path::new("last.txt")]
+    );
+}
+
+#[test]
+fn matching_directories_can_be_descended_into() {
+    let local_entries = vec![local("albums", true), local("last.txt", true)];
+
+    let collisions = find_collisions(Path::new("photos"), &local_entries, &large_listing());
+
+    let albums = &collisions[0];
+    assert_eq!(albums.path, PathBuf::from("photos/albums"));
+    assert_eq!(albums.remote_folder_id.as_deref(), Some("idAlbums"));
+    assert_eq!(albums.local_path, PathBuf::from("/home/user/photos/albums"));
+
+    // A local directory with the name of a remote file still collides, but there is nothing to descend into
+    let last = &collisions[1];
+    assert!(last.is_dir);
+    assert_eq!(last.remote_folder_id, None);
+}
+
+#[test]
+fn summary_counts_files_and_their_size() {
+    let local_entries = vec![
+        local("file1.txt", false),
+        local("last.txt", false),
+        local("albums", true),
+    ];
+    let collisions = find_collisions(Path::new(""), &local_entries, &large_listing());
+
+    assert_eq!(
+        collision_summary(&collisions),
+        format!(
+            "2 files ({}) and 1 directories will be overwritten:",
+            byte_size::format_human(2058u64)
+        )
+    );
+}
+
+#[test]
+fn nothing_collides_with_an_empty_destination() {
+    let collisions = find_collisions(Path::new(""), &[local("a.txt", false)], &[]);
+    assert!(collisions.is_empty());
+}
+
+#[tokio::test]
+async fn existing_files_are_found_by_name_in_the_parent() {
+    let drive = FakeDrive::new();
+    let photos = drive.add_path("photos");
+    let existing = drive.add_file(&photos, "a.jpg", b"jpg");
+    let trashed = drive.add_file(&photos, "b.jpg", b"jpg");
+    drive.trash(&trashed);
+
+    let found = upload_with_check::find_existing_file(&drive, &photos, "a.jpg")
+        .await
+        .unwrap();
+    assert_eq!(found.and_then(|file| file.id), Some(existing));
+
+    let found = upload_with_check::find_existing_file(&drive, &photos, "b.jpg")
+        .await
+        .unwrap();
+    assert!(found.is_none());
+
+    let found = upload_with_check::find_existing_file(&drive, ROOT_ID, "a.jpg")
+        .await
+        .unwrap();
+    assert!(found.is_none());
+}
+
+#[tokio::test]
+async fn deep_check_descends_into_directories_on_both_sides() {
+    let local_dir = tempfile::tempdir().unwrap();
+    let local_photos = local_dir.path().join("photos");
+    std::fs::create_dir_all(local_photos.join("2023")).unwrap();
+    std::fs::write(local_photos.join("a.jpg"), "jpg").unwrap();
+    std::fs::write(local_photos.join("new.jpg"), "jpg").unwrap();
+    std::fs::write(local_photos.join("2023").join("b.jpg"), "jpg").unwrap();
+
+    let drive = FakeDrive::new();
+    let photos = drive.add_path("photos");
+    let year = drive.add_path("photos/2023");
+    drive.add_file(&photos, "a.jpg", b"old");
+    drive.add_file(&year, "b.jpg", b"older");
+
+    let top_level = || {
+        vec![LocalEntry {
+            name: String::from("photos"),
+            path: local_photos.clone(),
+            is_dir: true,
+        }]
+    };
+
+    let shallow = upload_with_check::check_destination(&drive, ROOT_ID, top_level(), false)
+        .await
+        .unwrap();
+    let paths: Vec<&Path> = shallow.iter().map(|c| c.path.as_path()).collect();
+    assert_eq!(paths, vec![Path::new("photos")]);
+
+    let deep = upload_with_check::check_destination(&drive, ROOT_ID, top_level(), true)
+        .await
+        .unwrap();
+    let paths: Vec<&Path> = deep.iter().map(|c| c.path.as_path()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            Path::new("photos"),
+            Path::new("photos/2023"),
+            Path::new("photos/2023/b.jpg"),
+            Path::new("photos/a.jpg"),
+        ]
+    );
+    assert_eq!(
+        collision_summary(&deep),
+        format!(
+            "2 files ({}) and 2 directories will be overwritten:",
+            byte_size::format_human(8u64)
+        )
+    );
+}
@@ -0,0 +1,110 @@
+use gdrive::common::path_filter::PathFilter;
+use gdrive::common::path_filter::Pattern;
+use gdrive::files::path_utils::wildcard_to_regex;
+use std::path::Path;
+
+fn patterns(globs: &[&str]) -> Vec<Pattern> {
+    globs.iter().map(|glob| glob.parse().unwrap()).collect()
+}
+
+fn filter(max_depth: Option<usize>, include: &[&str], exclude: &[&str]) -> PathFilter {
+    PathFilter {
+        max_depth,
+        include: patterns(include),
+        exclude: patterns(exclude),
+        match_path: false,
+    }
+}
+
+#[test]
+fn double_star_matches_across_folders() {
+    let regex = regex::Regex::new(&wildcard_to_regex("reports/**/*.csv")).unwrap();
+    assert!(regex.is_match("reports/a.csv"));
+    assert!(regex.is_match("reports/2024/q1/a.csv"));
+    assert!(!regex.is_match("other/a.csv"));
+
+    // A single star stays within a folder
+    let regex = regex::Regex::new(&wildcard_to_regex("reports/*.csv")).unwrap();
+    assert!(regex.is_match("reports/a.csv"));
+    assert!(!regex.is_match("reports/2024/a.csv"));
+}
+
+#[test]
+fn name_patterns_match_at_any_depth() {
+    let filter = filter(None, &["*.csv"], &[]);
+
+    assert!(filter.includes_file(Path::new("a.csv")));
+    assert!(filter.includes_file(Path::new("2024/q1/a.csv")));
+    assert!(!filter.includes_file(Path::new("2024/a.pdf")));
+    assert!(filter.enters_folder(Path::new("2024")));
+}
+
+#[test]
+fn excludes_win_over_includes() {
+    let filter = filter(None, &["*.csv"], &["tmp", "**/draft-*"]);
+
+    assert!(!filter.includes_file(Path::new("2024/draft-a.csv")));
+    assert!(filter.includes_file(Path::new("2024/a.csv")));
+    assert!(!filter.enters_folder(Path::new("2024/tmp")));
+}
+
+#[test]
+fn max_depth_limits_files_and_folders() {
+    let filter = filter(Some(2), &[], &[]);
+
+    assert!(filter.includes_file(Path::new("a.txt")));
+    assert!(filter.includes_file(Path::new("2024/a.txt")));
+    assert!(!filter.includes_file(Path::new("2024/q1/a.txt")));
+    assert!(filter.enters_folder(Path::new("2024")));
+    assert!(!filter.enters_folder(Path::new("2024/q1")));
+}
+
+#[test]
+fn folders_past_the_max_depth_are_below_the_depth_limit() {
+    let limited = filter(Some(2), &[], &[]);
+    assert!(!limited.is_below_depth_limit(Path::new("2024")));
+    assert!(limited.is_below_depth_limit(Path::new("2024/q1")));
+
+    let unlimited = filter(None, &[], &[]);
+    assert!(!unlimited.is_below_depth_limit(Path::new("2024/q1")));
+}
+
+#[test]
+fn folders_are_pruned_when_nothing_below_can_match() {
+    let filter = filter(None, &["reports/*/summary.csv"], &["build/**"]);
+
+    assert!(filter.enters_folder(Path::new("reports")));
+    assert!(filter.enters_folder(Path::new("reports/2024")));
+    assert!(!filter.enters_folder(Path::new("reports/2024/q1")));
+    assert!(!filter.enters_folder(Path::new("photos")));
+    assert!(!filter.enters_folder(Path::new("build")));
+
+    assert!(filter.includes_file(Path::new("reports/2024/summary.csv")));
+    assert!(!filter.includes_file(Path::new("reports/summary.csv")));
+}
+
+#[test]
+fn empty_filter_includes_everything() {
+    let filter = PathFilter::default();
+
+    assert!(filter.is_empty());
+    assert!(filter.includes_file(Path::new("a/b/c/d.txt")));
+    assert!(filter.enters_folder(Path::new("a/b/c")));
+}
+
+#[test]
+fn match_path_matches_globs_without_slash_against_the_relative_path() {
+    let filter = PathFilter {
+        max_depth: None,
+        include: patterns(&["*.csv"]),
+        exclude: patterns(&["tmp*"]),
+        match_path: true,
+    };
+
+    assert!(filter.includes_file(Path::new("a.csv")));
+    assert!(!filter.includes_file(Path::new("data/a.csv")));
+    assert!(!filter.includes_file(Path::new("tmp.csv")));
+    assert!(!filter.enters_folder(Path::new("tmp")));
+    // *.csv can only match files directly in the downloaded directory
+    assert!(!filter.enters_folder(Path::new("data")));
+}
@@ -1,27 +1,207 @@
+use gdrive::common::fake_drive::FakeDrive;
+use gdrive::common::fake_drive::ROOT_ID;
 use gdrive::files::path_utils;
-use gdrive::hub::Hub;
-use std::error::Error;
-
-#[tokio::test]
-async fn test_resolve_path() -> Result<(), Box<dyn Error>> {
-    let hub = Hub::new()?;
-    
-    // Test resolving root
-    let root = path_utils::resolve_path(&hub, "/").await?;
-    assert_eq!(root.id.unwrap(), "root");
-    
-    // Test resolving a path that should exist
-    let result = path_utils::resolve_path(&hub, "/My Drive").await;
-    assert!(result.is_ok(), "Should be able to resolve /My Drive");
-    
-    Ok(())
-}
-
-#[tokio::test]
-async fn test_resolve_nonexistent_path() {
-    let hub = Hub::new().unwrap();
-    
-    // Test resolving a non-existent path
-    let result = path_utils::resolve_path(&hub, "/nonexistent/path").await;
-    assert!(matches!(result, Err(path_utils::PathResolutionError::NotFound(_))));
+use gdrive::files::path_utils::PathResolutionError;
+
+// root/projects/app/Makefile, root/projects/lib/src/Makefile and root/notes.txt
+fn drive() -> FakeDrive {
+    let drive = FakeDrive::new();
+    let app = drive.add_path("projects/app");
+    let src = drive.add_path("projects/lib/src");
+    drive.add_file(&app, "Makefile", b"app");
+    drive.add_file(&src, "Makefile", b"lib");
+    drive.add_file(ROOT_ID, "notes.txt", b"notes");
+    drive
+}
+
+#[tokio::test]
+async fn resolves_nested_paths() {
+    let drive = drive();
+
+    let file = path_utils::resolve_path(&drive, "/projects/app/Makefile")
+        .await
+        .unwrap();
+
+    assert_eq!(file.name.as_deref(), Some("Makefile"));
+    assert_eq!(drive.content(&file.id.unwrap()).unwrap(), b"app");
+    assert_eq!(drive.requests().len(), 3);
+    assert!(!drive.requests().contains(&String::from("get root")));
+}
+
+#[tokio::test]
+async fn missing_parts_are_named_in_the_error() {
+    let drive = drive();
+
+    let result = path_utils::resolve_path(&drive, "/projects/missing/Makefile").await;
+
+    assert!(matches!(result, Err(PathResolutionError::NotFound(part)) if part == "missing"));
+}
+
+#[tokio::test]
+async fn empty_paths_are_invalid() {
+    let drive = drive();
+
+    let result = path_utils::resolve_path(&drive, "/").await;
+
+    assert!(matches!(result, Err(PathResolutionError::InvalidPath)));
+}
+
+#[tokio::test]
+async fn ids_are_used_without_a_request() {
+    let drive = drive();
+
+    let id = path_utils::resolve_file_id(&drive, "1AbC").await.unwrap();
+
+    assert_eq!(id, "1AbC");
+    assert!(drive.requests().is_empty());
+}
+
+#[tokio::test]
+async fn the_first_of_several_files_with_the_same_name_wins() {
+    let drive = FakeDrive::new();
+    let first = drive.add_file(ROOT_ID, "report.pdf", b"first");
+    drive.add_file(ROOT_ID, "report.pdf", b"second");
+
+    let id = path_utils::resolve_file_id(&drive, "/report.pdf")
+        .await
+        .unwrap();
+
+    assert_eq!(id, first);
+}
+
+#[tokio::test]
+async fn trashed_files_are_only_found_when_asked_for() {
+    let drive = FakeDrive::new();
+    let trashed = drive.add_file(ROOT_ID, "old.pdf", b"old");
+    drive.trash(&trashed);
+
+    let result = path_utils::resolve_path(&drive, "/old.pdf").await;
+    assert!(matches!(result, Err(PathResolutionError::NotFound(_))));
+
+    let resolved = path_utils::resolve_path_with_trashed(&drive, "/old.pdf", true)
+        .await
+        .unwrap();
+    assert_eq!(resolved.file.id, Some(trashed));
+    assert!(!resolved.trashed_duplicate);
+}
+
+#[tokio::test]
+async fn a_file_that_isnt_trashed_wins_over_a_trashed_one() {
+    let drive = FakeDrive::new();
+    let trashed = drive.add_file(ROOT_ID, "report.pdf", b"old");
+    drive.trash(&trashed);
+    let live = drive.add_file(ROOT_ID, "report.pdf", b"new");
+
+    let resolved = path_utils::resolve_path_with_trashed(&drive, "/report.pdf", true)
+        .await
+        .unwrap();
+
+    assert_eq!(resolved.file.id, Some(live.clone()));
+    assert!(resolved.trashed_duplicate);
+
+    let resolved = path_utils::resolve_path_with_trashed(&drive, "/report.pdf", false)
+        .await
+        .unwrap();
+
+    assert_eq!(resolved.file.id, Some(live));
+    assert!(!resolved.trashed_duplicate);
+}
+
+#[tokio::test]
+async fn missing_folders_are_created() {
+    let drive = FakeDrive::new();
+    let projects = drive.add_path("projects");
+
+    let chain = path_utils::resolve_or_create_chain(&drive, "/projects/new/deep")
+        .await
+        .unwrap();
+
+    let created: Vec<bool> = chain.iter().map(|folder| folder.created).collect();
+    assert_eq!(created, vec![false, true, true]);
+    assert_eq!(chain[0].file.id, Some(projects));
+    assert_eq!(
+        drive.add_path("projects/new/deep"),
+        chain[2].file.id.clone().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn directories_are_only_created_when_asked_for() {
+    let drive = FakeDrive::new();
+
+    let result = path_utils::resolve_directory(&drive, "/backups", false).await;
+    assert!(matches!(result, Err(PathResolutionError::NotFound(_))));
+    assert!(drive.children(ROOT_ID).is_empty());
+
+    let folder = path_utils::resolve_directory(&drive, "/backups", true)
+        .await
+        .unwrap();
+    assert_eq!(folder.name.as_deref(), Some("backups"));
+}
+
+#[tokio::test]
+async fn parents_must_be_directories() {
+    let drive = drive();
+
+    let result = path_utils::resolve_parent_ids(&drive, &[String::from("/notes.txt")], false).await;
+    assert!(
+        matches!(result, Err(PathResolutionError::NotADirectory(path)) if path == "/notes.txt")
+    );
+
+    let ids = path_utils::resolve_parent_ids(
+        &drive,
+        &[String::from("/projects/app"), String::from("1AbC")],
+        false,
+    )
+    .await
+    .unwrap();
+    assert_eq!(ids[0], drive.add_path("projects/app"));
+    assert_eq!(ids[1], "1AbC");
+}
+
+#[tokio::test]
+async fn wildcards_match_at_any_depth() {
+    let drive = drive();
+
+    let files = path_utils::resolve_wildcard_path(&drive, "/projects/**/Makefile")
+        .await
+        .unwrap();
+    let contents: Vec<Vec<u8>> = files
+        .iter()
+        .map(|file| drive.content(file.id.as_deref().unwrap()).unwrap())
+        .collect();
+
+    assert_eq!(contents, vec![b"app".to_vec(), b"lib".to_vec()]);
+}
+
+#[tokio::test]
+async fn wildcards_without_matches_are_an_error() {
+    let drive = drive();
+
+    let result = path_utils::resolve_wildcard_path(&drive, "/projects/*/README*").await;
+
+    assert!(matches!(
+        result,
+        Err(PathResolutionError::NoMatchesFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn file_paths_start_below_the_root_folder() {
+    let drive = drive();
+    let file = path_utils::resolve_path(&drive, "/projects/lib/src/Makefile")
+        .await
+        .unwrap();
+
+    let path = path_utils::file_path(&drive, &file).await.unwrap();
+
+    assert_eq!(path, "/projects/lib/src/Makefile");
+
+    let notes = path_utils::resolve_path(&drive, "/notes.txt")
+        .await
+        .unwrap();
+    assert_eq!(
+        path_utils::file_path(&drive, &notes).await.unwrap(),
+        "/notes.txt"
+    );
 }
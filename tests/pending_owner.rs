@@ -0,0 +1,57 @@
+mod common;
+
+use common::mock_drive;
+use gdrive::common::pending_owner;
+use gdrive::hub::Hub;
+
+const PERMISSIONS: &str = r#"{"permissions": [
+    {"id": "p1", "type": "user", "role": "owner", "emailAddress": "owner@example.com"},
+    {"id": "p2", "type": "user", "role": "writer", "emailAddress": "bob@example.com", "pendingOwner": true}
+]}"#;
+
+#[tokio::test]
+async fn permissions_are_listed_with_pending_owner_in_one_request() {
+    let (root_url, requests) = mock_drive(|_| (200, String::from(PERMISSIONS))).await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    let permissions = pending_owner::list_permissions(&hub, "f1").await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].path(), "/drive/v3/files/f1/permissions");
+    assert!(requests[0].target.contains("pendingOwner"));
+
+    assert_eq!(permissions.len(), 2);
+    assert_eq!(permissions[0].permission.role.as_deref(), Some("owner"));
+    assert!(!permissions[0].pending_owner);
+    assert_eq!(
+        permissions[1].permission.email_address.as_deref(),
+        Some("bob@example.com")
+    );
+    assert!(permissions[1].pending_owner);
+}
+
+#[tokio::test]
+async fn pending_owner_ids_only_include_offered_permissions() {
+    let (root_url, _) = mock_drive(|_| (200, String::from(PERMISSIONS))).await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    let ids = pending_owner::pending_owner_ids(&hub, "f1").await.unwrap();
+
+    assert_eq!(ids, vec!["p2"]);
+}
+
+#[tokio::test]
+async fn pending_owner_is_set_on_the_permission() {
+    let (root_url, requests) = mock_drive(|_| (200, String::from("{}"))).await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    pending_owner::set_pending_owner(&hub, "f1", "p2", false)
+        .await
+        .unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[0].method, "PATCH");
+    assert_eq!(requests[0].path(), "/drive/v3/files/f1/permissions/p2");
+    assert_eq!(requests[0].body, r#"{"pendingOwner":false}"#);
+}
@@ -0,0 +1,122 @@
+use gdrive::permissions::audit;
+use gdrive::permissions::audit::AuditFormat;
+use gdrive::permissions::audit::AuditRow;
+use gdrive::permissions::audit::Report;
+use google_drive3::api::File;
+use google_drive3::api::Permission;
+use google_drive3::chrono::TimeZone;
+use google_drive3::chrono::Utc;
+
+fn permission(type_: &str, email: Option<&str>, domain: Option<&str>) -> Permission {
+    Permission {
+        type_: Some(type_.to_string()),
+        role: Some(String::from("reader")),
+        email_address: email.map(String::from),
+        domain: domain.map(String::from),
+        ..Permission::default()
+    }
+}
+
+fn rows() -> Vec<AuditRow> {
+    let file = File {
+        id: Some(String::from("1AbC")),
+        ..File::default()
+    };
+
+    audit::audit_rows(
+        "Finance/q1.csv",
+        &file,
+        vec![
+            permission("user", Some("bob@example.com"), Some("example.com")),
+            permission("user", Some("eve@partner.org"), Some("partner.org")),
+            permission("domain", None, Some("example.com")),
+            permission("anyone", None, None),
+        ],
+    )
+}
+
+fn principals(rows: &[AuditRow], only_external: Option<&str>) -> Vec<String> {
+    rows.iter()
+        .filter(|row| audit::is_reported(row, only_external))
+        .map(|row| row.principal.clone())
+        .collect()
+}
+
+#[test]
+fn rows_name_the_principal_of_each_permission() {
+    let rows = rows();
+
+    assert_eq!(
+        principals(&rows, None),
+        vec![
+            "bob@example.com",
+            "eve@partner.org",
+            "example.com",
+            "anyone"
+        ]
+    );
+    assert!(rows.iter().all(|row| row.file_id == "1AbC"));
+    assert!(rows.iter().all(|row| row.inherited.is_none()));
+}
+
+#[test]
+fn only_external_keeps_access_from_outside_the_domain() {
+    assert_eq!(
+        principals(&rows(), Some("Example.com")),
+        vec!["eve@partner.org", "anyone"]
+    );
+}
+
+#[test]
+fn the_audited_folder_is_reported_under_its_name() {
+    assert_eq!(audit::report_path("Finance", ""), "Finance");
+    assert_eq!(
+        audit::report_path("Finance", "2024/q1.csv"),
+        "Finance/2024/q1.csv"
+    );
+}
+
+#[test]
+fn csv_fields_with_separators_are_quoted() {
+    assert_eq!(audit::csv_field("q1.csv"), "q1.csv");
+    assert_eq!(audit::csv_field("a, b"), "\"a, b\"");
+    assert_eq!(audit::csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+}
+
+#[test]
+fn csv_reports_have_a_header_and_a_line_per_row() {
+    let mut row = rows().remove(0);
+    row.expiration = Some(
+        Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0)
+            .unwrap()
+            .to_rfc3339(),
+    );
+
+    let mut report = Report::start(Vec::new(), AuditFormat::Csv).unwrap();
+    report.write_row(&row).unwrap();
+    let output = String::from_utf8(report.finish().unwrap()).unwrap();
+
+    assert_eq!(
+        output,
+        "path,file_id,principal,role,type,discoverable,expiration,inherited\n\
+         Finance/q1.csv,1AbC,bob@example.com,reader,user,,2024-06-01T12:00:00+00:00,\n"
+    );
+}
+
+#[test]
+fn json_reports_are_an_array() {
+    let mut report = Report::start(Vec::new(), AuditFormat::Json).unwrap();
+    for row in rows() {
+        report.write_row(&row).unwrap();
+    }
+    let output = String::from_utf8(report.finish().unwrap()).unwrap();
+
+    let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 4);
+    assert_eq!(json[3]["principal"], "anyone");
+    assert_eq!(json[3]["type"], "anyone");
+
+    let empty = Report::start(Vec::new(), AuditFormat::Json).unwrap();
+    let output = String::from_utf8(empty.finish().unwrap()).unwrap();
+    assert_eq!(output, "[]\n");
+}
@@ -0,0 +1,134 @@
+use gdrive::permissions::revoke;
+use gdrive::permissions::revoke::Error;
+use gdrive::permissions::revoke::RevokeAction;
+use google_drive3::api::Permission;
+use google_drive3::chrono::DateTime;
+use google_drive3::chrono::Duration;
+use google_drive3::chrono::TimeZone;
+use google_drive3::chrono::Utc;
+
+fn permission(id: &str, type_: &str, email: Option<&str>, domain: Option<&str>) -> Permission {
+    Permission {
+        id: Some(id.to_string()),
+        type_: Some(type_.to_string()),
+        role: Some(String::from("reader")),
+        email_address: email.map(String::from),
+        domain: domain.map(String::from),
+        ..Permission::default()
+    }
+}
+
+fn permissions() -> Vec<Permission> {
+    vec![
+        permission("p1", "user", Some("bob@example.com"), Some("example.com")),
+        permission("p2", "group", Some("team@example.com"), Some("example.com")),
+        permission("p3", "domain", None, Some("example.com")),
+        permission("p4", "anyone", None, None),
+    ]
+}
+
+fn ids(permissions: Vec<Permission>) -> Vec<String> {
+    permissions.into_iter().filter_map(|p| p.id).collect()
+}
+
+#[test]
+fn email_matches_the_grantee_ignoring_case() {
+    let action = RevokeAction::Email(String::from("Bob@Example.com"));
+
+    let matching = action.get_matching_permissions(permissions()).unwrap();
+
+    assert_eq!(ids(matching), vec!["p1"]);
+}
+
+#[test]
+fn email_of_the_owner_is_never_revoked() {
+    let owner = Permission {
+        role: Some(String::from("owner")),
+        ..permission("p0", "user", Some("owner@example.com"), Some("example.com"))
+    };
+    let mut permissions = permissions();
+    permissions.push(owner);
+
+    let action = RevokeAction::Email(String::from("owner@example.com"));
+    assert!(matches!(
+        action.get_matching_permissions(permissions.clone()),
+        Err(Error::NoPermissionForEmail(_))
+    ));
+
+    let matching = revoke::permissions_for_email(permissions, "owner@example.com", now());
+    assert!(matching.is_empty());
+}
+
+#[test]
+fn domain_only_matches_domain_grantees() {
+    let action = RevokeAction::Domain(String::from("example.com"));
+
+    let matching = action.get_matching_permissions(permissions()).unwrap();
+
+    assert_eq!(ids(matching), vec!["p3"]);
+}
+
+#[test]
+fn missing_grantees_are_an_error() {
+    let action = RevokeAction::Email(String::from("alice@example.com"));
+    assert!(matches!(
+        action.get_matching_permissions(permissions()),
+        Err(Error::NoPermissionForEmail(email)) if email == "alice@example.com"
+    ));
+
+    let action = RevokeAction::Domain(String::from("other.org"));
+    assert!(matches!(
+        action.get_matching_permissions(permissions()),
+        Err(Error::NoPermissionForDomain(_))
+    ));
+}
+
+fn now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap()
+}
+
+fn expiring(permission: Permission, expiration_time: DateTime<Utc>) -> Permission {
+    Permission {
+        expiration_time: Some(expiration_time),
+        ..permission
+    }
+}
+
+#[test]
+fn email_matches_groups() {
+    let matching = revoke::permissions_for_email(permissions(), "team@example.com", now());
+
+    assert_eq!(ids(matching), vec!["p2"]);
+}
+
+#[test]
+fn expired_permissions_are_skipped() {
+    let permissions = vec![
+        expiring(
+            permission("p1", "user", Some("bob@example.com"), None),
+            now() - Duration::days(1),
+        ),
+        expiring(
+            permission("p2", "user", Some("bob@example.com"), None),
+            now() + Duration::days(1),
+        ),
+        expiring(permission("p3", "domain", None, Some("example.com")), now()),
+    ];
+
+    let by_email = revoke::permissions_for_email(permissions.clone(), "bob@example.com", now());
+    assert_eq!(ids(by_email), vec!["p2"]);
+
+    let by_domain = revoke::permissions_for_domain(permissions, "example.com", now());
+    assert!(by_domain.is_empty());
+}
+
+#[test]
+fn only_missing_grantees_are_no_match_errors() {
+    let action = RevokeAction::Domain(String::from("other.org"));
+    let err = action.get_matching_permissions(permissions()).unwrap_err();
+    assert!(err.is_no_match());
+
+    let action = RevokeAction::Id(String::from("p9"));
+    let err = action.get_matching_permissions(permissions()).unwrap_err();
+    assert!(!err.is_no_match());
+}
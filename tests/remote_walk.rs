@@ -0,0 +1,368 @@
+use futures::StreamExt;
+use gdrive::common::path_filter::PathFilter;
+use gdrive::common::path_filter::Pattern;
+use gdrive::common::remote_walk;
+use gdrive::common::remote_walk::FolderLister;
+use gdrive::common::remote_walk::ListPage;
+use gdrive::common::remote_walk::ShortcutPolicy;
+use gdrive::common::remote_walk::WalkConfig;
+use google_drive3::api::File;
+use google_drive3::api::FileShortcutDetails;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+fn folder(id: &str, name: &str) -> File {
+    File {
+        id: Some(id.to_string()),
+        name: Some(name.to_string()),
+        mime_type: Some(String::from("application/vnd.google-apps.folder")),
+        ..Default::default()
+    }
+}
+
+fn file(id: &str, name: &str) -> File {
+    File {
+        id: Some(id.to_string()),
+        name: Some(name.to_string()),
+        mime_type: Some(String::from("text/plain")),
+        ..Default::default()
+    }
+}
+
+fn shortcut(id: &str, name: &str, target_id: &str) -> File {
+    File {
+        id: Some(id.to_string()),
+        name: Some(name.to_string()),
+        mime_type: Some(String::from("application/vnd.google-apps.shortcut")),
+        shortcut_details: Some(FileShortcutDetails {
+            target_id: Some(target_id.to_string()),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+// Folders are listed from pages kept in memory, page tokens are the index of the next page
+#[derive(Default)]
+struct MockLister {
+    pages: HashMap<String, Vec<Vec<File>>>,
+    files: HashMap<String, File>,
+    requests: RefCell<Vec<(String, Option<String>)>>,
+}
+
+impl MockLister {
+    fn folder(mut self, id: &str, pages: Vec<Vec<File>>) -> MockLister {
+        self.pages.insert(id.to_string(), pages);
+        self
+    }
+
+    fn file(mut self, file: File) -> MockLister {
+        self.files.insert(file.id.clone().unwrap(), file);
+        self
+    }
+
+    fn requests(&self) -> Vec<(String, Option<String>)> {
+        self.requests.borrow().clone()
+    }
+}
+
+impl FolderLister for MockLister {
+    async fn list_page(
+        &self,
+        folder_id: &str,
+        page_token: Option<&str>,
+    ) -> Result<ListPage, google_drive3::Error> {
+        self.requests
+            .borrow_mut()
+            .push((folder_id.to_string(), page_token.map(String::from)));
+
+        let pages = match self.pages.get(folder_id) {
+            Some(pages) => pages,
+            None => return Err(google_drive3::Error::Cancelled),
+        };
+
+        let index: usize = page_token.map(|token| token.parse().unwrap()).unwrap_or(0);
+        let files = pages.get(index).cloned().unwrap_or_default();
+        let next_page_token = (index + 1 < pages.len()).then(|| (index + 1).to_string());
+
+        Ok(ListPage {
+            files,
+            next_page_token,
+        })
+    }
+
+    async fn get_file(&self, file_id: &str) -> Result<File, google_drive3::Error> {
+        self.files
+            .get(file_id)
+            .cloned()
+            .ok_or(google_drive3::Error::Cancelled)
+    }
+}
+
+fn patterns(globs: &[&str]) -> Vec<Pattern> {
+    globs.iter().map(|glob| glob.parse().unwrap()).collect()
+}
+
+fn config(max_depth: Option<usize>, include: &[&str], exclude: &[&str]) -> WalkConfig {
+    WalkConfig {
+        filter: PathFilter {
+            max_depth,
+            include: patterns(include),
+            exclude: patterns(exclude),
+            match_path: false,
+        },
+        shortcuts: ShortcutPolicy::default(),
+    }
+}
+
+async fn paths(lister: &MockLister, config: &WalkConfig) -> Vec<String> {
+    remote_walk::walk_all(lister, "root", config)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.path)
+        .collect()
+}
+
+// root/a/b/c/d/deep.txt, with a file next to every folder
+fn deep_tree() -> MockLister {
+    MockLister::default()
+        .folder("root", vec![vec![folder("a", "a"), file("f0", "top.txt")]])
+        .folder("a", vec![vec![folder("b", "b"), file("f1", "one.txt")]])
+        .folder("b", vec![vec![folder("c", "c"), file("f2", "two.txt")]])
+        .folder("c", vec![vec![folder("d", "d"), file("f3", "three.txt")]])
+        .folder("d", vec![vec![file("f4", "deep.txt")]])
+}
+
+#[tokio::test]
+async fn walks_deep_trees_breadth_first() {
+    let lister = deep_tree();
+
+    assert_eq!(
+        paths(&lister, &WalkConfig::default()).await,
+        vec![
+            "a",
+            "top.txt",
+            "a/b",
+            "a/one.txt",
+            "a/b/c",
+            "a/b/two.txt",
+            "a/b/c/d",
+            "a/b/c/three.txt",
+            "a/b/c/d/deep.txt",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn entries_know_their_depth_and_parent() {
+    let lister = deep_tree();
+    let entries = remote_walk::walk_all(&lister, "root", &WalkConfig::default())
+        .await
+        .unwrap();
+
+    let deep = entries
+        .iter()
+        .find(|entry| entry.path == "a/b/c/d/deep.txt")
+        .unwrap();
+    assert_eq!(deep.depth, 5);
+    assert_eq!(deep.parent_path(), "a/b/c/d");
+    assert!(!deep.is_folder());
+
+    let top = entries.iter().find(|entry| entry.path == "a").unwrap();
+    assert_eq!(top.depth, 1);
+    assert_eq!(top.parent_path(), "");
+    assert!(top.is_folder());
+}
+
+#[tokio::test]
+async fn empty_folders_are_yielded_without_children() {
+    let lister = MockLister::default()
+        .folder(
+            "root",
+            vec![vec![folder("empty", "empty"), folder("full", "full")]],
+        )
+        .folder("empty", vec![vec![]])
+        .folder("full", vec![vec![folder("nested", "nested")]])
+        .folder("nested", vec![]);
+
+    assert_eq!(
+        paths(&lister, &WalkConfig::default()).await,
+        vec!["empty", "full", "full/nested"]
+    );
+}
+
+#[tokio::test]
+async fn walking_an_empty_folder_yields_nothing() {
+    let lister = MockLister::default().folder("root", vec![vec![]]);
+
+    assert!(paths(&lister, &WalkConfig::default()).await.is_empty());
+    assert_eq!(lister.requests(), vec![(String::from("root"), None)]);
+}
+
+#[tokio::test]
+async fn follows_every_page_of_a_folder() {
+    let lister = MockLister::default()
+        .folder(
+            "root",
+            vec![
+                vec![file("1", "1.txt"), folder("sub", "sub")],
+                vec![file("2", "2.txt")],
+                vec![file("3", "3.txt")],
+            ],
+        )
+        .folder(
+            "sub",
+            vec![vec![file("4", "4.txt")], vec![file("5", "5.txt")]],
+        );
+
+    assert_eq!(
+        paths(&lister, &WalkConfig::default()).await,
+        vec!["1.txt", "sub", "2.txt", "3.txt", "sub/4.txt", "sub/5.txt"]
+    );
+
+    // The rest of a folder is listed before the folders found in it
+    assert_eq!(
+        lister.requests(),
+        vec![
+            (String::from("root"), None),
+            (String::from("root"), Some(String::from("1"))),
+            (String::from("root"), Some(String::from("2"))),
+            (String::from("sub"), None),
+            (String::from("sub"), Some(String::from("1"))),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn empty_pages_with_a_next_page_token_are_skipped() {
+    let lister = MockLister::default().folder(
+        "root",
+        vec![vec![], vec![file("1", "1.txt")], vec![], vec![]],
+    );
+
+    assert_eq!(paths(&lister, &WalkConfig::default()).await, vec!["1.txt"]);
+    assert_eq!(lister.requests().len(), 4);
+}
+
+#[tokio::test]
+async fn stops_listing_when_the_consumer_stops() {
+    let lister = deep_tree();
+    let config = WalkConfig::default();
+
+    let first: Vec<_> = remote_walk::walk(&lister, "root", &config)
+        .take(2)
+        .collect()
+        .await;
+
+    assert_eq!(first.len(), 2);
+    assert_eq!(lister.requests(), vec![(String::from("root"), None)]);
+}
+
+#[tokio::test]
+async fn max_depth_limits_the_folders_listed() {
+    let lister = deep_tree();
+
+    assert_eq!(
+        paths(&lister, &config(Some(2), &[], &[])).await,
+        vec!["a", "top.txt", "a/b", "a/one.txt"]
+    );
+
+    let listed: Vec<String> = lister
+        .requests()
+        .into_iter()
+        .map(|(folder_id, _)| folder_id)
+        .collect();
+    assert_eq!(listed, vec!["root", "a"]);
+}
+
+#[tokio::test]
+async fn includes_and_excludes_are_matched_against_relative_paths() {
+    let lister = deep_tree();
+
+    assert_eq!(
+        paths(&lister, &config(None, &["*.txt"], &["a/b/c/**"])).await,
+        vec!["a", "top.txt", "a/b", "a/one.txt", "a/b/two.txt"]
+    );
+
+    // Excluded folders aren't listed
+    assert!(!lister
+        .requests()
+        .iter()
+        .any(|(folder_id, _)| folder_id == "c"));
+}
+
+#[tokio::test]
+async fn shortcuts_are_kept_by_default() {
+    let lister = MockLister::default()
+        .folder("root", vec![vec![shortcut("s", "link", "target")]])
+        .file(folder("target", "target"));
+
+    let entries = remote_walk::walk_all(&lister, "root", &WalkConfig::default())
+        .await
+        .unwrap();
+
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].file.id.as_deref(), Some("s"));
+    assert_eq!(lister.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn shortcuts_can_be_skipped() {
+    let lister = MockLister::default().folder(
+        "root",
+        vec![vec![shortcut("s", "link", "target"), file("1", "1.txt")]],
+    );
+    let config = WalkConfig {
+        shortcuts: ShortcutPolicy::Skip,
+        ..Default::default()
+    };
+
+    assert_eq!(paths(&lister, &config).await, vec!["1.txt"]);
+}
+
+#[tokio::test]
+async fn followed_shortcuts_are_walked_under_their_own_name() {
+    let lister = MockLister::default()
+        .folder("root", vec![vec![shortcut("s", "link", "target")]])
+        .folder("target", vec![vec![file("1", "1.txt")]])
+        .file(folder("target", "target"));
+    let config = WalkConfig {
+        shortcuts: ShortcutPolicy::Follow,
+        ..Default::default()
+    };
+
+    let entries = remote_walk::walk_all(&lister, "root", &config)
+        .await
+        .unwrap();
+    let paths: Vec<&str> = entries.iter().map(|entry| entry.path.as_str()).collect();
+
+    assert_eq!(paths, vec!["link", "link/1.txt"]);
+    assert_eq!(entries[0].file.id.as_deref(), Some("target"));
+}
+
+#[tokio::test]
+async fn shortcut_cycles_are_walked_once() {
+    let lister = MockLister::default()
+        .folder("root", vec![vec![folder("a", "a")]])
+        .folder("a", vec![vec![shortcut("s", "back", "root")]])
+        .file(folder("root", "root"));
+    let config = WalkConfig {
+        shortcuts: ShortcutPolicy::Follow,
+        ..Default::default()
+    };
+
+    assert_eq!(paths(&lister, &config).await, vec!["a", "a/back"]);
+    assert_eq!(lister.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn list_errors_name_the_folder() {
+    let lister = MockLister::default().folder("root", vec![vec![folder("missing", "gone")]]);
+
+    let err = remote_walk::walk_all(&lister, "root", &WalkConfig::default())
+        .await
+        .unwrap_err();
+
+    assert!(matches!(&err, remote_walk::Error::ListFolder(path, _) if path == "gone"));
+}
@@ -0,0 +1,76 @@
+use gdrive::files;
+use gdrive::files::download;
+use gdrive::hub::Hub;
+use gdrive::hub::HubConfig;
+use std::time::Duration;
+use std::time::Instant;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::net::TcpListener;
+
+// Server that answers with `response` and then stalls, keeping the connection open
+async fn stalling_drive(response: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let root_url = format!("http://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+
+            tokio::spawn(async move {
+                let mut reader = BufReader::new(stream);
+
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).await.unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                }
+
+                let mut stream = reader.into_inner();
+                stream.write_all(response.as_bytes()).await.unwrap();
+                tokio::time::sleep(Duration::from_secs(60)).await;
+            });
+        }
+    });
+
+    root_url
+}
+
+fn hub_with_short_timeout(root_url: &str) -> Hub {
+    let config = HubConfig {
+        request_timeout: Duration::from_secs(1),
+        ..HubConfig::default()
+    };
+    Hub::with_root_url_and_config(root_url, "test-token", &config)
+}
+
+#[tokio::test]
+async fn metadata_request_times_out() {
+    let root_url = stalling_drive("").await;
+    let hub = hub_with_short_timeout(&root_url);
+    let started = Instant::now();
+
+    let result = files::info::get_file(&hub, "report1").await;
+
+    assert!(result.is_err());
+    assert!(started.elapsed() < Duration::from_secs(30));
+}
+
+#[tokio::test]
+async fn stalled_download_removes_the_partial_file() {
+    let root_url =
+        stalling_drive("HTTP/1.1 200 OK\r\ncontent-length: 100\r\n\r\nfirst bytes of the file").await;
+    let hub = hub_with_short_timeout(&root_url);
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("report.txt");
+
+    let body = download::download_file(&hub, "report1").await.unwrap();
+    let result = download::save_body_to_file(body, &file_path, None).await;
+
+    assert!(matches!(result, Err(download::Error::ReadChunk(_))));
+    assert!(!file_path.exists());
+    assert!(!file_path.with_extension("incomplete").exists());
+}
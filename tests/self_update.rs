@@ -0,0 +1,109 @@
+#![cfg(feature = "self-update")]
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use gdrive::self_update::asset_name;
+use gdrive::self_update::checksum_for;
+use gdrive::self_update::extract_binary;
+use gdrive::self_update::is_newer;
+use gdrive::self_update::latest_release_url;
+use gdrive::self_update::parse_version;
+use gdrive::self_update::sha256_hex;
+
+#[test]
+fn parses_versions_and_tags() {
+    assert_eq!(parse_version("3.9.1"), Some(vec![3, 9, 1]));
+    assert_eq!(parse_version("v3.10.0"), Some(vec![3, 10, 0]));
+    assert_eq!(parse_version("3.10.0-beta.1"), Some(vec![3, 10, 0]));
+    assert_eq!(parse_version("latest"), None);
+}
+
+#[test]
+fn compares_versions_numerically() {
+    assert!(is_newer("v3.10.0", "3.9.1").unwrap());
+    assert!(is_newer("4.0.0", "3.9.1").unwrap());
+    assert!(!is_newer("3.9.1", "3.9.1").unwrap());
+    assert!(!is_newer("3.9", "3.9.0").unwrap());
+    assert!(!is_newer("3.8.9", "3.9.1").unwrap());
+    assert!(is_newer("nightly", "3.9.1").is_err());
+}
+
+#[test]
+fn releases_come_from_the_repository_of_the_build() {
+    let expected = "https://api.github.com/repos/wjurkowlaniec/gdrive/releases/latest";
+
+    assert_eq!(
+        latest_release_url("https://github.com/wjurkowlaniec/gdrive"),
+        expected
+    );
+    assert_eq!(
+        latest_release_url("https://github.com/wjurkowlaniec/gdrive.git/"),
+        expected
+    );
+    assert_eq!(latest_release_url(env!("CARGO_PKG_REPOSITORY")), expected);
+}
+
+#[test]
+fn maps_targets_to_release_assets() {
+    assert_eq!(
+        asset_name("x86_64-unknown-linux-musl"),
+        Some("gdrive_linux-x64.tar.gz")
+    );
+    assert_eq!(
+        asset_name("x86_64-pc-windows-msvc"),
+        Some("gdrive_windows-x64.zip")
+    );
+    assert_eq!(asset_name("aarch64-unknown-linux-gnu"), None);
+}
+
+#[test]
+fn finds_checksum_of_the_asset() {
+    let checksums = "AAAA  gdrive_macos-x64.tar.gz\nbbbb *gdrive_linux-x64.tar.gz\n";
+    assert_eq!(
+        checksum_for(checksums, "gdrive_macos-x64.tar.gz"),
+        Some(String::from("aaaa"))
+    );
+    assert_eq!(
+        checksum_for(checksums, "gdrive_linux-x64.tar.gz"),
+        Some(String::from("bbbb"))
+    );
+    assert_eq!(checksum_for(checksums, "gdrive_windows-x64.zip"), None);
+
+    // A file with only the checksum
+    assert_eq!(
+        checksum_for("cccc\n", "gdrive_windows-x64.zip"),
+        Some(String::from("cccc"))
+    );
+}
+
+#[test]
+fn sha256_matches_known_digest() {
+    assert_eq!(
+        sha256_hex(b"abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+}
+
+#[cfg(not(windows))]
+#[test]
+fn extracts_binary_from_tar_gz() {
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for (name, content) in [("README.md", &b"readme"[..]), ("gdrive", &b"binary"[..])] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder.append_data(&mut header, name, content).unwrap();
+    }
+    let archive = builder.into_inner().unwrap().finish().unwrap();
+
+    let binary = extract_binary("gdrive_linux-x64.tar.gz", &archive).unwrap();
+    assert_eq!(binary, b"binary");
+
+    let empty = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()))
+        .into_inner()
+        .unwrap()
+        .finish()
+        .unwrap();
+    assert!(extract_binary("gdrive_linux-x64.tar.gz", &empty).is_err());
+}
@@ -0,0 +1,83 @@
+use gdrive::common::drive_file;
+use gdrive::common::fake_drive::FakeDrive;
+use gdrive::common::fake_drive::ROOT_ID;
+use gdrive::files::info;
+use google_drive3::api::File;
+use google_drive3::api::FileShortcutDetails;
+
+// The root holds report.pdf, the target of the shortcuts
+fn drive() -> FakeDrive {
+    let drive = FakeDrive::new();
+    drive.add(File {
+        id: Some(String::from("report1")),
+        name: Some(String::from("report.pdf")),
+        mime_type: Some(String::from("application/pdf")),
+        parents: Some(vec![ROOT_ID.to_string()]),
+        size: Some(2048),
+        md5_checksum: Some(String::from("abc")),
+        ..Default::default()
+    });
+    drive
+}
+
+fn shortcut(target_id: Option<&str>) -> File {
+    File {
+        id: Some(String::from("shortcut1")),
+        mime_type: Some(String::from("application/vnd.google-apps.shortcut")),
+        shortcut_details: Some(FileShortcutDetails {
+            target_id: target_id.map(String::from),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn target_id_comes_from_the_shortcut_details() {
+    assert_eq!(
+        drive_file::shortcut_target_id(&shortcut(Some("report1"))).as_deref(),
+        Some("report1")
+    );
+    assert_eq!(drive_file::shortcut_target_id(&shortcut(None)), None);
+    assert_eq!(drive_file::shortcut_target_id(&File::default()), None);
+}
+
+#[tokio::test]
+async fn target_of_a_shortcut_is_fetched() {
+    let drive = drive();
+
+    let target = info::get_shortcut_target(&drive, &shortcut(Some("report1")))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(target.id.as_deref(), Some("report1"));
+    assert_eq!(target.size, Some(2048));
+    assert!(drive_file::is_binary(&target));
+}
+
+#[tokio::test]
+async fn files_that_arent_shortcuts_have_no_target() {
+    let drive = drive();
+
+    let file = File {
+        id: Some(String::from("report1")),
+        mime_type: Some(String::from("application/pdf")),
+        ..Default::default()
+    };
+    assert!(info::get_shortcut_target(&drive, &file)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(info::get_shortcut_target(&drive, &shortcut(None))
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn missing_target_is_an_error() {
+    let drive = drive();
+
+    let result = info::get_shortcut_target(&drive, &shortcut(Some("deleted1"))).await;
+    assert!(result.is_err());
+}
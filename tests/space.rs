@@ -0,0 +1,32 @@
+use gdrive::files::list::Space;
+use std::str::FromStr;
+
+#[test]
+fn space_round_trip() {
+    for space in [Space::Drive, Space::AppDataFolder, Space::Photos] {
+        assert_eq!(Space::from_str(&space.to_string()), Ok(space));
+    }
+
+    assert_eq!(Space::AppDataFolder.to_string(), "appDataFolder");
+    assert!(Space::from_str("appdata").is_err());
+}
+
+#[test]
+fn default_query_per_space() {
+    assert_eq!(
+        Space::Drive.default_query().to_string(),
+        "'root' in parents and trashed = false"
+    );
+    assert_eq!(
+        Space::AppDataFolder.default_query().to_string(),
+        "'appDataFolder' in parents and trashed = false"
+    );
+    assert_eq!(Space::Photos.default_query().to_string(), "trashed = false");
+}
+
+#[test]
+fn only_drive_uses_the_default_scopes() {
+    assert_eq!(Space::Drive.required_scope(), None);
+    assert!(Space::AppDataFolder.required_scope().is_some());
+    assert!(Space::Photos.required_scope().is_some());
+}
@@ -0,0 +1,57 @@
+use gdrive::files::stat::StatField;
+use gdrive::files::stat::StatFormat;
+use google_drive3::chrono::TimeZone;
+use google_drive3::chrono::Utc;
+use std::str::FromStr;
+
+fn report() -> google_drive3::api::File {
+    google_drive3::api::File {
+        id: Some(String::from("1AbC")),
+        name: Some(String::from("report.txt")),
+        size: Some(1536),
+        mime_type: Some(String::from("text/plain")),
+        modified_time: Some(Utc.with_ymd_and_hms(2023, 4, 5, 6, 7, 8).unwrap()),
+        parents: Some(vec![String::from("0AFolder"), String::from("0BFolder")]),
+        ..google_drive3::api::File::default()
+    }
+}
+
+#[test]
+fn default_format() {
+    let format = StatFormat::default();
+
+    assert_eq!(format.api_fields(), "id,name,size,mimeType,modifiedTime");
+    assert_eq!(
+        format.format_line(&report(), "\t"),
+        "1AbC\treport.txt\t1536\ttext/plain\t2023-04-05T06:07:08Z"
+    );
+}
+
+#[test]
+fn custom_format() {
+    let format = StatFormat::from_str("name, size,parents").unwrap();
+
+    assert_eq!(
+        format,
+        StatFormat(vec![StatField::Name, StatField::Size, StatField::Parents])
+    );
+    assert_eq!(
+        format.format_line(&report(), " "),
+        "report.txt 1536 0AFolder,0BFolder"
+    );
+    assert_eq!(format.to_string(), "name,size,parents");
+}
+
+#[test]
+fn missing_values_are_empty() {
+    let format = StatFormat::from_str("id,md5Checksum,createdTime").unwrap();
+
+    assert_eq!(format.format_line(&report(), "|"), "1AbC||");
+}
+
+#[test]
+fn invalid_format() {
+    assert!(StatFormat::from_str("").is_err());
+    assert!(StatFormat::from_str("id,owner").is_err());
+    assert!(StatFormat::from_str("mimetype").is_err());
+}
@@ -0,0 +1,51 @@
+use gdrive::common::temp_copy::TempCopy;
+use std::fs;
+use std::io::Read;
+
+#[test]
+fn temp_copy_is_removed_after_upload() {
+    let dir = tempfile::tempdir().unwrap();
+    let src = dir.path().join("report.txt");
+    fs::write(&src, "quarterly numbers").unwrap();
+
+    let copy = TempCopy::create(&src, "renamed.txt", false).unwrap();
+    let path = copy.path().to_path_buf();
+    assert_eq!(path.file_name().unwrap(), "renamed.txt");
+
+    // Simulated upload: read the copy the same way the uploader does
+    let mut content = String::new();
+    fs::File::open(&path)
+        .unwrap()
+        .read_to_string(&mut content)
+        .unwrap();
+    assert_eq!(content, "quarterly numbers");
+
+    drop(copy);
+    assert!(!path.exists());
+    assert!(!path.parent().unwrap().exists());
+    assert!(src.exists());
+}
+
+#[test]
+fn temp_copy_is_kept_when_requested() {
+    let dir = tempfile::tempdir().unwrap();
+    let src = dir.path().join("report.txt");
+    fs::write(&src, "quarterly numbers").unwrap();
+
+    let copy = TempCopy::create(&src, "renamed.txt", true).unwrap();
+    let path = copy.path().to_path_buf();
+    assert!(copy.is_kept());
+
+    drop(copy);
+    assert!(path.exists());
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn temp_copy_of_missing_file_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let src = dir.path().join("missing.txt");
+
+    assert!(TempCopy::create(&src, "renamed.txt", false).is_err());
+}
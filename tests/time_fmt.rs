@@ -0,0 +1,123 @@
+use gdrive::common::time_fmt;
+use gdrive::common::time_fmt::TimeStyle;
+use google_drive3::chrono::DateTime;
+use google_drive3::chrono::Utc;
+use std::env;
+use std::process::Command;
+
+fn utc(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+}
+
+#[test]
+fn parse_time_style() {
+    assert_eq!("local".parse::<TimeStyle>(), Ok(TimeStyle::Local));
+    assert_eq!("relative".parse::<TimeStyle>(), Ok(TimeStyle::Relative));
+    assert_eq!("iso".parse::<TimeStyle>(), Ok(TimeStyle::Iso));
+    assert!("utc".parse::<TimeStyle>().is_err());
+    assert_eq!(TimeStyle::default(), TimeStyle::Local);
+}
+
+#[test]
+fn iso_keeps_rfc3339_utc() {
+    let time = utc("2024-03-31T01:30:00.123Z");
+    assert_eq!(
+        time_fmt::format_time(time, &TimeStyle::Iso),
+        "2024-03-31T01:30:00Z"
+    );
+}
+
+// Central European time as a posix rule, so the zone doesn't depend on the tz database
+const CENTRAL_EUROPE: &str = "CET-1CEST,M3.5.0,M10.5.0/3";
+
+// Set for the child process that runs the local time checks
+const LOCAL_TZ_CHILD: &str = "GDRIVE_TEST_LOCAL_TZ_CHILD";
+
+// The local zone is read from TZ once per process, so the checks run in a copy of this test
+// binary with TZ set
+#[test]
+fn local_time_on_both_sides_of_dst_change() {
+    let output = Command::new(env::current_exe().unwrap())
+        .args(["local_time_in_central_europe", "--exact", "--nocapture"])
+        .env("TZ", CENTRAL_EUROPE)
+        .env(LOCAL_TZ_CHILD, "1")
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("1 passed"));
+}
+
+#[test]
+fn local_time_in_central_europe() {
+    if env::var_os(LOCAL_TZ_CHILD).is_none() {
+        return;
+    }
+
+    let local = |s: &str| time_fmt::format_time(utc(s), &TimeStyle::Local);
+
+    // Clocks jump from 02:00 +01:00 to 03:00 +02:00 at 2024-03-31 01:00 UTC
+    assert_eq!(local("2024-03-31T00:59:59Z"), "2024-03-31 01:59:59");
+    assert_eq!(local("2024-03-31T01:00:00Z"), "2024-03-31 03:00:00");
+
+    // And fall back from 03:00 +02:00 to 02:00 +01:00 at 2024-10-27 01:00 UTC
+    assert_eq!(local("2024-10-27T00:59:59Z"), "2024-10-27 02:59:59");
+    assert_eq!(local("2024-10-27T01:00:00Z"), "2024-10-27 02:00:00");
+}
+
+#[test]
+fn relative_age_across_dst_change() {
+    // Only two hours pass even though local clocks jump forward by three
+    let time = utc("2024-03-31T00:30:00Z");
+    let now = utc("2024-03-31T02:30:00Z");
+    assert_eq!(time_fmt::format_relative(time, now), "2 hours ago");
+}
+
+#[test]
+fn relative_sub_minute_ages() {
+    let now = utc("2024-06-01T12:00:00Z");
+
+    assert_eq!(time_fmt::format_relative(now, now), "just now");
+    assert_eq!(
+        time_fmt::format_relative(utc("2024-06-01T11:59:59Z"), now),
+        "1 second ago"
+    );
+    assert_eq!(
+        time_fmt::format_relative(utc("2024-06-01T11:59:01Z"), now),
+        "59 seconds ago"
+    );
+    assert_eq!(
+        time_fmt::format_relative(utc("2024-06-01T11:59:00Z"), now),
+        "1 minute ago"
+    );
+}
+
+#[test]
+fn relative_larger_ages() {
+    let now = utc("2024-06-01T12:00:00Z");
+
+    assert_eq!(
+        time_fmt::format_relative(utc("2024-06-01T09:00:00Z"), now),
+        "3 hours ago"
+    );
+    assert_eq!(
+        time_fmt::format_relative(utc("2024-05-30T12:00:00Z"), now),
+        "2 days ago"
+    );
+    assert_eq!(
+        time_fmt::format_relative(utc("2024-03-01T12:00:00Z"), now),
+        "3 months ago"
+    );
+    assert_eq!(
+        time_fmt::format_relative(utc("2022-06-01T12:00:00Z"), now),
+        "2 years ago"
+    );
+    assert_eq!(
+        time_fmt::format_relative(utc("2024-06-01T12:05:00Z"), now),
+        "in the future"
+    );
+}
@@ -0,0 +1,171 @@
+use gdrive::files::transfer;
+use gdrive::hub::Hub;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+mod common;
+
+// Name, mime type and parents of every created file, in order
+type Created = Arc<Mutex<Vec<(String, String, Vec<String>)>>>;
+
+// md5 of "hello"
+const HELLO_MD5: &str = "5d41402abc4b2a76b9719d911017c592";
+
+fn metadata(body: &str) -> Option<serde_json::Value> {
+    body.lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|value| value.get("name").is_some())
+}
+
+// Stands in for both accounts. The source has a photos folder (src1) with a.txt and a google
+// document, the destination has a backup folder with a photos folder holding a.txt.
+fn drive_api(method: &str, target: &str, body: &str, created: &Created) -> (u16, String) {
+    let path = target.split('?').next().unwrap_or_default();
+
+    match (method, path) {
+        ("GET", "/drive/v3/files/src1") => (
+            200,
+            String::from(
+                r#"{"id": "src1", "name": "photos", "mimeType": "application/vnd.google-apps.folder"}"#,
+            ),
+        ),
+        ("GET", "/drive/v3/files/a1") => (200, String::from("hello")),
+        ("GET", "/drive/v3/files/doc1/export") => (200, String::from("docx")),
+        ("GET", "/drive/v3/files") if target.contains("%27src1%27") => (
+            200,
+            format!(
+                r#"{{"files": [{{"id": "a1", "name": "a.txt", "mimeType": "text/plain", "size": "5", "md5Checksum": "{}"}}, {{"id": "doc1", "name": "notes", "mimeType": "application/vnd.google-apps.document"}}]}}"#,
+                HELLO_MD5
+            ),
+        ),
+        ("GET", "/drive/v3/files") if target.contains("%27root%27") => (
+            200,
+            String::from(
+                r#"{"files": [{"id": "backup", "name": "backup", "mimeType": "application/vnd.google-apps.folder"}]}"#,
+            ),
+        ),
+        ("GET", "/drive/v3/files") if target.contains("%27backup%27") => (
+            200,
+            String::from(
+                r#"{"files": [{"id": "old-photos", "name": "photos", "mimeType": "application/vnd.google-apps.folder"}]}"#,
+            ),
+        ),
+        ("GET", "/drive/v3/files") if target.contains("%27old-photos%27") => (
+            200,
+            String::from(
+                r#"{"files": [{"id": "old-a", "name": "a.txt", "mimeType": "text/plain"}]}"#,
+            ),
+        ),
+        ("GET", "/drive/v3/files") => (200, String::from(r#"{"files": []}"#)),
+        ("POST", "/upload/drive/v3/files") => {
+            let metadata = metadata(body).unwrap_or_default();
+            let name = metadata["name"].as_str().unwrap_or_default().to_string();
+            let mime_type = metadata["mimeType"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string();
+            let parents: Vec<String> = metadata["parents"]
+                .as_array()
+                .map(|parents| {
+                    parents
+                        .iter()
+                        .filter_map(|parent| parent.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            created
+                .lock()
+                .unwrap()
+                .push((name.clone(), mime_type.clone(), parents.clone()));
+
+            let response = serde_json::json!({
+                "id": format!("{}-id", name),
+                "name": name,
+                "mimeType": mime_type,
+                "parents": parents,
+            });
+            (200, response.to_string())
+        }
+        _ => common::not_found(),
+    }
+}
+
+async fn mock_drive() -> (String, Created) {
+    let created: Created = Arc::new(Mutex::new(Vec::new()));
+    let recorded = created.clone();
+
+    let (root_url, _) = common::mock_drive(move |request| {
+        drive_api(&request.method, &request.target, &request.body, &recorded)
+    })
+    .await;
+
+    (root_url, created)
+}
+
+fn config(skip_existing: bool) -> transfer::Config {
+    transfer::Config {
+        source: String::from("src1"),
+        destination: String::from("/backup"),
+        skip_existing,
+    }
+}
+
+#[tokio::test]
+async fn folder_is_recreated_in_the_other_account() {
+    let (root_url, created) = mock_drive().await;
+    let src_hub = Hub::with_root_url(&root_url, "source-token");
+    let dst_hub = Hub::with_root_url(&root_url, "destination-token");
+
+    transfer::transfer(&src_hub, &dst_hub, config(false))
+        .await
+        .unwrap();
+
+    let created: Vec<(String, String, Vec<String>)> = created.lock().unwrap().clone();
+    let expected = [
+        ("photos", "application/vnd.google-apps.folder", "backup"),
+        ("a.txt", "text/plain", "photos-id"),
+        // Exported to docx and converted back to a google document
+        ("notes", "application/vnd.google-apps.document", "photos-id"),
+    ];
+    assert_eq!(created.len(), expected.len());
+    for ((name, mime_type, parents), (expected_name, expected_mime, expected_parent)) in
+        created.iter().zip(expected)
+    {
+        assert_eq!(name, expected_name);
+        assert_eq!(mime_type, expected_mime);
+        assert_eq!(parents, &[expected_parent]);
+    }
+}
+
+#[tokio::test]
+async fn skip_existing_only_transfers_missing_files() {
+    let (root_url, created) = mock_drive().await;
+    let src_hub = Hub::with_root_url(&root_url, "source-token");
+    let dst_hub = Hub::with_root_url(&root_url, "destination-token");
+
+    transfer::transfer(&src_hub, &dst_hub, config(true))
+        .await
+        .unwrap();
+
+    // photos and a.txt already exist, notes is added to the existing folder
+    let created = created.lock().unwrap().clone();
+    assert_eq!(created.len(), 1);
+    assert_eq!(created[0].0, "notes");
+    assert_eq!(created[0].2, ["old-photos"]);
+}
+
+#[test]
+fn report_summary_counts_skipped_files() {
+    let report = transfer::Report {
+        transferred: 2,
+        transferred_size: 2048,
+        skipped: 1,
+        unsupported: 1,
+    };
+
+    assert!(report.summary().starts_with("Transferred 2 files ("));
+    assert!(report
+        .summary()
+        .ends_with("skipped 1 existing and 1 unsupported files"));
+}
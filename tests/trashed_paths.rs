@@ -0,0 +1,41 @@
+use gdrive::files::path_utils;
+use google_drive3::api::File;
+
+fn drive_file(id: &str, trashed: bool) -> File {
+    File {
+        id: Some(id.to_string()),
+        trashed: Some(trashed),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn only_the_trashed_clause_depends_on_include_trashed() {
+    assert_eq!(
+        path_utils::path_part_query("root", "a.txt", false),
+        "'root' in parents and name = 'a.txt' and trashed = false"
+    );
+    assert_eq!(
+        path_utils::path_part_query("root", "a.txt", true),
+        "'root' in parents and name = 'a.txt'"
+    );
+}
+
+#[test]
+fn pick_prefers_the_live_file() {
+    let live_only = path_utils::pick_path_match(vec![drive_file("live", false)]).unwrap();
+    assert_eq!(live_only.file.id.as_deref(), Some("live"));
+    assert!(!live_only.trashed_duplicate);
+
+    let trashed_only = path_utils::pick_path_match(vec![drive_file("gone", true)]).unwrap();
+    assert_eq!(trashed_only.file.id.as_deref(), Some("gone"));
+    assert!(!trashed_only.trashed_duplicate);
+
+    let both =
+        path_utils::pick_path_match(vec![drive_file("gone", true), drive_file("live", false)])
+            .unwrap();
+    assert_eq!(both.file.id.as_deref(), Some("live"));
+    assert!(both.trashed_duplicate);
+
+    assert!(path_utils::pick_path_match(vec![]).is_none());
+}
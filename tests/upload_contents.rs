@@ -0,0 +1,628 @@
+use gdrive::common::delegate::ChunkSize;
+use gdrive::common::delegate::UploadDelegateConfig;
+use gdrive::common::delegate::UploadMode;
+use gdrive::common::parent_check;
+use gdrive::files::mkdir;
+use gdrive::files::shortcut;
+use gdrive::files::update;
+use gdrive::files::upload;
+use gdrive::hub::Hub;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+mod common;
+
+// Name and parents of every created file, in order
+type Created = Arc<Mutex<Vec<(String, Vec<String>)>>>;
+
+// Id and new name of the files that were updated instead of created
+type Updated = Arc<Mutex<Vec<(String, String)>>>;
+
+// Metadata part of a multipart upload body
+fn metadata(body: &str) -> Option<serde_json::Value> {
+    body.lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|value| value.get("name").is_some())
+}
+
+// Stands in for the Drive api, created files get the id "<name>-id". The destination already has
+// a photos folder (old-photos) with a.txt (old-a) in it, which only the overwrite tests look at.
+// readme.txt and c.txt were modified far in the future, a.txt long ago.
+fn drive_api(
+    method: &str,
+    target: &str,
+    body: &str,
+    created: &Created,
+    updated: &Updated,
+) -> (u16, String) {
+    let path = target.split('?').next().unwrap_or_default();
+
+    match (method, path) {
+        ("GET", "/drive/v3/files") if target.contains("%27dest%27") => (
+            200,
+            String::from(
+                r#"{"files": [{"id": "old-photos", "name": "photos", "mimeType": "application/vnd.google-apps.folder"}, {"id": "old-readme", "name": "readme.txt", "mimeType": "text/plain", "modifiedTime": "2100-01-01T00:00:00.000Z"}]}"#,
+            ),
+        ),
+        ("GET", "/drive/v3/files") if target.contains("%27old-photos%27") => (
+            200,
+            String::from(
+                r#"{"files": [{"id": "old-a", "name": "a.txt", "mimeType": "text/plain", "modifiedTime": "2001-01-01T00:00:00.000Z"}, {"id": "old-c", "name": "c.txt", "mimeType": "text/plain", "modifiedTime": "2100-01-01T00:00:00.000Z"}]}"#,
+            ),
+        ),
+        ("GET", "/drive/v3/files") => (200, String::from(r#"{"files": []}"#)),
+        ("GET", "/drive/v3/files/old-a") => (
+            200,
+            String::from(
+                r#"{"id": "old-a", "name": "a.txt", "mimeType": "text/plain", "parents": ["old-photos"]}"#,
+            ),
+        ),
+        ("GET", "/drive/v3/files/old-photos") => (
+            200,
+            String::from(
+                r#"{"id": "old-photos", "name": "photos", "mimeType": "application/vnd.google-apps.folder", "parents": ["dest"]}"#,
+            ),
+        ),
+        ("PATCH", _) if path.starts_with("/upload/drive/v3/files/") => {
+            let file_id = path.trim_start_matches("/upload/drive/v3/files/").to_string();
+            let metadata = metadata(body).unwrap_or_default();
+            let name = metadata["name"].as_str().unwrap_or_default().to_string();
+            updated.lock().unwrap().push((file_id.clone(), name.clone()));
+
+            let response = serde_json::json!({ "id": file_id, "name": name });
+            (200, response.to_string())
+        }
+        ("GET", "/drive/v3/files/generateIds") => {
+            let ids: Vec<String> = (0..50).map(|i| format!("gen{}", i)).collect();
+            (200, serde_json::json!({ "ids": ids }).to_string())
+        }
+        ("POST", "/upload/drive/v3/files") => {
+            let metadata = metadata(body).unwrap_or_default();
+            let name = metadata["name"].as_str().unwrap_or_default().to_string();
+            let parents: Vec<String> = metadata["parents"]
+                .as_array()
+                .map(|parents| {
+                    parents
+                        .iter()
+                        .filter_map(|parent| parent.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            created.lock().unwrap().push((name.clone(), parents.clone()));
+
+            let response = serde_json::json!({
+                "id": format!("{}-id", name),
+                "name": name,
+                "parents": parents,
+            });
+            (200, response.to_string())
+        }
+        _ => common::not_found(),
+    }
+}
+
+async fn mock_drive() -> (String, Created) {
+    let (root_url, created, _) = mock_drive_with_updates().await;
+    (root_url, created)
+}
+
+async fn mock_drive_with_updates() -> (String, Created, Updated) {
+    let created: Created = Arc::new(Mutex::new(Vec::new()));
+    let updated: Updated = Arc::new(Mutex::new(Vec::new()));
+    let recorded = created.clone();
+    let recorded_updates = updated.clone();
+
+    let (root_url, _) = common::mock_drive(move |request| {
+        drive_api(
+            &request.method,
+            &request.target,
+            &request.body,
+            &recorded,
+            &recorded_updates,
+        )
+    })
+    .await;
+
+    (root_url, created, updated)
+}
+
+// photos/a.txt and photos/albums/
+fn local_tree(dir: &Path) -> PathBuf {
+    let root = dir.join("photos");
+    fs::create_dir_all(root.join("albums")).unwrap();
+    fs::write(root.join("a.txt"), "a").unwrap();
+    root
+}
+
+fn config(file_path: PathBuf, contents_only: bool) -> upload::Config {
+    upload::Config {
+        file_path,
+        mime_type: None,
+        parents: Some(vec![String::from("dest")]),
+        chunk_size: ChunkSize::default(),
+        print_chunk_errors: false,
+        print_chunk_info: false,
+        upload_directories: true,
+        print_only_id: false,
+        error_log: None,
+        truncate_error_log: false,
+        max_retry_duration: Duration::from_secs(60),
+        upload_mode: UploadMode::default(),
+        continue_on_error: false,
+        error_manifest: None,
+        retry_manifest: None,
+        skip_parent_check: true,
+        create_parents: false,
+        json: true,
+        contents_only: false,
+        root_name: None,
+        strict: false,
+        overwrite: false,
+        mtime_from_git: false,
+        if_newer: false,
+        dedupe: None,
+    }
+    .with_contents_only(contents_only)
+}
+
+fn parents_of<'a>(created: &'a [(String, Vec<String>)], name: &str) -> &'a [String] {
+    &created.iter().find(|(n, _)| n == name).unwrap().1
+}
+
+#[tokio::test]
+async fn directory_is_created_in_the_destination() {
+    let (root_url, created) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+    let dir = tempfile::tempdir().unwrap();
+    let root = local_tree(dir.path());
+
+    let report = upload::upload_directory(&hub, &config(root, false), Default::default())
+        .await
+        .unwrap();
+
+    let created = created.lock().unwrap().clone();
+    assert_eq!(parents_of(&created, "photos"), ["dest"]);
+    assert_eq!(parents_of(&created, "albums"), ["photos-id"]);
+    assert_eq!(parents_of(&created, "a.txt"), ["photos-id"]);
+    assert_eq!(report.folders.len(), 2);
+}
+
+#[tokio::test]
+async fn contents_are_uploaded_into_the_destination() {
+    let (root_url, created) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+    let dir = tempfile::tempdir().unwrap();
+    let root = local_tree(dir.path());
+
+    let report = upload::upload_directory(&hub, &config(root, true), Default::default())
+        .await
+        .unwrap();
+
+    let created = created.lock().unwrap().clone();
+    assert!(created.iter().all(|(name, _)| name != "photos"));
+    assert_eq!(parents_of(&created, "albums"), ["dest"]);
+    assert_eq!(parents_of(&created, "a.txt"), ["dest"]);
+    assert_eq!(report.folders.len(), 1);
+
+    // Folders directly in the contents are reported in the destination
+    let json = report.to_json(&[String::from("dest")]);
+    let albums = json.iter().find(|file| file.name == "albums").unwrap();
+    assert_eq!(albums.parents, vec![String::from("dest")]);
+}
+
+#[test]
+fn trailing_slash_means_contents() {
+    assert!(upload::has_trailing_separator(Path::new("photos/")));
+    assert!(upload::has_trailing_separator(Path::new("./")));
+    assert!(!upload::has_trailing_separator(Path::new("photos")));
+    assert!(!upload::has_trailing_separator(Path::new(".")));
+
+    assert!(config(PathBuf::from("photos/"), false).contents_only);
+    assert!(config(PathBuf::from("photos"), true).contents_only);
+    assert!(!config(PathBuf::from("photos"), false).contents_only);
+}
+
+#[test]
+fn root_folder_is_named_after_the_canonical_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = local_tree(dir.path());
+    let sibling = dir.path().join("sibling");
+    fs::create_dir_all(&sibling).unwrap();
+
+    let name = |path: &Path| upload::root_folder_name(path, None).unwrap();
+
+    assert_eq!(name(&root), "photos");
+    assert_eq!(name(&root.join(".")), "photos");
+    assert_eq!(name(&root.join("./")), "photos");
+    assert_eq!(name(&root.join("albums/..")), "photos");
+    assert_eq!(name(&root.join("../sibling")), "sibling");
+
+    let cwd = std::env::current_dir().unwrap();
+    let cwd_name = cwd.file_name().unwrap().to_string_lossy().to_string();
+    assert_eq!(name(Path::new(".")), cwd_name);
+    assert_eq!(name(Path::new("./")), cwd_name);
+}
+
+#[test]
+fn root_folder_name_can_be_overridden() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = local_tree(dir.path());
+
+    assert_eq!(
+        upload::root_folder_name(&root, Some("Backup 2024")).unwrap(),
+        "Backup 2024"
+    );
+}
+
+#[test]
+fn filesystem_root_is_rejected() {
+    let result = upload::root_folder_name(Path::new("/"), None);
+    assert!(matches!(result, Err(upload::Error::FilesystemRoot)));
+}
+
+#[tokio::test]
+async fn renamed_root_folder_is_created_and_reported() {
+    let (root_url, created) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+    let dir = tempfile::tempdir().unwrap();
+    let root = local_tree(dir.path());
+
+    let config = upload::Config {
+        root_name: Some(String::from("Backup")),
+        ..config(root.join("."), false)
+    };
+    let report = upload::upload_directory(&hub, &config, Default::default())
+        .await
+        .unwrap();
+
+    let created = created.lock().unwrap().clone();
+    assert_eq!(parents_of(&created, "Backup"), ["dest"]);
+    assert_eq!(parents_of(&created, "a.txt"), ["Backup-id"]);
+
+    let json = report.to_json(&[String::from("dest")]);
+    assert!(json.iter().any(|file| file.name == "Backup"));
+    assert!(json.iter().any(|file| file.name == "albums"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn special_files_are_skipped() {
+    let (root_url, created) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+    let dir = tempfile::tempdir().unwrap();
+    let root = local_tree(dir.path());
+    fs::write(root.join("empty.txt"), "").unwrap();
+    let _listener = std::os::unix::net::UnixListener::bind(root.join("socket")).unwrap();
+
+    let report = upload::upload_directory(&hub, &config(root, false), Default::default())
+        .await
+        .unwrap();
+
+    let created = created.lock().unwrap().clone();
+    assert_eq!(parents_of(&created, "empty.txt"), ["photos-id"]);
+    assert!(created.iter().all(|(name, _)| name != "socket"));
+    assert_eq!(report.skipped.len(), 1);
+    assert!(report.summary().ends_with("skipped 1 special files"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn special_files_fail_a_strict_upload() {
+    let (root_url, created) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+    let dir = tempfile::tempdir().unwrap();
+    let root = local_tree(dir.path());
+    let _listener = std::os::unix::net::UnixListener::bind(root.join("socket")).unwrap();
+
+    let config = upload::Config {
+        strict: true,
+        ..config(root, false)
+    };
+    let result = upload::upload_directory(&hub, &config, Default::default()).await;
+
+    assert!(matches!(result, Err(upload::Error::SpecialFiles(1))));
+    assert!(created.lock().unwrap().is_empty());
+}
+
+fn updated_ids(updated: &Updated) -> Vec<String> {
+    updated.lock().unwrap().iter().map(|(id, _)| id.clone()).collect()
+}
+
+// Simple uploads keep the mock to a single request per file
+fn simple_uploads() -> UploadDelegateConfig {
+    UploadDelegateConfig {
+        upload_mode: UploadMode::Simple,
+        ..UploadDelegateConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn overwrite_updates_existing_files_and_merges_folders() {
+    let (root_url, created, updated) = mock_drive_with_updates().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+    let dir = tempfile::tempdir().unwrap();
+    let root = local_tree(dir.path());
+    fs::write(root.join("b.txt"), "b").unwrap();
+
+    let config = upload::Config {
+        overwrite: true,
+        ..config(root, false)
+    };
+    let report = upload::upload_directory(&hub, &config, simple_uploads())
+        .await
+        .unwrap();
+
+    // The existing folder and file keep their ids
+    assert_eq!(updated_ids(&updated), vec!["old-a"]);
+    assert!(report.folders.contains(&(PathBuf::from("photos"), String::from("old-photos"))));
+
+    // Everything else is created in the existing folder
+    let created = created.lock().unwrap().clone();
+    assert!(created.iter().all(|(name, _)| name != "photos" && name != "a.txt"));
+    assert_eq!(parents_of(&created, "albums"), ["old-photos"]);
+    assert_eq!(parents_of(&created, "b.txt"), ["old-photos"]);
+}
+
+#[tokio::test]
+async fn overwrite_updates_a_single_file() {
+    let (root_url, created, updated) = mock_drive_with_updates().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+    let dir = tempfile::tempdir().unwrap();
+    let readme = dir.path().join("readme.txt");
+    fs::write(&readme, "readme").unwrap();
+
+    let config = upload::Config {
+        overwrite: true,
+        upload_directories: false,
+        upload_mode: UploadMode::Simple,
+        ..config(readme, false)
+    };
+    upload::upload(&hub, config).await.unwrap();
+
+    assert_eq!(updated_ids(&updated), vec!["old-readme"]);
+    assert!(created.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn if_newer_skips_a_single_file_that_is_up_to_date() {
+    let (root_url, created, updated) = mock_drive_with_updates().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+    let dir = tempfile::tempdir().unwrap();
+    let readme = dir.path().join("readme.txt");
+    fs::write(&readme, "readme").unwrap();
+
+    let config = upload::Config {
+        if_newer: true,
+        upload_directories: false,
+        upload_mode: UploadMode::Simple,
+        ..config(readme, false)
+    };
+    upload::upload(&hub, config).await.unwrap();
+
+    assert!(updated.lock().unwrap().is_empty());
+    assert!(created.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn if_newer_only_uploads_files_newer_than_the_remote_file() {
+    let (root_url, created, updated) = mock_drive_with_updates().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+    let dir = tempfile::tempdir().unwrap();
+    let root = local_tree(dir.path());
+    fs::write(root.join("b.txt"), "b").unwrap();
+    fs::write(root.join("c.txt"), "c").unwrap();
+
+    let config = upload::Config {
+        if_newer: true,
+        ..config(root, false)
+    };
+    let report = upload::upload_directory(&hub, &config, simple_uploads())
+        .await
+        .unwrap();
+
+    // a.txt is older remotely and updated, c.txt is newer remotely and skipped
+    assert_eq!(updated_ids(&updated), vec!["old-a"]);
+    let created = created.lock().unwrap().clone();
+    assert_eq!(parents_of(&created, "b.txt"), ["old-photos"]);
+    assert!(created.iter().all(|(name, _)| name != "c.txt"));
+
+    assert_eq!(report.up_to_date, 1);
+    assert!(report.summary().ends_with("skipped 1 up to date files"));
+}
+
+#[test]
+fn existing_files_match_on_name_and_type() {
+    let folder = google_drive3::api::File {
+        id: Some(String::from("folder1")),
+        name: Some(String::from("photos")),
+        mime_type: Some(String::from("application/vnd.google-apps.folder")),
+        ..google_drive3::api::File::default()
+    };
+    let file = google_drive3::api::File {
+        id: Some(String::from("file1")),
+        name: Some(String::from("photos")),
+        mime_type: Some(String::from("text/plain")),
+        ..google_drive3::api::File::default()
+    };
+    let remote_files = vec![folder, file];
+
+    let found = |name: &str, is_dir: bool| {
+        upload::find_existing(&remote_files, name, is_dir).and_then(|file| file.id.clone())
+    };
+
+    assert_eq!(found("photos", true).as_deref(), Some("folder1"));
+    assert_eq!(found("photos", false).as_deref(), Some("file1"));
+    assert_eq!(found("albums", true), None);
+}
+
+fn update_config(file_id: &str, file_path: PathBuf, name: Option<&str>) -> update::Config {
+    update::Config {
+        file_id: file_id.to_string(),
+        file_path: Some(file_path),
+        mime_type: None,
+        chunk_size: ChunkSize::default(),
+        print_chunk_errors: false,
+        print_chunk_info: false,
+        error_log: None,
+        truncate_error_log: false,
+        max_retry_duration: Duration::from_secs(60),
+        upload_mode: UploadMode::Simple,
+        json: false,
+        print_only_id: true,
+        name: name.map(String::from),
+    }
+}
+
+#[tokio::test]
+async fn update_keeps_the_remote_name_or_renames() {
+    let (root_url, _, updated) = mock_drive_with_updates().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+    let dir = tempfile::tempdir().unwrap();
+    let local = dir.path().join("local-copy.txt");
+    fs::write(&local, "new content").unwrap();
+
+    update::update(&hub, update_config("old-a", local.clone(), None))
+        .await
+        .unwrap();
+    update::update(&hub, update_config("old-a", local, Some("renamed.txt")))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        *updated.lock().unwrap(),
+        vec![
+            (String::from("old-a"), String::from("a.txt")),
+            (String::from("old-a"), String::from("renamed.txt")),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn update_refuses_folders() {
+    let (root_url, _, updated) = mock_drive_with_updates().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+    let dir = tempfile::tempdir().unwrap();
+    let local = dir.path().join("a.txt");
+    fs::write(&local, "a").unwrap();
+
+    let result = update::update(&hub, update_config("old-photos", local, None)).await;
+
+    assert!(matches!(result, Err(update::Error::IsDirectory(_))));
+    assert!(updated.lock().unwrap().is_empty());
+}
+
+
+#[test]
+fn delegate_config_keeps_upload_flags() {
+    let config = upload::Config {
+        chunk_size: ChunkSize::Fixed(4 * 1024 * 1024),
+        print_chunk_errors: true,
+        max_retry_duration: Duration::from_secs(5),
+        ..config(PathBuf::from("photos"), false)
+    };
+
+    // Both the plain and the overwrite checked upload use this for every file
+    let delegate_config = config.delegate_config();
+    assert_eq!(delegate_config.chunk_size.in_bytes(), 4 * 1024 * 1024);
+    assert!(delegate_config.print_chunk_errors);
+    assert!(!delegate_config.print_chunk_info);
+    assert_eq!(
+        delegate_config.backoff_config.max_retry_duration,
+        Duration::from_secs(5)
+    );
+}
+
+#[tokio::test]
+async fn shortcut_is_created_in_the_parent() {
+    let (root_url, created) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    let file = shortcut::create_shortcut(&hub, "old-a", "a link", Some(vec![String::from("dest")]))
+        .await
+        .unwrap();
+
+    assert_eq!(file.id.as_deref(), Some("a link-id"));
+    assert_eq!(parents_of(&created.lock().unwrap(), "a link"), ["dest"]);
+}
+
+#[tokio::test]
+async fn upload_creates_parent_paths_with_create_parents() {
+    let (root_url, created) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+    let dir = tempfile::tempdir().unwrap();
+    let readme = dir.path().join("readme.txt");
+    fs::write(&readme, "readme").unwrap();
+
+    let config = upload::Config {
+        parents: Some(vec![String::from("/archive")]),
+        create_parents: true,
+        upload_directories: false,
+        upload_mode: UploadMode::Simple,
+        ..config(readme, false)
+    };
+    upload::upload(&hub, config).await.unwrap();
+
+    let created = created.lock().unwrap().clone();
+    assert_eq!(parents_of(&created, "archive"), ["root"]);
+    assert_eq!(parents_of(&created, "readme.txt"), ["archive-id"]);
+}
+
+#[tokio::test]
+async fn mkdir_creates_parent_paths_with_create_parents() {
+    let (root_url, created) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    let config = mkdir::Config {
+        id: None,
+        name: String::from("2024"),
+        parents: Some(vec![String::from("/archive")]),
+        print_only_id: true,
+        skip_parent_check: false,
+        create_parents: true,
+    };
+    mkdir::mkdir(&hub, config).await.unwrap();
+
+    let created = created.lock().unwrap().clone();
+    assert_eq!(parents_of(&created, "archive"), ["root"]);
+    assert_eq!(parents_of(&created, "2024"), ["archive-id"]);
+}
+
+#[tokio::test]
+async fn missing_parent_path_is_named_in_the_error() {
+    let (root_url, created) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    let parents = [String::from("dest"), String::from("/missing")];
+    let err = parent_check::resolve_parents(&hub, &parents, false, false)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(&err, parent_check::Error::ResolvePath(path, _) if path == "/missing"));
+    assert!(err.to_string().contains("'/missing'"));
+    assert!(created.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn parent_id_that_is_not_a_folder_is_named_in_the_error() {
+    let (root_url, _) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    let parents = [String::from("old-photos"), String::from("old-a")];
+    let err = parent_check::resolve_parents(&hub, &parents, false, true)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(&err, parent_check::Error::NotFolder(id, _, _) if id == "old-a"));
+    assert!(err.to_string().contains("(old-a)"));
+
+    // Ids are used as they are without the check
+    let ids = parent_check::resolve_parents(&hub, &parents, false, false)
+        .await
+        .unwrap();
+    assert_eq!(ids, parents);
+}
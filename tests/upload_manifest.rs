@@ -0,0 +1,30 @@
+use gdrive::common::upload_manifest::FailedFile;
+use gdrive::common::upload_manifest::UploadManifest;
+use std::path::PathBuf;
+
+#[test]
+fn upload_manifest_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("failures.json");
+
+    let mut manifest = UploadManifest::default();
+    manifest.push(FailedFile {
+        path: PathBuf::from("/data/photos/unreadable.jpg"),
+        parent_id: String::from("1AbCdEf"),
+        error: String::from("Permission denied (os error 13)"),
+    });
+    manifest.save(&path).unwrap();
+
+    let loaded = UploadManifest::load(&path).unwrap();
+    assert_eq!(loaded, manifest);
+    assert_eq!(loaded.len(), 1);
+}
+
+#[test]
+fn upload_manifest_load_invalid_json_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("failures.json");
+    std::fs::write(&path, "not json").unwrap();
+
+    assert!(UploadManifest::load(&path).is_err());
+}
@@ -0,0 +1,35 @@
+use gdrive::common::delegate::ChunkSize;
+use gdrive::common::delegate::UploadDelegateConfig;
+use gdrive::common::delegate::UploadMode;
+
+const CHUNK: u64 = 256 * 1024;
+
+fn config(upload_mode: UploadMode) -> UploadDelegateConfig {
+    UploadDelegateConfig {
+        chunk_size: ChunkSize::Fixed(CHUNK),
+        upload_mode,
+        ..UploadDelegateConfig::default()
+    }
+}
+
+#[test]
+fn auto_mode_resumes_files_larger_than_a_chunk() {
+    let auto = config(UploadMode::Auto);
+
+    assert!(!auto.is_resumable(1));
+    assert!(!auto.is_resumable(CHUNK));
+    assert!(auto.is_resumable(CHUNK + 1));
+}
+
+#[test]
+fn forced_modes_ignore_the_size() {
+    assert!(config(UploadMode::Resumable).is_resumable(1));
+    assert!(!config(UploadMode::Simple).is_resumable(CHUNK + 1));
+}
+
+#[test]
+fn empty_files_are_never_resumable() {
+    for mode in [UploadMode::Auto, UploadMode::Resumable, UploadMode::Simple] {
+        assert!(!config(mode).is_resumable(0));
+    }
+}
@@ -0,0 +1,201 @@
+use gdrive::common::delegate::UploadDelegateConfig;
+use gdrive::common::file_info::FileInfo;
+use gdrive::common::parent_check;
+use gdrive::files::upload;
+use gdrive::hub::Hub;
+use std::io::Cursor;
+
+mod common;
+
+use common::Requests;
+
+const FOLDER: &str = r#"{"mimeType": "application/vnd.google-apps.folder"}"#;
+
+// Stands in for the Drive api
+fn drive_api(method: &str, target: &str) -> (u16, String) {
+    let path = target.split('?').next().unwrap_or_default();
+
+    match (method, path) {
+        ("GET", "/drive/v3/files/root") => (200, with_id(FOLDER, "0AFolderRoot")),
+        ("GET", "/drive/v3/files/folderA") => (200, with_id(FOLDER, "folderA")),
+        ("GET", "/drive/v3/files/folderB") => (200, with_id(FOLDER, "folderB")),
+        ("GET", "/drive/v3/files/report.txt") => (
+            200,
+            String::from(r#"{"id": "report.txt", "name": "Q3 report", "mimeType": "text/plain"}"#),
+        ),
+        ("GET", _) => common::not_found(),
+        ("POST", "/upload/drive/v3/files") => (
+            200,
+            String::from(r#"{"id": "file1", "name": "report.txt", "parents": ["folderA"]}"#),
+        ),
+        ("PATCH", "/drive/v3/files/file1") if target.contains("addParents=folderB") => (
+            200,
+            String::from(
+                r#"{"id": "file1", "name": "report.txt", "parents": ["folderA", "folderB"]}"#,
+            ),
+        ),
+        ("PATCH", "/drive/v3/files/file1") if target.contains("addParents=folderC") => (
+            200,
+            String::from(
+                r#"{"id": "file1", "name": "report.txt", "parents": ["folderA", "folderB", "folderC"]}"#,
+            ),
+        ),
+        _ => (
+            403,
+            String::from(
+                r#"{"error": {"errors": [{"reason": "cannotAddParent", "message": "Cannot add parent"}], "code": 403, "message": "Cannot add parent"}}"#,
+            ),
+        ),
+    }
+}
+
+fn with_id(json: &str, id: &str) -> String {
+    let mut value: serde_json::Value = serde_json::from_str(json).unwrap();
+    value["id"] = serde_json::Value::from(id);
+    value.to_string()
+}
+
+async fn mock_drive() -> (String, Requests) {
+    common::mock_drive(|request| drive_api(&request.method, &request.target)).await
+}
+
+fn report_info(parents: &[&str]) -> FileInfo {
+    FileInfo {
+        name: String::from("report.txt"),
+        mime_type: mime::TEXT_PLAIN,
+        parents: Some(parents.iter().map(|p| p.to_string()).collect()),
+        size: 5,
+    }
+}
+
+#[tokio::test]
+async fn upload_file_adds_additional_parents() {
+    let (root_url, requests) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    let file = upload::upload_file(
+        &hub,
+        Cursor::new(b"hello".to_vec()),
+        None,
+        report_info(&["folderA", "folderB", "folderC"]),
+        UploadDelegateConfig::default(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        file.parents,
+        Some(vec![
+            String::from("folderA"),
+            String::from("folderB"),
+            String::from("folderC")
+        ])
+    );
+
+    let requests = requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 3);
+
+    // Drive only accepts a single parent when the file is created
+    assert_eq!(requests[0].method, "POST");
+    assert!(requests[0].body.contains(r#""parents":["folderA"]"#));
+
+    assert_eq!(requests[1].method, "PATCH");
+    assert!(requests[1].target.contains("addParents=folderB"));
+    assert_eq!(requests[2].method, "PATCH");
+    assert!(requests[2].target.contains("addParents=folderC"));
+}
+
+#[tokio::test]
+async fn upload_file_reports_parents_that_could_not_be_added() {
+    let (root_url, _) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+    let parents = vec![String::from("folderA"), String::from("folderD")];
+
+    let file = upload::upload_file(
+        &hub,
+        Cursor::new(b"hello".to_vec()),
+        None,
+        report_info(&["folderA", "folderD"]),
+        UploadDelegateConfig::default(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(file.id, Some(String::from("file1")));
+    assert_eq!(
+        upload::missing_parents(&parents, &file),
+        vec![String::from("folderD")]
+    );
+}
+
+#[tokio::test]
+async fn upload_file_with_single_parent_does_not_update() {
+    let (root_url, requests) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    upload::upload_file(
+        &hub,
+        Cursor::new(b"hello".to_vec()),
+        None,
+        report_info(&["folderA"]),
+        UploadDelegateConfig::default(),
+    )
+    .await
+    .unwrap();
+
+    let requests = requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "POST");
+}
+
+#[tokio::test]
+async fn validate_parents_resolves_ids() {
+    let (root_url, _) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    let ids = parent_check::validate_parents(&hub, &[String::from("root"), String::from("folderB")])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        ids,
+        vec![String::from("0AFolderRoot"), String::from("folderB")]
+    );
+}
+
+#[tokio::test]
+async fn validate_parents_rejects_missing_parent() {
+    let (root_url, _) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    let result =
+        parent_check::validate_parents(&hub, &[String::from("folderA"), String::from("missing")]).await;
+
+    assert!(matches!(result, Err(parent_check::Error::NotFound(id)) if id == "missing"));
+}
+
+#[tokio::test]
+async fn validate_parents_rejects_file_parent() {
+    let (root_url, _) = mock_drive().await;
+    let hub = Hub::with_root_url(&root_url, "test-token");
+
+    let result = parent_check::validate_parents(&hub, &[String::from("report.txt")]).await;
+
+    assert!(matches!(
+        result,
+        Err(parent_check::Error::NotFolder(id, name, mime_type))
+            if id == "report.txt" && name == "Q3 report" && mime_type == "text/plain"
+    ));
+}
+
+#[test]
+fn split_parents_keeps_first_for_creation() {
+    let (first, rest) = upload::split_parents(Some(vec![
+        String::from("folderA"),
+        String::from("folderB"),
+    ]));
+
+    assert_eq!(first, Some(String::from("folderA")));
+    assert_eq!(rest, vec![String::from("folderB")]);
+    assert_eq!(upload::split_parents(None), (None, vec![]));
+}
@@ -0,0 +1,61 @@
+use gdrive::common::file_info;
+use gdrive::common::file_info::FileInfo;
+use std::fs;
+use std::io::Read;
+use std::io::Write;
+
+#[test]
+fn read_buffer_grows_with_the_chunk_size() {
+    // BufReader defaults to 8 KiB, uploads read at least 64 KiB at a time
+    assert_eq!(file_info::read_buffer_size(0), 64 * 1024);
+    assert_eq!(
+        file_info::read_buffer_size(16 * 1024 * 1024),
+        4 * 1024 * 1024
+    );
+    assert_eq!(
+        file_info::read_buffer_size(1024 * 1024 * 1024),
+        8 * 1024 * 1024
+    );
+}
+
+#[test]
+fn upload_reads_the_same_handle_from_the_start() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.txt");
+    fs::write(&path, "hello world").unwrap();
+
+    let mut file = fs::File::open(&path).unwrap();
+    let info = FileInfo::from_file(
+        &file,
+        &file_info::Config {
+            file_path: path.clone(),
+            mime_type: None,
+            parents: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(info.size, 11);
+
+    let mut start = [0; 5];
+    file.read_exact(&mut start).unwrap();
+
+    let mut reader = file_info::upload_reader(file, 8 * 1024 * 1024).unwrap();
+    let mut content = String::new();
+    reader.read_to_string(&mut content).unwrap();
+    assert_eq!(content, "hello world");
+}
+
+#[test]
+fn size_change_of_the_open_file_is_detected() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("log.txt");
+    fs::write(&path, "12345").unwrap();
+
+    let file = fs::File::open(&path).unwrap();
+    assert_eq!(file_info::changed_size(&file, 5), None);
+
+    let mut writer = fs::OpenOptions::new().append(true).open(&path).unwrap();
+    writer.write_all(b"678").unwrap();
+
+    assert_eq!(file_info::changed_size(&file, 5), Some(8));
+}
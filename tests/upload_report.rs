@@ -0,0 +1,117 @@
+use gdrive::common::upload_manifest::FailedFile;
+use gdrive::files::upload::UploadReport;
+use std::path::PathBuf;
+
+fn drive_file(id: &str, name: &str, size: i64, parent_id: &str) -> google_drive3::api::File {
+    google_drive3::api::File {
+        id: Some(id.to_string()),
+        name: Some(name.to_string()),
+        mime_type: Some(String::from("text/plain")),
+        size: Some(size),
+        parents: Some(vec![parent_id.to_string()]),
+        ..google_drive3::api::File::default()
+    }
+}
+
+// Records what upload_directory does for photos/ with a nested 2024/ folder, where one file fails
+fn mocked_upload() -> UploadReport {
+    let mut report = UploadReport::default();
+
+    report
+        .folders
+        .push((PathBuf::from("photos"), String::from("1Photos")));
+    report
+        .folders
+        .push((PathBuf::from("photos/2024"), String::from("1Year")));
+
+    report.files.push((
+        PathBuf::from("photos/index.txt"),
+        drive_file("1Index", "index.txt", 120, "1Photos"),
+    ));
+    report.files.push((
+        PathBuf::from("photos/2024/cat.txt"),
+        drive_file("1Cat", "cat.txt", 5 * 1024 * 1024 * 1024, "1Year"),
+    ));
+
+    report.failed.push(FailedFile {
+        path: PathBuf::from("/home/user/photos/2024/dog.txt"),
+        parent_id: String::from("1Year"),
+        error: String::from("Failed to upload file: connection reset"),
+    });
+
+    report
+}
+
+#[test]
+fn report_maps_paths_to_ids() {
+    let report = mocked_upload();
+
+    let folders: Vec<(&str, &str)> = report
+        .folders
+        .iter()
+        .map(|(path, id)| (path.to_str().unwrap(), id.as_str()))
+        .collect();
+    assert_eq!(folders, vec![("photos", "1Photos"), ("photos/2024", "1Year")]);
+
+    let files: Vec<(&str, &str)> = report
+        .files
+        .iter()
+        .map(|(path, file)| (path.to_str().unwrap(), file.id.as_deref().unwrap()))
+        .collect();
+    assert_eq!(
+        files,
+        vec![("photos/index.txt", "1Index"), ("photos/2024/cat.txt", "1Cat")]
+    );
+}
+
+#[test]
+fn report_keeps_completed_entries_next_to_failures() {
+    let report = mocked_upload();
+
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.files.len(), 2);
+    assert!(report
+        .summary()
+        .starts_with("Uploaded 2 files in 2 directories with a total size of "));
+    assert_eq!(report.total_file_size(), 5 * 1024 * 1024 * 1024 + 120);
+}
+
+#[test]
+fn summary_counts_files_already_in_the_drive() {
+    let report = UploadReport {
+        duplicates: 3,
+        ..mocked_upload()
+    };
+
+    assert!(report
+        .summary()
+        .ends_with(", skipped 3 files already in the drive"));
+}
+
+#[test]
+fn report_json_includes_folder_parents() {
+    let json = mocked_upload().to_json(&[String::from("0ARoot")]);
+
+    let entries: Vec<(&str, &str, Vec<String>)> = json
+        .iter()
+        .map(|entry| {
+            (
+                entry.path.as_deref().unwrap(),
+                entry.id.as_str(),
+                entry.parents.clone(),
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        entries,
+        vec![
+            ("photos", "1Photos", vec![String::from("0ARoot")]),
+            ("photos/2024", "1Year", vec![String::from("1Photos")]),
+            ("photos/index.txt", "1Index", vec![String::from("1Photos")]),
+            ("photos/2024/cat.txt", "1Cat", vec![String::from("1Year")]),
+        ]
+    );
+    assert_eq!(json[1].mime_type, "application/vnd.google-apps.folder");
+    assert_eq!(json[1].name, "2024");
+}
@@ -0,0 +1,53 @@
+use gdrive::version;
+use gdrive::version::VersionInfo;
+
+#[test]
+fn dirty_flag_is_parsed() {
+    assert_eq!(version::parse_dirty("true"), Some(true));
+    assert_eq!(version::parse_dirty("false"), Some(false));
+    assert_eq!(version::parse_dirty("unknown"), None);
+}
+
+#[test]
+fn features_are_comma_separated() {
+    assert!(version::parse_features("").is_empty());
+    assert_eq!(version::parse_features("a,b-c"), ["a", "b-c"]);
+}
+
+#[test]
+fn current_version_has_build_metadata() {
+    let info = VersionInfo::current();
+    assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+    assert!(!info.commit.is_empty());
+    assert!(!info.target.is_empty());
+
+    let json = serde_json::to_value(&info).unwrap();
+    for field in [
+        "name",
+        "version",
+        "commit",
+        "dirty",
+        "buildDate",
+        "rustc",
+        "target",
+        "features",
+    ] {
+        assert!(json.get(field).is_some(), "missing {}", field);
+    }
+}
+
+#[test]
+fn dirty_commits_are_marked() {
+    let info = VersionInfo {
+        commit: String::from("abc123"),
+        dirty: Some(true),
+        ..VersionInfo::current()
+    };
+    assert_eq!(info.commit_description(), "abc123 (dirty)");
+
+    let info = VersionInfo {
+        dirty: None,
+        ..info
+    };
+    assert_eq!(info.commit_description(), "abc123");
+}
@@ -0,0 +1,75 @@
+use gdrive::files::watch::plan;
+use gdrive::files::watch::Action;
+use gdrive::files::watch::Pending;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+
+fn paths(paths: &[&str]) -> Vec<PathBuf> {
+    paths.iter().map(PathBuf::from).collect()
+}
+
+#[test]
+fn changes_are_due_after_the_debounce_time() {
+    let debounce = Duration::from_secs(2);
+    let start = Instant::now();
+    let mut pending = Pending::default();
+
+    pending.record(PathBuf::from("b.txt"), start);
+    pending.record(PathBuf::from("a.txt"), start);
+    pending.record(PathBuf::from("c.txt"), start + Duration::from_secs(1));
+    assert!(pending
+        .take_due(start + Duration::from_secs(1), debounce)
+        .is_empty());
+
+    // Another event for a.txt starts its wait over
+    pending.record(PathBuf::from("a.txt"), start + Duration::from_millis(1500));
+    assert_eq!(
+        pending.take_due(start + Duration::from_secs(2), debounce),
+        paths(&["b.txt"])
+    );
+    assert_eq!(pending.len(), 2);
+
+    assert_eq!(pending.take_all(), paths(&["a.txt", "c.txt"]));
+    assert!(pending.is_empty());
+}
+
+#[test]
+fn new_folder_is_pushed_with_its_contents() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("photos/2024")).unwrap();
+    fs::write(dir.path().join("photos/a.jpg"), "a").unwrap();
+    fs::write(dir.path().join("photos/2024/b.jpg"), "b").unwrap();
+    fs::write(dir.path().join("notes.txt"), "notes").unwrap();
+
+    let changed = paths(&["photos/a.jpg", "notes.txt", "photos", "photos/2024/b.jpg"]);
+    let actions = plan(dir.path(), &changed, false);
+
+    assert_eq!(
+        actions,
+        vec![
+            Action::Upload(PathBuf::from("notes.txt")),
+            Action::CreateFolder(PathBuf::from("photos")),
+            Action::CreateFolder(PathBuf::from("photos/2024")),
+            Action::Upload(PathBuf::from("photos/2024/b.jpg")),
+            Action::Upload(PathBuf::from("photos/a.jpg")),
+        ]
+    );
+}
+
+#[test]
+fn deletes_are_only_pushed_with_propagate_deletes() {
+    let dir = tempfile::tempdir().unwrap();
+    let deleted = paths(&["old", "old/a.txt", "gone.txt"]);
+
+    assert!(plan(dir.path(), &deleted, false).is_empty());
+    assert_eq!(
+        plan(dir.path(), &deleted, true),
+        vec![
+            Action::Trash(PathBuf::from("gone.txt")),
+            // Trashing the folder trashes what was in it
+            Action::Trash(PathBuf::from("old")),
+        ]
+    );
+}
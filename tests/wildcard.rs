@@ -0,0 +1,146 @@
+use gdrive::common::path_filter::PathFilter;
+use gdrive::files::path_utils::has_wildcard;
+use gdrive::files::path_utils::wildcard_to_regex;
+use regex::Regex;
+use std::path::Path;
+
+fn wildcard(pattern: &str) -> Regex {
+    Regex::new(&wildcard_to_regex(pattern)).unwrap()
+}
+
+fn assert_matches(pattern: &str, matching: &[&str], not_matching: &[&str]) {
+    let regex = wildcard(pattern);
+    for s in matching {
+        assert!(regex.is_match(s), "{} should match {}", pattern, s);
+    }
+    for s in not_matching {
+        assert!(!regex.is_match(s), "{} should not match {}", pattern, s);
+    }
+}
+
+#[test]
+fn star_and_question_mark_stay_within_a_segment() {
+    assert_matches("*.txt", &["a.txt", ".txt"], &["a/b.txt", "a.txt.bak"]);
+    assert_matches(
+        "file?.pdf",
+        &["file1.pdf"],
+        &["file.pdf", "file12.pdf", "file/.pdf"],
+    );
+}
+
+#[test]
+fn double_star_crosses_segments() {
+    assert_matches("**", &["a", "a/b/c"], &[]);
+    assert_matches(
+        "a/**/b",
+        &["a/b", "a/x/b", "a/x/y/b"],
+        &["ab", "a/b/c", "x/a/b"],
+    );
+    assert_matches("**/*.csv", &["a.csv", "x/y/a.csv"], &["a.csv/x"]);
+    assert_matches("logs/**", &["logs/a", "logs/a/b"], &["logs"]);
+}
+
+#[test]
+fn character_classes() {
+    assert_matches(
+        "file[0-9].pdf",
+        &["file0.pdf", "file9.pdf"],
+        &["filea.pdf", "file10.pdf"],
+    );
+    assert_matches("[abc]", &["a", "c"], &["d", "ab", ""]);
+    assert_matches("[a-cx-z]", &["b", "y"], &["d", "w"]);
+    // A trailing '-' is literal
+    assert_matches("[a-]", &["a", "-"], &["b"]);
+}
+
+#[test]
+fn negated_character_classes_dont_match_the_separator() {
+    assert_matches("[!a-c]x", &["dx", "1x"], &["ax", "cx", "/x"]);
+    assert_matches("[^0-9]", &["a"], &["5", "/"]);
+}
+
+#[test]
+fn closing_bracket_first_in_a_class_is_literal() {
+    assert_matches("[]]", &["]"], &["[", "]]"]);
+    assert_matches("[!]]", &["a"], &["]"]);
+    assert_matches("[]a]", &["]", "a"], &["b"]);
+}
+
+#[test]
+fn regex_metacharacters_inside_classes_are_escaped() {
+    assert_matches("[\\]^&&]", &["]", "^", "&"], &["a", "\\"]);
+    assert_matches("[[]", &["["], &["]"]);
+    assert_matches("[.*+?|$]", &[".", "*", "+", "?", "|", "$"], &["a"]);
+    assert_matches("[~~]", &["~"], &["a"]);
+    assert_matches("[\\-]", &["-"], &["a"]);
+    // Escaped letters aren't turned into regex classes like \d
+    assert_matches("[\\d]", &["d"], &["1"]);
+    assert_matches("[€ é]", &["€", " ", "é"], &["e"]);
+}
+
+#[test]
+fn unclosed_brackets_and_braces_are_literal() {
+    assert_matches("[a", &["[a"], &["a"]);
+    assert_matches("{a", &["{a"], &["a"]);
+    assert_matches("a{b,c", &["a{b,c"], &["ab"]);
+    assert_matches("a]", &["a]"], &["a"]);
+    assert_matches("a}", &["a}"], &["a"]);
+}
+
+#[test]
+fn brace_expansion() {
+    assert_matches("*.{jpg,png}", &["a.jpg", "b.png"], &["c.gif", "a.jpgpng"]);
+    assert_matches(
+        "report{,-final}.pdf",
+        &["report.pdf", "report-final.pdf"],
+        &["report-.pdf"],
+    );
+    assert_matches("{a,{b,c}d}", &["a", "bd", "cd"], &["b", "ad"]);
+    assert_matches(
+        "{src,tests}/**/*.rs",
+        &["src/a.rs", "tests/x/y.rs"],
+        &["docs/a.rs"],
+    );
+    assert_matches("{[0-9],x*}", &["5", "xyz"], &["55", "y"]);
+    // Commas outside braces are literal
+    assert_matches("a,b", &["a,b"], &["a"]);
+}
+
+#[test]
+fn regex_metacharacters_are_escaped() {
+    assert_matches("a.b+(c)|^$#", &["a.b+(c)|^$#"], &["axb+(c)|^$#", "abbc"]);
+}
+
+#[test]
+fn backslash_escapes_the_next_character() {
+    assert_matches("a\\*b", &["a*b"], &["axb"]);
+    assert_matches("\\[a]", &["[a]"], &["a"]);
+    assert_matches("\\{a,b}", &["{a,b}"], &["a"]);
+    // A trailing backslash is literal
+    assert_matches("a\\", &["a\\"], &["a"]);
+}
+
+#[test]
+fn has_wildcard_detects_every_kind_of_wildcard() {
+    assert!(has_wildcard("*.txt"));
+    assert!(has_wildcard("file?.pdf"));
+    assert!(has_wildcard("file[0-9].pdf"));
+    assert!(has_wildcard("*.{jpg,png}"));
+    assert!(!has_wildcard("report (final).pdf"));
+}
+
+#[test]
+fn path_filters_use_classes_and_braces() {
+    let filter = PathFilter {
+        max_depth: None,
+        include: vec!["*.{csv,tsv}".parse().unwrap()],
+        exclude: vec!["tmp[0-9]/**".parse().unwrap()],
+        match_path: false,
+    };
+
+    assert!(filter.includes_file(Path::new("data/a.tsv")));
+    assert!(!filter.includes_file(Path::new("data/a.json")));
+    assert!(!filter.includes_file(Path::new("tmp1/a.csv")));
+    assert!(!filter.enters_folder(Path::new("tmp1")));
+    assert!(filter.enters_folder(Path::new("tmpx")));
+}